@@ -0,0 +1,76 @@
+// Bakes the git commit, the enabled features, and a version for the assets into the build, for
+// `version`.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+// 32-bit FNV-1a, like `Replay::hash`
+fn fnv(hash: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+// every file under `path`, in the same order every time
+fn files(path: &Path, found: &mut Vec<std::path::PathBuf>) {
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            let mut entries = entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .collect::<Vec<_>>();
+            entries.sort();
+            for entry in entries {
+                files(&entry, found);
+            }
+        }
+        Err(_) => found.push(path.to_path_buf()),
+    }
+}
+
+fn main() {
+    // building from a source snapshot without git still works, it just can't say which commit
+    let hash = git(&["rev-parse", "--short", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=SLAKE_GIT_HASH={hash}");
+
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        println!("cargo:rerun-if-changed={git_dir}/refs/heads");
+    }
+
+    // cargo passes each enabled feature as `CARGO_FEATURE_<NAME>`
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+
+    println!("cargo:rustc-env=SLAKE_FEATURES={}", features.join(","));
+
+    // Changes whenever anything that goes into the page does, for offline caches to tell one
+    // build from the next, even between commits. See `version::ASSET_VERSION`.
+    let mut inputs = vec![];
+    for path in ["Cargo.toml", "index.html", "src"] {
+        files(Path::new(path), &mut inputs);
+        println!("cargo:rerun-if-changed={path}");
+    }
+    let assets = inputs.iter().fold(0x811c_9dc5, |assets, path| {
+        let assets = fnv(assets, path.to_string_lossy().as_bytes());
+        fnv(assets, &fs::read(path).unwrap_or_default())
+    });
+    let assets = fnv(assets, features.join(",").as_bytes());
+    println!("cargo:rustc-env=SLAKE_ASSET_VERSION={assets:08x}");
+}
@@ -0,0 +1,84 @@
+use crate::version;
+
+use std::cell::Cell;
+
+// The what's new overlay, listing how the rules changed from one rules version to the next (see
+// `version::RULE_CHANGES`), so anyone comparing scores across releases can tell which ones
+// were played the same way. It's drawn over the board like the pause message, and the run
+// waits underneath until any key closes it.
+
+thread_local! {
+    static SHOWING: Cell<bool> = const { Cell::new(false) };
+    // whether opening it is what paused the run, so closing it only carries on what it stopped
+    static PAUSED_GAME: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn is_showing() -> bool {
+    SHOWING.with(|showing| showing.get())
+}
+
+pub fn toggle() {
+    if is_showing() {
+        close();
+        return;
+    }
+
+    crate::GAME.with(|game| {
+        let mut game = game.borrow_mut();
+
+        // the other player wouldn't stop
+        if crate::net::is_active() && !game.is_game_over() {
+            return;
+        }
+
+        let pausing = !game.paused && !game.is_game_over();
+        game.paused = true;
+        PAUSED_GAME.with(|paused_game| paused_game.set(pausing));
+        SHOWING.with(|showing| showing.set(true));
+    });
+}
+
+fn close() {
+    SHOWING.with(|showing| showing.set(false));
+
+    if PAUSED_GAME.with(|paused_game| paused_game.take()) {
+        crate::GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            game.paused = false;
+            // a moment to find the keys again
+            game.start_countdown();
+        });
+    }
+}
+
+// newest first, since that's what anyone coming back wants to see
+pub fn text() -> String {
+    let changes = version::RULE_CHANGES
+        .iter()
+        .rev()
+        .map(|(rules, change)| format!("rules {rules}: {change}"))
+        .collect::<Vec<_>>();
+
+    format!(
+        "what's new\nslake {}, rules version {}\n\n{}\n\npress any key to go back",
+        version::VERSION,
+        version::RULES_VERSION,
+        changes.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn about_text() {
+        let text = text();
+        assert!(text.contains(&format!("rules version {}", version::RULES_VERSION)));
+
+        // newest first
+        let newest = text.find(version::RULE_CHANGES[2].1).unwrap();
+        let oldest = text.find(version::RULE_CHANGES[0].1).unwrap();
+        assert!(newest < oldest);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::error::SlakeError;
+use crate::event::GameEvent;
+use crate::snake::GameState;
+use crate::snake::SnakeGame;
+
+use web_sys::HtmlElement;
+
+// The board is a grid of emoji that doesn't mean much read out loud, so the things worth
+// knowing about also get put into a hidden live region, which screen readers read out whenever
+// it changes.
+
+const ANNOUNCER_ID: &str = "announcer";
+
+// what gets read out for an event, if anything
+pub fn message(event: &GameEvent, game: &SnakeGame) -> Option<String> {
+    match event {
+        GameEvent::ScoreChanged { .. } if game.is_versus() => Some(versus_scores(game)),
+        GameEvent::ScoreChanged { score } => Some(format!("score {score}")),
+        GameEvent::SnakeSplit => Some("the snake split in two".to_string()),
+        GameEvent::SnakeCut { player, segments } if game.is_versus() => Some(format!(
+            "player {} bit off {segments} of their own segments",
+            player + 1
+        )),
+        GameEvent::SnakeCut { segments, .. } => {
+            Some(format!("bit off {segments} segments of your own tail"))
+        }
+        GameEvent::SnakeStunned { player } if game.is_versus() => {
+            Some(format!("player {} is stunned", player + 1))
+        }
+        GameEvent::SnakeStunned { .. } => {
+            Some("stunned, another slip now would end the run".to_string())
+        }
+        GameEvent::SnakeBounced { player } if game.is_versus() => {
+            Some(format!("player {} turned around at the wall", player + 1))
+        }
+        GameEvent::SnakeBounced { .. } => Some("turned around at the wall".to_string()),
+        GameEvent::WallsClosingIn => Some("the walls are closing in".to_string()),
+        GameEvent::WaveCleared { wave } => Some(format!("wave {wave} cleared")),
+        GameEvent::WaveStarted { wave } => game
+            .wave()
+            .map(|coming| format!("wave {wave}, eat {} fruit to clear it", coming.quota())),
+        GameEvent::GameOver { reason } if game.is_versus() => {
+            let result = match game.winner {
+                _ if game.coop => format!("{} together", game.score),
+                Some(player) => format!("player {} wins", player + 1),
+                None => "it's a draw".to_string(),
+            };
+            Some(format!(
+                "game over, {reason}. {result}. {}. press space to restart",
+                versus_scores(game)
+            ))
+        }
+        GameEvent::GameOver { .. } if game.state() == GameState::Won => Some(format!(
+            "you won! score {}, high score {}. press space to play again",
+            score_out_of(game),
+            game.high_score()
+        )),
+        GameEvent::GameOver { reason } => Some(format!(
+            "game over, {reason}. score {}, high score {}. press space to restart",
+            score_out_of(game),
+            game.high_score()
+        )),
+        GameEvent::Restarted => Some("new game".to_string()),
+        _ => None,
+    }
+}
+
+fn score_out_of(game: &SnakeGame) -> String {
+    match game.possible_score() {
+        Some(possible) => format!("{} out of {possible} possible", game.score),
+        None => game.score.to_string(),
+    }
+}
+
+fn versus_scores(game: &SnakeGame) -> String {
+    game.scores
+        .iter()
+        .enumerate()
+        .map(|(player, score)| format!("player {} {score}", player + 1))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn announce(text: &str) {
+    match crate::get_element::<HtmlElement>(ANNOUNCER_ID) {
+        Ok(announcer) => announcer.set_inner_text(text),
+        Err(error) => crate::error::report(error, None),
+    }
+}
+
+// The live region, and the board's container made into something that can be tabbed to. The
+// container is never rebuilt, only what's inside it, so this only has to happen once.
+pub fn build() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let announcer = document.create_element("div")?;
+    announcer.set_id(ANNOUNCER_ID);
+    announcer.set_class_name("visually_hidden");
+    announcer.set_attribute("role", "status")?;
+    announcer.set_attribute("aria-live", "polite")?;
+    crate::error::body()?.append_child(&announcer)?;
+
+    let root_container = crate::get_element::<HtmlElement>("root")?;
+    root_container.set_tab_index(0);
+    // so the arrow keys go to the game instead of the screen reader
+    root_container.set_attribute("role", "application")?;
+    root_container.set_attribute(
+        "aria-label",
+        "Snake board. Steer with the arrow keys, press space to restart",
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Vector;
+
+    #[test]
+    fn messages() {
+        let mut game = SnakeGame::new(21, 15);
+
+        assert_eq!(
+            message(&GameEvent::ScoreChanged { score: 3 }, &game),
+            Some("score 3".to_string())
+        );
+        assert_eq!(
+            message(
+                &GameEvent::HazardSpawned {
+                    position: Vector(0, 0)
+                },
+                &game
+            ),
+            None
+        );
+
+        game.set_versus(true);
+        game.scores = vec![2, 5];
+        game.winner = Some(1);
+        assert_eq!(
+            message(&GameEvent::GameOver { reason: "avoid walls" }, &game),
+            Some(
+                "game over, avoid walls. player 2 wins. player 1 2, player 2 5. press space to restart"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::settings::MIN_TICK_MS;
+use crate::stats::RecentGame;
+
+// Adaptive pace, for finding a difficulty without having to pick one. It goes by how long the
+// last few games lasted, see `stats::recent_games`: every one of them that was over quickly eases
+// the next run off a step, slower and with puddles drying up sooner, and every one that went on
+// a long while steps it up, faster and with puddles lying around longer. Games in between leave
+// it be, so it settles wherever runs last a decent time. The steps go on top of the speed and
+// difficulty in the settings, and only ever change between runs.
+
+// under this long is a quick death, and at least this long is a long run
+const QUICK_MS: u64 = 20_000;
+const LONG_MS: u64 = 90_000;
+pub const MAX_STEPS: i32 = 3;
+// how much of the time between ticks each step takes off or puts on
+const TICK_STEP: f64 = 0.1;
+// and of how long puddles last
+const LIFETIME_STEP: f64 = 0.25;
+// puddles that never dry up start drying up after this many ticks on easing off
+const EASED_LIFETIME: usize = 400;
+
+// Steps harder for positive, easier for negative, going by `recent`, see `RecentGame`.
+pub fn steps(recent: &[RecentGame]) -> i32 {
+    recent
+        .iter()
+        .filter_map(|game| game.survival_ms)
+        .map(|survival_ms| match survival_ms {
+            ms if ms < QUICK_MS => -1,
+            ms if ms >= LONG_MS => 1,
+            _ => 0,
+        })
+        .sum::<i32>()
+        .clamp(-MAX_STEPS, MAX_STEPS)
+}
+
+// harder is quicker, though never quicker than the settings allow
+pub fn tick_ms(tick_ms: i32, steps: i32) -> i32 {
+    if steps == 0 {
+        return tick_ms;
+    }
+
+    let paced = (tick_ms as f64 * (1.0 - TICK_STEP * steps as f64)).round() as i32;
+    paced.max(MIN_TICK_MS.min(tick_ms))
+}
+
+// and puddles stay around for longer, see `SnakeGame::hazard_lifetime`
+pub fn hazard_lifetime(lifetime: Option<usize>, steps: i32) -> Option<usize> {
+    let lifetime = match (lifetime, steps) {
+        (lifetime, 0) => return lifetime,
+        // forever's as hard as it gets
+        (None, 1..) => return None,
+        (lifetime, _) => lifetime.unwrap_or(EASED_LIFETIME),
+    };
+
+    let paced = (lifetime as f64 * (1.0 + LIFETIME_STEP * steps as f64)).round() as usize;
+    Some(paced.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Difficulty;
+
+    fn lasting(survival_ms: Option<u64>) -> RecentGame {
+        RecentGame {
+            difficulty: Difficulty::Normal,
+            score: 5,
+            cause: None,
+            survival_ms,
+        }
+    }
+
+    #[test]
+    fn pace() {
+        assert_eq!(steps(&[]), 0);
+        // a quick death and a long run cancel out, and games from before don't count
+        let mixed = [5_000, 40_000, 120_000].map(|ms| lasting(Some(ms)));
+        assert_eq!(steps(&mixed), 0);
+        assert_eq!(steps(&[lasting(None), lasting(Some(1_000))]), -1);
+        assert_eq!(steps(&[lasting(Some(1_000)); 5]), -MAX_STEPS);
+        assert_eq!(steps(&[lasting(Some(600_000)); 5]), MAX_STEPS);
+
+        assert_eq!(tick_ms(100, 0), 100);
+        assert_eq!(tick_ms(100, 2), 80);
+        assert_eq!(tick_ms(100, -3), 130);
+        assert_eq!(tick_ms(MIN_TICK_MS, 3), MIN_TICK_MS);
+
+        assert_eq!(hazard_lifetime(Some(250), 0), Some(250));
+        assert_eq!(hazard_lifetime(Some(100), 2), Some(150));
+        assert_eq!(hazard_lifetime(Some(100), -3), Some(25));
+        assert_eq!(hazard_lifetime(None, 3), None);
+        assert_eq!(hazard_lifetime(None, -1), Some(300));
+    }
+}
@@ -0,0 +1,513 @@
+use crate::error::SlakeError;
+use crate::geometry;
+use crate::geometry::Grid;
+use crate::geometry::Topology;
+use crate::geometry::Vector;
+use crate::snake::GameState;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, HtmlDivElement, HtmlElement};
+
+// A look back at how a game went, shown once it's over. While playing we keep a replay with a
+// little about every tick, and the metrics are all worked out from that afterwards. Only the
+// first player's first snake is followed, so hydra heads and versus rivals don't count. Under
+// the metrics there's a map of the run: the path the head took over a heatmap of where it spent
+// the most time, the move that ended it in red, and along the bottom when each fruit got eaten.
+
+// how big a tile is on the map at most, in pixels, and how wide the map can be
+const MAP_TILE_PX: f64 = 16.0;
+const MAP_WIDTH_PX: f64 = 320.0;
+
+#[derive(Clone, Debug)]
+pub struct ReplayTick {
+    pub head: Vector,
+    pub ate: bool,
+    // the biggest stretch of open board the head couldn't get to anymore
+    pub enclosed_area: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    pub width: isize,
+    pub height: isize,
+    pub grid: Grid,
+    pub topology: Topology,
+    // walls don't change during a game, so they're only kept once
+    pub walls: Vec<Vector>,
+    pub ticks: Vec<ReplayTick>,
+    pub rejected_inputs: usize,
+    // where the head was and where it was going when the run ended, if it didn't end in a win
+    pub fatal_move: Option<(Vector, Vector)>,
+}
+
+impl Replay {
+    pub fn new(game: &SnakeGame) -> Replay {
+        Replay {
+            width: game.width,
+            height: game.height,
+            grid: game.grid,
+            topology: game.topology,
+            walls: game.walls.clone(),
+            ticks: vec![],
+            rejected_inputs: 0,
+            fatal_move: None,
+        }
+    }
+
+    // once the run's over
+    pub fn end(&mut self, game: &SnakeGame) {
+        let snake = &game.snakes()[0];
+        if game.state() != GameState::Won {
+            self.fatal_move = Some((
+                snake.head().clone(),
+                game.next_tile(snake.head(), snake.direction()),
+            ));
+        }
+    }
+
+    // how many ticks the head spent on each tile, by its index in the rows
+    pub fn heat(&self) -> Vec<usize> {
+        let mut heat = vec![0; (self.width * self.height) as usize];
+        for tick in &self.ticks {
+            if geometry::is_within_board(self.width, self.height, &tick.head) {
+                heat[(tick.head.1 * self.width + tick.head.0) as usize] += 1;
+            }
+        }
+        heat
+    }
+
+    // the ticks fruit got eaten on, counting from 1
+    pub fn eaten_ticks(&self) -> Vec<usize> {
+        self.ticks
+            .iter()
+            .enumerate()
+            .filter(|(_, tick)| tick.ate)
+            .map(|(index, _)| index + 1)
+            .collect()
+    }
+
+    // call once after every tick
+    pub fn record(&mut self, game: &SnakeGame, ate: bool) {
+        self.ticks.push(ReplayTick {
+            head: game.snakes()[0].head().clone(),
+            ate,
+            enclosed_area: enclosed_area(game),
+        });
+        self.rejected_inputs = game.rejected_inputs;
+    }
+
+    // A fingerprint of the path the head took, so a submitted score can be matched up with the
+    // run behind it. This is 32-bit FNV-1a over every position and whether it ate.
+    #[cfg(any(test, feature = "leaderboard"))]
+    pub fn hash(&self) -> u32 {
+        let mut hash: u32 = 0x811c_9dc5;
+
+        for tick in &self.ticks {
+            let Vector(x, y) = tick.head;
+            let bytes = [
+                x as u8,
+                (x >> 8) as u8,
+                y as u8,
+                (y >> 8) as u8,
+                tick.ate as u8,
+            ];
+
+            for byte in bytes {
+                hash ^= byte as u32;
+                hash = hash.wrapping_mul(0x0100_0193);
+            }
+        }
+
+        hash
+    }
+
+    fn is_near_wall(&self, pos: &Vector) -> bool {
+        let on_edge = geometry::neighbors8(self.width, self.height, pos).count() < 8;
+
+        (on_edge && self.topology == Topology::Walls)
+            || geometry::neighbors8(self.width, self.height, pos)
+                .any(|neighbor| self.walls.contains(&neighbor))
+    }
+}
+
+// The largest group of open tiles that the first snake's head can't reach anymore. This doesn't
+// look across wrapping edges, so on wrapping boards a group split by the edge counts as two.
+pub fn enclosed_area(game: &SnakeGame) -> usize {
+    let blocked = game
+        .snakes()
+        .iter()
+        .flat_map(|snake| snake.body.iter())
+        .chain(game.hazards().iter())
+        .chain(game.walls.iter())
+        .cloned()
+        .collect::<HashSet<_>>();
+
+    let open = |pos: &Vector| !blocked.contains(pos);
+
+    let mut seen = geometry::flood_fill(game.width, game.height, game.snakes()[0].head(), open)
+        .into_iter()
+        .collect::<HashSet<_>>();
+
+    let mut largest = 0;
+
+    for y in 0..game.height {
+        for x in 0..game.width {
+            let pos = Vector(x, y);
+
+            if seen.contains(&pos) || !open(&pos) {
+                continue;
+            }
+
+            let region = geometry::flood_fill(game.width, game.height, &pos, open);
+            largest = largest.max(region.len());
+            seen.extend(region);
+        }
+    }
+
+    largest
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Analysis {
+    // `None` if nothing got eaten
+    pub moves_per_fruit: Option<f64>,
+    // from 0 to 1
+    pub time_near_walls: f64,
+    pub rejected_inputs: usize,
+    pub largest_enclosed_area: usize,
+}
+
+pub fn analyse(replay: &Replay) -> Analysis {
+    let moves = replay.ticks.len();
+    let fruit = replay.ticks.iter().filter(|tick| tick.ate).count();
+    let near_walls = replay
+        .ticks
+        .iter()
+        .filter(|tick| replay.is_near_wall(&tick.head))
+        .count();
+
+    Analysis {
+        moves_per_fruit: (fruit > 0).then(|| moves as f64 / fruit as f64),
+        time_near_walls: if moves > 0 {
+            near_walls as f64 / moves as f64
+        } else {
+            0.0
+        },
+        rejected_inputs: replay.rejected_inputs,
+        largest_enclosed_area: replay
+            .ticks
+            .iter()
+            .map(|tick| tick.enclosed_area)
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+// (label, value, how full the bar is from 0 to 1)
+pub fn bars(analysis: &Analysis, replay: &Replay) -> Vec<(&'static str, String, f64)> {
+    let area = (replay.width * replay.height) as f64;
+    // a fruit all the way across the board fills the bar
+    let across = (replay.width + replay.height) as f64;
+
+    vec![
+        (
+            "Moves per fruit",
+            analysis
+                .moves_per_fruit
+                .map_or("-".to_string(), |moves| format!("{moves:.1}")),
+            analysis.moves_per_fruit.unwrap_or(0.0) / across,
+        ),
+        (
+            "Time next to walls",
+            format!("{:.0}%", analysis.time_near_walls * 100.0),
+            analysis.time_near_walls,
+        ),
+        (
+            "Turns that didn't work",
+            analysis.rejected_inputs.to_string(),
+            analysis.rejected_inputs as f64 / 10.0,
+        ),
+        (
+            "Largest area sealed off",
+            format!("{} tiles", analysis.largest_enclosed_area),
+            analysis.largest_enclosed_area as f64 / area,
+        ),
+    ]
+}
+
+thread_local! {
+    static REPLAY: RefCell<Option<Replay>> = const { RefCell::new(None) };
+}
+
+pub fn start(game: &SnakeGame) {
+    REPLAY.with(|replay| *replay.borrow_mut() = Some(Replay::new(game)));
+    crate::error::report_failure(hide_panel());
+}
+
+pub fn record(game: &SnakeGame, ate: bool) {
+    if game.is_game_over() {
+        return;
+    }
+
+    REPLAY.with(|replay| {
+        // only once per tick, and not during the countdown
+        if let Some(replay) = replay
+            .borrow_mut()
+            .as_mut()
+            .filter(|replay| replay.ticks.len() < game.ticks)
+        {
+            replay.record(game, ate);
+        }
+    });
+}
+
+// for the run still going, or the one that just ended as long as `finish` hasn't been called
+#[cfg(feature = "leaderboard")]
+pub fn replay_hash() -> Option<u32> {
+    REPLAY.with(|replay| replay.borrow().as_ref().map(Replay::hash))
+}
+
+pub fn finish(game: &SnakeGame) {
+    let Some(mut replay) = REPLAY.with(|replay| replay.borrow_mut().take()) else {
+        return;
+    };
+    replay.end(game);
+
+    // showing the analysis isn't worth stopping the game over
+    let _ = show_panel(&analyse(&replay), &replay);
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("analysis");
+    panel_element.set_class_name("analysis");
+    panel_element.style().set_property("display", "none")?;
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    Ok(())
+}
+
+fn show_panel(analysis: &Analysis, replay: &Replay) -> Result<(), SlakeError> {
+    let rows = bars(analysis, replay)
+        .into_iter()
+        .map(|(label, value, fraction)| {
+            format!(
+                r#"<div>{label}: {value}</div><div class="bar"><div style="width: {:.0}%"></div></div>"#,
+                fraction.clamp(0.0, 1.0) * 100.0
+            )
+        })
+        .collect::<String>();
+
+    // each fruit along the run, from the start on the left to the end on the right
+    let moves = replay.ticks.len().max(1) as f64;
+    let markers = replay
+        .eaten_ticks()
+        .into_iter()
+        .map(|tick| {
+            format!(
+                r#"<div style="left: {:.1}%"></div>"#,
+                tick as f64 / moves * 100.0
+            )
+        })
+        .collect::<String>();
+
+    let panel_element = crate::get_element::<HtmlElement>("analysis")?;
+    panel_element.set_inner_html(&format!(
+        r#"{rows}<div>Where you went</div><canvas id="analysis_map" class="analysis_map"></canvas><div>Fruit eaten</div><div class="timeline">{markers}</div>"#
+    ));
+    draw_map(replay)?;
+    panel_element.style().set_property("display", "block")?;
+    Ok(())
+}
+
+fn draw_map(replay: &Replay) -> Result<(), SlakeError> {
+    let canvas = crate::get_element::<HtmlCanvasElement>("analysis_map")?;
+    let geometry = replay.grid.geometry();
+    let (across, down) = geometry.extent(replay.width, replay.height);
+    let tile_px = (MAP_WIDTH_PX / across).min(MAP_TILE_PX).floor().max(1.0);
+    canvas.set_width((across * tile_px) as u32);
+    canvas.set_height((down * tile_px) as u32);
+
+    let context = crate::error::context_2d(&canvas)?;
+    let corner = |pos: &Vector| {
+        let (left, top) = geometry.tile_offset(pos);
+        (left * tile_px, top * tile_px)
+    };
+    let center = |pos: &Vector| {
+        let (left, top) = corner(pos);
+        (left + tile_px / 2.0, top + tile_px / 2.0)
+    };
+    let fill = |pos: &Vector| {
+        let (left, top) = corner(pos);
+        context.fill_rect(left, top, tile_px, tile_px);
+    };
+
+    context.set_fill_style_str("white");
+    context.fill_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+    context.set_fill_style_str("dimgrey");
+    replay.walls.iter().for_each(fill);
+
+    // the hotter the tile, the longer the head was on it
+    let heat = replay.heat();
+    let hottest = heat.iter().max().copied().unwrap_or(0).max(1) as f64;
+    for (index, ticks) in heat.iter().enumerate().filter(|(_, ticks)| **ticks > 0) {
+        let pos = Vector(index as isize % replay.width, index as isize / replay.width);
+        let strength = 0.15 + 0.65 * *ticks as f64 / hottest;
+        context.set_fill_style_str(&format!("rgba(255, 140, 0, {strength:.2})"));
+        fill(&pos);
+    }
+
+    // broken off wherever it wrapped around or went through a portal
+    context.set_stroke_style_str("purple");
+    context.set_line_width((tile_px / 6.0).max(1.0));
+    context.begin_path();
+    let heads = replay.ticks.iter().map(|tick| &tick.head);
+    for (index, head) in heads.clone().enumerate() {
+        let (x, y) = center(head);
+        match index
+            .checked_sub(1)
+            .map(|previous| &replay.ticks[previous].head)
+        {
+            Some(previous) if geometry.distance(previous, head) <= 1 => context.line_to(x, y),
+            _ => context.move_to(x, y),
+        }
+    }
+    context.stroke();
+
+    // fruit along the way
+    context.set_fill_style_str("deeppink");
+    for tick in replay.ticks.iter().filter(|tick| tick.ate) {
+        let (x, y) = center(&tick.head);
+        context.begin_path();
+        context.arc(x, y, tile_px / 4.0, 0.0, std::f64::consts::TAU)?;
+        context.fill();
+    }
+
+    if let Some((from, to)) = &replay.fatal_move {
+        let ((from_x, from_y), (to_x, to_y)) = (center(from), center(to));
+        context.set_stroke_style_str("red");
+        context.set_line_width((tile_px / 4.0).max(1.0));
+        context.begin_path();
+        context.move_to(from_x, from_y);
+        context.line_to(to_x, to_y);
+        context.stroke();
+        context.set_fill_style_str("rgba(255, 0, 0, 0.5)");
+        fill(to);
+    }
+
+    Ok(())
+}
+
+fn hide_panel() -> Result<(), SlakeError> {
+    crate::get_element::<HtmlElement>("analysis")?
+        .style()
+        .set_property("display", "none")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Direction;
+
+    #[test]
+    fn sealed_off_areas() {
+        let mut game = SnakeGame::new(7, 5);
+        assert_eq!(enclosed_area(&game), 0);
+
+        // a wall down the middle, leaving the snake on the right
+        game.walls = (0..5).map(|y| Vector(3, y)).collect();
+        assert_eq!(enclosed_area(&game), 15);
+    }
+
+    #[test]
+    fn metrics() {
+        let mut game = SnakeGame::new(21, 15);
+        let mut replay = Replay::new(&game);
+
+        // reversing doesn't work, turning does
+        game.change_direction(Direction::Right);
+        game.change_direction(Direction::Up);
+
+        for tick in 0..4 {
+            game.tick();
+            replay.record(&game, tick % 2 == 1);
+        }
+
+        let analysis = analyse(&replay);
+        assert_eq!(analysis.moves_per_fruit, Some(2.0));
+        assert_eq!(analysis.rejected_inputs, 1);
+        assert_eq!(analysis.time_near_walls, 0.0);
+        assert_eq!(analysis.largest_enclosed_area, 0);
+
+        // straight up the right side of the board, next to the edge the whole way
+        let mut replay = Replay::new(&game);
+        replay.ticks = (0..4)
+            .map(|y| ReplayTick {
+                head: Vector(20, y),
+                ate: false,
+                enclosed_area: 0,
+            })
+            .collect();
+        assert_eq!(analyse(&replay).time_near_walls, 1.0);
+        assert_eq!(analyse(&replay).moves_per_fruit, None);
+    }
+
+    #[test]
+    fn paths() {
+        let mut game = SnakeGame::new(7, 5);
+        game.skip_countdown();
+        let mut replay = Replay::new(&game);
+
+        // straight on until it hits the side
+        while !game.is_game_over() {
+            game.tick();
+            replay.record(&game, false);
+        }
+        replay.end(&game);
+
+        let (from, to) = replay.fatal_move.clone().unwrap();
+        assert_eq!(&from, game.snakes()[0].head());
+        assert!(!geometry::is_within_board(7, 5, &to));
+
+        replay.ticks[1].ate = true;
+        replay.ticks[2].head = replay.ticks[1].head.clone();
+        assert_eq!(replay.eaten_ticks(), vec![2]);
+        let heat = replay.heat();
+        let Vector(x, y) = replay.ticks[1].head;
+        assert_eq!(heat[(y * 7 + x) as usize], 2);
+        assert_eq!(heat.iter().sum::<usize>(), replay.ticks.len());
+    }
+
+    #[test]
+    fn replay_hashes() {
+        let game = SnakeGame::new(21, 15);
+        let mut replay = Replay::new(&game);
+        let empty = replay.hash();
+
+        replay.ticks = (0..4)
+            .map(|y| ReplayTick {
+                head: Vector(20, y),
+                ate: false,
+                enclosed_area: 0,
+            })
+            .collect();
+        let path = replay.hash();
+        assert_ne!(path, empty);
+
+        // eating somewhere else along the same path counts as a different run
+        replay.ticks[2].ate = true;
+        assert_ne!(replay.hash(), path);
+        replay.ticks[2].ate = false;
+        assert_eq!(replay.hash(), path);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::event::GameEvent;
+use crate::geometry::Vector;
+use crate::render::Decoration;
+use crate::renderer::BoardTransform;
+use crate::snake::GameState;
+use crate::snake::SnakeGame;
+
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::{prelude::*, JsCast};
+
+// Little flourishes that play out in real time rather than in ticks: the tile food was eaten
+// from flashes, and when a run ends the snake crumbles into hazards a segment at a time before
+// the game over message comes up, or flashes all over if it was won. They're drawn as
+// decorations, so both render styles get them for free. They run on animation frames of their
+// own while there's something to show, which keeps them the same speed however slow the game is
+// set.
+//
+// Floating text is the exception, since it isn't on any one tile for long: what food was worth,
+// or that the snake split, drifts up from where it happened and fades out. Each render style
+// draws it over the board itself, see `floating_texts`.
+//
+// So is the board shaking, when the snake crashes or only just turns away from a hazard, and
+// swaying about for the drunk board, since they move all of it at once. Only the smooth style
+// draws them, see `BoardTransform`.
+
+const FLASH_MS: f64 = 150.0;
+const COLLAPSE_STEP_MS: f64 = 60.0;
+// long snakes crumble faster, so nobody's kept waiting for the message
+const COLLAPSE_MAX_MS: f64 = 1200.0;
+const FLOAT_MS: f64 = 800.0;
+const SHAKE_MS: f64 = 300.0;
+// how far it shakes to start with, in tiles
+const CRASH_SHAKE: f64 = 0.3;
+const NEAR_MISS_SHAKE: f64 = 0.1;
+// how long it takes to lean one way then the other, and how far, in radians
+const SWAY_MS: f64 = 7000.0;
+const SWAY_ANGLE: f64 = 0.12;
+// and to flip over and back again
+const FLIP_MS: f64 = 23000.0;
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct FloatingText {
+    // the tile it started from
+    pub position: Vector,
+    pub text: String,
+    // how far along it is, from 0 to 1. it's a tile higher up and gone by 1
+    pub progress: f64,
+}
+
+// what a bite was worth, with how the combo helped
+fn food_text(points: isize, multiplier: usize) -> String {
+    match multiplier {
+        2.. => format!("{points:+} combo!"),
+        _ => format!("{points:+}"),
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Animations {
+    // tiles that were eaten from, and when
+    flashes: Vec<(Vector, f64)>,
+    // every dead segment, heads first, and when they started crumbling
+    collapse: Option<(Vec<Vector>, f64)>,
+    // whether the collapse was still going as of the last frame
+    collapsing: bool,
+    // where each one started, what it says, and when
+    texts: Vec<(Vector, String, f64)>,
+    // how hard, and when it started
+    shake: Option<(f64, f64)>,
+}
+
+impl Animations {
+    pub fn on_event(&mut self, event: &GameEvent, game: &SnakeGame, now_ms: f64) {
+        match event {
+            GameEvent::FoodEaten {
+                position,
+                multiplier,
+                points,
+                ..
+            } => {
+                self.flashes.push((position.clone(), now_ms));
+                self.texts
+                    .push((position.clone(), food_text(*points, *multiplier), now_ms));
+            }
+            // the new head's the one that came out of it
+            GameEvent::SnakeSplit => {
+                if let Some(snake) = game.snakes().last() {
+                    self.texts
+                        .push((snake.head().clone(), "split!".to_string(), now_ms));
+                }
+            }
+            GameEvent::GameOver { .. } if game.state() == GameState::Won => {
+                self.flashes.extend(
+                    game.snakes()
+                        .iter()
+                        .flat_map(|snake| snake.body.iter())
+                        .map(|pos| (pos.clone(), now_ms)),
+                );
+            }
+            GameEvent::NearMiss { .. } => self.start_shake(NEAR_MISS_SHAKE, now_ms),
+            GameEvent::GameOver { .. } => {
+                self.start_shake(CRASH_SHAKE, now_ms);
+                let segments = game
+                    .snakes()
+                    .iter()
+                    .flat_map(|snake| snake.body.iter().cloned())
+                    .collect::<Vec<_>>();
+                self.collapse = Some((segments, now_ms));
+                self.collapsing = true;
+            }
+            GameEvent::Restarted => *self = Animations::default(),
+            _ => (),
+        }
+    }
+
+    // unless there's a harder one still going
+    fn start_shake(&mut self, strength: f64, now_ms: f64) {
+        if self.shake_strength(now_ms) <= strength {
+            self.shake = Some((strength, now_ms));
+        }
+    }
+
+    fn shake_strength(&self, now_ms: f64) -> f64 {
+        match self.shake {
+            Some((strength, start_ms)) => {
+                strength * (1.0 - (now_ms - start_ms) / SHAKE_MS).max(0.0)
+            }
+            None => 0.0,
+        }
+    }
+
+    // how far out of place the board is at `now_ms`, in tiles, settling down as it goes
+    pub fn shake_offset(&mut self, now_ms: f64) -> (f64, f64) {
+        let strength = self.shake_strength(now_ms);
+        if strength == 0.0 {
+            self.shake = None;
+            return (0.0, 0.0);
+        }
+        let since_ms = now_ms - self.shake.map_or(now_ms, |(_, start_ms)| start_ms);
+        (
+            strength * (since_ms * 0.11).sin(),
+            strength * (since_ms * 0.07).cos(),
+        )
+    }
+
+    // what the board should look like at `now_ms`
+    pub fn decorations(&mut self, now_ms: f64) -> Vec<(Vector, Decoration)> {
+        self.flashes
+            .retain(|(_, start_ms)| now_ms - start_ms < FLASH_MS);
+        // settled down, even if nothing's been drawing it
+        if self.shake_strength(now_ms) == 0.0 {
+            self.shake = None;
+        }
+
+        let mut decorations = self
+            .flashes
+            .iter()
+            .map(|(pos, _)| (pos.clone(), Decoration::Flash))
+            .collect::<Vec<_>>();
+
+        self.collapsing = false;
+        if let Some((segments, start_ms)) = self.collapse.as_ref() {
+            let step_ms = COLLAPSE_STEP_MS.min(COLLAPSE_MAX_MS / segments.len().max(1) as f64);
+            let crumbled = (((now_ms - start_ms) / step_ms) as usize + 1).min(segments.len());
+
+            self.collapsing = crumbled < segments.len();
+            // what's crumbled stays crumbled until the next game
+            decorations.extend(
+                segments[..crumbled]
+                    .iter()
+                    .map(|pos| (pos.clone(), Decoration::Collapsed)),
+            );
+        }
+
+        decorations
+    }
+
+    // what should be floating over the board at `now_ms`
+    pub fn floating_texts(&mut self, now_ms: f64) -> Vec<FloatingText> {
+        self.texts
+            .retain(|(_, _, start_ms)| now_ms - start_ms < FLOAT_MS);
+
+        self.texts
+            .iter()
+            .map(|(position, text, start_ms)| FloatingText {
+                position: position.clone(),
+                text: text.clone(),
+                progress: ((now_ms - start_ms) / FLOAT_MS).clamp(0.0, 1.0),
+            })
+            .collect()
+    }
+
+    // the game over message waits for the snake to finish crumbling
+    pub fn holds_overlay(&self) -> bool {
+        self.collapsing
+    }
+
+    fn is_running(&self) -> bool {
+        !self.flashes.is_empty()
+            || self.collapsing
+            || !self.texts.is_empty()
+            || self.shake.is_some()
+    }
+}
+
+thread_local! {
+    static ANIMATIONS: RefCell<Animations> = RefCell::new(Animations::default());
+
+    static RUNNING: Cell<bool> = const { Cell::new(false) };
+
+    static FRAME_CLOSURE: Closure<dyn FnMut()> =
+        Closure::wrap(Box::new(animation_frame) as Box<dyn FnMut()>);
+}
+
+pub fn on_event(event: &GameEvent, game: &SnakeGame) {
+    // with reduced motion everything just happens, but a restart still clears what's left
+    let reduced_motion = crate::SETTINGS.with(|settings| settings.borrow().reduced_motion);
+    if reduced_motion && *event != GameEvent::Restarted {
+        return;
+    }
+
+    let running = ANIMATIONS.with(|animations| {
+        let mut animations = animations.borrow_mut();
+        animations.on_event(event, game, js_sys::Date::now());
+        animations.is_running()
+    });
+
+    if running && !RUNNING.with(|running| running.replace(true)) {
+        request_animation_frame();
+    }
+}
+
+pub fn decorations() -> Vec<(Vector, Decoration)> {
+    ANIMATIONS.with(|animations| animations.borrow_mut().decorations(js_sys::Date::now()))
+}
+
+pub fn floating_texts() -> Vec<FloatingText> {
+    ANIMATIONS.with(|animations| animations.borrow_mut().floating_texts(js_sys::Date::now()))
+}
+
+pub fn shake_offset() -> (f64, f64) {
+    ANIMATIONS.with(|animations| animations.borrow_mut().shake_offset(js_sys::Date::now()))
+}
+
+// the drunk board at `now_ms`, leaning one way and the other, and every so often turning
+// itself over to mirrored and back
+pub fn sway(now_ms: f64) -> BoardTransform {
+    let turn = |period_ms: f64| now_ms / period_ms * std::f64::consts::TAU;
+    BoardTransform {
+        angle: SWAY_ANGLE * turn(SWAY_MS).sin(),
+        scale_x: turn(FLIP_MS).cos(),
+        ..BoardTransform::default()
+    }
+}
+
+pub fn holds_overlay() -> bool {
+    ANIMATIONS.with(|animations| animations.borrow().holds_overlay())
+}
+
+fn request_animation_frame() {
+    let requested = FRAME_CLOSURE.with(|closure| {
+        crate::error::window()?.request_animation_frame(closure.as_ref().unchecked_ref())?;
+        Ok(())
+    });
+    crate::error::report_failure(requested);
+}
+
+fn animation_frame() {
+    crate::draw();
+
+    if ANIMATIONS.with(|animations| animations.borrow().is_running()) {
+        request_animation_frame();
+    } else {
+        RUNNING.with(|running| running.set(false));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodKind;
+
+    #[test]
+    fn collapse() {
+        let game = SnakeGame::new(21, 15);
+        let length = game.snakes()[0].body.len();
+        let mut animations = Animations::default();
+
+        animations.on_event(
+            &GameEvent::FoodEaten {
+                position: Vector(3, 3),
+                kind: FoodKind::Normal,
+                multiplier: 1,
+                points: 1,
+            },
+            &game,
+            0.0,
+        );
+        animations.on_event(
+            &GameEvent::GameOver {
+                reason: "avoid walls",
+            },
+            &game,
+            0.0,
+        );
+
+        let decorations = animations.decorations(0.0);
+        assert!(decorations.contains(&(Vector(3, 3), Decoration::Flash)));
+        assert!(decorations.contains(&(game.snakes()[0].head().clone(), Decoration::Collapsed)));
+        assert_eq!(animations.collapse.as_ref().unwrap().0.len(), length);
+
+        // a longer snake, to watch it go a bit at a time
+        let segments = (0..5).map(|x| Vector(x, 0)).collect::<Vec<_>>();
+        animations.collapse = Some((segments, 0.0));
+        animations.decorations(0.0);
+        assert!(animations.holds_overlay());
+
+        // the flash is over long before the snake is
+        let decorations = animations.decorations(FLASH_MS);
+        assert!(!decorations.contains(&(Vector(3, 3), Decoration::Flash)));
+        assert_eq!(decorations.len(), 3);
+
+        let decorations = animations.decorations(COLLAPSE_STEP_MS * 5.0);
+        assert_eq!(decorations.len(), 5);
+        assert!(!animations.holds_overlay());
+        // once what the food was worth has floated off too
+        assert!(animations.is_running());
+        animations.floating_texts(FLOAT_MS);
+        assert!(!animations.is_running());
+
+        animations.on_event(&GameEvent::Restarted, &game, 0.0);
+        assert!(animations.decorations(0.0).is_empty());
+    }
+
+    #[test]
+    fn shake() {
+        let game = SnakeGame::new(21, 15);
+        let mut animations = Animations::default();
+        assert_eq!(animations.shake_offset(0.0), (0.0, 0.0));
+
+        animations.on_event(&GameEvent::NearMiss { player: 0 }, &game, 0.0);
+        assert_eq!(animations.shake_offset(0.0), (0.0, NEAR_MISS_SHAKE));
+        assert!(animations.is_running());
+
+        // a crash shakes harder, and a near miss doesn't cut it short
+        animations.on_event(
+            &GameEvent::GameOver {
+                reason: "avoid walls",
+            },
+            &game,
+            100.0,
+        );
+        animations.on_event(&GameEvent::NearMiss { player: 0 }, &game, 150.0);
+        assert_eq!(animations.shake, Some((CRASH_SHAKE, 100.0)));
+        let (x, y) = animations.shake_offset(250.0);
+        assert!(x.abs().max(y.abs()) <= CRASH_SHAKE / 2.0 + 1e-9);
+
+        assert_eq!(animations.shake_offset(100.0 + SHAKE_MS), (0.0, 0.0));
+        animations.decorations(10_000.0);
+        animations.floating_texts(10_000.0);
+        assert!(!animations.is_running());
+
+        // the drunk board starts out straight, and is mirrored halfway through a flip
+        assert_eq!(sway(0.0), BoardTransform::default());
+        assert!((sway(FLIP_MS / 2.0).scale_x + 1.0).abs() < 1e-9);
+        assert!(sway(SWAY_MS / 4.0).angle > 0.0);
+    }
+
+    #[test]
+    fn floating_texts() {
+        let game = SnakeGame::new(21, 15);
+        let mut animations = Animations::default();
+
+        for (kind, multiplier) in [(FoodKind::Normal, 1), (FoodKind::Bonus, 3)] {
+            animations.on_event(
+                &GameEvent::FoodEaten {
+                    position: Vector(3, 3),
+                    kind,
+                    multiplier,
+                    points: kind.score_delta() * multiplier as isize,
+                },
+                &game,
+                0.0,
+            );
+        }
+        assert_eq!(food_text(-1, 1), "-1");
+
+        let texts = animations.floating_texts(FLOAT_MS / 2.0);
+        assert_eq!(
+            texts.iter().map(|text| &text.text[..]).collect::<Vec<_>>(),
+            vec!["+1", "+9 combo!"]
+        );
+        assert_eq!(texts[0].progress, 0.5);
+        assert_eq!(texts[0].position, Vector(3, 3));
+
+        // they keep the frames coming until they're gone, flash or no flash
+        animations.decorations(FLASH_MS);
+        assert!(animations.is_running());
+        assert!(animations.floating_texts(FLOAT_MS).is_empty());
+        assert!(!animations.is_running());
+    }
+}
@@ -0,0 +1,259 @@
+use crate::bot;
+use crate::event::GameEvent;
+use crate::food::FoodKind;
+use crate::frame;
+use crate::geometry::Direction;
+use crate::geometry::Vector;
+use crate::profile;
+use crate::replay;
+use crate::scenario;
+use crate::settings::BoardSize;
+use crate::snake::SnakeGame;
+use crate::update;
+use crate::version;
+use crate::GAME;
+use crate::SETTINGS;
+
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+// Functions for the host page to control the game with, instead of faking keyboard events.
+// They all act on the same game the page is showing.
+
+thread_local! {
+    // see `slake_on_game_event`
+    static EVENT_CALLBACKS: RefCell<Vec<js_sys::Function>> = const { RefCell::new(vec![]) };
+}
+
+#[wasm_bindgen]
+pub fn slake_restart() {
+    GAME.with(|game| game.borrow_mut().restart());
+}
+
+// Takes "up", "down", "left", or "right", and returns whether that was one of them. On a hex
+// board those steer like the keys do, and "up_right" and "down_left" work too.
+#[wasm_bindgen]
+pub fn slake_set_direction(direction: &str) -> bool {
+    match Direction::from_name(direction) {
+        Some(direction) => {
+            GAME.with(|game| {
+                let mut game = game.borrow_mut();
+                game.change_player_direction(0, direction.clone());
+                replay::note(game.ticks, 0, direction);
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+#[wasm_bindgen]
+pub fn slake_get_score() -> usize {
+    GAME.with(|game| game.borrow().score)
+}
+
+// how long the current run has been played, by the page's clock, leaving out pauses and the
+// countdown
+#[wasm_bindgen]
+pub fn slake_get_play_ms() -> f64 {
+    crate::run_ms()
+}
+
+// Runs the game forward by `dt_ms` milliseconds. Pages that want to drive time themselves, from
+// their own animation loop or a test clock, should also call `slake_use_external_clock(true)`,
+// or the game keeps ticking on its own as well.
+#[wasm_bindgen]
+pub fn slake_advance(dt_ms: f64) {
+    crate::advance(dt_ms);
+}
+
+// stops the game's own timer, or starts it again with `false`
+#[wasm_bindgen]
+pub fn slake_use_external_clock(external: bool) {
+    crate::set_external_clock(external);
+}
+
+// which build is running, as JSON like
+// `{"version":"0.1.0","git_hash":"1a2b3c4","rules":3,"features":[]}`
+#[wasm_bindgen]
+pub fn slake_version() -> String {
+    version::version_json()
+}
+
+// For the page's service worker, as JSON: which cache this build goes in, and the URLs to put
+// in it, see `version::offline_manifest`
+#[wasm_bindgen]
+pub fn slake_offline_manifest() -> String {
+    version::offline_manifest()
+}
+
+// For when the service worker's found a new build, with its `assets` from `slake_version`.
+// Puts up a notice to reload, and returns whether it was newer than this one.
+#[wasm_bindgen]
+pub fn slake_update_available(assets: &str) -> bool {
+    update::announce(assets)
+}
+
+// Loads a script of dev console commands to run as the game goes, one `at <tick> <command>` per
+// line, replacing any that was already loaded. Returns what was wrong with it, or an empty
+// string if nothing was.
+#[wasm_bindgen]
+pub fn slake_load_scenario(source: &str) -> String {
+    match scenario::load(source) {
+        Ok(()) => String::new(),
+        Err(error) => error,
+    }
+}
+
+#[wasm_bindgen]
+pub fn slake_stop_scenario() {
+    scenario::stop();
+}
+
+// the board as it is now in the compact binary format described in `frame`, as a Uint8Array
+#[wasm_bindgen]
+pub fn slake_get_frame() -> Vec<u8> {
+    GAME.with(|game| frame::encode(&game.borrow()))
+}
+
+// Posts every tick's frame as a message to the page this one's embedded in, or to this page if
+// it isn't, for visualizers that would rather be told than ask. `target_origin` works like it
+// does for `postMessage`, and an empty string stops the frames.
+#[wasm_bindgen]
+pub fn slake_stream_frames(target_origin: &str) {
+    frame::set_target_origin(Some(target_origin.to_string()).filter(|origin| !origin.is_empty()));
+}
+
+// Changes the board to a custom size, as if it were picked in the settings, and starts over on
+// it. Returns what was wrong with the size, or an empty string if nothing was.
+#[wasm_bindgen]
+pub fn slake_set_board_size(width: isize, height: isize) -> String {
+    let render_style = SETTINGS.with(|settings| settings.borrow().render_style);
+    if let Err(error) = render_style.check_board(width, height) {
+        return error;
+    }
+
+    crate::update_settings(|settings| {
+        settings.board_size = BoardSize::Custom;
+        settings.width = width;
+        settings.height = height;
+    });
+    String::new()
+}
+
+// Everything the game has saved as one code, see `profile`, or an empty string if there's no
+// storage to read it from.
+#[wasm_bindgen]
+pub fn slake_export_profile() -> String {
+    profile::export_profile().unwrap_or_default()
+}
+
+// Replaces everything saved with a code from `slake_export_profile` and reloads the page with
+// it. Returns what was wrong with the code, or an empty string if nothing was.
+#[wasm_bindgen]
+pub fn slake_import_profile(code: &str) -> String {
+    match profile::import_profile(code) {
+        Ok(()) => String::new(),
+        Err(error) => error,
+    }
+}
+
+// Lets a bot steer player one, see `bot`. `bot` is called with the state as JSON before every
+// tick and returns a direction, or nothing to keep going. `null` takes it off again.
+#[wasm_bindgen]
+pub fn slake_register_bot(bot: Option<js_sys::Function>) {
+    bot::register(bot);
+}
+
+// Calls `callback` with everything that happens in the game from now on, as it happens, for the
+// page's own analytics or whatever else. Each event's an object like `GameEvent::to_json`'s, with
+// the `tick` of the run it happened on as well. None of it goes anywhere else.
+#[wasm_bindgen]
+pub fn slake_on_game_event(callback: js_sys::Function) {
+    EVENT_CALLBACKS.with(|callbacks| callbacks.borrow_mut().push(callback));
+}
+
+pub fn send_event(event: &GameEvent, tick: usize) {
+    // a callback could add another, so they're called from a copy
+    let callbacks = EVENT_CALLBACKS.with(|callbacks| callbacks.borrow().clone());
+    if callbacks.is_empty() {
+        return;
+    }
+
+    let Ok(object) = js_sys::JSON::parse(&event.to_json()) else {
+        return;
+    };
+    let _ = js_sys::Reflect::set(&object, &"tick".into(), &tick.into());
+    for callback in callbacks {
+        // the page's mistakes aren't the game's to stop over
+        let _ = callback.call1(&JsValue::NULL, &object);
+    }
+}
+
+// everything on the board as a JSON string. positions are `[x, y]` pairs, and each snake's body
+// starts with its head
+#[wasm_bindgen]
+pub fn slake_get_state_json() -> String {
+    GAME.with(|game| state_json(&game.borrow()))
+}
+
+fn positions_json<'a>(positions: impl Iterator<Item = &'a Vector>) -> String {
+    let positions = positions
+        .map(|Vector(x, y)| format!("[{x},{y}]"))
+        .collect::<Vec<_>>();
+
+    format!("[{}]", positions.join(","))
+}
+
+pub fn state_json(game: &SnakeGame) -> String {
+    let food_json = |kind| {
+        positions_json(
+            game.food()
+                .iter()
+                .filter(|food| food.kind == kind)
+                .map(|food| &food.position),
+        )
+    };
+
+    let snakes = game
+        .snakes()
+        .iter()
+        .map(|snake| positions_json(snake.body.iter()))
+        .collect::<Vec<_>>();
+
+    // one per player, so just one outside of versus
+    let scores = game
+        .scores
+        .iter()
+        .map(|score| score.to_string())
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"{{"width":{},"height":{},"score":{},"scores":[{}],"winner":{},"high_score":{},"game_over":{},"game_over_reason":{},"countdown":{},"snakes":[{}],"food":{},"bonus_food":{},"rotten_food":{},"speed_food":{},"shrink_food":{},"golden_food":{},"hazards":{},"walls":{}}}"#,
+        game.width,
+        game.height,
+        game.score,
+        scores.join(","),
+        match game.winner {
+            Some(player) => player.to_string(),
+            None => "null".to_string(),
+        },
+        game.high_score(),
+        game.is_game_over(),
+        match game.game_over_reason {
+            Some(reason) => format!("\"{reason}\""),
+            None => "null".to_string(),
+        },
+        game.countdown()
+            .map_or("null".to_string(), |count| count.to_string()),
+        snakes.join(","),
+        food_json(FoodKind::Normal),
+        food_json(FoodKind::Bonus),
+        food_json(FoodKind::Rotten),
+        food_json(FoodKind::Speed),
+        food_json(FoodKind::Shrink),
+        positions_json(game.golden_food().into_iter()),
+        positions_json(game.hazards().iter()),
+        positions_json(game.walls.iter()),
+    )
+}
@@ -0,0 +1,55 @@
+// Times `SnakeGame::tick` by letting the greedy bot play lots of games on boards of a few sizes,
+// and prints how fast it went along with how well the bot did.
+//
+//   cargo run --release --features bench --bin slake-bench -- [games per board]
+
+use slake::random;
+use slake::simulate;
+use slake::snake::SnakeGame;
+
+use std::time::Instant;
+
+const DEFAULT_GAMES: u16 = 200;
+const MAX_TICKS: usize = 20_000;
+const BOARDS: [(isize, isize); 3] = [(15, 11), (31, 21), (60, 40)];
+
+fn bench(width: isize, height: isize, games: u16) {
+    let mut ticks = 0;
+    let mut score = 0;
+    let started = Instant::now();
+
+    for seed in 0..games {
+        random::seed([seed, 0x5eed]);
+
+        let mut game = SnakeGame::new(width, height);
+        let result = simulate::simulate_game(&mut game, simulate::greedy, MAX_TICKS);
+
+        ticks += result.ticks;
+        score += result.score;
+    }
+
+    let seconds = started.elapsed().as_secs_f64();
+
+    println!(
+        "{width}x{height}: {games} games, {ticks} ticks in {seconds:.2}s, {:.0} ticks/s, average score {:.1}",
+        ticks as f64 / seconds,
+        score as f64 / games as f64,
+    );
+}
+
+fn main() {
+    let games = match std::env::args().nth(1) {
+        Some(games) => match games.parse() {
+            Ok(games) => games,
+            Err(_) => {
+                eprintln!("usage: slake-bench [games per board]");
+                std::process::exit(1);
+            }
+        },
+        None => DEFAULT_GAMES,
+    };
+
+    for (width, height) in BOARDS {
+        bench(width, height, games);
+    }
+}
@@ -0,0 +1,574 @@
+// Plays lots of random games looking for panics and broken bookkeeping, checking
+// `SnakeGame::invariants` after every tick. Failing runs are shrunk down to as few inputs as
+// possible and written out as case files, which can be played back with `--replay`.
+//
+//   cargo run --features fuzz --bin slake-fuzz -- [runs]
+//   cargo run --features fuzz --bin slake-fuzz -- --replay fuzz-cases/case-123.txt
+
+use slake::food::FoodCount;
+use slake::geometry::Direction;
+use slake::geometry::Grid;
+use slake::geometry::Topology;
+use slake::level::Level;
+use slake::level::BUILTIN_LEVELS;
+use slake::mode::GameMode;
+use slake::random;
+use slake::rules::RuleSet;
+use slake::snake::SnakeGame;
+use slake::version::Compatibility;
+use slake::version::Fingerprint;
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+const DEFAULT_RUNS: u64 = 1000;
+const INPUTS_PER_RUN: usize = 2000;
+const CASE_DIRECTORY: &str = "fuzz-cases";
+// for working out time attack's time limit
+const TICK_MS: i32 = 100;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Input {
+    Wait,
+    Steer(Direction),
+    Restart,
+}
+
+impl Input {
+    fn to_char(&self) -> char {
+        match self {
+            Input::Wait => '.',
+            Input::Steer(Direction::Up) => 'u',
+            Input::Steer(Direction::Right) => 'r',
+            Input::Steer(Direction::Down) => 'd',
+            Input::Steer(Direction::Left) => 'l',
+            Input::Steer(Direction::UpRight) => 'e',
+            Input::Steer(Direction::DownLeft) => 'z',
+            Input::Restart => '!',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Input> {
+        match c {
+            '.' => Some(Input::Wait),
+            'u' => Some(Input::Steer(Direction::Up)),
+            'r' => Some(Input::Steer(Direction::Right)),
+            'd' => Some(Input::Steer(Direction::Down)),
+            'l' => Some(Input::Steer(Direction::Left)),
+            'e' => Some(Input::Steer(Direction::UpRight)),
+            'z' => Some(Input::Steer(Direction::DownLeft)),
+            '!' => Some(Input::Restart),
+            _ => None,
+        }
+    }
+}
+
+// everything needed to play the exact same game again
+#[derive(Clone, Debug)]
+struct Case {
+    seed: [u16; 2],
+    width: isize,
+    height: isize,
+    topology: Topology,
+    grid: Grid,
+    hydra_mode: bool,
+    versus: bool,
+    coop: bool,
+    enemy_count: usize,
+    special_food: bool,
+    food_away_from_heads: bool,
+    food_count: FoodCount,
+    hazard_lifetime: Option<usize>,
+    starve_ticks: Option<usize>,
+    energy_drain: Option<usize>,
+    double_hazards: bool,
+    rules: RuleSet,
+    hazard_stun: bool,
+    grace_ticks: usize,
+    win_percent: Option<usize>,
+    combos: bool,
+    mode: GameMode,
+    // index into `BUILTIN_LEVELS`, which overrides the size
+    level: Option<usize>,
+    inputs: Vec<Input>,
+    // from the build that saved the case, `None` for new ones and ones saved before there were
+    // fingerprints
+    fingerprint: Option<Fingerprint>,
+}
+
+impl Case {
+    // on this build
+    fn fingerprint(&self) -> Fingerprint {
+        let (width, height) = match self.level {
+            Some(index) => {
+                let level = Level::parse(BUILTIN_LEVELS[index]).unwrap();
+                (level.width, level.height)
+            }
+            None => (self.width, self.height),
+        };
+
+        Fingerprint {
+            grid: self.grid,
+            ..Fingerprint::new(width, height, self.topology)
+        }
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "fingerprint={}\nseed={},{}\nwidth={}\nheight={}\ntopology={}\ngrid={}\nhydra_mode={}\nversus={}\ncoop={}\nenemy_count={}\nspecial_food={}\nfood_away_from_heads={}\nfood_count={}\nhazard_lifetime={}\nstarve_ticks={}\nenergy_drain={}\ndouble_hazards={}\nrules={}\nhazard_stun={}\ngrace_ticks={}\nwin_percent={}\ncombos={}\nmode={}\nlevel={}\ninputs={}\n",
+            self.fingerprint().serialize(),
+            self.seed[0],
+            self.seed[1],
+            self.width,
+            self.height,
+            self.topology.name(),
+            self.grid.name(),
+            self.hydra_mode,
+            self.versus,
+            self.coop,
+            self.enemy_count,
+            self.special_food,
+            self.food_away_from_heads,
+            self.food_count.name(),
+            self.hazard_lifetime.map_or("none".to_string(), |ticks| ticks.to_string()),
+            self.starve_ticks.map_or("none".to_string(), |ticks| ticks.to_string()),
+            self.energy_drain.map_or("none".to_string(), |drain| drain.to_string()),
+            self.double_hazards,
+            self.rules.name(),
+            self.hazard_stun,
+            self.grace_ticks,
+            self.win_percent.map_or("none".to_string(), |percent| percent.to_string()),
+            self.combos,
+            self.mode.name(),
+            self.level.map_or("none".to_string(), |index| index.to_string()),
+            self.inputs.iter().map(|input| input.to_char()).collect::<String>(),
+        )
+    }
+
+    // unlike the settings, a broken case file is an error, since we'd just be replaying the wrong
+    // game otherwise
+    fn parse(source: &str) -> Result<Case, String> {
+        let mut case = Case {
+            seed: [0, 0],
+            width: 21,
+            height: 15,
+            topology: Topology::Walls,
+            grid: Grid::Square,
+            hydra_mode: false,
+            versus: false,
+            coop: false,
+            enemy_count: 0,
+            special_food: false,
+            food_away_from_heads: false,
+            food_count: FoodCount::default(),
+            hazard_lifetime: None,
+            starve_ticks: None,
+            energy_drain: None,
+            double_hazards: false,
+            rules: RuleSet::default(),
+            hazard_stun: false,
+            grace_ticks: 0,
+            win_percent: None,
+            combos: false,
+            mode: GameMode::Classic,
+            level: None,
+            inputs: vec![],
+            fingerprint: None,
+        };
+
+        let optional = |value: &str| -> Result<Option<usize>, String> {
+            match value {
+                "none" => Ok(None),
+                _ => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("bad number '{value}'")),
+            }
+        };
+        let number = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("bad number '{value}'"))
+        };
+
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                // cases from before fingerprints only had this, for people to read
+                "version" => (),
+                "fingerprint" => {
+                    case.fingerprint =
+                        Some(Fingerprint::parse(value).ok_or("the fingerprint doesn't parse")?)
+                }
+                "seed" => {
+                    let (a, b) = value.split_once(',').ok_or("seed needs two numbers")?;
+                    case.seed = [number(a)? as u16, number(b)? as u16];
+                }
+                "width" => case.width = number(value)? as isize,
+                "height" => case.height = number(value)? as isize,
+                "topology" => {
+                    case.topology = Topology::from_name(value)
+                        .ok_or_else(|| format!("unknown topology '{value}'"))?
+                }
+                "grid" => {
+                    case.grid =
+                        Grid::from_name(value).ok_or_else(|| format!("unknown grid '{value}'"))?
+                }
+                "hydra_mode" => case.hydra_mode = value == "true",
+                "versus" => case.versus = value == "true",
+                "coop" => case.coop = value == "true",
+                "enemy_count" => case.enemy_count = number(value)?,
+                "special_food" => case.special_food = value == "true",
+                "food_away_from_heads" => case.food_away_from_heads = value == "true",
+                "food_count" => {
+                    case.food_count = FoodCount::from_name(value)
+                        .ok_or_else(|| format!("unknown food count '{value}'"))?
+                }
+                "hazard_lifetime" => case.hazard_lifetime = optional(value)?,
+                "starve_ticks" => case.starve_ticks = optional(value)?,
+                "energy_drain" => case.energy_drain = optional(value)?,
+                "double_hazards" => case.double_hazards = value == "true",
+                "rules" => {
+                    case.rules = RuleSet::from_name(value)
+                        .ok_or_else(|| format!("unknown rules '{value}'"))?
+                }
+                "hazard_stun" => case.hazard_stun = value == "true",
+                "grace_ticks" => case.grace_ticks = number(value)?,
+                "win_percent" => case.win_percent = optional(value)?,
+                "combos" => case.combos = value == "true",
+                "mode" => {
+                    case.mode = GameMode::from_name(value)
+                        .ok_or_else(|| format!("unknown mode '{value}'"))?
+                }
+                "level" => case.level = optional(value)?,
+                "inputs" => {
+                    case.inputs = value
+                        .chars()
+                        .map(|c| Input::from_char(c).ok_or_else(|| format!("unknown input '{c}'")))
+                        .collect::<Result<_, _>>()?
+                }
+                other => return Err(format!("unknown key '{other}'")),
+            }
+        }
+
+        Ok(case)
+    }
+}
+
+thread_local! {
+    // where the last panic happened, filled in by the panic hook
+    static PANIC_LOCATION: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+// a small xorshift, kept apart from the game's own random numbers so that picking inputs doesn't
+// change what the game rolls
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+fn random_case(run: u64) -> Case {
+    let mut rng = Rng(run.wrapping_mul(0x9e3779b97f4a7c15) | 1);
+
+    let inputs = (0..INPUTS_PER_RUN)
+        .map(|_| match rng.below(100) {
+            0..=59 => Input::Wait,
+            // the hex diagonals too, which square boards should turn down
+            60..=98 => Input::Steer(Direction::HEX[rng.below(6) as usize].clone()),
+            _ => Input::Restart,
+        })
+        .collect();
+
+    Case {
+        seed: [rng.next() as u16, rng.next() as u16],
+        width: 5 + rng.below(20) as isize,
+        height: 3 + rng.below(15) as isize,
+        topology: if rng.below(2) == 0 {
+            Topology::Walls
+        } else {
+            Topology::Wrap
+        },
+        grid: if rng.below(3) == 0 {
+            Grid::Hex
+        } else {
+            Grid::Square
+        },
+        hydra_mode: rng.below(2) == 0,
+        versus: rng.below(3) == 0,
+        coop: rng.below(2) == 0,
+        enemy_count: match rng.below(3) {
+            0 => 1 + rng.below(4) as usize,
+            _ => 0,
+        },
+        special_food: rng.below(2) == 0,
+        food_away_from_heads: rng.below(2) == 0,
+        food_count: match rng.below(4) {
+            0 => FoodCount::Fixed(2 + rng.below(3) as usize),
+            1 => FoodCount::UpTo(2 + rng.below(3) as usize),
+            _ => FoodCount::default(),
+        },
+        hazard_lifetime: match rng.below(3) {
+            0 => None,
+            _ => Some(1 + rng.below(50) as usize),
+        },
+        starve_ticks: match rng.below(3) {
+            0 => Some(1 + rng.below(30) as usize),
+            _ => None,
+        },
+        energy_drain: match rng.below(3) {
+            0 => Some(1 + rng.below(100) as usize),
+            _ => None,
+        },
+        double_hazards: rng.below(3) == 0,
+        rules: RuleSet {
+            hazards_on_eat: rng.below(4) != 0,
+            tail_blocks: rng.below(2) == 0,
+            self_collision: rng.below(4) != 0,
+            tail_cut: rng.below(3) == 0,
+        },
+        hazard_stun: rng.below(2) == 0,
+        grace_ticks: match rng.below(3) {
+            0 => rng.below(10) as usize,
+            _ => 0,
+        },
+        win_percent: match rng.below(3) {
+            0 => Some(1 + rng.below(100) as usize),
+            _ => None,
+        },
+        combos: rng.below(2) == 0,
+        level: match rng.below(4) {
+            0 => Some(rng.below(BUILTIN_LEVELS.len() as u64) as usize),
+            _ => None,
+        },
+        mode: GameMode::ALL[rng.below(GameMode::ALL.len() as u64) as usize],
+        inputs,
+        fingerprint: None,
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+// Plays the case through, returning the first problem along with the input it happened at.
+fn run_case(case: &Case) -> Result<(), (usize, String)> {
+    let current_input = Cell::new(0);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        random::seed(case.seed);
+
+        let mut game = SnakeGame::new(case.width, case.height);
+        game.topology = case.topology;
+        game.grid = case.grid;
+        game.hydra_mode = case.hydra_mode;
+        game.special_food = case.special_food;
+        game.food_away_from_heads = case.food_away_from_heads;
+        game.food_count = case.food_count;
+        game.hazard_lifetime = case.hazard_lifetime;
+        game.starve_ticks = case.starve_ticks;
+        game.energy_drain = case.energy_drain;
+        game.double_hazards = case.double_hazards;
+        game.rules = case.rules;
+        game.coop = case.coop;
+        game.enemy_count = case.enemy_count;
+        game.hazard_stun = case.hazard_stun;
+        game.grace_ticks = case.grace_ticks;
+        game.win_percent = case.win_percent;
+        game.combos = case.combos;
+        game.time_limit = case.mode.time_limit(TICK_MS);
+        game.tick_ms = TICK_MS as usize;
+        game.set_mode(case.mode);
+
+        // levels turn versus back off
+        match case.level {
+            Some(index) => game.load_level(&Level::parse(BUILTIN_LEVELS[index]).unwrap()),
+            None => game.set_versus(case.versus),
+        }
+
+        game.invariants().map_err(|error| (0, error))?;
+
+        for (index, input) in case.inputs.iter().enumerate() {
+            current_input.set(index);
+
+            match input {
+                Input::Wait => (),
+                Input::Steer(direction) => game.change_direction(direction.clone()),
+                Input::Restart => game.restart(),
+            }
+
+            game.tick();
+            game.take_events();
+
+            game.invariants().map_err(|error| (index, error))?;
+        }
+
+        Ok(())
+    }));
+
+    match result {
+        Ok(result) => result,
+        Err(payload) => Err((
+            current_input.get(),
+            format!(
+                "panic at {}: {}",
+                PANIC_LOCATION.with(|location| location.borrow().clone()),
+                panic_message(payload)
+            ),
+        )),
+    }
+}
+
+// Cuts the inputs down while the case keeps failing: first by dropping everything after the
+// failure, then chunks of ever smaller size, then by turning single inputs into waits.
+fn minimize(case: &Case) -> Case {
+    let mut case = case.clone();
+
+    if let Err((index, _)) = run_case(&case) {
+        case.inputs.truncate(index + 1);
+    }
+
+    let mut chunk = case.inputs.len() / 2;
+
+    while chunk > 0 {
+        let mut start = 0;
+
+        while start < case.inputs.len() {
+            let mut candidate = case.clone();
+            let end = (start + chunk).min(candidate.inputs.len());
+            candidate.inputs.drain(start..end);
+
+            if run_case(&candidate).is_err() {
+                case = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+
+        chunk /= 2;
+    }
+
+    for index in 0..case.inputs.len() {
+        if case.inputs[index] == Input::Wait {
+            continue;
+        }
+
+        let mut candidate = case.clone();
+        candidate.inputs[index] = Input::Wait;
+
+        if run_case(&candidate).is_err() {
+            case = candidate;
+        }
+    }
+
+    case
+}
+
+fn save_case(run: u64, case: &Case) -> std::io::Result<String> {
+    std::fs::create_dir_all(CASE_DIRECTORY)?;
+
+    let path = format!("{CASE_DIRECTORY}/case-{run}.txt");
+    std::fs::write(&path, case.serialize())?;
+
+    Ok(path)
+}
+
+fn replay(path: &str) -> Result<(), String> {
+    let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let case = Case::parse(&source)?;
+
+    match case
+        .fingerprint
+        .as_ref()
+        .map(|saved| saved.check(&case.fingerprint()))
+    {
+        Some(Compatibility::Compatible) => (),
+        Some(Compatibility::OtherBuild(warning)) => eprintln!("warning: {warning}"),
+        Some(Compatibility::Incompatible(reason)) => {
+            return Err(format!("{reason}, so it won't play back the same way"))
+        }
+        None => eprintln!(
+            "warning: the case has no fingerprint, so it might not play back the same way"
+        ),
+    }
+
+    match run_case(&case) {
+        Ok(()) => println!("{path} runs clean"),
+        Err((index, error)) => println!("{path} fails at input {index}: {error}"),
+    }
+
+    Ok(())
+}
+
+fn fuzz(runs: u64) {
+    let mut failures = 0;
+
+    for run in 0..runs {
+        let case = random_case(run);
+
+        if let Err((index, error)) = run_case(&case) {
+            failures += 1;
+
+            let minimized = minimize(&case);
+            println!(
+                "run {run} failed at input {index}: {error} (shrunk to {} inputs)",
+                minimized.inputs.len()
+            );
+
+            match save_case(run, &minimized) {
+                Ok(path) => println!("  saved to {path}"),
+                Err(error) => println!("  couldn't save the case: {error}"),
+            }
+        }
+    }
+
+    println!("{runs} runs, {failures} failed");
+}
+
+fn main() {
+    // panics are caught and reported per run, so just remember where they happened instead of
+    // printing the default message for each
+    panic::set_hook(Box::new(|info| {
+        if let Some(location) = info.location() {
+            PANIC_LOCATION.with(|old_location| *old_location.borrow_mut() = location.to_string());
+        }
+    }));
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args.iter().map(|arg| &arg[..]).collect::<Vec<_>>()[..] {
+        ["--replay", path] => {
+            if let Err(error) = replay(path) {
+                eprintln!("couldn't replay {path}: {error}");
+                std::process::exit(1);
+            }
+        }
+        [] => fuzz(DEFAULT_RUNS),
+        [runs] => match runs.parse() {
+            Ok(runs) => fuzz(runs),
+            Err(_) => eprintln!("usage: slake-fuzz [runs] | --replay <case file>"),
+        },
+        _ => eprintln!("usage: slake-fuzz [runs] | --replay <case file>"),
+    }
+}
@@ -0,0 +1,223 @@
+// Plays the game in a terminal, with the same `SnakeGame` the page runs and the retro theme's
+// glyphs and colors, for playing or poking at the rules without a browser.
+//
+//   cargo run --release --features tui --bin slake-tui -- [width height]
+//
+// Arrow keys or WASD steer, P pauses, R starts over, and Q or Esc quits.
+
+use slake::effects;
+use slake::geometry::Direction;
+use slake::geometry::Vector;
+use slake::random;
+use slake::snake;
+use slake::snake::SnakeGame;
+use slake::theme::Theme;
+use slake::view::TileKind;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_SIZE: (isize, isize) = (21, 15);
+// the same speed the page starts out at, which is what `SnakeGame::new` times effects by
+const TICK: Duration = Duration::from_millis(effects::DEFAULT_TICK_MS as u64);
+const THEME: Theme = Theme::Retro;
+
+enum Action {
+    Steer(Direction),
+    Pause,
+    Restart,
+    Quit,
+}
+
+fn action(key: KeyEvent) -> Option<Action> {
+    // some terminals send releases too
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let action = match key.code {
+        KeyCode::Up | KeyCode::Char('w') => Action::Steer(Direction::Up),
+        KeyCode::Right | KeyCode::Char('d') => Action::Steer(Direction::Right),
+        KeyCode::Down | KeyCode::Char('s') => Action::Steer(Direction::Down),
+        KeyCode::Left | KeyCode::Char('a') => Action::Steer(Direction::Left),
+        KeyCode::Char('p') | KeyCode::Char(' ') => Action::Pause,
+        KeyCode::Char('r') => Action::Restart,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+// the theme's colors are all "#rrggbb" for the retro one
+fn color(css: &str) -> Color {
+    let channel = |range: std::ops::Range<usize>| u8::from_str_radix(css.get(range)?, 16).ok();
+    match (css.len(), channel(1..3), channel(3..5), channel(5..7)) {
+        (7, Some(r), Some(g), Some(b)) => Color::Rgb { r, g, b },
+        _ => Color::Reset,
+    }
+}
+
+// Two columns to a tile, so the board comes out about square in most fonts.
+fn tile(kind: TileKind) -> (String, Color) {
+    let glyph = THEME.glyph(kind);
+    let text = match kind {
+        TileKind::Empty => "  ".to_string(),
+        TileKind::Wall => glyph.repeat(2),
+        _ => format!("{glyph} "),
+    };
+
+    (text, THEME.color(kind).map(color).unwrap_or(Color::Reset))
+}
+
+fn status(game: &SnakeGame) -> String {
+    if let Some(reason) = game.game_over_reason {
+        return format!("{reason}! R to play again, Q to quit");
+    }
+    if let Some(countdown) = game.countdown() {
+        return format!("{countdown}...");
+    }
+    if game.paused {
+        return "paused, P to carry on".to_string();
+    }
+
+    "arrows or WASD to steer, P to pause".to_string()
+}
+
+fn draw(out: &mut impl Write, game: &SnakeGame) -> io::Result<()> {
+    let view = game.board_view();
+    let (wall, wall_color) = tile(TileKind::Wall);
+
+    queue!(
+        out,
+        MoveTo(0, 0),
+        SetBackgroundColor(Color::Black),
+        SetForegroundColor(color(THEME.text_color())),
+        Print(format!("score {}  best {}", game.score, game.high_score())),
+        Clear(ClearType::UntilNewLine),
+    )?;
+
+    // the edge of the board is a wall too, just not one that's in `walls`
+    for y in -1..=view.height {
+        queue!(out, MoveTo(0, (y + 2) as u16))?;
+        for x in -1..=view.width {
+            let on_edge = x < 0 || y < 0 || x == view.width || y == view.height;
+            let (text, color) = match on_edge {
+                true => (wall.clone(), wall_color),
+                false => tile(view.get(&Vector(x, y))),
+            };
+            queue!(out, SetForegroundColor(color), Print(text))?;
+        }
+    }
+
+    queue!(
+        out,
+        MoveTo(0, (view.height + 3) as u16),
+        SetForegroundColor(color(THEME.text_color())),
+        Print(status(game)),
+        Clear(ClearType::UntilNewLine),
+        ResetColor,
+    )?;
+
+    out.flush()
+}
+
+fn play(out: &mut impl Write, game: &mut SnakeGame) -> io::Result<()> {
+    let mut next_tick = Instant::now() + TICK;
+    draw(out, game)?;
+
+    loop {
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) => match action(key) {
+                    Some(Action::Steer(direction)) => game.change_direction(direction),
+                    Some(Action::Pause) if !game.is_game_over() => {
+                        game.paused = !game.paused;
+                    }
+                    Some(Action::Restart) => {
+                        game.restart();
+                        next_tick = Instant::now() + TICK;
+                    }
+                    Some(Action::Quit) => return Ok(()),
+                    _ => (),
+                },
+                // a bigger window would leave the old board around it
+                Event::Resize(..) => queue!(out, Clear(ClearType::All))?,
+                _ => (),
+            }
+            draw(out, game)?;
+            continue;
+        }
+
+        game.tick();
+        // nothing here makes sounds or fireworks, so they'd only pile up
+        game.take_events();
+        draw(out, game)?;
+
+        next_tick += TICK;
+        // after a long pause at the terminal, like being suspended, don't rush to catch up
+        if next_tick < Instant::now() {
+            next_tick = Instant::now() + TICK;
+        }
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (width, height) = match &args[..] {
+        [] => DEFAULT_SIZE,
+        [width, height] => match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => {
+                eprintln!("usage: slake-tui [width height]");
+                std::process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("usage: slake-tui [width height]");
+            std::process::exit(1);
+        }
+    };
+    if let Err(error) = snake::check_board(width, height) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+
+    // anything that changes from one run of the program to the next will do
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.subsec_nanos())
+        .unwrap_or_default();
+    random::seed([nanos as u16, (nanos >> 16) as u16]);
+
+    let mut game = SnakeGame::new(width, height);
+    let mut out = io::stdout();
+
+    // a panic in raw mode would leave the message scrambled, and the terminal along with it
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        default_hook(info);
+    }));
+
+    let result = terminal::enable_raw_mode()
+        .and_then(|_| execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All)))
+        .and_then(|_| play(&mut out, &mut game));
+
+    // put the terminal back however it went
+    let _ = execute!(out, ResetColor, Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+
+    if let Err(error) = result {
+        eprintln!("slake-tui: {error}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,166 @@
+// Lets the greedy bot play a grid of rule settings, speed by food count by hazard rules, and
+// prints a CSV of how it did in each cell, for tuning the difficulties with numbers rather than
+// hunches. Every cell plays the same seeds, so differences between cells come from the rules.
+//
+//   cargo run --release --features sweep --bin slake-sweep -- [runs per cell] > sweep.csv
+//
+// The bot reacts instantly whatever the speed, so each run gets the same time on the clock
+// instead of the same number of ticks. A faster game packs more ticks into it, which is as near
+// as a bot gets to being rushed.
+
+use slake::food::FoodCount;
+use slake::random;
+use slake::simulate;
+use slake::snake::SnakeGame;
+
+const DEFAULT_RUNS: u16 = 50;
+// of game time, per run
+const RUN_SECONDS: usize = 120;
+const SPEEDS: [usize; 3] = [60, 90, 120];
+const FOOD_COUNTS: [usize; 3] = [1, 2, 3];
+
+struct Hazards {
+    name: &'static str,
+    // in ticks, `None` for forever
+    lifetime: Option<usize>,
+    double: bool,
+}
+
+// what the difficulties use, plus puddles that double up
+const HAZARDS: [Hazards; 4] = [
+    Hazards {
+        name: "fade_100",
+        lifetime: Some(100),
+        double: false,
+    },
+    Hazards {
+        name: "fade_250",
+        lifetime: Some(250),
+        double: false,
+    },
+    Hazards {
+        name: "forever",
+        lifetime: None,
+        double: false,
+    },
+    Hazards {
+        name: "double",
+        lifetime: None,
+        double: true,
+    },
+];
+
+// column names for the reasons a game can end, anything else counts as "other"
+const REASONS: [(&str, &str); 4] = [
+    ("wall", "avoid walls"),
+    ("tail", "avoid crashing into your own tail"),
+    ("hazard", "don't slip on the leftovers"),
+    ("starved", "don't starve"),
+];
+
+fn play(tick_ms: usize, food: usize, hazards: &Hazards, seed: u16) -> simulate::SimResult {
+    random::seed([seed, 0x5eed]);
+
+    let mut game = SnakeGame::new(21, 15);
+    game.hazard_lifetime = hazards.lifetime;
+    game.double_hazards = hazards.double;
+    game.tick_ms = tick_ms;
+    game.food_count = FoodCount::Fixed(food);
+    game.restart();
+
+    simulate::simulate_game(&mut game, simulate::greedy, RUN_SECONDS * 1000 / tick_ms)
+}
+
+fn cell(tick_ms: usize, food: usize, hazards: &Hazards, runs: u16) -> String {
+    let results = (0..runs)
+        .map(|seed| play(tick_ms, food, hazards, seed))
+        .collect::<Vec<_>>();
+
+    let mut scores = results
+        .iter()
+        .map(|result| result.score)
+        .collect::<Vec<_>>();
+    scores.sort_unstable();
+
+    let mean = |value: &dyn Fn(&simulate::SimResult) -> usize| {
+        results.iter().map(value).sum::<usize>() as f64 / runs as f64
+    };
+    let share = |reason: Option<&str>| {
+        let count = results
+            .iter()
+            .filter(|result| match reason {
+                Some(reason) => result.game_over_reason == Some(reason),
+                None => result
+                    .game_over_reason
+                    .is_some_and(|reason| REASONS.iter().all(|(_, known)| *known != reason)),
+            })
+            .count();
+        count as f64 / runs as f64
+    };
+
+    let mean_ticks = mean(&|result| result.ticks);
+    let mut row = vec![
+        tick_ms.to_string(),
+        food.to_string(),
+        hazards.name.to_string(),
+        runs.to_string(),
+        format!("{:.2}", mean(&|result| result.score)),
+        scores[scores.len() / 2].to_string(),
+        format!("{:.2}", mean(&|result| result.length)),
+        format!("{mean_ticks:.1}"),
+        format!("{:.1}", mean_ticks * tick_ms as f64 / 1000.0),
+        format!(
+            "{:.3}",
+            results
+                .iter()
+                .filter(|result| result.game_over_reason.is_none())
+                .count() as f64
+                / runs as f64
+        ),
+    ];
+    row.extend(
+        REASONS
+            .iter()
+            .map(|(_, reason)| format!("{:.3}", share(Some(reason)))),
+    );
+    row.push(format!("{:.3}", share(None)));
+
+    row.join(",")
+}
+
+fn main() {
+    let runs = match std::env::args().nth(1) {
+        Some(runs) => match runs.parse() {
+            Ok(runs) if runs > 0 => runs,
+            _ => {
+                eprintln!("usage: slake-sweep [runs per cell]");
+                std::process::exit(1);
+            }
+        },
+        None => DEFAULT_RUNS,
+    };
+
+    let mut header = vec![
+        "tick_ms",
+        "food",
+        "hazards",
+        "runs",
+        "mean_score",
+        "median_score",
+        "mean_length",
+        "mean_ticks",
+        "mean_seconds",
+        "timed_out",
+    ];
+    header.extend(REASONS.iter().map(|(name, _)| *name));
+    header.push("other");
+    println!("{}", header.join(","));
+
+    for tick_ms in SPEEDS {
+        for food in FOOD_COUNTS {
+            for hazards in HAZARDS.iter() {
+                println!("{}", cell(tick_ms, food, hazards, runs));
+            }
+        }
+    }
+}
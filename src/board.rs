@@ -0,0 +1,290 @@
+use crate::geometry;
+use crate::geometry::Vector;
+
+// `SnakeGame` keeps its snakes, hazards, and walls in lists, which are handy for drawing but
+// slow to search on big boards. These keep the same information by tile instead, so asking
+// what's on a tile or picking a free one doesn't mean going through everything.
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+struct Cell {
+    // counts rather than flags, since hazards can stack
+    snakes: u16,
+    hazards: u16,
+    wall: bool,
+    // see `enemy`. only ever one to a tile, since they only step onto free ones
+    enemy: bool,
+    // see `Level::portals`. nothing else ever goes on one
+    portal: bool,
+}
+
+// What's on each tile. Anything off the board counts as empty.
+#[derive(Clone, Default, Debug)]
+pub struct Board {
+    width: isize,
+    height: isize,
+    cells: Vec<Cell>,
+}
+
+impl Board {
+    pub fn new(width: isize, height: isize) -> Board {
+        Board {
+            width,
+            height,
+            cells: vec![Cell::default(); (width * height) as usize],
+        }
+    }
+
+    fn cell(&self, pos: &Vector) -> Option<&Cell> {
+        geometry::is_within_board(self.width, self.height, pos)
+            .then(|| &self.cells[(pos.1 * self.width + pos.0) as usize])
+    }
+
+    fn cell_mut(&mut self, pos: &Vector) -> Option<&mut Cell> {
+        geometry::is_within_board(self.width, self.height, pos)
+            .then(|| &mut self.cells[(pos.1 * self.width + pos.0) as usize])
+    }
+
+    pub fn is_snake_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| cell.snakes > 0)
+    }
+
+    pub fn is_hazard_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| cell.hazards > 0)
+    }
+
+    pub fn is_wall_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| cell.wall)
+    }
+
+    pub fn is_enemy_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| cell.enemy)
+    }
+
+    pub fn is_portal_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| cell.portal)
+    }
+
+    // whether a tile has a snake, a hazard, a wall, an enemy or a portal on it. food doesn't
+    // count
+    pub fn is_blocked_at(&self, pos: &Vector) -> bool {
+        self.cell(pos).is_some_and(|cell| {
+            cell.snakes > 0 || cell.hazards > 0 || cell.wall || cell.enemy || cell.portal
+        })
+    }
+
+    pub fn add_snake(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.snakes += 1;
+        }
+    }
+
+    pub fn remove_snake(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.snakes = cell.snakes.saturating_sub(1);
+        }
+    }
+
+    pub fn add_hazard(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.hazards += 1;
+        }
+    }
+
+    pub fn remove_hazard(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.hazards = cell.hazards.saturating_sub(1);
+        }
+    }
+
+    pub fn add_wall(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.wall = true;
+        }
+    }
+
+    pub fn set_enemy(&mut self, pos: &Vector, enemy: bool) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.enemy = enemy;
+        }
+    }
+
+    pub fn add_portal(&mut self, pos: &Vector) {
+        if let Some(cell) = self.cell_mut(pos) {
+            cell.portal = true;
+        }
+    }
+}
+
+// The tiles food can spawn on, as a list to pick from at random along with where each tile is
+// in that list. Adding, taking, and checking for a tile are all quick, and like a `Vec`,
+// `swap_remove` moves the last tile into the gap.
+#[derive(Clone, Default, Debug)]
+pub struct FreeSlots {
+    width: isize,
+    height: isize,
+    positions: Vec<Vector>,
+    // for every tile on the board, its index in `positions` if it's free
+    indices: Vec<Option<usize>>,
+}
+
+impl FreeSlots {
+    pub fn new(width: isize, height: isize) -> FreeSlots {
+        let mut slots = FreeSlots::default();
+        slots.reset(width, height);
+        slots
+    }
+
+    // empties it out for a board of this size
+    pub fn reset(&mut self, width: isize, height: isize) {
+        let area = (width * height) as usize;
+
+        self.width = width;
+        self.height = height;
+        self.positions.clear();
+        self.positions.reserve(area);
+        self.indices.clear();
+        self.indices.resize(area, None);
+    }
+
+    fn tile_index(&self, pos: &Vector) -> Option<usize> {
+        geometry::is_within_board(self.width, self.height, pos)
+            .then(|| (pos.1 * self.width + pos.0) as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Vector> {
+        self.positions.iter()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Vector> {
+        self.positions.get(index)
+    }
+
+    pub fn contains(&self, pos: &Vector) -> bool {
+        self.tile_index(pos)
+            .is_some_and(|tile| self.indices[tile].is_some())
+    }
+
+    // Marks a tile as free. Returns false if it already was, or it's off the board.
+    pub fn insert(&mut self, pos: Vector) -> bool {
+        let Some(tile) = self.tile_index(&pos) else {
+            return false;
+        };
+
+        if self.indices[tile].is_some() {
+            return false;
+        }
+
+        self.indices[tile] = Some(self.positions.len());
+        self.positions.push(pos);
+        true
+    }
+
+    // Marks a tile as taken. Returns false if it wasn't free to begin with.
+    pub fn remove(&mut self, pos: &Vector) -> bool {
+        match self.tile_index(pos).and_then(|tile| self.indices[tile]) {
+            Some(index) => {
+                self.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // takes the tile at `index` in the list, panicking if there isn't one
+    pub fn swap_remove(&mut self, index: usize) -> Vector {
+        let pos = self.positions.swap_remove(index);
+
+        let tile = self.tile_index(&pos).unwrap();
+        self.indices[tile] = None;
+
+        // the one that used to be last is in the gap now
+        if let Some(moved) = self.positions.get(index) {
+            let moved_tile = self.tile_index(moved).unwrap();
+            self.indices[moved_tile] = Some(index);
+        }
+
+        pos
+    }
+
+    pub fn extend(&mut self, positions: impl IntoIterator<Item = Vector>) {
+        for pos in positions {
+            self.insert(pos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_slots() {
+        let mut slots = FreeSlots::new(4, 3);
+        slots.extend((0..4).map(|x| Vector(x, 1)));
+
+        assert!(!slots.insert(Vector(2, 1)));
+        assert!(!slots.insert(Vector(9, 9)));
+        assert_eq!(slots.len(), 4);
+
+        assert!(slots.remove(&Vector(1, 1)));
+        assert!(!slots.remove(&Vector(1, 1)));
+        assert!(!slots.contains(&Vector(1, 1)));
+
+        // the last one filled the gap, and can still be found
+        assert_eq!(slots.get(1), Some(&Vector(3, 1)));
+        assert!(slots.remove(&Vector(3, 1)));
+        assert_eq!(
+            slots.iter().cloned().collect::<Vec<_>>(),
+            vec![Vector(0, 1), Vector(2, 1)]
+        );
+
+        assert_eq!(slots.swap_remove(0), Vector(0, 1));
+        assert!(slots.contains(&Vector(2, 1)));
+        assert!(!slots.contains(&Vector(0, 1)));
+
+        slots.reset(5, 5);
+        assert!(slots.is_empty());
+        assert!(slots.insert(Vector(4, 4)));
+    }
+
+    #[test]
+    fn occupancy() {
+        let mut board = Board::new(5, 5);
+        let pos = Vector(2, 3);
+
+        board.add_hazard(&pos);
+        board.add_hazard(&pos);
+        board.remove_hazard(&pos);
+        assert!(board.is_hazard_at(&pos));
+        board.remove_hazard(&pos);
+        assert!(!board.is_blocked_at(&pos));
+
+        board.add_snake(&pos);
+        board.add_wall(&Vector(0, 0));
+        assert!(board.is_snake_at(&pos));
+        assert!(board.is_wall_at(&Vector(0, 0)));
+        assert!(board.is_blocked_at(&pos));
+
+        board.set_enemy(&Vector(1, 1), true);
+        assert!(board.is_enemy_at(&Vector(1, 1)));
+        assert!(board.is_blocked_at(&Vector(1, 1)));
+        board.set_enemy(&Vector(1, 1), false);
+        assert!(!board.is_blocked_at(&Vector(1, 1)));
+
+        board.add_portal(&Vector(4, 0));
+        assert!(board.is_portal_at(&Vector(4, 0)));
+        assert!(board.is_blocked_at(&Vector(4, 0)));
+
+        // nothing's ever off the board
+        board.add_wall(&Vector(-1, 0));
+        assert!(!board.is_blocked_at(&Vector(-1, 0)));
+    }
+}
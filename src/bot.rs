@@ -0,0 +1,81 @@
+use crate::api;
+use crate::geometry::Direction;
+use crate::replay;
+use crate::GAME;
+
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+// Bots written in JavaScript, see `slake_register_bot`. Before every tick the bot gets the board
+// as `slake_get_state_json` has it and answers with where to steer player one, the same names
+// as `slake_set_direction` takes, or nothing to keep going. The tick waits for the answer, so a
+// bot never falls behind however long it thinks, and two bots given the same seed play the same
+// game. Runs a bot's steered don't count for high scores, stats, or ghosts, and bots sit out
+// online games.
+
+thread_local! {
+    static BOT: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+// `None` takes the bot off again
+pub fn register(bot: Option<js_sys::Function>) {
+    BOT.with(|registered| *registered.borrow_mut() = bot);
+}
+
+// what the bot answered, which can be nothing at all
+fn parse_reply(reply: Option<String>) -> Result<Option<Direction>, String> {
+    match reply.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(name) => Direction::from_name(name)
+            .map(Some)
+            .ok_or_else(|| format!("the bot said to go '{name}'")),
+    }
+}
+
+// Asks the bot where to go, just before a tick.
+pub fn steer() {
+    let Some(bot) = BOT.with(|bot| bot.borrow().clone()) else {
+        return;
+    };
+    if crate::net::is_active() || GAME.with(|game| game.borrow().is_game_over()) {
+        return;
+    }
+
+    let state = GAME.with(|game| api::state_json(&game.borrow()));
+    let reply = bot
+        .call1(&JsValue::NULL, &state.into())
+        .map_err(|error| format!("the bot threw {error:?}"))
+        .and_then(|reply| parse_reply(reply.as_string()));
+
+    match reply {
+        Ok(Some(direction)) => GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            game.change_player_direction(0, direction.clone());
+            game.tampered = true;
+            replay::note(game.ticks, 0, direction);
+        }),
+        // it's still playing, even when it's only watching
+        Ok(None) => GAME.with(|game| game.borrow_mut().tampered = true),
+        // rather than the same thing going wrong every tick
+        Err(error) => {
+            crate::log(&format!("Taking the bot off: {error}"));
+            register(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies() {
+        assert_eq!(parse_reply(None), Ok(None));
+        assert_eq!(parse_reply(Some(" ".to_string())), Ok(None));
+        assert_eq!(
+            parse_reply(Some("left\n".to_string())),
+            Ok(Some(Direction::Left))
+        );
+        assert!(parse_reply(Some("sideways".to_string())).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use crate::error::SlakeError;
+use crate::geometry::Geometry;
+use crate::geometry::Grid;
+use crate::geometry::Vector;
+use crate::render;
+use crate::render::Decoration;
+use crate::render::Tile;
+use crate::renderer::HudState;
+use crate::renderer::Renderer;
+use crate::settings::RenderStyle;
+use crate::settings::Settings;
+use crate::snake::Snake;
+use crate::snake::SnakeGame;
+use crate::sprites;
+use crate::theme::Theme;
+use crate::view::Segment;
+use crate::view::TileKind;
+
+use std::ops::Range;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlDivElement, HtmlElement, HtmlImageElement,
+};
+
+// The smooth style draws the whole board onto a canvas every animation frame. Everything but the
+// snake sits on its tile, same as the other styles, but the head and tail slide from one tile to
+// the next in between ticks. Hex boards get hexagons instead of squares, laid out by
+// `Geometry::tile_offset`. On levels with floors, the canvas is only as big as the floor that's
+// shown, and anything on the others is clipped off. The sprites theme draws its pictures in
+// place of the glyphs, and the snake a tile at a time with them instead of as a line.
+
+// in canvas pixels, about the size of a `.field` div
+const TILE_SIZE: f64 = 40.0;
+
+pub struct CanvasBoard {
+    width: isize,
+    height: isize,
+    // see `render::shown_rows`
+    rows: Range<isize>,
+    grid: Grid,
+    show_border: bool,
+    presentation: bool,
+    theme: Theme,
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    info_element: HtmlDivElement,
+    previous_info: String,
+}
+
+pub fn build_board(
+    game: &SnakeGame,
+    settings: &Settings,
+    rows: Range<isize>,
+) -> Result<CanvasBoard, SlakeError> {
+    let document = crate::error::document()?;
+    let border = if settings.show_border { 1 } else { 0 };
+
+    let root_container = crate::get_element::<HtmlElement>("root")?;
+
+    root_container.set_inner_html("");
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    canvas.set_class_name("canvas_board");
+    canvas.set_attribute("aria-hidden", "true")?;
+    let (width, height) = game
+        .geometry()
+        .extent(game.width + border * 2, rows.end - rows.start + border * 2);
+    canvas.set_width((width * TILE_SIZE) as u32);
+    canvas.set_height((height * TILE_SIZE) as u32);
+    // drawn at the one size, and shown at whatever fits, see `layout`
+    canvas
+        .style()
+        .set_property("--columns", &width.to_string())?;
+    canvas.style().set_property("--rows", &height.to_string())?;
+    root_container.append_child(&canvas)?;
+
+    let context = crate::error::context_2d(&canvas)?;
+
+    context.set_text_align("center");
+    context.set_text_baseline("middle");
+    // the sprites are pixel art, and blurring them up to size only makes them look smudged
+    context.set_image_smoothing_enabled(false);
+
+    let info_element = render::create_div(render::info_class(settings))?;
+    root_container.append_child(&info_element)?;
+
+    Ok(CanvasBoard {
+        width: game.width,
+        height: game.height,
+        rows,
+        grid: game.grid,
+        show_border: settings.show_border,
+        presentation: settings.presentation,
+        theme: settings.theme,
+        canvas,
+        context,
+        info_element,
+        previous_info: String::new(),
+    })
+}
+
+fn tile_center(geometry: &dyn Geometry, pos: &Vector) -> (f64, f64) {
+    let (left, top) = geometry.tile_offset(pos);
+    ((left + 0.5) * TILE_SIZE, (top + 0.5) * TILE_SIZE)
+}
+
+// fills in the tile's square, or its hexagon, with whatever fill is set
+fn fill_tile(context: &CanvasRenderingContext2d, grid: Grid, pos: &Vector) {
+    let (left, top) = grid.geometry().tile_offset(pos);
+    let (left, top) = (left * TILE_SIZE, top * TILE_SIZE);
+
+    match grid {
+        Grid::Square => context.fill_rect(left, top, TILE_SIZE, TILE_SIZE),
+        Grid::Hex => {
+            // pointy end up, matching the `.hex` fields in the page's stylesheet
+            let corners = [
+                (0.5, 0.0),
+                (1.0, 0.25),
+                (1.0, 0.75),
+                (0.5, 1.0),
+                (0.0, 0.75),
+                (0.0, 0.25),
+            ];
+            context.begin_path();
+            for (x, y) in corners {
+                context.line_to(left + x * TILE_SIZE, top + y * TILE_SIZE);
+            }
+            context.close_path();
+            context.fill();
+        }
+    }
+}
+
+// `None` when the two tiles are on opposite edges of a wrapping board, where there's nothing
+// sensible to slide along
+fn between(geometry: &dyn Geometry, from: &Vector, to: &Vector, alpha: f64) -> Option<(f64, f64)> {
+    if geometry.distance(from, to) > 1 {
+        return None;
+    }
+
+    let from = tile_center(geometry, from);
+    let to = tile_center(geometry, to);
+
+    Some((
+        from.0 + (to.0 - from.0) * alpha,
+        from.1 + (to.1 - from.1) * alpha,
+    ))
+}
+
+// `sheet` is the sprite sheet, when the theme has sprites and it's loaded
+fn draw_glyph(
+    context: &CanvasRenderingContext2d,
+    theme: Theme,
+    sheet: Option<&HtmlImageElement>,
+    kind: TileKind,
+    segment: Option<Segment>,
+    center: (f64, f64),
+) {
+    if let (Some(sheet), Some((column, row))) = (sheet, sprites::cell(kind, segment)) {
+        let _ = context
+            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                sheet,
+                column as f64 * sprites::SPRITE_PX,
+                row as f64 * sprites::SPRITE_PX,
+                sprites::SPRITE_PX,
+                sprites::SPRITE_PX,
+                center.0 - TILE_SIZE / 2.0,
+                center.1 - TILE_SIZE / 2.0,
+                TILE_SIZE,
+                TILE_SIZE,
+            );
+        return;
+    }
+
+    // emoji bring their own colors, anything else takes the fill
+    context.set_fill_style_str(theme.text_color());
+
+    // a failed draw just leaves the tile blank, not worth stopping the frame over
+    let _ = context.fill_text(theme.glyph(kind), center.0, center.1);
+}
+
+// `segments` are which way the head and the tail face, for sprites
+fn draw_snake(
+    context: &CanvasRenderingContext2d,
+    geometry: &dyn Geometry,
+    theme: Theme,
+    sheet: Option<&HtmlImageElement>,
+    snake: &Snake,
+    segments: (Option<Segment>, Option<Segment>),
+    alpha: f64,
+) {
+    let body = &snake.body;
+
+    // the head slides out of the neck, and the tail slides after it
+    let head = match body.get(1) {
+        Some(neck) => between(geometry, neck, &body[0], alpha),
+        None => None,
+    }
+    .unwrap_or(tile_center(geometry, &body[0]));
+    let tail = snake
+        .previous_tail
+        .as_ref()
+        .and_then(|previous_tail| between(geometry, previous_tail, snake.tail(), alpha))
+        .unwrap_or(tile_center(geometry, snake.tail()));
+
+    let (head_kind, tail_kind, body_kind) = TileKind::snake_parts(snake.player);
+
+    // the body's sprites are drawn on their tiles along with everything else
+    if sheet.is_some() {
+        if body.len() > 1 {
+            draw_glyph(context, theme, sheet, tail_kind, segments.1, tail);
+        }
+        draw_glyph(context, theme, sheet, head_kind, segments.0, head);
+        return;
+    }
+
+    context.set_stroke_style_str(theme.color(body_kind).unwrap());
+    context.set_line_width(TILE_SIZE * 0.6);
+    context.set_line_cap("round");
+    context.set_line_join("round");
+    context.begin_path();
+    context.move_to(head.0, head.1);
+
+    for (index, pos) in body.iter().enumerate().skip(1) {
+        let (x, y) = tile_center(geometry, pos);
+
+        // don't draw a line across the whole board where the snake wraps around
+        if geometry.distance(&body[index - 1], pos) > 1 {
+            context.move_to(x, y);
+        } else {
+            context.line_to(x, y);
+        }
+    }
+
+    context.line_to(tail.0, tail.1);
+    context.stroke();
+
+    if body.len() > 1 {
+        draw_glyph(context, theme, None, tail_kind, None, tail);
+    }
+    draw_glyph(context, theme, None, head_kind, None, head);
+}
+
+impl Renderer for CanvasBoard {
+    fn fits(&self, game: &SnakeGame, settings: &Settings) -> bool {
+        settings.render_style == RenderStyle::Smooth
+            && self.width == game.width
+            && self.height == game.height
+            && self.rows == render::shown_rows(game)
+            && self.grid == game.grid
+            && self.show_border == settings.show_border
+            && self.presentation == settings.presentation
+            && self.theme == settings.theme
+    }
+
+    fn draw(&mut self, game: &SnakeGame, tiles: Vec<Tile>, hud: &HudState) -> Result<(), JsValue> {
+        let rows = self.rows.clone();
+        let context = &self.context;
+        let (grid, geometry) = (game.grid, game.geometry());
+        let border = if self.show_border { 1 } else { 0 };
+        let (board_width, board_height) = geometry.extent(game.width, rows.end - rows.start);
+        let (board_width, board_height) = (board_width * TILE_SIZE, board_height * TILE_SIZE);
+        // where the top left of the rows that are shown is, drawn from the top of the board
+        let (floor_left, floor_top) = geometry.tile_offset(&Vector(0, rows.start));
+        let (floor_left, floor_top) = (floor_left * TILE_SIZE, floor_top * TILE_SIZE);
+
+        // nothing moves once the game is over
+        let alpha = if game.is_game_over() {
+            1.0
+        } else {
+            hud.alpha.clamp(0.0, 1.0)
+        };
+
+        let theme = self.theme;
+        let sheet = sprites::image().filter(|_| theme.has_sprites());
+        let sheet = sheet.as_ref();
+
+        let canvas = &self.canvas;
+        context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+
+        // the whole thing moved about around its middle, see `BoardTransform`
+        context.save();
+        let transform = &hud.transform;
+        let (middle_x, middle_y) = (canvas.width() as f64 / 2.0, canvas.height() as f64 / 2.0);
+        context.translate(
+            middle_x + transform.offset.0 * TILE_SIZE,
+            middle_y + transform.offset.1 * TILE_SIZE,
+        )?;
+        context.rotate(transform.angle)?;
+        context.scale(transform.scale_x, 1.0)?;
+        context.translate(-middle_x, -middle_y)?;
+
+        // everything from here on is drawn from the top left of the board itself, moved up so
+        // the rows that are shown start at the top of the canvas
+        context.save();
+        let (left, top) = geometry.tile_offset(&Vector(border, border));
+        context.translate(left * TILE_SIZE - floor_left, top * TILE_SIZE - floor_top)?;
+
+        for y in rows.start - border..rows.end + border {
+            for x in -border..game.width + border {
+                let pos = Vector(x, y);
+                match geometry.contains(game.width, game.height, &pos) && rows.contains(&y) {
+                    true => context.set_fill_style_str(theme.background()),
+                    false => context.set_fill_style_str(theme.color(TileKind::Wall).unwrap()),
+                }
+                fill_tile(context, grid, &pos);
+            }
+        }
+
+        // the other floors are still there, they just aren't shown
+        context.save();
+        context.begin_path();
+        context.rect(floor_left, floor_top, board_width, board_height);
+        context.clip();
+
+        context.set_font(&format!("{}px sans-serif", TILE_SIZE * 0.7));
+
+        for (index, tile) in tiles.iter().enumerate() {
+            let pos = Vector(index as isize % game.width, index as isize / game.width);
+
+            // decorations go underneath everything, snakes included
+            if let Some(decoration) = tile.decoration {
+                context.set_global_alpha(1.0);
+                context.set_fill_style_str(decoration.color());
+                fill_tile(context, grid, &pos);
+            }
+
+            // the snakes get drawn on their own below, apart from sprites of their bodies
+            let body = matches!(tile.kind, TileKind::Body | TileKind::RivalBody);
+            if tile.kind == TileKind::Empty || (tile.kind.is_snake() && !(body && sheet.is_some()))
+            {
+                continue;
+            }
+
+            context.set_global_alpha(tile.opacity as f64 / 100.0);
+            draw_glyph(
+                context,
+                theme,
+                sheet,
+                tile.kind,
+                tile.segment,
+                tile_center(geometry, &pos),
+            );
+        }
+
+        context.set_global_alpha(1.0);
+
+        for snake in game.snakes().iter() {
+            // stunned snakes aren't going anywhere
+            let alpha = if snake.is_stunned() { 1.0 } else { alpha };
+            let segment = |pos: &Vector| tiles[(pos.1 * game.width + pos.0) as usize].segment;
+            let segments = (segment(&snake.body[0]), segment(snake.tail()));
+            draw_snake(context, geometry, theme, sheet, snake, segments, alpha);
+        }
+
+        // the snakes are drawn whole, so what's crumbled of them goes over the top
+        for (index, tile) in tiles.iter().enumerate() {
+            if tile.decoration == Some(Decoration::Collapsed) {
+                let pos = Vector(index as isize % game.width, index as isize / game.width);
+                context.set_fill_style_str(theme.background());
+                fill_tile(context, grid, &pos);
+                draw_glyph(
+                    context,
+                    theme,
+                    sheet,
+                    TileKind::Hazard,
+                    None,
+                    tile_center(geometry, &pos),
+                );
+            }
+        }
+
+        // see-through, since the snakes don't sit on their tiles to be drawn over like the rest
+        context.set_global_alpha(0.5);
+        for (index, tile) in tiles.iter().enumerate() {
+            if let Some(color) = tile.highlight.and_then(|highlight| highlight.color()) {
+                let pos = Vector(index as isize % game.width, index as isize / game.width);
+                context.set_fill_style_str(color);
+                fill_tile(context, grid, &pos);
+            }
+        }
+        context.set_global_alpha(1.0);
+        context.restore();
+
+        context.set_fill_style_str(theme.text_color());
+        context.set_font(&format!("bold {}px sans-serif", TILE_SIZE * 0.5));
+        for text in hud
+            .texts
+            .iter()
+            .filter(|text| rows.contains(&text.position.1))
+        {
+            let (x, y) = tile_center(geometry, &text.position);
+            context.set_global_alpha(1.0 - text.progress);
+            context.fill_text(&text.text, x, y - text.progress * TILE_SIZE)?;
+        }
+        context.set_global_alpha(1.0);
+
+        if let Some(text) = &hud.overlay {
+            context.set_fill_style_str(theme.overlay_color());
+            context.fill_rect(floor_left, floor_top, board_width, board_height);
+            context.set_fill_style_str(theme.text_color());
+            context.set_font(&format!("{}px sans-serif", TILE_SIZE * 0.6));
+
+            let lines = text.lines().collect::<Vec<_>>();
+            let top = floor_top + board_height / 2.0 - (lines.len() - 1) as f64 * TILE_SIZE / 2.0;
+            let middle = floor_left + board_width / 2.0;
+
+            for (index, line) in lines.iter().enumerate() {
+                context.fill_text(line, middle, top + index as f64 * TILE_SIZE)?;
+            }
+        }
+
+        context.restore();
+        context.restore();
+
+        // presentation mode leaves the name out when there isn't one
+        let info_text = [hud.info.0.as_str(), hud.info.1.as_str()]
+            .into_iter()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if self.previous_info != info_text {
+            self.info_element.set_inner_text(&info_text);
+        }
+
+        self.previous_info = info_text;
+
+        Ok(())
+    }
+}
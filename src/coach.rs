@@ -0,0 +1,112 @@
+use crate::geometry;
+use crate::geometry::Vector;
+use crate::snake::SnakeGame;
+
+// Beginner hints, for spotting trouble a move or two before it happens. These only look at the
+// first snake, and only at where it's headed right now.
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Hint {
+    // the way ahead is blocked, or ends in a dead end with no room to turn
+    NoTurnSpace,
+    // going ahead leads into a space too small to fit the snake
+    SealingIn,
+}
+
+// Where to show the hint, and what's wrong, if anything. That's the tile the snake is about to
+// move onto, or the head itself when that's off the board.
+pub fn hint(game: &SnakeGame) -> Option<(Vector, Hint)> {
+    let snake = game.snakes().first()?;
+    let direction = snake.direction();
+
+    let step = |pos: &Vector, direction: &geometry::Direction| game.next_tile(pos, direction);
+
+    let is_open = |pos: &Vector| {
+        geometry::is_within_board(game.width, game.height, pos)
+            && !game.is_wall_at(pos)
+            && !game.is_portal_at(pos)
+            && !game.is_hazard_at(pos)
+            && !game.is_snake_at(pos)
+    };
+
+    let ahead = step(snake.head(), direction);
+
+    if !is_open(&ahead) {
+        let shown_at = if geometry::is_within_board(game.width, game.height, &ahead) {
+            ahead
+        } else {
+            snake.head().clone()
+        };
+        return Some((shown_at, Hint::NoTurnSpace));
+    }
+
+    // a tile with nothing open but the way back out
+    let sides = game
+        .geometry()
+        .directions()
+        .iter()
+        .filter(|side| *side != direction && **side != direction.opposite());
+    let can_turn = sides.into_iter().any(|side| is_open(&step(&ahead, side)));
+
+    if !can_turn && !is_open(&step(&ahead, direction)) {
+        return Some((ahead, Hint::NoTurnSpace));
+    }
+
+    // this doesn't look across wrapping edges, so it's a bit jumpy on wrapping boards
+    let room = game
+        .geometry()
+        .flood_fill(game.width, game.height, &ahead, &is_open)
+        .len();
+
+    if room < snake.body.len() {
+        return Some((ahead, Hint::SealingIn));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints() {
+        // heading left along the middle of an open board is fine
+        let mut game = SnakeGame::new(21, 15);
+        assert_eq!(hint(&game), None);
+
+        // the edge of the board right in front
+        game.snakes_mut()[0].body[0] = Vector(0, 7);
+        game.snakes_mut()[0].body[1] = Vector(1, 7);
+        game.rebuild_board();
+        assert_eq!(hint(&game), Some((Vector(0, 7), Hint::NoTurnSpace)));
+        game.snakes_mut()[0].body[0] = Vector(19, 7);
+        game.snakes_mut()[0].body[1] = Vector(20, 7);
+
+        // a wall right in front
+        game.walls = vec![Vector(18, 7)];
+        game.rebuild_board();
+        assert_eq!(hint(&game), Some((Vector(18, 7), Hint::NoTurnSpace)));
+
+        // a dead end one tile ahead
+        game.walls = vec![Vector(17, 7), Vector(18, 6), Vector(18, 8)];
+        game.rebuild_board();
+        assert_eq!(hint(&game), Some((Vector(18, 7), Hint::NoTurnSpace)));
+
+        // a pocket of four tiles, with room to turn but not enough for the snake
+        game.walls = vec![
+            Vector(18, 6),
+            Vector(18, 8),
+            Vector(17, 5),
+            Vector(17, 9),
+            Vector(16, 6),
+            Vector(16, 7),
+            Vector(16, 8),
+        ];
+        game.snakes_mut()[0].body.push_back(Vector(20, 6));
+        game.snakes_mut()[0].body.push_back(Vector(20, 5));
+        game.snakes_mut()[0].body.push_back(Vector(20, 4));
+        game.rebuild_board();
+        assert_eq!(hint(&game), Some((Vector(18, 7), Hint::SealingIn)));
+    }
+}
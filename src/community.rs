@@ -0,0 +1,221 @@
+use crate::error::SlakeError;
+use crate::level::Level;
+
+use js_sys::{Array, Reflect, JSON};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement, Url, XmlHttpRequest};
+
+// Community levels are listed in a JSON index, set with `data-level-index` on the root element:
+//
+//     [{ "name": "Spiral", "author": "someone", "url": "spiral.txt" }, ...]
+//
+// Level urls are relative to the index. Everything fetched is cached in local storage, so the
+// levels that were played once keep working offline.
+
+const CACHE_PREFIX: &str = "slake_cache:";
+
+#[derive(Clone)]
+pub struct CommunityLevel {
+    pub name: String,
+    pub author: String,
+    pub url: String,
+}
+
+pub fn parse_index(source: &str, index_url: &str) -> Result<Vec<CommunityLevel>, String> {
+    let value = JSON::parse(source).map_err(|_| "the level index isn't valid JSON".to_string())?;
+
+    let entries = value
+        .dyn_into::<Array>()
+        .map_err(|_| "the level index should be a list of levels".to_string())?;
+
+    entries
+        .iter()
+        .map(|entry| {
+            let field = |name: &str| {
+                Reflect::get(&entry, &name.into())
+                    .ok()
+                    .and_then(|value| value.as_string())
+            };
+
+            let url = field("url").ok_or_else(|| "a level in the index has no url".to_string())?;
+
+            Ok(CommunityLevel {
+                name: field("name").unwrap_or_else(|| "Untitled".to_string()),
+                author: field("author").unwrap_or_else(|| "anonymous".to_string()),
+                url: Url::new_with_base(&url, index_url)
+                    .map(|url| url.href())
+                    .unwrap_or(url),
+            })
+        })
+        .collect()
+}
+
+// fetches the text at `url`, falling back to the copy from last time when that fails
+pub fn fetch_cached(url: &str, done: impl FnOnce(Result<String, String>) + 'static) {
+    let cache_key = format!("{CACHE_PREFIX}{url}");
+    let done = RefCell::new(Some(done));
+
+    let finish = Rc::new(move |fetched: Option<String>| {
+        // both the load and error handlers can end up here, but only the first one counts
+        let Some(done) = done.borrow_mut().take() else {
+            return;
+        };
+
+        let storage = crate::local_storage();
+
+        let result = match fetched {
+            Some(text) => {
+                if let Some(storage) = storage {
+                    let _ = storage.set_item(&cache_key, &text);
+                }
+                Ok(text)
+            }
+            None => storage
+                .and_then(|storage| storage.get_item(&cache_key).ok().flatten())
+                .ok_or_else(|| "couldn't download it, and there's no saved copy".to_string()),
+        };
+
+        done(result);
+    });
+
+    let Ok(request) = XmlHttpRequest::new() else {
+        finish(None);
+        return;
+    };
+
+    if request.open("GET", url).is_err() {
+        finish(None);
+        return;
+    }
+
+    let handle_load = {
+        let finish = finish.clone();
+        let request = request.clone();
+
+        Closure::wrap(Box::new(move || {
+            let ok = (200..300).contains(&request.status().unwrap_or(0));
+            finish(request.response_text().ok().flatten().filter(|_| ok));
+        }) as Box<dyn FnMut()>)
+    };
+
+    let handle_error = {
+        let finish = finish.clone();
+
+        Closure::wrap(Box::new(move || finish(None)) as Box<dyn FnMut()>)
+    };
+
+    request.set_onload(Some(handle_load.as_ref().unchecked_ref()));
+    request.set_onerror(Some(handle_error.as_ref().unchecked_ref()));
+
+    // each request only ever finishes once, so leaking its handlers is cheaper than tracking them
+    handle_load.forget();
+    handle_error.forget();
+
+    if request.send().is_err() {
+        finish(None);
+    }
+}
+
+fn index_url() -> Option<String> {
+    crate::error::document()
+        .ok()?
+        .get_element_by_id("root")?
+        .get_attribute("data-level-index")
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("community");
+    panel_element.set_class_name("settings");
+    panel_element.style().set_property("display", "none")?;
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    Ok(())
+}
+
+pub fn toggle_panel() -> Result<(), SlakeError> {
+    let panel_element = crate::get_element::<HtmlElement>("community")?;
+    let visible = panel_element.style().get_property_value("display")? != "none";
+
+    if visible {
+        panel_element.style().set_property("display", "none")?;
+        return Ok(());
+    }
+
+    panel_element.style().set_property("display", "block")?;
+
+    let Some(index_url) = index_url() else {
+        panel_element.set_inner_text("This page doesn't list any community levels.");
+        return Ok(());
+    };
+
+    panel_element.set_inner_text("Loading community levels...");
+
+    // always fetch the index again on open, in case new levels showed up
+    fetch_cached(&index_url.clone(), move |result| {
+        let result = result.and_then(|source| parse_index(&source, &index_url));
+
+        match result {
+            Ok(levels) => crate::error::report_failure(show_levels(&panel_element, levels)),
+            Err(error) => {
+                panel_element.set_inner_text(&format!("Couldn't load community levels: {error}"))
+            }
+        }
+    });
+    Ok(())
+}
+
+fn show_levels(panel_element: &HtmlElement, levels: Vec<CommunityLevel>) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    panel_element.set_inner_text(if levels.is_empty() {
+        "No community levels yet."
+    } else {
+        ""
+    });
+
+    for community_level in levels {
+        let level_element = document
+            .create_element("div")?
+            .dyn_into::<HtmlDivElement>()?;
+
+        level_element.set_class_name("button");
+        level_element.set_inner_text(&format!(
+            "{} by {}",
+            community_level.name, community_level.author
+        ));
+
+        let status_element = level_element.clone();
+
+        let handle_click = Closure::wrap(Box::new(move || {
+            let status_element = status_element.clone();
+
+            fetch_cached(&community_level.url, move |result| {
+                match result.and_then(|source| Level::parse(&source)) {
+                    Ok(level) => {
+                        crate::play_level(&level);
+                        crate::error::report_failure(toggle_panel());
+                    }
+                    Err(error) => status_element.set_inner_text(&format!("Couldn't load: {error}")),
+                }
+            });
+        }) as Box<dyn FnMut()>);
+
+        level_element.set_onclick(Some(handle_click.as_ref().unchecked_ref()));
+
+        // the list only gets rebuilt when reopening the panel, so these don't pile up much
+        handle_click.forget();
+
+        panel_element.append_child(&level_element)?;
+    }
+
+    Ok(())
+}
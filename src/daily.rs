@@ -0,0 +1,114 @@
+use crate::mutator;
+use crate::settings::BoardSize;
+
+use prng::Prng16;
+use std::cell::RefCell;
+
+// The daily challenge is one board a day, the same for everybody. Like the weekly mutator it
+// all comes from the date in UTC, so there's no server to ask: the seed that places the maze,
+// the food and the hazards, along with the board size and how many enemies there are. Every
+// restart that day plays the same board again, and each day keeps its own best score.
+
+const SCORES_PREFIX: &str = "slake_daily:";
+
+// the sizes a day can get, all small enough for any render style
+const SIZES: [BoardSize; 2] = [BoardSize::Small, BoardSize::Medium];
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Challenge {
+    // like "2026-10-14"
+    pub date: String,
+    pub seed: [u16; 2],
+    pub size: BoardSize,
+    pub enemies: usize,
+}
+
+impl Challenge {
+    // for a day counted from the Unix epoch
+    pub fn for_days(days: i64) -> Challenge {
+        let (year, month, day) = mutator::civil_from_days(days);
+        let mut prng = Prng16::new([(days >> 16) as u16, days as u16]);
+        let mut roll = || prng.next().unwrap();
+
+        Challenge {
+            date: format!("{year}-{month:02}-{day:02}"),
+            seed: [roll(), roll()],
+            size: SIZES[roll() as usize % SIZES.len()],
+            enemies: roll() as usize % 3,
+        }
+    }
+
+    pub fn dimensions(&self) -> (isize, isize) {
+        self.size.dimensions().unwrap()
+    }
+}
+
+thread_local! {
+    // worked out once, so the board doesn't change at midnight in the middle of a run
+    static TODAY: RefCell<Option<Challenge>> = const { RefCell::new(None) };
+}
+
+pub fn today() -> Challenge {
+    TODAY.with(|today| {
+        today
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                Challenge::for_days((js_sys::Date::now() / 86_400_000.0).floor() as i64)
+            })
+            .clone()
+    })
+}
+
+// like "slake_daily:2026-10-14"
+fn scores_key() -> String {
+    format!("{SCORES_PREFIX}{}", today().date)
+}
+
+// saved as `best=12`
+pub fn parse_best(source: &str) -> Option<usize> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "best")
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+// today's best score so far, if there's been a run today
+pub fn best() -> Option<usize> {
+    crate::local_storage()
+        .and_then(|storage| storage.get_item(&scores_key()).ok().flatten())
+        .and_then(|saved| parse_best(&saved))
+}
+
+pub fn record_score(score: usize) {
+    if best().is_some_and(|best| best >= score) {
+        return;
+    }
+
+    if let Some(storage) = crate::local_storage() {
+        // like the mutator's table, it's just for fun
+        let _ = storage.set_item(&scores_key(), &format!("best={score}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenges() {
+        let day = mutator::days_from_civil(2026, 10, 14);
+        let challenge = Challenge::for_days(day);
+
+        assert_eq!(challenge.date, "2026-10-14");
+        assert!(challenge.enemies < 3);
+        // the same for everybody, and different the next day
+        assert_eq!(Challenge::for_days(day), challenge);
+        assert_ne!(Challenge::for_days(day + 1).seed, challenge.seed);
+        assert_eq!(Challenge::for_days(day + 1).date, "2026-10-15");
+
+        assert_eq!(parse_best("best=12\n"), Some(12));
+        assert_eq!(parse_best("best=lots"), None);
+        assert_eq!(parse_best(""), None);
+    }
+}
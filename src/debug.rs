@@ -0,0 +1,172 @@
+use crate::error::SlakeError;
+use crate::snake::SnakeGame;
+
+use std::cell::Cell;
+use std::collections::HashSet;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+// A debug overlay, for seeing what the game sees. The board gets tinted where tiles are taken
+// or semi-open, see `render::board_tiles`, and a panel in the corner has the numbers behind it
+// along with how long the last frame took to draw.
+//
+// Along with it come controls for watching a run closely: freezing the clock, going on a tick
+// at a time, and slowing everything down. Like the dev console, they spoil the run for high
+// scores, and they're off while playing online.
+
+// how many times slower than usual the game can go, in the order slow motion cycles through
+pub const SLOW_MOTION: [u32; 4] = [1, 2, 4, 8];
+
+thread_local! {
+    static SHOWING: Cell<bool> = const { Cell::new(false) };
+    // into `SLOW_MOTION`
+    static SLOW_MOTION_INDEX: Cell<usize> = const { Cell::new(0) };
+}
+
+// 1 at the usual speed
+pub fn slow_motion() -> u32 {
+    SLOW_MOTION[SLOW_MOTION_INDEX.with(|index| index.get())]
+}
+
+fn is_frozen() -> bool {
+    crate::SCHEDULER.with(|scheduler| scheduler.is_paused())
+}
+
+// whether the run's being played any differently than usual
+pub fn in_use() -> bool {
+    slow_motion() > 1 || is_frozen()
+}
+
+// the other player would be left behind
+fn allowed() -> bool {
+    if crate::net::is_active() {
+        let _ = crate::toast::show("Not while playing online");
+        return false;
+    }
+
+    crate::GAME.with(|game| game.borrow_mut().tampered = true);
+    true
+}
+
+pub fn toggle_freeze() {
+    if !allowed() {
+        return;
+    }
+
+    crate::SCHEDULER.with(|scheduler| match scheduler.is_paused() {
+        true => scheduler.resume(),
+        false => scheduler.pause(),
+    });
+    crate::draw();
+}
+
+// a tick right now, freezing the clock first so it's the only one
+pub fn step() {
+    if !allowed() {
+        return;
+    }
+
+    crate::SCHEDULER.with(|scheduler| scheduler.pause());
+    crate::step_game();
+}
+
+pub fn cycle_slow_motion() {
+    if !allowed() {
+        return;
+    }
+
+    SLOW_MOTION_INDEX.with(|index| index.set((index.get() + 1) % SLOW_MOTION.len()));
+    // the scheduler has to slow down to match
+    crate::start_ticking(crate::SETTINGS.with(|settings| settings.borrow().tick_ms));
+    let _ = crate::toast::show(&format!("Slow motion: {}x", slow_motion()));
+    crate::draw();
+}
+
+pub fn is_showing() -> bool {
+    SHOWING.with(|showing| showing.get())
+}
+
+pub fn toggle() -> Result<(), SlakeError> {
+    let showing = !SHOWING.with(|showing| showing.replace(!showing.get()));
+
+    let panel_element = panel()?;
+    let display = if showing { "block" } else { "none" };
+    panel_element.style().set_property("display", display)?;
+
+    crate::draw();
+    Ok(())
+}
+
+// made the first time it's needed, since most players never open it
+fn panel() -> Result<HtmlElement, SlakeError> {
+    if let Ok(panel_element) = crate::get_element::<HtmlElement>("debug_overlay") {
+        return Ok(panel_element);
+    }
+
+    let panel_element = crate::error::document()?
+        .create_element("div")?
+        .dyn_into::<HtmlElement>()?;
+    panel_element.set_id("debug_overlay");
+    panel_element.set_class_name("debug_overlay");
+    // the screen reader has the game's own announcements
+    panel_element.set_attribute("aria-hidden", "true")?;
+
+    crate::error::body()?.append_child(&panel_element)?;
+    Ok(panel_element)
+}
+
+pub fn text(game: &SnakeGame, render_ms: f64, frozen: bool) -> String {
+    let area = (game.width * game.height) as usize;
+    let free = game.free_positions().len();
+    // tiles next to more than one head or fruit only count once
+    let semi_open = game
+        .get_semi_open_tiles()
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .len();
+
+    let clock = match (frozen, slow_motion()) {
+        (true, _) => "frozen".to_string(),
+        (false, 1) => "running".to_string(),
+        (false, slow_motion) => format!("{slow_motion}x slower"),
+    };
+
+    format!(
+        "tick {}, clock {clock}\nseed {},{}\nfree {free}, taken {} (orange)\n\
+semi-open {semi_open} (grey)\nrender {render_ms:.1} ms",
+        game.ticks,
+        game.seed[0],
+        game.seed[1],
+        area - free
+    )
+}
+
+// after every frame that's drawn while it's showing
+pub fn update(game: &SnakeGame, render_ms: f64) -> Result<(), SlakeError> {
+    if is_showing() {
+        panel()?.set_inner_text(&text(game, render_ms, is_frozen()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_text() {
+        let mut game = SnakeGame::new(5, 3);
+        game.seed = [1, 2];
+
+        // a two tile snake and one fruit, on fifteen tiles
+        let shown = text(&game, 2.0, false);
+        assert!(shown.starts_with("tick 0, clock running\nseed 1,2\nfree 12, taken 3"));
+        assert!(shown.ends_with("render 2.0 ms"));
+
+        game.tick();
+        assert!(text(&game, 2.0, true).starts_with("tick 1, clock frozen\n"));
+
+        SLOW_MOTION_INDEX.with(|index| index.set(2));
+        assert!(text(&game, 2.0, false).starts_with("tick 1, clock 4x slower\n"));
+    }
+}
@@ -0,0 +1,395 @@
+use crate::error::SlakeError;
+use crate::food::FoodKind;
+use crate::geometry::Vector;
+use crate::random;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement, HtmlInputElement, KeyboardEvent};
+
+// A console for developers, opened with the backtick key, that takes little commands like
+// `spawn food 3` or `teleport 5 5` for setting up whatever's being tested without having to
+// play until it happens. Anything done from here spoils the run, so it doesn't count for high
+// scores or stats.
+
+const HELP: &str =
+    "spawn <normal|bonus|rotten|speed|shrink> [count], spawn hazard [count|ring], set speed <ms>, \
+teleport <x> <y>, seed <number>, phase <countdown|playing|paused|gameover>, \
+clock <pause|resume|step>, help";
+
+// how many lines of output stay on screen
+const HISTORY_LENGTH: usize = 8;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Phase {
+    Countdown,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+impl Phase {
+    fn from_name(name: &str) -> Option<Phase> {
+        match name {
+            "countdown" => Some(Phase::Countdown),
+            "playing" => Some(Phase::Playing),
+            "paused" => Some(Phase::Paused),
+            "gameover" => Some(Phase::GameOver),
+            _ => None,
+        }
+    }
+}
+
+// the page's clock rather than the game's, see `Scheduler`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Clock {
+    Pause,
+    Resume,
+    // one tick's worth of time, which is a tick unless something else is holding the game up
+    Step,
+}
+
+impl Clock {
+    fn from_name(name: &str) -> Option<Clock> {
+        match name {
+            "pause" => Some(Clock::Pause),
+            "resume" => Some(Clock::Resume),
+            "step" => Some(Clock::Step),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HazardSpawn {
+    // on free tiles picked at random
+    Random(usize),
+    // all the way around the edge of the board, wherever there's room
+    Ring,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Command {
+    Help,
+    Spawn { kind: FoodKind, count: usize },
+    SpawnHazards(HazardSpawn),
+    // milliseconds per tick, which goes through the settings like any other speed change
+    SetSpeed(i32),
+    Teleport(Vector),
+    Seed([u16; 2]),
+    Phase(Phase),
+    Clock(Clock),
+}
+
+impl Command {
+    pub fn parse(source: &str) -> Result<Command, String> {
+        let words = source.split_whitespace().collect::<Vec<_>>();
+
+        let number = |word: &str| {
+            word.parse::<i64>()
+                .map_err(|_| format!("'{word}' isn't a number"))
+        };
+
+        match words[..] {
+            ["help"] => Ok(Command::Help),
+            ["spawn", "hazard"] => Ok(Command::SpawnHazards(HazardSpawn::Random(1))),
+            ["spawn", "hazard", "ring"] => Ok(Command::SpawnHazards(HazardSpawn::Ring)),
+            ["spawn", "hazard", count] => Ok(Command::SpawnHazards(HazardSpawn::Random(
+                number(count)?.max(0) as usize,
+            ))),
+            ["spawn", kind] | ["spawn", kind, _] => {
+                // "food" reads better for the usual kind
+                let kind = match kind {
+                    "food" => FoodKind::Normal,
+                    _ => FoodKind::from_name(kind)
+                        .ok_or_else(|| format!("no food called '{kind}'"))?,
+                };
+                let count = match words.get(2) {
+                    Some(count) => number(count)?.max(0) as usize,
+                    None => 1,
+                };
+
+                Ok(Command::Spawn { kind, count })
+            }
+            ["set", "speed", ms] => Ok(Command::SetSpeed(
+                number(ms)?.clamp(0, i32::MAX as i64) as i32
+            )),
+            ["teleport", x, y] => Ok(Command::Teleport(Vector(
+                number(x)? as isize,
+                number(y)? as isize,
+            ))),
+            // the same two numbers the game seeds with, packed into one
+            ["seed", seed] => {
+                let seed = seed
+                    .parse::<u32>()
+                    .map_err(|_| format!("'{seed}' isn't a seed"))?;
+                Ok(Command::Seed([(seed >> 16) as u16, seed as u16]))
+            }
+            ["phase", phase] => Phase::from_name(phase)
+                .map(Command::Phase)
+                .ok_or_else(|| format!("no phase called '{phase}'")),
+            ["clock", clock] => Clock::from_name(clock)
+                .map(Command::Clock)
+                .ok_or_else(|| format!("the clock can't '{clock}'")),
+            [] => Err("type help for the commands".to_string()),
+            _ => Err(format!("don't know how to '{source}', try help")),
+        }
+    }
+}
+
+// Does whatever `command` says to the game, and says how it went. Changing the speed and the
+// clock aren't up to the game, so those are left to whoever has the settings and the clock.
+pub fn run(command: &Command, game: &mut SnakeGame) -> Result<String, String> {
+    let message = match command {
+        Command::Help => return Ok(HELP.to_string()),
+        Command::SetSpeed(_) => return Err("the speed is up to the settings".to_string()),
+        Command::Clock(_) => return Err("the clock is up to the page".to_string()),
+        Command::Spawn { kind, count } => {
+            let spawned = game.spawn_food(*kind, *count);
+            format!("spawned {spawned} {}", kind.name())
+        }
+        Command::SpawnHazards(spawn) => {
+            let spawned = match spawn {
+                HazardSpawn::Random(count) => (0..*count)
+                    .filter(|_| {
+                        let free = game.free_positions().len();
+                        free > 0 && {
+                            let index = random::get_u16() as usize % free;
+                            let pos = game.free_positions().get(index).unwrap().clone();
+                            game.spawn_hazard(pos)
+                        }
+                    })
+                    .count(),
+                HazardSpawn::Ring => {
+                    let (width, height) = (game.width, game.height);
+                    (0..height)
+                        .flat_map(|y| (0..width).map(move |x| Vector(x, y)))
+                        .filter(|pos| {
+                            pos.0 == 0 || pos.1 == 0 || pos.0 == width - 1 || pos.1 == height - 1
+                        })
+                        .filter(|pos| game.spawn_hazard(pos.clone()))
+                        .count()
+                }
+            };
+            format!("spawned {spawned} hazards")
+        }
+        Command::Teleport(head) => {
+            game.teleport(0, head.clone())?;
+            format!("teleported to {},{}", head.0, head.1)
+        }
+        Command::Seed(seed) => {
+            game.restart_with_seed(*seed);
+            format!("restarted with seed {},{}", seed[0], seed[1])
+        }
+        Command::Phase(phase) => {
+            match phase {
+                Phase::Countdown => game.start_countdown(),
+                Phase::Playing => {
+                    game.paused = false;
+                    game.skip_countdown();
+                }
+                Phase::Paused => game.paused = true,
+                Phase::GameOver => game.end_now("ended from the console"),
+            }
+            format!("{phase:?}").to_lowercase()
+        }
+    };
+
+    game.tampered = true;
+    Ok(message)
+}
+
+thread_local! {
+    static HISTORY: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+// `run` on the game the page is showing, plus the speed. scenarios go through here too
+pub fn execute(command: &Command) -> Result<String, String> {
+    if crate::net::is_active() {
+        // the other side would never know
+        return Err("not while playing online".to_string());
+    }
+
+    match command {
+        Command::SetSpeed(tick_ms) => {
+            crate::update_settings(|settings| settings.tick_ms = *tick_ms);
+            let tick_ms = crate::SETTINGS.with(|settings| settings.borrow().tick_ms);
+            crate::GAME.with(|game| game.borrow_mut().tampered = true);
+            Ok(format!("{tick_ms} ms per tick"))
+        }
+        Command::Clock(clock) => {
+            crate::GAME.with(|game| game.borrow_mut().tampered = true);
+            crate::SCHEDULER.with(|scheduler| match clock {
+                Clock::Pause => scheduler.pause(),
+                Clock::Resume => scheduler.resume(),
+                Clock::Step => scheduler.step(),
+            });
+            Ok(format!("clock {clock:?}").to_lowercase())
+        }
+        _ => crate::GAME.with(|game| run(command, &mut game.borrow_mut())),
+    }
+}
+
+fn submit(source: &str) -> Result<(), SlakeError> {
+    let result = Command::parse(source).and_then(|command| execute(&command));
+
+    let output = match result {
+        Ok(message) => message,
+        Err(error) => format!("error: {error}"),
+    };
+
+    let history = HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        history.push(format!("> {source}"));
+        history.push(output);
+
+        let overflow = history.len().saturating_sub(HISTORY_LENGTH);
+        history.drain(..overflow);
+        history.join("\n")
+    });
+
+    crate::get_element::<HtmlElement>("dev_console_output")?.set_inner_text(&history);
+    Ok(())
+}
+
+pub fn toggle() -> Result<(), SlakeError> {
+    let panel_element = crate::get_element::<HtmlElement>("dev_console")?;
+    let visible = panel_element.style().get_property_value("display")? != "none";
+
+    panel_element
+        .style()
+        .set_property("display", if visible { "none" } else { "block" })?;
+
+    let input = crate::get_element::<HtmlInputElement>("dev_console_input")?;
+    if visible {
+        // so the game's keys work again straight away
+        let _ = input.blur();
+    } else {
+        let _ = input.focus();
+    }
+    Ok(())
+}
+
+fn key(event: &KeyboardEvent) -> Result<(), SlakeError> {
+    let input = crate::get_element::<HtmlInputElement>("dev_console_input")?;
+
+    match &event.key()[..] {
+        "Enter" => {
+            submit(&input.value())?;
+            input.set_value("");
+        }
+        "Escape" | "`" => {
+            event.prevent_default();
+            toggle()?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("dev_console");
+    panel_element.set_class_name("settings dev_console");
+    panel_element.style().set_property("display", "none")?;
+
+    panel_element.set_inner_html(
+        r#"<div id="dev_console_output"></div>
+<input id="dev_console_input" type="text" spellcheck="false" autocomplete="off">"#,
+    );
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    let handle_keydown = Closure::wrap(Box::new(|event: KeyboardEvent| {
+        // the console has the keyboard to itself, so typing doesn't steer or wake anything up
+        event.stop_propagation();
+        crate::error::report_failure(key(&event));
+    }) as Box<dyn FnMut(KeyboardEvent)>);
+
+    crate::get_element::<HtmlInputElement>("dev_console_input")?
+        .set_onkeydown(Some(handle_keydown.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither is its handler
+    handle_keydown.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawner::Spawner;
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            Command::parse("spawn food 3"),
+            Ok(Command::Spawn {
+                kind: FoodKind::Normal,
+                count: 3
+            })
+        );
+        assert_eq!(Command::parse(" set  speed 50 "), Ok(Command::SetSpeed(50)));
+        assert_eq!(
+            Command::parse("teleport 5 5"),
+            Ok(Command::Teleport(Vector(5, 5)))
+        );
+        assert_eq!(Command::parse("seed 65537"), Ok(Command::Seed([1, 1])));
+        assert_eq!(
+            Command::parse("phase gameover"),
+            Ok(Command::Phase(Phase::GameOver))
+        );
+        assert_eq!(
+            Command::parse("spawn hazard ring"),
+            Ok(Command::SpawnHazards(HazardSpawn::Ring))
+        );
+        assert_eq!(
+            Command::parse("clock step"),
+            Ok(Command::Clock(Clock::Step))
+        );
+        assert!(Command::parse("clock rewind").is_err());
+        assert!(Command::parse("spawn cake").is_err());
+        assert!(Command::parse("teleport 5").is_err());
+        assert!(Command::parse("").is_err());
+
+        // all the food out of the way of the row it's teleported to
+        let mut game = SnakeGame::new(21, 15);
+        game.spawner = Spawner::fixed(vec![Vector(0, 0), Vector(0, 14), Vector(20, 14)]);
+        game.restart();
+        assert_eq!(
+            run(&Command::parse("spawn bonus 2").unwrap(), &mut game),
+            Ok("spawned 2 bonus".to_string())
+        );
+        assert_eq!(game.food().len(), 3);
+        assert!(game.tampered);
+
+        let length = game.snakes()[0].body.len();
+        run(&Command::Teleport(Vector(3, 3)), &mut game).unwrap();
+        assert_eq!(*game.snakes()[0].head(), Vector(3, 3));
+        assert_eq!(game.snakes()[0].body.len(), length);
+        assert!(run(&Command::Teleport(Vector(20, 3)), &mut game).is_err());
+
+        let free = game.free_positions().len();
+        run(&Command::SpawnHazards(HazardSpawn::Random(4)), &mut game).unwrap();
+        assert_eq!(game.hazards().len(), 4);
+        assert_eq!(game.free_positions().len(), free - 4);
+        run(&Command::SpawnHazards(HazardSpawn::Ring), &mut game).unwrap();
+        // nothing free left along the edge
+        assert!(
+            (0..21).all(|x| !game.free_positions().contains(&Vector(x, 0))
+                && !game.free_positions().contains(&Vector(x, 14)))
+        );
+
+        game.score = 5;
+        run(&Command::Phase(Phase::GameOver), &mut game).unwrap();
+        assert_eq!(game.game_over_reason, Some("ended from the console"));
+        // spoiled runs don't make the high score
+        assert_eq!(game.high_score(), 0);
+    }
+}
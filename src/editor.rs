@@ -0,0 +1,333 @@
+use crate::error::SlakeError;
+use crate::geometry::Direction;
+use crate::geometry::Vector;
+use crate::level::Level;
+use crate::render::Decoration;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{Element, HtmlDivElement, HtmlElement, HtmlTextAreaElement, MouseEvent};
+
+// A level editor on the board itself. While it's open the game sits paused on the level being
+// made, and clicking a tile cycles it from empty to wall to hazard to food spawn and back. The
+// snake stays put where it starts. The layout can be exported as a level string (see `level`)
+// to keep or share, and "play" starts a game on it.
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EditorTile {
+    Empty,
+    Wall,
+    Hazard,
+    FoodSpawn,
+}
+
+impl EditorTile {
+    fn next(&self) -> EditorTile {
+        match self {
+            EditorTile::Empty => EditorTile::Wall,
+            EditorTile::Wall => EditorTile::Hazard,
+            EditorTile::Hazard => EditorTile::FoodSpawn,
+            EditorTile::FoodSpawn => EditorTile::Empty,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Editor {
+    pub level: Level,
+}
+
+impl Editor {
+    // an empty board, with a two tile snake in the middle heading left
+    pub fn new(width: isize, height: isize) -> Editor {
+        let head = Vector(width / 2, height / 2);
+        let neck = Vector(head.0 + 1, head.1);
+
+        Editor {
+            level: Level {
+                name: "Custom".to_string(),
+                width,
+                height,
+                walls: vec![],
+                hazards: vec![],
+                snake: vec![head, neck],
+                direction: Direction::Left,
+                food: vec![],
+                portals: vec![],
+                dividers: vec![],
+                ladders: vec![],
+            },
+        }
+    }
+
+    pub fn tile(&self, pos: &Vector) -> EditorTile {
+        if self.level.walls.contains(pos) {
+            EditorTile::Wall
+        } else if self.level.hazards.contains(pos) {
+            EditorTile::Hazard
+        } else if self.level.food.contains(pos) {
+            EditorTile::FoodSpawn
+        } else {
+            EditorTile::Empty
+        }
+    }
+
+    // Returns whether anything changed, which it doesn't off the board, on the snake, or on a
+    // portal, ladder or gap between floors from a level that had them. New food spawns go last
+    // in the spawn order.
+    pub fn click(&mut self, pos: &Vector) -> bool {
+        if !crate::geometry::is_within_board(self.level.width, self.level.height, pos)
+            || self.level.snake.contains(pos)
+            || crate::level::other_end(&self.level.portals, pos).is_some()
+            || crate::level::other_end(&self.level.ladders, pos).is_some()
+            || self.level.dividers.contains(&pos.1)
+        {
+            return false;
+        }
+
+        let next = self.tile(pos).next();
+
+        let level = &mut self.level;
+        level.walls.retain(|tile| tile != pos);
+        level.hazards.retain(|tile| tile != pos);
+        level.food.retain(|tile| tile != pos);
+
+        match next {
+            EditorTile::Empty => (),
+            EditorTile::Wall => self.level.walls.push(pos.clone()),
+            EditorTile::Hazard => self.level.hazards.push(pos.clone()),
+            EditorTile::FoodSpawn => self.level.food.push(pos.clone()),
+        }
+
+        true
+    }
+}
+
+// Which tile is at `(x, y)`, as fractions of the way across the board as drawn, border and all.
+// Both render styles draw the board as one evenly divided rectangle, so this works for either.
+pub fn tile_at(x: f64, y: f64, width: isize, height: isize, border: isize) -> Option<Vector> {
+    let pos = Vector(
+        (x * (width + border * 2) as f64).floor() as isize - border,
+        (y * (height + border * 2) as f64).floor() as isize - border,
+    );
+
+    crate::geometry::is_within_board(width, height, &pos).then_some(pos)
+}
+
+thread_local! {
+    static EDITOR: RefCell<Option<Editor>> = const { RefCell::new(None) };
+}
+
+pub fn is_editing() -> bool {
+    EDITOR.with(|editor| editor.borrow().is_some())
+}
+
+// food spawns don't show on the board until it's their turn, so the editor marks them
+pub fn decorations() -> Vec<(Vector, Decoration)> {
+    EDITOR.with(|editor| match editor.borrow().as_ref() {
+        Some(editor) => editor
+            .level
+            .food
+            .iter()
+            .map(|pos| (pos.clone(), Decoration::FoodSpawn))
+            .collect(),
+        None => vec![],
+    })
+}
+
+// puts what's being edited on the board, stopped
+fn show(level: &Level) {
+    crate::GAME.with(|game| {
+        let mut game = game.borrow_mut();
+        game.load_level(level);
+        game.paused = true;
+    });
+    crate::draw();
+}
+
+fn set_panel_visible(visible: bool) -> Result<(), SlakeError> {
+    crate::get_element::<HtmlDivElement>("editor")?
+        .style()
+        .set_property("display", if visible { "block" } else { "none" })?;
+    Ok(())
+}
+
+// Opens the editor on whatever level is loaded, or an empty board the size of this one. Closing
+// it plays what was made.
+pub fn toggle() {
+    if is_editing() {
+        play();
+        return;
+    }
+
+    // the other player would be stuck waiting
+    if crate::net::is_active() {
+        return;
+    }
+
+    let editor = crate::GAME.with(|game| {
+        let game = game.borrow();
+        match game.level() {
+            Some(level) => Editor {
+                level: level.clone(),
+            },
+            None => Editor::new(game.width, game.height),
+        }
+    });
+
+    show(&editor.level);
+    EDITOR.with(|old_editor| old_editor.replace(Some(editor)));
+    crate::error::report_failure(set_panel_visible(true));
+}
+
+fn play() {
+    let Some(editor) = EDITOR.with(|editor| editor.take()) else {
+        return;
+    };
+    crate::error::report_failure(set_panel_visible(false));
+
+    crate::play_level(&editor.level);
+    crate::GAME.with(|game| game.borrow_mut().paused = false);
+}
+
+fn export() -> Result<(), SlakeError> {
+    let source = EDITOR.with(|editor| {
+        editor
+            .borrow()
+            .as_ref()
+            .map(|editor| editor.level.to_source())
+    });
+
+    if let Some(source) = source {
+        let text_area = crate::get_element::<HtmlTextAreaElement>("editor_export")?;
+        text_area.set_value(&source);
+        text_area.select();
+    }
+    Ok(())
+}
+
+fn click(event: MouseEvent) {
+    // whichever style is showing
+    let root_container = match crate::get_element::<Element>("root") {
+        Ok(root_container) => root_container,
+        Err(error) => return crate::error::report(error, None),
+    };
+    let Ok(Some(board_element)) = root_container.query_selector(".field_holder, .canvas_board")
+    else {
+        return;
+    };
+    let rect = board_element.get_bounding_client_rect();
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return;
+    }
+
+    let border = crate::SETTINGS.with(|settings| settings.borrow().show_border) as isize;
+
+    let changed = EDITOR.with(|editor| {
+        let mut editor = editor.borrow_mut();
+        let editor = editor.as_mut()?;
+
+        let pos = tile_at(
+            (event.client_x() as f64 - rect.left()) / rect.width(),
+            (event.client_y() as f64 - rect.top()) / rect.height(),
+            editor.level.width,
+            editor.level.height,
+            border,
+        )?;
+
+        editor.click(&pos).then(|| editor.level.clone())
+    });
+
+    if let Some(level) = changed {
+        show(&level);
+    }
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("editor");
+    panel_element.set_class_name("settings");
+    panel_element.style().set_property("display", "none")?;
+
+    panel_element.set_inner_html(
+        r#"<div>Level editor: click tiles to cycle empty, wall, hazard, and food spawn</div>
+<button id="editor_play">Play</button>
+<button id="editor_export_button">Export</button>
+<textarea id="editor_export" rows="4" readonly spellcheck="false"></textarea>"#,
+    );
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    let handle_play = Closure::wrap(Box::new(play) as Box<dyn FnMut()>);
+    let handle_export =
+        Closure::wrap(Box::new(|| crate::error::report_failure(export())) as Box<dyn FnMut()>);
+    let handle_click = Closure::wrap(Box::new(click) as Box<dyn FnMut(MouseEvent)>);
+
+    crate::get_element::<HtmlElement>("editor_play")?
+        .set_onclick(Some(handle_play.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("editor_export_button")?
+        .set_onclick(Some(handle_export.as_ref().unchecked_ref()));
+    // the board itself gets rebuilt now and then, but its container doesn't
+    crate::get_element::<HtmlElement>("root")?
+        .set_onclick(Some(handle_click.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither are its handlers
+    handle_play.forget();
+    handle_export.forget();
+    handle_click.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editing() {
+        let mut editor = Editor::new(9, 5);
+        assert_eq!(editor.level.snake, vec![Vector(4, 2), Vector(5, 2)]);
+
+        let pos = Vector(1, 1);
+        for tile in [
+            EditorTile::Wall,
+            EditorTile::Hazard,
+            EditorTile::FoodSpawn,
+            EditorTile::Empty,
+        ] {
+            assert!(editor.click(&pos));
+            assert_eq!(editor.tile(&pos), tile);
+        }
+
+        assert!(!editor.click(&Vector(4, 2)));
+        assert!(!editor.click(&Vector(9, 0)));
+
+        editor.click(&Vector(0, 0));
+        editor.click(&Vector(8, 4));
+        editor.click(&Vector(8, 4));
+        editor.click(&Vector(8, 4));
+        assert_eq!(
+            editor.level.to_source(),
+            "; name: Custom\n\
+             #........\n\
+             .........\n\
+             ....@o...\n\
+             .........\n\
+             ........1\n"
+        );
+    }
+
+    #[test]
+    fn hit_testing() {
+        // a 5x3 board with a border is drawn 7 tiles by 5
+        assert_eq!(tile_at(0.5, 0.5, 5, 3, 1), Some(Vector(2, 1)));
+        assert_eq!(tile_at(1.5 / 7.0, 1.5 / 5.0, 5, 3, 1), Some(Vector(0, 0)));
+        assert_eq!(tile_at(0.5 / 7.0, 0.5, 5, 3, 1), None);
+        assert_eq!(tile_at(0.99, 0.99, 5, 3, 0), Some(Vector(4, 2)));
+    }
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+// Effects that last a set time rather than a set number of moves, like bonus fruit going off or
+// a stun wearing off, are given in milliseconds and timed against `SnakeGame::elapsed_ms`, the
+// play time so far. Each tick adds however long ticks are at the time, so speeding up or slowing
+// down partway through an effect only changes how many ticks the rest of it takes.
+//
+// Puddles drying up and starving are still counted in ticks, since they're about how far the
+// snake has moved rather than how long it took.
+
+// what the settings start out with, for games that haven't been told otherwise
+pub const DEFAULT_TICK_MS: usize = 100;
+
+// how many ticks `ms` takes at `tick_ms` each, rounded up so it's never cut short
+pub fn ticks(ms: usize, tick_ms: usize) -> usize {
+    ms.div_ceil(tick_ms.max(1))
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Timer {
+    // on the play clock
+    pub ends_ms: usize,
+}
+
+impl Timer {
+    pub fn new(now_ms: usize, duration_ms: usize) -> Timer {
+        Timer {
+            ends_ms: now_ms + duration_ms,
+        }
+    }
+
+    pub fn ms_left(&self, now_ms: usize) -> usize {
+        self.ends_ms.saturating_sub(now_ms)
+    }
+
+    pub fn is_done(&self, now_ms: usize) -> bool {
+        self.ms_left(now_ms) == 0
+    }
+
+    // at the speed it is now, which might change
+    pub fn ticks_left(&self, now_ms: usize, tick_ms: usize) -> usize {
+        ticks(self.ms_left(now_ms), tick_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timers() {
+        assert_eq!(ticks(4000, 100), 40);
+        assert_eq!(ticks(4000, 60), 67);
+        assert_eq!(ticks(150, 0), 150);
+
+        let timer = Timer::new(1000, 4000);
+        assert_eq!(timer.ticks_left(1000, 100), 40);
+
+        // halfway through, at double the speed, half the time left takes just as many ticks
+        assert_eq!(timer.ticks_left(3000, 50), 40);
+        assert!(!timer.is_done(4999));
+        assert!(timer.is_done(5000));
+        assert_eq!(timer.ms_left(9000), 0);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::error::SlakeError;
+use crate::settings::Settings;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{MessageEvent, Window};
+
+// For portal sites that host the built game in an iframe, and would rather talk to it with
+// postMessage than reach into the wasm module. Every message either way is an object with a
+// `slake` field saying what it is.
+//
+// The embedding page can send:
+//
+//   { slake: "start" }       a new game, right away
+//   { slake: "pause" }
+//   { slake: "resume" }      after a short countdown
+//   { slake: "configure", settings: "tick_ms=80\nspecial_food=false" }
+//                            changes settings, in the same `key=value` lines they're saved in.
+//                            anything left out stays as it was
+//   { slake: "state" }       asks for a `state` message back
+//
+// and the game sends:
+//
+//   { slake: "ready" }       once it's loaded, to whoever embedded it
+//   { slake: "state", state: "..." }       the same JSON as `slake_get_state_json`
+//   { slake: "score", score: 12 }          whenever the score changes
+//   { slake: "game_over", score: 12, reason: "avoid walls" }
+//
+// Only "ready" goes out before the embedding page has said anything, since until then there's
+// no telling which origin it's on. After that everything goes to the origin of the last message.
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EmbedCommand {
+    Start,
+    Pause,
+    Resume,
+    Configure(String),
+    State,
+}
+
+impl EmbedCommand {
+    // `settings` is only for "configure"
+    pub fn parse(kind: &str, settings: Option<String>) -> Result<EmbedCommand, String> {
+        match kind {
+            "start" => Ok(EmbedCommand::Start),
+            "pause" => Ok(EmbedCommand::Pause),
+            "resume" => Ok(EmbedCommand::Resume),
+            "configure" => settings
+                .map(EmbedCommand::Configure)
+                .ok_or_else(|| "configure needs some settings".to_string()),
+            "state" => Ok(EmbedCommand::State),
+            _ => Err(format!("unknown message '{kind}'")),
+        }
+    }
+}
+
+// `settings` with whichever lines `changes` has replacing their own
+pub fn configure(settings: &Settings, changes: &str) -> Settings {
+    // later lines win, so the changes just go on the end
+    Settings::parse(&format!("{}\n{changes}", settings.serialize()))
+}
+
+thread_local! {
+    // where the embedding page is, once it's said something
+    static ORIGIN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// the page this one's embedded in, if it is
+fn embedder() -> Option<Window> {
+    let window = crate::error::window().ok()?;
+    let parent = window.parent().ok().flatten()?;
+
+    (parent != window).then_some(parent)
+}
+
+pub fn is_embedded() -> bool {
+    embedder().is_some()
+}
+
+fn post(message: &[(&str, JsValue)], origin: &str) {
+    let Some(embedder) = embedder() else {
+        return;
+    };
+
+    let object = js_sys::Object::new();
+    for (key, value) in message {
+        let _ = js_sys::Reflect::set(&object, &(*key).into(), value);
+    }
+
+    // a page that's gone away isn't worth stopping the game over
+    let _ = embedder.post_message(&object, origin);
+}
+
+fn send(message: &[(&str, JsValue)]) {
+    if let Some(origin) = ORIGIN.with(|origin| origin.borrow().clone()) {
+        post(message, &origin);
+    }
+}
+
+pub fn notify_score(score: usize) {
+    send(&[("slake", "score".into()), ("score", score.into())]);
+}
+
+pub fn notify_game_over(score: usize, reason: &str) {
+    send(&[
+        ("slake", "game_over".into()),
+        ("score", score.into()),
+        ("reason", reason.into()),
+    ]);
+}
+
+fn run(command: EmbedCommand) {
+    match command {
+        EmbedCommand::Start => {
+            crate::restart_game();
+            crate::GAME.with(|game| game.borrow_mut().paused = false);
+        }
+        EmbedCommand::Pause => crate::GAME.with(|game| game.borrow_mut().paused = true),
+        EmbedCommand::Resume => crate::GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            if game.paused {
+                game.paused = false;
+                game.start_countdown();
+            }
+        }),
+        EmbedCommand::Configure(changes) => {
+            let settings = crate::SETTINGS.with(|settings| configure(&settings.borrow(), &changes));
+            crate::update_settings(|old_settings| *old_settings = settings);
+        }
+        EmbedCommand::State => send(&[
+            ("slake", "state".into()),
+            ("state", crate::api::slake_get_state_json().into()),
+        ]),
+    }
+}
+
+fn handle_message(event: MessageEvent) {
+    // only the embedding page gets a say, not other frames or this page's own messages
+    let from_embedder = match (embedder(), event.source()) {
+        (Some(embedder), Some(source)) => JsValue::from(embedder) == JsValue::from(source),
+        _ => false,
+    };
+    if !from_embedder {
+        return;
+    }
+
+    let data = event.data();
+    let field = |key: &str| {
+        js_sys::Reflect::get(&data, &key.into())
+            .ok()
+            .and_then(|value| value.as_string())
+    };
+    // not one of ours
+    let Some(kind) = field("slake") else {
+        return;
+    };
+
+    ORIGIN.with(|origin| origin.replace(Some(event.origin())));
+
+    match EmbedCommand::parse(&kind, field("settings")) {
+        Ok(EmbedCommand::State) => run(EmbedCommand::State),
+        // online games play by the host's rules, so the embedding page waits like anyone else
+        Ok(_) if crate::net::is_active() => (),
+        Ok(command) => run(command),
+        Err(error) => crate::log(&format!("Embedding page: {error}")),
+    }
+}
+
+// Starts listening, and says hello, if the page is in an iframe.
+pub fn listen() -> Result<(), SlakeError> {
+    if embedder().is_none() {
+        return Ok(());
+    }
+
+    let handle_message = Closure::wrap(Box::new(handle_message) as Box<dyn FnMut(MessageEvent)>);
+    crate::error::window()?
+        .add_event_listener_with_callback("message", handle_message.as_ref().unchecked_ref())?;
+    // the page is never unloaded, as far as we're concerned
+    handle_message.forget();
+
+    // nothing in it worth keeping from anyone
+    post(&[("slake", "ready".into())], "*");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        assert_eq!(EmbedCommand::parse("pause", None), Ok(EmbedCommand::Pause));
+        assert_eq!(
+            EmbedCommand::parse("configure", Some("tick_ms=80".to_string())),
+            Ok(EmbedCommand::Configure("tick_ms=80".to_string()))
+        );
+        assert!(EmbedCommand::parse("configure", None).is_err());
+        assert!(EmbedCommand::parse("explode", None).is_err());
+
+        let settings = Settings {
+            sound: false,
+            ..Settings::default()
+        };
+        let configured = configure(&settings, "tick_ms=80\nspecial_food=false");
+        assert_eq!(configured.tick_ms, 80);
+        assert!(!configured.special_food);
+        // the rest stays the same
+        assert!(!configured.sound);
+        assert_eq!(configured.key_bindings, settings.key_bindings);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::geometry::Geometry;
+use crate::geometry::Vector;
+
+use serde::{Deserialize, Serialize};
+
+// Hazards that don't sit still. Every `MOVE_TICKS` ticks each one takes a step to a free tile
+// next to it, so never onto food, a snake, or anything else, and never into `START_CLEARANCE`
+// of where the snakes start, so a run can't begin with one in front of the snake. They don't
+// go over the edge of the board, even where the snake wraps around. Running into one ends the
+// run like a wall does.
+
+pub const MOVE_TICKS: usize = 4;
+// in tiles either way, not counting diagonals as any further
+pub const START_CLEARANCE: isize = 3;
+// more than that and a small board has nowhere left to go
+pub const MAX_ENEMIES: usize = 8;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Behavior {
+    // a step in a random direction
+    Wander,
+    // a step towards whichever head is nearest, or none if it can't get any closer
+    Chase,
+}
+
+impl Behavior {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Behavior::Wander => "wander",
+            Behavior::Chase => "chase",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Behavior> {
+        match name {
+            "wander" => Some(Behavior::Wander),
+            "chase" => Some(Behavior::Chase),
+            _ => None,
+        }
+    }
+
+    // every other one chases, starting with the second, so even one enemy is no trouble to
+    // start with
+    pub fn for_index(index: usize) -> Behavior {
+        match index % 2 {
+            0 => Behavior::Wander,
+            _ => Behavior::Chase,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Enemy {
+    pub position: Vector,
+    pub behavior: Behavior,
+}
+
+impl Enemy {
+    // Where it goes next, out of `options`, the tiles next to it that are free to step on.
+    // `roll` is a random number for picking between them, drawn whether or not it's used so
+    // the random numbers stay in step either way.
+    pub fn step(
+        &self,
+        geometry: &dyn Geometry,
+        options: &[Vector],
+        heads: &[Vector],
+        roll: u16,
+    ) -> Option<Vector> {
+        let distance = |pos: &Vector| {
+            heads
+                .iter()
+                .map(|head| geometry.distance(pos, head))
+                .min()
+                .unwrap_or(0)
+        };
+
+        let options = match self.behavior {
+            Behavior::Wander => options.to_vec(),
+            Behavior::Chase => {
+                let closest = options.iter().map(distance).min();
+                options
+                    .iter()
+                    .filter(|pos| {
+                        Some(distance(pos)) == closest && distance(pos) < distance(&self.position)
+                    })
+                    .cloned()
+                    .collect()
+            }
+        };
+
+        (!options.is_empty()).then(|| options[roll as usize % options.len()].clone())
+    }
+}
+
+// whether `pos` is too close to any of `starts` for an enemy
+pub fn near_start(starts: &[Vector], pos: &Vector) -> bool {
+    starts.iter().any(|start| {
+        (start.0 - pos.0).abs() <= START_CLEARANCE && (start.1 - pos.1).abs() <= START_CLEARANCE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Square;
+
+    #[test]
+    fn steps() {
+        let options = [Vector(4, 5), Vector(6, 5), Vector(5, 4), Vector(5, 6)];
+        let heads = [Vector(9, 5), Vector(0, 0)];
+
+        let wanderer = Enemy {
+            position: Vector(5, 5),
+            behavior: Behavior::Wander,
+        };
+        assert_eq!(
+            wanderer.step(&Square, &options, &heads, 2),
+            Some(Vector(5, 4))
+        );
+        assert_eq!(
+            wanderer.step(&Square, &options, &heads, 6),
+            Some(Vector(5, 4))
+        );
+        assert_eq!(wanderer.step(&Square, &[], &heads, 2), None);
+
+        // heads for the nearest head, whatever the roll
+        let chaser = Enemy {
+            behavior: Behavior::Chase,
+            ..wanderer
+        };
+        for roll in 0..4 {
+            assert_eq!(
+                chaser.step(&Square, &options, &heads, roll),
+                Some(Vector(6, 5))
+            );
+        }
+        // and waits rather than backing off
+        assert_eq!(chaser.step(&Square, &options[..1], &heads, 0), None);
+
+        assert!(near_start(&[Vector(10, 7)], &Vector(13, 4)));
+        assert!(!near_start(&[Vector(10, 7)], &Vector(14, 7)));
+
+        for behavior in [Behavior::Wander, Behavior::Chase] {
+            assert_eq!(Behavior::from_name(behavior.name()), Some(behavior));
+        }
+    }
+}
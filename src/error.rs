@@ -0,0 +1,202 @@
+use std::cell::RefCell;
+use std::fmt;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{
+    console, CanvasRenderingContext2d, Document, Element, HtmlCanvasElement, HtmlElement, Window,
+};
+
+// What can go wrong outside of the game itself: building the page, drawing it, and saving to
+// local storage. Rather than the whole module stopping dead on whatever the browser threw, these
+// come up in a banner across the top of the page, see `report`. Problems with the page can be
+// passing, like an extension getting in the way while it loads, so their banner has a button to
+// try again. Anything that's come up once isn't shown again until something else has, so a
+// frame that won't draw doesn't put up sixty banners a second.
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum SlakeError {
+    // a part of the page that's missing or couldn't be put together
+    Dom(String),
+    // a frame that couldn't be drawn
+    Render(String),
+    // storage that's full, or turned off partway through
+    Storage(String),
+}
+
+impl SlakeError {
+    // whether trying the same thing again might work
+    pub fn is_transient(&self) -> bool {
+        matches!(self, SlakeError::Dom(_) | SlakeError::Render(_))
+    }
+
+    pub fn render(error: JsValue) -> SlakeError {
+        SlakeError::Render(describe(&error))
+    }
+
+    pub fn storage(error: JsValue) -> SlakeError {
+        SlakeError::Storage(describe(&error))
+    }
+}
+
+impl fmt::Display for SlakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlakeError::Dom(error) => write!(f, "couldn't set up the page: {error}"),
+            SlakeError::Render(error) => write!(f, "couldn't draw the board: {error}"),
+            SlakeError::Storage(error) => write!(f, "couldn't save: {error}"),
+        }
+    }
+}
+
+// most of what the browser throws is a DOM problem
+impl From<JsValue> for SlakeError {
+    fn from(error: JsValue) -> SlakeError {
+        SlakeError::Dom(describe(&error))
+    }
+}
+
+// what `dyn_into` hands back for an element that isn't the kind that was asked for
+impl From<Element> for SlakeError {
+    fn from(element: Element) -> SlakeError {
+        let tag = element.tag_name().to_lowercase();
+        SlakeError::Dom(format!("a {tag} isn't the kind of element it should be"))
+    }
+}
+
+// for setup that still goes by what the browser throws, just with what went wrong, so it comes
+// back out as the same message
+impl From<SlakeError> for JsValue {
+    fn from(error: SlakeError) -> JsValue {
+        match error {
+            SlakeError::Dom(error) | SlakeError::Render(error) | SlakeError::Storage(error) => {
+                JsValue::from_str(&error)
+            }
+        }
+    }
+}
+
+// an exception's own message where it has one, rather than all of its debug output
+fn describe(error: &JsValue) -> String {
+    if let Some(error) = error.dyn_ref::<js_sys::Error>() {
+        return String::from(error.message());
+    }
+    error.as_string().unwrap_or_else(|| format!("{error:?}"))
+}
+
+pub fn window() -> Result<Window, SlakeError> {
+    web_sys::window().ok_or_else(|| SlakeError::Dom("there's no window".to_string()))
+}
+
+pub fn document() -> Result<Document, SlakeError> {
+    window()?
+        .document()
+        .ok_or_else(|| SlakeError::Dom("there's no document".to_string()))
+}
+
+pub fn body() -> Result<HtmlElement, SlakeError> {
+    document()?
+        .body()
+        .ok_or_else(|| SlakeError::Dom("there's no body".to_string()))
+}
+
+pub fn context_2d(canvas: &HtmlCanvasElement) -> Result<CanvasRenderingContext2d, SlakeError> {
+    canvas
+        .get_context("2d")?
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or_else(|| SlakeError::Render("there's no 2d context to draw with".to_string()))
+}
+
+thread_local! {
+    // the last thing put up, see `report`
+    static SHOWN: RefCell<Option<SlakeError>> = const { RefCell::new(None) };
+}
+
+// Puts `error` up in the banner. For one that's transient, `retry` is what the banner's button
+// does, after taking the banner down.
+pub fn report(error: SlakeError, retry: Option<fn()>) {
+    if SHOWN.with(|shown| shown.borrow().as_ref() == Some(&error)) {
+        return;
+    }
+
+    console::error_1(&error.to_string().into());
+    let retry = retry.filter(|_| error.is_transient());
+    // with the page itself broken, the console is all there is
+    let _ = show_banner(&error, retry);
+
+    SHOWN.with(|shown| *shown.borrow_mut() = Some(error));
+}
+
+fn show_banner(error: &SlakeError, retry: Option<fn()>) -> Result<(), SlakeError> {
+    let document = document()?;
+    let body = body()?;
+
+    let banner = match crate::get_element::<HtmlElement>("error_banner") {
+        Ok(banner) => banner,
+        Err(_) => {
+            let banner = document.create_element("div")?.dyn_into::<HtmlElement>()?;
+            banner.set_id("error_banner");
+            banner.set_class_name("error_banner");
+            body.prepend_with_node_1(&banner)?;
+            banner
+        }
+    };
+
+    banner.set_inner_text(&format!("Something went wrong: {error}. "));
+
+    // dismissing leaves it marked as shown, so it stays away
+    let buttons = match retry {
+        Some(retry) => vec![("Try again", Some(retry)), ("Dismiss", None)],
+        None => vec![("Dismiss", None)],
+    };
+    for (label, retry) in buttons {
+        let button = document.create_element("span")?.dyn_into::<HtmlElement>()?;
+        button.set_class_name("button");
+        button.set_inner_text(label);
+
+        let handle_click = Closure::once_into_js(move || {
+            dismiss();
+            if let Some(retry) = retry {
+                // if it goes wrong again, that's worth hearing about
+                SHOWN.with(|shown| *shown.borrow_mut() = None);
+                retry();
+            }
+        });
+        button.set_onclick(Some(handle_click.unchecked_ref()));
+        banner.append_child(&button)?;
+    }
+
+    Ok(())
+}
+
+fn dismiss() {
+    if let Ok(banner) = crate::get_element::<HtmlElement>("error_banner") {
+        banner.remove();
+    }
+}
+
+// for anything with nowhere to pass a problem on to, like an event handler or a key's action
+pub fn report_failure(result: Result<(), SlakeError>) {
+    if let Err(error) = result {
+        report(error, None);
+    }
+}
+
+// for when whatever failed was partway through putting the page together, so the only way to
+// try again is from the start
+pub fn reload() {
+    if let Ok(window) = window() {
+        let _ = window.location().reload();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        let error = SlakeError::Storage("the quota's been used up".to_string());
+        assert_eq!(error.to_string(), "couldn't save: the quota's been used up");
+        assert!(!error.is_transient());
+        assert!(SlakeError::Dom("there's no body".to_string()).is_transient());
+    }
+}
@@ -0,0 +1,247 @@
+use crate::food::FoodKind;
+use crate::geometry::Vector;
+use crate::snake;
+
+// Things that happened during a tick, for the frontend to react to. The game only queues these
+// up; it's up to whoever drives it to drain them with `SnakeGame::take_events`.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum GameEvent {
+    Restarted,
+    // `multiplier` is the combo it was eaten on, see `SnakeGame::combos`, and `points` what it
+    // was worth in the end, with that and how fresh it was, see `SnakeGame::fresh_food`
+    FoodEaten {
+        position: Vector,
+        kind: FoodKind,
+        multiplier: usize,
+        points: isize,
+    },
+    HazardSpawned {
+        position: Vector,
+    },
+    SnakeSplit,
+    // ran into its own body and bit it off, see `RuleSet::tail_cut`
+    SnakeCut {
+        player: usize,
+        segments: usize,
+    },
+    SnakeStunned {
+        player: usize,
+    },
+    // ran into a wall during `SnakeGame::grace_ticks` and turned around
+    SnakeBounced {
+        player: usize,
+    },
+    // turned away on the tick it would've gone straight into a hazard
+    NearMiss {
+        player: usize,
+    },
+    // another ring of walls is going up, in `GameMode::ClosingIn`
+    WallsClosingIn,
+    // in `GameMode::Waves`, the quota's been eaten and everything in the way has gone
+    WaveCleared {
+        wave: usize,
+    },
+    // and after the break, the next one's puddles and enemies have gone down
+    WaveStarted {
+        wave: usize,
+    },
+    ScoreChanged {
+        score: usize,
+    },
+    GameOver {
+        reason: &'static str,
+    },
+}
+
+impl GameEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::Restarted => "restarted",
+            GameEvent::FoodEaten { .. } => "food_eaten",
+            GameEvent::HazardSpawned { .. } => "hazard_spawned",
+            GameEvent::SnakeSplit => "snake_split",
+            GameEvent::SnakeCut { .. } => "snake_cut",
+            GameEvent::SnakeStunned { .. } => "snake_stunned",
+            GameEvent::SnakeBounced { .. } => "snake_bounced",
+            GameEvent::NearMiss { .. } => "near_miss",
+            GameEvent::WallsClosingIn => "walls_closing_in",
+            GameEvent::WaveCleared { .. } => "wave_cleared",
+            GameEvent::WaveStarted { .. } => "wave_started",
+            GameEvent::ScoreChanged { .. } => "score_changed",
+            GameEvent::GameOver { .. } => "game_over",
+        }
+    }
+
+    // As a JSON object with the name as its `type`, and the rest of it alongside, like
+    // {"type":"food_eaten","position":[3,4],"kind":"bonus","multiplier":2,"points":6}
+    pub fn to_json(&self) -> String {
+        let fields = match self {
+            GameEvent::Restarted | GameEvent::SnakeSplit | GameEvent::WallsClosingIn => {
+                String::new()
+            }
+            GameEvent::FoodEaten {
+                position,
+                kind,
+                multiplier,
+                points,
+            } => format!(
+                r#","position":[{},{}],"kind":"{}","multiplier":{multiplier},"points":{points}"#,
+                position.0,
+                position.1,
+                kind.name()
+            ),
+            GameEvent::HazardSpawned { position } => {
+                format!(r#","position":[{},{}]"#, position.0, position.1)
+            }
+            GameEvent::SnakeCut { player, segments } => {
+                format!(r#","player":{player},"segments":{segments}"#)
+            }
+            GameEvent::SnakeStunned { player }
+            | GameEvent::SnakeBounced { player }
+            | GameEvent::NearMiss { player } => {
+                format!(r#","player":{player}"#)
+            }
+            GameEvent::WaveCleared { wave } | GameEvent::WaveStarted { wave } => {
+                format!(r#","wave":{wave}"#)
+            }
+            GameEvent::ScoreChanged { score } => format!(r#","score":{score}"#),
+            // the reasons are all plain text of our own, with nothing to escape
+            GameEvent::GameOver { reason } => format!(r#","reason":"{reason}""#),
+        };
+
+        format!(r#"{{"type":"{}"{fields}}}"#, self.name())
+    }
+}
+
+// Everything a tick did in one place, as `SnakeGame::tick` returns it, for whoever would rather
+// look at that than go through the events one at a time. It's worked out from the same events,
+// so the two never disagree, and they're still queued up for `take_events` as well.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct TickOutcome {
+    // false when it was paused, counting down, or already over
+    pub ticked: bool,
+    // where each snake's head is afterwards, in the order of `SnakeGame::snakes`
+    pub heads: Vec<Vector>,
+    // what was eaten, in the order it was. a sped up snake or a second player can eat more than
+    // once in a tick
+    pub eaten: Vec<FoodKind>,
+    // the run ending, and what did it if it was a crash or starving
+    pub game_over: Option<&'static str>,
+    pub died: Option<DeathCause>,
+    // what turned up this tick
+    pub spawned_food: Vec<Vector>,
+    pub spawned_hazards: Vec<Vector>,
+}
+
+impl TickOutcome {
+    // from the events a tick queued up, and the food there was before it
+    pub fn of(game: &snake::SnakeGame, events: &[GameEvent], food_before: &[Vector]) -> Self {
+        let mut outcome = TickOutcome {
+            heads: game
+                .snakes()
+                .iter()
+                .map(|snake| snake.head().clone())
+                .collect(),
+            spawned_food: game
+                .food()
+                .iter()
+                .map(|food| food.position.clone())
+                .filter(|position| !food_before.contains(position))
+                .collect(),
+            ..TickOutcome::default()
+        };
+
+        for event in events {
+            match event {
+                GameEvent::FoodEaten { kind, .. } => outcome.eaten.push(*kind),
+                GameEvent::HazardSpawned { position } => {
+                    outcome.spawned_hazards.push(position.clone())
+                }
+                GameEvent::GameOver { reason } => {
+                    outcome.game_over = Some(reason);
+                    outcome.died = DeathCause::from_reason(reason);
+                }
+                _ => (),
+            }
+        }
+
+        outcome
+    }
+}
+
+// how a run ended, for the ones that ended with a crash or starving
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum DeathCause {
+    Wall,
+    Tail,
+    Hazard,
+    Starved,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 4] = [
+        DeathCause::Wall,
+        DeathCause::Tail,
+        DeathCause::Hazard,
+        DeathCause::Starved,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::Wall => "wall",
+            DeathCause::Tail => "tail",
+            DeathCause::Hazard => "hazard",
+            DeathCause::Starved => "starved",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeathCause::Wall => "Hit a wall",
+            DeathCause::Tail => "Hit a tail",
+            DeathCause::Hazard => "Slipped in a puddle",
+            DeathCause::Starved => "Starved",
+        }
+    }
+
+    // from the reason `SnakeGame` gives when the game ends
+    pub fn from_reason(reason: &str) -> Option<DeathCause> {
+        match reason {
+            "avoid walls" => Some(DeathCause::Wall),
+            "avoid crashing into your own tail" => Some(DeathCause::Tail),
+            "don't slip on the leftovers" => Some(DeathCause::Hazard),
+            "don't starve" | snake::STARVED_REASON => Some(DeathCause::Starved),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let eaten = GameEvent::FoodEaten {
+            position: Vector(3, 4),
+            kind: FoodKind::Bonus,
+            multiplier: 2,
+            points: 6,
+        };
+        assert_eq!(
+            eaten.to_json(),
+            r#"{"type":"food_eaten","position":[3,4],"kind":"bonus","multiplier":2,"points":6}"#
+        );
+        assert_eq!(GameEvent::Restarted.to_json(), r#"{"type":"restarted"}"#);
+        assert_eq!(
+            GameEvent::GameOver {
+                reason: crate::snake::WON_REASON
+            }
+            .to_json(),
+            format!(
+                r#"{{"type":"game_over","reason":"{}"}}"#,
+                crate::snake::WON_REASON
+            )
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::error::SlakeError;
+use crate::geometry::Vector;
+use crate::snake::SnakeGame;
+use crate::theme::Theme;
+use crate::toast;
+use crate::view::TileKind;
+
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlAnchorElement, HtmlCanvasElement};
+
+// Sharing how a run went, as a picture of the board to save or as text to paste anywhere. Both
+// come straight from a `BoardView`, so they look the same whichever style the page is drawn in:
+// the picture has the theme's colors with its glyphs on top, and the text is the classic emoji,
+// since that's what shows up everywhere text does.
+
+// in image pixels. smaller than on the page, so a big board still makes a small file
+const TILE_SIZE: f64 = 24.0;
+// room under the board for what the text starts with
+const CAPTION_HEIGHT: f64 = 32.0;
+// text has no background to leave showing, so empty tiles need something of their own
+const EMPTY_TILE: &str = "⬛";
+
+// the seed packed into one number, the way the dev console's `seed` takes it, so anyone curious
+// can play the same fruit
+fn packed_seed(game: &SnakeGame) -> u32 {
+    (game.seed[0] as u32) << 16 | game.seed[1] as u32
+}
+
+pub fn caption(game: &SnakeGame) -> String {
+    format!("slake: {} points, seed {}", game.score, packed_seed(game))
+}
+
+// the caption, then the board a row to a line
+pub fn share_text(game: &SnakeGame) -> String {
+    let view = game.board_view();
+    let rows = (0..view.height).map(|y| {
+        (0..view.width)
+            .map(|x| match view.get(&Vector(x, y)) {
+                TileKind::Empty => EMPTY_TILE,
+                kind => Theme::Classic.glyph(kind),
+            })
+            .collect::<String>()
+    });
+
+    std::iter::once(caption(game))
+        .chain(rows)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// draws the board onto a canvas that's never put on the page
+fn draw_image(game: &SnakeGame, theme: Theme) -> Result<HtmlCanvasElement, SlakeError> {
+    let document = crate::error::document()?;
+    let view = game.board_view();
+    let width = view.width as f64 * TILE_SIZE;
+    let height = view.height as f64 * TILE_SIZE;
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width as u32);
+    canvas.set_height((height + CAPTION_HEIGHT) as u32);
+
+    let context = crate::error::context_2d(&canvas)?;
+
+    context.set_fill_style_str(theme.background());
+    context.fill_rect(0.0, 0.0, width, height + CAPTION_HEIGHT);
+    context.set_text_align("center");
+    context.set_text_baseline("middle");
+    context.set_font(&format!("{}px sans-serif", TILE_SIZE * 0.7));
+
+    for y in 0..view.height {
+        for x in 0..view.width {
+            let kind = view.get(&Vector(x, y));
+            let Some(color) = theme.color(kind) else {
+                continue;
+            };
+            let (left, top) = (x as f64 * TILE_SIZE, y as f64 * TILE_SIZE);
+
+            context.set_fill_style_str(color);
+            context.fill_rect(left, top, TILE_SIZE, TILE_SIZE);
+            // emoji bring their own colors, anything else takes the fill
+            context.set_fill_style_str(theme.text_color());
+            context.fill_text(
+                theme.glyph(kind),
+                left + TILE_SIZE / 2.0,
+                top + TILE_SIZE / 2.0,
+            )?;
+        }
+    }
+
+    context.set_font(&format!("{}px sans-serif", CAPTION_HEIGHT * 0.5));
+    context.fill_text(&caption(game), width / 2.0, height + CAPTION_HEIGHT / 2.0)?;
+
+    Ok(canvas)
+}
+
+fn download_image(game: &SnakeGame, theme: Theme) -> Result<(), SlakeError> {
+    let url = draw_image(game, theme)?.to_data_url_with_type("image/png")?;
+
+    let link = crate::error::document()?
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(&format!("slake-{}.png", packed_seed(game)));
+    link.click();
+
+    Ok(())
+}
+
+// `navigator.clipboard` is still marked unstable in web-sys, so this goes around it. pages that
+// don't have one, like ones not served over https, get the text in a prompt to copy instead
+pub fn copy_text(text: &str) -> bool {
+    let Ok(window) = crate::error::window() else {
+        return false;
+    };
+    let write_text = js_sys::Reflect::get(&window.navigator(), &"clipboard".into())
+        .ok()
+        .filter(|clipboard| !clipboard.is_undefined())
+        .and_then(|clipboard| {
+            let write_text = js_sys::Reflect::get(&clipboard, &"writeText".into()).ok()?;
+            Some((clipboard, write_text.dyn_into::<js_sys::Function>().ok()?))
+        });
+
+    match write_text {
+        Some((clipboard, write_text)) => write_text.call1(&clipboard, &text.into()).is_ok(),
+        None => {
+            let _ = window.prompt_with_message_and_default("Copy this to share it", text);
+            false
+        }
+    }
+}
+
+// saves a picture of the board as it is now, and copies it as text too
+pub fn share() {
+    let theme = crate::SETTINGS.with(|settings| settings.borrow().theme);
+    let (saved, text) = crate::GAME.with(|game| {
+        let game = game.borrow();
+        (download_image(&game, theme), share_text(&game))
+    });
+
+    let message = match (saved, copy_text(&text)) {
+        (Ok(()), true) => "Saved a picture of the board, and copied it as text",
+        (Ok(()), false) => "Saved a picture of the board",
+        (Err(_), true) => "Copied the board as text",
+        (Err(_), false) => "Couldn't save the board",
+    };
+    let _ = toast::show(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn share_text_board() {
+        let mut game = SnakeGame::new(5, 3);
+        game.seed = [1, 2];
+        game.score = 7;
+
+        let text = share_text(&game);
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "slake: 7 points, seed 65538");
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1..].iter().all(|row| row.chars().count() == 5));
+
+        // the snake starts on the middle row, facing left
+        assert!(lines[2].contains(Theme::Classic.glyph(TileKind::Head)));
+        assert!(lines[2].ends_with(Theme::Classic.glyph(TileKind::Tail)));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::error::SlakeError;
+use crate::snake::SnakeGame;
+use crate::theme::Theme;
+use crate::view::TileKind;
+
+use std::cell::RefCell;
+use std::ops::Range;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, HtmlElement};
+
+// A little map of every floor under the board, for levels with more than one, since the board
+// only shows the floor the snake's on. That floor gets an outline. It's all plain squares in the
+// theme's colors whatever the style, since glyphs that small would only be noise.
+
+// in canvas pixels
+const TILE_SIZE: f64 = 6.0;
+
+// what's on the map, so it's only drawn again once something's changed
+#[derive(PartialEq)]
+struct Drawn {
+    tiles: Vec<TileKind>,
+    floor: Range<isize>,
+    theme: Theme,
+}
+
+thread_local! {
+    static DRAWN: RefCell<Option<Drawn>> = const { RefCell::new(None) };
+}
+
+// after every frame. the map is made when it's first needed, and goes again on a level without
+// floors
+pub fn update(game: &SnakeGame, theme: Theme) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+    let existing = document.get_element_by_id("floor_map");
+
+    if game.floors().len() < 2 {
+        if let Some(map) = existing {
+            map.remove();
+        }
+        return Ok(());
+    }
+
+    let canvas = match existing {
+        Some(map) => map.dyn_into::<HtmlCanvasElement>()?,
+        // the board being built again takes the map with it
+        None => {
+            DRAWN.with(|drawn| drawn.borrow_mut().take());
+
+            let canvas = document
+                .create_element("canvas")?
+                .dyn_into::<HtmlCanvasElement>()?;
+            canvas.set_id("floor_map");
+            canvas.set_class_name("floor_map");
+            canvas.set_attribute("aria-hidden", "true")?;
+            crate::get_element::<HtmlElement>("root")?.append_child(&canvas)?;
+            canvas
+        }
+    };
+
+    let shown = Drawn {
+        tiles: game.board_view().tiles,
+        floor: game.active_floor(),
+        theme,
+    };
+    if DRAWN.with(|drawn| drawn.borrow().as_ref() == Some(&shown)) {
+        return Ok(());
+    }
+
+    // setting the size clears it too
+    let (width, height) = (
+        game.width as f64 * TILE_SIZE,
+        game.height as f64 * TILE_SIZE,
+    );
+    canvas.set_width(width as u32);
+    canvas.set_height(height as u32);
+
+    let context = crate::error::context_2d(&canvas)?;
+
+    let Drawn {
+        tiles,
+        floor,
+        theme,
+    } = &shown;
+
+    context.set_fill_style_str(theme.background());
+    context.fill_rect(0.0, 0.0, width, height);
+
+    for (index, kind) in tiles.iter().enumerate() {
+        let Some(color) = theme.color(*kind) else {
+            continue;
+        };
+        let (x, y) = (index as isize % game.width, index as isize / game.width);
+
+        context.set_fill_style_str(color);
+        context.fill_rect(
+            x as f64 * TILE_SIZE,
+            y as f64 * TILE_SIZE,
+            TILE_SIZE,
+            TILE_SIZE,
+        );
+    }
+
+    context.set_stroke_style_str(theme.text_color());
+    context.set_line_width(2.0);
+    context.stroke_rect(
+        1.0,
+        floor.start as f64 * TILE_SIZE + 1.0,
+        width - 2.0,
+        (floor.end - floor.start) as f64 * TILE_SIZE - 2.0,
+    );
+
+    DRAWN.with(|drawn| *drawn.borrow_mut() = Some(shown));
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::error::SlakeError;
+
+use std::cell::Cell;
+use wasm_bindgen::{prelude::*, JsCast};
+
+// Pausing when the page isn't being looked at, so the snake doesn't crash somewhere nobody can
+// see it. Switching to another tab or window pauses the run with a note over the board, and a
+// click or any key carries on after the usual countdown. While the tab is hidden altogether the
+// clock stops as well, so there's nothing ticking away in the background. Online games can't
+// stop for one side, and pages running the clock themselves have their own idea of when to
+// stop, so neither is paused.
+
+thread_local! {
+    // whether it was the page losing focus that paused the run, for what the overlay says
+    static PAUSED_GAME: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn paused_game() -> bool {
+    PAUSED_GAME.with(|paused_game| paused_game.get())
+}
+
+// for when the run's carried on some other way, like with a key
+pub fn forget() {
+    PAUSED_GAME.with(|paused_game| paused_game.set(false));
+}
+
+fn leaves_running() -> bool {
+    crate::net::is_active() || crate::EXTERNAL_CLOCK.with(|external_clock| external_clock.get())
+}
+
+fn pause() {
+    // the title and the editor keep the run paused already, and the replay viewer isn't it
+    if leaves_running()
+        || crate::title::is_showing()
+        || crate::editor::is_editing()
+        || crate::replay::is_watching()
+    {
+        return;
+    }
+
+    let paused = crate::GAME.with(|game| {
+        let mut game = game.borrow_mut();
+        if game.paused || game.is_game_over() {
+            return false;
+        }
+
+        game.paused = true;
+        true
+    });
+
+    if paused {
+        PAUSED_GAME.with(|paused_game| paused_game.set(true));
+        crate::log("Paused, since the page went out of focus");
+        crate::draw();
+    }
+}
+
+// the click that carries on, if it was losing focus that paused it
+fn resume() {
+    if !PAUSED_GAME.with(|paused_game| paused_game.take()) {
+        return;
+    }
+
+    crate::GAME.with(|game| {
+        let mut game = game.borrow_mut();
+        if game.paused && !game.is_game_over() {
+            game.paused = false;
+            // a moment to find the keys again
+            game.start_countdown();
+        }
+    });
+    crate::draw();
+}
+
+fn handle_visibility() {
+    let Ok(document) = crate::error::document() else {
+        return;
+    };
+    if !document.hidden() {
+        if !leaves_running() {
+            crate::start_ticking(crate::SETTINGS.with(|settings| settings.borrow().tick_ms));
+        }
+        return;
+    }
+
+    pause();
+    if !leaves_running() {
+        // and starting again starts the wait over, so the time away isn't caught up on
+        crate::SCHEDULER.with(|scheduler| scheduler.stop());
+    }
+}
+
+pub fn listen() -> Result<(), SlakeError> {
+    let window = crate::error::window()?;
+
+    let handle_blur = Closure::wrap(Box::new(pause) as Box<dyn FnMut()>);
+    let handle_visibility = Closure::wrap(Box::new(handle_visibility) as Box<dyn FnMut()>);
+    let handle_press = Closure::wrap(Box::new(resume) as Box<dyn FnMut()>);
+
+    window.add_event_listener_with_callback("blur", handle_blur.as_ref().unchecked_ref())?;
+    crate::error::document()?.add_event_listener_with_callback(
+        "visibilitychange",
+        handle_visibility.as_ref().unchecked_ref(),
+    )?;
+    window
+        .add_event_listener_with_callback("pointerdown", handle_press.as_ref().unchecked_ref())?;
+
+    // the page is never unloaded, as far as we're concerned
+    handle_blur.forget();
+    handle_visibility.forget();
+    handle_press.forget();
+
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use crate::effects::Timer;
+use crate::geometry::Vector;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum FoodKind {
+    Normal,
+    // worth a lot, but only sticks around for a little while
+    Bonus,
+    // costs points, and still leaves a puddle behind
+    Rotten,
+    // speeds the snake up for a while, see `snake::BOOST_MS`
+    Speed,
+    // takes `SHRINK_TILES` off the tail, for when the snake's got too long to get around
+    Shrink,
+}
+
+// what a shrink berry takes off
+pub const SHRINK_TILES: usize = 3;
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 5] = [
+        FoodKind::Normal,
+        FoodKind::Bonus,
+        FoodKind::Rotten,
+        FoodKind::Speed,
+        FoodKind::Shrink,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FoodKind::Normal => "normal",
+            FoodKind::Bonus => "bonus",
+            FoodKind::Rotten => "rotten",
+            FoodKind::Speed => "speed",
+            FoodKind::Shrink => "shrink",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FoodKind> {
+        match name {
+            "normal" => Some(FoodKind::Normal),
+            "bonus" => Some(FoodKind::Bonus),
+            "rotten" => Some(FoodKind::Rotten),
+            "speed" => Some(FoodKind::Speed),
+            "shrink" => Some(FoodKind::Shrink),
+            _ => None,
+        }
+    }
+
+    pub fn score_delta(&self) -> isize {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Bonus => 3,
+            FoodKind::Rotten => -1,
+            FoodKind::Speed => 1,
+            // getting out of a tight spot is its own reward
+            FoodKind::Shrink => 0,
+        }
+    }
+
+    // how many tiles the snake grows by, unless `SnakeGame::growth` says otherwise
+    pub fn growth(&self) -> usize {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Bonus => 1,
+            FoodKind::Rotten => 0,
+            FoodKind::Speed => 1,
+            FoodKind::Shrink => 0,
+        }
+    }
+
+    pub fn spawns_hazard(&self) -> bool {
+        match self {
+            FoodKind::Normal => true,
+            FoodKind::Bonus => false,
+            FoodKind::Rotten => true,
+            FoodKind::Speed => false,
+            FoodKind::Shrink => false,
+        }
+    }
+
+    // in milliseconds of play, or `None` for food that waits around until it's eaten
+    pub fn lifetime_ms(&self) -> Option<usize> {
+        match self {
+            FoodKind::Normal => None,
+            FoodKind::Bonus => Some(4000),
+            FoodKind::Rotten => Some(6000),
+            FoodKind::Speed => Some(5000),
+            FoodKind::Shrink => Some(6000),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Food {
+    pub position: Vector,
+    pub kind: FoodKind,
+    // when it disappears, if it does
+    pub despawn: Option<Timer>,
+    // when it came out, on the same clock, for `fresh_bonus`
+    pub spawned_ms: usize,
+}
+
+// With fresh food on, see `SnakeGame::fresh_food`, fruit that's just come out is worth this
+// many points more than usual, one less every `FRESH_STEP_MS` until it's worth what it always is.
+pub const MAX_FRESH_BONUS: usize = 4;
+pub const FRESH_STEP_MS: usize = 2500;
+
+impl Food {
+    // `now_ms` is `SnakeGame::elapsed_ms`
+    pub fn new(position: Vector, kind: FoodKind, now_ms: usize) -> Food {
+        Food {
+            position,
+            kind,
+            despawn: kind
+                .lifetime_ms()
+                .map(|lifetime| Timer::new(now_ms, lifetime)),
+            spawned_ms: now_ms,
+        }
+    }
+
+    // what getting to it at `now_ms` is worth on top of its score, when fresh food is on. only
+    // food that scores at all goes off, so rotten fruit costs the same as ever
+    pub fn fresh_bonus(&self, now_ms: usize) -> usize {
+        if self.kind.score_delta() <= 0 {
+            return 0;
+        }
+
+        let age = now_ms.saturating_sub(self.spawned_ms);
+        MAX_FRESH_BONUS.saturating_sub(age / FRESH_STEP_MS)
+    }
+
+    pub fn ms_left(&self, now_ms: usize) -> Option<usize> {
+        self.despawn.map(|despawn| despawn.ms_left(now_ms))
+    }
+}
+
+// how many kinds of food there were to say the growth of, before shrink berries
+const LEGACY_GROWTH_KINDS: usize = 4;
+
+// more than that and a couple of fruit fill up a small board
+pub const MAX_GROWTH: usize = 9;
+
+// How many tiles each kind of food grows a snake by, in the same order as `FoodKind::ALL`. The
+// snake grows by a tile a tick until it's caught up, keeping its tail where it is, and eating
+// more in the meantime only adds to what's left.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Growth([usize; FoodKind::ALL.len()]);
+
+impl Default for Growth {
+    fn default() -> Self {
+        Growth(FoodKind::ALL.map(|kind| kind.growth()))
+    }
+}
+
+impl Growth {
+    pub fn of(&self, kind: FoodKind) -> usize {
+        self.0[kind as usize]
+    }
+
+    // `tiles` is clamped to `MAX_GROWTH`
+    pub fn with(mut self, kind: FoodKind, tiles: usize) -> Growth {
+        self.0[kind as usize] = tiles.min(MAX_GROWTH);
+        self
+    }
+
+    // like "1,1,0,1,0"
+    pub fn name(&self) -> String {
+        self.0
+            .iter()
+            .map(|tiles| tiles.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn from_name(name: &str) -> Option<Growth> {
+        let tiles = name
+            .split(',')
+            .map(|tiles| {
+                tiles
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|tiles| *tiles <= MAX_GROWTH)
+            })
+            .collect::<Option<Vec<usize>>>()?;
+
+        // saved before shrink berries came along, which don't grow the snake anyway
+        if !(LEGACY_GROWTH_KINDS..=FoodKind::ALL.len()).contains(&tiles.len()) {
+            return None;
+        }
+        let mut growth = Growth::default();
+        growth.0[..tiles.len()].copy_from_slice(&tiles);
+        Some(growth)
+    }
+}
+
+// with `FoodCount::UpTo`, how many points each extra food takes
+pub const FOOD_SCORE_STEP: usize = 10;
+pub const MAX_FOOD_COUNT: usize = 5;
+
+// How much normal food is out at once. Eating one only brings out more when there's less than
+// this left, so the special kinds don't count.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum FoodCount {
+    Fixed(usize),
+    // one to start with, and another every `FOOD_SCORE_STEP` points until there's this many
+    UpTo(usize),
+}
+
+impl Default for FoodCount {
+    fn default() -> Self {
+        FoodCount::Fixed(1)
+    }
+}
+
+impl FoodCount {
+    // like "3" for a fixed count, or "up_to_3"
+    pub fn name(&self) -> String {
+        match self {
+            FoodCount::Fixed(count) => count.to_string(),
+            FoodCount::UpTo(count) => format!("up_to_{count}"),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FoodCount> {
+        let food_count = match name.strip_prefix("up_to_") {
+            Some(count) => FoodCount::UpTo(count.parse().ok()?),
+            None => FoodCount::Fixed(name.parse().ok()?),
+        };
+
+        (1..=MAX_FOOD_COUNT)
+            .contains(&food_count.most())
+            .then_some(food_count)
+    }
+
+    pub fn most(&self) -> usize {
+        match self {
+            FoodCount::Fixed(count) | FoodCount::UpTo(count) => *count,
+        }
+    }
+
+    // how much should be out with `score` points so far. never less than one, so there's always
+    // something to go for
+    pub fn target(&self, score: usize) -> usize {
+        match self {
+            FoodCount::Fixed(count) => (*count).max(1),
+            FoodCount::UpTo(count) => (1 + score / FOOD_SCORE_STEP).min(*count).max(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn food_counts() {
+        assert_eq!(FoodCount::default().target(100), 1);
+        assert_eq!(FoodCount::Fixed(3).target(0), 3);
+
+        let scaling = FoodCount::UpTo(3);
+        assert_eq!(scaling.target(0), 1);
+        assert_eq!(scaling.target(FOOD_SCORE_STEP - 1), 1);
+        assert_eq!(scaling.target(FOOD_SCORE_STEP), 2);
+        assert_eq!(scaling.target(FOOD_SCORE_STEP * 10), 3);
+
+        for food_count in [FoodCount::Fixed(2), scaling] {
+            assert_eq!(FoodCount::from_name(&food_count.name()), Some(food_count));
+        }
+        assert_eq!(FoodCount::from_name("0"), None);
+        assert_eq!(FoodCount::from_name("up_to_99"), None);
+        assert_eq!(FoodCount::from_name("lots"), None);
+    }
+
+    #[test]
+    fn growth() {
+        let growth = Growth::default();
+        assert_eq!(growth.name(), "1,1,0,1,0");
+        assert_eq!(growth.of(FoodKind::Rotten), 0);
+
+        let growth = growth.with(FoodKind::Bonus, 4).with(FoodKind::Normal, 100);
+        assert_eq!(growth.of(FoodKind::Bonus), 4);
+        assert_eq!(growth.of(FoodKind::Normal), MAX_GROWTH);
+        assert_eq!(Growth::from_name(&growth.name()), Some(growth));
+
+        assert_eq!(Growth::from_name("1,1,0"), None);
+        assert_eq!(Growth::from_name("1,1,0,1,1,1"), None);
+        assert_eq!(Growth::from_name("1,1,0,10"), None);
+        assert_eq!(
+            Growth::from_name("2,1,0,1"),
+            Some(Growth::default().with(FoodKind::Normal, 2))
+        );
+        assert_eq!(Growth::from_name("lots"), None);
+    }
+}
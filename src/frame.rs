@@ -0,0 +1,233 @@
+use crate::food::FoodKind;
+use crate::geometry::Vector;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+
+// A compact binary picture of the board, one per tick, for things outside the page that want to
+// mirror the game cheaply, like a stream overlay or an LED wall. Everything is little endian:
+//
+//   u8   version, currently 1
+//   u16  width
+//   u16  height
+//   u32  tick
+//   u8   flags: 1 game over, 2 paused, 4 versus
+//   then one bitplane per layer, in the order of `Layer`. a bitplane has one bit per tile, in
+//   rows from the top left, starting from the lowest bit of each byte, padded out to a whole
+//   byte at the end
+//   u16  entity count
+//   then that many entities of 6 bytes each: u8 kind (see `Entity`), u8 player, u16 x, u16 y
+//
+// The bitplanes say what's where, and the entities pick out the things worth drawing
+// differently, like heads and the different kinds of food.
+
+pub const FRAME_VERSION: u8 = 1;
+
+const FLAG_GAME_OVER: u8 = 1;
+const FLAG_PAUSED: u8 = 2;
+const FLAG_VERSUS: u8 = 4;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Layer {
+    Wall,
+    Hazard,
+    // the first player's snakes, hydra heads and all
+    FirstSnake,
+    // the second player's in versus
+    SecondSnake,
+    // any kind of food, golden fruit included
+    Food,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 5] = [
+        Layer::Wall,
+        Layer::Hazard,
+        Layer::FirstSnake,
+        Layer::SecondSnake,
+        Layer::Food,
+    ];
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[repr(u8)]
+pub enum Entity {
+    Head = 0,
+    Tail = 1,
+    Food = 2,
+    BonusFood = 3,
+    RottenFood = 4,
+    GoldenFood = 5,
+    SpeedFood = 6,
+    ShrinkFood = 7,
+}
+
+pub fn encode(game: &SnakeGame) -> Vec<u8> {
+    let tiles = (game.width * game.height) as usize;
+    let plane_length = tiles.div_ceil(8);
+
+    let mut frame = vec![FRAME_VERSION];
+    frame.extend((game.width as u16).to_le_bytes());
+    frame.extend((game.height as u16).to_le_bytes());
+    frame.extend((game.ticks as u32).to_le_bytes());
+
+    let mut flags = 0;
+    if game.is_game_over() {
+        flags |= FLAG_GAME_OVER;
+    }
+    if game.paused {
+        flags |= FLAG_PAUSED;
+    }
+    if game.is_versus() {
+        flags |= FLAG_VERSUS;
+    }
+    frame.push(flags);
+
+    let mut planes = vec![vec![0u8; plane_length]; Layer::ALL.len()];
+    let mut set = |layer: Layer, pos: &Vector| {
+        let tile = (pos.1 * game.width + pos.0) as usize;
+        planes[layer as usize][tile / 8] |= 1 << (tile % 8);
+    };
+
+    for pos in game.walls.iter() {
+        set(Layer::Wall, pos);
+    }
+    for pos in game.hazards().iter() {
+        set(Layer::Hazard, pos);
+    }
+    for snake in game.snakes().iter() {
+        let layer = match snake.player {
+            0 => Layer::FirstSnake,
+            _ => Layer::SecondSnake,
+        };
+        for pos in snake.body.iter() {
+            set(layer, pos);
+        }
+    }
+    for pos in game
+        .food()
+        .iter()
+        .map(|food| &food.position)
+        .chain(game.golden_food())
+    {
+        set(Layer::Food, pos);
+    }
+
+    for plane in planes {
+        frame.extend(plane);
+    }
+
+    let mut entities = vec![];
+    for snake in game.snakes().iter() {
+        entities.push((Entity::Head, snake.player, snake.head()));
+        entities.push((Entity::Tail, snake.player, snake.tail()));
+    }
+    for food in game.food().iter() {
+        let kind = match food.kind {
+            FoodKind::Normal => Entity::Food,
+            FoodKind::Bonus => Entity::BonusFood,
+            FoodKind::Rotten => Entity::RottenFood,
+            FoodKind::Speed => Entity::SpeedFood,
+            FoodKind::Shrink => Entity::ShrinkFood,
+        };
+        entities.push((kind, 0, &food.position));
+    }
+    if let Some(pos) = game.golden_food() {
+        entities.push((Entity::GoldenFood, 0, pos));
+    }
+
+    frame.extend((entities.len() as u16).to_le_bytes());
+    for (kind, player, pos) in entities {
+        frame.push(kind as u8);
+        frame.push(player as u8);
+        frame.extend((pos.0 as u16).to_le_bytes());
+        frame.extend((pos.1 as u16).to_le_bytes());
+    }
+
+    frame
+}
+
+thread_local! {
+    // where to send each tick's frame, if anywhere. see `stream`
+    static TARGET_ORIGIN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// `None` stops sending them
+pub fn set_target_origin(target_origin: Option<String>) {
+    TARGET_ORIGIN.with(|old_target_origin| old_target_origin.replace(target_origin));
+}
+
+// Sends the frame with postMessage, as `{ type: "slake_frame", frame: Uint8Array }`, to the page
+// this one's embedded in, or to this page if it isn't.
+pub fn stream(game: &SnakeGame) {
+    let Some(target_origin) = TARGET_ORIGIN.with(|target_origin| target_origin.borrow().clone())
+    else {
+        return;
+    };
+
+    let Ok(window) = crate::error::window() else {
+        return;
+    };
+    let target = window.parent().ok().flatten().unwrap_or(window);
+
+    let message = js_sys::Object::new();
+    let frame = js_sys::Uint8Array::from(&encode(game)[..]);
+    let _ = js_sys::Reflect::set(&message, &"type".into(), &"slake_frame".into());
+    let _ = js_sys::Reflect::set(&message, &"frame".into(), &frame);
+
+    // a visualizer that's gone away isn't worth stopping the game over
+    let _ = target.post_message(&message, &target_origin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        let mut game = SnakeGame::new(21, 15);
+        game.walls = vec![Vector(0, 0), Vector(9, 0)];
+        game.food_mut().truncate(1);
+        let food = game.food()[0].position.clone();
+
+        let frame = encode(&game);
+        let plane_length = (21 * 15usize).div_ceil(8);
+        let entities = game.snakes().len() * 2 + 1;
+        assert_eq!(
+            frame.len(),
+            10 + plane_length * Layer::ALL.len() + 2 + entities * 6
+        );
+
+        assert_eq!(frame[0], FRAME_VERSION);
+        assert_eq!(u16::from_le_bytes([frame[1], frame[2]]), 21);
+        assert_eq!(u16::from_le_bytes([frame[3], frame[4]]), 15);
+        assert_eq!(frame[9], 0);
+
+        // the walls, in the first plane
+        assert_eq!(frame[10], 1);
+        assert_eq!(frame[11], 2);
+
+        let is_set = |layer: Layer, pos: &Vector| {
+            let tile = (pos.1 * 21 + pos.0) as usize;
+            frame[10 + layer as usize * plane_length + tile / 8] & (1 << (tile % 8)) != 0
+        };
+        assert!(is_set(Layer::FirstSnake, game.snakes()[0].head()));
+        assert!(!is_set(Layer::SecondSnake, game.snakes()[0].head()));
+        assert!(is_set(Layer::Food, &food));
+
+        let entity_start = 10 + plane_length * Layer::ALL.len();
+        assert_eq!(
+            u16::from_le_bytes([frame[entity_start], frame[entity_start + 1]]) as usize,
+            entities
+        );
+        let head = &frame[entity_start + 2..entity_start + 8];
+        assert_eq!(head[0], Entity::Head as u8);
+        assert_eq!(
+            Vector(
+                u16::from_le_bytes([head[2], head[3]]) as isize,
+                u16::from_le_bytes([head[4], head[5]]) as isize
+            ),
+            *game.snakes()[0].head()
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::geometry;
+use crate::geometry::Vector;
+use crate::level::MIN_REACHABLE_TILES;
+
+use prng::Prng16;
+
+// Random wall layouts, grown like caves: scatter walls over the board, then smooth them out a
+// few times so they clump together into rocks and ridges with passages between them. Whatever
+// open space the snake couldn't get to afterwards is filled in, so there are never pockets of
+// food out of reach.
+
+// in percent of the board, before smoothing. smoothing takes some of them away again, mostly
+// the loners
+pub const MAZE_DENSITY: usize = 40;
+
+const SMOOTHING_STEPS: usize = 3;
+// room to get going in front of a start, before the first wall
+const RUNWAY: isize = 3;
+
+// `snakes` are where the snakes start, each head first like `Snake::body`. They stay open, along
+// with a runway in front of each head, and everything open ends up joined to the first head.
+// `density` is lowered until there's enough room to play, so tiny boards might get no walls at
+// all. The same seed always gives the same maze.
+pub fn maze(
+    width: isize,
+    height: isize,
+    seed: [u16; 2],
+    density: usize,
+    snakes: &[Vec<Vector>],
+) -> Vec<Vector> {
+    let mut density = density.min(100);
+    let snake_tiles = snakes.iter().map(|snake| snake.len()).sum::<usize>();
+
+    loop {
+        let walls = try_maze(width, height, seed, density, snakes);
+
+        let open = (width * height) as usize - walls.len();
+        if open >= MIN_REACHABLE_TILES + snake_tiles || density == 0 {
+            return walls;
+        }
+
+        density = density.saturating_sub(10);
+    }
+}
+
+fn try_maze(
+    width: isize,
+    height: isize,
+    seed: [u16; 2],
+    density: usize,
+    snakes: &[Vec<Vector>],
+) -> Vec<Vector> {
+    let mut prng = Prng16::new(seed);
+    let index = |pos: &Vector| (pos.1 * width + pos.0) as usize;
+    let positions = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Vector(x, y)))
+        .collect::<Vec<_>>();
+
+    let mut is_wall = positions
+        .iter()
+        .map(|_| (prng.next().unwrap() as usize % 100) < density)
+        .collect::<Vec<_>>();
+
+    // a tile with most of its neighbours walled becomes a wall, and one with few opens up. off
+    // the board counts as open, since the snake can't go there anyway and a solid border would
+    // just make the board smaller
+    for _ in 0..SMOOTHING_STEPS {
+        is_wall = positions
+            .iter()
+            .map(|pos| {
+                let walled = geometry::neighbors8(width, height, pos)
+                    .filter(|next| is_wall[index(next)])
+                    .count();
+
+                match walled {
+                    5.. => true,
+                    4 => is_wall[index(pos)],
+                    _ => false,
+                }
+            })
+            .collect();
+    }
+
+    for snake in snakes.iter() {
+        for pos in snake.iter() {
+            is_wall[index(pos)] = false;
+        }
+
+        // snakes set off away from their necks
+        let (Some(head), Some(neck)) = (snake.first(), snake.get(1)) else {
+            continue;
+        };
+        let step = Vector(head.0 - neck.0, head.1 - neck.1);
+        let mut runway = head.clone();
+        for _ in 0..RUNWAY {
+            runway = &runway + &step;
+            if geometry::is_within_board(width, height, &runway) {
+                is_wall[index(&runway)] = false;
+            }
+        }
+    }
+
+    // any other snake that can't get to the first one gets a path dug to it, straight across
+    // and then straight down or up
+    if let Some(start) = snakes.first().and_then(|snake| snake.first()) {
+        for pos in snakes.iter().skip(1).filter_map(|snake| snake.first()) {
+            let reachable =
+                geometry::flood_fill(width, height, start, |next| !is_wall[index(next)]);
+            if reachable.contains(pos) {
+                continue;
+            }
+
+            let mut dig = pos.clone();
+            while dig != *start {
+                if dig.0 != start.0 {
+                    dig.0 += (start.0 - dig.0).signum();
+                } else {
+                    dig.1 += (start.1 - dig.1).signum();
+                }
+                is_wall[index(&dig)] = false;
+            }
+        }
+
+        let reachable = geometry::flood_fill(width, height, start, |next| !is_wall[index(next)]);
+        is_wall = vec![true; positions.len()];
+        for pos in reachable.iter() {
+            is_wall[index(pos)] = false;
+        }
+    }
+
+    positions
+        .into_iter()
+        .filter(|pos| is_wall[index(pos)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mazes() {
+        // like in versus, head first
+        let snakes = [
+            vec![Vector(19, 7), Vector(20, 7)],
+            vec![Vector(1, 7), Vector(0, 7)],
+        ];
+
+        for seed in 0..50 {
+            let walls = maze(21, 15, [seed, 1], MAZE_DENSITY, &snakes);
+            assert_eq!(walls, maze(21, 15, [seed, 1], MAZE_DENSITY, &snakes));
+
+            assert!(snakes.iter().flatten().all(|pos| !walls.contains(pos)));
+            // with a runway in front of each
+            assert!((16..19).all(|x| !walls.contains(&Vector(x, 7))));
+            assert!((2..5).all(|x| !walls.contains(&Vector(x, 7))));
+
+            // every open tile can be got to from the start
+            let reachable = geometry::flood_fill(21, 15, &snakes[0][0], |pos| !walls.contains(pos));
+            assert_eq!(reachable.len() + walls.len(), 21 * 15);
+            assert!(reachable.len() >= MIN_REACHABLE_TILES);
+        }
+
+        // something to look at, at least most of the time
+        assert!(!maze(21, 15, [3, 4], MAZE_DENSITY, &snakes).is_empty());
+        assert!(maze(21, 15, [3, 4], 0, &snakes).is_empty());
+
+        // too small for walls to leave enough room
+        assert!(maze(5, 3, [3, 4], 100, &[vec![Vector(3, 1), Vector(4, 1)]]).is_empty());
+    }
+}
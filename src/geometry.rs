@@ -0,0 +1,573 @@
+use serde::{Deserialize, Serialize};
+
+// Grid math shared by the game rules, spawning, and anything that wants to reason about the board.
+// Positions are plain `Vector`s with (0, 0) in the top left corner, and a board is described by
+// its width and height.
+//
+// Boards are square tiles unless they're hexagons, see `Grid`. A hex board keeps the same
+// positions, tile for tile, and only changes which tiles are next to each other and how they're
+// laid out on screen, so anything that only cares about what's on a tile works the same on both.
+
+// a tile position, or an offset between two tiles
+#[derive(PartialEq, Eq, Hash, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Vector(pub isize, pub isize);
+
+impl std::ops::Add<&Vector> for &Vector {
+    type Output = Vector;
+
+    fn add(self, other: &Vector) -> Vector {
+        Vector(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl std::ops::AddAssign<&Vector> for Vector {
+    fn add_assign(&mut self, other: &Vector) {
+        self.0 += other.0;
+        self.1 += other.1;
+    }
+}
+
+// One of the ways the snake can move. Only hex boards have the last two.
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    #[default]
+    Left,
+    UpRight,
+    DownLeft,
+}
+
+impl Direction {
+    // the four directions on a square board, clockwise from up
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    // the six directions on a hex board, clockwise from up, which is up and to the left there
+    pub const HEX: [Direction; 6] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+    ];
+
+    // lowercase name, as accepted by `from_name`
+    pub fn name(&self) -> &'static str {
+        use Direction::*;
+        match self {
+            Up => "up",
+            Right => "right",
+            Down => "down",
+            Left => "left",
+            UpRight => "up_right",
+            DownLeft => "down_left",
+        }
+    }
+
+    // parses a direction name, ignoring case
+    pub fn from_name(name: &str) -> Option<Direction> {
+        Direction::HEX
+            .into_iter()
+            .find(|direction| direction.name().eq_ignore_ascii_case(name))
+    }
+
+    // the one-tile step this direction moves by
+    pub fn to_vector(&self) -> Vector {
+        use Direction::*;
+        match self {
+            Up => Vector(0, -1),
+            Right => Vector(1, 0),
+            Down => Vector(0, 1),
+            Left => Vector(-1, 0),
+            UpRight => Vector(1, -1),
+            DownLeft => Vector(-1, 1),
+        }
+    }
+
+    // the direction pointing the other way, which the snake can never turn into directly
+    pub fn opposite(&self) -> Direction {
+        use Direction::*;
+        match self {
+            Up => Down,
+            Right => Left,
+            Down => Up,
+            Left => Right,
+            UpRight => DownLeft,
+            DownLeft => UpRight,
+        }
+    }
+
+    // the direction as seen in a mirror held along the vertical axis, swapping left and right
+    pub fn mirror_horizontal(&self) -> Direction {
+        use Direction::*;
+        match self {
+            Right => Left,
+            Left => Right,
+            other => other.clone(),
+        }
+    }
+
+    // The direction of a single step, if `step` is one. The steps only hex boards have count,
+    // since they never turn up on a square one.
+    pub fn from_vector(step: &Vector) -> Option<Direction> {
+        Direction::HEX
+            .into_iter()
+            .find(|direction| direction.to_vector() == *step)
+    }
+}
+
+// what happens at the edges of the board
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub enum Topology {
+    // the edges are deadly
+    #[default]
+    Walls,
+    // leaving one edge brings you back in on the opposite side, see `wrap_position`
+    Wrap,
+}
+
+impl Topology {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Walls => "walls",
+            Topology::Wrap => "wrap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Topology> {
+        match name {
+            "walls" => Some(Topology::Walls),
+            "wrap" => Some(Topology::Wrap),
+            _ => None,
+        }
+    }
+}
+
+// how tiles fit together on the board
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub enum Grid {
+    #[default]
+    Square,
+    // hexagons, each row half a tile further right than the one above, see `Hex`
+    Hex,
+}
+
+impl Grid {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grid::Square => "square",
+            Grid::Hex => "hex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Grid> {
+        match name {
+            "square" => Some(Grid::Square),
+            "hex" => Some(Grid::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn geometry(&self) -> &'static dyn Geometry {
+        match self {
+            Grid::Square => &Square,
+            Grid::Hex => &Hex,
+        }
+    }
+}
+
+// what sets one kind of board apart from another: which tiles are next to each other, how far
+// apart tiles are, which way the player's four directions steer, and where tiles go on screen
+pub trait Geometry {
+    // every way a snake can go, clockwise from up
+    fn directions(&self) -> &'static [Direction];
+
+    // the number of single steps it takes to walk from `a` to `b`, ignoring anything in the
+    // way
+    fn distance(&self, a: &Vector, b: &Vector) -> usize;
+
+    // which way a snake headed `heading` goes when the player presses `pressed`, one of the
+    // four square directions that every kind of input has
+    fn steer(&self, pressed: Direction, heading: &Direction) -> Direction;
+
+    // which way `direction` points on screen, in degrees clockwise from up
+    fn degrees(&self, direction: &Direction) -> u16;
+
+    // where the top left of the tile at `position` goes on screen, in tiles from the top left
+    // of the board
+    fn tile_offset(&self, position: &Vector) -> (f64, f64);
+
+    // how much room a `width` by `height` board takes on screen, in tiles
+    fn extent(&self, width: isize, height: isize) -> (f64, f64) {
+        let (right, bottom) = self.tile_offset(&Vector(width - 1, height - 1));
+        (right + 1.0, bottom + 1.0)
+    }
+
+    // whether `position` is on a `width` by `height` board, which is the same for every grid
+    // since they all keep their tiles in rows
+    fn contains(&self, width: isize, height: isize, position: &Vector) -> bool {
+        is_within_board(width, height, position)
+    }
+
+    // the tiles one step from `position` that are on the board
+    fn neighbors(&self, width: isize, height: isize, position: &Vector) -> Vec<Vector> {
+        self.directions()
+            .iter()
+            .map(|direction| position + &direction.to_vector())
+            .filter(|pos| self.contains(width, height, pos))
+            .collect()
+    }
+
+    // Every tile reachable from `start` by single steps through tiles where `passable` is true,
+    // including `start` itself. `start` doesn't have to be passable.
+    fn flood_fill(
+        &self,
+        width: isize,
+        height: isize,
+        start: &Vector,
+        passable: &dyn Fn(&Vector) -> bool,
+    ) -> Vec<Vector> {
+        let mut visited = vec![false; (width * height).max(0) as usize];
+        let mut region = vec![];
+        let mut frontier = vec![start.clone()];
+
+        if !self.contains(width, height, start) {
+            return region;
+        }
+
+        visited[(start.1 * width + start.0) as usize] = true;
+
+        while let Some(pos) = frontier.pop() {
+            for next in self.neighbors(width, height, &pos) {
+                let index = (next.1 * width + next.0) as usize;
+
+                if !visited[index] && passable(&next) {
+                    visited[index] = true;
+                    frontier.push(next);
+                }
+            }
+
+            region.push(pos);
+        }
+
+        region
+    }
+}
+
+// square tiles, four ways out of each
+pub struct Square;
+
+impl Geometry for Square {
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::ALL
+    }
+
+    fn distance(&self, a: &Vector, b: &Vector) -> usize {
+        manhattan_distance(a, b)
+    }
+
+    fn steer(&self, pressed: Direction, _heading: &Direction) -> Direction {
+        pressed
+    }
+
+    fn degrees(&self, direction: &Direction) -> u16 {
+        let index = Direction::ALL.iter().position(|other| other == direction);
+        index.unwrap_or(0) as u16 * 90
+    }
+
+    fn tile_offset(&self, &Vector(x, y): &Vector) -> (f64, f64) {
+        (x as f64, y as f64)
+    }
+}
+
+// How far apart rows of hexagons are, in tiles. They're drawn pointy end up, so each row tucks
+// into the one above.
+pub const HEX_ROW_STEP: f64 = 0.75;
+
+// Hexagons, six ways out of each. Positions go along rows like on a square board, but every
+// row is drawn half a tile further right than the one above, so the board's a slanted rhombus.
+// That way a step is the same whichever row it's from: up is up and to the left on screen,
+// down is down and to the right, and `UpRight` and `DownLeft` are the two diagonals that
+// square boards don't have. Wrapping still works, since opposite edges are still opposite.
+pub struct Hex;
+
+impl Geometry for Hex {
+    fn directions(&self) -> &'static [Direction] {
+        &Direction::HEX
+    }
+
+    fn distance(&self, a: &Vector, b: &Vector) -> usize {
+        let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+        (dx.unsigned_abs() + dy.unsigned_abs() + (dx + dy).unsigned_abs()) / 2
+    }
+
+    // Left and right go straight along the row. Up and down go whichever of their two
+    // diagonals is on the side the snake's already headed, so a snake going right goes up
+    // and to the right, and one going left goes down and to the left. From anywhere, that's
+    // somewhere new for all but the way back. The two diagonals go as they are, for bots.
+    fn steer(&self, pressed: Direction, heading: &Direction) -> Direction {
+        use Direction::*;
+        let rightwards = matches!(heading, Right | UpRight | Down);
+
+        match (pressed, rightwards) {
+            (Up, true) => UpRight,
+            (Down, false) => DownLeft,
+            (pressed, _) => pressed,
+        }
+    }
+
+    fn degrees(&self, direction: &Direction) -> u16 {
+        let index = Direction::HEX.iter().position(|other| other == direction);
+        (index.unwrap_or(0) as u16 * 60 + 330) % 360
+    }
+
+    fn tile_offset(&self, &Vector(x, y): &Vector) -> (f64, f64) {
+        (x as f64 + y as f64 / 2.0, y as f64 * HEX_ROW_STEP)
+    }
+}
+
+// whether `position` is on a `width` by `height` board
+pub fn is_within_board(width: isize, height: isize, &Vector(x, y): &Vector) -> bool {
+    x >= 0 && y >= 0 && x < width && y < height
+}
+
+// the tiles directly above, below, left, and right of `position` that are on the board
+pub fn adjacent_tiles(
+    width: isize,
+    height: isize,
+    position: &Vector,
+) -> impl Iterator<Item = Vector> {
+    [
+        Vector(position.0 - 1, position.1),
+        Vector(position.0 + 1, position.1),
+        Vector(position.0, position.1 - 1),
+        Vector(position.0, position.1 + 1),
+    ]
+    .into_iter()
+    .filter(move |pos| is_within_board(width, height, pos))
+}
+
+// like `adjacent_tiles`, but also including the four diagonals
+pub fn neighbors8(width: isize, height: isize, position: &Vector) -> impl Iterator<Item = Vector> {
+    let Vector(x, y) = *position;
+
+    (-1..=1)
+        .flat_map(move |dy| (-1..=1).map(move |dx| Vector(x + dx, y + dy)))
+        .filter(move |pos| *pos != Vector(x, y) && is_within_board(width, height, pos))
+}
+
+// the number of single steps it takes to walk from `a` to `b`, ignoring anything in the way
+pub fn manhattan_distance(a: &Vector, b: &Vector) -> usize {
+    (a.0 - b.0).unsigned_abs() + (a.1 - b.1).unsigned_abs()
+}
+
+// brings a position that went off one edge of the board back in through the opposite edge
+pub fn wrap_position(width: isize, height: isize, &Vector(x, y): &Vector) -> Vector {
+    Vector(x.rem_euclid(width), y.rem_euclid(height))
+}
+
+// The tile across the center of the board from `pos`, as if the board were turned halfway
+// around. Doing it twice gets back to `pos`, and on a board with odd width and height the
+// center tile is its own mirror.
+pub fn mirror_position(width: isize, height: isize, &Vector(x, y): &Vector) -> Vector {
+    Vector(width - 1 - x, height - 1 - y)
+}
+
+// `Geometry::flood_fill` on a square board
+pub fn flood_fill(
+    width: isize,
+    height: isize,
+    start: &Vector,
+    passable: impl Fn(&Vector) -> bool,
+) -> Vec<Vector> {
+    Square.flood_fill(width, height, start, &passable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_board() {
+        for y in -2..5 {
+            for x in -2..7 {
+                let inside = (0..5).contains(&x) && (0..3).contains(&y);
+                assert_eq!(is_within_board(5, 3, &Vector(x, y)), inside, "{x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn directions() {
+        for direction in Direction::ALL {
+            let step = direction.to_vector();
+            let back = direction.opposite().to_vector();
+
+            assert_eq!(&step + &back, Vector(0, 0));
+            assert_eq!(manhattan_distance(&step, &Vector(0, 0)), 1);
+            assert_eq!(direction.opposite().opposite(), direction);
+            assert_eq!(Direction::from_vector(&step), Some(direction.clone()));
+            assert_eq!(
+                Direction::from_name(direction.name()),
+                Some(direction.clone())
+            );
+            assert_eq!(direction.mirror_horizontal().mirror_horizontal(), direction);
+        }
+    }
+
+    #[test]
+    fn direction_conversions() {
+        assert_eq!(Direction::from_vector(&Vector(0, 0)), None);
+        assert_eq!(Direction::from_vector(&Vector(2, 0)), None);
+        assert_eq!(Direction::from_vector(&Vector(1, 1)), None);
+        assert_eq!(Direction::from_name("UP"), Some(Direction::Up));
+        assert_eq!(Direction::from_name("sideways"), None);
+    }
+
+    #[test]
+    fn adjacent_tiles_stay_on_board() {
+        let count = |x, y| adjacent_tiles(5, 3, &Vector(x, y)).count();
+
+        assert_eq!(count(0, 0), 2);
+        assert_eq!(count(4, 2), 2);
+        assert_eq!(count(2, 0), 3);
+        assert_eq!(count(0, 1), 3);
+        assert_eq!(count(2, 1), 4);
+
+        for pos in adjacent_tiles(5, 3, &Vector(2, 1)) {
+            assert_eq!(manhattan_distance(&pos, &Vector(2, 1)), 1);
+        }
+    }
+
+    #[test]
+    fn neighbors8_stay_on_board() {
+        let count = |x, y| neighbors8(5, 3, &Vector(x, y)).count();
+
+        assert_eq!(count(0, 0), 3);
+        assert_eq!(count(4, 2), 3);
+        assert_eq!(count(2, 0), 5);
+        assert_eq!(count(0, 1), 5);
+        assert_eq!(count(2, 1), 8);
+
+        assert!(!neighbors8(5, 3, &Vector(2, 1)).any(|pos| pos == Vector(2, 1)));
+    }
+
+    #[test]
+    fn manhattan() {
+        assert_eq!(manhattan_distance(&Vector(0, 0), &Vector(0, 0)), 0);
+        assert_eq!(manhattan_distance(&Vector(1, 2), &Vector(4, 0)), 5);
+        assert_eq!(manhattan_distance(&Vector(-3, 2), &Vector(3, -2)), 10);
+    }
+
+    #[test]
+    fn flood_fill_regions() {
+        // a wall down the middle column of a 5x3 board
+        let open = |pos: &Vector| pos.0 != 2;
+
+        let left = flood_fill(5, 3, &Vector(0, 0), open);
+        assert_eq!(left.len(), 6);
+        assert!(left.iter().all(|pos| pos.0 < 2));
+
+        // starting inside the wall still counts the start, but goes nowhere else
+        assert_eq!(
+            flood_fill(5, 3, &Vector(2, 1), |pos| pos.0 == 2 && pos.1 != 1).len(),
+            3
+        );
+        assert_eq!(
+            flood_fill(5, 3, &Vector(2, 1), |_| false),
+            vec![Vector(2, 1)]
+        );
+        assert_eq!(flood_fill(5, 3, &Vector(0, 0), |_| true).len(), 15);
+        assert!(flood_fill(5, 3, &Vector(-1, 0), |_| true).is_empty());
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(wrap_position(5, 3, &Vector(2, 1)), Vector(2, 1));
+        assert_eq!(wrap_position(5, 3, &Vector(-1, 1)), Vector(4, 1));
+        assert_eq!(wrap_position(5, 3, &Vector(5, 1)), Vector(0, 1));
+        assert_eq!(wrap_position(5, 3, &Vector(2, -1)), Vector(2, 2));
+        assert_eq!(wrap_position(5, 3, &Vector(2, 3)), Vector(2, 0));
+        assert_eq!(wrap_position(5, 3, &Vector(-6, 7)), Vector(4, 1));
+    }
+
+    #[test]
+    fn hex_grid() {
+        let hex = Grid::Hex.geometry();
+
+        for direction in Direction::HEX {
+            let step = direction.to_vector();
+            assert_eq!(hex.distance(&step, &Vector(0, 0)), 1);
+            assert_eq!(&step + &direction.opposite().to_vector(), Vector(0, 0));
+            assert_eq!(Direction::from_vector(&step), Some(direction.clone()));
+            assert_eq!(Direction::from_name(direction.name()), Some(direction));
+        }
+        assert_eq!(hex.distance(&Vector(0, 0), &Vector(3, -3)), 3);
+        assert_eq!(hex.distance(&Vector(0, 0), &Vector(3, 3)), 6);
+
+        // six around the middle, fewer on the edges and fewest in the sharp corners
+        let count = |x, y| hex.neighbors(5, 3, &Vector(x, y)).len();
+        assert_eq!(count(2, 1), 6);
+        assert_eq!(count(0, 0), 2);
+        assert_eq!(count(4, 0), 3);
+        assert_eq!(count(0, 2), 3);
+        assert_eq!(count(4, 2), 2);
+        assert_eq!(
+            Grid::Square.geometry().neighbors(5, 3, &Vector(2, 1)).len(),
+            4
+        );
+
+        // a diagonal wall has gaps in it that a square board doesn't
+        let open = |pos: &Vector| pos.0 != pos.1;
+        assert_eq!(flood_fill(5, 3, &Vector(0, 2), open).len(), 3);
+        assert_eq!(hex.flood_fill(5, 3, &Vector(0, 2), &open).len(), 12);
+
+        // the four keys steer towards the side the snake's already going
+        assert_eq!(
+            hex.steer(Direction::Up, &Direction::Right),
+            Direction::UpRight
+        );
+        assert_eq!(hex.steer(Direction::Up, &Direction::Left), Direction::Up);
+        assert_eq!(
+            hex.steer(Direction::Down, &Direction::Left),
+            Direction::DownLeft
+        );
+        assert_eq!(
+            hex.steer(Direction::Down, &Direction::UpRight),
+            Direction::Down
+        );
+        assert_eq!(hex.steer(Direction::Left, &Direction::Up), Direction::Left);
+
+        assert_eq!(hex.degrees(&Direction::Right), 90);
+        assert_eq!(hex.degrees(&Direction::Up), 330);
+        assert_eq!(hex.tile_offset(&Vector(1, 2)), (2.0, 1.5));
+        assert_eq!(hex.extent(5, 3), (6.0, 2.5));
+
+        for grid in [Grid::Square, Grid::Hex] {
+            assert_eq!(Grid::from_name(grid.name()), Some(grid));
+        }
+    }
+
+    #[test]
+    fn mirroring() {
+        assert_eq!(mirror_position(5, 3, &Vector(0, 0)), Vector(4, 2));
+        assert_eq!(mirror_position(5, 3, &Vector(2, 1)), Vector(2, 1));
+        assert_eq!(mirror_position(6, 4, &Vector(1, 3)), Vector(4, 0));
+
+        for y in 0..4 {
+            for x in 0..6 {
+                let pos = Vector(x, y);
+                let mirrored = mirror_position(6, 4, &pos);
+                assert!(is_within_board(6, 4, &mirrored));
+                assert_eq!(mirror_position(6, 4, &mirrored), pos);
+            }
+        }
+    }
+}
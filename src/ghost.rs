@@ -0,0 +1,241 @@
+use crate::geometry::Grid;
+use crate::geometry::Vector;
+use crate::mode::GameMode;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+
+// The best run on each board size is saved as the path its head took, one tile per tick. Later
+// games on the same board show where that run was at the same tick as a faint trail, to race
+// against. Only solo games on the default empty board count, since levels and versus play out
+// too differently to compare.
+
+const GHOST_PREFIX: &str = "slake_ghost:";
+
+// how many of the ghost's tiles are shown at once
+pub const TRAIL_LENGTH: usize = 6;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GhostRun {
+    pub score: usize,
+    // where the head was after each tick, starting with the first
+    pub path: Vec<Vector>,
+}
+
+impl GhostRun {
+    // Saved as `score=...` and `path=x,y;x,y;...` lines. Unlike the settings, a broken run is
+    // thrown away entirely, since half a path isn't worth racing.
+    pub fn parse(source: &str) -> Option<GhostRun> {
+        let mut score = None;
+        let mut path = None;
+
+        for line in source.lines() {
+            match line.split_once('=')? {
+                ("score", value) => score = value.parse().ok(),
+                ("path", "") => path = Some(vec![]),
+                ("path", value) => {
+                    path = value
+                        .split(';')
+                        .map(|pos| {
+                            let (x, y) = pos.split_once(',')?;
+                            Some(Vector(x.parse().ok()?, y.parse().ok()?))
+                        })
+                        .collect()
+                }
+                _ => (),
+            }
+        }
+
+        Some(GhostRun {
+            score: score?,
+            path: path?,
+        })
+    }
+
+    pub fn serialize(&self) -> String {
+        let path = self
+            .path
+            .iter()
+            .map(|Vector(x, y)| format!("{x},{y}"))
+            .collect::<Vec<_>>();
+
+        format!("score={}\npath={}\n", self.score, path.join(";"))
+    }
+
+    // the last few tiles the ghost's head was on, up to and including `tick`
+    pub fn trail(&self, tick: usize) -> &[Vector] {
+        let end = tick.min(self.path.len());
+        &self.path[end.saturating_sub(TRAIL_LENGTH)..end]
+    }
+}
+
+#[derive(Default)]
+pub struct GhostRecorder {
+    path: Vec<Vector>,
+}
+
+impl GhostRecorder {
+    pub fn start(&mut self) {
+        self.path.clear();
+    }
+
+    // call after every tick, it only takes one tile per tick however often it's called
+    pub fn record(&mut self, game: &SnakeGame) {
+        if !game.is_game_over() && game.ticks > self.path.len() {
+            self.path.push(game.snakes()[0].head().clone());
+        }
+    }
+
+    pub fn finish(&mut self, score: usize) -> GhostRun {
+        GhostRun {
+            score,
+            path: std::mem::take(&mut self.path),
+        }
+    }
+}
+
+// whether this game gets a ghost, and could become one
+pub fn counts(game: &SnakeGame) -> bool {
+    game.level().is_none() && !game.is_versus()
+}
+
+// each mode and grid races its own ghost. classic on squares keeps the key from before there
+// were either
+fn board_key(game: &SnakeGame) -> String {
+    let mut key = format!(
+        "{GHOST_PREFIX}{}x{}:{}",
+        game.width,
+        game.height,
+        game.topology.name()
+    );
+    if game.grid != Grid::Square {
+        key += &format!(":{}", game.grid.name());
+    }
+
+    match game.mode() {
+        GameMode::Classic => key,
+        // yesterday's run was on another board
+        GameMode::Daily => format!("{key}:daily:{}", crate::daily::today().date),
+        mode => format!("{key}:{}", mode.name()),
+    }
+}
+
+thread_local! {
+    static RECORDER: RefCell<GhostRecorder> = RefCell::new(GhostRecorder::default());
+
+    // the best run for the board it was last looked up for, so storage is only read when the
+    // board changes
+    static BEST_RUN: RefCell<Option<(String, Option<GhostRun>)>> = const { RefCell::new(None) };
+}
+
+fn with_best_run<T>(game: &SnakeGame, f: impl FnOnce(&mut Option<GhostRun>) -> T) -> T {
+    let key = board_key(game);
+
+    BEST_RUN.with(|best_run| {
+        let mut best_run = best_run.borrow_mut();
+
+        if best_run.as_ref().map(|(old_key, _)| old_key) != Some(&key) {
+            let saved = crate::local_storage()
+                .and_then(|storage| storage.get_item(&key).ok().flatten())
+                .and_then(|saved| GhostRun::parse(&saved));
+            *best_run = Some((key, saved));
+        }
+
+        f(&mut best_run.as_mut().unwrap().1)
+    })
+}
+
+pub fn start() {
+    RECORDER.with(|recorder| recorder.borrow_mut().start());
+}
+
+pub fn record(game: &SnakeGame) {
+    RECORDER.with(|recorder| recorder.borrow_mut().record(game));
+}
+
+// keeps the run that just ended if it's the new best
+pub fn finish(game: &SnakeGame) {
+    let run = RECORDER.with(|recorder| recorder.borrow_mut().finish(game.score));
+
+    if !counts(game) {
+        return;
+    }
+
+    with_best_run(game, |best_run| {
+        if best_run
+            .as_ref()
+            .is_some_and(|best_run| best_run.score >= run.score)
+        {
+            return;
+        }
+
+        if let Some(storage) = crate::local_storage() {
+            // the ghost is just for fun, so failing to save it doesn't matter much
+            let _ = storage.set_item(&board_key(game), &run.serialize());
+        }
+
+        *best_run = Some(run);
+    });
+}
+
+// where the best run's trail should be drawn right now
+pub fn trail(game: &SnakeGame) -> Vec<Vector> {
+    if !counts(game) || game.is_game_over() {
+        return vec![];
+    }
+
+    with_best_run(game, |best_run| match best_run {
+        Some(best_run) => best_run.trail(game.ticks).to_vec(),
+        None => vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let run = GhostRun {
+            score: 12,
+            path: vec![Vector(3, 4), Vector(2, 4), Vector(2, 3)],
+        };
+
+        assert_eq!(GhostRun::parse(&run.serialize()), Some(run));
+        assert_eq!(
+            GhostRun::parse("score=0\npath="),
+            Some(GhostRun {
+                score: 0,
+                path: vec![]
+            })
+        );
+        assert_eq!(GhostRun::parse("score=3\npath=1,2;banana"), None);
+        assert_eq!(GhostRun::parse("path=1,2"), None);
+    }
+
+    #[test]
+    fn recording() {
+        let mut game = SnakeGame::new(21, 15);
+        let mut recorder = GhostRecorder::default();
+
+        for _i in 0..3 {
+            game.tick();
+            recorder.record(&game);
+            // extra calls in the same tick don't count
+            recorder.record(&game);
+        }
+
+        let run = recorder.finish(game.score);
+        assert_eq!(run.path, vec![Vector(18, 7), Vector(17, 7), Vector(16, 7)]);
+
+        assert_eq!(run.trail(0), &[] as &[Vector]);
+        assert_eq!(run.trail(2), &run.path[..2]);
+        assert_eq!(run.trail(100), &run.path[..]);
+
+        let long_run = GhostRun {
+            score: 0,
+            path: (0..20).map(|x| Vector(x, 0)).collect(),
+        };
+        assert_eq!(long_run.trail(10), &long_run.path[4..10]);
+    }
+}
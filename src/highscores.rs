@@ -0,0 +1,520 @@
+use crate::error::SlakeError;
+use crate::mode::GameMode;
+use crate::timer;
+
+use std::cell::Cell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement, HtmlInputElement, KeyboardEvent};
+
+// The best solo runs on this browser, each with a name typed in arcade style when it makes the
+// table. They're shown once the game is over and along with the stats, and saved in local
+// storage like them. Every game mode has a table of its own, and each score remembers the day
+// it was set and the size of the board it was set on, since that changes what's possible. Races
+// keep times instead, in milliseconds of play, with the fastest first.
+
+const HIGH_SCORES_KEY: &str = "slake_high_scores";
+
+// how many scores the table keeps
+pub const TABLE_LENGTH: usize = 10;
+
+pub const MIN_NAME_LENGTH: usize = 3;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: usize,
+    // width and height. scores from before they were kept don't have one
+    pub board: Option<(isize, isize)>,
+    // like "2026-10-14", in the player's own time zone. none on older scores either
+    pub date: Option<String>,
+}
+
+impl ScoreEntry {
+    fn board_text(&self) -> String {
+        self.board.map_or("-".to_string(), |(width, height)| {
+            format!("{width}x{height}")
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct HighScores {
+    // highest first, or fastest first for races
+    pub entries: Vec<ScoreEntry>,
+    // whether the scores are race times, see `parse_times`
+    pub fastest_first: bool,
+    // filled in the next time a name gets asked for
+    pub last_name: String,
+}
+
+impl HighScores {
+    fn beats(&self, score: usize, other: usize) -> bool {
+        match self.fastest_first {
+            true => score < other,
+            false => score > other,
+        }
+    }
+
+    // Where a score would place from 0, if it makes the table at all. Nothing scores zero.
+    pub fn place_for(&self, score: usize) -> Option<usize> {
+        let place = self
+            .entries
+            .iter()
+            .position(|entry| self.beats(score, entry.score))
+            .unwrap_or(self.entries.len());
+
+        (score > 0 && place < TABLE_LENGTH).then_some(place)
+    }
+
+    pub fn add(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let place = self.place_for(entry.score)?;
+
+        self.last_name = entry.name.clone();
+        self.entries.insert(place, entry);
+        self.entries.truncate(TABLE_LENGTH);
+
+        Some(place)
+    }
+
+    // Saved as `score=12,21x15,2026-10-14,NAME` lines, plus `last_name=NAME`. Older saves have
+    // `score=12,NAME`, which still load. Broken lines are left out.
+    pub fn parse(source: &str) -> HighScores {
+        HighScores::parse_ranked(source, false)
+    }
+
+    // the same, for a race's times
+    pub fn parse_times(source: &str) -> HighScores {
+        HighScores::parse_ranked(source, true)
+    }
+
+    fn parse_ranked(source: &str, fastest_first: bool) -> HighScores {
+        let mut high_scores = HighScores {
+            fastest_first,
+            ..HighScores::default()
+        };
+
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            match key.trim() {
+                "score" => {
+                    high_scores.entries.extend(parse_entry(value));
+                }
+                "last_name" => high_scores.last_name = clean_name(value).unwrap_or_default(),
+                _ => (),
+            }
+        }
+
+        match fastest_first {
+            true => high_scores.entries.sort_by_key(|entry| entry.score),
+            false => high_scores
+                .entries
+                .sort_by_key(|entry| std::cmp::Reverse(entry.score)),
+        }
+        high_scores.entries.truncate(TABLE_LENGTH);
+
+        high_scores
+    }
+
+    // a score the way the table shows it
+    pub fn score_text(&self, score: usize) -> String {
+        match self.fastest_first {
+            true => timer::race_text(score),
+            false => score.to_string(),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut source = format!("last_name={}\n", self.last_name);
+
+        for entry in &self.entries {
+            let board = entry
+                .board
+                .map_or(String::new(), |(width, height)| format!("{width}x{height}"));
+            let date = entry.date.as_deref().unwrap_or_default();
+            source += &format!("score={},{board},{date},{}\n", entry.score, entry.name);
+        }
+
+        source
+    }
+}
+
+// names can't have commas in them, so whatever's after the last one is the name
+fn parse_entry(value: &str) -> Option<ScoreEntry> {
+    let fields = value.splitn(4, ',').collect::<Vec<_>>();
+
+    let (score, board, date, name) = match fields[..] {
+        [score, name] => (score, "", "", name),
+        [score, board, date, name] => (score, board, date, name),
+        _ => return None,
+    };
+
+    let board = match board.trim() {
+        "" => None,
+        board => {
+            let (width, height) = board.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        }
+    };
+    let date = Some(date.trim().to_string()).filter(|date| !date.is_empty());
+
+    Some(ScoreEntry {
+        name: clean_name(name).ok()?,
+        score: score.trim().parse().ok()?,
+        board,
+        date,
+    })
+}
+
+// today, the way scores keep it
+fn today() -> String {
+    let now = js_sys::Date::new_0();
+    format!(
+        "{}-{:02}-{:02}",
+        now.get_full_year(),
+        now.get_month() + 1,
+        now.get_date()
+    )
+}
+
+// Names are 3 to 12 letters, numbers, and spaces, in capitals like an arcade cabinet.
+pub fn clean_name(name: &str) -> Result<String, String> {
+    let name = name.trim().to_uppercase();
+    let length = name.chars().count();
+
+    if !(MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&length) {
+        return Err(format!(
+            "names are {MIN_NAME_LENGTH} to {MAX_NAME_LENGTH} characters"
+        ));
+    }
+
+    if !name
+        .chars()
+        .all(|character| character.is_alphanumeric() || character == ' ')
+    {
+        return Err("only letters, numbers, and spaces".to_string());
+    }
+
+    Ok(name)
+}
+
+// a score waiting on a name, with the mode and board size it was set on
+#[derive(Clone, Copy)]
+struct PendingScore {
+    score: usize,
+    mode: GameMode,
+    board: (isize, isize),
+}
+
+thread_local! {
+    // while the name entry is showing
+    static PENDING_SCORE: Cell<Option<PendingScore>> = const { Cell::new(None) };
+}
+
+// classic keeps the key from before there were modes
+fn storage_key(mode: GameMode) -> String {
+    match mode {
+        GameMode::Classic => HIGH_SCORES_KEY.to_string(),
+        _ => format!("{HIGH_SCORES_KEY}_{}", mode.name()),
+    }
+}
+
+pub fn load(mode: GameMode) -> HighScores {
+    crate::local_storage()
+        .and_then(|storage| storage.get_item(&storage_key(mode)).ok().flatten())
+        .map(|saved| match mode.fruit_target() {
+            Some(_) => HighScores::parse_times(&saved),
+            None => HighScores::parse(&saved),
+        })
+        .unwrap_or(HighScores {
+            fastest_first: mode.fruit_target().is_some(),
+            ..HighScores::default()
+        })
+}
+
+fn save(mode: GameMode, high_scores: &HighScores) {
+    // the banner doesn't get in the way of the game over, see `error::report`
+    if let Err(error) = crate::store(&storage_key(mode), &high_scores.serialize()) {
+        crate::error::report(error, None);
+    }
+}
+
+// while a name is being typed in, the game's keys are all turned off
+pub fn is_entering_name() -> bool {
+    PENDING_SCORE.with(|pending| pending.get()).is_some()
+}
+
+// Shows the table once a run's over, and asks for a name first if the run made it. For a race,
+// `score` is its time, see `SnakeGame::race_ms`.
+pub fn finish(score: usize, mode: GameMode, board: (isize, isize)) -> Result<(), SlakeError> {
+    let high_scores = load(mode);
+    show_table(mode, None)?;
+
+    let Some(place) = high_scores.place_for(score) else {
+        return Ok(());
+    };
+
+    PENDING_SCORE.with(|pending| pending.set(Some(PendingScore { score, mode, board })));
+
+    crate::get_element::<HtmlElement>("name_entry_title")?.set_inner_text(&format!(
+        "New high score, #{}: {}",
+        place + 1,
+        high_scores.score_text(score)
+    ));
+    crate::get_element::<HtmlElement>("name_entry_error")?.set_inner_text("");
+
+    let input = crate::get_element::<HtmlInputElement>("name_entry_input")?;
+    input.set_value(&high_scores.last_name);
+
+    show_entry(true)?;
+    let _ = input.focus();
+    input.select();
+    Ok(())
+}
+
+fn show_entry(visible: bool) -> Result<(), SlakeError> {
+    crate::get_element::<HtmlElement>("name_entry")?
+        .style()
+        .set_property("display", if visible { "block" } else { "none" })?;
+    Ok(())
+}
+
+fn submit_name() -> Result<(), SlakeError> {
+    let Some(PendingScore { score, mode, board }) = PENDING_SCORE.with(|pending| pending.get())
+    else {
+        return Ok(());
+    };
+
+    let name = crate::get_element::<HtmlInputElement>("name_entry_input")?.value();
+
+    match clean_name(&name) {
+        Ok(name) => {
+            let mut high_scores = load(mode);
+            let place = high_scores.add(ScoreEntry {
+                name,
+                score,
+                board: Some(board),
+                date: Some(today()),
+            });
+            save(mode, &high_scores);
+
+            close_entry()?;
+            show_table(mode, place)?;
+            crate::stats::refresh_panel()?;
+        }
+        Err(error) => crate::get_element::<HtmlElement>("name_entry_error")?.set_inner_text(&error),
+    }
+    Ok(())
+}
+
+fn close_entry() -> Result<(), SlakeError> {
+    PENDING_SCORE.with(|pending| pending.set(None));
+    show_entry(false)?;
+
+    // so the game's keys work again straight away
+    let _ = crate::get_element::<HtmlElement>("name_entry_input")?.blur();
+    Ok(())
+}
+
+// The table for the stats panel and the game over screen, with `highlight` in bold. Names are
+// checked by `clean_name`, so they're safe as HTML.
+pub fn table_html(mode: GameMode, highlight: Option<usize>) -> String {
+    let high_scores = load(mode);
+    let title = match mode {
+        GameMode::Classic => "High scores".to_string(),
+        _ => format!("High scores, {}", mode.label()),
+    };
+
+    if high_scores.entries.is_empty() {
+        return format!("<p>{title}: none yet.</p>");
+    }
+
+    let rows = high_scores
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(place, entry)| {
+            let row = format!(
+                "<td>{}.</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+                place + 1,
+                entry.name,
+                high_scores.score_text(entry.score),
+                entry.board_text(),
+                entry.date.as_deref().unwrap_or("-")
+            );
+            match Some(place) == highlight {
+                true => format!(r#"<tr style="font-weight: bold">{row}</tr>"#),
+                false => format!("<tr>{row}</tr>"),
+            }
+        })
+        .collect::<String>();
+
+    format!(
+        "<table><tr><th></th><th>{title}</th><th></th><th>Board</th><th>Date</th></tr>{rows}</table>"
+    )
+}
+
+fn show_table(mode: GameMode, highlight: Option<usize>) -> Result<(), SlakeError> {
+    let panel_element = crate::get_element::<HtmlElement>("local_scores")?;
+    panel_element.set_inner_html(&table_html(mode, highlight));
+    panel_element.style().set_property("display", "block")?;
+    Ok(())
+}
+
+// when the next run starts
+pub fn hide_table() -> Result<(), SlakeError> {
+    crate::get_element::<HtmlElement>("local_scores")?
+        .style()
+        .set_property("display", "none")?;
+    Ok(())
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("name_entry");
+    panel_element.set_class_name("settings name_entry");
+    panel_element.style().set_property("display", "none")?;
+
+    panel_element.set_inner_html(&format!(
+        r#"<div id="name_entry_title"></div>
+<label>Your name <input id="name_entry_input" type="text" minlength="{MIN_NAME_LENGTH}" maxlength="{MAX_NAME_LENGTH}"></label>
+<div id="name_entry_error"></div>
+<div id="name_entry_save" class="button">Save</div>
+<div id="name_entry_skip" class="button">Skip</div>"#
+    ));
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    let table_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    table_element.set_id("local_scores");
+    table_element.set_class_name("leaderboard");
+    table_element.style().set_property("display", "none")?;
+
+    crate::error::body()?.append_child(&table_element)?;
+
+    let handle_save =
+        Closure::wrap(Box::new(|| crate::error::report_failure(submit_name())) as Box<dyn FnMut()>);
+    let handle_skip =
+        Closure::wrap(Box::new(|| crate::error::report_failure(close_entry())) as Box<dyn FnMut()>);
+
+    let handle_keydown = Closure::wrap(Box::new(|event: KeyboardEvent| match &event.key()[..] {
+        "Enter" => crate::error::report_failure(submit_name()),
+        "Escape" => crate::error::report_failure(close_entry()),
+        _ => (),
+    }) as Box<dyn FnMut(KeyboardEvent)>);
+
+    crate::get_element::<HtmlElement>("name_entry_save")?
+        .set_onclick(Some(handle_save.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("name_entry_skip")?
+        .set_onclick(Some(handle_skip.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("name_entry_input")?
+        .set_onkeydown(Some(handle_keydown.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither are its handlers
+    handle_save.forget();
+    handle_skip.forget();
+    handle_keydown.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.place_for(0), None);
+
+        let entry = |name: &str, score| ScoreEntry {
+            name: name.to_string(),
+            score,
+            board: Some((21, 15)),
+            date: Some("2026-10-14".to_string()),
+        };
+
+        for score in 1..=12 {
+            high_scores.add(entry("AAA", score));
+        }
+        assert_eq!(high_scores.entries.len(), TABLE_LENGTH);
+        assert_eq!(high_scores.entries[0].score, 12);
+        // ties go below the score that got there first
+        assert_eq!(high_scores.place_for(3), None);
+        assert_eq!(high_scores.place_for(4), Some(9));
+
+        assert_eq!(high_scores.add(entry("SLINKY", 20)), Some(0));
+        assert_eq!(high_scores.last_name, "SLINKY");
+
+        assert_eq!(HighScores::parse(&high_scores.serialize()), high_scores);
+
+        // out of order or broken lines don't spoil the rest
+        let parsed = HighScores::parse("score=4,BOB\nscore=lots,ANN\nscore=9,X\nscore=7,SUE");
+        let names = parsed
+            .entries
+            .iter()
+            .map(|entry| &entry.name[..])
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["SUE", "BOB"]);
+
+        // scores saved before boards and dates were kept
+        let parsed =
+            HighScores::parse("score=4,BOB\nscore=6,9x9,2026-01-02,ANN\nscore=5,9by9,,SUE");
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(
+            parsed.entries[0],
+            ScoreEntry {
+                board: Some((9, 9)),
+                date: Some("2026-01-02".to_string()),
+                ..entry("ANN", 6)
+            }
+        );
+        assert_eq!(
+            parsed.entries[1],
+            ScoreEntry {
+                board: None,
+                date: None,
+                ..entry("BOB", 4)
+            }
+        );
+        assert_eq!(HighScores::parse(&parsed.serialize()), parsed);
+    }
+
+    #[test]
+    fn race_times() {
+        let mut times = HighScores::parse_times("score=61500,BOB\nscore=48200,ANN");
+        assert_eq!(times.entries[0].name, "ANN");
+        assert_eq!(times.place_for(50_000), Some(1));
+        assert_eq!(times.place_for(0), None);
+
+        times.add(ScoreEntry {
+            name: "SUE".to_string(),
+            score: 40_000,
+            board: None,
+            date: None,
+        });
+        assert_eq!(times.entries[0].name, "SUE");
+        assert_eq!(times.score_text(40_000), "0:40.0");
+        assert_eq!(HighScores::parse_times(&times.serialize()), times);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(clean_name("  jo jo "), Ok("JO JO".to_string()));
+        assert_eq!(clean_name("Ünïcode1"), Ok("ÜNÏCODE1".to_string()));
+        assert!(clean_name("jo").is_err());
+        assert!(clean_name("thirteen char").is_err());
+        assert!(clean_name("<b>hi</b>").is_err());
+    }
+}
@@ -0,0 +1,295 @@
+// Where steering comes from, and which player it goes to. In versus, each player picks an input
+// source in the settings. Outside of versus there's only one player, so everything steers them.
+
+use crate::geometry::Direction;
+
+pub const MAX_PLAYERS: usize = 2;
+
+// how far a stick has to be pushed before it counts
+const STICK_DEADZONE: f64 = 0.5;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum InputSource {
+    Arrows,
+    Wasd,
+    Ijkl,
+    // the browser's index for the gamepad, which stays the same while it's connected
+    Gamepad(u32),
+    // the on-screen d-pad
+    Touch,
+    // steering towards the pointer, see `mouse`
+    Mouse,
+}
+
+impl InputSource {
+    pub fn name(&self) -> String {
+        match self {
+            InputSource::Arrows => "arrows".to_string(),
+            InputSource::Wasd => "wasd".to_string(),
+            InputSource::Ijkl => "ijkl".to_string(),
+            InputSource::Gamepad(index) => format!("gamepad{index}"),
+            InputSource::Touch => "touch".to_string(),
+            InputSource::Mouse => "mouse".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<InputSource> {
+        match name {
+            "arrows" => Some(InputSource::Arrows),
+            "wasd" => Some(InputSource::Wasd),
+            "ijkl" => Some(InputSource::Ijkl),
+            "touch" => Some(InputSource::Touch),
+            "mouse" => Some(InputSource::Mouse),
+            _ => name
+                .strip_prefix("gamepad")
+                .and_then(|index| index.parse().ok())
+                .map(InputSource::Gamepad),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            InputSource::Arrows => "Arrow keys".to_string(),
+            InputSource::Wasd => "WASD".to_string(),
+            InputSource::Ijkl => "IJKL".to_string(),
+            InputSource::Gamepad(index) => format!("Gamepad {}", index + 1),
+            InputSource::Touch => "On-screen arrows".to_string(),
+            InputSource::Mouse => "Mouse".to_string(),
+        }
+    }
+
+    // the keyboard layouts besides the arrows, whose keys usually have other jobs too
+    pub fn is_extra_layout(&self) -> bool {
+        matches!(self, InputSource::Wasd | InputSource::Ijkl)
+    }
+}
+
+// the player steering with `source`, if anyone is
+pub fn player_for(inputs: &[InputSource], source: InputSource) -> Option<usize> {
+    inputs.iter().position(|input| *input == source)
+}
+
+// What to offer in the settings: everything that's always there, the gamepads that are
+// connected, and any gamepad someone already picked that isn't connected right now.
+pub fn choices(connected_gamepads: &[u32], inputs: &[InputSource]) -> Vec<InputSource> {
+    let mut choices = vec![
+        InputSource::Arrows,
+        InputSource::Wasd,
+        InputSource::Ijkl,
+        InputSource::Touch,
+        InputSource::Mouse,
+    ];
+
+    let gamepads = connected_gamepads
+        .iter()
+        .map(|index| InputSource::Gamepad(*index))
+        .chain(inputs.iter().cloned());
+
+    for source in gamepads {
+        if !choices.contains(&source) {
+            choices.push(source);
+        }
+    }
+
+    choices
+}
+
+// Counts ticks since anyone last steered or pressed anything, so the game can pause itself when
+// the player seems to have walked away.
+#[derive(Default, Debug)]
+pub struct IdleWatch {
+    ticks: usize,
+}
+
+impl IdleWatch {
+    pub fn input(&mut self) {
+        self.ticks = 0;
+    }
+
+    // Counts a tick of the game running. Returns whether `limit` ticks have gone by without any
+    // input, where a limit of 0 never runs out.
+    pub fn tick(&mut self, limit: usize) -> bool {
+        self.ticks += 1;
+        limit > 0 && self.ticks >= limit
+    }
+}
+
+// The steering keys being held down, newest first, so one that's held can keep turning the snake
+// every tick rather than only when it went down. The browser's own repeats of a held key don't
+// go through here, so they don't pile up turns either.
+#[derive(Default, Debug)]
+pub struct HeldKeys {
+    keys: Vec<(String, InputSource, Direction)>,
+}
+
+impl HeldKeys {
+    // a key that was already held, with its let go missed, just moves to the front
+    pub fn press(&mut self, key: &str, source: InputSource, direction: Direction) {
+        self.release(key);
+        self.keys.insert(0, (key.to_string(), source, direction));
+    }
+
+    pub fn release(&mut self, key: &str) {
+        self.keys.retain(|(held, ..)| held != key);
+    }
+
+    // for when the page loses the keyboard, and won't hear about anything being let go
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    // what to steer with before a tick, the newest key first
+    pub fn steering(&self) -> Vec<(InputSource, Direction)> {
+        self.keys
+            .iter()
+            .map(|(_, source, direction)| (*source, direction.clone()))
+            .collect()
+    }
+}
+
+// Reads a gamepad in the standard layout, where buttons 12 to 15 are the d-pad. `dpad` is
+// pressed-ness in up, down, left, right order. The d-pad wins over the stick, and the stick
+// goes whichever way it's pushed furthest.
+pub fn gamepad_direction(axes: &[f64], dpad: [bool; 4]) -> Option<Direction> {
+    let buttons = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    if let Some(index) = dpad.iter().position(|pressed| *pressed) {
+        return Some(buttons[index].clone());
+    }
+
+    let (x, y) = match axes {
+        [x, y, ..] => (*x, *y),
+        _ => return None,
+    };
+
+    if x.abs().max(y.abs()) < STICK_DEADZONE {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x < 0.0 {
+            Direction::Left
+        } else {
+            Direction::Right
+        })
+    } else {
+        Some(if y < 0.0 {
+            Direction::Up
+        } else {
+            Direction::Down
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::KeyBindings;
+
+    #[test]
+    fn names() {
+        for source in [
+            InputSource::Arrows,
+            InputSource::Wasd,
+            InputSource::Ijkl,
+            InputSource::Gamepad(0),
+            InputSource::Gamepad(3),
+            InputSource::Touch,
+            InputSource::Mouse,
+        ] {
+            assert_eq!(InputSource::from_name(&source.name()), Some(source));
+        }
+
+        assert_eq!(InputSource::from_name("gamepad"), None);
+        assert_eq!(InputSource::from_name("joystick"), None);
+    }
+
+    #[test]
+    fn routing() {
+        let inputs = [InputSource::Gamepad(1), InputSource::Arrows];
+
+        let key_bindings = KeyBindings::default();
+        let (source, direction) = key_bindings.actions_for("ArrowUp")[0].steering().unwrap();
+        assert_eq!(direction, Direction::Up);
+        assert_eq!(player_for(&inputs, source), Some(1));
+        assert_eq!(player_for(&inputs, InputSource::Gamepad(1)), Some(0));
+        assert_eq!(player_for(&inputs, InputSource::Wasd), None);
+        assert!(key_bindings.actions_for("q").is_empty());
+
+        assert_eq!(
+            choices(&[0], &inputs),
+            vec![
+                InputSource::Arrows,
+                InputSource::Wasd,
+                InputSource::Ijkl,
+                InputSource::Touch,
+                InputSource::Mouse,
+                InputSource::Gamepad(0),
+                InputSource::Gamepad(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn idle() {
+        let mut watch = IdleWatch::default();
+
+        assert!(!watch.tick(3));
+        assert!(!watch.tick(3));
+        assert!(watch.tick(3));
+
+        watch.input();
+        assert!(!watch.tick(3));
+        assert!(!(0..1000).any(|_| watch.tick(0)));
+    }
+
+    #[test]
+    fn held_keys() {
+        let mut held = HeldKeys::default();
+        held.press("ArrowUp", InputSource::Arrows, Direction::Up);
+        held.press("a", InputSource::Wasd, Direction::Left);
+        held.press("Numpad6", InputSource::Arrows, Direction::Right);
+        assert_eq!(
+            held.steering(),
+            vec![
+                (InputSource::Arrows, Direction::Right),
+                (InputSource::Wasd, Direction::Left),
+                (InputSource::Arrows, Direction::Up),
+            ]
+        );
+
+        // pressed again without being let go, since that got missed
+        held.press("ArrowUp", InputSource::Arrows, Direction::Up);
+        held.release("Numpad6");
+        assert_eq!(
+            held.steering(),
+            vec![
+                (InputSource::Arrows, Direction::Up),
+                (InputSource::Wasd, Direction::Left),
+            ]
+        );
+
+        held.clear();
+        assert!(held.steering().is_empty());
+    }
+
+    #[test]
+    fn gamepads() {
+        let none = [false; 4];
+
+        assert_eq!(gamepad_direction(&[], none), None);
+        assert_eq!(gamepad_direction(&[0.2, -0.3], none), None);
+        assert_eq!(
+            gamepad_direction(&[0.9, -0.6], none),
+            Some(Direction::Right)
+        );
+        assert_eq!(gamepad_direction(&[0.1, -0.6], none), Some(Direction::Up));
+        assert_eq!(
+            gamepad_direction(&[0.9, 0.0], [false, true, false, false]),
+            Some(Direction::Down)
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use crate::geometry::Direction;
+use crate::keyboard;
+use crate::keys;
+use crate::keys::Action;
+use crate::random;
+use crate::render;
+use crate::render::DomBoard;
+use crate::renderer::HudState;
+use crate::renderer::Renderer;
+use crate::scheduler::Scheduler;
+use crate::settings::RenderStyle;
+use crate::settings::Settings;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlElement, KeyboardEvent};
+
+// Games of their own, each on a board in some element of the page, for pages that want more
+// than one at once, like a player next to a bot. Each has its own clock, its own random numbers
+// so it doesn't take any from the others, and only the keys pressed while its board has focus,
+// see `keyboard`.
+// They start out with the page's own settings, and don't save anything, keep stats, or put up
+// anything but the board and the score under it. The page's own game, the one everything else
+// works on, carries on the same as ever beside them.
+//
+//   const left = new SlakeInstance("left_board");
+//   left.start();
+//   left.set_direction("up");
+
+struct Instance {
+    game: SnakeGame,
+    settings: Settings,
+    random: random::Aside,
+    container: HtmlElement,
+    board: Option<DomBoard>,
+}
+
+impl Instance {
+    // `f` gets the game with its own random numbers swapped in
+    fn with_game<R>(&mut self, f: impl FnOnce(&mut SnakeGame) -> R) -> R {
+        random::swap(&mut self.random);
+        let result = f(&mut self.game);
+        random::swap(&mut self.random);
+        result
+    }
+
+    fn tick(&mut self) {
+        self.with_game(|game| {
+            game.tick();
+            // there's nothing here to play sounds or keep stats with them
+            game.take_events();
+        });
+        // a board that's been taken off the page isn't worth stopping over
+        let _ = self.draw();
+    }
+
+    fn overlay_text(&self) -> Option<String> {
+        let game = &self.game;
+        match game.game_over_reason {
+            Some(reason) => Some(format!("{reason}\npress space to restart")),
+            None if game.paused => Some("paused".to_string()),
+            None => game.countdown().map(|count| count.to_string()),
+        }
+    }
+
+    fn draw(&mut self) -> Result<(), JsValue> {
+        let (game, settings) = (&self.game, &self.settings);
+        if !self
+            .board
+            .as_ref()
+            .is_some_and(|board| board.fits(game, settings))
+        {
+            let rows = render::shown_rows(game);
+            self.board = Some(render::build_board(game, settings, rows, &self.container)?);
+        }
+
+        let hud = HudState {
+            info: render::info_text(game, settings.theme),
+            overlay: self.overlay_text(),
+            ..HudState::default()
+        };
+        let (game, settings) = (&self.game, &self.settings);
+        self.board.as_mut().unwrap().draw(
+            game,
+            render::board_tiles(game, &[], false, settings.reduced_motion),
+            &hud,
+        )
+    }
+
+    fn key(&mut self, event: &KeyboardEvent) -> bool {
+        let actions = self
+            .settings
+            .key_bindings
+            .actions_for(&keys::event_key(event));
+        let steering = actions.iter().find_map(|action| action.steering());
+        match steering {
+            Some((_, direction)) => self.game.change_player_direction(0, direction),
+            None if actions.contains(&Action::Restart) && self.game.is_game_over() => {
+                self.with_game(|game| game.restart());
+            }
+            None => return false,
+        }
+
+        let _ = self.draw();
+        true
+    }
+}
+
+#[wasm_bindgen]
+pub struct SlakeInstance {
+    instance: Rc<RefCell<Instance>>,
+    scheduler: Scheduler,
+    handle_keydown: Closure<dyn FnMut(KeyboardEvent)>,
+    handle_press: Closure<dyn FnMut()>,
+}
+
+#[wasm_bindgen]
+impl SlakeInstance {
+    // on the element with the id `container_id`, which has whatever was in it replaced
+    #[wasm_bindgen(constructor)]
+    pub fn new(container_id: &str) -> Result<SlakeInstance, JsValue> {
+        let container = crate::error::document()?
+            .get_element_by_id(container_id)
+            .ok_or_else(|| JsValue::from(format!("there's no element '{container_id}'")))?
+            .dyn_into::<HtmlElement>()?;
+
+        let mut settings = crate::SETTINGS.with(|settings| settings.borrow().clone());
+        // the canvas only draws the page's own board
+        if settings.render_style == RenderStyle::Smooth {
+            settings.render_style = RenderStyle::Squares;
+        }
+
+        let now = js_sys::Date::now() as u64;
+        let mut random = random::Aside::new([now as u16, (now >> 16) as u16]);
+        random::swap(&mut random);
+        let mut game = SnakeGame::new(21, 15);
+        game.tick_ms = settings.tick_ms.max(0) as usize;
+        game.paused = true;
+        random::swap(&mut random);
+
+        let handle_press = keyboard::make_focusable(&container)?;
+
+        let instance = Rc::new(RefCell::new(Instance {
+            game,
+            settings,
+            random,
+            container: container.clone(),
+            board: None,
+        }));
+        instance.borrow_mut().draw()?;
+
+        let scheduler = Scheduler::new({
+            let instance = instance.clone();
+            move || instance.borrow_mut().tick()
+        });
+
+        let handle_keydown = Closure::wrap(Box::new({
+            let instance = instance.clone();
+            move |event: KeyboardEvent| {
+                if instance.borrow_mut().key(&event) {
+                    // the page's own game and the page's scrolling stay out of it
+                    event.prevent_default();
+                    event.stop_propagation();
+                }
+            }
+        }) as Box<dyn FnMut(KeyboardEvent)>);
+        container
+            .add_event_listener_with_callback("keydown", handle_keydown.as_ref().unchecked_ref())?;
+
+        Ok(SlakeInstance {
+            instance,
+            scheduler,
+            handle_keydown,
+            handle_press,
+        })
+    }
+
+    // starts it ticking, after the countdown if it hasn't started yet
+    pub fn start(&self) {
+        let tick_ms = {
+            let mut instance = self.instance.borrow_mut();
+            if instance.game.paused {
+                instance.game.paused = false;
+                instance.game.start_countdown();
+            }
+            let _ = instance.draw();
+            instance.settings.tick_ms
+        };
+        self.scheduler.start(tick_ms);
+    }
+
+    pub fn stop(&self) {
+        self.scheduler.stop();
+        let mut instance = self.instance.borrow_mut();
+        instance.game.paused = true;
+        let _ = instance.draw();
+    }
+
+    pub fn restart(&self) {
+        let mut instance = self.instance.borrow_mut();
+        instance.with_game(|game| game.restart());
+        let _ = instance.draw();
+    }
+
+    // the same names as `slake_set_direction`
+    pub fn set_direction(&self, direction: &str) -> bool {
+        let Some(direction) = Direction::from_name(direction) else {
+            return false;
+        };
+
+        let mut instance = self.instance.borrow_mut();
+        instance.game.change_player_direction(0, direction);
+        true
+    }
+
+    // gives it the keys, the same as clicking on it
+    pub fn focus(&self) -> Result<(), JsValue> {
+        self.instance.borrow().container.focus()
+    }
+
+    pub fn score(&self) -> usize {
+        self.instance.borrow().game.score
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.instance.borrow().game.is_game_over()
+    }
+
+    // the same as `slake_get_state_json`, for this game
+    pub fn state_json(&self) -> String {
+        crate::api::state_json(&self.instance.borrow().game)
+    }
+}
+
+// `free` from the page, which takes the board away with it
+impl Drop for SlakeInstance {
+    fn drop(&mut self) {
+        let instance = self.instance.borrow();
+        let _ = instance.container.remove_event_listener_with_callback(
+            "keydown",
+            self.handle_keydown.as_ref().unchecked_ref(),
+        );
+        let _ = instance.container.remove_event_listener_with_callback(
+            "pointerdown",
+            self.handle_press.as_ref().unchecked_ref(),
+        );
+        instance.container.set_inner_html("");
+    }
+}
@@ -0,0 +1,66 @@
+use crate::error::SlakeError;
+
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlElement, Node};
+
+// Keys only go to a board while it has focus, so a page with the game somewhere in it keeps its
+// arrow keys for scrolling everywhere else. A board can be tabbed to, and clicking on it gives it
+// focus too, with an outline around it while it has it, see `.keyboard_scope` in the page's
+// stylesheet. The page's own board takes focus as soon as it's loaded, so on a page to itself
+// nothing's any different, unless the root element has `data-autofocus="false"`.
+
+const SCOPE_CLASS: &str = "keyboard_scope";
+
+// Lets `element` take focus, by tabbing or clicking. The click handler comes back to be kept for
+// as long as the element's in use.
+pub fn make_focusable(element: &HtmlElement) -> Result<Closure<dyn FnMut()>, SlakeError> {
+    element.set_tab_index(0);
+    element.class_list().add_1(SCOPE_CLASS)?;
+
+    // the tab index takes care of clicks on the element itself, but not always on whatever's
+    // covering it, like the overlay
+    let focused = element.clone();
+    let handle_press = Closure::wrap(Box::new(move || {
+        let _ = focused.focus();
+    }) as Box<dyn FnMut()>);
+    element
+        .add_event_listener_with_callback("pointerdown", handle_press.as_ref().unchecked_ref())?;
+
+    Ok(handle_press)
+}
+
+// whether what's focused is `element` or something in it
+pub fn has_focus(element: &HtmlElement) -> bool {
+    crate::error::document()
+        .ok()
+        .and_then(|document| document.active_element())
+        .is_some_and(|active| element.contains(Some(active.unchecked_ref::<Node>())))
+}
+
+fn root() -> Result<HtmlElement, SlakeError> {
+    crate::get_element::<HtmlElement>("root")
+}
+
+// the page's own board, which the key handler goes by
+pub fn board_has_focus() -> bool {
+    root().is_ok_and(|root| has_focus(&root))
+}
+
+// for when something of the game's that took focus away, like the settings panel, is done with
+pub fn focus_board() {
+    if let Ok(root) = root() {
+        let _ = root.focus();
+    }
+}
+
+pub fn listen() -> Result<(), SlakeError> {
+    let root = root()?;
+    // the page is never unloaded, as far as we're concerned
+    make_focusable(&root)?.forget();
+
+    if root.get_attribute("data-autofocus").as_deref() != Some("false") {
+        root.focus()?;
+    }
+
+    Ok(())
+}
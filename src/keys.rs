@@ -0,0 +1,586 @@
+use crate::error::SlakeError;
+use crate::geometry::Direction;
+use crate::input::InputSource;
+
+use std::cell::Cell;
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{Element, Event, HtmlElement, KeyboardEvent};
+
+// Which keys do what. Every action can have a few keys, saved along with the settings, and the
+// settings panel changes them by pressing whatever key you'd like instead. Keys are stored the
+// way the browser names them, so other keyboard layouts get the letters that are printed on
+// them. The numpad's are the exception, see `event_key`.
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    // the second keyboard layout, which only steers in versus
+    SecondUp,
+    SecondDown,
+    SecondLeft,
+    SecondRight,
+    // the third, which is IJKL
+    ThirdUp,
+    ThirdDown,
+    ThirdLeft,
+    ThirdRight,
+    Restart,
+    Online,
+    // see `peer`
+    Peer,
+    Level,
+    Border,
+    Hydra,
+    Versus,
+    Dpad,
+    BoardSize,
+    Theme,
+    Presentation,
+    Community,
+    Settings,
+    Stats,
+    Console,
+    // see `debug`
+    Debug,
+    Freeze,
+    Step,
+    SlowMotion,
+    Editor,
+    // see `stats::suggestion`
+    TakeSuggestion,
+    // see `Settings::last_played`
+    QuickStart,
+    // see `about`
+    About,
+    // see `rewind`
+    Rewind,
+    // see `export`
+    Share,
+    // see `replay`
+    Replay,
+    // see `layout`
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+}
+
+impl Action {
+    pub const ALL: [Action; 41] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::SecondUp,
+        Action::SecondDown,
+        Action::SecondLeft,
+        Action::SecondRight,
+        Action::ThirdUp,
+        Action::ThirdDown,
+        Action::ThirdLeft,
+        Action::ThirdRight,
+        Action::Restart,
+        Action::Online,
+        Action::Peer,
+        Action::Level,
+        Action::Border,
+        Action::Hydra,
+        Action::Versus,
+        Action::Dpad,
+        Action::BoardSize,
+        Action::Theme,
+        Action::Presentation,
+        Action::Community,
+        Action::Settings,
+        Action::Stats,
+        Action::Console,
+        Action::Debug,
+        Action::Freeze,
+        Action::Step,
+        Action::SlowMotion,
+        Action::Editor,
+        Action::TakeSuggestion,
+        Action::QuickStart,
+        Action::About,
+        Action::Rewind,
+        Action::Share,
+        Action::Replay,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomReset,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::SecondUp => "second_up",
+            Action::SecondDown => "second_down",
+            Action::SecondLeft => "second_left",
+            Action::SecondRight => "second_right",
+            Action::ThirdUp => "third_up",
+            Action::ThirdDown => "third_down",
+            Action::ThirdLeft => "third_left",
+            Action::ThirdRight => "third_right",
+            Action::Restart => "restart",
+            Action::Online => "online",
+            Action::Peer => "peer",
+            Action::Level => "level",
+            Action::Border => "border",
+            Action::Hydra => "hydra",
+            Action::Versus => "versus",
+            Action::Dpad => "dpad",
+            Action::BoardSize => "board_size",
+            Action::Theme => "theme",
+            Action::Presentation => "presentation",
+            Action::Community => "community",
+            Action::Settings => "settings",
+            Action::Stats => "stats",
+            Action::Console => "console",
+            Action::Debug => "debug",
+            Action::Freeze => "freeze",
+            Action::Step => "step",
+            Action::SlowMotion => "slow_motion",
+            Action::Editor => "editor",
+            Action::TakeSuggestion => "take_suggestion",
+            Action::QuickStart => "quick_start",
+            Action::About => "about",
+            Action::Rewind => "rewind",
+            Action::Share => "share",
+            Action::Replay => "replay",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ZoomReset => "zoom_reset",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::SecondUp => "Second player up",
+            Action::SecondDown => "Second player down",
+            Action::SecondLeft => "Second player left",
+            Action::SecondRight => "Second player right",
+            Action::ThirdUp => "Third layout up",
+            Action::ThirdDown => "Third layout down",
+            Action::ThirdLeft => "Third layout left",
+            Action::ThirdRight => "Third layout right",
+            Action::Restart => "Restart",
+            Action::Online => "Online versus",
+            Action::Peer => "Online without a server",
+            Action::Level => "Next level",
+            Action::Border => "Border",
+            Action::Hydra => "Hydra mode",
+            Action::Versus => "Versus mode",
+            Action::Dpad => "On-screen arrows",
+            Action::BoardSize => "Board size",
+            Action::Theme => "Theme",
+            Action::Presentation => "Presentation mode",
+            Action::Community => "Community levels",
+            Action::Settings => "Settings",
+            Action::Stats => "Stats",
+            Action::Console => "Dev console",
+            Action::Debug => "Debug overlay",
+            Action::Freeze => "Freeze the clock",
+            Action::Step => "One tick at a time",
+            Action::SlowMotion => "Slow motion",
+            Action::Editor => "Level editor",
+            Action::TakeSuggestion => "Take the suggested difficulty",
+            Action::QuickStart => "Play the last setup again",
+            Action::About => "What's new in the rules",
+            Action::Rewind => "Rewind, in practice mode",
+            Action::Share => "Share a picture of the board",
+            Action::Replay => "Copy a link to watch the last run",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ZoomReset => "Zoom to fit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["ArrowUp", "Numpad8"],
+            Action::Down => &["ArrowDown", "Numpad2"],
+            Action::Left => &["ArrowLeft", "Numpad4"],
+            Action::Right => &["ArrowRight", "Numpad6"],
+            Action::SecondUp => &["w"],
+            Action::SecondDown => &["s"],
+            Action::SecondLeft => &["a"],
+            Action::SecondRight => &["d"],
+            Action::ThirdUp => &["i"],
+            Action::ThirdDown => &["k"],
+            Action::ThirdLeft => &["j"],
+            Action::ThirdRight => &["l"],
+            Action::Restart => &["Space"],
+            Action::Online => &["n"],
+            Action::Peer => &["p"],
+            Action::Level => &["l"],
+            Action::Border => &["b"],
+            Action::Hydra => &["h"],
+            Action::Versus => &["v"],
+            Action::Dpad => &["d"],
+            Action::BoardSize => &["z"],
+            Action::Theme => &["k"],
+            Action::Presentation => &["F8"],
+            Action::Community => &["c"],
+            Action::Settings => &["s"],
+            Action::Stats => &["t"],
+            Action::Console => &["`"],
+            Action::Debug => &["F3"],
+            Action::Freeze => &["F9"],
+            Action::Step => &["F10"],
+            Action::SlowMotion => &["F7"],
+            Action::Editor => &["e"],
+            Action::TakeSuggestion => &["y"],
+            Action::QuickStart => &["r"],
+            Action::About => &["?"],
+            Action::Rewind => &["Backspace"],
+            Action::Share => &["x"],
+            Action::Replay => &["g"],
+            Action::ZoomIn => &["=", "+"],
+            Action::ZoomOut => &["-"],
+            Action::ZoomReset => &["0"],
+        }
+    }
+
+    // which keyboard layout steers this way, for the steering actions
+    pub fn steering(&self) -> Option<(InputSource, Direction)> {
+        let steering = match self {
+            Action::Up => (InputSource::Arrows, Direction::Up),
+            Action::Down => (InputSource::Arrows, Direction::Down),
+            Action::Left => (InputSource::Arrows, Direction::Left),
+            Action::Right => (InputSource::Arrows, Direction::Right),
+            Action::SecondUp => (InputSource::Wasd, Direction::Up),
+            Action::SecondDown => (InputSource::Wasd, Direction::Down),
+            Action::SecondLeft => (InputSource::Wasd, Direction::Left),
+            Action::SecondRight => (InputSource::Wasd, Direction::Right),
+            Action::ThirdUp => (InputSource::Ijkl, Direction::Up),
+            Action::ThirdDown => (InputSource::Ijkl, Direction::Down),
+            Action::ThirdLeft => (InputSource::Ijkl, Direction::Left),
+            Action::ThirdRight => (InputSource::Ijkl, Direction::Right),
+            _ => return None,
+        };
+
+        Some(steering)
+    }
+
+    // online games have to stay the same on both sides, so these wait until it's over
+    pub fn changes_game(&self) -> bool {
+        matches!(
+            self,
+            Action::Level
+                | Action::Hydra
+                | Action::Versus
+                | Action::BoardSize
+                | Action::Community
+                | Action::Settings
+                | Action::Editor
+                | Action::TakeSuggestion
+                | Action::QuickStart
+                | Action::Rewind
+        )
+    }
+
+    // The extra layouts' keys usually only steer in versus, so they can double up with the keys
+    // that do anything but steer, like WASD and IJKL do by default. Everything else gets a key to
+    // itself.
+    fn can_share_with(&self, other: &Action) -> bool {
+        let extra_layout = |action: &Action| {
+            action
+                .steering()
+                .is_some_and(|(source, _)| source.is_extra_layout())
+        };
+
+        (extra_layout(self) && other.steering().is_none())
+            || (extra_layout(other) && self.steering().is_none())
+    }
+}
+
+// Keys as they're saved: single characters in lowercase, so caps lock doesn't get in the way,
+// and "Space" for the space bar, which would otherwise disappear into the whitespace.
+pub fn normalize(key: &str) -> String {
+    match key {
+        " " => "Space".to_string(),
+        _ if key.chars().count() == 1 => key.to_lowercase(),
+        _ => key.to_string(),
+    }
+}
+
+// The key `event` is for. The numpad's keys go by where they are instead, like "Numpad8", so they
+// steer whether num lock's on or not, and don't get mixed up with the numbers along the top.
+pub fn event_key(event: &KeyboardEvent) -> String {
+    match event.location() == KeyboardEvent::DOM_KEY_LOCATION_NUMPAD {
+        true => event.code(),
+        false => event.key(),
+    }
+}
+
+pub fn key_label(key: &str) -> String {
+    match key {
+        "ArrowUp" => "↑".to_string(),
+        "ArrowDown" => "↓".to_string(),
+        "ArrowLeft" => "←".to_string(),
+        "ArrowRight" => "→".to_string(),
+        _ if key.starts_with("Numpad") => format!("Numpad {}", &key["Numpad".len()..]),
+        _ if key.chars().count() == 1 => key.to_uppercase(),
+        _ => key.to_string(),
+    }
+}
+
+// one list of keys for each of `Action::ALL`, in the same order
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct KeyBindings {
+    keys: Vec<Vec<String>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            keys: Action::ALL
+                .iter()
+                .map(|action| {
+                    action
+                        .default_keys()
+                        .iter()
+                        .map(|key| key.to_string())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    fn index(action: Action) -> usize {
+        Action::ALL
+            .iter()
+            .position(|other| *other == action)
+            .unwrap()
+    }
+
+    pub fn keys(&self, action: Action) -> &[String] {
+        &self.keys[KeyBindings::index(action)]
+    }
+
+    // everything a key does, in the order of `Action::ALL`
+    pub fn actions_for(&self, key: &str) -> Vec<Action> {
+        let key = normalize(key);
+
+        Action::ALL
+            .into_iter()
+            .filter(|action| self.keys(*action).contains(&key))
+            .collect()
+    }
+
+    // adds a key to an action, taking it away from anything it can't share with
+    pub fn bind(&mut self, action: Action, key: &str) {
+        let key = normalize(key);
+
+        for other in Action::ALL {
+            if other != action && !action.can_share_with(&other) {
+                self.keys[KeyBindings::index(other)].retain(|other_key| *other_key != key);
+            }
+        }
+
+        let keys = &mut self.keys[KeyBindings::index(action)];
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.keys[KeyBindings::index(action)].clear();
+    }
+
+    // for the settings, which save each action on a line like `keys_up=ArrowUp k`
+    pub fn set_keys(&mut self, action: Action, keys: &str) {
+        self.keys[KeyBindings::index(action)] = keys.split_whitespace().map(normalize).collect();
+    }
+
+    pub fn keys_string(&self, action: Action) -> String {
+        self.keys(action).join(" ")
+    }
+}
+
+thread_local! {
+    // what the settings panel shows, until it's applied
+    static DRAFT: RefCell<KeyBindings> = RefCell::new(KeyBindings::default());
+
+    // the action waiting for a key to be pressed
+    static CAPTURING: Cell<Option<Action>> = const { Cell::new(None) };
+}
+
+// While the panel's waiting for a key, the next one pressed is taken for the binding instead of
+// doing anything else. Escape gives up. Returns whether the key was taken.
+pub fn capture(event: &KeyboardEvent) -> bool {
+    let Some(action) = CAPTURING.with(|capturing| capturing.take()) else {
+        return false;
+    };
+
+    if event.key() != "Escape" {
+        DRAFT.with(|draft| draft.borrow_mut().bind(action, &event_key(event)));
+    }
+
+    event.prevent_default();
+    show_draft();
+    true
+}
+
+pub fn fill_panel(key_bindings: &KeyBindings) {
+    CAPTURING.with(|capturing| capturing.set(None));
+    DRAFT.with(|draft| *draft.borrow_mut() = key_bindings.clone());
+    show_draft();
+}
+
+pub fn read_panel() -> KeyBindings {
+    CAPTURING.with(|capturing| capturing.set(None));
+    DRAFT.with(|draft| draft.borrow().clone())
+}
+
+fn show_draft() {
+    let capturing = CAPTURING.with(|capturing| capturing.get());
+
+    let rows = DRAFT.with(|draft| {
+        let draft = draft.borrow();
+
+        Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = if capturing == Some(*action) {
+                    "press a key, or escape to stop".to_string()
+                } else if draft.keys(*action).is_empty() {
+                    "none".to_string()
+                } else {
+                    draft
+                        .keys(*action)
+                        .iter()
+                        .map(|key| key_label(key))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+
+                // a key can be anything the browser calls it, like "<"
+                format!(
+                    r#"<div class="key_binding">{}: {} <span class="button" data-action="{}" data-do="add">Add key</span> <span class="button" data-action="{}" data-do="clear">Clear</span></div>"#,
+                    action.label(),
+                    escape(&keys),
+                    action.name(),
+                    action.name(),
+                )
+            })
+            .collect::<String>()
+    });
+
+    match crate::get_element::<HtmlElement>("setting_keys") {
+        Ok(list) => list.set_inner_html(&format!(
+            r#"{rows}<span class="button" data-do="reset">Reset keys</span>"#
+        )),
+        Err(error) => crate::error::report(error, None),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// the buttons are redrawn all the time, so one handler on the list takes care of all of them
+pub fn build_panel() -> Result<(), SlakeError> {
+    let handle_click = Closure::wrap(Box::new(|event: Event| {
+        let Some(target) = event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok())
+        else {
+            return;
+        };
+
+        let action = target
+            .get_attribute("data-action")
+            .and_then(|name| Action::from_name(&name));
+
+        match (target.get_attribute("data-do").as_deref(), action) {
+            (Some("add"), Some(action)) => CAPTURING.with(|capturing| capturing.set(Some(action))),
+            (Some("clear"), Some(action)) => DRAFT.with(|draft| draft.borrow_mut().clear(action)),
+            (Some("reset"), _) => DRAFT.with(|draft| *draft.borrow_mut() = KeyBindings::default()),
+            _ => return,
+        }
+
+        show_draft();
+    }) as Box<dyn FnMut(Event)>);
+
+    crate::get_element::<HtmlElement>("setting_keys")?
+        .set_onclick(Some(handle_click.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither is its handler
+    handle_click.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings() {
+        let mut key_bindings = KeyBindings::default();
+        assert_eq!(key_bindings.actions_for(" "), [Action::Restart]);
+        assert_eq!(
+            key_bindings.actions_for("S"),
+            [Action::SecondDown, Action::Settings]
+        );
+
+        // vim keys take h, k, and l away from what they did before
+        for (action, key) in [
+            (Action::Left, "h"),
+            (Action::Down, "j"),
+            (Action::Up, "k"),
+            (Action::Right, "l"),
+        ] {
+            key_bindings.bind(action, key);
+        }
+        assert_eq!(
+            key_bindings.keys(Action::Left),
+            ["ArrowLeft", "Numpad4", "h"]
+        );
+        assert_eq!(key_bindings.actions_for("h"), [Action::Left]);
+        assert!(key_bindings.keys(Action::Hydra).is_empty());
+
+        // the second player can still share with the other jobs
+        key_bindings.bind(Action::SecondUp, "t");
+        assert_eq!(
+            key_bindings.actions_for("t"),
+            [Action::SecondUp, Action::Stats]
+        );
+        // but the first one can't
+        key_bindings.bind(Action::Up, "t");
+        assert_eq!(key_bindings.actions_for("t"), [Action::Up]);
+
+        key_bindings.clear(Action::Restart);
+        assert!(key_bindings.actions_for(" ").is_empty());
+
+        // IJKL shares with the theme and level keys, and the numpad steers like the arrows
+        let key_bindings = KeyBindings::default();
+        assert_eq!(
+            key_bindings.actions_for("k"),
+            [Action::ThirdDown, Action::Theme]
+        );
+        assert_eq!(key_bindings.actions_for("Numpad4"), [Action::Left]);
+        assert!(key_bindings.actions_for("4").is_empty());
+        assert_eq!(key_label("Numpad8"), "Numpad 8");
+    }
+}
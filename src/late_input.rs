@@ -0,0 +1,198 @@
+use crate::geometry::Direction;
+use crate::random;
+use crate::snake::SnakeGame;
+
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::{prelude::*, JsCast};
+
+// A turn that lands just after a tick was usually meant for it, and the player only pressed a
+// moment late. So each tick is kept off the screen for a moment after it runs, with the game as
+// it was before the tick kept aside. A turn that comes in during that moment, and would have
+// made a difference, plays the tick again from before with the turn in. Nothing reacts to the
+// tick, no sounds or stats or drawing, until the moment's over, so the redone tick is the only
+// one anyone sees.
+
+#[derive(Default)]
+pub struct LateInputs {
+    // the game just before the last tick, the random numbers as they were then, and when it ran
+    before: Option<(SnakeGame, ([u16; 2], usize), f64)>,
+    window_ms: f64,
+    // late turns already played into the last tick, which go in again if another one comes
+    turns: Vec<(usize, Direction)>,
+}
+
+impl LateInputs {
+    // call this just before ticking. a window of 0 doesn't hold anything back
+    pub fn before_tick(&mut self, game: &SnakeGame, now_ms: f64, window_ms: f64) {
+        self.turns.clear();
+        self.window_ms = window_ms;
+        self.before = (window_ms > 0.0).then(|| (game.clone(), random::state(), now_ms));
+    }
+
+    pub fn is_holding(&self) -> bool {
+        self.before.is_some()
+    }
+
+    // Stops holding the last tick back, so it's too late to change it. Returns whether there
+    // was one.
+    pub fn release(&mut self) -> bool {
+        self.turns.clear();
+        self.before.take().is_some()
+    }
+
+    // Plays the last tick again with the turn in, if it's still recent enough and the turn would
+    // have changed anything. Returns whether it did, otherwise it's up to the caller to steer
+    // `game` as usual.
+    pub fn replay(
+        &mut self,
+        game: &mut SnakeGame,
+        player: usize,
+        direction: Direction,
+        now_ms: f64,
+    ) -> bool {
+        let Some((before, random_state, ticked_ms)) = self.before.as_ref() else {
+            return false;
+        };
+
+        // a restart or anything else since the tick would be undone along with it
+        if now_ms - ticked_ms > self.window_ms
+            || game.ticks != before.ticks + 1
+            || game.seed != before.seed
+        {
+            return false;
+        }
+
+        let mut replayed = before.clone();
+        for (player, direction) in self.turns.iter() {
+            replayed.change_player_direction(*player, direction.clone());
+        }
+        if !replayed.would_turn(player, &direction) {
+            return false;
+        }
+
+        replayed.change_player_direction(player, direction.clone());
+        // the tick draws the same numbers again, for the same food and the same everything else
+        random::restore(*random_state);
+        replayed.tick();
+
+        *game = replayed;
+        self.turns.push((player, direction));
+        true
+    }
+}
+
+thread_local! {
+    static LATE_INPUTS: RefCell<LateInputs> = RefCell::new(LateInputs::default());
+
+    // so a tick that's released early doesn't leave its timeout to release the next one
+    static TIMEOUT_HANDLE: Cell<Option<i32>> = const { Cell::new(None) };
+
+    static RELEASE_CLOSURE: Closure<dyn FnMut()> =
+        Closure::wrap(Box::new(crate::release_held_tick) as Box<dyn FnMut()>);
+}
+
+// Holds the tick that's about to run back for `window_ms`, after which `release_held_tick`
+// shows it.
+pub fn before_tick(game: &SnakeGame, window_ms: u32) {
+    LATE_INPUTS.with(|late_inputs| {
+        late_inputs
+            .borrow_mut()
+            .before_tick(game, js_sys::Date::now(), window_ms as f64)
+    });
+
+    if window_ms == 0 {
+        return;
+    }
+
+    let Ok(window) = crate::error::window() else {
+        return;
+    };
+    if let Some(handle) = TIMEOUT_HANDLE.with(|handle| handle.take()) {
+        window.clear_timeout_with_handle(handle);
+    }
+
+    let handle = RELEASE_CLOSURE.with(|closure| {
+        window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            closure.as_ref().unchecked_ref(),
+            window_ms as i32,
+        )
+    });
+    match handle {
+        Ok(handle) => TIMEOUT_HANDLE.with(|old_handle| old_handle.set(Some(handle))),
+        Err(error) => crate::error::report(error.into(), None),
+    }
+}
+
+pub fn is_holding() -> bool {
+    LATE_INPUTS.with(|late_inputs| late_inputs.borrow().is_holding())
+}
+
+pub fn release() -> bool {
+    LATE_INPUTS.with(|late_inputs| late_inputs.borrow_mut().release())
+}
+
+pub fn replay(player: usize, direction: Direction) -> bool {
+    LATE_INPUTS.with(|late_inputs| {
+        crate::GAME.with(|game| {
+            late_inputs.borrow_mut().replay(
+                &mut game.borrow_mut(),
+                player,
+                direction,
+                js_sys::Date::now(),
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Vector;
+
+    #[test]
+    fn late_turns() {
+        random::seed([1, 2]);
+        let mut game = SnakeGame::new(21, 15);
+        let start = game.snakes()[0].head().clone();
+        let mut late_inputs = LateInputs::default();
+
+        late_inputs.before_tick(&game, 0.0, 30.0);
+        game.tick();
+        assert!(late_inputs.is_holding());
+        assert_eq!(*game.snakes()[0].head(), Vector(start.0 - 1, start.1));
+
+        // as if up had been pressed just before the tick
+        assert!(late_inputs.replay(&mut game, 0, Direction::Up, 10.0));
+        assert_eq!(*game.snakes()[0].head(), Vector(start.0, start.1 - 1));
+        assert_eq!(game.ticks, 1);
+        // it's in already
+        assert!(!late_inputs.replay(&mut game, 0, Direction::Up, 20.0));
+        // and the snake was still heading left before the tick, so it can't turn right there
+        assert!(!late_inputs.replay(&mut game, 0, Direction::Right, 20.0));
+
+        // just like a game where up came in on time, random numbers and all
+        random::seed([1, 2]);
+        let mut on_time = SnakeGame::new(21, 15);
+        on_time.change_direction(Direction::Up);
+        on_time.tick();
+        assert_eq!(game.to_snapshot(), on_time.to_snapshot());
+
+        assert!(late_inputs.release());
+        assert!(!late_inputs.release());
+        assert!(!late_inputs.replay(&mut game, 0, Direction::Left, 20.0));
+
+        // too late
+        late_inputs.before_tick(&game, 100.0, 30.0);
+        game.tick();
+        assert!(!late_inputs.replay(&mut game, 0, Direction::Left, 140.0));
+
+        // or restarted in the meantime
+        late_inputs.before_tick(&game, 200.0, 30.0);
+        game.tick();
+        game.restart();
+        assert!(!late_inputs.replay(&mut game, 0, Direction::Up, 210.0));
+
+        late_inputs.before_tick(&game, 300.0, 0.0);
+        assert!(!late_inputs.is_holding());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::SlakeError;
+use crate::render;
+use crate::settings::Settings;
+use crate::snake::SnakeGame;
+
+use std::cell::Cell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlElement, WheelEvent};
+
+// How big the tiles are drawn, so the board fits in the window. When there's room they're the
+// size they've always been, and when there isn't they shrink to fit, down to `MIN_TILE_PX`,
+// where emoji are still easy to make out. A board that doesn't fit even then runs off the page
+// and scrolls. Zooming, with the zoom keys or by pinching on a trackpad, goes in and out from
+// whatever fits, for getting a closer look at a big custom board. Everything that draws the
+// board goes by the `--tile` style this sets on the page.
+
+pub const TILE_PX: f64 = 40.0;
+pub const MIN_TILE_PX: f64 = 16.0;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 4.0;
+// how much each press of a zoom key zooms by
+const ZOOM_STEP: f64 = 1.25;
+// left around the board for the page's margins, and the score line under it, in pixels
+const MARGIN_PX: f64 = 16.0;
+const INFO_PX: f64 = 96.0;
+
+thread_local! {
+    static ZOOM: Cell<f64> = const { Cell::new(1.0) };
+    // what `--tile` was set to last, so it's only set again when it changes
+    static SHOWN_TILE_PX: Cell<Option<f64>> = const { Cell::new(None) };
+}
+
+// The tile size in pixels that fits a board `extent` tiles across and down, see
+// `Geometry::extent`, into a window `viewport` pixels big. Never any bigger than usual.
+pub fn fitting_tile_px(viewport: (f64, f64), extent: (f64, f64)) -> f64 {
+    let across = (viewport.0 - MARGIN_PX) / extent.0;
+    let down = (viewport.1 - INFO_PX) / extent.1;
+    across.min(down).clamp(MIN_TILE_PX, TILE_PX)
+}
+
+// zoomed out still keeps to the smallest size that's readable
+pub fn zoomed_tile_px(fitting: f64, zoom: f64) -> f64 {
+    (fitting * zoom).max(MIN_TILE_PX)
+}
+
+// `zoom` after `steps` presses of zoom in, or out for negative ones
+pub fn zoomed(zoom: f64, steps: i32) -> f64 {
+    (zoom * ZOOM_STEP.powi(steps)).clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+pub fn zoom(steps: i32) {
+    ZOOM.with(|zoom| zoom.set(zoomed(zoom.get(), steps)));
+    crate::draw();
+}
+
+// back to whatever fits
+pub fn reset_zoom() {
+    ZOOM.with(|zoom| zoom.set(1.0));
+    crate::draw();
+}
+
+// Sizes the tiles for `game`'s board, as it's about to be drawn.
+pub fn update(game: &SnakeGame, settings: &Settings) -> Result<(), SlakeError> {
+    let window = crate::error::window()?;
+    let viewport = (
+        window.inner_width()?.as_f64().unwrap_or(0.0),
+        window.inner_height()?.as_f64().unwrap_or(0.0),
+    );
+
+    let border = if settings.show_border { 1 } else { 0 };
+    let rows = render::shown_rows(game);
+    let extent = game
+        .geometry()
+        .extent(game.width + border * 2, rows.end - rows.start + border * 2);
+
+    let fitting = fitting_tile_px(viewport, extent);
+    let tile_px = zoomed_tile_px(fitting, ZOOM.with(|zoom| zoom.get()));
+    if SHOWN_TILE_PX.with(|shown| shown.replace(Some(tile_px))) == Some(tile_px) {
+        return Ok(());
+    }
+
+    let page = crate::error::document()?
+        .document_element()
+        .ok_or_else(|| SlakeError::Dom("the document is empty".to_string()))?
+        .dyn_into::<HtmlElement>()?;
+    page.style()
+        .set_property("--tile", &format!("{tile_px}px"))?;
+    Ok(())
+}
+
+pub fn listen() -> Result<(), SlakeError> {
+    let window = crate::error::window()?;
+
+    let handle_resize = Closure::wrap(Box::new(crate::draw) as Box<dyn FnMut()>);
+    // pinching on a trackpad comes through as the wheel with ctrl held down. anything else is
+    // left to scroll the page
+    let handle_wheel = Closure::wrap(Box::new(|event: WheelEvent| {
+        if !event.ctrl_key() || event.delta_y() == 0.0 {
+            return;
+        }
+
+        event.prevent_default();
+        zoom(if event.delta_y() < 0.0 { 1 } else { -1 });
+    }) as Box<dyn FnMut(WheelEvent)>);
+
+    window.add_event_listener_with_callback("resize", handle_resize.as_ref().unchecked_ref())?;
+    // on the board only, where it isn't passive, so the page doesn't zoom along with it
+    crate::get_element::<HtmlElement>("root")?
+        .add_event_listener_with_callback("wheel", handle_wheel.as_ref().unchecked_ref())?;
+
+    handle_resize.forget();
+    handle_wheel.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitting() {
+        // the default board has room to spare on a desktop
+        assert_eq!(fitting_tile_px((1920.0, 1080.0), (23.0, 17.0)), TILE_PX);
+        // a phone held upright goes by the width
+        let tile_px = fitting_tile_px((390.0, 844.0), (23.0, 17.0));
+        assert!(tile_px < TILE_PX);
+        assert!(tile_px * 23.0 <= 390.0 - MARGIN_PX);
+        // and a huge board stops shrinking somewhere it can still be read
+        assert_eq!(fitting_tile_px((1280.0, 720.0), (62.0, 42.0)), MIN_TILE_PX);
+
+        assert_eq!(zoomed_tile_px(20.0, 2.0), 40.0);
+        assert_eq!(zoomed_tile_px(20.0, 0.5), MIN_TILE_PX);
+
+        assert_eq!(zoomed(1.0, 1), ZOOM_STEP);
+        assert_eq!(zoomed(1.0, -100), MIN_ZOOM);
+        assert_eq!(zoomed(1.0, 100), MAX_ZOOM);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::error::SlakeError;
+use crate::snake::SnakeGame;
+use crate::version;
+use crate::version::Fingerprint;
+
+use js_sys::{Array, Promise, Reflect};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::rc::Rc;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement, Request, RequestInit, Response};
+
+// Finished solo runs can be sent to a leaderboard server, set with `data-leaderboard` on the
+// root element. Each score is POSTed there as JSON:
+//
+//     { "name": "someone", "score": 12, "seed": [123, 456], "replay": "1a2b3c4d",
+//       "version": "0.1.0 (1a2b3c4)", "fingerprint": "1:21x15:walls:0.1.0 (1a2b3c4)" }
+//
+// The fingerprint is a `version::Fingerprint`, for telling whether the run could be played back.
+// A GET to the same url should answer with a list of `{ "name": ..., "score": ... }`, which is
+// shown once the game is over. Only built with the `leaderboard` feature, so pages without a
+// server don't carry any of it.
+
+const NAME_KEY: &str = "slake_leaderboard_name";
+
+// how many places are shown
+pub const TABLE_LENGTH: usize = 10;
+
+// names longer than this get cut off
+pub const NAME_LENGTH: usize = 16;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Submission {
+    pub name: String,
+    pub score: usize,
+    // the seed the run started with, see `SnakeGame::seed`
+    pub seed: [u16; 2],
+    // see `analysis::Replay::hash`
+    pub replay_hash: u32,
+    // so the server can tell which runs it could play back itself
+    pub fingerprint: Fingerprint,
+}
+
+impl Submission {
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"name":{},"score":{},"seed":[{},{}],"replay":"{:08x}","version":{},"fingerprint":{}}}"#,
+            json_string(&self.name),
+            self.score,
+            self.seed[0],
+            self.seed[1],
+            self.replay_hash,
+            json_string(&version::version_string()),
+            json_string(&self.fingerprint.serialize()),
+        )
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from('"');
+
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            character if character.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub score: usize,
+}
+
+// the best places, highest first, whatever order the server sent them in
+pub fn top_entries(mut entries: Vec<Entry>) -> Vec<Entry> {
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(TABLE_LENGTH);
+    entries
+}
+
+// `None` for names that are nothing but spaces
+pub fn clean_name(name: &str) -> Option<String> {
+    let name = name.trim().chars().take(NAME_LENGTH).collect::<String>();
+    (!name.is_empty()).then_some(name)
+}
+
+fn parse_entries(value: &JsValue) -> Result<Vec<Entry>, String> {
+    let entries = value
+        .dyn_ref::<Array>()
+        .ok_or_else(|| "the leaderboard should be a list of scores".to_string())?;
+
+    let entries = entries
+        .iter()
+        .filter_map(|entry| {
+            let name = Reflect::get(&entry, &"name".into()).ok()?.as_string()?;
+            let score = Reflect::get(&entry, &"score".into()).ok()?.as_f64()?;
+
+            Some(Entry {
+                name: clean_name(&name).unwrap_or_else(|| "anonymous".to_string()),
+                score: score.max(0.0) as usize,
+            })
+        })
+        .collect();
+
+    Ok(top_entries(entries))
+}
+
+fn endpoint() -> Option<String> {
+    crate::error::document()
+        .ok()?
+        .get_element_by_id("root")?
+        .get_attribute("data-leaderboard")
+}
+
+// Asked for once and remembered after that. Cancelling means the score isn't sent, and asks
+// again next time.
+fn player_name() -> Option<String> {
+    let storage = crate::local_storage();
+
+    if let Some(name) = storage
+        .as_ref()
+        .and_then(|storage| storage.get_item(NAME_KEY).ok().flatten())
+        .and_then(|name| clean_name(&name))
+    {
+        return Some(name);
+    }
+
+    let name = crate::error::window()
+        .ok()?
+        .prompt_with_message("Name for the leaderboard:")
+        .ok()
+        .flatten()
+        .and_then(|name| clean_name(&name))?;
+
+    if let Some(storage) = storage {
+        let _ = storage.set_item(NAME_KEY, &name);
+    }
+
+    Some(name)
+}
+
+// calls `done` with what the promise resolved to, or `None` if it was rejected
+fn when_settled(promise: &Promise, done: impl FnOnce(Option<JsValue>) + 'static) {
+    let done = RefCell::new(Some(done));
+
+    let finish = Rc::new(move |value: Option<JsValue>| {
+        if let Some(done) = done.borrow_mut().take() {
+            done(value);
+        }
+    });
+
+    let handle_resolve = {
+        let finish = finish.clone();
+
+        Closure::wrap(Box::new(move |value: JsValue| finish(Some(value))) as Box<dyn FnMut(JsValue)>)
+    };
+
+    let handle_reject = {
+        let finish = finish.clone();
+
+        Closure::wrap(Box::new(move |_: JsValue| finish(None)) as Box<dyn FnMut(JsValue)>)
+    };
+
+    let _ = promise.then2(&handle_resolve, &handle_reject);
+
+    // like in `community::fetch_cached`, each promise only settles once
+    handle_resolve.forget();
+    handle_reject.forget();
+}
+
+// `None` if the request failed, or the server answered with an error
+fn fetch(request: &Request, done: impl FnOnce(Option<Response>) + 'static) {
+    let Ok(window) = crate::error::window() else {
+        done(None);
+        return;
+    };
+    let promise = window.fetch_with_request(request);
+
+    when_settled(&promise, move |value| {
+        done(
+            value
+                .and_then(|value| value.dyn_into::<Response>().ok())
+                .filter(|response| response.ok()),
+        )
+    });
+}
+
+fn fetch_scores(url: &str, done: impl FnOnce(Result<Vec<Entry>, String>) + 'static) {
+    let Ok(request) = Request::new_with_str(url) else {
+        done(Err("the leaderboard url isn't valid".to_string()));
+        return;
+    };
+
+    fetch(&request, move |response| {
+        let Some(json) = response.and_then(|response| response.json().ok()) else {
+            done(Err("couldn't reach the leaderboard".to_string()));
+            return;
+        };
+
+        when_settled(&json, move |value| {
+            done(match value {
+                Some(value) => parse_entries(&value),
+                None => Err("the leaderboard isn't valid JSON".to_string()),
+            })
+        });
+    });
+}
+
+fn post_score(url: &str, submission: &Submission, done: impl FnOnce(bool) + 'static) {
+    let init = RequestInit::new();
+    init.set_method("POST");
+    init.set_body(&submission.to_json().into());
+
+    let request = Request::new_with_str_and_init(url, &init).and_then(|request| {
+        request.headers().set("Content-Type", "application/json")?;
+        Ok(request)
+    });
+
+    match request {
+        Ok(request) => fetch(&request, move |response| done(response.is_some())),
+        Err(_) => done(false),
+    }
+}
+
+// sends the score of a run that just ended and shows the table, if the page has a leaderboard
+pub fn submit(game: &SnakeGame, replay_hash: u32) -> Result<(), SlakeError> {
+    let Some(url) = endpoint() else {
+        return Ok(());
+    };
+
+    let panel_element = crate::get_element::<HtmlElement>("leaderboard")?;
+    panel_element.style().set_property("display", "block")?;
+
+    let show = {
+        let url = url.clone();
+        let panel_element = panel_element.clone();
+
+        move |sent: Option<bool>| {
+            panel_element.set_inner_text("Loading the leaderboard...");
+
+            fetch_scores(&url, move |result| {
+                let note = match sent {
+                    Some(false) => Some("Couldn't send your score."),
+                    _ => None,
+                };
+
+                match result {
+                    Ok(entries) => {
+                        crate::error::report_failure(show_table(&panel_element, &entries, note))
+                    }
+                    Err(error) => panel_element
+                        .set_inner_text(&format!("Couldn't load the leaderboard: {error}")),
+                }
+            });
+        }
+    };
+
+    let Some(name) = player_name() else {
+        show(None);
+        return Ok(());
+    };
+
+    let submission = Submission {
+        name,
+        score: game.score,
+        seed: game.seed,
+        replay_hash,
+        fingerprint: Fingerprint::of(game),
+    };
+
+    panel_element.set_inner_text("Sending your score...");
+    post_score(&url, &submission, move |sent| show(Some(sent)));
+    Ok(())
+}
+
+fn show_table(
+    panel_element: &HtmlElement,
+    entries: &[Entry],
+    note: Option<&str>,
+) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    panel_element.set_inner_text(if entries.is_empty() {
+        "No scores yet."
+    } else {
+        ""
+    });
+
+    // names come from other players, so they only ever go in as text
+    for (place, entry) in entries.iter().enumerate() {
+        let row_element = document
+            .create_element("div")?
+            .dyn_into::<HtmlDivElement>()?;
+
+        row_element.set_inner_text(&format!("{}. {}: {}", place + 1, entry.name, entry.score));
+        panel_element.append_child(&row_element)?;
+    }
+
+    if let Some(note) = note {
+        let note_element = document
+            .create_element("div")?
+            .dyn_into::<HtmlDivElement>()?;
+
+        note_element.set_inner_text(note);
+        panel_element.append_child(&note_element)?;
+    }
+
+    Ok(())
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("leaderboard");
+    panel_element.set_class_name("leaderboard");
+    panel_element.style().set_property("display", "none")?;
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    Ok(())
+}
+
+pub fn hide_panel() -> Result<(), SlakeError> {
+    crate::get_element::<HtmlElement>("leaderboard")?
+        .style()
+        .set_property("display", "none")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Topology;
+
+    #[test]
+    fn submissions() {
+        let submission = Submission {
+            name: "say \"hi\"\n".to_string(),
+            score: 12,
+            seed: [123, 456],
+            replay_hash: 0xbeef,
+            fingerprint: Fingerprint::new(21, 15, Topology::Walls),
+        };
+
+        let json = submission.to_json();
+        assert!(json.starts_with(
+            r#"{"name":"say \"hi\"\u000a","score":12,"seed":[123,456],"replay":"0000beef","version":""#
+        ));
+        assert!(json.contains(&version::version_string()));
+        assert!(json.ends_with(&format!(
+            r#""fingerprint":"{}"}}"#,
+            submission.fingerprint.serialize()
+        )));
+
+        assert_eq!(clean_name("  slinky  "), Some("slinky".to_string()));
+        assert_eq!(clean_name("   "), None);
+        assert_eq!(clean_name(&"s".repeat(40)).unwrap().len(), NAME_LENGTH);
+    }
+
+    #[test]
+    fn top_ten() {
+        let entries = (0..15)
+            .map(|score| Entry {
+                name: format!("player {score}"),
+                score,
+            })
+            .collect();
+
+        let top = top_entries(entries);
+        assert_eq!(top.len(), TABLE_LENGTH);
+        assert_eq!(top[0].score, 14);
+        assert_eq!(top[9].score, 5);
+    }
+}
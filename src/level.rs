@@ -0,0 +1,631 @@
+use crate::geometry;
+use crate::geometry::Direction;
+use crate::geometry::Vector;
+
+use std::ops::Range;
+
+// Levels are plain ASCII grids, one character per tile:
+//
+//   `#` wall
+//   `~` hazard
+//   `@` snake head
+//   `o` snake body, connected to the head tile by tile
+//   `1` to `9` scripted food, spawned in that order instead of at random
+//   `A` to `F` portals, two of each letter. going into one comes out of the other
+//   `H` ladder, going into one comes out of the one in the same spot on the next floor over
+//   `=` a whole row of them splits the level into floors, stacked top to bottom
+//   `.` or ` ` empty
+//
+// Lines starting with `;` are comments. The first comment of the form `; name: ...` names the
+// level. Rows shorter than the widest one are padded with empty tiles.
+//
+// Only one floor shows at a time, whichever the snake's on. The rows between floors are walls
+// as far as the game's concerned, and in wrap the snake wraps around its own floor.
+pub const BUILTIN_LEVELS: &[&str] = &[
+    "; name: Pillars
+.....................
+.....................
+...##.....##.....##..
+...##.....##.....##..
+.....................
+.....................
+.....................
+.................@o..
+.....................
+.....................
+.....................
+...##.....##.....##..
+...##.....##.....##..
+.....................
+.....................
+",
+    "; name: Box
+#####################
+#...................#
+#...................#
+#...................#
+#........~~~........#
+#...................#
+#...................#
+#................@o.#
+#...................#
+#...................#
+#........~~~........#
+#...................#
+#...................#
+#...................#
+#####################
+",
+    "; name: Portals
+.....................
+.A.................B.
+.....................
+.....................
+.....................
+.....................
+.....................
+.................@o..
+.....................
+.....................
+.....................
+.....................
+.....................
+.B.................A.
+.....................
+",
+    "; name: Floors
+.....................
+.....................
+...H.....###.....H...
+.....................
+.................@o..
+.....................
+.....................
+=====================
+.....................
+.....................
+...H.....~~~.....H...
+.....................
+.....................
+.....................
+.....................
+",
+];
+
+// less room than this to move around in from the start isn't much of a game
+pub const MIN_REACHABLE_TILES: usize = 20;
+
+// food can spawn in any open tile, including ones the snake can't get to. tiny nooks next to
+// walls are usually just decoration, but anything bigger is likely a mistake
+pub const MAX_POCKET_SIZE: usize = 2;
+
+// one for each of the letters `A` to `F`, so themes only need that many looks for them
+pub const MAX_PORTAL_PAIRS: usize = 6;
+
+// any less and there's no room to turn around
+pub const MIN_FLOOR_HEIGHT: isize = 3;
+
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    pub width: isize,
+    pub height: isize,
+    pub walls: Vec<Vector>,
+    pub hazards: Vec<Vector>,
+    // head first, same as `Snake::body`
+    pub snake: Vec<Vector>,
+    pub direction: Direction,
+    // scripted food positions, in spawn order. empty means random food as usual
+    pub food: Vec<Vector>,
+    // in letter order. nothing else can be on a portal tile, and it's never free for food
+    pub portals: Vec<[Vector; 2]>,
+    // the rows between floors, top to bottom. none if it isn't split up, see `floors`
+    pub dividers: Vec<isize>,
+    // top end first, and like portals nothing else goes on them
+    pub ladders: Vec<[Vector; 2]>,
+}
+
+// the other end of the portal at `pos`, if there is one
+pub fn other_end<'a>(portals: &'a [[Vector; 2]], pos: &Vector) -> Option<&'a Vector> {
+    portals.iter().find_map(|[a, b]| match pos {
+        pos if pos == a => Some(b),
+        pos if pos == b => Some(a),
+        _ => None,
+    })
+}
+
+// the rows between `dividers`, which could be none if two are right next to each other
+fn floors(height: isize, dividers: &[isize]) -> Vec<Range<isize>> {
+    let mut floors = vec![];
+    let mut start = 0;
+
+    for end in dividers.iter().chain(std::iter::once(&height)) {
+        floors.push(start..*end);
+        start = end + 1;
+    }
+
+    floors
+}
+
+impl Level {
+    pub fn parse(source: &str) -> Result<Level, String> {
+        let mut name = String::new();
+        let mut rows = vec![];
+
+        for line in source.lines() {
+            if let Some(comment) = line.strip_prefix(';') {
+                if let Some(level_name) = comment.trim().strip_prefix("name:") {
+                    if name.is_empty() {
+                        name = level_name.trim().to_string();
+                    }
+                }
+                continue;
+            }
+
+            rows.push(line.trim_end());
+        }
+
+        // ignore blank lines around the grid, but not inside it
+        while rows.last().is_some_and(|row| row.is_empty()) {
+            rows.pop();
+        }
+        let first_row = rows
+            .iter()
+            .position(|row| !row.is_empty())
+            .unwrap_or(rows.len());
+        let rows = &rows[first_row..];
+
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as isize;
+        let height = rows.len() as isize;
+
+        if width < 5 || height < 3 {
+            return Err(format!(
+                "level is {width}x{height}, but must be at least 5x3"
+            ));
+        }
+
+        let mut walls = vec![];
+        let mut hazards = vec![];
+        let mut head = None;
+        let mut body = vec![];
+        let mut food = vec![];
+        let mut portal_tiles = vec![];
+        let mut ladder_tiles = vec![];
+        let mut dividers = vec![];
+
+        for (y, row) in rows.iter().enumerate() {
+            if row.contains('=') {
+                if row.chars().any(|tile| tile != '=') {
+                    return Err(format!(
+                        "line {} splits floors, but has something other than '=' on it",
+                        y + 1
+                    ));
+                }
+
+                // the gap between floors is a wall all the way across
+                walls.extend((0..width).map(|x| Vector(x, y as isize)));
+                dividers.push(y as isize);
+                continue;
+            }
+
+            for (x, tile) in row.chars().enumerate() {
+                let pos = Vector(x as isize, y as isize);
+
+                match tile {
+                    '#' => walls.push(pos),
+                    '~' => hazards.push(pos),
+                    'o' => body.push(pos),
+                    '@' => {
+                        if head.is_some() {
+                            return Err("level has more than one snake head".to_string());
+                        }
+                        head = Some(pos);
+                    }
+                    '1'..='9' => food.push((tile, pos)),
+                    'A'..='F' => portal_tiles.push((tile, pos)),
+                    'H' => ladder_tiles.push(pos),
+                    '.' | ' ' => (),
+                    _ => {
+                        return Err(format!(
+                            "unknown tile '{tile}' at line {}, column {}",
+                            y + 1,
+                            x + 1
+                        ))
+                    }
+                }
+            }
+        }
+
+        let floors = floors(height, &dividers);
+        if let Some(floor) = floors
+            .iter()
+            .position(|floor| floor.end - floor.start < MIN_FLOOR_HEIGHT)
+        {
+            return Err(format!(
+                "floor {} is {} rows, but must be at least {MIN_FLOOR_HEIGHT}",
+                floor + 1,
+                floors[floor].end - floors[floor].start
+            ));
+        }
+
+        // each ladder goes down to the one in the same spot on the floor below, and any left
+        // over have nowhere to go
+        let floor_of = |pos: &Vector| floors.iter().position(|floor| floor.contains(&pos.1));
+        let mut ladders = vec![];
+        while !ladder_tiles.is_empty() {
+            let top = ladder_tiles.remove(0);
+            let floor = floor_of(&top).unwrap();
+            let bottom = floors.get(floor + 1).and_then(|below| {
+                let pos = Vector(top.0, top.1 - floors[floor].start + below.start);
+                let index = ladder_tiles.iter().position(|tile| *tile == pos)?;
+                Some(ladder_tiles.remove(index))
+            });
+
+            match bottom {
+                Some(bottom) => ladders.push([top, bottom]),
+                None => {
+                    return Err(format!(
+                        "ladder at line {}, column {} doesn't lead to another floor",
+                        top.1 + 1,
+                        top.0 + 1
+                    ))
+                }
+            }
+        }
+
+        let head = head.ok_or_else(|| "level has no snake head".to_string())?;
+
+        // walk the body outward from the head, one adjacent segment at a time
+        let mut snake = vec![head];
+
+        while !body.is_empty() {
+            let last = snake.last().unwrap();
+
+            let next_index = body
+                .iter()
+                .position(|pos| geometry::manhattan_distance(pos, last) == 1);
+
+            match next_index {
+                Some(index) => snake.push(body.swap_remove(index)),
+                None => return Err("snake body isn't connected to its head".to_string()),
+            }
+        }
+
+        // start moving away from the neck, or left if there's only a head
+        let direction = match snake.get(1) {
+            Some(neck) => Direction::from_vector(&Vector(snake[0].0 - neck.0, snake[0].1 - neck.1))
+                .unwrap_or_default(),
+            None => Direction::Left,
+        };
+
+        // stable, so repeated digits spawn in reading order
+        food.sort_by_key(|(tile, _)| *tile);
+        let food = food.into_iter().map(|(_, pos)| pos).collect();
+
+        portal_tiles.sort_by_key(|(tile, _)| *tile);
+        let mut portals = vec![];
+        for letter in 'A'..='F' {
+            let ends = portal_tiles
+                .iter()
+                .filter(|(tile, _)| *tile == letter)
+                .map(|(_, pos)| pos.clone())
+                .collect::<Vec<_>>();
+
+            match <[Vector; 2]>::try_from(ends) {
+                Ok(pair) => portals.push(pair),
+                Err(ends) if ends.is_empty() => (),
+                Err(ends) => {
+                    return Err(format!(
+                        "portal '{letter}' has {} ends, but needs exactly two",
+                        ends.len()
+                    ))
+                }
+            }
+        }
+
+        Ok(Level {
+            name,
+            width,
+            height,
+            walls,
+            hazards,
+            snake,
+            direction,
+            food,
+            portals,
+            dividers,
+            ladders,
+        })
+    }
+
+    // which rows each floor has, top to bottom. just the one with every row if it isn't split up
+    pub fn floors(&self) -> Vec<Range<isize>> {
+        floors(self.height, &self.dividers)
+    }
+
+    // Back to the grid `parse` reads. Food past the ninth all gets a `9`, so from there on it
+    // spawns in reading order instead.
+    pub fn to_source(&self) -> String {
+        let mut rows = vec![vec!['.'; self.width as usize]; self.height as usize];
+        let mut put = |pos: &Vector, tile| rows[pos.1 as usize][pos.0 as usize] = tile;
+
+        for pos in self.walls.iter() {
+            put(pos, '#');
+        }
+        for pos in self.hazards.iter() {
+            put(pos, '~');
+        }
+        for (index, pos) in self.food.iter().enumerate() {
+            put(
+                pos,
+                char::from_digit((index + 1).min(9) as u32, 10).unwrap(),
+            );
+        }
+        for (pos, letter) in self.portals.iter().zip('A'..='F') {
+            put(&pos[0], letter);
+            put(&pos[1], letter);
+        }
+        for pos in self.ladders.iter().flatten() {
+            put(pos, 'H');
+        }
+        // the rows between floors, which are walls as well
+        for y in self.dividers.iter() {
+            for x in 0..self.width {
+                put(&Vector(x, *y), '=');
+            }
+        }
+        for pos in self.snake.iter() {
+            put(pos, 'o');
+        }
+        put(&self.snake[0], '@');
+
+        let mut source = String::new();
+        if !self.name.is_empty() {
+            source.push_str(&format!("; name: {}\n", self.name));
+        }
+        for row in rows {
+            source.extend(row);
+            source.push('\n');
+        }
+
+        source
+    }
+
+    // Checks for layouts that parse fine but make for a broken game. These are warnings rather
+    // than errors, since a weird level might be weird on purpose.
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = vec![];
+
+        let blocked = |pos: &Vector| {
+            self.walls.contains(pos)
+                || self.hazards.contains(pos)
+                || other_end(&self.portals, pos).is_some()
+                || other_end(&self.ladders, pos).is_some()
+        };
+        let open = |pos: &Vector| !blocked(pos) && !self.snake.contains(pos);
+        let on_board = |pos: &Vector| geometry::is_within_board(self.width, self.height, pos);
+        // one step, going through a portal or up or down a ladder if there's one there
+        let step = |pos: &Vector, direction: &Direction| {
+            let next = pos + &direction.to_vector();
+            match other_end(&self.portals, &next).or_else(|| other_end(&self.ladders, &next)) {
+                Some(exit) => exit + &direction.to_vector(),
+                None => next,
+            }
+        };
+
+        if self.snake.iter().any(blocked) {
+            warnings
+                .push("the snake starts on top of a wall, hazard, portal or ladder".to_string());
+        }
+
+        let in_front = step(&self.snake[0], &self.direction);
+        if !on_board(&in_front) || !open(&in_front) {
+            warnings.push("the snake starts out facing something deadly".to_string());
+        }
+
+        let mut reachable = geometry::flood_fill(self.width, self.height, &self.snake[0], open);
+
+        // then on through any portals and ladders it can get into, until there's nowhere new to
+        // come out
+        loop {
+            let exits = reachable
+                .iter()
+                .flat_map(|pos| Direction::ALL.map(|direction| step(pos, &direction)))
+                .filter(|exit| on_board(exit) && open(exit) && !reachable.contains(exit))
+                .collect::<Vec<_>>();
+            let Some(exit) = exits.first() else {
+                break;
+            };
+
+            let region = geometry::flood_fill(self.width, self.height, exit, open);
+            reachable.extend(region);
+        }
+
+        // the head itself isn't room to move in
+        if reachable.len() - 1 < MIN_REACHABLE_TILES {
+            warnings.push(format!(
+                "the snake can only reach {} tiles, at least {MIN_REACHABLE_TILES} are needed",
+                reachable.len() - 1
+            ));
+        }
+
+        let mut seen = reachable;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = Vector(x, y);
+
+                if !open(&pos) || seen.contains(&pos) {
+                    continue;
+                }
+
+                let pocket = geometry::flood_fill(self.width, self.height, &pos, open);
+
+                if pocket.len() > MAX_POCKET_SIZE {
+                    warnings.push(format!(
+                        "there's a pocket of {} tiles at {x}, {y} the snake can't reach",
+                        pocket.len()
+                    ));
+                }
+
+                seen.extend(pocket);
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_builtin_levels() {
+        for source in BUILTIN_LEVELS {
+            let level = Level::parse(source).unwrap();
+
+            assert!(!level.name.is_empty());
+            assert_eq!(level.width, 21);
+            assert_eq!(level.height, 15);
+            assert_eq!(level.snake.len(), 2);
+            assert!(level.direction == Direction::Left);
+            assert!(level.validate().is_empty(), "{:?}", level.validate());
+        }
+    }
+
+    #[test]
+    fn warns_about_broken_levels() {
+        let level = Level::parse(
+            "
+......#......
+......#......
+......#...@o.
+......#......
+",
+        )
+        .unwrap();
+
+        assert_eq!(level.validate().len(), 1);
+
+        // walled in, facing a wall, with the rest of the board out of reach
+        let level = Level::parse(
+            "
+.....#.
+.....#.
+....#@o
+.....##
+",
+        )
+        .unwrap();
+
+        assert_eq!(level.validate().len(), 3);
+    }
+
+    #[test]
+    fn orders_snake_from_head() {
+        let level = Level::parse(
+            "
+.....
+.ooo.
+.o.@.
+",
+        )
+        .unwrap();
+
+        assert!(
+            level.snake
+                == vec![
+                    Vector(3, 2),
+                    Vector(3, 1),
+                    Vector(2, 1),
+                    Vector(1, 1),
+                    Vector(1, 2)
+                ]
+        );
+        assert!(level.direction == Direction::Down);
+    }
+
+    #[test]
+    fn scripted_food_order() {
+        let level = Level::parse(
+            "
+.2.1.
+.@o..
+.1..3
+",
+        )
+        .unwrap();
+
+        assert!(level.food == vec![Vector(3, 0), Vector(1, 2), Vector(1, 0), Vector(4, 2)]);
+    }
+
+    #[test]
+    fn round_trip() {
+        for source in BUILTIN_LEVELS {
+            assert_eq!(Level::parse(source).unwrap().to_source(), *source);
+        }
+
+        // repeated digits get numbered apart, in the order they spawn
+        let level = Level::parse("; name: Scripted\n~11..\n.@o..\n.3..#\n").unwrap();
+        assert_eq!(level.to_source(), "; name: Scripted\n~12..\n.@o..\n.3..#\n");
+        assert!(Level::parse(&level.to_source()).unwrap().food == level.food);
+    }
+
+    #[test]
+    fn portals() {
+        let source = ".....#.....\n.A...#...A.\n.....#.@o..\n";
+        let level = Level::parse(source).unwrap();
+
+        assert!(level.portals == vec![[Vector(1, 1), Vector(9, 1)]]);
+        assert_eq!(
+            other_end(&level.portals, &Vector(9, 1)),
+            Some(&Vector(1, 1))
+        );
+        assert_eq!(other_end(&level.portals, &Vector(2, 1)), None);
+        assert_eq!(level.to_source(), source);
+
+        // the other side of the wall is only a portal away
+        assert!(level.validate().is_empty(), "{:?}", level.validate());
+
+        assert!(Level::parse(".A...\n.@o..\n.....").is_err());
+        assert!(Level::parse(".A.A.\n.@oA.\n.....").is_err());
+    }
+
+    #[test]
+    fn floors() {
+        let source = ".......\n.H...#.\n.......\n=======\n.......\n.H.@o..\n.......\n";
+        let level = Level::parse(source).unwrap();
+
+        assert!(level.floors() == vec![0..3, 4..7]);
+        assert!(level.ladders == vec![[Vector(1, 1), Vector(1, 5)]]);
+        assert_eq!(level.walls.len(), 8);
+        assert_eq!(level.to_source(), source);
+
+        // the top floor is only a ladder away
+        assert!(level.validate().is_empty(), "{:?}", level.validate());
+
+        let single = Level::parse(BUILTIN_LEVELS[0]).unwrap();
+        assert!(single.dividers.is_empty());
+        assert_eq!(single.floors().len(), 1);
+
+        // ladders that don't line up, or don't have a floor to go to
+        assert!(Level::parse(".H...\n.....\n.....\n=====\n..H..\n.@o..\n.....").is_err());
+        assert!(Level::parse(".H...\n.@o..\n.....").is_err());
+        assert!(Level::parse(".....\n.@o..\n.....\n==.==\n.....\n.....\n.....").is_err());
+        assert!(Level::parse(".....\n.@o..\n.....\n=====\n.....\n.....").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        assert!(Level::parse("....\n..@.\n....").is_err());
+        assert!(Level::parse(".....\n.....\n.....").is_err());
+        assert!(Level::parse(".....\n.@.@.\n.....").is_err());
+        assert!(Level::parse(".....\n.@..o\n.....").is_err());
+        assert!(Level::parse(".....\n.@.?.\n.....").is_err());
+    }
+}
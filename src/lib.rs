@@ -1,189 +1,1985 @@
-mod random;
-mod snake;
+mod about;
+mod accessibility;
+mod adaptive;
+mod analysis;
+mod animation;
+mod api;
+pub mod board;
+mod bot;
+mod canvas;
+mod coach;
+mod community;
+mod daily;
+mod debug;
+mod dev_console;
+mod editor;
+pub mod effects;
+mod embed;
+pub mod enemy;
+mod error;
+pub mod event;
+mod export;
+mod floor_map;
+mod focus;
+pub mod food;
+mod frame;
+pub mod generate;
+pub mod geometry;
+mod ghost;
+mod highscores;
+mod input;
+mod instance;
+mod keyboard;
+mod keys;
+mod late_input;
+mod layout;
+#[cfg(feature = "leaderboard")]
+mod leaderboard;
+pub mod level;
+pub mod mode;
+mod mouse;
+mod mutator;
+mod net;
+mod peer;
+mod profile;
+pub mod random;
+mod render;
+mod renderer;
+mod replay;
+mod rewind;
+pub mod rules;
+mod scenario;
+mod scheduler;
+mod settings;
+pub mod simulate;
+pub mod snake;
+pub mod snapshot;
+mod sound;
+pub mod spawner;
+mod splits;
+mod sprites;
+mod stats;
+pub mod theme;
+mod timer;
+mod title;
+mod toast;
+mod tutorial;
+mod update;
+pub mod version;
+pub mod view;
+pub mod waves;
 
-use crate::snake::Direction;
+use crate::event::GameEvent;
+use crate::food::FoodKind;
+use crate::food::Growth;
+use crate::geometry::Direction;
+use crate::geometry::Vector;
+use crate::input::HeldKeys;
+use crate::input::IdleWatch;
+use crate::input::InputSource;
+use crate::keys::Action;
+use crate::level::Level;
+use crate::level::BUILTIN_LEVELS;
+use crate::mode::GameMode;
+use crate::mutator::Mutator;
+use crate::render::Decoration;
+use crate::rules::RuleSet;
+use crate::scheduler::Scheduler;
+use crate::settings::BoardSize;
+use crate::settings::Difficulty;
+use crate::settings::LastPlayed;
+use crate::settings::RenderStyle;
+use crate::settings::Settings;
 use crate::snake::SnakeGame;
-use crate::snake::Vector;
+use crate::snake::HYDRA_UNLOCK_SCORE;
+use crate::theme::Theme;
+use crate::timer::PlayTimer;
+use crate::timer::RunClock;
+use error::SlakeError;
 
-use js_sys::Function;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::{prelude::*, JsCast};
-use web_sys::{console, window, HtmlDivElement, HtmlElement, KeyboardEvent};
+use web_sys::{
+    console, window, Element, Event, Gamepad, GamepadButton, HtmlDivElement, HtmlElement,
+    HtmlInputElement, HtmlSelectElement, KeyboardEvent, Storage,
+};
+
+const SETTINGS_KEY: &str = "slake_settings";
+
+// if the page falls way behind, skip ahead instead of playing catch-up all at once
+const MAX_TICKS_PER_ADVANCE: u32 = 10;
 
 thread_local! {
     static GAME: Rc<RefCell<SnakeGame>> = Rc::new(RefCell::new(SnakeGame::new(21, 15)));
 
-    static TICK_CLOSURE: Closure<dyn FnMut()> = Closure::wrap(Box::new({
-        || {
-            GAME.with(|game| game.borrow_mut().tick());
-            render(false).unwrap_throw();
+    // 0 is the default empty board, anything else is an index into `BUILTIN_LEVELS` plus one
+    static LEVEL_INDEX: Cell<usize> = const { Cell::new(0) };
+
+    static SETTINGS: RefCell<Settings> = RefCell::new(Settings::default());
+
+    static PLAY_TIMER: RefCell<PlayTimer> = RefCell::new(PlayTimer::default());
+
+    // how long the current run has been played, shown next to the score
+    static RUN_CLOCK: RefCell<RunClock> = RefCell::new(RunClock::default());
+
+    // time passed that hasn't added up to a whole tick yet
+    static UNSPENT_MS: Cell<f64> = const { Cell::new(0.0) };
+
+    // set when the host page drives time with `advance` itself, instead of the scheduler
+    static EXTERNAL_CLOCK: Cell<bool> = const { Cell::new(false) };
+
+    // how far adaptive runs are eased off or stepped up, see `adaptive`
+    static PACE_STEPS: Cell<i32> = const { Cell::new(0) };
+
+    static IDLE_WATCH: RefCell<IdleWatch> = RefCell::new(IdleWatch::default());
+
+    // the steering keys that are down, see `steer_held_keys`
+    static HELD_KEYS: RefCell<HeldKeys> = RefCell::new(HeldKeys::default());
+
+    // the page's own clock is just another one calling `advance`, one tick's worth at a time,
+    // and drawing the smooth style in between. the dev console can pause it and step through
+    static SCHEDULER: Scheduler = Scheduler::new(|| {
+        advance(tick_ms() as f64)
+    })
+    .between_ticks(|| {
+        if draws_smoothly() && !replay::is_watching() {
+            draw();
         }
-    }) as Box<dyn FnMut()>);
+    });
 
     static HANDLE_KEYDOWN: Closure<dyn FnMut(KeyboardEvent)> = Closure::wrap(Box::new({
         |event: KeyboardEvent| {
-            let direction = match &event.key()[..] {
-                "ArrowUp" => Direction::Up,
-                "ArrowDown" => Direction::Down,
-                "ArrowLeft" => Direction::Left,
-                "ArrowRight" => Direction::Right,
-                " " => {
-                    GAME.with(|game| game.borrow_mut().restart());
+            // the name entry has the keyboard to itself until it's closed, and so does the
+            // settings panel while it's waiting on a key to bind
+            if highscores::is_entering_name() || keys::capture(&event) {
+                return;
+            }
+
+            // everything else is for the board, and only while it has focus, see `keyboard`
+            if !keyboard::board_has_focus() {
+                return;
+            }
+
+            // the editor only listens for the key that closes it, so nothing else disturbs the
+            // level being made
+            if editor::is_editing() {
+                let actions = SETTINGS.with(|settings| {
+                    settings
+                        .borrow()
+                        .key_bindings
+                        .actions_for(&keys::event_key(&event))
+                });
+                if actions.contains(&Action::Editor) {
+                    editor::toggle();
+                    event.prevent_default();
+                }
+                return;
+            }
+
+            // any key closes what's new, and that's all it does
+            if about::is_showing() {
+                about::toggle();
+                event.prevent_default();
+                return;
+            }
+
+            // so does the replay viewer, which has nothing of the page's own run to steer
+            if replay::is_watching() {
+                if replay::key(&event.key()) {
+                    event.prevent_default();
+                }
+                return;
+            }
+
+            // the title screen's menu has the keyboard until something on it is picked
+            if title::is_showing() {
+                let steering = SETTINGS.with(|settings| {
+                    let actions = settings
+                        .borrow()
+                        .key_bindings
+                        .actions_for(&keys::event_key(&event));
+                    actions.iter().find_map(|action| action.steering())
+                });
+                if title::key(&event.key(), steering.map(|(_, direction)| direction)) {
+                    event.prevent_default();
+                }
+                return;
+            }
+
+            if tutorial::is_running() && event.key() == "Escape" {
+                tutorial::skip();
+                event.prevent_default();
+                return;
+            }
+
+            // the key that wakes the game up doesn't do anything else
+            if note_input() {
+                event.prevent_default();
+                return;
+            }
+
+            // let the settings panel have its keys while typing into it
+            if let Some(target) = event.target().and_then(|target| target.dyn_into::<Element>().ok()) {
+                if matches!(&target.tag_name()[..], "INPUT" | "SELECT") {
+                    return;
+                }
+            }
+
+            let key = keys::normalize(&keys::event_key(&event));
+            let actions = SETTINGS.with(|settings| settings.borrow().key_bindings.actions_for(&key));
+
+            // In versus, whoever picked the keyboard layout a key belongs to gets it, which wins
+            // over the key's usual job. Outside of versus, the extra layouts' keys keep their
+            // usual jobs, unless they're set to steer as well.
+            let versus = GAME.with(|game| game.borrow().is_versus());
+            let all_layouts = SETTINGS.with(|settings| settings.borrow().all_layouts);
+            for (source, direction) in actions.iter().filter_map(|action| action.steering()) {
+                if !versus && !all_layouts && source.is_extra_layout() {
+                    continue;
+                }
+
+                // a held key turns again every tick by itself, so the browser's repeats of it
+                // are only kept from scrolling the page
+                if event.repeat() && player_steering_with(source).is_some() {
+                    event.prevent_default();
+                    return;
+                }
+                if steer_from(source, direction.clone()) {
+                    HELD_KEYS.with(|held| held.borrow_mut().press(&key, source, direction));
                     event.prevent_default();
                     return;
-                },
-                _ => return,
+                }
+            }
+
+            let Some(action) = actions.into_iter().find(|action| action.steering().is_none())
+            else {
+                return;
             };
-            GAME.with(|game| game.borrow_mut().change_direction(direction));
+
+            // online games have to stay the same on both sides, so anything else that changes
+            // the game waits until it's over
+            if action.changes_game() && net::is_active() {
+                return;
+            }
+
+            run_action(action);
             event.prevent_default();
         }
     }) as Box<dyn FnMut(KeyboardEvent)>);
+
+    static HANDLE_KEYUP: Closure<dyn FnMut(KeyboardEvent)> = Closure::wrap(Box::new({
+        |event: KeyboardEvent| {
+            let key = keys::normalize(&keys::event_key(&event));
+            HELD_KEYS.with(|held| held.borrow_mut().release(&key));
+        }
+    }) as Box<dyn FnMut(KeyboardEvent)>);
 }
 
-#[wasm_bindgen(start)]
-pub fn main() {
-    console::log_1(&"Starting...".into());
+// everything a key can do besides steering
+fn run_action(action: Action) {
+    match action {
+        Action::Restart => restart_game(),
+        Action::Online => net::toggle(),
+        Action::Peer => peer::toggle(),
+        Action::Level => cycle_level(),
+        Action::Border => update_settings(|settings| settings.show_border = !settings.show_border),
+        Action::Hydra => toggle_hydra_mode(),
+        Action::Versus => toggle_versus_mode(),
+        Action::Dpad => update_settings(|settings| settings.show_dpad = !settings.show_dpad),
+        Action::BoardSize => {
+            update_settings(|settings| settings.board_size = settings.board_size.next())
+        }
+        Action::Theme => update_settings(|settings| settings.theme = settings.theme.next()),
+        Action::Presentation => {
+            update_settings(|settings| settings.presentation = !settings.presentation)
+        }
+        Action::Community => error::report_failure(community::toggle_panel()),
+        Action::Settings => error::report_failure(toggle_settings_panel()),
+        Action::Stats => error::report_failure(stats::toggle_panel()),
+        Action::Console => error::report_failure(dev_console::toggle()),
+        Action::Debug => error::report_failure(debug::toggle()),
+        Action::Freeze => debug::toggle_freeze(),
+        Action::Step => debug::step(),
+        Action::SlowMotion => debug::cycle_slow_motion(),
+        Action::Editor => editor::toggle(),
+        Action::TakeSuggestion => stats::take_suggestion(),
+        // the new build's ready, see `update`
+        Action::QuickStart if update::is_waiting() => error::reload(),
+        Action::QuickStart => quick_start(),
+        Action::About => about::toggle(),
+        Action::Rewind => rewind_game(),
+        Action::Share => export::share(),
+        Action::Replay => replay::share(),
+        Action::ZoomIn => layout::zoom(1),
+        Action::ZoomOut => layout::zoom(-1),
+        Action::ZoomReset => layout::reset_zoom(),
+        // steering goes through `steer_from` instead
+        _ => (),
+    }
+}
 
-    TICK_CLOSURE.with(|closure| {
-        window()
-            .unwrap_throw()
-            .set_interval_with_callback_and_timeout_and_arguments_0(
-                closure.as_ref().dyn_ref::<Function>().unwrap_throw(),
-                100,
-            )
-            .unwrap_throw()
+// Moves the game forward by `dt_ms` milliseconds, running however many ticks fit in that time
+// and rendering once at the end.
+fn advance(dt_ms: f64) {
+    // the replay viewer ticks on a clock of its own, and the page's own run waits underneath
+    if replay::is_watching() {
+        return;
+    }
+
+    // before the ticks, so a run that ends in one of them has its time up to now
+    update_run_clock();
+
+    if !EXTERNAL_CLOCK.with(|external_clock| external_clock.get()) {
+        title::check_idle(now_ms());
+    }
+
+    let tick_ms = tick_ms() as f64;
+    let mut unspent_ms = UNSPENT_MS.with(|unspent_ms| unspent_ms.get()) + dt_ms.max(0.0);
+
+    let mut ticks = 0;
+    while unspent_ms >= tick_ms {
+        if ticks == MAX_TICKS_PER_ADVANCE {
+            unspent_ms %= tick_ms;
+            break;
+        }
+
+        // the run waits underneath while the title screen's demo plays
+        if title::is_showing() {
+            title::tick();
+            unspent_ms -= tick_ms;
+            ticks += 1;
+            continue;
+        }
+
+        // the last tick has had its chance at late turns by now
+        release_held_tick();
+
+        poll_gamepads();
+        steer_held_keys();
+
+        // until someone presses something, the clock's just stopped
+        if GAME.with(|game| game.borrow().paused) {
+            unspent_ms = 0.0;
+            break;
+        }
+
+        steer_by_mouse();
+        bot::steer();
+
+        // online, a tick only runs once the other player's inputs for it are in
+        if net::is_active() {
+            let Some(inputs) = net::next_frame() else {
+                unspent_ms = unspent_ms.min(tick_ms);
+                break;
+            };
+            GAME.with(|game| net::apply(&mut game.borrow_mut(), &inputs));
+        }
+
+        GAME.with(|game| {
+            if SETTINGS.with(|settings| settings.borrow().practice) {
+                rewind::record(&game.borrow());
+            }
+            late_input::before_tick(&game.borrow(), late_input_ms());
+            game.borrow_mut().tick();
+        });
+        if !late_input::is_holding() {
+            finish_tick();
+        }
+
+        unspent_ms -= tick_ms;
+        ticks += 1;
+    }
+
+    UNSPENT_MS.with(|old_unspent_ms| old_unspent_ms.set(unspent_ms));
+
+    // the smooth style moves a little even when there was no tick
+    if ticks > 0 || draws_smoothly() {
+        draw();
+    }
+}
+
+// Online games have to tick the same on both sides, and pages running the clock themselves
+// expect a tick to be done once `advance` is, so neither waits on late turns.
+fn late_input_ms() -> u32 {
+    if net::is_active() || EXTERNAL_CLOCK.with(|external_clock| external_clock.get()) {
+        0
+    } else {
+        SETTINGS.with(|settings| settings.borrow().late_input_ms)
+    }
+}
+
+// everything that reacts to a tick, once it's settled
+fn finish_tick() {
+    // a run started over in slow motion doesn't count either
+    if debug::in_use() {
+        GAME.with(|game| game.borrow_mut().tampered = true);
+    }
+
+    handle_events();
+    GAME.with(|game| frame::stream(&game.borrow()));
+    update_play_timer();
+    watch_for_idling();
+}
+
+// A tick right away, whatever the clock is up to and even while paused, for the debug controls.
+// It's the game's own tick and what reacts to it, without anything that only makes sense on the
+// clock, like waiting for late turns.
+fn step_game() {
+    release_held_tick();
+
+    GAME.with(|game| game.borrow_mut().step());
+    finish_tick();
+    draw();
+}
+
+// shows the tick `late_input` was holding back, if there is one
+fn release_held_tick() {
+    if late_input::release() {
+        finish_tick();
+        draw();
+    }
+}
+
+// with reduced motion, the smooth style jumps from tick to tick like the others
+fn draws_smoothly() -> bool {
+    SETTINGS.with(|settings| {
+        let settings = settings.borrow();
+        settings.render_style == RenderStyle::Smooth && !settings.reduced_motion
+    })
+}
+
+// Draws the board as it is right now, part way to the next tick for the smooth style. Animations
+// call this too, in between ticks.
+fn draw() {
+    // what's on the board might still change, see `late_input`
+    if late_input::is_holding() {
+        return;
+    }
+
+    // the title screen's demo has nothing of the player's drawn over it
+    if title::with_demo(|demo| draw_game(demo, &[])).is_some() {
+        return;
+    }
+    if replay::with_watched(|watched| draw_game(watched, &[])).is_some() {
+        return;
+    }
+
+    GAME.with(|game| {
+        let game = game.borrow();
+
+        let decorations = SETTINGS.with(|settings| {
+            let mut decorations = ghost::trail(&game)
+                .into_iter()
+                .map(|pos| (pos, Decoration::Ghost))
+                .collect::<Vec<_>>();
+
+            // drawn last, so it shows over the ghost
+            if settings.borrow().coaching && !game.is_game_over() {
+                if let Some((pos, _)) = coach::hint(&game) {
+                    decorations.push((pos, Decoration::Warning));
+                }
+            }
+
+            // the whole board shows again once the game is over
+            if mutator::active(&settings.borrow()) == Some(Mutator::Fog) && !game.is_game_over() {
+                let fog = mutator::fogged_tiles(&game);
+                decorations.extend(fog.into_iter().map(|pos| (pos, Decoration::Fog)));
+            }
+
+            decorations.extend(animation::decorations());
+            decorations.extend(editor::decorations());
+            decorations
+        });
+
+        draw_game(&game, &decorations);
     });
+}
 
-    HANDLE_KEYDOWN.with(|handle_keydown| {
-        window()
-            .unwrap_throw()
-            .add_event_listener_with_callback(
-                "keydown",
-                handle_keydown.as_ref().dyn_ref::<Function>().unwrap_throw(),
-            )
-            .unwrap_throw();
+fn draw_game(game: &SnakeGame, decorations: &[(Vector, Decoration)]) {
+    let smooth = draws_smoothly();
+    let tick_ms = tick_ms() as f64;
+    // the host page's clock keeps what's left over itself, and the page's own keeps it in the
+    // scheduler
+    let unspent_ms = UNSPENT_MS.with(|unspent_ms| unspent_ms.get())
+        + SCHEDULER.with(|scheduler| scheduler.fraction()) * tick_ms;
+
+    let drawn = SETTINGS.with(|settings| {
+        let started_ms = now_ms();
+        render::render(
+            game,
+            &settings.borrow(),
+            decorations,
+            debug::is_showing(),
+            if smooth { unspent_ms / tick_ms } else { 1.0 },
+        )?;
+        debug::update(game, now_ms() - started_ms)?;
+
+        Ok::<(), SlakeError>(())
     });
+
+    // the game carries on underneath, and the next frame might well draw
+    if let Err(error) = drawn {
+        error::report(error, Some(draw));
+    }
 }
 
-fn render(debug_mode: bool) -> Result<(), JsValue> {
-    let height = GAME.with(|game| game.borrow().height);
-    let width = GAME.with(|game| game.borrow().width);
+// Called for every key and steer. Returns whether it woke the game up from pausing itself.
+fn note_input() -> bool {
+    IDLE_WATCH.with(|watch| watch.borrow_mut().input());
+    title::note_input(now_ms());
 
-    let document = window().unwrap_throw().document().unwrap_throw();
+    // the editor keeps the game paused until it's done, and so does the title screen
+    if editor::is_editing() || title::is_showing() {
+        return false;
+    }
 
-    let root_container = document
-        .get_element_by_id("root")
-        .unwrap_throw() // we unwrap this one because it's actually an option so, it's easier to just throw here
-        .dyn_into::<HtmlElement>()?;
+    GAME.with(|game| {
+        let mut game = game.borrow_mut();
 
-    root_container.set_inner_html("");
+        if !game.paused {
+            return false;
+        }
 
-    let field_holder_element = document
-        .create_element("div")?
-        .dyn_into::<HtmlDivElement>()?;
+        game.paused = false;
+        // a moment to find the keys again
+        game.start_countdown();
+        focus::forget();
+        true
+    })
+}
 
-    field_holder_element.set_class_name("field_holder");
+// pauses the run if it's been going a while without anyone steering
+fn watch_for_idling() {
+    // online games can't stop for one side, and pages running the clock themselves have their
+    // own idea of when to stop
+    if net::is_active() || EXTERNAL_CLOCK.with(|external_clock| external_clock.get()) {
+        return;
+    }
 
-    field_holder_element.set_inner_text("");
+    let limit = SETTINGS.with(|settings| settings.borrow().idle_pause_ticks);
 
-    root_container.append_child(&field_holder_element)?;
+    GAME.with(|game| {
+        let mut game = game.borrow_mut();
 
-    field_holder_element
-        .style()
-        .set_property("display", "inline-grid")?;
+        if game.is_game_over() || game.countdown().is_some() {
+            return;
+        }
 
-    field_holder_element.style().set_property(
-        "grid-template",
-        &format!("repeat({height}, auto) / repeat({width}, auto)"),
-    )?;
+        if IDLE_WATCH.with(|watch| watch.borrow_mut().tick(limit)) {
+            game.paused = true;
+            log("Paused, since nothing's been pressed in a while");
+        }
+    });
+}
 
-    let semi_open_tiles = if debug_mode {
-        Some(GAME.with(|game| game.borrow().get_semi_open_tiles()))
-    } else {
-        None
+// goes back to how the last finished run was set up, and starts another
+fn quick_start() {
+    let Some(last_played) = SETTINGS.with(|settings| settings.borrow().last_played) else {
+        log("Nothing's been played to the end yet");
+        return;
     };
 
-    for y in 0..height {
-        for x in 0..width {
-            let pos = Vector(x, y);
+    update_settings(|settings| last_played.apply_to(settings));
+    restart_game();
+    log(&format!(
+        "Quick start: {}, {}, {} board",
+        last_played.mode.label(),
+        last_played.difficulty.label(),
+        last_played.board_size.name()
+    ));
+}
+
+fn restart_game() {
+    if net::is_active() {
+        net::restart();
+    } else {
+        GAME.with(|game| game.borrow_mut().restart());
+    }
+}
+
+// only in practice, which is why it's one of the keys that wait out online games
+fn rewind_game() {
+    if !SETTINGS.with(|settings| settings.borrow().practice) {
+        return;
+    }
+
+    // whatever the last tick set off has to have happened before it's undone
+    release_held_tick();
+    if rewind::rewind() {
+        draw();
+    }
+}
+
+// Steers the snakes belonging to whoever picked `source`, returning whether anyone did. Outside
+// of versus everything steers player one.
+fn steer_from(source: InputSource, direction: Direction) -> bool {
+    note_input();
+
+    let direction = inverted(direction);
 
-            let field_element = document
-                .create_element("div")?
-                .dyn_into::<HtmlDivElement>()?;
+    tutorial::note(tutorial::Action::Steered);
 
-            field_element.set_class_name("field");
+    // online, every input steers our own snake, on both sides at once
+    if net::is_active() {
+        net::steer(direction);
+        return true;
+    }
 
+    match player_steering_with(source) {
+        Some(player) => {
+            // a turn that just missed the last tick goes into it instead, if it's not too late
+            let late = late_input::replay(player, direction.clone());
             GAME.with(|game| {
-                if debug_mode {
-                    if semi_open_tiles.as_ref().unwrap().contains(&pos) {
-                        field_element
-                            .style()
-                            .set_property("background-color", "grey")
-                            .unwrap_throw();
-                    }
+                let mut game = game.borrow_mut();
+                if !late {
+                    game.change_player_direction(player, direction.clone());
+                }
+                replay::note(game.ticks - late as usize, player, direction);
+            });
+            true
+        }
+        None => false,
+    }
+}
 
-                    if !game.borrow().free_positions.contains(&pos) {
-                        field_element
-                            .style()
-                            .set_property("background-color", "orange")
-                            .unwrap_throw();
-                    }
+// Turns again with the steering keys that are still held down, once a tick like the gamepads.
+// Only the newest key that would change where a player's snake goes steers each player, so
+// holding a key down doesn't pile up turns the same way, and another one held under it still
+// gets its turn once the snake can take it.
+fn steer_held_keys() {
+    // online, every turn has to be sent across, so only presses count
+    if net::is_active() {
+        return;
+    }
+
+    let held = HELD_KEYS.with(|held| held.borrow().steering());
+    let mut steered = vec![];
+    for (source, direction) in held {
+        let Some(player) = player_steering_with(source) else {
+            continue;
+        };
+        let turns = GAME.with(|game| {
+            game.borrow()
+                .would_turn(player, &inverted(direction.clone()))
+        });
+        if !steered.contains(&player) && turns {
+            steer_from(source, direction);
+            steered.push(player);
+        }
+    }
+}
+
+// the way the inverted controls mutator turns `direction`, when it's on
+fn inverted(direction: Direction) -> Direction {
+    let inverted = SETTINGS
+        .with(|settings| mutator::active(&settings.borrow()) == Some(Mutator::InvertedControls));
+    if inverted {
+        direction.opposite()
+    } else {
+        direction
+    }
+}
+
+// outside of versus there's only the one
+fn player_steering_with(source: InputSource) -> Option<usize> {
+    if GAME.with(|game| game.borrow().is_versus()) {
+        SETTINGS.with(|settings| input::player_for(&settings.borrow().player_inputs, source))
+    } else {
+        Some(0)
+    }
+}
+
+// the pointer doesn't send anything while it sits still, so it gets checked before every tick,
+// like the gamepads
+fn steer_by_mouse() {
+    let (steering, border) = SETTINGS.with(|settings| {
+        let settings = settings.borrow();
+        (settings.mouse_steering, settings.show_border as isize)
+    });
+    if !steering {
+        return;
+    }
+
+    // online it's always our own snake
+    let player = match net::is_active() {
+        true => net::local_player(),
+        false => player_steering_with(InputSource::Mouse),
+    };
+    let direction = player
+        .and_then(|player| GAME.with(|game| mouse::direction(&game.borrow(), player, border)));
+
+    if let Some(direction) = direction {
+        steer_from(InputSource::Mouse, direction);
+    }
+}
+
+fn connected_gamepads() -> Vec<Gamepad> {
+    // browsers without gamepad support just don't have any
+    let Some(gamepads) = error::window()
+        .ok()
+        .and_then(|window| window.navigator().get_gamepads().ok())
+    else {
+        return vec![];
+    };
+
+    // empty slots are null
+    gamepads
+        .iter()
+        .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+        .collect()
+}
+
+// gamepads don't send events for their buttons, so they get checked before every tick
+fn poll_gamepads() {
+    for gamepad in connected_gamepads() {
+        let axes = gamepad
+            .axes()
+            .iter()
+            .filter_map(|axis| axis.as_f64())
+            .collect::<Vec<_>>();
+
+        let buttons = gamepad.buttons();
+        let pressed = |index| {
+            buttons
+                .get(index)
+                .dyn_into::<GamepadButton>()
+                .is_ok_and(|button| button.pressed())
+        };
+
+        let dpad = [pressed(12), pressed(13), pressed(14), pressed(15)];
+
+        if let Some(direction) = input::gamepad_direction(&axes, dpad) {
+            steer_from(InputSource::Gamepad(gamepad.index()), direction);
+        }
+    }
+}
+
+// hands the clock over to the host page, or takes it back
+fn set_external_clock(external: bool) {
+    EXTERNAL_CLOCK.with(|external_clock| external_clock.set(external));
+    start_ticking(tick_ms());
+}
+
+fn handle_events() {
+    // once anything's reacted to a tick, it's too late to play it again
+    late_input::release();
+
+    let events = GAME.with(|game| game.borrow_mut().take_events());
+    let mut ate = false;
+
+    let messages = GAME.with(|game| {
+        let game = game.borrow();
+        events
+            .iter()
+            .filter_map(|event| accessibility::message(event, &game))
+            .collect::<Vec<_>>()
+    });
+    if !messages.is_empty() {
+        accessibility::announce(&messages.join(". "));
+    }
+
+    GAME.with(|game| {
+        let game = game.borrow();
+        for event in events.iter() {
+            animation::on_event(event, &game);
+        }
+    });
+
+    let tick = GAME.with(|game| game.borrow().ticks);
+    for event in events.iter() {
+        api::send_event(event, tick);
+    }
+
+    for event in events.iter() {
+        let action = match event {
+            GameEvent::FoodEaten { .. } => tutorial::Action::Ate,
+            GameEvent::GameOver { .. } => tutorial::Action::Crashed,
+            GameEvent::Restarted => tutorial::Action::Restarted,
+            _ => continue,
+        };
+        tutorial::note(action);
+    }
+
+    for event in events {
+        match event {
+            GameEvent::FoodEaten { kind, .. } => {
+                ate = true;
+
+                match kind {
+                    FoodKind::Normal => play_sound(660.0, 0.08),
+                    FoodKind::Bonus => play_sound(990.0, 0.12),
+                    FoodKind::Rotten => play_sound(220.0, 0.15),
+                    FoodKind::Speed => play_sound(1320.0, 0.1),
+                    FoodKind::Shrink => play_sound(440.0, 0.12),
                 }
 
-                field_element.set_inner_text(if game.borrow().food.contains(&pos) {
-                    "🍆"
-                } else if pos == game.borrow().snake[0] {
-                    "😩"
-                } else if pos == *game.borrow().snake.back().unwrap() {
-                    "🍑"
-                } else if game.borrow().snake.contains(&pos) {
-                    "🟡"
-                } else if game.borrow().hazards.contains(&pos) {
-                    "💦"
+                GAME.with(|game| {
+                    let game = game.borrow();
+                    if !game.is_versus() && !game.tampered {
+                        stats::record_food(longest_snake(&game));
+                    }
+                    if SETTINGS.with(|settings| settings.borrow().speedrun) {
+                        splits::note(&game, run_ms());
+                    }
+                });
+            }
+            GameEvent::SnakeStunned { .. } => play_sound(165.0, 0.25),
+            GameEvent::SnakeBounced { .. } => play_sound(220.0, 0.1),
+            GameEvent::WallsClosingIn => play_sound(98.0, 0.3),
+            GameEvent::WaveCleared { .. } => play_sound(1175.0, 0.3),
+            GameEvent::WaveStarted { .. } => play_sound(131.0, 0.3),
+            GameEvent::ScoreChanged { score } => embed::notify_score(score),
+            GameEvent::GameOver { reason } => {
+                if reason == snake::WON_REASON {
+                    play_sound(880.0, 0.4);
                 } else {
-                    ""
+                    play_sound(110.0, 0.4);
+                }
+                GAME.with(|game| embed::notify_game_over(game.borrow().score, reason));
+
+                GAME.with(|game| {
+                    let game = game.borrow();
+
+                    // runs that went through the dev console don't count for anything
+                    if !game.is_versus() && !game.tampered {
+                        let survival_ms = run_ms() as u64;
+                        let difficulty = SETTINGS.with(|settings| settings.borrow().difficulty);
+                        stats::record_game(
+                            reason,
+                            longest_snake(&game),
+                            survival_ms,
+                            game.score,
+                            difficulty,
+                        );
+
+                        if game.mode().is_daily() {
+                            if game.level().is_none() {
+                                daily::record_score(game.score);
+                            }
+                        } else if SETTINGS.with(|settings| settings.borrow().mutator) {
+                            // the week's table is for classic runs
+                            if game.mode() == GameMode::Classic {
+                                mutator::record_score(game.score);
+                            }
+                        } else if game.level().is_none() {
+                            // races go by time, and one that wasn't finished has none
+                            let score = match game.mode().fruit_target() {
+                                Some(_) => game.race_ms().unwrap_or(0),
+                                None => game.score,
+                            };
+                            let board = (game.width, game.height);
+                            error::report_failure(highscores::finish(score, game.mode(), board));
+                        }
+                    }
+
+                    if !game.tampered {
+                        ghost::finish(&game);
+                    }
+                    splits::finish(&game);
+                    replay::finish(&game);
+                    if !game.is_versus() {
+                        // levels, mutators, and other modes score too differently to share a table
+                        #[cfg(feature = "leaderboard")]
+                        if game.level().is_none()
+                            && game.mode() == GameMode::Classic
+                            && !game.hazard_stun
+                            && !game.food_away_from_heads
+                            && game.food_count.most() == 1
+                            && game.rules == RuleSet::SLAKE
+                            && game.grid == geometry::Grid::Square
+                            && !game.combos
+                            && !game.fresh_food
+                            && game.growth == Growth::default()
+                            && game.grace_ticks == 0
+                            && game.win_percent.is_none()
+                            && game.enemy_count == 0
+                            && game.energy_drain.is_none()
+                            && !game.tampered
+                            && !SETTINGS.with(|settings| {
+                                let settings = settings.borrow();
+                                settings.mutator || settings.adaptive
+                            })
+                        {
+                            if let Some(replay_hash) = analysis::replay_hash() {
+                                error::report_failure(leaderboard::submit(&game, replay_hash));
+                            }
+                        }
+
+                        analysis::finish(&game);
+                    }
+
+                    log(&format!(
+                        "{} / Score: {} / High Score: {}",
+                        reason,
+                        render::score_out_of(&game),
+                        game.high_score()
+                    ));
+                });
+
+                // online games are set up by the host, not by anything in our settings
+                if !net::is_active() {
+                    repace();
+                    SETTINGS.with(|settings| {
+                        let mut settings = settings.borrow_mut();
+                        let last_played = LastPlayed::of(&settings);
+                        settings.last_played = Some(last_played);
+                        save_settings(&settings);
+                    });
+                }
+            }
+            GameEvent::Restarted => {
+                scenario::on_restart();
+                ghost::start();
+                GAME.with(|game| {
+                    analysis::start(&game.borrow());
+                    replay::start(&game.borrow());
+                    if SETTINGS.with(|settings| settings.borrow().speedrun) {
+                        splits::note(&game.borrow(), run_ms());
+                    }
                 });
+                error::report_failure(highscores::hide_table());
+                #[cfg(feature = "leaderboard")]
+                error::report_failure(leaderboard::hide_panel());
+            }
+            _ => (),
+        }
+    }
+
+    GAME.with(|game| {
+        let game = game.borrow();
+        ghost::record(&game);
+        analysis::record(&game, ate);
+    });
+
+    scenario::run_due();
+}
+
+// in hydra mode, the longest of the heads
+fn longest_snake(game: &SnakeGame) -> usize {
+    game.snakes()
+        .iter()
+        .map(|snake| snake.body.len())
+        .max()
+        .unwrap_or(0)
+}
+
+fn update_play_timer() {
+    let playing = GAME.with(|game| !game.borrow().is_game_over());
+    let reminder_minutes = SETTINGS.with(|settings| settings.borrow().reminder_minutes);
+
+    let reminder = PLAY_TIMER.with(|timer| {
+        let mut timer = timer.borrow_mut();
+        timer.update(js_sys::Date::now(), playing);
+        timer.take_reminder(reminder_minutes)
+    });
+
+    if let Some(minutes) = reminder {
+        // a missing reminder isn't worth stopping the game over
+        let _ = toast::show(&format!(
+            "You've been playing {minutes} minutes. Maybe time for a break?"
+        ));
+    }
+}
+
+// the page's clock, in milliseconds, or 0 on pages without one
+fn now_ms() -> f64 {
+    window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
+
+// by the page's clock rather than how many ticks went by, since ticks can come late
+fn update_run_clock() {
+    let Some(now_ms) = window()
+        .and_then(|window| window.performance())
+        .map(|performance| performance.now())
+    else {
+        return;
+    };
+
+    GAME.with(|game| {
+        let game = game.borrow();
+        let running = !game.paused && !game.is_game_over() && game.countdown().is_none();
+        RUN_CLOCK.with(|clock| {
+            clock
+                .borrow_mut()
+                .update(now_ms, game.seed, game.ticks, running)
+        });
+    });
+}
+
+fn run_ms() -> f64 {
+    RUN_CLOCK.with(|clock| clock.borrow().elapsed_ms())
+}
+
+fn play_sound(frequency: f32, duration: f64) {
+    if SETTINGS.with(|settings| settings.borrow().sound) {
+        // no sound isn't worth stopping the game over
+        let _ = sound::beep(frequency, duration);
+    }
+}
+
+// called whenever the page might be going away, so a closed tab can pick the run up again
+fn save_game() {
+    // online games can't carry on without the other player
+    let snapshot = if net::is_active() {
+        None
+    } else {
+        GAME.with(|game| game.borrow().to_snapshot())
+    };
+
+    snapshot::save(snapshot.as_ref());
+}
+
+// carries on with the run from last time, if it was on the same board the settings ask for
+fn resume_saved_game() {
+    let Some(snapshot) = snapshot::load() else {
+        return;
+    };
+
+    // only ever once, so a run that won't load doesn't keep coming back
+    snapshot::save(None);
+
+    let (width, height) = SETTINGS.with(|settings| mutator::board_dimensions(&settings.borrow()));
+    let (topology, grid) = SETTINGS.with(|settings| {
+        let settings = settings.borrow();
+        (settings.topology, settings.grid)
+    });
+    let fingerprint = &snapshot.fingerprint;
+    if (
+        fingerprint.width,
+        fingerprint.height,
+        fingerprint.topology,
+        fingerprint.grid,
+    ) != (width, height, topology, grid)
+    {
+        return;
+    }
+
+    match SnakeGame::from_snapshot(&snapshot) {
+        Ok(mut resumed) => {
+            // a moment to find the keys again, instead of carrying on at full speed
+            resumed.start_countdown();
+
+            GAME.with(|game| {
+                *game.borrow_mut() = resumed;
+
+                ghost::start();
+                analysis::start(&game.borrow());
             });
+            log("Picked up where you left off");
+        }
+        Err(error) => console::warn_1(&format!("Couldn't resume the saved game: {error}").into()),
+    }
+}
+
+fn local_storage() -> Option<Storage> {
+    // storage can be missing or disabled entirely, in which case we just don't save anything
+    error::window().ok()?.local_storage().ok().flatten()
+}
+
+fn load_settings() -> Settings {
+    match local_storage().and_then(|storage| storage.get_item(SETTINGS_KEY).ok().flatten()) {
+        Some(saved) => Settings::parse(&saved),
+        None => Settings {
+            // touch screens don't have arrow keys, so start with the d-pad out
+            show_dpad: error::window()
+                .is_ok_and(|window| window.navigator().max_touch_points() > 0),
+            reduced_motion: prefers_reduced_motion(),
+            ..Settings::default()
+        },
+    }
+}
+
+// whether the browser's been asked to keep movement down
+fn prefers_reduced_motion() -> bool {
+    error::window()
+        .ok()
+        .and_then(|window| window.match_media("(prefers-reduced-motion: reduce)").ok())
+        .flatten()
+        .is_some_and(|query| query.matches())
+}
 
-            field_holder_element.append_child(&field_element)?;
+// Saves `value` under `key`. Having no storage at all isn't an error, it only means nothing's
+// ever kept.
+fn store(key: &str, value: &str) -> Result<(), SlakeError> {
+    match local_storage() {
+        Some(storage) => storage.set_item(key, value).map_err(SlakeError::storage),
+        None => Ok(()),
+    }
+}
+
+fn save_settings(settings: &Settings) {
+    if let Err(error) = store(SETTINGS_KEY, &settings.serialize()) {
+        error::report(error, None);
+    }
+}
+
+fn update_settings(change: impl FnOnce(&mut Settings)) {
+    let mut settings = SETTINGS.with(|settings| settings.borrow().clone());
+    change(&mut settings);
+    apply_settings(settings.clamped());
+}
+
+fn apply_settings(settings: Settings) {
+    let old_tick_ms = tick_ms();
+    let old_settings = SETTINGS.with(|old_settings| old_settings.replace(settings.clone()));
+    update_pace(&settings);
+
+    let starve_ticks = settings.starve_ticks();
+    let energy_drain = settings.energy_drain();
+    // online games play by the host's rules for as long as they last
+    let online = net::is_active();
+    let difficulty_changed = !online
+        && GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            game.special_food = settings.special_food;
+            // only where the next food goes, so it can wait for that
+            game.food_away_from_heads = settings.food_away_from_heads;
+            // more food comes out as it's eaten, and what's over stays until it is
+            game.food_count = settings.food_count();
+            let double_hazards = mutator::active(&settings) == Some(Mutator::DoubleHazards);
+            let mutator_changed =
+                std::mem::replace(&mut game.double_hazards, double_hazards) != double_hazards;
+
+            let hazards_changed = pace_game(&mut game, &settings);
+            if game.ticks == 0 {
+                game.start_countdown();
+            }
+
+            let starving_changed =
+                std::mem::replace(&mut game.starve_ticks, starve_ticks) != starve_ticks;
+            let hunger_changed =
+                std::mem::replace(&mut game.energy_drain, energy_drain) != energy_drain;
+            let stun_changed = std::mem::replace(&mut game.hazard_stun, settings.hazard_stun)
+                != settings.hazard_stun;
+            let combos_changed =
+                std::mem::replace(&mut game.combos, settings.combos) != settings.combos;
+            let fresh_food_changed =
+                std::mem::replace(&mut game.fresh_food, settings.fresh_food) != settings.fresh_food;
+            let growth_changed =
+                std::mem::replace(&mut game.growth, settings.growth) != settings.growth;
+            let rules_changed =
+                std::mem::replace(&mut game.rules, settings.rules) != settings.rules;
+            // the daily challenge has its own, like its board
+            let enemies = match settings.mode.is_daily() {
+                true => daily::today().enemies,
+                false => settings.enemies,
+            };
+            // which only makes a difference in versus
+            let enemies_changed = std::mem::replace(&mut game.enemy_count, enemies) != enemies;
+            let coop_changed = std::mem::replace(&mut game.coop, settings.coop) != settings.coop
+                && game.is_versus();
+            // only the start of a run is forgiving, so there's no need to start over for it
+            game.grace_ticks = settings.grace_ticks;
+            // nor for how much of the board wins, which is checked as the snake grows
+            game.win_percent = (settings.win_percent > 0).then_some(settings.win_percent);
+
+            game.daily_seed = settings.mode.is_daily().then(|| daily::today().seed);
+            if game.mode() != settings.mode {
+                game.set_mode(settings.mode);
+            }
+
+            hazards_changed
+                || starving_changed
+                || hunger_changed
+                || stun_changed
+                || combos_changed
+                || fresh_food_changed
+                || growth_changed
+                || rules_changed
+                || coop_changed
+                || enemies_changed
+                || mutator_changed
+        });
+
+    if let (Some(mutator), false) = (mutator::active(&settings), old_settings.mutator) {
+        log(&format!(
+            "This week's mutator: {}, {}",
+            mutator.label(),
+            mutator.description()
+        ));
+    }
+
+    // a different board means starting over
+    if !online
+        && (mutator::board_dimensions(&settings) != mutator::board_dimensions(&old_settings)
+            || settings.topology != old_settings.topology
+            || settings.grid != old_settings.grid)
+    {
+        let (width, height) = mutator::board_dimensions(&settings);
+
+        GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            game.topology = settings.topology;
+            game.grid = settings.grid;
+            game.resize(width, height);
+        });
+        LEVEL_INDEX.with(|index| index.set(0));
+
+        log(&format!(
+            "Board size: {} ({width}x{height})",
+            settings.board_size.name()
+        ));
+    } else if difficulty_changed {
+        GAME.with(|game| game.borrow_mut().restart());
+    }
+
+    if tick_ms() != old_tick_ms || !SCHEDULER.with(|scheduler| scheduler.is_running()) {
+        start_ticking(tick_ms());
+    }
+
+    // nobody watching needs the arrows
+    let show_dpad = settings.show_dpad && !settings.presentation;
+    error::report_failure(set_dpad_visible(show_dpad));
+    save_settings(&settings);
+}
+
+// the time between ticks, which adaptive runs change from the settings'
+fn tick_ms() -> i32 {
+    let steps = PACE_STEPS.with(|steps| steps.get());
+    SETTINGS.with(|settings| adaptive::tick_ms(settings.borrow().tick_ms, steps))
+}
+
+// works out the pace from how the last few games went, which only changes once one's over
+fn update_pace(settings: &Settings) {
+    let steps = match settings.adaptive && !net::is_active() {
+        true => adaptive::steps(&stats::recent_games()),
+        false => 0,
+    };
+    PACE_STEPS.with(|pace| pace.set(steps));
+}
+
+// Sets up the speed and puddles of `game` for the pace, returning whether the puddles changed,
+// since that means starting over.
+fn pace_game(game: &mut SnakeGame, settings: &Settings) -> bool {
+    let tick_ms = tick_ms();
+    // about a second for each number, whatever the speed
+    game.countdown_ticks = (1000 / tick_ms.max(1)).max(1) as usize;
+    // timed effects keep their time whatever the speed, so this can change mid-run
+    game.tick_ms = tick_ms.max(1) as usize;
+    // but the clock goes by ticks, so it's worked out again whenever the speed changes
+    game.time_limit = settings.mode.time_limit(tick_ms);
+
+    // compared against the game rather than the old settings, since the game starts out with
+    // its own default
+    let steps = PACE_STEPS.with(|steps| steps.get());
+    let hazard_lifetime = adaptive::hazard_lifetime(settings.difficulty.hazard_lifetime(), steps);
+    std::mem::replace(&mut game.hazard_lifetime, hazard_lifetime) != hazard_lifetime
+}
+
+// Once a game's over, gets the next run ready for whatever pace it's ended up calling for.
+fn repace() {
+    let settings = SETTINGS.with(|settings| settings.borrow().clone());
+    let (old_steps, old_tick_ms) = (PACE_STEPS.with(|steps| steps.get()), tick_ms());
+    update_pace(&settings);
+
+    let steps = PACE_STEPS.with(|steps| steps.get());
+    if steps == old_steps {
+        return;
+    }
+
+    GAME.with(|game| pace_game(&mut game.borrow_mut(), &settings));
+    if tick_ms() != old_tick_ms {
+        start_ticking(tick_ms());
+    }
+    log(&format!(
+        "Adaptive pace: {}",
+        match steps.cmp(&old_steps) {
+            std::cmp::Ordering::Less => "easing off",
+            _ => "stepping up",
         }
+    ));
+}
+
+fn start_ticking(tick_ms: i32) {
+    SCHEDULER.with(|scheduler| scheduler.stop());
+
+    if EXTERNAL_CLOCK.with(|external_clock| external_clock.get()) {
+        return;
     }
 
-    //~ document.create_element("FOOTER");
+    // each tick still counts for as long as it usually does, there's just longer between them
+    let interval_ms = tick_ms.saturating_mul(debug::slow_motion() as i32);
+    SCHEDULER.with(|scheduler| scheduler.start(interval_ms));
+}
 
-    let info_element = document
+fn get_element<T: JsCast>(id: &str) -> Result<T, SlakeError> {
+    error::document()?
+        .get_element_by_id(id)
+        .ok_or_else(|| SlakeError::Dom(format!("there's no element '{id}'")))?
+        .dyn_into::<T>()
+        .map_err(|_| SlakeError::Dom(format!("'{id}' is the wrong kind of element")))
+}
+
+// like the d-pad, the panel lives outside of the root container
+fn build_settings_panel() -> Result<(), SlakeError> {
+    let document = error::document()?;
+
+    let panel_element = document
         .create_element("div")?
         .dyn_into::<HtmlDivElement>()?;
 
-    info_element.set_class_name("info");
+    panel_element.set_id("settings");
+    panel_element.set_class_name("settings");
 
-    //~ info_element
-    //~ .style()
-    //~ .set_property("display", "inline-grid")?;
+    let growth_inputs = FoodKind::ALL
+        .iter()
+        .map(|kind| {
+            format!(
+                r#"{} <input id="setting_growth_{}" type="number" min="0" max="{}">"#,
+                kind.name(),
+                kind.name(),
+                food::MAX_GROWTH
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
 
-    //~ info_element.style().set_property(
-    //~ "grid-template",
-    //~ &format!("repeat(1, auto) / repeat(2, auto)"),
-    //~ )?;
+    let mode_options = GameMode::ALL
+        .iter()
+        .map(|mode| {
+            format!(
+                r#"<option value="{}">{}</option>"#,
+                mode.name(),
+                mode.label()
+            )
+        })
+        .collect::<String>();
 
-    let score_element = document
-        .create_element("div")?
-        .dyn_into::<HtmlDivElement>()?;
+    let theme_options = Theme::ALL
+        .iter()
+        .map(|theme| {
+            format!(
+                r#"<option value="{}">{}</option>"#,
+                theme.name(),
+                theme.label()
+            )
+        })
+        .collect::<String>();
+
+    panel_element.set_inner_html(&format!(
+        r#"<label>Board <select id="setting_board_size">
+    <option value="small">Small</option>
+    <option value="medium">Medium</option>
+    <option value="large">Large</option>
+    <option value="custom">Custom</option>
+</select></label>
+<label>Width <input id="setting_width" type="number" min="{}" max="{}"></label>
+<label>Height <input id="setting_height" type="number" min="{}" max="{}"></label>
+<label>Tick (ms) <input id="setting_tick_ms" type="number" min="{}" max="{}"></label>
+<label>Edges <select id="setting_topology">
+    <option value="walls">Walls</option>
+    <option value="wrap">Wrap around</option>
+</select></label>
+<label>Tiles <select id="setting_grid">
+    <option value="square">Squares</option>
+    <option value="hex">Hexagons</option>
+</select></label>
+<label>Difficulty <select id="setting_difficulty">
+    <option value="easy">Easy</option>
+    <option value="normal">Normal</option>
+    <option value="hard">Hard</option>
+</select></label>
+<label><input id="setting_adaptive" type="checkbox"> Adaptive (speeds up or eases off to fit how you're doing)</label>
+<label>Mode <select id="setting_mode">{}</select></label>
+<div id="daily_info"></div>
+<label>Style <select id="setting_render_style">
+    <option value="emoji">Emoji</option>
+    <option value="squares">Colored squares</option>
+    <option value="smooth">Smooth</option>
+</select></label>
+<label>Theme <select id="setting_theme">{}</select></label>
+<label><input id="setting_special_food" type="checkbox"> Bonus and rotten fruit</label>
+<label><input id="setting_food_away_from_heads" type="checkbox"> Keep new fruit from landing right in front of the snake</label>
+<label>Fruit out at once <input id="setting_food_count" type="number" min="1" max="{}"></label>
+<label><input id="setting_food_scales" type="checkbox"> Start with one fruit, and work up to that as the score goes up</label>
+<label>Tiles each fruit grows the snake by: {}</label>
+<label><input id="setting_rules_hazards_on_eat" type="checkbox"> Eating leaves leftovers behind</label>
+<label><input id="setting_rules_tail_blocks" type="checkbox"> The tile a tail is leaving still blocks (off for classic snake rules)</label>
+<label><input id="setting_rules_self_collision" type="checkbox"> Running into a snake ends the run</label>
+<label><input id="setting_rules_tail_cut" type="checkbox"> Running into your own body bites it off there instead, leaving leftovers and costing a point a segment</label>
+<label><input id="setting_coaching" type="checkbox"> Beginner hints</label>
+<label><input id="setting_starving" type="checkbox"> Starving (shrink when hungry)</label>
+<label><input id="setting_hunger" type="checkbox"> Hunger (an energy meter that eating fills up, and running out ends the run)</label>
+<label><input id="setting_hazard_stun" type="checkbox"> Leftovers stun the first time instead of ending the run</label>
+<label><input id="setting_combos" type="checkbox"> Combos (eating quickly multiplies points)</label>
+<label><input id="setting_fresh_food" type="checkbox"> Fresh food (fruit is worth more the sooner you get to it)</label>
+<label>Walls turn the snake around for the first <input id="setting_grace_ticks" type="number" min="0" max="{}"> ticks (0 for never)</label>
+<label>Win once the snake covers <input id="setting_win_percent" type="number" min="0" max="100">% of the board (0 to keep going until it's full)</label>
+<label>Wandering enemies <input id="setting_enemies" type="number" min="0" max="{}"></label>
+<label><input id="setting_practice" type="checkbox"> Practice (Backspace rewinds a second, and rewound runs don't count)</label>
+<label><input id="setting_coop" type="checkbox"> Co-op, with both versus players on the same side</label>
+<label><input id="setting_mutator" type="checkbox"> This week's mutator: <span id="mutator_name"></span></label>
+<div id="mutator_scores"></div>
+<label><input id="setting_sound" type="checkbox"> Sound</label>
+<label><input id="setting_show_border" type="checkbox"> Border</label>
+<label><input id="setting_show_dpad" type="checkbox"> On-screen arrows</label>
+<label><input id="setting_mouse_steering" type="checkbox"> Steer towards the mouse</label>
+<label><input id="setting_all_layouts" type="checkbox"> Steer with WASD and IJKL too, outside of versus</label>
+<label><input id="setting_presentation" type="checkbox"> Presentation mode, with a big score</label>
+<label><input id="setting_speedrun" type="checkbox"> Speedrun splits every {} fruit</label>
+<label><input id="setting_drunk_board" type="checkbox"> Drunk board, slowly tilting and flipping over (smooth style only)</label>
+<label><input id="setting_screen_shake" type="checkbox"> Screen shake on crashes and near misses (smooth style only)</label>
+<label><input id="setting_reduced_motion" type="checkbox"> Reduced motion</label>
+<label>Name to show <input id="setting_player_name" type="text" maxlength="{}"></label>
+<label>Versus player 1 <select id="setting_player_input_0"></select></label>
+<label>Versus player 2 <select id="setting_player_input_1"></select></label>
+<label>Break reminder every <input id="setting_reminder_minutes" type="number" min="0" max="{}"> minutes (0 for never)</label>
+<label>Pause after <input id="setting_idle_pause_ticks" type="number" min="0" max="{}"> ticks without a key press (0 for never)</label>
+<label>Turns up to <input id="setting_late_input_ms" type="number" min="0" max="{}"> ms after a tick still count for it (0 for never)</label>
+<div>Keys</div>
+<div id="setting_keys"></div>
+<div id="setting_export_profile" class="button">Copy profile</div>
+<div id="setting_import_profile" class="button">Paste profile</div>
+<div id="setting_export_splits" class="button">Copy splits</div>
+<div id="setting_apply" class="button">Apply</div>
+<div class="about">slake {}</div>"#,
+        settings::MIN_WIDTH,
+        settings::MAX_WIDTH,
+        settings::MIN_HEIGHT,
+        settings::MAX_HEIGHT,
+        settings::MIN_TICK_MS,
+        settings::MAX_TICK_MS,
+        mode_options,
+        theme_options,
+        food::MAX_FOOD_COUNT,
+        growth_inputs,
+        settings::MAX_GRACE_TICKS,
+        enemy::MAX_ENEMIES,
+        splits::SPLIT_EVERY,
+        settings::MAX_PLAYER_NAME_LENGTH,
+        settings::MAX_REMINDER_MINUTES,
+        settings::MAX_IDLE_PAUSE_TICKS,
+        settings::MAX_LATE_INPUT_MS,
+        version::version_string(),
+    ));
+
+    panel_element.style().set_property("display", "none")?;
 
-    let high_score_element = document
+    error::body()?.append_child(&panel_element)?;
+
+    let handle_apply = Closure::wrap(Box::new(|| {
+        let settings = match read_settings_panel() {
+            Ok(settings) => settings,
+            Err(error) => return error::report(error, None),
+        };
+
+        // numbers out of range just get pulled back in, but the board is worth a word
+        if settings.board_size == BoardSize::Custom {
+            if let Err(error) = settings
+                .render_style
+                .check_board(settings.width, settings.height)
+            {
+                let (width, height) = settings.clone().clamped().board_dimensions();
+                let _ = toast::show(&format!(
+                    "Board size: {error}, so it's {width}x{height} instead"
+                ));
+            }
+        }
+
+        apply_settings(settings.clamped());
+        error::report_failure(toggle_settings_panel());
+    }) as Box<dyn FnMut()>);
+
+    get_element::<HtmlElement>("setting_apply")?
+        .set_onclick(Some(handle_apply.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither is its handler
+    handle_apply.forget();
+
+    profile::build_buttons()?;
+    splits::build_buttons()?;
+    keys::build_panel()
+}
+
+fn fill_settings_panel(settings: &Settings) -> Result<(), SlakeError> {
+    get_element::<HtmlSelectElement>("setting_board_size")?.set_value(settings.board_size.name());
+    get_element::<HtmlInputElement>("setting_width")?.set_value_as_number(settings.width as f64);
+    get_element::<HtmlInputElement>("setting_height")?.set_value_as_number(settings.height as f64);
+    get_element::<HtmlInputElement>("setting_tick_ms")?
+        .set_value_as_number(settings.tick_ms as f64);
+    get_element::<HtmlSelectElement>("setting_topology")?.set_value(settings.topology.name());
+    get_element::<HtmlSelectElement>("setting_grid")?.set_value(settings.grid.name());
+    get_element::<HtmlSelectElement>("setting_difficulty")?.set_value(settings.difficulty.name());
+    get_element::<HtmlInputElement>("setting_adaptive")?.set_checked(settings.adaptive);
+    get_element::<HtmlSelectElement>("setting_mode")?.set_value(&settings.mode.name());
+    get_element::<HtmlSelectElement>("setting_render_style")?
+        .set_value(settings.render_style.name());
+    get_element::<HtmlSelectElement>("setting_theme")?.set_value(settings.theme.name());
+    get_element::<HtmlInputElement>("setting_special_food")?.set_checked(settings.special_food);
+    get_element::<HtmlInputElement>("setting_food_away_from_heads")?
+        .set_checked(settings.food_away_from_heads);
+    get_element::<HtmlInputElement>("setting_food_count")?
+        .set_value_as_number(settings.food_count as f64);
+    for kind in FoodKind::ALL {
+        get_element::<HtmlInputElement>(&format!("setting_growth_{}", kind.name()))?
+            .set_value_as_number(settings.growth.of(kind) as f64);
+    }
+    get_element::<HtmlInputElement>("setting_food_scales")?.set_checked(settings.food_scales);
+    get_element::<HtmlInputElement>("setting_rules_hazards_on_eat")?
+        .set_checked(settings.rules.hazards_on_eat);
+    get_element::<HtmlInputElement>("setting_rules_tail_blocks")?
+        .set_checked(settings.rules.tail_blocks);
+    get_element::<HtmlInputElement>("setting_rules_self_collision")?
+        .set_checked(settings.rules.self_collision);
+    get_element::<HtmlInputElement>("setting_rules_tail_cut")?.set_checked(settings.rules.tail_cut);
+    get_element::<HtmlInputElement>("setting_coaching")?.set_checked(settings.coaching);
+    get_element::<HtmlInputElement>("setting_starving")?.set_checked(settings.starving);
+    get_element::<HtmlInputElement>("setting_hunger")?.set_checked(settings.hunger);
+    get_element::<HtmlInputElement>("setting_hazard_stun")?.set_checked(settings.hazard_stun);
+    get_element::<HtmlInputElement>("setting_combos")?.set_checked(settings.combos);
+    get_element::<HtmlInputElement>("setting_fresh_food")?.set_checked(settings.fresh_food);
+    get_element::<HtmlInputElement>("setting_grace_ticks")?
+        .set_value_as_number(settings.grace_ticks as f64);
+    get_element::<HtmlInputElement>("setting_win_percent")?
+        .set_value_as_number(settings.win_percent as f64);
+    get_element::<HtmlInputElement>("setting_enemies")?
+        .set_value_as_number(settings.enemies as f64);
+    get_element::<HtmlInputElement>("setting_practice")?.set_checked(settings.practice);
+    get_element::<HtmlInputElement>("setting_coop")?.set_checked(settings.coop);
+    get_element::<HtmlInputElement>("setting_mutator")?.set_checked(settings.mutator);
+    fill_mutator_info()?;
+    fill_daily_info()?;
+    get_element::<HtmlInputElement>("setting_sound")?.set_checked(settings.sound);
+    get_element::<HtmlInputElement>("setting_show_border")?.set_checked(settings.show_border);
+    get_element::<HtmlInputElement>("setting_show_dpad")?.set_checked(settings.show_dpad);
+    get_element::<HtmlInputElement>("setting_mouse_steering")?.set_checked(settings.mouse_steering);
+    get_element::<HtmlInputElement>("setting_all_layouts")?.set_checked(settings.all_layouts);
+    get_element::<HtmlInputElement>("setting_presentation")?.set_checked(settings.presentation);
+    get_element::<HtmlInputElement>("setting_speedrun")?.set_checked(settings.speedrun);
+    get_element::<HtmlInputElement>("setting_drunk_board")?.set_checked(settings.drunk_board);
+    get_element::<HtmlInputElement>("setting_screen_shake")?.set_checked(settings.screen_shake);
+    get_element::<HtmlInputElement>("setting_reduced_motion")?.set_checked(settings.reduced_motion);
+    get_element::<HtmlInputElement>("setting_player_name")?.set_value(&settings.player_name);
+    get_element::<HtmlInputElement>("setting_reminder_minutes")?
+        .set_value_as_number(settings.reminder_minutes as f64);
+    get_element::<HtmlInputElement>("setting_idle_pause_ticks")?
+        .set_value_as_number(settings.idle_pause_ticks as f64);
+    get_element::<HtmlInputElement>("setting_late_input_ms")?
+        .set_value_as_number(settings.late_input_ms as f64);
+    keys::fill_panel(&settings.key_bindings);
+
+    // gamepads come and go, so the choices are worked out fresh every time the panel opens
+    let connected = connected_gamepads()
+        .iter()
+        .map(|gamepad| gamepad.index())
+        .collect::<Vec<_>>();
+
+    let options = input::choices(&connected, &settings.player_inputs)
+        .iter()
+        .map(|source| {
+            let missing =
+                matches!(source, InputSource::Gamepad(index) if !connected.contains(index));
+            format!(
+                r#"<option value="{}">{}{}</option>"#,
+                source.name(),
+                source.label(),
+                if missing { " (not connected)" } else { "" }
+            )
+        })
+        .collect::<String>();
+
+    for (player, source) in settings.player_inputs.iter().enumerate() {
+        let select = get_element::<HtmlSelectElement>(&format!("setting_player_input_{player}"))?;
+        select.set_inner_html(&options);
+        select.set_value(&source.name());
+    }
+    Ok(())
+}
+
+// which mutator it is this week, and the best scores with it so far
+fn fill_mutator_info() -> Result<(), SlakeError> {
+    let week = mutator::this_week();
+    let mutator = Mutator::for_week(week);
+
+    get_element::<HtmlElement>("mutator_name")?.set_inner_text(&format!(
+        "{} ({})",
+        mutator.label(),
+        mutator.description()
+    ));
+
+    let scores = mutator::scores().scores;
+    let text = if scores.is_empty() {
+        format!("No scores for {} yet", week.name())
+    } else {
+        let scores = scores
+            .iter()
+            .map(|score| score.to_string())
+            .collect::<Vec<_>>();
+        format!("Best in {}: {}", week.name(), scores.join(", "))
+    };
+
+    get_element::<HtmlElement>("mutator_scores")?.set_inner_text(&text);
+    Ok(())
+}
+
+// what today's challenge is, and the best anyone's done at it here
+fn fill_daily_info() -> Result<(), SlakeError> {
+    let challenge = daily::today();
+    let (width, height) = challenge.dimensions();
+    let best = match daily::best() {
+        Some(best) => format!("best {best}"),
+        None => "not played yet".to_string(),
+    };
+
+    get_element::<HtmlElement>("daily_info")?.set_inner_text(&format!(
+        "Daily challenge for {}: {width}x{height}, {} enemies, {best}",
+        challenge.date, challenge.enemies
+    ));
+    Ok(())
+}
+
+// as typed, so call `clamped` before using them
+fn read_settings_panel() -> Result<Settings, SlakeError> {
+    let number = |id| {
+        let value = get_element::<HtmlInputElement>(id)?.value_as_number();
+
+        // empty inputs come back as NaN, which casts to zero and then gets clamped
+        Ok::<_, SlakeError>(value as i32)
+    };
+
+    let old_settings = SETTINGS.with(|settings| settings.borrow().clone());
+
+    let mut player_inputs = old_settings.player_inputs;
+    for (player, source) in player_inputs.iter_mut().enumerate() {
+        let select = get_element::<HtmlSelectElement>(&format!("setting_player_input_{player}"))?;
+        if let Some(chosen) = InputSource::from_name(&select.value()) {
+            *source = chosen;
+        }
+    }
+
+    Ok(Settings {
+        board_size: BoardSize::from_name(
+            &get_element::<HtmlSelectElement>("setting_board_size")?.value(),
+        )
+        .unwrap_or(old_settings.board_size),
+        width: number("setting_width")? as isize,
+        height: number("setting_height")? as isize,
+        tick_ms: number("setting_tick_ms")?,
+        topology: geometry::Topology::from_name(
+            &get_element::<HtmlSelectElement>("setting_topology")?.value(),
+        )
+        .unwrap_or(old_settings.topology),
+        grid: geometry::Grid::from_name(&get_element::<HtmlSelectElement>("setting_grid")?.value())
+            .unwrap_or(old_settings.grid),
+        difficulty: Difficulty::from_name(
+            &get_element::<HtmlSelectElement>("setting_difficulty")?.value(),
+        )
+        .unwrap_or(old_settings.difficulty),
+        adaptive: get_element::<HtmlInputElement>("setting_adaptive")?.checked(),
+        mode: GameMode::from_name(&get_element::<HtmlSelectElement>("setting_mode")?.value())
+            .unwrap_or(old_settings.mode),
+        render_style: RenderStyle::from_name(
+            &get_element::<HtmlSelectElement>("setting_render_style")?.value(),
+        )
+        .unwrap_or(old_settings.render_style),
+        theme: Theme::from_name(&get_element::<HtmlSelectElement>("setting_theme")?.value())
+            .unwrap_or(old_settings.theme),
+        special_food: get_element::<HtmlInputElement>("setting_special_food")?.checked(),
+        food_away_from_heads: get_element::<HtmlInputElement>("setting_food_away_from_heads")?
+            .checked(),
+        food_count: number("setting_food_count")?.max(1) as usize,
+        growth: FoodKind::ALL
+            .iter()
+            .try_fold(Growth::default(), |growth, kind| {
+                let input =
+                    get_element::<HtmlInputElement>(&format!("setting_growth_{}", kind.name()))?;
+                // empty ones come back as NaN, which `max` turns into zero
+                Ok::<_, SlakeError>(growth.with(*kind, input.value_as_number().max(0.0) as usize))
+            })?,
+        food_scales: get_element::<HtmlInputElement>("setting_food_scales")?.checked(),
+        rules: RuleSet {
+            hazards_on_eat: get_element::<HtmlInputElement>("setting_rules_hazards_on_eat")?
+                .checked(),
+            tail_blocks: get_element::<HtmlInputElement>("setting_rules_tail_blocks")?.checked(),
+            self_collision: get_element::<HtmlInputElement>("setting_rules_self_collision")?
+                .checked(),
+            tail_cut: get_element::<HtmlInputElement>("setting_rules_tail_cut")?.checked(),
+        },
+        coaching: get_element::<HtmlInputElement>("setting_coaching")?.checked(),
+        starving: get_element::<HtmlInputElement>("setting_starving")?.checked(),
+        hunger: get_element::<HtmlInputElement>("setting_hunger")?.checked(),
+        hazard_stun: get_element::<HtmlInputElement>("setting_hazard_stun")?.checked(),
+        combos: get_element::<HtmlInputElement>("setting_combos")?.checked(),
+        fresh_food: get_element::<HtmlInputElement>("setting_fresh_food")?.checked(),
+        grace_ticks: number("setting_grace_ticks")?.max(0) as usize,
+        win_percent: number("setting_win_percent")?.max(0) as usize,
+        enemies: number("setting_enemies")?.max(0) as usize,
+        practice: get_element::<HtmlInputElement>("setting_practice")?.checked(),
+        coop: get_element::<HtmlInputElement>("setting_coop")?.checked(),
+        mutator: get_element::<HtmlInputElement>("setting_mutator")?.checked(),
+        sound: get_element::<HtmlInputElement>("setting_sound")?.checked(),
+        show_border: get_element::<HtmlInputElement>("setting_show_border")?.checked(),
+        show_dpad: get_element::<HtmlInputElement>("setting_show_dpad")?.checked(),
+        mouse_steering: get_element::<HtmlInputElement>("setting_mouse_steering")?.checked(),
+        all_layouts: get_element::<HtmlInputElement>("setting_all_layouts")?.checked(),
+        presentation: get_element::<HtmlInputElement>("setting_presentation")?.checked(),
+        speedrun: get_element::<HtmlInputElement>("setting_speedrun")?.checked(),
+        drunk_board: get_element::<HtmlInputElement>("setting_drunk_board")?.checked(),
+        screen_shake: get_element::<HtmlInputElement>("setting_screen_shake")?.checked(),
+        reduced_motion: get_element::<HtmlInputElement>("setting_reduced_motion")?.checked(),
+        player_name: get_element::<HtmlInputElement>("setting_player_name")?.value(),
+        // negative numbers saturate to zero
+        reminder_minutes: number("setting_reminder_minutes")? as u32,
+        idle_pause_ticks: number("setting_idle_pause_ticks")?.max(0) as usize,
+        late_input_ms: number("setting_late_input_ms")?.max(0) as u32,
+        player_inputs,
+        key_bindings: keys::read_panel(),
+        // not something the panel shows
+        last_played: old_settings.last_played,
+    })
+}
+
+fn toggle_settings_panel() -> Result<(), SlakeError> {
+    let panel_element = get_element::<HtmlElement>("settings")?;
+    let visible = panel_element.style().get_property_value("display")? != "none";
+
+    match visible {
+        // clicking around in the panel takes the keys off the board
+        true => keyboard::focus_board(),
+        false => SETTINGS.with(|settings| fill_settings_panel(&settings.borrow()))?,
+    }
+
+    panel_element
+        .style()
+        .set_property("display", if visible { "none" } else { "block" })?;
+    Ok(())
+}
+
+enum DpadButton {
+    Blank,
+    Move(Direction),
+    Restart,
+}
+
+// the d-pad lives outside of the root container so that it survives re-rendering the board
+fn build_dpad() -> Result<(), SlakeError> {
+    let document = error::document()?;
+
+    let dpad_element = document
         .create_element("div")?
         .dyn_into::<HtmlDivElement>()?;
 
+    dpad_element.set_id("dpad");
+    dpad_element.set_class_name("dpad");
+
+    let buttons = [
+        DpadButton::Blank,
+        DpadButton::Move(Direction::Up),
+        DpadButton::Blank,
+        DpadButton::Move(Direction::Left),
+        DpadButton::Restart,
+        DpadButton::Move(Direction::Right),
+        DpadButton::Blank,
+        DpadButton::Move(Direction::Down),
+        DpadButton::Blank,
+    ];
+
+    for button in buttons {
+        let button_element = document
+            .create_element("div")?
+            .dyn_into::<HtmlDivElement>()?;
+
+        let label = match &button {
+            DpadButton::Blank => {
+                dpad_element.append_child(&button_element)?;
+                continue;
+            }
+            DpadButton::Move(Direction::Up) => "⬆️",
+            DpadButton::Move(Direction::Right) => "➡️",
+            DpadButton::Move(Direction::Down) => "⬇️",
+            DpadButton::Move(Direction::Left) => "⬅️",
+            // the dpad steers with the same four as the keys, even on a hex board
+            DpadButton::Move(Direction::UpRight) => "↗️",
+            DpadButton::Move(Direction::DownLeft) => "↙️",
+            DpadButton::Restart => "🔄",
+        };
+
+        button_element.set_class_name("dpad_button");
+        button_element.set_inner_text(label);
+
+        // pointerdown rather than click, so touches register immediately
+        let handle_press = Closure::wrap(Box::new(move |event: Event| {
+            match &button {
+                DpadButton::Move(direction) => {
+                    steer_from(InputSource::Touch, direction.clone());
+                }
+                DpadButton::Restart => restart_game(),
+                DpadButton::Blank => (),
+            }
+            event.prevent_default();
+        }) as Box<dyn FnMut(Event)>);
+
+        button_element.add_event_listener_with_callback(
+            "pointerdown",
+            handle_press.as_ref().unchecked_ref(),
+        )?;
+
+        // the buttons are never removed, so neither is their handler
+        handle_press.forget();
+
+        dpad_element.append_child(&button_element)?;
+    }
+
+    error::body()?.append_child(&dpad_element)?;
+
+    Ok(())
+}
+
+fn set_dpad_visible(visible: bool) -> Result<(), SlakeError> {
+    get_element::<HtmlElement>("dpad")?
+        .style()
+        .set_property("display", if visible { "inline-grid" } else { "none" })?;
+    Ok(())
+}
+
+fn toggle_hydra_mode() {
     GAME.with(|game| {
-        score_element.set_inner_text(&format!("🍆 {}", game.borrow().score));
-        high_score_element.set_inner_text(&format!("⭐ {}", game.borrow().high_score_display));
+        let mut game = game.borrow_mut();
+
+        if game.is_versus() {
+            log("Hydra mode doesn't work in versus");
+            return;
+        }
+
+        if game.high_score() < HYDRA_UNLOCK_SCORE {
+            log(&format!(
+                "Score {HYDRA_UNLOCK_SCORE} in a single run to unlock hydra mode"
+            ));
+            return;
+        }
+
+        game.hydra_mode = !game.hydra_mode;
+        log(if game.hydra_mode {
+            "Hydra mode on. Look out for golden fruit!"
+        } else {
+            "Hydra mode off"
+        });
+
+        game.restart();
     });
+}
+
+fn toggle_versus_mode() {
+    // versus is always on the empty board
+    LEVEL_INDEX.with(|index| index.set(0));
+
+    GAME.with(|game| {
+        let mut game = game.borrow_mut();
+
+        let versus = !game.is_versus();
+        game.set_versus(versus);
+        log(if versus && game.coop {
+            "Co-op mode on. Arrows and WASD together!"
+        } else if versus {
+            "Versus mode on. Arrows against WASD!"
+        } else {
+            "Versus mode off"
+        });
+    });
+}
 
-    info_element.append_child(&score_element)?;
-    info_element.append_child(&high_score_element)?;
+// for levels that don't come from `BUILTIN_LEVELS`
+fn play_level(level: &Level) {
+    LEVEL_INDEX.with(|index| index.set(0));
+    log(&format!("Level: {}", level.name));
 
-    root_container.append_child(&info_element)?;
+    for warning in level.validate() {
+        console::warn_1(&format!("Level warning: {warning}").into());
+    }
+
+    // community levels can be anything at all
+    if let Err(error) = GAME.with(|game| game.borrow_mut().try_load_level(level)) {
+        log(&format!("Can't play {}: {error}", level.name));
+        let _ = toast::show(&format!("Can't play {}: {error}", level.name));
+    }
+}
+
+fn cycle_level() {
+    let index = LEVEL_INDEX.with(|index| {
+        index.set((index.get() + 1) % (BUILTIN_LEVELS.len() + 1));
+        index.get()
+    });
+
+    GAME.with(|game| {
+        if index == 0 {
+            console::log_1(&"Level: Empty".into());
+            game.borrow_mut().unload_level();
+        } else {
+            // the built-in levels are covered by tests, so they always parse
+            let level = Level::parse(BUILTIN_LEVELS[index - 1]).unwrap_throw();
+            console::log_1(&format!("Level: {}", level.name).into());
+            game.borrow_mut().load_level(&level);
+        }
+    });
+}
+
+#[wasm_bindgen(start)]
+pub fn main() {
+    console::log_1(&"Starting...".into());
+
+    // whatever was put together before it went wrong is still on the page, so trying again
+    // means starting over
+    if let Err(error) = start() {
+        error::report(error, Some(error::reload));
+    }
+}
+
+fn start() -> Result<(), SlakeError> {
+    accessibility::build()?;
+    build_dpad()?;
+    build_settings_panel()?;
+    community::build_panel()?;
+    stats::build_panel()?;
+    analysis::build_panel()?;
+    highscores::build_panel()?;
+    dev_console::build_panel()?;
+    editor::build_panel()?;
+    embed::listen()?;
+    mouse::listen()?;
+    focus::listen()?;
+    layout::listen()?;
+    keyboard::listen()?;
+    sprites::load()?;
+    #[cfg(feature = "leaderboard")]
+    leaderboard::build_panel()?;
+    stats::load();
+
+    // also starts the tick interval
+    apply_settings(load_settings());
+    resume_saved_game();
+    // a replay link opens the viewer over everything. otherwise an embedding page starts the
+    // game itself, and the very first time there's a tutorial instead of the title
+    if !replay::watch_from_location() && !embed::is_embedded() {
+        match tutorial::seen() {
+            true => title::show(),
+            false => tutorial::start(),
+        }
+    }
+
+    let window = error::window()?;
+    HANDLE_KEYDOWN.with(|handle_keydown| {
+        window.add_event_listener_with_callback("keydown", handle_keydown.as_ref().unchecked_ref())
+    })?;
+    HANDLE_KEYUP.with(|handle_keyup| {
+        window.add_event_listener_with_callback("keyup", handle_keyup.as_ref().unchecked_ref())
+    })?;
+    // keys let go of somewhere else never come up here
+    let handle_blur = Closure::wrap(
+        Box::new(|| HELD_KEYS.with(|held| held.borrow_mut().clear())) as Box<dyn FnMut()>,
+    );
+    window.add_event_listener_with_callback("blur", handle_blur.as_ref().unchecked_ref())?;
+    handle_blur.forget();
+
+    // pagehide doesn't always fire on phones, where tabs get thrown away from the background
+    let handle_leave = Closure::wrap(Box::new(save_game) as Box<dyn FnMut()>);
+    for event in ["pagehide", "visibilitychange"] {
+        window.add_event_listener_with_callback(event, handle_leave.as_ref().unchecked_ref())?;
+    }
+    handle_leave.forget();
 
     Ok(())
 }
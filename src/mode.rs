@@ -0,0 +1,152 @@
+use crate::effects;
+
+use serde::{Deserialize, Serialize};
+
+// What kind of run it is. Classic is the game as it's always been. Time attack is as much as
+// you can eat before the clock runs out, and zen has no puddles, with the snake sliding over
+// itself instead of crashing. In closing in, rings of wall go up from the edges now and then
+// until there's hardly any room left. The daily challenge is a maze too, but the same one for
+// everybody all day, see `daily`. Waves is a round at a time, each with puddles to dodge and
+// fruit to eat to clear them, see `waves`. A race is over as soon as enough fruit's been eaten,
+// and goes by how quickly, so its high scores are the fastest times. Each one keeps its own
+// high scores.
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    TimeAttack {
+        seconds: usize,
+    },
+    Zen,
+    ClosingIn,
+    // a different maze of walls every run, see `generate`
+    Maze,
+    // the day's maze, every run
+    Daily,
+    Waves,
+    Race {
+        fruit: usize,
+    },
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 10] = [
+        GameMode::Classic,
+        GameMode::TimeAttack { seconds: 60 },
+        GameMode::TimeAttack { seconds: 120 },
+        GameMode::Zen,
+        GameMode::ClosingIn,
+        GameMode::Maze,
+        GameMode::Daily,
+        GameMode::Waves,
+        GameMode::Race { fruit: 15 },
+        GameMode::Race { fruit: 30 },
+    ];
+
+    // like "time_attack_60" or "race_30"
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Classic => "classic".to_string(),
+            GameMode::TimeAttack { seconds } => format!("time_attack_{seconds}"),
+            GameMode::Zen => "zen".to_string(),
+            GameMode::ClosingIn => "closing_in".to_string(),
+            GameMode::Maze => "maze".to_string(),
+            GameMode::Daily => "daily".to_string(),
+            GameMode::Waves => "waves".to_string(),
+            GameMode::Race { fruit } => format!("race_{fruit}"),
+        }
+    }
+
+    // only the modes in `ALL`, so there's no time attack or race with some other length
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            GameMode::Classic => "Classic".to_string(),
+            GameMode::TimeAttack { seconds } => format!("Time attack, {seconds} seconds"),
+            GameMode::Zen => "Zen".to_string(),
+            GameMode::ClosingIn => "Walls close in".to_string(),
+            GameMode::Maze => "Random maze".to_string(),
+            GameMode::Daily => "Daily challenge".to_string(),
+            GameMode::Waves => "Waves".to_string(),
+            GameMode::Race { fruit } => format!("Race to {fruit} fruit"),
+        }
+    }
+
+    // whether eating leaves puddles behind
+    pub fn has_hazards(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    // whether running into a snake is the end of the run
+    pub fn snakes_collide(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    // whether walls go up as the run goes on, see `SnakeGame::pending_walls`
+    pub fn closes_in(&self) -> bool {
+        *self == GameMode::ClosingIn
+    }
+
+    // whether runs start in a maze, see `generate::maze`
+    pub fn builds_maze(&self) -> bool {
+        matches!(self, GameMode::Maze | GameMode::Daily)
+    }
+
+    // whether the run goes in rounds, see `waves`
+    pub fn has_waves(&self) -> bool {
+        *self == GameMode::Waves
+    }
+
+    // whether every run starts from the same seed, see `SnakeGame::daily_seed`
+    pub fn is_daily(&self) -> bool {
+        *self == GameMode::Daily
+    }
+
+    // in a race, how much fruit wins it
+    pub fn fruit_target(&self) -> Option<usize> {
+        match self {
+            GameMode::Race { fruit } => Some(*fruit),
+            _ => None,
+        }
+    }
+
+    // How many ticks a time attack run lasts when each one takes `tick_ms`, rounded up so the
+    // run is never shorter than it says. `None` for the other modes.
+    pub fn time_limit(&self, tick_ms: i32) -> Option<usize> {
+        match self {
+            GameMode::TimeAttack { seconds } => {
+                Some(effects::ticks(seconds * 1000, tick_ms.max(1) as usize))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes() {
+        for mode in GameMode::ALL {
+            assert_eq!(GameMode::from_name(&mode.name()), Some(mode));
+        }
+        assert_eq!(GameMode::from_name("time_attack_30"), None);
+
+        assert_eq!(GameMode::ALL[1].time_limit(100), Some(600));
+        assert_eq!(GameMode::ALL[2].time_limit(70), Some(1715));
+        assert_eq!(GameMode::Zen.time_limit(100), None);
+
+        assert_eq!(
+            GameMode::from_name("race_30"),
+            Some(GameMode::Race { fruit: 30 })
+        );
+        assert_eq!(GameMode::Race { fruit: 15 }.fruit_target(), Some(15));
+        assert_eq!(GameMode::Race { fruit: 15 }.time_limit(100), None);
+        assert_eq!(GameMode::Waves.fruit_target(), None);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::SlakeError;
+use crate::geometry::Direction;
+use crate::geometry::Geometry;
+use crate::geometry::Vector;
+use crate::render;
+use crate::snake::SnakeGame;
+
+use std::cell::Cell;
+use std::ops::Range;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{Element, HtmlElement, MouseEvent};
+
+// Steering towards the mouse, for playing on a trackpad or with one hand. While the pointer's
+// over the board, every tick the snake turns whichever way takes its head closest to the tile
+// under it, as the crow flies on screen, and otherwise carries on. Once it gets there it goes
+// round and round it until the pointer moves. From there it's the same as pressing a key, so
+// in versus it's whoever picked the mouse in the settings that it steers.
+
+thread_local! {
+    // in the page's pixels, or `None` while it's off the board
+    static POINTER: Cell<Option<(f64, f64)>> = const { Cell::new(None) };
+}
+
+pub fn listen() -> Result<(), SlakeError> {
+    // the board goes in here, and gets thrown away and built again whenever its shape changes
+    let root = crate::get_element::<HtmlElement>("root")?;
+
+    let handle_move = Closure::wrap(Box::new(|event: MouseEvent| {
+        let pointer = (event.client_x() as f64, event.client_y() as f64);
+        POINTER.with(|shown| shown.set(Some(pointer)));
+    }) as Box<dyn FnMut(MouseEvent)>);
+    let handle_leave = Closure::wrap(Box::new(|| {
+        POINTER.with(|shown| shown.set(None));
+    }) as Box<dyn FnMut()>);
+
+    root.add_event_listener_with_callback("mousemove", handle_move.as_ref().unchecked_ref())?;
+    root.add_event_listener_with_callback("mouseleave", handle_leave.as_ref().unchecked_ref())?;
+    handle_move.forget();
+    handle_leave.forget();
+
+    Ok(())
+}
+
+// The tile at `point`, in tiles from the top left of the board as it's drawn, border and all.
+// `rows` are the ones that are shown, see `render::shown_rows`. `None` off the edge, or on the
+// border.
+pub fn tile_at(
+    geometry: &dyn Geometry,
+    width: isize,
+    rows: Range<isize>,
+    border: isize,
+    (x, y): (f64, f64),
+) -> Option<Vector> {
+    let top = rows.start;
+
+    // on a hex board the nearest middle is the hexagon it's in, and on a square one it's the
+    // square
+    let (pos, (left, top)) = rows
+        .flat_map(|row| (0..width).map(move |column| Vector(column, row)))
+        .map(|pos| {
+            let offset = geometry.tile_offset(&Vector(pos.0 + border, pos.1 - top + border));
+            (pos, offset)
+        })
+        .min_by(|(_, a), (_, b)| {
+            let apart = |(left, top): &(f64, f64)| (left + 0.5 - x).hypot(top + 0.5 - y);
+            apart(a).total_cmp(&apart(b))
+        })?;
+
+    // and past the edge, the nearest one's a tile away or more
+    let within = (left..=left + 1.0).contains(&x) && (top..=top + 1.0).contains(&y);
+    within.then_some(pos)
+}
+
+// Which way `player` should steer to get closer to `target`, if anywhere other than where
+// they're already going. Hex boards go through `Geometry::steer` like the keys do, so the
+// directions that can't be pressed straight from here are never picked.
+pub fn direction_towards(game: &SnakeGame, player: usize, target: &Vector) -> Option<Direction> {
+    let snake = game.snakes().iter().find(|snake| snake.player == player)?;
+    let geometry = game.geometry();
+
+    let target_offset = geometry.tile_offset(target);
+    let apart = |pressed: &Direction| {
+        let steered = snake.steered(pressed, geometry);
+        let (left, top) = geometry.tile_offset(&game.next_tile(snake.head(), &steered));
+        (left - target_offset.0).hypot(top - target_offset.1)
+    };
+    let straight_on = |pressed: &Direction| snake.steered(pressed, geometry) == *snake.direction();
+
+    let pressed = geometry
+        .directions()
+        .iter()
+        .filter(|pressed| snake.steered(pressed, geometry) != snake.direction().opposite())
+        // keeping on as it is wins a tie, so it doesn't wiggle
+        .min_by(|a, b| {
+            apart(a)
+                .total_cmp(&apart(b))
+                .then(straight_on(b).cmp(&straight_on(a)))
+        })?;
+
+    game.would_turn(player, pressed).then(|| pressed.clone())
+}
+
+// the board element, whichever style drew it
+fn board_element() -> Option<Element> {
+    let document = crate::error::document().ok()?;
+    document
+        .query_selector("#root .field_holder, #root .canvas_board")
+        .ok()
+        .flatten()
+}
+
+// Which way `player` should turn before the next tick, if the pointer's on the board and they
+// aren't already headed for it.
+pub fn direction(game: &SnakeGame, player: usize, border: isize) -> Option<Direction> {
+    let (x, y) = POINTER.with(|pointer| pointer.get())?;
+    let rect = board_element()?.get_bounding_client_rect();
+
+    let rows = render::shown_rows(game);
+    let (width, height) = game
+        .geometry()
+        .extent(game.width + border * 2, rows.end - rows.start + border * 2);
+    let point = (
+        (x - rect.left()) / rect.width() * width,
+        (y - rect.top()) / rect.height() * height,
+    );
+
+    let target = tile_at(game.geometry(), game.width, rows, border, point)?;
+    direction_towards(game, player, &target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Grid;
+    use crate::geometry::Hex;
+    use crate::geometry::Square;
+
+    #[test]
+    fn hit_testing() {
+        assert_eq!(tile_at(&Square, 5, 0..3, 0, (0.5, 0.5)), Some(Vector(0, 0)));
+        assert_eq!(tile_at(&Square, 5, 0..3, 0, (4.9, 2.1)), Some(Vector(4, 2)));
+        assert_eq!(tile_at(&Square, 5, 0..3, 0, (5.2, 1.0)), None);
+        // the border takes up a tile all the way round
+        assert_eq!(tile_at(&Square, 5, 0..3, 1, (0.5, 0.5)), None);
+        assert_eq!(tile_at(&Square, 5, 0..3, 1, (1.5, 1.5)), Some(Vector(0, 0)));
+        // only the floor that's shown is on the page
+        assert_eq!(tile_at(&Square, 5, 4..7, 0, (2.5, 1.5)), Some(Vector(2, 5)));
+
+        // the second row of hexagons starts half a tile along
+        assert_eq!(tile_at(&Hex, 5, 0..3, 0, (0.5, 1.1)), Some(Vector(0, 1)));
+        assert_eq!(tile_at(&Hex, 5, 0..3, 0, (0.2, 1.1)), None);
+        assert_eq!(tile_at(&Hex, 5, 0..3, 0, (1.6, 1.1)), Some(Vector(1, 1)));
+    }
+
+    #[test]
+    fn steering() {
+        // heading left from the right edge, see `SnakeGame::restart`
+        let mut game = SnakeGame::new(21, 15);
+        let head = game.snakes()[0].head().clone();
+        assert_eq!(head, Vector(19, 7));
+
+        // it's already going that way
+        assert_eq!(direction_towards(&game, 0, &Vector(2, 7)), None);
+        assert_eq!(
+            direction_towards(&game, 0, &Vector(15, 2)),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            direction_towards(&game, 0, &Vector(17, 12)),
+            Some(Direction::Down)
+        );
+        // straight behind, it goes round instead of reversing
+        assert_eq!(
+            direction_towards(&game, 0, &Vector(20, 7)),
+            Some(Direction::Up)
+        );
+        // and there's nobody else to steer
+        assert_eq!(direction_towards(&game, 1, &Vector(15, 2)), None);
+
+        // a turn that's already coming isn't asked for again
+        game.change_direction(Direction::Up);
+        assert_eq!(direction_towards(&game, 0, &Vector(15, 2)), None);
+
+        // heading left on a hex board, up goes up and to the left, and down's the same as
+        // down and to the left
+        game.grid = Grid::Hex;
+        game.restart();
+        assert_eq!(
+            direction_towards(&game, 0, &Vector(19, 2)),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            direction_towards(&game, 0, &Vector(14, 12)),
+            Some(Direction::Down)
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use crate::geometry::Topology;
+use crate::geometry::Vector;
+use crate::settings::Settings;
+use crate::snake::SnakeGame;
+
+use prng::Prng16;
+use std::cell::Cell;
+
+// Every week one mutator is picked, the same one for everybody, and played on top of whatever
+// mode is set up. The pick comes from the PRNG seeded with the ISO week, so it needs no server.
+// Scores with the mutator on go in a table of their own for that week, since they aren't
+// comparable to normal runs.
+
+const SCORES_PREFIX: &str = "slake_mutator:";
+
+// how many scores each week's table keeps
+pub const TABLE_LENGTH: usize = 5;
+
+pub const TINY_BOARD: (isize, isize) = (9, 7);
+
+// how many tiles away from a head you can still see through the fog
+pub const FOG_RADIUS: isize = 3;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Mutator {
+    DoubleHazards,
+    TinyBoard,
+    InvertedControls,
+    Fog,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 4] = [
+        Mutator::DoubleHazards,
+        Mutator::TinyBoard,
+        Mutator::InvertedControls,
+        Mutator::Fog,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutator::DoubleHazards => "double_hazards",
+            Mutator::TinyBoard => "tiny_board",
+            Mutator::InvertedControls => "inverted_controls",
+            Mutator::Fog => "fog",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Mutator::DoubleHazards => "Double hazards",
+            Mutator::TinyBoard => "Tiny board",
+            Mutator::InvertedControls => "Inverted controls",
+            Mutator::Fog => "Fog",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Mutator::DoubleHazards => "every fruit leaves two puddles behind",
+            Mutator::TinyBoard => "the board shrinks to 9 by 7",
+            Mutator::InvertedControls => "every direction steers the other way",
+            Mutator::Fog => "you can only see a few tiles around your head",
+        }
+    }
+
+    pub fn for_week(week: IsoWeek) -> Mutator {
+        let mut prng = Prng16::new([week.year as u16, week.week as u16]);
+        Mutator::ALL[prng.next().unwrap() as usize % Mutator::ALL.len()]
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct IsoWeek {
+    pub year: i32,
+    // from 1 to 53
+    pub week: u32,
+}
+
+impl IsoWeek {
+    // the week a time falls in, in milliseconds since the Unix epoch like `Date.now()`. weeks are
+    // in UTC, so everyone switches at the same moment
+    pub fn from_unix_ms(ms: f64) -> IsoWeek {
+        IsoWeek::from_days((ms / 86_400_000.0).floor() as i64)
+    }
+
+    // ISO weeks start on Monday, and belong to whichever year their Thursday is in
+    pub fn from_days(days: i64) -> IsoWeek {
+        // the epoch was a Thursday
+        let weekday = (days + 3).rem_euclid(7);
+        let thursday = days - weekday + 3;
+
+        let (year, _, _) = civil_from_days(thursday);
+        let day_of_year = thursday - days_from_civil(year, 1, 1);
+
+        IsoWeek {
+            year: year as i32,
+            week: (day_of_year / 7 + 1) as u32,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}-W{:02}", self.year, self.week)
+    }
+}
+
+// days since the epoch, from Howard Hinnant's `days_from_civil`
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    // counting from March, so the leap day is at the end
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// the other way around, as (year, month, day)
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+
+    // January and February are at the end of the March-based year
+    match month_index {
+        0..=9 => (era * 400 + year_of_era, month_index + 3, day),
+        _ => (era * 400 + year_of_era + 1, month_index - 9, day),
+    }
+}
+
+// the best scores with the mutator on, highest first
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct ScoreTable {
+    pub scores: Vec<usize>,
+}
+
+impl ScoreTable {
+    // Adds a score if it makes the table, returning where it placed from 0.
+    pub fn add(&mut self, score: usize) -> Option<usize> {
+        let place = self.scores.iter().position(|old| score > *old);
+        let place = place.unwrap_or(self.scores.len());
+
+        if place >= TABLE_LENGTH {
+            return None;
+        }
+
+        self.scores.insert(place, score);
+        self.scores.truncate(TABLE_LENGTH);
+        Some(place)
+    }
+
+    // saved as `scores=12,9,4`. anything that doesn't parse gets left out
+    pub fn parse(source: &str) -> ScoreTable {
+        let mut scores = source
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| key.trim() == "scores")
+            .flat_map(|(_, value)| value.split(','))
+            .filter_map(|score| score.trim().parse().ok())
+            .collect::<Vec<usize>>();
+
+        scores.sort_unstable_by(|a, b| b.cmp(a));
+        scores.truncate(TABLE_LENGTH);
+
+        ScoreTable { scores }
+    }
+
+    pub fn serialize(&self) -> String {
+        let scores = self
+            .scores
+            .iter()
+            .map(|score| score.to_string())
+            .collect::<Vec<_>>();
+
+        format!("scores={}\n", scores.join(","))
+    }
+}
+
+// The tiles hidden by fog: anything further than `FOG_RADIUS` from every head, measured as the
+// bigger of the two distances. Snakes stay visible, so you always know where your body is.
+pub fn fogged_tiles(game: &SnakeGame) -> Vec<Vector> {
+    let distance = |a: isize, b: isize, size: isize| {
+        let distance = (a - b).abs();
+        match game.topology {
+            Topology::Walls => distance,
+            // you can see across the edge too
+            Topology::Wrap => distance.min(size - distance),
+        }
+    };
+
+    let is_visible = |pos: &Vector| {
+        game.snakes().iter().any(|snake| {
+            let head = snake.head();
+            distance(head.0, pos.0, game.width).max(distance(head.1, pos.1, game.height))
+                <= FOG_RADIUS
+        })
+    };
+
+    (0..game.height)
+        .flat_map(|y| (0..game.width).map(move |x| Vector(x, y)))
+        .filter(|pos| !is_visible(pos))
+        .collect()
+}
+
+thread_local! {
+    // worked out once, so the mutator doesn't change in the middle of a visit
+    static THIS_WEEK: Cell<Option<IsoWeek>> = const { Cell::new(None) };
+}
+
+pub fn this_week() -> IsoWeek {
+    THIS_WEEK.with(|this_week| match this_week.get() {
+        Some(week) => week,
+        None => {
+            let week = IsoWeek::from_unix_ms(js_sys::Date::now());
+            this_week.set(Some(week));
+            week
+        }
+    })
+}
+
+// this week's mutator, if the settings have it turned on. the daily challenge is the same for
+// everybody, so it goes without
+pub fn active(settings: &Settings) -> Option<Mutator> {
+    (settings.mutator && !settings.mode.is_daily()).then(|| Mutator::for_week(this_week()))
+}
+
+// the board size to play on, with the tiny board mutator and the daily challenge taken into
+// account
+pub fn board_dimensions(settings: &Settings) -> (isize, isize) {
+    if settings.mode.is_daily() {
+        return crate::daily::today().dimensions();
+    }
+
+    match active(settings) {
+        Some(Mutator::TinyBoard) => TINY_BOARD,
+        _ => settings.board_dimensions(),
+    }
+}
+
+// like "slake_mutator:2026-W42:fog"
+fn scores_key() -> String {
+    let week = this_week();
+    format!(
+        "{SCORES_PREFIX}{}:{}",
+        week.name(),
+        Mutator::for_week(week).name()
+    )
+}
+
+pub fn scores() -> ScoreTable {
+    crate::local_storage()
+        .and_then(|storage| storage.get_item(&scores_key()).ok().flatten())
+        .map(|saved| ScoreTable::parse(&saved))
+        .unwrap_or_default()
+}
+
+pub fn record_score(score: usize) {
+    let mut table = scores();
+
+    if table.add(score).is_none() {
+        return;
+    }
+
+    if let Some(storage) = crate::local_storage() {
+        // the table is just for fun, so failing to save it doesn't matter much
+        let _ = storage.set_item(&scores_key(), &table.serialize());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iso_weeks() {
+        let week = |year, month, day| IsoWeek::from_days(days_from_civil(year, month, day));
+
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        for (year, month, day) in [(1970, 1, 1), (2024, 2, 29), (2026, 10, 14), (2000, 3, 1)] {
+            assert_eq!(
+                civil_from_days(days_from_civil(year, month, day)),
+                (year, month, day)
+            );
+        }
+        assert_eq!(
+            week(1970, 1, 1),
+            IsoWeek {
+                year: 1970,
+                week: 1
+            }
+        );
+        assert_eq!(week(2026, 10, 14).name(), "2026-W42");
+
+        // the first few days of January can still be in last year's last week, and the end of
+        // December in next year's first
+        assert_eq!(week(2021, 1, 1).name(), "2020-W53");
+        assert_eq!(week(2024, 12, 30).name(), "2025-W01");
+        assert_eq!(week(2024, 2, 29).name(), "2024-W09");
+
+        // a Sunday late in the day is still the same week as its Monday
+        let monday = days_from_civil(2026, 10, 12) as f64 * 86_400_000.0;
+        assert_eq!(
+            IsoWeek::from_unix_ms(monday + 6.9 * 86_400_000.0),
+            IsoWeek::from_unix_ms(monday)
+        );
+    }
+
+    #[test]
+    fn score_table() {
+        let mut table = ScoreTable::default();
+
+        for score in [4, 9, 1, 12, 9, 3] {
+            table.add(score);
+        }
+        assert_eq!(table.scores, vec![12, 9, 9, 4, 3]);
+        assert_eq!(table.add(2), None);
+        assert_eq!(table.add(10), Some(1));
+
+        assert_eq!(ScoreTable::parse(&table.serialize()), table);
+        assert_eq!(ScoreTable::parse("scores=3,lots,7").scores, vec![7, 3]);
+        assert_eq!(ScoreTable::parse("").scores, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn fog() {
+        let game = SnakeGame::new(21, 15);
+
+        // the head starts at 19, 7, next to the right edge
+        let fogged = fogged_tiles(&game);
+        assert!(!fogged.contains(&Vector(16, 4)));
+        assert!(fogged.contains(&Vector(15, 7)));
+        assert!(fogged.contains(&Vector(0, 7)));
+        assert_eq!(fogged.len(), 21 * 15 - 5 * 7);
+    }
+}
@@ -0,0 +1,781 @@
+use crate::error::SlakeError;
+use crate::food::FoodCount;
+use crate::food::Growth;
+use crate::geometry::Direction;
+use crate::mode::GameMode;
+use crate::random;
+use crate::rules::RuleSet;
+use crate::snake;
+use crate::snake::SnakeGame;
+use crate::toast;
+use crate::version::Compatibility;
+use crate::version::Fingerprint;
+use crate::GAME;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{MessageEvent, RtcDataChannel, RtcPeerConnection, WebSocket};
+
+// Online versus between two browsers, through a relay server set with `data-relay` on the root
+// element. The relay only has to pass every text message from one browser on to the other one
+// in the same room, so rooms are up to the server and its urls. Without a relay, the messages
+// can go straight from one browser to the other instead, see `peer`.
+//
+// Both sides run the whole game themselves, in lockstep. Everything that changes the game is an
+// input for a numbered frame, sent a few frames ahead, and a frame only runs once both players'
+// inputs for it are in. Starting from the same seed and rules, the two games then play out
+// exactly the same, without ever sending the board.
+//
+// Messages are single lines of text:
+//
+//     hello <nonce>             sent on connecting, and once more in reply. the lower nonce hosts
+//     start <seed> <rules...>   from the host, see `Rules`
+//     frame <number> <inputs>   one for every frame from each side, see `Input`
+
+// frames between pressing a key and it taking effect, so inputs have time to arrive
+pub const INPUT_DELAY: usize = 3;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Input {
+    Turn(Direction),
+    Restart,
+}
+
+impl Input {
+    fn to_char(&self) -> char {
+        match self {
+            Input::Turn(Direction::Up) => 'u',
+            Input::Turn(Direction::Right) => 'r',
+            Input::Turn(Direction::Down) => 'd',
+            Input::Turn(Direction::Left) => 'l',
+            // the two only hex boards have. players steer with the four keys there too, so these
+            // are only for anything that picks its own way, like a bot
+            Input::Turn(Direction::UpRight) => 'e',
+            Input::Turn(Direction::DownLeft) => 'z',
+            Input::Restart => '!',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Input> {
+        match c {
+            'u' => Some(Input::Turn(Direction::Up)),
+            'r' => Some(Input::Turn(Direction::Right)),
+            'd' => Some(Input::Turn(Direction::Down)),
+            'l' => Some(Input::Turn(Direction::Left)),
+            'e' => Some(Input::Turn(Direction::UpRight)),
+            'z' => Some(Input::Turn(Direction::DownLeft)),
+            '!' => Some(Input::Restart),
+            _ => None,
+        }
+    }
+}
+
+// Everything about the game both sides need to agree on before the first frame. Whatever the
+// host has set up wins.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Rules {
+    pub seed: [u16; 2],
+    pub special_food: bool,
+    pub food_away_from_heads: bool,
+    pub food_count: FoodCount,
+    pub hazard_lifetime: Option<usize>,
+    pub starve_ticks: Option<usize>,
+    pub energy_drain: Option<usize>,
+    pub double_hazards: bool,
+    pub rule_set: RuleSet,
+    pub hazard_stun: bool,
+    pub grace_ticks: usize,
+    pub countdown_ticks: usize,
+    pub tick_ms: usize,
+    pub mode: GameMode,
+    pub time_limit: Option<usize>,
+    // see `SnakeGame::coop`
+    pub coop: bool,
+    pub enemy_count: usize,
+    pub growth: Growth,
+    // the board, and the build the host is on
+    pub fingerprint: Fingerprint,
+}
+
+impl Rules {
+    pub fn of(game: &SnakeGame, seed: [u16; 2]) -> Rules {
+        Rules {
+            seed,
+            special_food: game.special_food,
+            food_away_from_heads: game.food_away_from_heads,
+            food_count: game.food_count,
+            hazard_lifetime: game.hazard_lifetime,
+            starve_ticks: game.starve_ticks,
+            energy_drain: game.energy_drain,
+            double_hazards: game.double_hazards,
+            rule_set: game.rules,
+            hazard_stun: game.hazard_stun,
+            grace_ticks: game.grace_ticks,
+            countdown_ticks: game.countdown_ticks,
+            tick_ms: game.tick_ms,
+            mode: game.mode(),
+            time_limit: game.time_limit,
+            coop: game.coop,
+            enemy_count: game.enemy_count,
+            growth: game.growth,
+            fingerprint: Fingerprint::of(game),
+        }
+    }
+
+    // sets up a fresh versus game to play by these rules
+    pub fn apply(&self, game: &mut SnakeGame) {
+        game.special_food = self.special_food;
+        game.food_away_from_heads = self.food_away_from_heads;
+        game.food_count = self.food_count;
+        game.hazard_lifetime = self.hazard_lifetime;
+        game.starve_ticks = self.starve_ticks;
+        game.energy_drain = self.energy_drain;
+        game.double_hazards = self.double_hazards;
+        game.rules = self.rule_set;
+        game.hazard_stun = self.hazard_stun;
+        game.grace_ticks = self.grace_ticks;
+        game.countdown_ticks = self.countdown_ticks;
+        game.tick_ms = self.tick_ms;
+        game.time_limit = self.time_limit;
+        game.coop = self.coop;
+        game.enemy_count = self.enemy_count;
+        game.growth = self.growth;
+        game.topology = self.fingerprint.topology;
+        game.grid = self.fingerprint.grid;
+
+        game.set_mode(self.mode);
+        game.resize(self.fingerprint.width, self.fingerprint.height);
+        game.set_versus(true);
+        game.restart_with_seed(self.seed);
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Message {
+    Hello(u32),
+    Start(Rules),
+    Frame(usize, Vec<Input>),
+}
+
+impl Message {
+    pub fn parse(source: &str) -> Option<Message> {
+        let (kind, rest) = source.trim().split_once(' ')?;
+
+        match kind {
+            "hello" => Some(Message::Hello(rest.parse().ok()?)),
+            "start" => {
+                // the fingerprint has spaces in it, so it goes last and takes the rest
+                let fields = rest.splitn(19, ' ').collect::<Vec<_>>();
+                let [seed, special_food, food_away_from_heads, food_count, hazard_lifetime, starve_ticks, energy_drain, double_hazards, rule_set, hazard_stun, grace_ticks, countdown_ticks, tick_ms, mode, time_limit, coop, enemy_count, growth, fingerprint] =
+                    fields[..]
+                else {
+                    return None;
+                };
+
+                let (a, b) = seed.split_once(',')?;
+                let optional = |value: &str| match value {
+                    "none" => Some(None),
+                    _ => value.parse().ok().map(Some),
+                };
+
+                Some(Message::Start(Rules {
+                    seed: [a.parse().ok()?, b.parse().ok()?],
+                    special_food: special_food.parse().ok()?,
+                    food_away_from_heads: food_away_from_heads.parse().ok()?,
+                    food_count: FoodCount::from_name(food_count)?,
+                    hazard_lifetime: optional(hazard_lifetime)?,
+                    starve_ticks: optional(starve_ticks)?,
+                    energy_drain: optional(energy_drain)?,
+                    double_hazards: double_hazards.parse().ok()?,
+                    rule_set: RuleSet::from_name(rule_set)?,
+                    hazard_stun: hazard_stun.parse().ok()?,
+                    grace_ticks: grace_ticks.parse().ok()?,
+                    countdown_ticks: countdown_ticks.parse().ok()?,
+                    tick_ms: tick_ms.parse().ok()?,
+                    mode: GameMode::from_name(mode)?,
+                    time_limit: optional(time_limit)?,
+                    coop: coop.parse().ok()?,
+                    enemy_count: enemy_count.parse().ok()?,
+                    growth: Growth::from_name(growth)?,
+                    fingerprint: Fingerprint::parse(fingerprint)?,
+                }))
+            }
+            "frame" => {
+                let (frame, inputs) = rest.split_once(' ')?;
+                let inputs = match inputs {
+                    "-" => vec![],
+                    _ => inputs
+                        .chars()
+                        .map(Input::from_char)
+                        .collect::<Option<_>>()?,
+                };
+
+                Some(Message::Frame(frame.parse().ok()?, inputs))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        match self {
+            Message::Hello(nonce) => format!("hello {nonce}"),
+            Message::Start(rules) => {
+                let optional = |value: Option<usize>| {
+                    value.map_or("none".to_string(), |value| value.to_string())
+                };
+
+                format!(
+                    "start {},{} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+                    rules.seed[0],
+                    rules.seed[1],
+                    rules.special_food,
+                    rules.food_away_from_heads,
+                    rules.food_count.name(),
+                    optional(rules.hazard_lifetime),
+                    optional(rules.starve_ticks),
+                    optional(rules.energy_drain),
+                    rules.double_hazards,
+                    rules.rule_set.name(),
+                    rules.hazard_stun,
+                    rules.grace_ticks,
+                    rules.countdown_ticks,
+                    rules.tick_ms,
+                    rules.mode.name(),
+                    optional(rules.time_limit),
+                    rules.coop,
+                    rules.enemy_count,
+                    rules.growth.name(),
+                    rules.fingerprint.serialize()
+                )
+            }
+            Message::Frame(frame, inputs) if inputs.is_empty() => format!("frame {frame} -"),
+            Message::Frame(frame, inputs) => format!(
+                "frame {frame} {}",
+                inputs.iter().map(Input::to_char).collect::<String>()
+            ),
+        }
+    }
+}
+
+// Keeps track of both players' inputs by frame, and hands them out once both are in.
+pub struct Lockstep {
+    // 0 for the host, 1 for the other side
+    pub local_player: usize,
+    // the next frame to run
+    frame: usize,
+    // pressed since the last frame ran, to be sent for `INPUT_DELAY` frames from now
+    pending: Vec<Input>,
+    inputs: [BTreeMap<usize, Vec<Input>>; 2],
+}
+
+impl Lockstep {
+    // also returns the first few frames to send, which nobody could have pressed anything for
+    pub fn new(local_player: usize) -> (Lockstep, Vec<Message>) {
+        let mut lockstep = Lockstep {
+            local_player,
+            frame: 0,
+            pending: vec![],
+            inputs: [BTreeMap::new(), BTreeMap::new()],
+        };
+
+        let messages = (0..INPUT_DELAY)
+            .map(|frame| {
+                lockstep.inputs[local_player].insert(frame, vec![]);
+                Message::Frame(frame, vec![])
+            })
+            .collect();
+
+        (lockstep, messages)
+    }
+
+    pub fn queue(&mut self, input: Input) {
+        self.pending.push(input);
+    }
+
+    // the other side's inputs for a frame
+    pub fn receive(&mut self, frame: usize, inputs: Vec<Input>) {
+        // anything for a frame that already ran is a broken message, and can't be used anyway
+        if frame >= self.frame {
+            self.inputs[1 - self.local_player].insert(frame, inputs);
+        }
+    }
+
+    // Takes every player's inputs for the next frame, in player order so both sides apply them
+    // the same way, along with the message for the frame our pending inputs went to. `None`
+    // while the other side's inputs haven't arrived yet.
+    pub fn step(&mut self) -> Option<(Vec<(usize, Input)>, Message)> {
+        if !self
+            .inputs
+            .iter()
+            .all(|inputs| inputs.contains_key(&self.frame))
+        {
+            return None;
+        }
+
+        let frame_inputs = self
+            .inputs
+            .iter_mut()
+            .enumerate()
+            .flat_map(|(player, inputs)| {
+                let inputs = inputs.remove(&self.frame).unwrap();
+                inputs.into_iter().map(move |input| (player, input))
+            })
+            .collect();
+
+        let later_frame = self.frame + INPUT_DELAY;
+        let pending = std::mem::take(&mut self.pending);
+        self.inputs[self.local_player].insert(later_frame, pending.clone());
+
+        self.frame += 1;
+
+        Some((frame_inputs, Message::Frame(later_frame, pending)))
+    }
+}
+
+// one frame's worth of inputs, before the tick
+pub fn apply(game: &mut SnakeGame, inputs: &[(usize, Input)]) {
+    for (player, input) in inputs {
+        match input {
+            Input::Turn(direction) => game.change_player_direction(*player, direction.clone()),
+            Input::Restart => game.restart(),
+        }
+    }
+}
+
+// what the messages go over
+enum Channel {
+    Relay(WebSocket),
+    Peer(RtcPeerConnection, RtcDataChannel),
+}
+
+impl Channel {
+    fn send(&self, message: &Message) {
+        // a failed send means the channel is closing, which `onclose` handles
+        let _ = match self {
+            Channel::Relay(socket) => socket.send_with_str(&message.serialize()),
+            Channel::Peer(_, channel) => channel.send_with_str(&message.serialize()),
+        };
+    }
+
+    fn close(&self) {
+        match self {
+            Channel::Relay(socket) => {
+                socket.set_onclose(None);
+                let _ = socket.close();
+            }
+            Channel::Peer(peer, channel) => {
+                channel.set_onclose(None);
+                channel.close();
+                peer.close();
+            }
+        }
+    }
+}
+
+struct Connection {
+    channel: Channel,
+    nonce: u32,
+    // whether we've said hello back yet, since the first hello can go out before anyone's there
+    greeted: bool,
+    // `None` until both sides have agreed on the rules
+    lockstep: Option<Lockstep>,
+}
+
+thread_local! {
+    static CONNECTION: RefCell<Option<Connection>> = const { RefCell::new(None) };
+}
+
+fn relay_url() -> Option<String> {
+    crate::error::document()
+        .ok()?
+        .get_element_by_id("root")?
+        .get_attribute("data-relay")
+}
+
+// whether there's an online game going, or one being set up. the local game is off limits until
+// it's over
+pub fn is_active() -> bool {
+    CONNECTION.with(|connection| connection.borrow().is_some())
+}
+
+// which of the two snakes is ours, once the game's started
+pub fn local_player() -> Option<usize> {
+    CONNECTION.with(|connection| {
+        connection
+            .borrow()
+            .as_ref()
+            .and_then(|connection| connection.lockstep.as_ref())
+            .map(|lockstep| lockstep.local_player)
+    })
+}
+
+fn notify(message: &str) {
+    // the status is nice to have, not worth stopping over
+    let _ = toast::show(message);
+}
+
+pub fn toggle() {
+    if is_active() {
+        disconnect("Left online versus");
+        return;
+    }
+
+    let Some(url) = relay_url() else {
+        notify("This page doesn't have a server for online versus, press P to play without one.");
+        return;
+    };
+
+    if let Err(error) = connect(&url) {
+        notify(&format!("Couldn't connect: {error:?}"));
+    }
+}
+
+fn connect(url: &str) -> Result<(), SlakeError> {
+    let socket = WebSocket::new(url)?;
+    let nonce = new_nonce();
+    let (handle_open, handle_message, handle_close) =
+        handlers(Channel::Relay(socket.clone()), nonce);
+
+    socket.set_onopen(Some(handle_open.as_ref().unchecked_ref()));
+    socket.set_onmessage(Some(handle_message.as_ref().unchecked_ref()));
+    socket.set_onclose(Some(handle_close.as_ref().unchecked_ref()));
+
+    // connecting is rare enough that leaking a few handlers each time doesn't matter
+    handle_open.forget();
+    handle_message.forget();
+    handle_close.forget();
+
+    set_connection(Channel::Relay(socket), nonce);
+    Ok(())
+}
+
+// Plays over a data channel straight to the other browser, once it opens. `peer` sets it up.
+pub fn connect_peer(peer: RtcPeerConnection, channel: RtcDataChannel) {
+    let nonce = new_nonce();
+    let opener = Channel::Peer(peer.clone(), channel.clone());
+    let (handle_open, handle_message, handle_close) = handlers(opener, nonce);
+
+    channel.set_onopen(Some(handle_open.as_ref().unchecked_ref()));
+    channel.set_onmessage(Some(handle_message.as_ref().unchecked_ref()));
+    channel.set_onclose(Some(handle_close.as_ref().unchecked_ref()));
+
+    handle_open.forget();
+    handle_message.forget();
+    handle_close.forget();
+
+    set_connection(Channel::Peer(peer, channel), nonce);
+}
+
+fn new_nonce() -> u32 {
+    ((random::get_u16() as u32) << 16) | random::get_u16() as u32
+}
+
+fn set_connection(channel: Channel, nonce: u32) {
+    CONNECTION.with(|connection| {
+        *connection.borrow_mut() = Some(Connection {
+            channel,
+            nonce,
+            greeted: false,
+            lockstep: None,
+        })
+    });
+}
+
+type Handlers = (
+    Closure<dyn FnMut()>,
+    Closure<dyn FnMut(MessageEvent)>,
+    Closure<dyn FnMut()>,
+);
+
+// the same for either kind of channel, which both have an open, a message, and a close
+fn handlers(opener: Channel, nonce: u32) -> Handlers {
+    let handle_open = Closure::wrap(Box::new(move || {
+        opener.send(&Message::Hello(nonce));
+        notify("Waiting for the other player...");
+    }) as Box<dyn FnMut()>);
+
+    let handle_message = Closure::wrap(Box::new(|event: MessageEvent| {
+        if let Some(text) = event.data().as_string() {
+            handle_message(&text);
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+
+    let handle_close = Closure::wrap(Box::new(|| {
+        if is_active() {
+            disconnect("Lost the connection to the other player");
+        }
+    }) as Box<dyn FnMut()>);
+
+    (handle_open, handle_message, handle_close)
+}
+
+pub fn disconnect(reason: &str) {
+    let Some(connection) = CONNECTION.with(|connection| connection.borrow_mut().take()) else {
+        return;
+    };
+
+    connection.channel.close();
+    notify(reason);
+}
+
+fn start(connection: &mut Connection, local_player: usize) {
+    let (lockstep, messages) = Lockstep::new(local_player);
+
+    for message in messages.iter() {
+        connection.channel.send(message);
+    }
+
+    connection.lockstep = Some(lockstep);
+    notify(&format!(
+        "Online versus: you're player {}",
+        local_player + 1
+    ));
+}
+
+fn handle_message(text: &str) {
+    let mut failure = None;
+
+    CONNECTION.with(|connection| {
+        let mut connection = connection.borrow_mut();
+        let Some(connection) = connection.as_mut() else {
+            return;
+        };
+
+        match Message::parse(text) {
+            Some(Message::Hello(nonce)) if connection.lockstep.is_none() => {
+                if !connection.greeted {
+                    connection.greeted = true;
+                    connection.channel.send(&Message::Hello(connection.nonce));
+                }
+
+                // on the off chance both picked the same number, whoever sends start first wins
+                if connection.nonce < nonce {
+                    let seed = [random::get_u16(), random::get_u16()];
+                    let rules = GAME.with(|game| {
+                        let mut game = game.borrow_mut();
+                        let rules = Rules::of(&game, seed);
+                        rules.apply(&mut game);
+                        rules
+                    });
+
+                    connection.channel.send(&Message::Start(rules));
+                    start(connection, 0);
+                }
+            }
+            Some(Message::Start(rules)) if connection.lockstep.is_none() => {
+                let current = Fingerprint {
+                    grid: rules.fingerprint.grid,
+                    ..Fingerprint::new(
+                        rules.fingerprint.width,
+                        rules.fingerprint.height,
+                        rules.fingerprint.topology,
+                    )
+                };
+
+                // whatever a host sends, it can't be allowed to crash the game
+                if let Err(error) = snake::check_board(current.width, current.height) {
+                    failure = Some(format!("Can't play the other player: {error}"));
+                    return;
+                }
+
+                // another build is only fine as long as the rules are the same
+                match rules.fingerprint.check(&current) {
+                    Compatibility::Compatible => (),
+                    Compatibility::OtherBuild(warning) => notify(&format!("Heads up: {warning}")),
+                    Compatibility::Incompatible(reason) => {
+                        failure = Some(format!("Can't play the other player: {reason}"));
+                        return;
+                    }
+                }
+
+                GAME.with(|game| rules.apply(&mut game.borrow_mut()));
+                start(connection, 1);
+            }
+            Some(Message::Frame(frame, inputs)) => {
+                if let Some(lockstep) = connection.lockstep.as_mut() {
+                    lockstep.receive(frame, inputs);
+                }
+            }
+            _ => (),
+        }
+    });
+
+    if let Some(failure) = failure {
+        disconnect(&failure);
+    }
+}
+
+pub fn steer(direction: Direction) {
+    queue(Input::Turn(direction));
+}
+
+pub fn restart() {
+    queue(Input::Restart);
+}
+
+fn queue(input: Input) {
+    CONNECTION.with(|connection| {
+        if let Some(lockstep) = connection
+            .borrow_mut()
+            .as_mut()
+            .and_then(|connection| connection.lockstep.as_mut())
+        {
+            lockstep.queue(input);
+        }
+    });
+}
+
+// The inputs for the next tick, or `None` if the game has to wait, for the other player to
+// connect or for their inputs to arrive.
+pub fn next_frame() -> Option<Vec<(usize, Input)>> {
+    CONNECTION.with(|connection| {
+        let mut connection = connection.borrow_mut();
+        let connection = connection.as_mut()?;
+        let (inputs, message) = connection.lockstep.as_mut()?.step()?;
+
+        connection.channel.send(&message);
+        Some(inputs)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodKind;
+    use crate::geometry::Topology;
+
+    #[test]
+    fn messages() {
+        let rules = Rules {
+            seed: [123, 4567],
+            special_food: true,
+            food_away_from_heads: true,
+            food_count: FoodCount::UpTo(3),
+            hazard_lifetime: None,
+            starve_ticks: Some(40),
+            energy_drain: Some(8),
+            double_hazards: false,
+            rule_set: RuleSet::CLASSIC,
+            hazard_stun: true,
+            grace_ticks: 5,
+            countdown_ticks: 10,
+            tick_ms: 80,
+            mode: GameMode::TimeAttack { seconds: 120 },
+            time_limit: Some(1200),
+            coop: true,
+            enemy_count: 2,
+            growth: Growth::default().with(FoodKind::Speed, 2),
+            fingerprint: Fingerprint::new(21, 15, Topology::Wrap),
+        };
+
+        let messages = [
+            Message::Hello(99),
+            Message::Start(rules),
+            Message::Frame(7, vec![]),
+            Message::Frame(8, vec![Input::Turn(Direction::Up), Input::Restart]),
+        ];
+
+        for message in messages {
+            assert_eq!(Message::parse(&message.serialize()), Some(message));
+        }
+
+        assert_eq!(Message::parse("frame 3 ux"), None);
+        assert_eq!(Message::parse("start 1,2 true"), None);
+        assert_eq!(Message::parse("goodbye 1"), None);
+    }
+
+    #[test]
+    fn lockstep() {
+        let (mut host, host_messages) = Lockstep::new(0);
+        let (mut guest, guest_messages) = Lockstep::new(1);
+        assert_eq!(host_messages.len(), INPUT_DELAY);
+
+        // nothing runs until the other side's first frames are in
+        assert!(host.step().is_none());
+
+        let deliver = |lockstep: &mut Lockstep, messages: Vec<Message>| {
+            for message in messages {
+                if let Message::Frame(frame, inputs) = message {
+                    lockstep.receive(frame, inputs);
+                }
+            }
+        };
+        deliver(&mut host, guest_messages);
+        deliver(&mut guest, host_messages);
+
+        host.queue(Input::Turn(Direction::Up));
+        guest.queue(Input::Turn(Direction::Down));
+
+        let mut host_seen = vec![];
+        let mut guest_seen = vec![];
+
+        for _ in 0..INPUT_DELAY + 1 {
+            let (inputs, message) = host.step().unwrap();
+            host_seen.push(inputs);
+            deliver(&mut guest, vec![message]);
+
+            let (inputs, message) = guest.step().unwrap();
+            guest_seen.push(inputs);
+            deliver(&mut host, vec![message]);
+        }
+
+        // both sides see every input on the same frame, in the same order
+        assert_eq!(host_seen, guest_seen);
+        assert_eq!(
+            host_seen[INPUT_DELAY],
+            vec![
+                (0, Input::Turn(Direction::Up)),
+                (1, Input::Turn(Direction::Down))
+            ]
+        );
+        assert!(host_seen[..INPUT_DELAY]
+            .iter()
+            .all(|inputs| inputs.is_empty()));
+
+        // the host can only get as far ahead as the guest has sent frames for
+        for _ in 0..INPUT_DELAY {
+            assert!(host.step().is_some());
+        }
+        assert!(host.step().is_none());
+        assert_eq!(host.frame, INPUT_DELAY * 2 + 1);
+    }
+
+    #[test]
+    fn same_rules_same_game() {
+        let mut host = SnakeGame::new(21, 15);
+        host.starve_ticks = Some(20);
+        host.set_mode(GameMode::Zen);
+        let rules = Rules::of(&host, [5, 6]);
+        rules.apply(&mut host);
+
+        let play = |game: &mut SnakeGame| {
+            for tick in 0..30 {
+                let inputs = match tick {
+                    2 => vec![
+                        (0, Input::Turn(Direction::Up)),
+                        (1, Input::Turn(Direction::Down)),
+                    ],
+                    _ => vec![],
+                };
+
+                apply(game, &inputs);
+                game.tick();
+            }
+        };
+        play(&mut host);
+
+        // each browser has random numbers of its own, so the guest starts over from the seed
+        let mut guest = SnakeGame::new(9, 7);
+        rules.apply(&mut guest);
+        play(&mut guest);
+
+        assert!(guest.is_versus());
+        assert_eq!(guest.width, 21);
+        assert_eq!(guest.starve_ticks, Some(20));
+        assert_eq!(guest.mode(), GameMode::Zen);
+        assert_eq!(guest.snakes()[0].body, host.snakes()[0].body);
+        assert_eq!(guest.snakes()[1].body, host.snakes()[1].body);
+        assert_eq!(guest.food(), host.food());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::error::SlakeError;
+use crate::net;
+use crate::toast;
+
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{
+    RtcConfiguration, RtcDataChannelEvent, RtcIceGatheringState, RtcIceServer, RtcPeerConnection,
+    RtcSdpType, RtcSessionDescriptionInit,
+};
+
+// Online versus without a relay server, over a WebRTC data channel straight from one browser to
+// the other. Setting the channel up still takes two messages, an offer from the host and an
+// answer back, but they only go across once, so the players pass them along themselves: the
+// game shows each one as a line of text to copy, and asks for the other one to be pasted in.
+// After that it's the same lockstep game as through a relay, see `net`.
+//
+// Browsers behind most home routers need a STUN server to find a way to each other, set with
+// `data-stun` on the root element. Without one, only players on the same network can connect.
+
+const OFFER: &str = "slake-offer";
+const ANSWER: &str = "slake-answer";
+
+fn stun_url() -> Option<String> {
+    crate::error::document()
+        .ok()?
+        .get_element_by_id("root")?
+        .get_attribute("data-stun")
+}
+
+fn notify(message: &str) {
+    let _ = toast::show(message);
+}
+
+pub fn toggle() {
+    if net::is_active() {
+        net::disconnect("Left online versus");
+        return;
+    }
+
+    let result = crate::error::window().and_then(|window| {
+        let hosting = window
+            .confirm_with_message("Host a game? Cancel to join someone else's instead.")
+            .unwrap_or(false);
+        if hosting {
+            host()
+        } else {
+            join()
+        }
+    });
+
+    if let Err(error) = result {
+        notify(&format!("Couldn't connect: {error}"));
+    }
+}
+
+fn new_connection() -> Result<RtcPeerConnection, SlakeError> {
+    let config = RtcConfiguration::new();
+    if let Some(url) = stun_url() {
+        let server = RtcIceServer::new();
+        server.set_urls(&JsValue::from_str(&url));
+        config.set_ice_servers(&js_sys::Array::of1(&server));
+    }
+
+    Ok(RtcPeerConnection::new_with_configuration(&config)?)
+}
+
+fn host() -> Result<(), SlakeError> {
+    let connection = new_connection()?;
+    let channel = connection.create_data_channel("slake");
+    net::connect_peer(connection.clone(), channel);
+
+    when_gathered(&connection, {
+        let connection = connection.clone();
+
+        move |offer| {
+            let (Ok(window), Some(offer)) = (crate::error::window(), encode(OFFER, &offer)) else {
+                net::disconnect("Couldn't make an offer, so online versus is off");
+                return;
+            };
+            // the prompt shows the offer to copy, and takes the answer in its place
+            let answer = window
+                .prompt_with_message_and_default(
+                    "Send this to the other player, then paste their answer here",
+                    &offer,
+                )
+                .ok()
+                .flatten();
+
+            match answer.and_then(|answer| decode(ANSWER, &answer)) {
+                Some(answer) => {
+                    let description = RtcSessionDescriptionInit::new(RtcSdpType::Answer);
+                    description.set_sdp(&answer);
+                    after(connection.set_remote_description(&description), |_| ());
+                }
+                None => net::disconnect("That isn't an answer, so online versus is off"),
+            }
+        }
+    });
+
+    let offering = connection.clone();
+    after(connection.create_offer(), move |offer| {
+        after(
+            offering.set_local_description(offer.unchecked_ref()),
+            |_| (),
+        );
+    });
+
+    Ok(())
+}
+
+fn join() -> Result<(), SlakeError> {
+    let offer = crate::error::window()?
+        .prompt_with_message("Paste the host's offer")
+        .ok()
+        .flatten();
+    // cancelling is fine, but pasting something else deserves a word
+    let Some(offer) = offer else {
+        return Ok(());
+    };
+    let Some(offer) = decode(OFFER, &offer) else {
+        notify("That isn't an offer from a host");
+        return Ok(());
+    };
+
+    let connection = new_connection()?;
+
+    // the game starts once the host's channel turns up
+    let handle_channel = {
+        let connection = connection.clone();
+
+        Closure::wrap(Box::new(move |event: RtcDataChannelEvent| {
+            net::connect_peer(connection.clone(), event.channel());
+        }) as Box<dyn FnMut(RtcDataChannelEvent)>)
+    };
+    connection.set_ondatachannel(Some(handle_channel.as_ref().unchecked_ref()));
+    handle_channel.forget();
+
+    when_gathered(&connection, |answer| {
+        let (Ok(window), Some(answer)) = (crate::error::window(), encode(ANSWER, &answer)) else {
+            net::disconnect("Couldn't make an answer, so online versus is off");
+            return;
+        };
+        let _ =
+            window.prompt_with_message_and_default("Send this answer back to the host", &answer);
+        notify("Waiting for the host...");
+    });
+
+    let description = RtcSessionDescriptionInit::new(RtcSdpType::Offer);
+    description.set_sdp(&offer);
+    let answering = connection.clone();
+    after(connection.set_remote_description(&description), move |_| {
+        let connection = answering.clone();
+        after(answering.create_answer(), move |answer| {
+            after(
+                connection.set_local_description(answer.unchecked_ref()),
+                |_| (),
+            );
+        });
+    });
+
+    Ok(())
+}
+
+// Calls `done` with the connection's description once it's found every way the other side
+// could reach it, since without a server there's no sending any more of them along later.
+fn when_gathered(connection: &RtcPeerConnection, done: impl FnOnce(String) + 'static) {
+    let mut done = Some(done);
+    let gathering = connection.clone();
+
+    let handle_change = Closure::wrap(Box::new(move || {
+        if gathering.ice_gathering_state() != RtcIceGatheringState::Complete {
+            return;
+        }
+
+        if let (Some(description), Some(done)) = (gathering.local_description(), done.take()) {
+            done(description.sdp());
+        }
+    }) as Box<dyn FnMut()>);
+
+    connection.set_onicegatheringstatechange(Some(handle_change.as_ref().unchecked_ref()));
+    handle_change.forget();
+}
+
+// runs `then` once `promise` works out, or says why it didn't
+fn after(promise: js_sys::Promise, then: impl FnOnce(JsValue) + 'static) {
+    let handle_done = Closure::once(then);
+    let handle_error = Closure::once(|error: JsValue| {
+        net::disconnect(&format!("Couldn't connect: {error:?}"));
+    });
+
+    let _ = promise.then2(&handle_done, &handle_error);
+
+    // only one of them ever runs, and connecting is rare enough for the other to leak
+    handle_done.forget();
+    handle_error.forget();
+}
+
+// Descriptions go across as a single line, since that's all a prompt takes, with what kind it
+// is up front so an offer pasted back where the answer goes gets caught.
+fn encode(kind: &str, description: &str) -> Option<String> {
+    let encoded = crate::error::window().ok()?.btoa(description).ok()?;
+    Some(format!("{kind}:{encoded}"))
+}
+
+fn decode(kind: &str, text: &str) -> Option<String> {
+    crate::error::window().ok()?.atob(untag(kind, text)?).ok()
+}
+
+// whatever's after `kind`, ignoring anything copied along with it around the edges
+fn untag<'a>(kind: &str, text: &'a str) -> Option<&'a str> {
+    let encoded = text.trim().strip_prefix(kind)?.strip_prefix(':')?;
+    (!encoded.is_empty()).then_some(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags() {
+        assert_eq!(untag(OFFER, " slake-offer:djA9MA==\n"), Some("djA9MA=="));
+        assert_eq!(untag(ANSWER, "slake-offer:djA9MA=="), None);
+        assert_eq!(untag(OFFER, "slake-offer:"), None);
+        assert_eq!(untag(OFFER, "djA9MA=="), None);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::error::SlakeError;
+use crate::export;
+use crate::toast;
+
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlElement, Storage};
+
+// Everything the game keeps in local storage, as one code to paste into another browser: the
+// settings, high scores in every mode, stats, daily and weekly bests, ghosts, the saved game,
+// and whether the tutorial's been seen. Only community levels are left out, since those are a
+// cache that fills itself back up.
+//
+// A code looks like `slake-profile:1:89abcdef:<base64>`, with the format's version and a
+// checksum of the contents, so one that's come from a newer build or lost a few characters on
+// the way gets turned down before anything's overwritten. Inside, each saved item is its key
+// and its length in bytes on a line, then the item itself.
+
+const TAG: &str = "slake-profile";
+const FORMAT_VERSION: u32 = 1;
+// what everything the game saves starts with
+const KEY_PREFIX: &str = "slake_";
+const SKIPPED_PREFIX: &str = "slake_cache:";
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// `btoa` only takes Latin-1, and names in the settings can be anything
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let packed = chunk.iter().enumerate().fold(0u32, |packed, (i, byte)| {
+            packed | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => encoded.push(BASE64[(packed >> (18 - 6 * i) & 63) as usize] as char),
+                false => encoded.push('='),
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut bytes = vec![];
+    let (mut packed, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE64.iter().position(|known| *known == c)? as u32;
+        packed = packed << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((packed >> bits) as u8);
+            packed &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+// 32-bit FNV-1a, like `Replay::hash`
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn is_saved_key(key: &str) -> bool {
+    key.starts_with(KEY_PREFIX)
+        && !key.starts_with(SKIPPED_PREFIX)
+        && !key.contains(char::is_whitespace)
+}
+
+pub fn pack(items: &[(String, String)]) -> String {
+    let mut contents = String::new();
+    for (key, value) in items {
+        contents += &format!("{key} {}\n{value}\n", value.len());
+    }
+
+    format!(
+        "{TAG}:{FORMAT_VERSION}:{:08x}:{}",
+        checksum(contents.as_bytes()),
+        encode_base64(contents.as_bytes())
+    )
+}
+
+// Reads a code from `pack`, turning down anything that isn't exactly what was packed.
+pub fn unpack(code: &str) -> Result<Vec<(String, String)>, String> {
+    let [tag, version, expected, encoded] = code.trim().splitn(4, ':').collect::<Vec<_>>()[..]
+    else {
+        return Err("that isn't a profile code".to_string());
+    };
+    if tag != TAG {
+        return Err("that isn't a profile code".to_string());
+    }
+    match version.parse::<u32>() {
+        Ok(FORMAT_VERSION) => (),
+        Ok(version) if version > FORMAT_VERSION => {
+            return Err("that code's from a newer version of slake".to_string())
+        }
+        _ => return Err(format!("bad profile version '{version}'")),
+    }
+
+    let contents = decode_base64(encoded).ok_or("the code's been cut short or mangled")?;
+    if u32::from_str_radix(expected, 16).ok() != Some(checksum(&contents)) {
+        return Err("the code's been cut short or mangled".to_string());
+    }
+    let contents = String::from_utf8(contents).map_err(|_| "the code's been mangled")?;
+
+    let mut items = vec![];
+    let mut rest = contents.as_str();
+    while !rest.is_empty() {
+        let (header, after) = rest.split_once('\n').ok_or("the profile ends partway")?;
+        let (key, length) = header
+            .split_once(' ')
+            .ok_or_else(|| format!("bad profile item '{header}'"))?;
+        let length = length
+            .parse::<usize>()
+            .map_err(|_| format!("bad length '{length}'"))?;
+        if !is_saved_key(key) {
+            return Err(format!("'{key}' isn't something slake saves"));
+        }
+
+        let value = after
+            .get(..length)
+            .filter(|_| after[length..].starts_with('\n'))
+            .ok_or("the profile ends partway")?;
+        items.push((key.to_string(), value.to_string()));
+        rest = &after[length + 1..];
+    }
+
+    Ok(items)
+}
+
+fn saved_items(storage: &Storage) -> Vec<(String, String)> {
+    let length = storage.length().unwrap_or(0);
+    let mut items = (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .filter(|key| is_saved_key(key))
+        .filter_map(|key| {
+            let value = storage.get_item(&key).ok().flatten()?;
+            Some((key, value))
+        })
+        .collect::<Vec<_>>();
+    // storage has no order of its own, and the same profile should make the same code
+    items.sort();
+    items
+}
+
+pub fn export_profile() -> Result<String, String> {
+    let storage = crate::local_storage().ok_or("there's no storage to export from")?;
+    Ok(pack(&saved_items(&storage)))
+}
+
+// Replaces everything saved with what's in `code`, then reloads the page to start over with it.
+pub fn import_profile(code: &str) -> Result<(), String> {
+    let items = unpack(code)?;
+    let storage = crate::local_storage().ok_or("there's no storage to import into")?;
+
+    for (key, _) in saved_items(&storage) {
+        let _ = storage.remove_item(&key);
+    }
+    for (key, value) in items {
+        storage
+            .set_item(&key, &value)
+            .map_err(|_| "storage is full, so only some of it was imported")?;
+    }
+
+    crate::error::reload();
+    Ok(())
+}
+
+fn copy_profile() {
+    let message = match export_profile() {
+        Ok(code) if export::copy_text(&code) => "Copied your profile".to_string(),
+        // it's in the prompt to copy instead
+        Ok(_) => return,
+        Err(error) => format!("Couldn't export: {error}"),
+    };
+    let _ = toast::show(&message);
+}
+
+fn paste_profile() {
+    let code = crate::error::window().ok().and_then(|window| {
+        window
+            .prompt_with_message(
+                "Paste a profile code. Everything saved here gets replaced with it",
+            )
+            .ok()
+            .flatten()
+    });
+    let Some(code) = code.filter(|code| !code.trim().is_empty()) else {
+        return;
+    };
+
+    if let Err(error) = import_profile(&code) {
+        let _ = toast::show(&format!("Couldn't import: {error}"));
+    }
+}
+
+// the buttons for them in the settings panel
+pub fn build_buttons() -> Result<(), SlakeError> {
+    let handle_copy = Closure::wrap(Box::new(copy_profile) as Box<dyn FnMut()>);
+    let handle_paste = Closure::wrap(Box::new(paste_profile) as Box<dyn FnMut()>);
+
+    crate::get_element::<HtmlElement>("setting_export_profile")?
+        .set_onclick(Some(handle_copy.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("setting_import_profile")?
+        .set_onclick(Some(handle_paste.as_ref().unchecked_ref()));
+
+    // the panel is never removed, so neither are its handlers
+    handle_copy.forget();
+    handle_paste.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+        ] {
+            assert_eq!(encode_base64(bytes), encoded);
+            assert_eq!(decode_base64(encoded).as_deref(), Some(bytes));
+        }
+        assert_eq!(decode_base64("Zm9v!"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let items = vec![
+            (
+                "slake_settings".to_string(),
+                "tick_ms=80\nplayer_name=Zoë 🐍\n".to_string(),
+            ),
+            ("slake_stats".to_string(), String::new()),
+            ("slake_tutorial".to_string(), "done".to_string()),
+        ];
+        let code = pack(&items);
+        assert!(code.starts_with("slake-profile:1:"));
+        assert_eq!(unpack(&code), Ok(items.clone()));
+        // copied along with some of what was around it
+        assert_eq!(unpack(&format!("  {code}\n")), Ok(items));
+        assert_eq!(unpack(&pack(&[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn validation() {
+        let code = pack(&[("slake_tutorial".to_string(), "done".to_string())]);
+
+        assert!(unpack("slake-offer:djA9MA==").is_err());
+        assert!(unpack(&code.replacen(":1:", ":2:", 1))
+            .unwrap_err()
+            .contains("newer"));
+        // the last few characters lost, padding and all
+        assert!(unpack(&code[..code.len() - 4]).is_err());
+
+        // nothing that isn't ours gets in, even with the checksum right
+        let sneaky = pack(&[("other_site".to_string(), "x".to_string())]);
+        assert!(unpack(&sneaky).unwrap_err().contains("other_site"));
+        let cached = pack(&[("slake_cache:levels".to_string(), "x".to_string())]);
+        assert!(unpack(&cached).is_err());
+    }
+}
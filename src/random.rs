@@ -2,8 +2,26 @@ use prng::Prng16;
 
 use std::cell::RefCell;
 
+// the numbers drawn since the last seeding, counted so they can be picked up again partway
+// through, see `state`
+struct Stream {
+    prng: Prng16,
+    seed: [u16; 2],
+    draws: usize,
+}
+
+impl Stream {
+    fn new(seed: [u16; 2]) -> Stream {
+        Stream {
+            prng: Prng16::new(seed),
+            seed,
+            draws: 0,
+        }
+    }
+}
+
 thread_local! {
-    pub static PRNG: RefCell<Prng16> = RefCell::new(Prng16::new(get_prng_seed()));
+    static STREAM: RefCell<Stream> = RefCell::new(Stream::new(get_prng_seed()));
 }
 
 #[cfg(not(target_family = "wasm"))]
@@ -28,6 +46,46 @@ fn get_prng_seed() -> [u16; 2] {
     [number0 as u16, number1 as u16]
 }
 
+// for when a game needs to play out the same way again
+pub fn seed(seed: [u16; 2]) {
+    STREAM.with(|stream| stream.replace(Stream::new(seed)));
+}
+
 pub fn get_u16() -> u16 {
-    PRNG.with(|prng| prng.borrow_mut().next().unwrap())
+    STREAM.with(|stream| {
+        let mut stream = stream.borrow_mut();
+        stream.draws += 1;
+        stream.prng.next().unwrap()
+    })
+}
+
+// The last seed, and how many numbers have been drawn since. The prng doesn't hand out its
+// insides, so going back to a state means seeding and drawing that many again.
+pub fn state() -> ([u16; 2], usize) {
+    STREAM.with(|stream| {
+        let stream = stream.borrow();
+        (stream.seed, stream.draws)
+    })
+}
+
+pub fn restore((seed, draws): ([u16; 2], usize)) {
+    self::seed(seed);
+
+    for _ in 0..draws {
+        get_u16();
+    }
+}
+
+// A stream of its own, for a game played alongside the real one without taking any of its
+// numbers, like the title screen's demo. `swap` trades it with the one everything draws from.
+pub struct Aside(Stream);
+
+impl Aside {
+    pub fn new(seed: [u16; 2]) -> Aside {
+        Aside(Stream::new(seed))
+    }
+}
+
+pub fn swap(aside: &mut Aside) {
+    STREAM.with(|stream| std::mem::swap(&mut *stream.borrow_mut(), &mut aside.0));
 }
@@ -0,0 +1,1055 @@
+use crate::about;
+use crate::animation;
+use crate::animation::FloatingText;
+use crate::canvas;
+use crate::editor;
+use crate::error::SlakeError;
+use crate::floor_map;
+use crate::focus;
+use crate::geometry::is_within_board;
+use crate::geometry::Geometry;
+use crate::geometry::Grid;
+use crate::geometry::Vector;
+use crate::layout;
+use crate::renderer::BoardTransform;
+use crate::renderer::HudState;
+use crate::renderer::Renderer;
+use crate::replay;
+use crate::settings::RenderStyle;
+use crate::settings::Settings;
+use crate::snake;
+use crate::snake::GameState;
+use crate::snake::SnakeGame;
+use crate::splits;
+use crate::sprites;
+use crate::stats;
+use crate::theme::Theme;
+use crate::timer;
+use crate::title;
+use crate::tutorial;
+use crate::view::Segment;
+use crate::view::TileKind;
+
+use std::cell::RefCell;
+use std::ops::Range;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement};
+
+// The board is a grid of `.field` divs that stays in the page between frames. Every frame we
+// work out what should be on each tile, compare it to what was drawn last time, and only touch
+// the divs that changed. The grid is only rebuilt when its shape or style changes. A tile's div
+// only ever has its classes changed, see `Tile::class_name`, and the looks come from `stylesheet`.
+//
+// Hex boards are a CSS grid too, in columns half a tile wide, so each row can start half a tile
+// further along than the one above. The stylesheet cuts the fields into hexagons and tucks the
+// rows into each other.
+//
+// Levels with more than one floor only get the snake's own floor on the page, see
+// `shown_rows`, which means a new grid whenever it goes up or down a ladder.
+
+// Drawn underneath whatever is on a tile, for things that aren't really on the board
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Decoration {
+    // where the best run so far was, see `ghost`
+    Ghost,
+    // trouble ahead, see `coach`
+    Warning,
+    // hides whatever is on the tile, except for snakes. see `mutator`
+    Fog,
+    // food was just eaten here, see `animation`
+    Flash,
+    // a dead snake's segment that's crumbled into a hazard, see `animation`
+    Collapsed,
+    // where food will spawn on a level being made, see `editor`
+    FoodSpawn,
+    // a tile a tail's just moved off, so many ticks ago, see `SnakeGame::trail`. it's where
+    // the puddles from eating land, so it's the color of one
+    Trail(usize),
+}
+
+impl Decoration {
+    pub fn color(&self) -> &'static str {
+        match self {
+            Decoration::Ghost => "rgba(128, 128, 128, 0.25)",
+            Decoration::Warning => "rgba(255, 0, 0, 0.4)",
+            Decoration::Fog => "rgb(150, 150, 160)",
+            Decoration::Flash => "rgba(255, 255, 200, 0.8)",
+            Decoration::Collapsed => "rgba(0, 0, 0, 0.15)",
+            Decoration::FoodSpawn => "rgba(0, 200, 0, 0.35)",
+            Decoration::Trail(0) => "rgba(30, 144, 255, 0.3)",
+            Decoration::Trail(1) => "rgba(30, 144, 255, 0.18)",
+            Decoration::Trail(_) => "rgba(30, 144, 255, 0.08)",
+        }
+    }
+
+    // for the stylesheet, see `stylesheet`
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Decoration::Ghost => "deco-ghost",
+            Decoration::Warning => "deco-warning",
+            Decoration::Fog => "deco-fog",
+            Decoration::Flash => "deco-flash",
+            Decoration::Collapsed => "deco-collapsed",
+            Decoration::FoodSpawn => "deco-food-spawn",
+            Decoration::Trail(0) => "deco-trail-0",
+            Decoration::Trail(1) => "deco-trail-1",
+            Decoration::Trail(_) => "deco-trail-2",
+        }
+    }
+
+    // one of each that looks different
+    const ALL: [Decoration; 9] = [
+        Decoration::Ghost,
+        Decoration::Warning,
+        Decoration::Fog,
+        Decoration::Flash,
+        Decoration::Collapsed,
+        Decoration::FoodSpawn,
+        Decoration::Trail(0),
+        Decoration::Trail(1),
+        Decoration::Trail(2),
+    ];
+}
+
+// What debug mode says about a tile, see `SnakeGame::get_semi_open_tiles`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Highlight {
+    // there's something on it
+    Occupied,
+    // free, but with only one way out
+    SemiOpen,
+    Open,
+}
+
+impl Highlight {
+    const ALL: [Highlight; 3] = [Highlight::Occupied, Highlight::SemiOpen, Highlight::Open];
+
+    // drawn over everything else, where there is one. open tiles are left as they are
+    pub fn color(&self) -> Option<&'static str> {
+        match self {
+            Highlight::Occupied => Some("orange"),
+            Highlight::SemiOpen => Some("grey"),
+            Highlight::Open => None,
+        }
+    }
+
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            Highlight::Occupied => "tile-debug-occupied",
+            Highlight::SemiOpen => "tile-debug-semi-open",
+            Highlight::Open => "tile-debug-open",
+        }
+    }
+}
+
+// Every opacity `board_tiles` gives a tile: the quarters from `fade`, and those halved again
+// while hazards flash.
+const OPACITIES: [u8; 6] = [0, 12, 25, 37, 50, 75];
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Tile {
+    pub kind: TileKind,
+    // which way the snake goes through it, if there's one here
+    pub segment: Option<Segment>,
+    pub decoration: Option<Decoration>,
+    // only in debug mode
+    pub highlight: Option<Highlight>,
+    // in percent. hazards fade out as they dry up
+    pub opacity: u8,
+}
+
+impl Tile {
+    // Everything about it as classes for its div, which the stylesheet does the rest with, see
+    // `stylesheet`
+    pub fn class_name(&self) -> String {
+        let mut classes = vec!["field".to_string(), self.kind.class_name()];
+        classes.extend(self.segment.map(|segment| segment.class_name()));
+        classes.extend(
+            self.decoration
+                .map(|decoration| decoration.class_name().to_string()),
+        );
+        classes.extend(
+            self.highlight
+                .map(|highlight| highlight.class_name().to_string()),
+        );
+        if self.opacity < 100 {
+            classes.push(format!("faded-{}", self.opacity));
+        }
+
+        classes.join(" ")
+    }
+}
+
+// one `Tile` per board position, in rows from the top left
+pub fn board_tiles(
+    game: &SnakeGame,
+    decorations: &[(Vector, Decoration)],
+    debug_mode: bool,
+    reduced_motion: bool,
+) -> Vec<Tile> {
+    let width = game.width;
+    let view = game.board_view();
+    let mut tiles = view
+        .tiles
+        .into_iter()
+        .zip(view.segments)
+        .map(|(kind, segment)| Tile {
+            kind,
+            segment,
+            decoration: None,
+            highlight: None,
+            opacity: 100,
+        })
+        .collect::<Vec<_>>();
+
+    for (pos, decoration) in decorations.iter() {
+        if is_within_board(game.width, game.height, pos) {
+            let tile = &mut tiles[(pos.1 * width + pos.0) as usize];
+            tile.decoration = Some(*decoration);
+
+            match decoration {
+                Decoration::Fog if !tile.kind.is_snake() => tile.kind = TileKind::Empty,
+                Decoration::Collapsed => {
+                    tile.kind = TileKind::Hazard;
+                    tile.segment = None;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // only where there's nothing else to show, since it's so faint
+    for (pos, age) in game.trail() {
+        let tile = &mut tiles[(pos.1 * width + pos.0) as usize];
+        if tile.kind == TileKind::Empty && tile.decoration.is_none() {
+            tile.decoration = Some(Decoration::Trail(age));
+        }
+    }
+
+    // things that run out fade away as they do
+    if let Some(lifetime) = game.hazard_lifetime {
+        for pos in game.hazards().iter() {
+            let tile = &mut tiles[(pos.1 * width + pos.0) as usize];
+
+            if let (TileKind::Hazard, Some(ticks_left)) = (tile.kind, game.hazard_ticks_left(pos)) {
+                tile.opacity = fade(ticks_left, lifetime);
+            }
+        }
+    }
+    // hazards flash while a snake's stunned, as a reminder that the next one's for real
+    if !reduced_motion
+        && game.ticks % 2 == 1
+        && game.snakes().iter().any(|snake| snake.is_stunned())
+    {
+        for pos in game.hazards().iter() {
+            let tile = &mut tiles[(pos.1 * width + pos.0) as usize];
+            if tile.kind == TileKind::Hazard {
+                tile.opacity /= 2;
+            }
+        }
+    }
+    for food in game.food().iter() {
+        if let (Some(ms_left), Some(lifetime)) =
+            (food.ms_left(game.elapsed_ms), food.kind.lifetime_ms())
+        {
+            tiles[(food.position.1 * width + food.position.0) as usize].opacity =
+                fade(ms_left, lifetime);
+        }
+    }
+
+    if debug_mode {
+        for tile in tiles.iter_mut() {
+            tile.highlight = Some(Highlight::Occupied);
+        }
+        for pos in game.get_semi_open_tiles() {
+            tiles[(pos.1 * width + pos.0) as usize].highlight = Some(Highlight::SemiOpen);
+        }
+        // occupied tiles stay that way, even the semi-open ones
+        for pos in game.free_positions().iter() {
+            let tile = &mut tiles[(pos.1 * width + pos.0) as usize];
+            if tile.highlight == Some(Highlight::Occupied) {
+                tile.highlight = Some(Highlight::Open);
+            }
+        }
+    }
+
+    tiles
+}
+
+// Which rows of the board get drawn. That's the snake's floor, except in the editor, which
+// shows every floor so `editor::tile_at` can go by the whole board.
+pub fn shown_rows(game: &SnakeGame) -> Range<isize> {
+    match editor::is_editing() {
+        true => 0..game.height,
+        false => game.active_floor(),
+    }
+}
+
+// in steps of a quarter, so fading doesn't redraw every tile every tick
+fn fade(ticks_left: usize, lifetime: usize) -> u8 {
+    let quarters = (ticks_left * 4).div_ceil(lifetime);
+    (quarters * 25) as u8
+}
+
+pub struct DomBoard {
+    width: isize,
+    height: isize,
+    // see `shown_rows`
+    rows: Range<isize>,
+    grid: Grid,
+    show_border: bool,
+    render_style: RenderStyle,
+    theme: Theme,
+    // the sprite sheet loads after the first board's been drawn, so it's all drawn again
+    sprites_loaded: bool,
+    presentation: bool,
+    cells: Vec<HtmlDivElement>,
+    previous_tiles: Vec<Tile>,
+    score_element: HtmlDivElement,
+    high_score_element: HtmlDivElement,
+    // holds whatever's floating over the board, see `animation::floating_texts`
+    texts_element: HtmlDivElement,
+    previous_texts: Vec<FloatingText>,
+    // covers the board once the game is over
+    overlay_element: HtmlDivElement,
+    // reading `inner_text` back out of the page is slow, so remember what we put there
+    previous_info: (String, String),
+    previous_overlay: Option<String>,
+}
+
+impl Renderer for DomBoard {
+    fn fits(&self, game: &SnakeGame, settings: &Settings) -> bool {
+        self.width == game.width
+            && self.height == game.height
+            && self.rows == shown_rows(game)
+            && self.grid == game.grid
+            && self.show_border == settings.show_border
+            && self.render_style == settings.render_style
+            && self.theme == settings.theme
+            && self.sprites_loaded == sprites::is_loaded()
+            && self.presentation == settings.presentation
+    }
+
+    // only the rows it shows get drawn, of the tiles and what's floating over them
+    fn draw(
+        &mut self,
+        game: &SnakeGame,
+        mut tiles: Vec<Tile>,
+        hud: &HudState,
+    ) -> Result<(), JsValue> {
+        let rows = self.rows.clone();
+        tiles.truncate((rows.end * game.width) as usize);
+        tiles.drain(..(rows.start * game.width) as usize);
+
+        for (index, tile) in tiles.iter().enumerate() {
+            if self.previous_tiles.get(index) != Some(tile) {
+                self.cells[index].set_class_name(&tile.class_name());
+            }
+        }
+
+        self.previous_tiles = tiles;
+
+        // moved up to match the rows that are shown
+        let texts = hud
+            .texts
+            .iter()
+            .filter(|text| rows.contains(&text.position.1))
+            .map(|text| FloatingText {
+                position: Vector(text.position.0, text.position.1 - rows.start),
+                ..text.clone()
+            })
+            .collect::<Vec<_>>();
+        if self.previous_texts != texts {
+            let border = if self.show_border { 1 } else { 0 };
+            draw_floating_texts(&self.texts_element, &texts, game.geometry(), border)?;
+            self.previous_texts = texts;
+        }
+
+        if self.previous_info.0 != hud.info.0 {
+            self.score_element.set_inner_text(&hud.info.0);
+        }
+        if self.previous_info.1 != hud.info.1 {
+            self.high_score_element.set_inner_text(&hud.info.1);
+        }
+
+        self.previous_info = hud.info.clone();
+
+        if self.previous_overlay != hud.overlay {
+            match hud.overlay.as_ref() {
+                Some(text) => {
+                    self.overlay_element.set_inner_text(text);
+                    self.overlay_element
+                        .style()
+                        .set_property("display", "flex")?;
+                }
+                None => self
+                    .overlay_element
+                    .style()
+                    .set_property("display", "none")?,
+            }
+        }
+
+        self.previous_overlay = hud.overlay.clone();
+
+        Ok(())
+    }
+}
+
+thread_local! {
+    // whichever `Renderer` the settings picked, see `build_renderer`
+    static RENDERER: RefCell<Option<Box<dyn Renderer>>> = const { RefCell::new(None) };
+}
+
+pub fn create_div(class_name: &str) -> Result<HtmlDivElement, SlakeError> {
+    let element = crate::error::document()?
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    element.set_class_name(class_name);
+
+    Ok(element)
+}
+
+// a length of `tiles` tiles, at whatever size `layout` has them
+fn tiles_across(tiles: f64) -> String {
+    format!("calc(var(--tile) * {tiles})")
+}
+
+// there aren't ever many, so they're just made over again each frame. `border` is in tiles
+fn draw_floating_texts(
+    element: &HtmlDivElement,
+    texts: &[FloatingText],
+    geometry: &dyn Geometry,
+    border: isize,
+) -> Result<(), SlakeError> {
+    element.set_inner_html("");
+
+    for text in texts {
+        let text_element = create_div("floating_text")?;
+        text_element.set_inner_text(&text.text);
+
+        let (left, top) =
+            geometry.tile_offset(&Vector(text.position.0 + border, text.position.1 + border));
+        let top = top - text.progress;
+        let style = text_element.style();
+        style.set_property("left", &tiles_across(left))?;
+        style.set_property("top", &tiles_across(top))?;
+        style.set_property("opacity", &(1.0 - text.progress).to_string())?;
+
+        element.append_child(&text_element)?;
+    }
+
+    Ok(())
+}
+
+// Makes the board's tiles and everything around them in `root_container`, in place of whatever
+// was there.
+pub fn build_board(
+    game: &SnakeGame,
+    settings: &Settings,
+    shown: Range<isize>,
+    root_container: &HtmlElement,
+) -> Result<DomBoard, SlakeError> {
+    // from here on `y` counts from the top of the rows that are shown
+    let width = game.width;
+    let height = shown.end - shown.start;
+    let border = if settings.show_border { 1 } else { 0 };
+
+    root_container.set_inner_html("");
+
+    // holds the overlay on top of the board
+    let board_element = create_div("board")?;
+    // the emoji don't read out as anything useful, so screen readers get `accessibility` instead
+    board_element.set_attribute("aria-hidden", "true")?;
+    root_container.append_child(&board_element)?;
+
+    let mut holder_class = "field_holder".to_string();
+    if settings.show_border {
+        holder_class.push_str(" bordered");
+    }
+    if game.grid == Grid::Hex {
+        holder_class.push_str(" hex");
+    }
+    if settings.render_style != RenderStyle::Emoji {
+        holder_class.push_str(" squares");
+    } else {
+        holder_class.push_str(" emoji");
+        if settings.theme.has_sprites() && sprites::is_loaded() {
+            holder_class.push_str(" sprites");
+        }
+    }
+    let field_holder_element = create_div(&holder_class)?;
+
+    board_element.append_child(&field_holder_element)?;
+
+    field_holder_element
+        .style()
+        .set_property("display", "inline-grid")?;
+    // hexagons leave gaps around the edge of the grid, which shouldn't look like board
+    let background = match game.grid {
+        Grid::Square => "background-color",
+        Grid::Hex => "--background",
+    };
+    field_holder_element
+        .style()
+        .set_property(background, settings.theme.background())?;
+    field_holder_element
+        .style()
+        .set_property("color", settings.theme.text_color())?;
+
+    let rows = height + border * 2;
+    let columns = width + border * 2;
+    let template = match game.grid {
+        Grid::Square => format!("repeat({rows}, auto) / repeat({columns}, auto)"),
+        // the last row ends up half a tile along for every row above it
+        Grid::Hex => format!(
+            "repeat({rows}, auto) / repeat({}, {})",
+            columns * 2 + rows - 1,
+            tiles_across(0.5)
+        ),
+    };
+    field_holder_element
+        .style()
+        .set_property("grid-template", &template)?;
+
+    // hexagons say where each one goes, squares just fill in the rows in order
+    let place = |element: &HtmlDivElement, x: isize, y: isize| match game.grid {
+        Grid::Square => Ok(()),
+        Grid::Hex => {
+            let column = (x + border) * 2 + y + border + 1;
+            element.style().set_property(
+                "grid-area",
+                &format!("{} / {column} / auto / span 2", y + border + 1),
+            )
+        }
+    };
+
+    let mut cells = Vec::with_capacity((width * height) as usize);
+
+    for y in -border..height + border {
+        for x in -border..width + border {
+            if x < 0 || y < 0 || x >= width || y >= height {
+                // the border never changes, so it's drawn once and not tracked. it's a wall in
+                // every style, with the wall color even where tiles don't have one
+                let border_element =
+                    create_div(&format!("field border {}", TileKind::Wall.class_name()))?;
+                place(&border_element, x, y)?;
+                field_holder_element.append_child(&border_element)?;
+                continue;
+            }
+
+            let field_element = create_div("field")?;
+            place(&field_element, x, y)?;
+            field_holder_element.append_child(&field_element)?;
+            cells.push(field_element);
+        }
+    }
+
+    let texts_element = create_div("floating_texts")?;
+    texts_element
+        .style()
+        .set_property("color", settings.theme.text_color())?;
+    board_element.append_child(&texts_element)?;
+
+    let overlay_element = create_div("overlay")?;
+    overlay_element.style().set_property("display", "none")?;
+    overlay_element
+        .style()
+        .set_property("background-color", settings.theme.overlay_color())?;
+    overlay_element
+        .style()
+        .set_property("color", settings.theme.text_color())?;
+    board_element.append_child(&overlay_element)?;
+
+    //~ document.create_element("FOOTER");
+
+    let info_element = create_div(info_class(settings))?;
+
+    //~ info_element
+    //~ .style()
+    //~ .set_property("display", "inline-grid")?;
+
+    //~ info_element.style().set_property(
+    //~ "grid-template",
+    //~ &format!("repeat(1, auto) / repeat(2, auto)"),
+    //~ )?;
+
+    let score_element = create_div("")?;
+    let high_score_element = create_div("")?;
+
+    info_element.append_child(&score_element)?;
+    info_element.append_child(&high_score_element)?;
+
+    root_container.append_child(&info_element)?;
+
+    Ok(DomBoard {
+        width,
+        height: game.height,
+        rows: shown,
+        grid: game.grid,
+        show_border: settings.show_border,
+        render_style: settings.render_style,
+        theme: settings.theme,
+        sprites_loaded: sprites::is_loaded(),
+        presentation: settings.presentation,
+        cells,
+        // nothing has been drawn yet, so make sure every tile counts as changed
+        previous_tiles: vec![],
+        score_element,
+        high_score_element,
+        texts_element,
+        previous_texts: vec![],
+        overlay_element,
+        previous_info: (String::new(), String::new()),
+        previous_overlay: None,
+    })
+}
+
+// How the tiles of a board of divs look, by the classes `Tile::class_name` gives them. The theme
+// and the sprite sheet say what's on each one, and what goes under and over that is the same in
+// every theme. Decorations come before the squares style's colors so those cover them up,
+// highlights go over everything.
+fn stylesheet(theme: Theme) -> String {
+    let mut rules = Decoration::ALL
+        .iter()
+        .map(|decoration| {
+            format!(
+                ".field_holder .field.{} {{ background-color: {}; }}",
+                decoration.class_name(),
+                decoration.color()
+            )
+        })
+        .collect::<Vec<_>>();
+    rules.push(theme.stylesheet());
+    rules.push(sprites::stylesheet());
+
+    for highlight in Highlight::ALL {
+        if let Some(color) = highlight.color() {
+            rules.push(format!(
+                ".field_holder .field.{} {{ background-color: {color} !important; }}",
+                highlight.class_name()
+            ));
+        }
+    }
+    for opacity in OPACITIES {
+        rules.push(format!(".field.faded-{opacity} {{ opacity: {opacity}%; }}"));
+    }
+
+    rules.join("\n")
+}
+
+// in a stylesheet of its own that's made the first time a board is, and again whenever the board
+// is built over
+fn set_stylesheet(theme: Theme) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let style_element = match document.get_element_by_id("theme_colors") {
+        Some(style_element) => style_element,
+        None => {
+            let style_element = document.create_element("style")?;
+            style_element.set_id("theme_colors");
+            crate::error::body()?.append_child(&style_element)?;
+            style_element
+        }
+    };
+    style_element.set_text_content(Some(&stylesheet(theme)));
+
+    Ok(())
+}
+
+// presentation mode makes the text under the board big, see `hud_text`
+pub fn info_class(settings: &Settings) -> &'static str {
+    if settings.presentation {
+        "info presentation"
+    } else {
+        "info"
+    }
+}
+
+fn versus_scores(game: &SnakeGame, theme: Theme) -> String {
+    format!(
+        "{} {} : {} {}",
+        theme.glyph(TileKind::Head),
+        game.scores[0],
+        game.scores[1],
+        theme.glyph(TileKind::RivalHead)
+    )
+}
+
+// The two lines under the board. In presentation mode that's just the player's name and the
+// score, leaving out the high score and the hunger meter. Next to the score goes the time
+// played so far, or in time attack the seconds left, either way. A race's time is the one it's
+// scored on, see `SnakeGame::race_ms`.
+pub fn hud_text(game: &SnakeGame, settings: &Settings) -> (String, String) {
+    let theme = settings.theme;
+
+    // with a combo going, how high it's got
+    let combo = |score_text: String| {
+        if game.has_combos() && game.combo > 1 && !game.is_game_over() {
+            format!("{score_text} ×{}", game.combo)
+        } else {
+            score_text
+        }
+    };
+    let clock = |score_text: String| match game.ticks_left() {
+        Some(ticks_left) if !game.is_game_over() => {
+            let seconds = (ticks_left * settings.tick_ms.max(0) as usize).div_ceil(1000);
+            format!("{} ⏱️ {seconds}", combo(score_text))
+        }
+        // by the clock, to a hundredth
+        _ if settings.speedrun => format!(
+            "{} 🕒 {}",
+            combo(score_text),
+            timer::split_text(crate::run_ms())
+        ),
+        _ if game.fruit_left().is_some() => format!(
+            "{} 🕒 {}",
+            combo(score_text),
+            timer::clock_text(game.elapsed_ms as f64)
+        ),
+        // otherwise how long the run's been going, by the clock
+        _ => format!(
+            "{} 🕒 {}",
+            combo(score_text),
+            timer::clock_text(crate::run_ms())
+        ),
+    };
+
+    if !settings.presentation {
+        let (score_text, high_score_text) = info_text(game, theme);
+        return (clock(score_text), high_score_text);
+    }
+
+    let score_text = if game.is_versus() {
+        versus_scores(game, theme)
+    } else {
+        format!("{} {}", theme.glyph(TileKind::Food), game.score)
+    };
+
+    (settings.player_name.clone(), clock(score_text))
+}
+
+// the score, and what could've been had, when there's any telling
+pub fn score_out_of(game: &SnakeGame) -> String {
+    match game.possible_score() {
+        Some(possible) => format!("{} / {possible} possible", game.score),
+        None => game.score.to_string(),
+    }
+}
+
+// the score line under the board
+pub fn info_text(game: &SnakeGame, theme: Theme) -> (String, String) {
+    let food = theme.glyph(TileKind::Food);
+    let score_text = if game.is_versus() {
+        versus_scores(game, theme)
+    } else if game.is_game_over() {
+        format!("{food} {}", score_out_of(game))
+    } else {
+        format!("{food} {}", game.score)
+    };
+    let high_score_text = format!("⭐ {}", game.high_score_display);
+
+    // how full the snake is, a block for every fifth of the way to starving
+    let score_text = match (
+        game.is_versus(),
+        game.starve_ticks,
+        game.ticks_until_starving(0),
+    ) {
+        (false, Some(starve_ticks), Some(ticks_left)) if !game.is_game_over() => {
+            let full = (ticks_left * 5).div_ceil(starve_ticks);
+            format!(
+                "{score_text} 🍽️ {}{}",
+                "▮".repeat(full),
+                "▯".repeat(5 - full)
+            )
+        }
+        _ => score_text,
+    };
+
+    // and with hunger on, how much energy's left, the same way
+    let score_text = match (game.is_versus(), game.energy(0)) {
+        (false, Some(energy)) if !game.is_game_over() => {
+            let full = (energy * 5).div_ceil(snake::MAX_ENERGY);
+            format!(
+                "{score_text} 🔋 {}{}",
+                "▮".repeat(full),
+                "▯".repeat(5 - full)
+            )
+        }
+        _ => score_text,
+    };
+
+    // which wave it is, and how far through its quota
+    let score_text = match game.wave() {
+        Some(wave) if !game.is_game_over() && !wave.is_between() => format!(
+            "{score_text} 🌊 {} · {}/{}",
+            wave.number,
+            wave.eaten,
+            wave.quota()
+        ),
+        Some(wave) if !game.is_game_over() => format!("{score_text} 🌊 {}", wave.number),
+        _ => score_text,
+    };
+
+    // and in a race, how far to the finish, a block for every fifth of the way
+    let score_text = match (game.mode().fruit_target(), game.is_game_over()) {
+        (Some(target), false) => {
+            let eaten = game.fruit_eaten.min(target);
+            let done = eaten * 5 / target.max(1);
+            format!(
+                "{score_text} 🏁 {}{} {eaten}/{target}",
+                "▮".repeat(done),
+                "▯".repeat(5 - done)
+            )
+        }
+        _ => score_text,
+    };
+
+    (score_text, high_score_text)
+}
+
+// With fresh food on, what each fruit would be worth if it were eaten now, written over it while
+// it's worth more than usual. They sit still, so they're never part way through floating off.
+pub fn food_values(game: &SnakeGame) -> Vec<FloatingText> {
+    game.fresh_values()
+        .map(|(pos, value)| FloatingText {
+            position: pos.clone(),
+            text: value.to_string(),
+            progress: 0.0,
+        })
+        .collect()
+}
+
+// what to show over the board, if anything
+pub fn overlay_text(game: &SnakeGame, theme: Theme) -> Option<String> {
+    // the game over message waits for the snake to finish crumbling, and the editor's board
+    // sits paused without saying so
+    if animation::holds_overlay() || editor::is_editing() {
+        return None;
+    }
+
+    if about::is_showing() {
+        return Some(about::text());
+    }
+
+    if title::is_showing() {
+        return Some(title::text());
+    }
+
+    if replay::is_watching() {
+        return replay::overlay_text(game);
+    }
+
+    if game.paused && !game.is_game_over() && focus::paused_game() {
+        return Some("paused while you were away\nclick or press any key to carry on".to_string());
+    }
+
+    if game.paused && !game.is_game_over() {
+        return Some(
+            "paused, since nothing's been pressed in a while\npress any key to carry on"
+                .to_string(),
+        );
+    }
+
+    // between runs is when a change of difficulty is least in the way
+    let suggestion = (!game.is_versus()).then(stats::suggestion).flatten();
+    let text = game_over_text(game, theme).map(|text| match suggestion {
+        Some(suggestion) => format!("{text}\n{}, press y to switch", suggestion.text()),
+        None => text,
+    });
+
+    let text = text
+        .or_else(|| game.countdown().map(|count| count.to_string()))
+        .or_else(|| wave_text(game));
+
+    // the tutorial's prompt goes under whatever else there is
+    match (text, tutorial::prompt()) {
+        (Some(text), Some(prompt)) => Some(format!("{text}\n{prompt}")),
+        (text, prompt) => text.or(prompt),
+    }
+}
+
+// the next wave's number over the board, in the break before it comes
+fn wave_text(game: &SnakeGame) -> Option<String> {
+    let wave = game.wave().filter(|wave| wave.is_between())?;
+    Some(format!(
+        "wave {} cleared!\nwave {} coming up, eat {} fruit to clear it",
+        wave.number - 1,
+        wave.number,
+        wave.quota()
+    ))
+}
+
+pub fn game_over_text(game: &SnakeGame, theme: Theme) -> Option<String> {
+    if game.is_versus() {
+        return game.game_over_reason.map(|reason| {
+            let result = match game.winner {
+                // in co-op it's the both of you against the board
+                _ if game.coop => format!(
+                    "{reason}\n{} {} together",
+                    theme.glyph(TileKind::Food),
+                    game.score
+                ),
+                Some(player) => format!("player {} wins!", player + 1),
+                None => "it's a draw".to_string(),
+            };
+            format!(
+                "{result}\n{}\npress space to restart",
+                versus_scores(game, theme)
+            )
+        });
+    }
+
+    if let Some(race_ms) = game.race_ms() {
+        return Some(format!(
+            "🏁 you won! 🏁\n{} fruit in {}\n{} {}\n⭐ {}\npress space to race again",
+            game.fruit_eaten,
+            timer::race_text(race_ms),
+            theme.glyph(TileKind::Food),
+            game.score,
+            game.high_score()
+        ));
+    }
+
+    if game.state() == GameState::Won {
+        return Some(format!(
+            "🏆 you won! 🏆\nthe snake covers {}% of the board\n{} {}\n🕒 {}\n⭐ {}\npress space to play again",
+            game.fill_percent(),
+            theme.glyph(TileKind::Food),
+            score_out_of(game),
+            timer::clock_text(crate::run_ms()),
+            game.high_score()
+        ));
+    }
+
+    game.game_over_reason.map(|reason| {
+        format!(
+            "{reason}\n{} {}\n🕒 {}\n⭐ {}\npress space to restart",
+            theme.glyph(TileKind::Food),
+            score_out_of(game),
+            timer::clock_text(crate::run_ms()),
+            game.high_score()
+        )
+    })
+}
+
+// `alpha` is how far along we are towards the next tick, from 0 to 1. only the smooth style
+// draws anything in between
+pub fn render(
+    game: &SnakeGame,
+    settings: &Settings,
+    decorations: &[(Vector, Decoration)],
+    debug_mode: bool,
+    alpha: f64,
+) -> Result<(), SlakeError> {
+    layout::update(game, settings)?;
+
+    RENDERER.with(|renderer| {
+        let mut renderer = renderer.borrow_mut();
+
+        if !renderer
+            .as_ref()
+            .is_some_and(|renderer| renderer.fits(game, settings))
+        {
+            *renderer = Some(build_renderer(game, settings)?);
+        }
+
+        let hud = HudState {
+            texts: [food_values(game), animation::floating_texts()].concat(),
+            info: hud_text(game, settings),
+            overlay: overlay_text(game, settings.theme),
+            alpha,
+            transform: board_transform(settings),
+        };
+        renderer
+            .as_mut()
+            .unwrap()
+            .draw(
+                game,
+                board_tiles(game, decorations, debug_mode, settings.reduced_motion),
+                &hud,
+            )
+            .map_err(SlakeError::render)?;
+
+        floor_map::update(game, settings.theme)?;
+        splits::update(settings.speedrun && !game.is_versus())
+    })
+}
+
+// see `Settings::drunk_board` and `Settings::screen_shake`, which reduced motion keeps still
+fn board_transform(settings: &Settings) -> BoardTransform {
+    if settings.reduced_motion {
+        return BoardTransform::default();
+    }
+
+    let mut transform = match settings.drunk_board {
+        true => animation::sway(js_sys::Date::now()),
+        false => BoardTransform::default(),
+    };
+    if settings.screen_shake {
+        transform.offset = animation::shake_offset();
+    }
+    transform
+}
+
+// The one place that knows every kind of `Renderer`, to pick one for the settings' style. Each
+// one takes over the page from whatever was there before, so switching back builds it from
+// scratch.
+fn build_renderer(game: &SnakeGame, settings: &Settings) -> Result<Box<dyn Renderer>, SlakeError> {
+    let rows = shown_rows(game);
+    match settings.render_style {
+        RenderStyle::Smooth => Ok(Box::new(canvas::build_board(game, settings, rows)?)),
+        RenderStyle::Emoji | RenderStyle::Squares => {
+            set_stylesheet(settings.theme)?;
+            let root_container = crate::get_element::<HtmlElement>("root")?;
+            Ok(Box::new(build_board(
+                game,
+                settings,
+                rows,
+                &root_container,
+            )?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_classes() {
+        let tile = Tile {
+            kind: TileKind::RivalHead,
+            segment: Some(Segment::Head(270)),
+            decoration: Some(Decoration::Trail(5)),
+            highlight: Some(Highlight::SemiOpen),
+            opacity: 37,
+        };
+        assert_eq!(
+            tile.class_name(),
+            "field tile-rival-head seg-head-270 deco-trail-2 tile-debug-semi-open faded-37"
+        );
+        let plain = Tile {
+            kind: TileKind::Food,
+            segment: None,
+            decoration: None,
+            highlight: None,
+            opacity: 100,
+        };
+        assert_eq!(plain.class_name(), "field tile-food");
+
+        // every opacity a tile can have gets a rule
+        for lifetime in 1..20 {
+            for left in 0..lifetime {
+                let opacity = fade(left, lifetime);
+                for opacity in [opacity, opacity / 2] {
+                    assert!(opacity == 100 || OPACITIES.contains(&opacity), "{opacity}");
+                }
+            }
+        }
+        let stylesheet = stylesheet(Theme::Classic);
+        assert!(stylesheet.contains(".field.faded-12 { opacity: 12%; }"));
+        assert!(stylesheet.contains(".field.tile-debug-occupied { background-color: orange "));
+        assert!(!stylesheet.contains("tile-debug-open"));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::animation::FloatingText;
+use crate::render::Tile;
+use crate::settings::Settings;
+use crate::snake::SnakeGame;
+
+use wasm_bindgen::prelude::*;
+
+// What every way of drawing the board has in common, so the game loop doesn't care which one's
+// in use. `render::render` works out the tiles and everything around them once a frame and hands
+// them to whichever renderer the settings picked, building a new one when the old one no longer
+// fits, see `render::build_renderer`. That's the only place that needs to know about a new
+// kind, whether it draws into the page, onto a canvas, or somewhere else entirely.
+
+// Everything drawn on top of and around the tiles.
+#[derive(Clone, Default, Debug)]
+pub struct HudState {
+    // see `animation::floating_texts` and `render::food_values`
+    pub texts: Vec<FloatingText>,
+    // the two lines under the board, see `render::hud_text`
+    pub info: (String, String),
+    // what covers the board, if anything, see `render::overlay_text`
+    pub overlay: Option<String>,
+    // how far along it is towards the next tick, from 0 to 1, for the styles that draw
+    // anything in between
+    pub alpha: f64,
+    pub transform: BoardTransform,
+}
+
+// How the board as a whole is moved about, for `Settings::drunk_board` and
+// `Settings::screen_shake`. Only the smooth style goes along with it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BoardTransform {
+    // in tiles
+    pub offset: (f64, f64),
+    // in radians, around the middle of the board
+    pub angle: f64,
+    // how wide it is, from 1 down through 0 to -1 for mirrored
+    pub scale_x: f64,
+}
+
+impl Default for BoardTransform {
+    fn default() -> BoardTransform {
+        BoardTransform {
+            offset: (0.0, 0.0),
+            angle: 0.0,
+            scale_x: 1.0,
+        }
+    }
+}
+
+pub trait Renderer {
+    // whether it can still draw `game` with `settings`, or a new one has to be built first
+    fn fits(&self, game: &SnakeGame, settings: &Settings) -> bool;
+
+    // `tiles` is the whole board, see `render::board_tiles`, even where only some of its rows
+    // are shown. `game` is there for whatever the tiles don't say, like where in between two
+    // tiles a snake is.
+    fn draw(&mut self, game: &SnakeGame, tiles: Vec<Tile>, hud: &HudState) -> Result<(), JsValue>;
+}
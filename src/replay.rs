@@ -0,0 +1,624 @@
+use crate::error::SlakeError;
+use crate::geometry::Direction;
+use crate::random;
+use crate::scheduler::Scheduler;
+use crate::snake::SnakeGame;
+use crate::snapshot::Snapshot;
+use crate::toast;
+
+use std::cell::{Cell, RefCell};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{HtmlDivElement, HtmlElement, HtmlInputElement};
+
+// Watching a run again, from a link. Every run keeps a snapshot of how it started along with
+// each turn anyone pressed and the tick it went in before, and since everything else comes from
+// the random numbers the snapshot has, that's all it takes to play the whole run back exactly.
+// The link has it all after `#replay=`, and a page opened on one is only a viewer: the run plays
+// on a clock of its own, separate from the one the page's own run ticks on, with buttons to
+// pause it, change the speed, and go to any tick. Runs on levels have no snapshot, so they
+// can't be watched, and neither can online games or runs the dev console has been used on.
+
+const HASH_PREFIX: &str = "#replay=";
+
+pub const SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+// into `SPEEDS`
+const NORMAL_SPEED: usize = 1;
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Turn {
+    // the tick it went in before, counted the same as `SnakeGame::ticks`
+    pub tick: usize,
+    pub player: usize,
+    pub direction: Direction,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Recording {
+    pub start: Snapshot,
+    // in the order they were pressed
+    pub turns: Vec<Turn>,
+    // how many ticks the run went on for after `start`
+    pub length: usize,
+}
+
+impl Recording {
+    pub fn new(start: Snapshot) -> Recording {
+        Recording {
+            start,
+            turns: vec![],
+            length: 0,
+        }
+    }
+
+    // The snapshot on a line of its own, then `length=` and a `turn=tick player direction` line
+    // for each turn, like `turn=12 0 up`.
+    pub fn serialize(&self) -> String {
+        let mut source = self.start.serialize() + "\n";
+
+        source += &format!("length={}\n", self.length);
+        for turn in &self.turns {
+            source += &format!(
+                "turn={} {} {}\n",
+                turn.tick,
+                turn.player,
+                turn.direction.name()
+            );
+        }
+
+        source
+    }
+
+    // like snapshots, a recording that's broken anywhere is no good at all
+    pub fn parse(source: &str) -> Result<Recording, String> {
+        let mut start = String::new();
+        let mut turns = vec![];
+        let mut length = None;
+
+        for line in source.lines() {
+            match line.split_once('=') {
+                Some(("length", value)) => {
+                    length = Some(value.trim().parse().map_err(|_| "bad length")?)
+                }
+                Some(("turn", value)) => {
+                    let fields = value.split_whitespace().collect::<Vec<_>>();
+                    let [tick, player, direction] = fields[..] else {
+                        return Err(format!("bad turn '{value}'"));
+                    };
+
+                    turns.push(Turn {
+                        tick: tick.parse().map_err(|_| format!("bad turn '{value}'"))?,
+                        player: player.parse().map_err(|_| format!("bad turn '{value}'"))?,
+                        direction: Direction::from_name(direction)
+                            .ok_or_else(|| format!("unknown direction '{direction}'"))?,
+                    });
+                }
+                _ => {
+                    start += line;
+                    start += "\n";
+                }
+            }
+        }
+
+        let start = Snapshot::parse(&start)?;
+        if turns.iter().any(|turn| turn.tick < start.ticks) {
+            return Err("a turn comes before the start".to_string());
+        }
+        if turns.windows(2).any(|pair| pair[1].tick < pair[0].tick) {
+            return Err("the turns are out of order".to_string());
+        }
+
+        Ok(Recording {
+            start,
+            turns,
+            length: length.ok_or("there's no length")?,
+        })
+    }
+}
+
+// A recording being played back, with random numbers of its own like the title's demo, so
+// nothing it does draws from anything else's.
+pub struct Playback {
+    recording: Recording,
+    // how it was at the start, to go back to
+    start: SnakeGame,
+    game: SnakeGame,
+    random: random::Aside,
+    // into `recording.turns`, the first that hasn't gone in yet
+    next_turn: usize,
+}
+
+impl Playback {
+    pub fn new(recording: Recording) -> Result<Playback, String> {
+        let mut random = random::Aside::new(recording.start.seed);
+
+        random::swap(&mut random);
+        let start = SnakeGame::from_snapshot(&recording.start);
+        random::swap(&mut random);
+
+        let mut start = start?;
+        // the countdown only held up the person playing, and nothing moves during it
+        start.skip_countdown();
+        start.paused = false;
+
+        Ok(Playback {
+            recording,
+            game: start.clone(),
+            start,
+            random,
+            next_turn: 0,
+        })
+    }
+
+    pub fn game(&self) -> &SnakeGame {
+        &self.game
+    }
+
+    pub fn length(&self) -> usize {
+        self.recording.length
+    }
+
+    // ticks since the start
+    pub fn position(&self) -> usize {
+        self.game.ticks - self.start.ticks
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.position() >= self.length() || self.game.is_game_over()
+    }
+
+    pub fn step(&mut self) {
+        if self.is_over() {
+            return;
+        }
+
+        random::swap(&mut self.random);
+
+        while let Some(turn) = self
+            .recording
+            .turns
+            .get(self.next_turn)
+            .filter(|turn| turn.tick <= self.game.ticks)
+        {
+            self.game
+                .change_player_direction(turn.player, turn.direction.clone());
+            self.next_turn += 1;
+        }
+        self.game.tick();
+        // nobody's listening, the same as the title's demo
+        self.game.take_events();
+
+        random::swap(&mut self.random);
+    }
+
+    // Ticks only go forwards, so going back means starting over and playing up to `position`
+    // again.
+    pub fn seek(&mut self, position: usize) {
+        if position < self.position() {
+            self.game = self.start.clone();
+            self.next_turn = 0;
+
+            random::swap(&mut self.random);
+            random::restore(self.recording.start.random);
+            random::swap(&mut self.random);
+        }
+
+        while self.position() < position && !self.is_over() {
+            self.step();
+        }
+    }
+}
+
+struct Viewer {
+    playback: Playback,
+    playing: bool,
+    // into `SPEEDS`
+    speed: usize,
+}
+
+thread_local! {
+    // the run being played, and the last one to finish, which is what gets shared
+    static RECORDING: RefCell<Option<Recording>> = const { RefCell::new(None) };
+    static FINISHED: RefCell<Option<Recording>> = const { RefCell::new(None) };
+
+    static VIEWER: RefCell<Option<Viewer>> = const { RefCell::new(None) };
+    static CLOCK: Scheduler = Scheduler::new(tick);
+    // whether the scrubber's being dragged, so ticking doesn't pull it out from under the pointer
+    static SCRUBBING: Cell<bool> = const { Cell::new(false) };
+}
+
+// on every restart, once the run's started
+pub fn start(game: &SnakeGame) {
+    let recording = (!crate::net::is_active() && !game.tampered)
+        .then(|| game.to_snapshot())
+        .flatten()
+        .map(Recording::new);
+    RECORDING.with(|recording_in| *recording_in.borrow_mut() = recording);
+}
+
+// a turn for the run being recorded, going in before the tick after `tick`
+pub fn note(tick: usize, player: usize, direction: Direction) {
+    RECORDING.with(|recording| {
+        if let Some(recording) = recording.borrow_mut().as_mut() {
+            recording.turns.push(Turn {
+                tick,
+                player,
+                direction,
+            });
+        }
+    });
+}
+
+pub fn finish(game: &SnakeGame) {
+    let Some(mut recording) = RECORDING.with(|recording| recording.borrow_mut().take()) else {
+        return;
+    };
+    // whatever was done to it doesn't come along in the turns
+    if game.tampered {
+        return;
+    }
+
+    recording.length = game.ticks - recording.start.ticks;
+    FINISHED.with(|finished| *finished.borrow_mut() = Some(recording));
+}
+
+// copies a link to watch the last run that finished
+pub fn share() {
+    let Some(recording) = FINISHED.with(|finished| finished.borrow().clone()) else {
+        let _ = toast::show("There's no finished run to share yet");
+        return;
+    };
+
+    let Ok(window) = crate::error::window() else {
+        return;
+    };
+    let Ok(page) = window.location().href() else {
+        return;
+    };
+    // without whatever hash it has already
+    let page = page.split('#').next().unwrap_or_default();
+    let link = format!(
+        "{page}{HASH_PREFIX}{}",
+        js_sys::encode_uri_component(&recording.serialize())
+    );
+
+    if crate::export::copy_text(&link) {
+        let _ = toast::show("Copied a link to watch the last run");
+    }
+}
+
+pub fn is_watching() -> bool {
+    VIEWER.with(|viewer| viewer.borrow().is_some())
+}
+
+// the game being watched, to draw instead of the page's own
+pub fn with_watched<R>(f: impl FnOnce(&SnakeGame) -> R) -> Option<R> {
+    VIEWER.with(|viewer| {
+        viewer
+            .borrow()
+            .as_ref()
+            .map(|viewer| f(viewer.playback.game()))
+    })
+}
+
+// Opens the viewer if the page was opened on a replay link. Returns whether it did.
+pub fn watch_from_location() -> bool {
+    let Ok(window) = crate::error::window() else {
+        return false;
+    };
+    let hash = window.location().hash().unwrap_or_default();
+    let Some(encoded) = hash.strip_prefix(HASH_PREFIX) else {
+        return false;
+    };
+
+    let playback = js_sys::decode_uri_component(encoded)
+        .ok()
+        .and_then(|source| source.as_string())
+        .ok_or_else(|| "it isn't encoded properly".to_string())
+        .and_then(|source| Recording::parse(&source))
+        .and_then(Playback::new);
+
+    match playback {
+        Ok(playback) => match watch(playback) {
+            Ok(()) => true,
+            Err(error) => {
+                crate::error::report(error, None);
+                false
+            }
+        },
+        Err(error) => {
+            crate::log(&format!("Can't watch the replay: {error}"));
+            let _ = toast::show(&format!("Can't watch the replay: {error}"));
+            false
+        }
+    }
+}
+
+fn watch(playback: Playback) -> Result<(), SlakeError> {
+    let length = playback.length();
+    VIEWER.with(|viewer| {
+        *viewer.borrow_mut() = Some(Viewer {
+            playback,
+            playing: true,
+            speed: NORMAL_SPEED,
+        })
+    });
+
+    build_controls(length)?;
+    retime();
+    update_controls()?;
+    crate::draw();
+
+    Ok(())
+}
+
+// Back to the page's own run, on the title screen so it doesn't start before anyone's ready.
+pub fn close() {
+    CLOCK.with(|clock| clock.stop());
+    VIEWER.with(|viewer| viewer.borrow_mut().take());
+    if let Ok(controls) = crate::get_element::<HtmlElement>("replay") {
+        controls.remove();
+    }
+
+    // so reloading doesn't bring the viewer back
+    if let Ok(window) = crate::error::window() {
+        let _ = window.location().set_hash("");
+    }
+    crate::title::show();
+}
+
+fn with_viewer(f: impl FnOnce(&mut Viewer)) {
+    VIEWER.with(|viewer| {
+        if let Some(viewer) = viewer.borrow_mut().as_mut() {
+            f(viewer);
+        }
+    });
+}
+
+// one of the viewer's ticks, off its own clock
+fn tick() {
+    with_viewer(|viewer| {
+        viewer.playback.step();
+        // it waits at the end, for going back over it
+        if viewer.playback.is_over() {
+            viewer.playing = false;
+        }
+    });
+    retime();
+    crate::error::report_failure(update_controls());
+    crate::draw();
+}
+
+// puts the clock in line with whether it's playing and how fast
+fn retime() {
+    let timing = VIEWER.with(|viewer| {
+        viewer.borrow().as_ref().and_then(|viewer| {
+            let tick_ms = viewer.playback.game().tick_ms.max(1) as f64;
+            viewer
+                .playing
+                .then(|| (tick_ms / SPEEDS[viewer.speed]).max(1.0) as i32)
+        })
+    });
+
+    CLOCK.with(|clock| match timing {
+        Some(interval_ms) if !clock.is_running() => clock.start(interval_ms),
+        // starting it again would only start the wait over
+        Some(_) => (),
+        None => clock.stop(),
+    });
+}
+
+fn toggle_playing() {
+    with_viewer(|viewer| {
+        // playing from the end goes from the start again
+        if viewer.playback.is_over() {
+            viewer.playback.seek(0);
+        }
+        viewer.playing = !viewer.playing;
+    });
+    retime();
+    crate::error::report_failure(update_controls());
+    crate::draw();
+}
+
+fn change_speed() {
+    with_viewer(|viewer| viewer.speed = (viewer.speed + 1) % SPEEDS.len());
+    // the new speed only takes once the clock's started over
+    CLOCK.with(|clock| clock.stop());
+    retime();
+    crate::error::report_failure(update_controls());
+}
+
+fn seek(position: usize) {
+    with_viewer(|viewer| {
+        viewer.playing = false;
+        viewer.playback.seek(position);
+    });
+    retime();
+    crate::error::report_failure(update_controls());
+    crate::draw();
+}
+
+fn position() -> usize {
+    VIEWER.with(|viewer| {
+        viewer
+            .borrow()
+            .as_ref()
+            .map_or(0, |viewer| viewer.playback.position())
+    })
+}
+
+// Takes a key while watching: space plays and pauses, left and right go back and forward a
+// tick, and escape goes back to the page's own run. Returns whether it did anything with it.
+pub fn key(key: &str) -> bool {
+    match key {
+        " " => toggle_playing(),
+        "ArrowLeft" => seek(position().saturating_sub(1)),
+        "ArrowRight" => seek(position() + 1),
+        "Escape" => close(),
+        _ => return false,
+    }
+    true
+}
+
+fn build_controls(length: usize) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let controls = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+    controls.set_id("replay");
+    controls.set_class_name("replay");
+    controls.set_inner_html(&format!(
+        r#"<div id="replay_play" class="button"></div> <div id="replay_speed" class="button"></div> <span id="replay_position"></span><input id="replay_scrub" type="range" min="0" max="{length}" value="0">"#
+    ));
+    crate::error::body()?.append_child(&controls)?;
+
+    let handle_play = Closure::wrap(Box::new(toggle_playing) as Box<dyn FnMut()>);
+    let handle_speed = Closure::wrap(Box::new(change_speed) as Box<dyn FnMut()>);
+    let handle_scrub = Closure::wrap(Box::new(|| {
+        let scrub = match crate::get_element::<HtmlInputElement>("replay_scrub") {
+            Ok(scrub) => scrub,
+            Err(error) => return crate::error::report(error, None),
+        };
+        SCRUBBING.with(|scrubbing| scrubbing.set(true));
+        seek(scrub.value_as_number() as usize);
+        SCRUBBING.with(|scrubbing| scrubbing.set(false));
+    }) as Box<dyn FnMut()>);
+
+    crate::get_element::<HtmlElement>("replay_play")?
+        .set_onclick(Some(handle_play.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("replay_speed")?
+        .set_onclick(Some(handle_speed.as_ref().unchecked_ref()));
+    crate::get_element::<HtmlElement>("replay_scrub")?
+        .set_oninput(Some(handle_scrub.as_ref().unchecked_ref()));
+
+    // the viewer's only ever opened the once
+    handle_play.forget();
+    handle_speed.forget();
+    handle_scrub.forget();
+
+    Ok(())
+}
+
+fn update_controls() -> Result<(), SlakeError> {
+    let Some((playing, speed, position, length)) = VIEWER.with(|viewer| {
+        viewer.borrow().as_ref().map(|viewer| {
+            (
+                viewer.playing,
+                SPEEDS[viewer.speed],
+                viewer.playback.position(),
+                viewer.playback.length(),
+            )
+        })
+    }) else {
+        return Ok(());
+    };
+
+    crate::get_element::<HtmlElement>("replay_play")?.set_inner_text(if playing {
+        "pause"
+    } else {
+        "play"
+    });
+    crate::get_element::<HtmlElement>("replay_speed")?.set_inner_text(&format!("{speed}×"));
+    crate::get_element::<HtmlElement>("replay_position")?
+        .set_inner_text(&format!("tick {position} of {length} "));
+    if !SCRUBBING.with(|scrubbing| scrubbing.get()) {
+        crate::get_element::<HtmlInputElement>("replay_scrub")?
+            .set_value_as_number(position as f64);
+    }
+    Ok(())
+}
+
+// what to show over the board being watched
+pub fn overlay_text(game: &SnakeGame) -> Option<String> {
+    let (playing, over) = VIEWER.with(|viewer| {
+        viewer.borrow().as_ref().map_or((false, false), |viewer| {
+            (viewer.playing, viewer.playback.is_over())
+        })
+    });
+
+    match (game.game_over_reason, playing, over) {
+        (Some(reason), _, _) => Some(format!(
+            "{reason}\n{} points\nspace to watch again, escape to play",
+            game.score
+        )),
+        (None, _, true) => Some("that's where it ends\nspace to watch again".to_string()),
+        (None, false, false) => Some("replay paused\nspace to carry on".to_string()),
+        (None, true, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Topology;
+
+    // plays a run the way a person might, recording it as it goes
+    fn record_run() -> (Recording, SnakeGame) {
+        random::seed([7, 11]);
+        let mut game = SnakeGame::new(15, 11);
+        game.topology = Topology::Wrap;
+        game.special_food = true;
+        game.restart();
+
+        let mut recording = Recording::new(game.to_snapshot().unwrap());
+        for tick in 0..150 {
+            if tick % 7 == 3 {
+                let direction = Direction::ALL[tick % 4].clone();
+                recording.turns.push(Turn {
+                    tick: game.ticks,
+                    player: 0,
+                    direction: direction.clone(),
+                });
+                game.change_player_direction(0, direction);
+            }
+            game.tick();
+            if game.is_game_over() {
+                break;
+            }
+        }
+        recording.length = game.ticks - recording.start.ticks;
+
+        (recording, game)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (recording, _) = record_run();
+        assert!(!recording.turns.is_empty());
+        assert_eq!(
+            Recording::parse(&recording.serialize()),
+            Ok(recording.clone())
+        );
+
+        let broken = recording.serialize() + "turn=200 0 sideways\n";
+        assert!(Recording::parse(&broken).is_err());
+        let early = recording.serialize() + "turn=0 0 up\n";
+        assert!(Recording::parse(&early).is_err());
+        let no_length = recording.serialize().replace("length=", "# ");
+        assert!(Recording::parse(&no_length).is_err());
+    }
+
+    #[test]
+    fn playing_back() {
+        let (recording, played) = record_run();
+        let mut playback = Playback::new(recording.clone()).unwrap();
+
+        // taking numbers from the real stream in between doesn't change anything
+        while !playback.is_over() {
+            random::get_u16();
+            playback.step();
+        }
+        assert_eq!(playback.position(), recording.length);
+        assert_eq!(playback.game().score, played.score);
+        assert_eq!(playback.game().snakes()[0].body, played.snakes()[0].body);
+        assert_eq!(playback.game().game_over_reason, played.game_over_reason);
+
+        // going back, and forward over it all again
+        playback.seek(10);
+        assert_eq!(playback.position(), 10);
+        playback.seek(recording.length);
+        assert_eq!(playback.game().score, played.score);
+        assert_eq!(playback.game().snakes()[0].body, played.snakes()[0].body);
+    }
+}
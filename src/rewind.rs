@@ -0,0 +1,128 @@
+use crate::random;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+// Practice mode keeps the last few seconds of the run, so a tight spot can be gone back to and
+// tried again instead of starting over. The game as it was before each tick goes on the end,
+// along with where the random numbers were, so after a rewind the food comes out the same way
+// it did the first time unless the snake does something different. Runs that have been
+// rewound don't count for anything, like ones the dev console has been used on.
+
+// about ten seconds at the default speed
+pub const HISTORY_TICKS: usize = 100;
+// how far back each press goes
+pub const REWIND_TICKS: usize = 10;
+
+#[derive(Default)]
+pub struct History {
+    // oldest first
+    before: VecDeque<(SnakeGame, ([u16; 2], usize))>,
+}
+
+impl History {
+    // call this just before ticking
+    pub fn record(&mut self, game: &SnakeGame) {
+        if let Some((last, _)) = self.before.back() {
+            // a new run has nothing to go back to yet
+            if last.seed != game.seed || last.ticks > game.ticks {
+                self.before.clear();
+            // a tick that didn't move anything, like during the countdown, only needs keeping
+            // once
+            } else if last.ticks == game.ticks {
+                self.before.pop_back();
+            }
+        }
+
+        if self.before.len() == HISTORY_TICKS {
+            self.before.pop_front();
+        }
+        self.before.push_back((game.clone(), random::state()));
+    }
+
+    // Goes back `ticks` ticks, or as far back as there is, putting the random numbers back to
+    // how they were then. `None` with nothing to go back to.
+    pub fn rewind(&mut self, ticks: usize) -> Option<SnakeGame> {
+        let keep = self.before.len().saturating_sub(ticks.max(1));
+        let (mut game, random_state) = self.before.drain(keep..).next()?;
+
+        random::restore(random_state);
+        game.tampered = true;
+        Some(game)
+    }
+}
+
+thread_local! {
+    static HISTORY: RefCell<History> = RefCell::new(History::default());
+}
+
+pub fn record(game: &SnakeGame) {
+    HISTORY.with(|history| history.borrow_mut().record(game));
+}
+
+// Puts the game back `REWIND_TICKS` ticks, with a countdown to get ready again. Returns whether
+// there was anything to go back to.
+pub fn rewind() -> bool {
+    let Some(mut rewound) = HISTORY.with(|history| history.borrow_mut().rewind(REWIND_TICKS))
+    else {
+        return false;
+    };
+
+    rewound.start_countdown();
+    crate::GAME.with(|game| *game.borrow_mut() = rewound);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Direction;
+    use crate::geometry::Topology;
+    use crate::mode::GameMode;
+
+    #[test]
+    fn rewinding() {
+        random::seed([3, 4]);
+        // somewhere the snake can go on forever
+        let mut game = SnakeGame::new(21, 15);
+        game.topology = Topology::Wrap;
+        game.set_mode(GameMode::Zen);
+        game.restart();
+        let mut history = History::default();
+        assert!(history.rewind(REWIND_TICKS).is_none());
+
+        let mut snapshots = vec![];
+        for _i in 0..HISTORY_TICKS + 20 {
+            history.record(&game);
+            snapshots.push(game.to_snapshot());
+            game.tick();
+        }
+
+        let mut rewound = history.rewind(REWIND_TICKS).unwrap();
+        assert!(rewound.tampered);
+        rewound.tampered = false;
+        assert_eq!(rewound.to_snapshot(), snapshots[HISTORY_TICKS + 10]);
+
+        // and plays out the same way again, random numbers and all
+        let mut again = vec![];
+        for _i in 0..REWIND_TICKS {
+            again.push(rewound.to_snapshot());
+            rewound.tick();
+        }
+        assert_eq!(again, snapshots[HISTORY_TICKS + 10..]);
+
+        // only as far back as it kept
+        let oldest = history.rewind(1000).unwrap();
+        assert_eq!(oldest.ticks, 20);
+        assert!(history.rewind(1).is_none());
+
+        // starting over forgets the last run
+        history.record(&game);
+        game.restart();
+        game.change_direction(Direction::Up);
+        history.record(&game);
+        assert_eq!(history.rewind(5).unwrap().seed, game.seed);
+        assert!(history.rewind(5).is_none());
+    }
+}
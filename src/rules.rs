@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+// The basics of what's allowed, for players used to other snake games. Slake's own rules leave a
+// puddle behind every fruit and count the tile a tail is leaving as still taken, so following
+// your own tail too closely ends the run. The classic rules of most other snake games do
+// neither. Biting your own tail off instead of crashing is neither's, and only there for
+// anyone who'd like it.
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RuleSet {
+    // eating leaves a hazard where the tail was, see `SnakeGame::double_hazards` for how many
+    pub hazards_on_eat: bool,
+    // a tail moving off its tile this tick still blocks it, so a head can't go there until the
+    // tick after
+    pub tail_blocks: bool,
+    // running into a snake, your own included, ends the run. without it snakes slide over each
+    // other like in zen
+    pub self_collision: bool,
+    // with `self_collision`, running into your own body bites it off there instead of ending the
+    // run. what's bitten off turns into hazards, and costs a point a segment
+    pub tail_cut: bool,
+}
+
+const NAMES: [&str; 4] = [
+    "hazards_on_eat",
+    "tail_blocks",
+    "self_collision",
+    "tail_cut",
+];
+
+impl RuleSet {
+    pub const SLAKE: RuleSet = RuleSet {
+        hazards_on_eat: true,
+        tail_blocks: true,
+        self_collision: true,
+        tail_cut: false,
+    };
+
+    pub const CLASSIC: RuleSet = RuleSet {
+        hazards_on_eat: false,
+        tail_blocks: false,
+        self_collision: true,
+        tail_cut: false,
+    };
+
+    fn flags(&self) -> [bool; 4] {
+        [
+            self.hazards_on_eat,
+            self.tail_blocks,
+            self.self_collision,
+            self.tail_cut,
+        ]
+    }
+
+    // the rules that are on, like "hazards_on_eat,self_collision", or "none"
+    pub fn name(&self) -> String {
+        let on = NAMES
+            .iter()
+            .zip(self.flags())
+            .filter(|(_, on)| *on)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+
+        match on.is_empty() {
+            true => "none".to_string(),
+            false => on.join(","),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RuleSet> {
+        let mut rules = RuleSet {
+            hazards_on_eat: false,
+            tail_blocks: false,
+            self_collision: false,
+            tail_cut: false,
+        };
+
+        if name == "none" {
+            return Some(rules);
+        }
+
+        for rule in name.split(',') {
+            match rule.trim() {
+                "hazards_on_eat" => rules.hazards_on_eat = true,
+                "tail_blocks" => rules.tail_blocks = true,
+                "self_collision" => rules.self_collision = true,
+                "tail_cut" => rules.tail_cut = true,
+                _ => return None,
+            }
+        }
+
+        Some(rules)
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::SLAKE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(RuleSet::CLASSIC.name(), "self_collision");
+
+        let none = RuleSet {
+            self_collision: false,
+            ..RuleSet::CLASSIC
+        };
+        let biting = RuleSet {
+            tail_cut: true,
+            ..RuleSet::SLAKE
+        };
+        assert_eq!(
+            biting.name(),
+            "hazards_on_eat,tail_blocks,self_collision,tail_cut"
+        );
+
+        for rules in [RuleSet::SLAKE, RuleSet::CLASSIC, none, biting] {
+            assert_eq!(RuleSet::from_name(&rules.name()), Some(rules));
+        }
+
+        assert_eq!(RuleSet::from_name("tail_blocks,gravity"), None);
+        assert_eq!(RuleSet::from_name(""), None);
+    }
+}
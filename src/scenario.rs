@@ -0,0 +1,218 @@
+use crate::dev_console::Command;
+use crate::snake::SnakeGame;
+
+use std::cell::RefCell;
+
+// A script of dev console commands, each with the tick it happens on, like
+//
+//     # a ring of hazards once the snake's had a moment to get going
+//     at tick 50 spawn hazard ring
+//     at 80 spawn bonus 2
+//
+// for demos, tutorials, and stress tests that would otherwise need a new build. Blank lines and
+// lines starting with `#` are skipped, and "tick" is optional. The commands are the console's
+// own, so a script spoils the run the same way typing them would.
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Scenario {
+    // in the order they happen
+    pub steps: Vec<(usize, Command)>,
+}
+
+impl Scenario {
+    pub fn parse(source: &str) -> Result<Scenario, String> {
+        let mut steps = vec![];
+
+        for (line_number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let step = Self::parse_step(line).map_err(|error| {
+                // counting from 1, like an editor would
+                format!("line {}: {error}", line_number + 1)
+            })?;
+            steps.push(step);
+        }
+
+        // same tick, same order as written
+        steps.sort_by_key(|(tick, _)| *tick);
+
+        Ok(Scenario { steps })
+    }
+
+    fn parse_step(line: &str) -> Result<(usize, Command), String> {
+        let rest = line
+            .strip_prefix("at ")
+            .ok_or_else(|| "steps start with 'at <tick>'".to_string())?
+            .trim_start();
+        let rest = rest.strip_prefix("tick ").unwrap_or(rest).trim_start();
+
+        let (tick, command) = rest.split_once(' ').unwrap_or((rest, ""));
+        let tick = tick
+            .parse::<usize>()
+            .map_err(|_| format!("'{tick}' isn't a tick"))?;
+
+        Ok((tick, Command::parse(command)?))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScenarioRunner {
+    scenario: Scenario,
+    // the first step that hasn't happened yet
+    next: usize,
+    // whether the last restart was the script's own doing
+    seeded: bool,
+}
+
+impl ScenarioRunner {
+    pub fn new(scenario: Scenario) -> ScenarioRunner {
+        ScenarioRunner {
+            scenario,
+            next: 0,
+            seeded: false,
+        }
+    }
+
+    // the steps that should have happened by now and haven't yet
+    pub fn due(&mut self, game: &SnakeGame) -> Vec<Command> {
+        let due = self.scenario.steps[self.next..]
+            .iter()
+            .take_while(|(tick, _)| *tick <= game.ticks)
+            .map(|(_, command)| command.clone())
+            .collect::<Vec<_>>();
+
+        self.next += due.len();
+        // a seed restarts the game, which shouldn't start the script over again
+        if due
+            .iter()
+            .any(|command| matches!(command, Command::Seed(_)))
+        {
+            self.seeded = true;
+        }
+
+        due
+    }
+
+    // a new game plays the whole script again, unless the script is what restarted it
+    pub fn on_restart(&mut self) {
+        if !std::mem::take(&mut self.seeded) {
+            self.next = 0;
+        }
+    }
+}
+
+thread_local! {
+    static RUNNER: RefCell<Option<ScenarioRunner>> = const { RefCell::new(None) };
+}
+
+pub fn load(source: &str) -> Result<(), String> {
+    let scenario = Scenario::parse(source)?;
+    RUNNER.with(|runner| runner.replace(Some(ScenarioRunner::new(scenario))));
+
+    run_due();
+    Ok(())
+}
+
+pub fn stop() {
+    RUNNER.with(|runner| runner.replace(None));
+}
+
+pub fn on_restart() {
+    RUNNER.with(|runner| {
+        if let Some(runner) = runner.borrow_mut().as_mut() {
+            runner.on_restart();
+        }
+    });
+}
+
+// Runs whatever's due on the game the page is showing. Scripts don't run while playing online,
+// for the same reason the console doesn't.
+pub fn run_due() {
+    if crate::net::is_active() {
+        return;
+    }
+
+    let due = RUNNER.with(|runner| match runner.borrow_mut().as_mut() {
+        Some(runner) => crate::GAME.with(|game| runner.due(&game.borrow())),
+        None => vec![],
+    });
+
+    if due.is_empty() {
+        return;
+    }
+
+    for command in &due {
+        let message = match crate::dev_console::execute(command) {
+            Ok(message) => message,
+            Err(error) => format!("error: {error}"),
+        };
+        crate::log(&format!("scenario: {message}"));
+    }
+
+    // the commands have events of their own, like a seed's restart
+    crate::handle_events();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev_console::HazardSpawn;
+
+    #[test]
+    fn parsing() {
+        let scenario = Scenario::parse(
+            "# stress test\n\
+             at tick 50 spawn hazard ring\n\
+             \n\
+             at 0 seed 65537\n\
+             at 50 spawn food 3\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            scenario.steps,
+            vec![
+                (0, Command::Seed([1, 1])),
+                (50, Command::SpawnHazards(HazardSpawn::Ring)),
+                (
+                    50,
+                    Command::Spawn {
+                        kind: crate::food::FoodKind::Normal,
+                        count: 3
+                    }
+                ),
+            ]
+        );
+
+        assert_eq!(
+            Scenario::parse("at 1 help\nspawn food"),
+            Err("line 2: steps start with 'at <tick>'".to_string())
+        );
+        assert!(Scenario::parse("at soon help").is_err());
+        assert!(Scenario::parse("at 5 spawn cake").is_err());
+    }
+
+    #[test]
+    fn running() {
+        let scenario = Scenario::parse("at 0 seed 1\nat 2 spawn food\nat 2 help").unwrap();
+        let mut runner = ScenarioRunner::new(scenario);
+        let mut game = SnakeGame::new(21, 15);
+
+        assert_eq!(runner.due(&game), vec![Command::Seed([0, 1])]);
+        // the seed's own restart doesn't rewind
+        runner.on_restart();
+        assert!(runner.due(&game).is_empty());
+
+        game.ticks = 3;
+        assert_eq!(runner.due(&game).len(), 2);
+        assert!(runner.due(&game).is_empty());
+
+        // but the player's does
+        runner.on_restart();
+        game.ticks = 0;
+        assert_eq!(runner.due(&game), vec![Command::Seed([0, 1])]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::rc::Weak;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::window;
+
+// Calls something every so often, holding on to the callback and how often it's meant to run so
+// it can be retimed, paused, stepped through, or stopped. Everything goes through `&self`, so the
+// callback can change the timing while it runs. Dropping it stops it too, so an old one never
+// keeps going behind a new one.
+//
+// It runs off animation frames rather than `setInterval`, which drifts and bunches up when the
+// page is busy. Every frame, the time since the last one by `performance.now()` goes into an
+// `Accumulator`, and the callback runs once for every whole interval that's added up, so the
+// speed stays the same however often the frames come. A page that's fallen far behind only
+// catches up `MAX_CATCH_UP` calls' worth, and skips the rest.
+
+// any more than that at once and it's better to lose the time than to jump ahead
+const MAX_CATCH_UP: u32 = 5;
+
+// Time that's gone by and hasn't added up to a whole interval yet, by the page's clock.
+#[derive(Default, Debug)]
+struct Accumulator {
+    // the last frame, `None` until the first one since it was started over
+    last_ms: Option<f64>,
+    unspent_ms: f64,
+}
+
+impl Accumulator {
+    // How many intervals are due by `now_ms`. The first frame only starts the clock.
+    fn frame(&mut self, now_ms: f64, interval_ms: f64) -> u32 {
+        let interval_ms = interval_ms.max(1.0);
+        let Some(last_ms) = self.last_ms.replace(now_ms) else {
+            return 0;
+        };
+
+        // a clock that goes backwards just doesn't count
+        self.unspent_ms += (now_ms - last_ms).max(0.0);
+        let due = (self.unspent_ms / interval_ms).floor();
+        if due > MAX_CATCH_UP as f64 {
+            self.unspent_ms %= interval_ms;
+            return MAX_CATCH_UP;
+        }
+
+        self.unspent_ms -= due * interval_ms;
+        due as u32
+    }
+
+    // how far along it is towards the next one, from 0 to 1
+    fn fraction(&self, interval_ms: f64) -> f64 {
+        (self.unspent_ms / interval_ms.max(1.0)).clamp(0.0, 1.0)
+    }
+}
+
+// what the frames share with the scheduler, so dropping the scheduler lets them go
+struct Shared {
+    callback: RefCell<Box<dyn FnMut()>>,
+    // see `between_ticks`
+    between: RefCell<Option<Box<dyn FnMut()>>>,
+    // how often it's meant to run, `None` while stopped
+    interval_ms: Cell<Option<i32>>,
+    paused: Cell<bool>,
+    accumulator: RefCell<Accumulator>,
+    // the frame that's been asked for and hasn't come yet
+    handle: Cell<Option<i32>>,
+    // the frame closure as a function, to ask for the next one with
+    request: RefCell<Option<js_sys::Function>>,
+}
+
+pub struct Scheduler {
+    shared: Rc<Shared>,
+    // only kept so it lives as long as the scheduler does
+    _frame: Closure<dyn FnMut(f64)>,
+}
+
+impl Scheduler {
+    // stopped, until `start`
+    pub fn new(callback: impl FnMut() + 'static) -> Scheduler {
+        let shared = Rc::new(Shared {
+            callback: RefCell::new(Box::new(callback)),
+            between: RefCell::new(None),
+            interval_ms: Cell::new(None),
+            paused: Cell::new(false),
+            accumulator: RefCell::new(Accumulator::default()),
+            handle: Cell::new(None),
+            request: RefCell::new(None),
+        });
+
+        let frame = Closure::wrap(Box::new({
+            let shared = Rc::downgrade(&shared);
+            move |_: f64| on_frame(&shared)
+        }) as Box<dyn FnMut(f64)>);
+        *shared.request.borrow_mut() =
+            Some(frame.as_ref().unchecked_ref::<js_sys::Function>().clone());
+
+        Scheduler {
+            shared,
+            _frame: frame,
+        }
+    }
+
+    // Also calls `between` on every frame while it's running that doesn't get a call of its own,
+    // paused or not, for anything that draws in between.
+    pub fn between_ticks(self, between: impl FnMut() + 'static) -> Scheduler {
+        *self.shared.between.borrow_mut() = Some(Box::new(between));
+        self
+    }
+
+    // started and not stopped since, paused or not
+    pub fn is_running(&self) -> bool {
+        self.shared.interval_ms.get().is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.get()
+    }
+
+    // How far along it is towards the next call, from 0 to 1, or 0 while stopped.
+    pub fn fraction(&self) -> f64 {
+        match self.shared.interval_ms.get() {
+            Some(interval_ms) => self
+                .shared
+                .accumulator
+                .borrow()
+                .fraction(interval_ms as f64),
+            None => 0.0,
+        }
+    }
+
+    // Runs the callback every `interval_ms` from now on, starting the wait over if it was already
+    // going. While paused, that's from when it's resumed.
+    pub fn start(&self, interval_ms: i32) {
+        self.shared.interval_ms.set(Some(interval_ms));
+        *self.shared.accumulator.borrow_mut() = Accumulator::default();
+        self.shared.sync();
+    }
+
+    pub fn stop(&self) {
+        self.shared.interval_ms.set(None);
+        self.shared.sync();
+    }
+
+    // Pausing keeps the timing for `resume`, and doesn't stop it being started or stopped in the
+    // meantime. Anything else running off the same clock can check `is_paused` to hold off too.
+    pub fn pause(&self) {
+        self.shared.paused.set(true);
+        self.shared.sync();
+    }
+
+    // the time spent paused doesn't count towards the next call
+    pub fn resume(&self) {
+        self.shared.paused.set(false);
+        self.shared.accumulator.borrow_mut().last_ms = None;
+        self.shared.sync();
+    }
+
+    // one call right away, whether it's running or not, for going a step at a time while paused
+    pub fn step(&self) {
+        (self.shared.callback.borrow_mut())();
+    }
+}
+
+impl Shared {
+    // whether it needs frames at all. paused, only for whatever's drawn in between
+    fn wants_frames(&self) -> bool {
+        self.interval_ms.get().is_some() && (!self.paused.get() || self.between.borrow().is_some())
+    }
+
+    // asks for a frame if it needs one and hasn't already, or takes back the one it asked for
+    fn sync(&self) {
+        let Some(window) = window() else {
+            return;
+        };
+
+        match (self.wants_frames(), self.handle.get()) {
+            (true, None) => {
+                let request = self.request.borrow();
+                let handle = request
+                    .as_ref()
+                    .and_then(|request| window.request_animation_frame(request).ok());
+                self.handle.set(handle);
+            }
+            (false, Some(handle)) => {
+                let _ = window.cancel_animation_frame(handle);
+                self.handle.set(None);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn on_frame(shared: &Weak<Shared>) {
+    let Some(shared) = shared.upgrade() else {
+        return;
+    };
+    shared.handle.set(None);
+
+    let now_ms = window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now());
+    let calls = match (shared.interval_ms.get(), shared.paused.get()) {
+        (Some(interval_ms), false) => shared
+            .accumulator
+            .borrow_mut()
+            .frame(now_ms, interval_ms as f64),
+        _ => 0,
+    };
+
+    for _ in 0..calls {
+        // whatever it runs can stop it partway
+        if shared.interval_ms.get().is_none() || shared.paused.get() {
+            break;
+        }
+        (shared.callback.borrow_mut())();
+    }
+    if calls == 0 && shared.interval_ms.get().is_some() {
+        if let Some(between) = shared.between.borrow_mut().as_mut() {
+            between();
+        }
+    }
+
+    shared.sync();
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shared.interval_ms.set(None);
+        self.shared.sync();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulator() {
+        let mut accumulator = Accumulator::default();
+        assert_eq!(accumulator.frame(1000.0, 100.0), 0);
+
+        // frames that don't line up with the interval still add up to the right number of calls
+        let calls = (1..=60)
+            .map(|frame| accumulator.frame(1000.0 + frame as f64 * 16.7, 100.0))
+            .sum::<u32>();
+        assert_eq!(calls, 10);
+        assert!((accumulator.fraction(100.0) - 0.02).abs() < 1e-6);
+
+        // a frame that comes late makes up for it
+        assert_eq!(accumulator.frame(2302.0, 100.0), 3);
+        // but not for too long a wait, which only keeps the part of an interval
+        assert_eq!(accumulator.frame(9352.0, 100.0), MAX_CATCH_UP);
+        assert!((accumulator.fraction(100.0) - 0.52).abs() < 1e-6);
+
+        // going backwards doesn't take any back
+        assert_eq!(accumulator.frame(9000.0, 100.0), 0);
+        assert_eq!(accumulator.frame(9048.0, 100.0), 1);
+    }
+}
@@ -0,0 +1,776 @@
+use crate::enemy;
+use crate::food;
+use crate::food::FoodCount;
+use crate::food::Growth;
+use crate::geometry::Grid;
+use crate::geometry::Topology;
+use crate::input;
+use crate::input::InputSource;
+use crate::keys::Action;
+use crate::keys::KeyBindings;
+use crate::mode::GameMode;
+use crate::rules::RuleSet;
+use crate::snake;
+use crate::theme::Theme;
+
+pub const MIN_WIDTH: isize = snake::MIN_WIDTH;
+pub const MAX_WIDTH: isize = snake::MAX_WIDTH;
+pub const MIN_HEIGHT: isize = snake::MIN_HEIGHT;
+pub const MAX_HEIGHT: isize = snake::MAX_HEIGHT;
+pub const MIN_TICK_MS: i32 = 30;
+pub const MAX_TICK_MS: i32 = 1000;
+pub const MAX_REMINDER_MINUTES: u32 = 240;
+pub const MAX_PLAYER_NAME_LENGTH: usize = 24;
+pub const MAX_IDLE_PAUSE_TICKS: usize = 10000;
+pub const MAX_GRACE_TICKS: usize = 50;
+pub const MAX_LATE_INPUT_MS: u32 = 100;
+// see `SnakeGame::starve_ticks`
+pub const STARVE_TICKS: usize = 40;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BoardSize {
+    Small,
+    Medium,
+    Large,
+    // uses the width and height from the settings
+    Custom,
+}
+
+impl BoardSize {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoardSize::Small => "small",
+            BoardSize::Medium => "medium",
+            BoardSize::Large => "large",
+            BoardSize::Custom => "custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BoardSize> {
+        match name {
+            "small" => Some(BoardSize::Small),
+            "medium" => Some(BoardSize::Medium),
+            "large" => Some(BoardSize::Large),
+            "custom" => Some(BoardSize::Custom),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> BoardSize {
+        match self {
+            BoardSize::Small => BoardSize::Medium,
+            BoardSize::Medium => BoardSize::Large,
+            BoardSize::Large => BoardSize::Custom,
+            BoardSize::Custom => BoardSize::Small,
+        }
+    }
+
+    pub fn dimensions(&self) -> Option<(isize, isize)> {
+        match self {
+            BoardSize::Small => Some((15, 11)),
+            BoardSize::Medium => Some((21, 15)),
+            BoardSize::Large => Some((31, 21)),
+            BoardSize::Custom => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RenderStyle {
+    Emoji,
+    Squares,
+    // drawn on a canvas, with the snake sliding between tiles
+    Smooth,
+}
+
+impl RenderStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderStyle::Emoji => "emoji",
+            RenderStyle::Squares => "squares",
+            RenderStyle::Smooth => "smooth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RenderStyle> {
+        match name {
+            "emoji" => Some(RenderStyle::Emoji),
+            "squares" => Some(RenderStyle::Squares),
+            "smooth" => Some(RenderStyle::Smooth),
+            _ => None,
+        }
+    }
+
+    // The biggest board each style still draws quickly enough to play on. The grid styles have a
+    // div for every tile, and the page slows to a crawl laying out thousands of them, while the
+    // canvas doesn't mind.
+    pub fn max_board(&self) -> (isize, isize) {
+        match self {
+            RenderStyle::Emoji => (40, 30),
+            RenderStyle::Squares => (50, 35),
+            RenderStyle::Smooth => (MAX_WIDTH, MAX_HEIGHT),
+        }
+    }
+
+    // like `snake::check_board`, with the style's own limit on top
+    pub fn check_board(&self, width: isize, height: isize) -> Result<(), String> {
+        snake::check_board(width, height)?;
+
+        let (max_width, max_height) = self.max_board();
+        if width > max_width || height > max_height {
+            return Err(format!(
+                "a {width}x{height} board is too big for the {} style, it can be at most \
+                 {max_width}x{max_height}. The smooth style can draw bigger ones",
+                self.name()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn harder(&self) -> Option<Difficulty> {
+        match self {
+            Difficulty::Easy => Some(Difficulty::Normal),
+            Difficulty::Normal => Some(Difficulty::Hard),
+            Difficulty::Hard => None,
+        }
+    }
+
+    pub fn easier(&self) -> Option<Difficulty> {
+        match self {
+            Difficulty::Easy => None,
+            Difficulty::Normal => Some(Difficulty::Easy),
+            Difficulty::Hard => Some(Difficulty::Normal),
+        }
+    }
+
+    // in ticks, see `SnakeGame::hazard_lifetime`
+    pub fn hazard_lifetime(&self) -> Option<usize> {
+        match self {
+            Difficulty::Easy => Some(100),
+            Difficulty::Normal => Some(250),
+            Difficulty::Hard => None,
+        }
+    }
+
+    // out of `snake::MAX_ENERGY` a tick, see `SnakeGame::energy_drain`. that's 200, 125, and
+    // 80 ticks from full to empty
+    pub fn energy_drain(&self) -> usize {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 8,
+            Difficulty::Hard => 12,
+        }
+    }
+}
+
+// How the last run that was played to the end was set up, for starting another just like it
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct LastPlayed {
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    // a custom board goes by whatever width and height are set by then
+    pub board_size: BoardSize,
+    pub theme: Theme,
+}
+
+impl LastPlayed {
+    pub fn of(settings: &Settings) -> LastPlayed {
+        LastPlayed {
+            mode: settings.mode,
+            difficulty: settings.difficulty,
+            board_size: settings.board_size,
+            theme: settings.theme,
+        }
+    }
+
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.mode = self.mode;
+        settings.difficulty = self.difficulty;
+        settings.board_size = self.board_size;
+        settings.theme = self.theme;
+    }
+
+    // like `classic normal medium retro`
+    fn serialize(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.mode.name(),
+            self.difficulty.name(),
+            self.board_size.name(),
+            self.theme.name()
+        )
+    }
+
+    fn parse(source: &str) -> Option<LastPlayed> {
+        let [mode, difficulty, board_size, theme] = source
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .try_into()
+            .ok()?;
+
+        Some(LastPlayed {
+            mode: GameMode::from_name(mode)?,
+            difficulty: Difficulty::from_name(difficulty)?,
+            board_size: BoardSize::from_name(board_size)?,
+            theme: Theme::from_name(theme)?,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Settings {
+    pub board_size: BoardSize,
+    // only used for custom boards, see `board_dimensions`
+    pub width: isize,
+    pub height: isize,
+    // milliseconds between ticks, so smaller is faster
+    pub tick_ms: i32,
+    pub topology: Topology,
+    pub grid: Grid,
+    pub difficulty: Difficulty,
+    // speed and puddles change to fit how the last few games went, see `adaptive`
+    pub adaptive: bool,
+    pub mode: GameMode,
+    pub render_style: RenderStyle,
+    pub theme: Theme,
+    // bonus and rotten food, see `SnakeGame::special_food`
+    pub special_food: bool,
+    // see `SnakeGame::food_away_from_heads`
+    pub food_away_from_heads: bool,
+    // how much normal food is out at once, or at most with `food_scales`. see `food_count`
+    pub food_count: usize,
+    // start with one and work up to `food_count` as the score goes up
+    pub food_scales: bool,
+    // see `SnakeGame::growth`
+    pub growth: Growth,
+    // see `RuleSet`, like `RuleSet::CLASSIC` for how most other snake games play
+    pub rules: RuleSet,
+    // beginner hints, see `coach`
+    pub coaching: bool,
+    // the snake shrinks when it goes too long without eating
+    pub starving: bool,
+    // an energy meter that eating fills up, see `SnakeGame::energy_drain`
+    pub hunger: bool,
+    // see `SnakeGame::hazard_stun`
+    pub hazard_stun: bool,
+    // see `SnakeGame::combos`
+    pub combos: bool,
+    // see `SnakeGame::fresh_food`
+    pub fresh_food: bool,
+    // see `SnakeGame::grace_ticks`
+    pub grace_ticks: usize,
+    // see `SnakeGame::win_percent`, 0 to play until the board's full
+    pub win_percent: usize,
+    // how many enemies each run starts with, see `enemy`
+    pub enemies: usize,
+    // lets the rewind key go back a little way into the run, see `rewind`
+    pub practice: bool,
+    // versus with both players on the same side, see `SnakeGame::coop`
+    pub coop: bool,
+    // plays this week's mutator on top of everything else, see `mutator`
+    pub mutator: bool,
+    pub sound: bool,
+    pub show_border: bool,
+    pub show_dpad: bool,
+    // steering towards the pointer while it's over the board, see `mouse`
+    pub mouse_steering: bool,
+    // WASD and IJKL steering along with the arrows outside of versus, instead of the other jobs
+    // their keys have, see `keys`
+    pub all_layouts: bool,
+    // a big score and little else, for streaming or putting up on a projector
+    pub presentation: bool,
+    // a clock to a hundredth and splits under the board, see `splits`
+    pub speedrun: bool,
+    // the board slowly leaning about and mirroring itself while the game goes on the same as
+    // ever, in the smooth style. see `animation::sway`
+    pub drunk_board: bool,
+    // the board shakes when the snake crashes, or only just turns away from a hazard
+    pub screen_shake: bool,
+    // no in-between frames for the smooth style. starts out as whatever the browser prefers
+    pub reduced_motion: bool,
+    // shown over the score in presentation mode, nothing if empty
+    pub player_name: String,
+    // minutes of play between break reminders, 0 for none
+    pub reminder_minutes: u32,
+    // ticks without any input before the game pauses itself, 0 for never
+    pub idle_pause_ticks: usize,
+    // how long after a tick a turn still counts for it, 0 for not at all. see `late_input`
+    pub late_input_ms: u32,
+    // what each player steers with in versus, see `input`
+    pub player_inputs: [InputSource; input::MAX_PLAYERS],
+    pub key_bindings: KeyBindings,
+    // `None` until a run's been played to the end
+    pub last_played: Option<LastPlayed>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            board_size: BoardSize::Medium,
+            width: 21,
+            height: 15,
+            tick_ms: 100,
+            topology: Topology::Walls,
+            grid: Grid::Square,
+            difficulty: Difficulty::Normal,
+            adaptive: false,
+            mode: GameMode::Classic,
+            render_style: RenderStyle::Emoji,
+            theme: Theme::Classic,
+            special_food: true,
+            food_away_from_heads: false,
+            food_count: 1,
+            food_scales: false,
+            growth: Growth::default(),
+            rules: RuleSet::SLAKE,
+            coaching: false,
+            starving: false,
+            hunger: false,
+            hazard_stun: false,
+            combos: false,
+            fresh_food: false,
+            grace_ticks: 0,
+            win_percent: 0,
+            enemies: 0,
+            practice: false,
+            coop: false,
+            mutator: false,
+            sound: true,
+            show_border: false,
+            show_dpad: false,
+            mouse_steering: false,
+            all_layouts: false,
+            presentation: false,
+            speedrun: false,
+            drunk_board: false,
+            screen_shake: false,
+            reduced_motion: false,
+            player_name: String::new(),
+            reminder_minutes: 0,
+            idle_pause_ticks: 300,
+            late_input_ms: 30,
+            player_inputs: [InputSource::Arrows, InputSource::Wasd],
+            key_bindings: KeyBindings::default(),
+            last_played: None,
+        }
+    }
+}
+
+impl Settings {
+    // Reads settings saved by `serialize`, one `key=value` per line. This is meant for reading
+    // back our own saves, so anything missing or broken just quietly falls back to the default.
+    pub fn parse(source: &str) -> Settings {
+        let mut settings = Settings::default();
+
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "board_size" => {
+                    settings.board_size = BoardSize::from_name(value).unwrap_or(settings.board_size)
+                }
+                "width" => settings.width = value.parse().unwrap_or(settings.width),
+                "height" => settings.height = value.parse().unwrap_or(settings.height),
+                "tick_ms" => settings.tick_ms = value.parse().unwrap_or(settings.tick_ms),
+                "topology" => {
+                    settings.topology = Topology::from_name(value).unwrap_or(settings.topology)
+                }
+                "grid" => settings.grid = Grid::from_name(value).unwrap_or(settings.grid),
+                "difficulty" => {
+                    settings.difficulty =
+                        Difficulty::from_name(value).unwrap_or(settings.difficulty)
+                }
+                "adaptive" => settings.adaptive = value.parse().unwrap_or(settings.adaptive),
+                "mode" => settings.mode = GameMode::from_name(value).unwrap_or(settings.mode),
+                "render_style" => {
+                    settings.render_style =
+                        RenderStyle::from_name(value).unwrap_or(settings.render_style)
+                }
+                "theme" => settings.theme = Theme::from_name(value).unwrap_or(settings.theme),
+                "special_food" => {
+                    settings.special_food = value.parse().unwrap_or(settings.special_food)
+                }
+                "food_away_from_heads" => {
+                    settings.food_away_from_heads =
+                        value.parse().unwrap_or(settings.food_away_from_heads)
+                }
+                "food_count" => settings.food_count = value.parse().unwrap_or(settings.food_count),
+                "food_scales" => {
+                    settings.food_scales = value.parse().unwrap_or(settings.food_scales)
+                }
+                "growth" => settings.growth = Growth::from_name(value).unwrap_or(settings.growth),
+                "rules" => settings.rules = RuleSet::from_name(value).unwrap_or(settings.rules),
+                "coaching" => settings.coaching = value.parse().unwrap_or(settings.coaching),
+                "starving" => settings.starving = value.parse().unwrap_or(settings.starving),
+                "hunger" => settings.hunger = value.parse().unwrap_or(settings.hunger),
+                "hazard_stun" => {
+                    settings.hazard_stun = value.parse().unwrap_or(settings.hazard_stun)
+                }
+                "combos" => settings.combos = value.parse().unwrap_or(settings.combos),
+                "fresh_food" => settings.fresh_food = value.parse().unwrap_or(settings.fresh_food),
+                "grace_ticks" => {
+                    settings.grace_ticks = value.parse().unwrap_or(settings.grace_ticks)
+                }
+                "win_percent" => {
+                    settings.win_percent = value.parse().unwrap_or(settings.win_percent)
+                }
+                "enemies" => settings.enemies = value.parse().unwrap_or(settings.enemies),
+                "practice" => settings.practice = value.parse().unwrap_or(settings.practice),
+                "coop" => settings.coop = value.parse().unwrap_or(settings.coop),
+                "mutator" => settings.mutator = value.parse().unwrap_or(settings.mutator),
+                "sound" => settings.sound = value.parse().unwrap_or(settings.sound),
+                "show_border" => {
+                    settings.show_border = value.parse().unwrap_or(settings.show_border)
+                }
+                "show_dpad" => settings.show_dpad = value.parse().unwrap_or(settings.show_dpad),
+                "mouse_steering" => {
+                    settings.mouse_steering = value.parse().unwrap_or(settings.mouse_steering)
+                }
+                "presentation" => {
+                    settings.presentation = value.parse().unwrap_or(settings.presentation)
+                }
+                "all_layouts" => {
+                    settings.all_layouts = value.parse().unwrap_or(settings.all_layouts)
+                }
+                "speedrun" => settings.speedrun = value.parse().unwrap_or(settings.speedrun),
+                "drunk_board" => {
+                    settings.drunk_board = value.parse().unwrap_or(settings.drunk_board)
+                }
+                "screen_shake" => {
+                    settings.screen_shake = value.parse().unwrap_or(settings.screen_shake)
+                }
+                "reduced_motion" => {
+                    settings.reduced_motion = value.parse().unwrap_or(settings.reduced_motion)
+                }
+                "player_name" => settings.player_name = value.to_string(),
+                "reminder_minutes" => {
+                    settings.reminder_minutes = value.parse().unwrap_or(settings.reminder_minutes)
+                }
+                "idle_pause_ticks" => {
+                    settings.idle_pause_ticks = value.parse().unwrap_or(settings.idle_pause_ticks)
+                }
+                "late_input_ms" => {
+                    settings.late_input_ms = value.parse().unwrap_or(settings.late_input_ms)
+                }
+                "last_played" => settings.last_played = LastPlayed::parse(value),
+                "player_inputs" => {
+                    for (input, name) in settings.player_inputs.iter_mut().zip(value.split(',')) {
+                        *input = InputSource::from_name(name.trim()).unwrap_or(*input);
+                    }
+                }
+                other => {
+                    if let Some(action) = other.strip_prefix("keys_").and_then(Action::from_name) {
+                        settings.key_bindings.set_keys(action, value);
+                    }
+                }
+            }
+        }
+
+        settings.clamped()
+    }
+
+    // each action's keys go on a line of their own after the rest, like `keys_up=ArrowUp k`
+    pub fn serialize(&self) -> String {
+        let mut source = format!(
+            "board_size={}\nwidth={}\nheight={}\ntick_ms={}\ntopology={}\ngrid={}\ndifficulty={}\nadaptive={}\nmode={}\nrender_style={}\ntheme={}\nspecial_food={}\nfood_away_from_heads={}\nfood_count={}\nfood_scales={}\ngrowth={}\nrules={}\ncoaching={}\nstarving={}\nhunger={}\nhazard_stun={}\ncombos={}\nfresh_food={}\ngrace_ticks={}\nwin_percent={}\nenemies={}\npractice={}\ncoop={}\nmutator={}\nsound={}\nshow_border={}\nshow_dpad={}\nmouse_steering={}\nall_layouts={}\npresentation={}\nspeedrun={}\ndrunk_board={}\nscreen_shake={}\nreduced_motion={}\nplayer_name={}\nreminder_minutes={}\nidle_pause_ticks={}\nlate_input_ms={}\nplayer_inputs={}\n",
+            self.board_size.name(),
+            self.width,
+            self.height,
+            self.tick_ms,
+            self.topology.name(),
+            self.grid.name(),
+            self.difficulty.name(),
+            self.adaptive,
+            self.mode.name(),
+            self.render_style.name(),
+            self.theme.name(),
+            self.special_food,
+            self.food_away_from_heads,
+            self.food_count,
+            self.food_scales,
+            self.growth.name(),
+            self.rules.name(),
+            self.coaching,
+            self.starving,
+            self.hunger,
+            self.hazard_stun,
+            self.combos,
+            self.fresh_food,
+            self.grace_ticks,
+            self.win_percent,
+            self.enemies,
+            self.practice,
+            self.coop,
+            self.mutator,
+            self.sound,
+            self.show_border,
+            self.show_dpad,
+            self.mouse_steering,
+            self.all_layouts,
+            self.presentation,
+            self.speedrun,
+            self.drunk_board,
+            self.screen_shake,
+            self.reduced_motion,
+            self.player_name,
+            self.reminder_minutes,
+            self.idle_pause_ticks,
+            self.late_input_ms,
+            self.player_inputs
+                .iter()
+                .map(|input| input.name())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+
+        if let Some(last_played) = self.last_played {
+            source += &format!("last_played={}\n", last_played.serialize());
+        }
+
+        for action in Action::ALL {
+            source += &format!(
+                "keys_{}={}\n",
+                action.name(),
+                self.key_bindings.keys_string(action)
+            );
+        }
+
+        source
+    }
+
+    pub fn starve_ticks(&self) -> Option<usize> {
+        self.starving.then_some(STARVE_TICKS)
+    }
+
+    pub fn energy_drain(&self) -> Option<usize> {
+        self.hunger.then(|| self.difficulty.energy_drain())
+    }
+
+    // see `SnakeGame::food_count`
+    pub fn food_count(&self) -> FoodCount {
+        match self.food_scales {
+            true => FoodCount::UpTo(self.food_count),
+            false => FoodCount::Fixed(self.food_count),
+        }
+    }
+
+    pub fn board_dimensions(&self) -> (isize, isize) {
+        self.board_size
+            .dimensions()
+            .unwrap_or((self.width, self.height))
+    }
+
+    // keeps numbers the user typed in within what the game can actually handle, and the board
+    // within what the render style can draw
+    pub fn clamped(mut self) -> Settings {
+        let (max_width, max_height) = self.render_style.max_board();
+        self.width = self.width.clamp(MIN_WIDTH, max_width);
+        self.height = self.height.clamp(MIN_HEIGHT, max_height);
+        self.tick_ms = self.tick_ms.clamp(MIN_TICK_MS, MAX_TICK_MS);
+        self.reminder_minutes = self.reminder_minutes.min(MAX_REMINDER_MINUTES);
+        self.idle_pause_ticks = self.idle_pause_ticks.min(MAX_IDLE_PAUSE_TICKS);
+        self.late_input_ms = self.late_input_ms.min(MAX_LATE_INPUT_MS);
+        self.grace_ticks = self.grace_ticks.min(MAX_GRACE_TICKS);
+        self.win_percent = self.win_percent.min(100);
+        self.enemies = self.enemies.min(enemy::MAX_ENEMIES);
+        self.food_count = self.food_count.clamp(1, food::MAX_FOOD_COUNT);
+
+        // names are saved on a line of their own
+        let name = self
+            .player_name
+            .replace(|character: char| character.is_control(), "");
+        self.player_name = name.trim().chars().take(MAX_PLAYER_NAME_LENGTH).collect();
+
+        // two players can't share an input, so later players get bumped to a keyboard layout
+        // nobody's using
+        for player in 1..self.player_inputs.len() {
+            if self.player_inputs[..player].contains(&self.player_inputs[player]) {
+                let taken = self.player_inputs;
+                if let Some(free) = [InputSource::Arrows, InputSource::Wasd, InputSource::Ijkl]
+                    .into_iter()
+                    .find(|layout| !taken.contains(layout))
+                {
+                    self.player_inputs[player] = free;
+                }
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodKind;
+
+    #[test]
+    fn round_trip() {
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.bind(Action::Left, "h");
+        key_bindings.bind(Action::Restart, " ");
+        key_bindings.bind(Action::Stats, "=");
+        key_bindings.clear(Action::Online);
+
+        let settings = Settings {
+            board_size: BoardSize::Custom,
+            width: 30,
+            height: 20,
+            tick_ms: 60,
+            topology: Topology::Wrap,
+            grid: Grid::Hex,
+            difficulty: Difficulty::Hard,
+            adaptive: true,
+            mode: GameMode::Zen,
+            render_style: RenderStyle::Squares,
+            theme: Theme::Retro,
+            special_food: false,
+            food_away_from_heads: true,
+            food_count: 3,
+            food_scales: true,
+            growth: Growth::default().with(FoodKind::Bonus, 3),
+            rules: RuleSet::CLASSIC,
+            coaching: true,
+            starving: true,
+            hunger: true,
+            hazard_stun: true,
+            combos: true,
+            fresh_food: true,
+            grace_ticks: 5,
+            win_percent: 75,
+            enemies: 2,
+            practice: true,
+            coop: true,
+            mutator: true,
+            sound: false,
+            show_border: true,
+            show_dpad: true,
+            mouse_steering: true,
+            all_layouts: true,
+            presentation: true,
+            speedrun: true,
+            drunk_board: true,
+            screen_shake: true,
+            reduced_motion: true,
+            player_name: "Slinky Jo".to_string(),
+            reminder_minutes: 45,
+            idle_pause_ticks: 0,
+            late_input_ms: 80,
+            player_inputs: [InputSource::Gamepad(2), InputSource::Touch],
+            key_bindings,
+            last_played: Some(LastPlayed {
+                mode: GameMode::TimeAttack { seconds: 120 },
+                difficulty: Difficulty::Easy,
+                board_size: BoardSize::Large,
+                theme: Theme::Classic,
+            }),
+        };
+
+        assert_eq!(Settings::parse(&settings.serialize()), settings);
+    }
+
+    #[test]
+    fn falls_back_to_defaults() {
+        assert_eq!(Settings::parse(""), Settings::default());
+        assert_eq!(
+            Settings::parse("width=banana\nnonsense\ntopology=moebius"),
+            Settings::default()
+        );
+
+        let settings = Settings::parse(
+            "width=1000\nheight=-4\ntick_ms=1\nrender_style=smooth\nfood_count=0\nfood_scales=true",
+        );
+        assert_eq!(settings.width, MAX_WIDTH);
+        assert_eq!(settings.height, MIN_HEIGHT);
+        assert_eq!(settings.tick_ms, MIN_TICK_MS);
+        assert_eq!(settings.food_count(), FoodCount::UpTo(1));
+
+        assert_eq!(Settings::parse("last_played=zen hard").last_played, None);
+
+        let settings = Settings::parse("player_inputs=gamepad0,gamepad0");
+        assert_eq!(
+            settings.player_inputs,
+            [InputSource::Gamepad(0), InputSource::Arrows]
+        );
+
+        let settings = Settings {
+            player_name: format!("  tab\there{}", "!".repeat(40)),
+            ..Settings::default()
+        }
+        .clamped();
+        assert!(settings.player_name.starts_with("tabhere!"));
+        assert_eq!(settings.player_name.len(), MAX_PLAYER_NAME_LENGTH);
+    }
+
+    #[test]
+    fn board_sizes() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.board_dimensions(), (21, 15));
+
+        settings.width = 8;
+        settings.height = 6;
+
+        let mut sizes = vec![];
+        for _i in 0..4 {
+            settings.board_size = settings.board_size.next();
+            sizes.push(settings.board_dimensions());
+        }
+
+        assert_eq!(sizes, vec![(31, 21), (8, 6), (15, 11), (21, 15)]);
+
+        // the grid styles can't keep up with the biggest boards, so those get shrunk to fit
+        assert!(RenderStyle::Smooth.check_board(60, 40).is_ok());
+        assert!(RenderStyle::Emoji.check_board(60, 40).is_err());
+        assert!(RenderStyle::Squares.check_board(4, 3).is_err());
+        settings.width = 60;
+        settings.height = 40;
+        for style in [
+            RenderStyle::Emoji,
+            RenderStyle::Squares,
+            RenderStyle::Smooth,
+        ] {
+            settings.render_style = style;
+            let clamped = settings.clone().clamped();
+            assert_eq!((clamped.width, clamped.height), style.max_board());
+            assert!(style.check_board(clamped.width, clamped.height).is_ok());
+        }
+    }
+}
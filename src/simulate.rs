@@ -0,0 +1,119 @@
+use crate::geometry;
+use crate::geometry::Direction;
+use crate::random;
+use crate::snake::SnakeGame;
+
+// Plays whole games without a browser, for bots, benchmarks, and poking at the rules. A policy
+// looks at the game before every tick and picks where to steer, or `None` to keep going.
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SimResult {
+    pub score: usize,
+    pub ticks: usize,
+    // `None` if it ran out of ticks before the game ended
+    pub game_over_reason: Option<&'static str>,
+    // of the longest snake, in hydra mode
+    pub length: usize,
+}
+
+// seeds the random numbers and plays a fresh game on the default board
+pub fn simulate(
+    seed: [u16; 2],
+    policy: impl FnMut(&SnakeGame) -> Option<Direction>,
+    max_ticks: usize,
+) -> SimResult {
+    random::seed(seed);
+    let mut game = SnakeGame::new(21, 15);
+    simulate_game(&mut game, policy, max_ticks)
+}
+
+// Like `simulate`, for a game that's already set up with its own size, rules, or level. Seed the
+// random numbers first for the same game every time.
+pub fn simulate_game(
+    game: &mut SnakeGame,
+    mut policy: impl FnMut(&SnakeGame) -> Option<Direction>,
+    max_ticks: usize,
+) -> SimResult {
+    while !game.is_game_over() && game.ticks < max_ticks {
+        if let Some(direction) = policy(game) {
+            game.change_direction(direction);
+        }
+
+        game.tick();
+        // nobody's listening, so don't let them pile up
+        game.take_events();
+    }
+
+    SimResult {
+        score: game.score,
+        ticks: game.ticks,
+        game_over_reason: game.game_over_reason,
+        length: game
+            .snakes()
+            .iter()
+            .map(|snake| snake.body.len())
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+// A simple bot that heads for the closest food, as long as the next tile is safe. It doesn't
+// look any further ahead than that, so it does trap itself eventually.
+pub fn greedy(game: &SnakeGame) -> Option<Direction> {
+    let snake = &game.snakes()[0];
+    let head = snake.head();
+
+    let is_safe = |pos: &geometry::Vector| {
+        geometry::is_within_board(game.width, game.height, pos)
+            && !game.is_wall_at(pos)
+            && !game.is_portal_at(pos)
+            && !game.is_hazard_at(pos)
+            && !game.is_snake_at(pos)
+    };
+
+    let distance_to_food = |pos: &geometry::Vector| {
+        game.food()
+            .iter()
+            .map(|food| game.geometry().distance(pos, &food.position))
+            .min()
+            .unwrap_or(0)
+    };
+
+    game.geometry()
+        .directions()
+        .iter()
+        .filter(|direction| **direction != snake.direction().opposite())
+        .map(|direction| {
+            let next = game.next_tile(head, direction);
+            (direction, next)
+        })
+        .filter(|(_, next)| is_safe(next))
+        .min_by_key(|(_, next)| distance_to_food(next))
+        .map(|(direction, _)| direction.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_game() {
+        let first = simulate([1, 2], greedy, 500);
+        let second = simulate([1, 2], greedy, 500);
+
+        assert_eq!(first, second);
+        assert!(first.score > 0);
+    }
+
+    #[test]
+    fn stops_at_max_ticks() {
+        let result = simulate([3, 4], greedy, 5);
+        assert_eq!(result.ticks, 5);
+        assert_eq!(result.game_over_reason, None);
+
+        // and a snake that never steers runs into the wall
+        let result = simulate([3, 4], |_| None, 1000);
+        assert!(result.ticks < 1000);
+        assert_eq!(result.game_over_reason, Some("avoid walls"));
+    }
+}
@@ -1,309 +1,4666 @@
+use crate::board::Board;
+use crate::board::FreeSlots;
+use crate::effects;
+use crate::enemy;
+use crate::enemy::Behavior;
+use crate::enemy::Enemy;
+use crate::event::GameEvent;
+use crate::event::TickOutcome;
+use crate::food::Food;
+use crate::food::FoodCount;
+use crate::food::FoodKind;
+use crate::food::Growth;
+use crate::food::MAX_FOOD_COUNT;
+use crate::food::MAX_FRESH_BONUS;
+use crate::food::SHRINK_TILES;
+use crate::generate;
+use crate::geometry;
+use crate::geometry::Direction;
+use crate::geometry::Geometry;
+use crate::geometry::Grid;
+use crate::geometry::Topology;
+use crate::geometry::Vector;
+use crate::level;
+use crate::level::Level;
+use crate::mode::GameMode;
 use crate::random;
+use crate::rules::RuleSet;
+use crate::snapshot::SnakeSnapshot;
+use crate::snapshot::Snapshot;
+use crate::spawner;
+use crate::spawner::SpawnRules;
+use crate::spawner::Spawner;
+use crate::spawner::Surroundings;
+use crate::version::Compatibility;
+use crate::version::Fingerprint;
+use crate::view::BoardView;
+use crate::waves;
+use crate::waves::Wave;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::ops::Range;
 
-#[derive(PartialEq, Eq, Clone, Default)]
-pub struct Vector(pub isize, pub isize);
+fn remove_from_vec<T: std::cmp::PartialEq>(vec: &mut Vec<T>, search_element: &T) {
+    if let Some(index) = vec.iter().position(|value| *value == *search_element) {
+        vec.swap_remove(index);
+    }
+}
+
+// the countdown before each run goes 3, 2, 1
+pub const COUNTDOWN_FROM: usize = 3;
 
-impl std::ops::Add<&Vector> for &Vector {
-    type Output = Vector;
+// The smallest board has room for the starting snake to turn around, and the biggest is as big
+// as anything that draws the board has been made for.
+pub const MIN_WIDTH: isize = 5;
+pub const MIN_HEIGHT: isize = 3;
+pub const MAX_WIDTH: isize = 60;
+pub const MAX_HEIGHT: isize = 40;
 
-    fn add(self, other: &Vector) -> Vector {
-        Vector(self.0 + other.0, self.1 + other.1)
+// Whether there can be a game on a board this size, and if not, why not, in words fit to show
+// the player.
+pub fn check_board(width: isize, height: isize) -> Result<(), String> {
+    if width < MIN_WIDTH || height < MIN_HEIGHT {
+        Err(format!(
+            "a {width}x{height} board is too small, it has to be at least {MIN_WIDTH}x{MIN_HEIGHT}"
+        ))
+    } else if width > MAX_WIDTH || height > MAX_HEIGHT {
+        Err(format!(
+            "a {width}x{height} board is too big, it can be at most {MAX_WIDTH}x{MAX_HEIGHT}"
+        ))
+    } else {
+        Ok(())
     }
 }
 
-impl std::ops::AddAssign<&Vector> for Vector {
-    fn add_assign(&mut self, other: &Vector) {
-        self.0 = self.0 + other.0;
-        self.1 = self.1 + other.1;
-    }
+// what `SnakeGame::game_over_reason` says when a run ends in a win, see `SnakeGame::win_percent`
+pub const WON_REASON: &str = "you won";
+// and when a snake's run out of energy, see `SnakeGame::energy_drain`
+pub const STARVED_REASON: &str = "don't run out of energy";
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
 }
 
-fn remove_from_vec<T: std::cmp::PartialEq>(vec: &mut Vec<T>, search_element: &T) {
-    if let Some(index) = vec.iter().position(|value| *value == *search_element) {
-        vec.swap_remove(index);
+// The best score a perfect run could reach. Every fruit grows the snake by one tile, and with
+// hazards on, also leaves a hazard behind once the tail moves on, so each fruit costs two tiles.
+pub fn max_score(open_tiles: usize, initial_length: usize, hazards_on_eat: bool) -> usize {
+    max_score_with(open_tiles, initial_length, 1, hazards_on_eat as usize)
+}
+
+// Same as `max_score`, for when each fruit grows the snake by `growth` tiles, or leaves more
+// than one hazard behind. Fruit that costs no room at all could go on forever, so that's
+// counted as a tile each.
+pub fn max_score_with(
+    open_tiles: usize,
+    initial_length: usize,
+    growth: usize,
+    hazards_per_fruit: usize,
+) -> usize {
+    let room = open_tiles.saturating_sub(initial_length);
+    room / (growth + hazards_per_fruit).max(1)
+}
+
+// Hydra mode only opens up after a decent run
+pub const HYDRA_UNLOCK_SCORE: usize = 10;
+
+// how long snakes start out, unless they're told otherwise, see `SnakeGame::start_length`
+pub const START_LENGTH: usize = 2;
+
+// with `SnakeGame::hazard_stun`, how long a hazard keeps a snake still for
+pub const STUN_MS: usize = 100;
+
+// with `SnakeGame::combos`, how soon the next food has to be eaten to keep a combo going, and
+// how high the multiplier goes
+pub const COMBO_MS: usize = 1500;
+pub const MAX_COMBO: usize = 5;
+
+// how much energy a snake has after eating, with `SnakeGame::energy_drain`
+pub const MAX_ENERGY: usize = 1000;
+
+// How long speed fruit speeds a snake up for, and how fast it goes, in hundredths of a tile a
+// tick. That's a tile and a half, so it goes two tiles every other tick.
+pub const BOOST_MS: usize = 5000;
+pub const BOOST_SPEED: usize = 150;
+const NORMAL_SPEED: usize = 100;
+
+// how long a tile stays marked as just left behind by a tail, see `SnakeGame::trail`
+pub const TRAIL_TICKS: usize = 3;
+
+// shrink berries only turn up once a snake's this long, since there's no use for them before
+pub const SHRINK_FROM_LENGTH: usize = 10;
+
+// in `GameMode::ClosingIn`, how often another ring of walls goes up, and how many tiles across
+// the middle has to stay
+pub const CLOSE_IN_TICKS: usize = 100;
+pub const MIN_ARENA: isize = 3;
+
+// how many rings in from the edge of the board `pos` is, with the edge itself being 0
+fn ring_depth(width: isize, height: isize, pos: &Vector) -> isize {
+    pos.0
+        .min(pos.1)
+        .min(width - 1 - pos.0)
+        .min(height - 1 - pos.1)
+}
+
+// how many rings closing in mode puts up on a board this size before it stops
+fn close_in_rings(width: isize, height: isize) -> isize {
+    ((width.min(height) - MIN_ARENA) / 2).max(0)
+}
+
+// the rows of whichever of `floors` has `pos` on it, or all of them if there aren't any floors
+fn floor_of(floors: &[Range<isize>], height: isize, pos: &Vector) -> Range<isize> {
+    floors
+        .iter()
+        .find(|floor| floor.contains(&pos.1))
+        .cloned()
+        .unwrap_or(0..height)
+}
+
+// One step from `pos`, across the edge of its floor in wrap, and out the other end of a portal
+// or ladder if that's where it went. Only one a step, so coming out of one straight into another
+// runs into that one like a wall.
+fn step_from(
+    topology: Topology,
+    (width, height): (isize, isize),
+    floors: &[Range<isize>],
+    exits: [&[[Vector; 2]]; 2],
+    pos: &Vector,
+    direction: &Direction,
+) -> Vector {
+    let step = |pos: &Vector| {
+        let next = pos + &direction.to_vector();
+        match topology {
+            Topology::Walls => next,
+            Topology::Wrap => {
+                let floor = floor_of(floors, height, pos);
+                let Vector(x, y) = geometry::wrap_position(
+                    width,
+                    floor.end - floor.start,
+                    &Vector(next.0, next.1 - floor.start),
+                );
+                Vector(x, y + floor.start)
+            }
+        }
+    };
+
+    let next = step(pos);
+    match exits
+        .iter()
+        .find_map(|pairs| level::other_end(pairs, &next))
+    {
+        Some(exit) => step(exit),
+        None => next,
     }
 }
 
-#[derive(PartialEq, Eq, Clone)]
-pub enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
+// what happened on one tick of `SnakeGame::run_iter`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TickReport {
+    pub tick: usize,
+    // of the first snake, in hydra mode
+    pub head: Vector,
+    pub events: Vec<GameEvent>,
+    pub score: usize,
+    pub game_over: bool,
 }
 
-impl Default for Direction {
-    fn default() -> Self {
-        Direction::Left
+// Plays one input per tick, where `None` means keep going the same way. Ends when the inputs run
+// out, or right after the tick the game ends on.
+pub struct RunIter<'a, I> {
+    game: &'a mut SnakeGame,
+    inputs: I,
+}
+
+impl<I: Iterator<Item = Option<Direction>>> Iterator for RunIter<'_, I> {
+    type Item = TickReport;
+
+    fn next(&mut self) -> Option<TickReport> {
+        if self.game.is_game_over() {
+            return None;
+        }
+
+        if let Some(direction) = self.inputs.next()? {
+            self.game.change_direction(direction);
+        }
+
+        self.game.tick();
+
+        Some(TickReport {
+            tick: self.game.ticks,
+            head: self.game.snakes[0].head().clone(),
+            events: self.game.take_events(),
+            score: self.game.score,
+            game_over: self.game.is_game_over(),
+        })
     }
 }
 
-impl Direction {
-    fn to_vector(&self) -> Vector {
-        use Direction::*;
-        match self {
-            Up => Vector(0, -1),
-            Right => Vector(1, 0),
-            Down => Vector(0, 1),
-            Left => Vector(-1, 0),
+#[derive(Default, Clone)]
+pub struct Snake {
+    // the head is at the front of the queue. in other words, `body.front()` gets the head
+    pub body: VecDeque<Vector>,
+    direction: Direction,
+    next_direction: Direction,
+    // hydra heads split off from the tail end, so they steer with left and right swapped
+    mirrored: bool,
+    // tiles left to grow by, one per tick
+    growing: usize,
+    // ticks since this snake last ate anything, for starving
+    hungry_ticks: usize,
+    // how much of its energy it's used up since it last ate, see `SnakeGame::energy_drain`
+    spent_energy: usize,
+    // Ticks left of being stunned by a hazard. The snake sits still for all but the last one,
+    // and slipping on another hazard on that last one, as it gets going again, is the end of it.
+    stunned: usize,
+    // while it's sped up, see `FoodKind::Speed`
+    boost: Option<effects::Timer>,
+    // how far it's got towards a tile it hasn't moved onto yet, in hundredths of one. only ever
+    // anything while it's sped up
+    stride: usize,
+    // where the tail was before the last tick, for drawing it in between tiles
+    pub previous_tail: Option<Vector>,
+    // whose snake this is. always 0 outside of versus mode, even for hydra heads
+    pub player: usize,
+}
+
+impl Snake {
+    fn new(direction: Direction, mirrored: bool) -> Snake {
+        Snake {
+            direction: direction.clone(),
+            next_direction: direction,
+            mirrored,
+            ..Snake::default()
+        }
+    }
+
+    pub fn head(&self) -> &Vector {
+        self.body.front().unwrap()
+    }
+
+    pub fn direction(&self) -> &Direction {
+        &self.direction
+    }
+
+    pub fn tail(&self) -> &Vector {
+        self.body.back().unwrap()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.stunned > 0
+    }
+
+    pub fn is_boosted(&self) -> bool {
+        self.boost.is_some()
+    }
+
+    // How many tiles it goes this tick, `now_ms` into the run. The fractions add up, so a snake
+    // that's sped up sometimes goes two.
+    fn strides(&mut self, now_ms: usize) -> usize {
+        if self.boost.is_some_and(|boost| boost.is_done(now_ms)) {
+            self.boost = None;
+            self.stride = 0;
         }
+
+        self.stride += match self.boost {
+            Some(_) => BOOST_SPEED,
+            None => NORMAL_SPEED,
+        };
+        let strides = self.stride / NORMAL_SPEED;
+        self.stride %= NORMAL_SPEED;
+        strides
+    }
+
+    // turns around where it is, so the tail leads and it heads back the way it came
+    fn reverse(&mut self) {
+        self.body.make_contiguous().reverse();
+
+        // a step across a wrapped edge isn't one direction or another, so it goes by the way it
+        // was headed instead, same as a snake that's just a head
+        let head = self.head();
+        let direction = self
+            .body
+            .get(1)
+            .and_then(|neck| Direction::from_vector(&Vector(head.0 - neck.0, head.1 - neck.1)))
+            .unwrap_or_else(|| self.direction.opposite());
+
+        self.direction = direction.clone();
+        self.next_direction = direction;
+        // the tail didn't go anywhere, it just became the head
+        self.previous_tail = None;
+    }
+
+    // Which way pressing `direction` would have it go, see `Geometry::steer`. Hydra heads that
+    // steer backwards have left and right swapped before anything else.
+    pub fn steered(&self, direction: &Direction, geometry: &dyn Geometry) -> Direction {
+        let direction = if self.mirrored {
+            direction.mirror_horizontal()
+        } else {
+            direction.clone()
+        };
+
+        geometry.steer(direction, &self.direction)
     }
 
-    fn opposite(&self) -> Direction {
-        use Direction::*;
-        match self {
-            Up => Down,
-            Right => Left,
-            Down => Up,
-            Left => Right,
+    // returns false if the snake can't turn that way, because it would reverse into itself
+    fn change_direction(&mut self, direction: Direction, geometry: &dyn Geometry) -> bool {
+        let direction = self.steered(&direction, geometry);
+
+        // the diagonals only hex boards have
+        if !geometry.directions().contains(&direction) {
+            return false;
+        }
+
+        if self.direction.opposite() == direction {
+            return false;
+        }
+
+        if self.direction == direction {
+            return true;
         }
+
+        self.next_direction = direction;
+        true
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct SnakeGame {
     pub width: isize,
     pub height: isize,
+    pub topology: Topology,
+    // square or hex tiles, see `geometry`. restart after changing it, since a snake going one
+    // of the ways only hex boards have couldn't carry on on a square one
+    pub grid: Grid,
 
     // keep track of which grid tiles are available to spawn objects
-    pub free_positions: Vec<Vector>,
+    free_positions: FreeSlots,
+    // what's on each tile, kept in sync with `snakes`, `hazards`, and `walls`. call
+    // `rebuild_board` after changing `walls` by hand
+    board: Board,
 
-    // Snake's head is at the front of the queue. in other words, `snake.get(0)` gets the head
-    pub snake: VecDeque<Vector>,
-    direction: Direction,
-    next_direction: Direction,
-    pub hazards: Vec<Vector>,
-    pub food: Vec<Vector>,
+    // there's only ever more than one snake in hydra and versus mode
+    snakes: Vec<Snake>,
+    hazards: Vec<Vector>,
+    // when each hazard left behind by eating was spawned. hazards from levels aren't in here,
+    // since they never go away
+    pub hazard_spawn_ticks: Vec<(Vector, usize)>,
+    // how many ticks hazards last before drying up, or `None` to keep them forever. set this
+    // before restarting, since it changes `max_score`
+    pub hazard_lifetime: Option<usize>,
+    // every fruit leaves two hazards behind instead of one. set this before restarting, since it
+    // changes `max_score`
+    pub double_hazards: bool,
+    // what leaves hazards and what counts as a crash, see `RuleSet`. set this before
+    // restarting, since it changes `max_score`
+    pub rules: RuleSet,
+    // the first hazard a snake slips on stuns it for `STUN_MS` and goes away, instead of
+    // ending the run. it's only the second one, while still stunned, that does
+    pub hazard_stun: bool,
+    // for this many ticks at the start of a run, running into a wall turns the snake around
+    // instead of ending it, for players who weren't quite ready. see `in_grace`
+    pub grace_ticks: usize,
+    // Outside of versus, the run is won once the snakes cover this much of the board that isn't
+    // wall, in percent. Either way it's won when there's no room left for food.
+    pub win_percent: Option<usize>,
+    // eating again within `COMBO_MS` of the last food raises a multiplier on what food is
+    // worth, up to `MAX_COMBO`. only outside of versus, where the players would have to share
+    // it. set this before restarting, since it changes `max_score`
+    pub combos: bool,
+    // fresh food is worth more the sooner it's eaten, see `Food::fresh_bonus`. only outside of
+    // versus, like combos, and set this before restarting too
+    pub fresh_food: bool,
+    // what the last food was multiplied by, while there's still time to beat it. 1 with no
+    // combo going
+    pub combo: usize,
+    // when the last food was eaten, on the same clock as `elapsed_ms`, while there's still time
+    // to keep the combo going
+    pub last_food_ms: Option<usize>,
+    // in starving mode, snakes lose a tail segment every this many ticks without eating, and
+    // the game ends when one is down to just its head
+    pub starve_ticks: Option<usize>,
+    // With hunger on, every snake's energy goes down by this much a tick, out of `MAX_ENERGY`,
+    // and eating anything fills it back up. Running out is the end of the run.
+    pub energy_drain: Option<usize>,
+    // how many ticks each number of the countdown before a run lasts, or 0 to start moving right
+    // away. changes take effect on the next restart
+    pub countdown_ticks: usize,
+    // ticks left before the snakes start moving. steering still works in the meantime
+    countdown_left: usize,
+    // how many tiles long snakes start out on a board without a level, which has its own. set
+    // this before restarting, since it changes `max_score`
+    pub start_length: usize,
+    // how many tiles each kind of food grows a snake by. set this before restarting, since it
+    // changes `max_score`
+    pub growth: Growth,
+    // use `set_mode` to change it
+    mode: GameMode,
+    // in time attack, how many ticks the run lasts. it's up to whoever picks the mode to work
+    // this out from the speed, see `GameMode::time_limit`
+    pub time_limit: Option<usize>,
+    // nothing happens on ticks while this is set, not even the countdown. the page pauses when
+    // it looks like the player walked away
+    pub paused: bool,
+    // set once the dev console's been used on this run, which then doesn't count for high
+    // scores or anything else. cleared on restart
+    pub tampered: bool,
+    // ticks since the last restart, not counting the countdown
+    pub ticks: usize,
+    // fruit eaten since the last restart, by every snake, which is what a race goes by. only
+    // food that scores counts, like towards a wave's quota
+    pub fruit_eaten: usize,
+    // how long each tick takes, for timing effects, see `effects`. changing it partway through
+    // a run is fine, it only changes how long what's left of them takes in ticks
+    pub tick_ms: usize,
+    // play time since the last restart, going up by `tick_ms` each tick
+    pub elapsed_ms: usize,
+    // what the random numbers were seeded with when this run started. each one is drawn from
+    // the run before, so seeding once still plays out the same way every time
+    pub seed: [u16; 2],
+    // what every run starts from in daily mode, so it's the same board each time. `None` picks
+    // a new seed every run like the other modes
+    pub daily_seed: Option<[u16; 2]>,
+    // turns since the last restart that no snake could make, like reversing into itself
+    pub rejected_inputs: usize,
+    // tiles that tails have just moved off, and the tick they did, for drawing a trail that
+    // fades out behind them. only for `TRAIL_TICKS`, and never saved, since it's only for show
+    vacated: Vec<(Vector, usize)>,
+    // hazards that move about, see `enemy`
+    enemies: Vec<Enemy>,
+    // how many enemies each run starts with. changes take effect on the next restart
+    pub enemy_count: usize,
+    // static tiles that kill on contact, only loaded from levels for now
+    pub walls: Vec<Vector>,
+    // see `Level::portals`, loaded from the level on restart
+    portals: Vec<[Vector; 2]>,
+    // see `Level::floors` and `Level::ladders`, also from the level. no floors is the same as
+    // one with every row
+    floors: Vec<Range<isize>>,
+    ladders: Vec<[Vector; 2]>,
+    // In closing in mode, walls that are due to go up but are waiting for the way to be clear.
+    // A wall never goes up under a snake, right in front of a head, or where it would cut a
+    // snake off from the middle of the board.
+    pub pending_walls: Vec<Vector>,
+    // in waves mode, the one that's on or coming up, see `waves`
+    wave: Option<Wave>,
+    food: Vec<Food>,
     //~ pub food: Vector,
-    game_over: bool,
+    // whether bonus and rotten food show up alongside the normal kind
+    pub special_food: bool,
+    // keeps new food off the tiles right next to a head, so it doesn't turn up where a snake was
+    // about to go anyway
+    pub food_away_from_heads: bool,
+    // how much normal food is out at once. in versus, that's how many pairs of it
+    pub food_count: FoodCount,
+    // where food spawns. changes take effect on the next restart
+    pub spawner: Spawner,
+    pub hydra_mode: bool,
+    // two players on one board, starting and spawning food symmetrically. use `set_versus` to
+    // change it
+    versus: bool,
+    // in versus, the two players are on the same side: either one crashing ends the run for both,
+    // and nobody wins over the other
+    pub coop: bool,
+    // eating this splits the snake in two, only spawns in hydra mode
+    golden_food: Option<Vector>,
+    // why the last run ended, or `None` while it's still going
+    pub game_over_reason: Option<&'static str>,
+    // all players together
     pub score: usize,
+    // one for each player
+    pub scores: Vec<usize>,
+    // in versus mode, whoever didn't crash. `None` for a draw
+    pub winner: Option<usize>,
     high_score: usize,
     pub high_score_display: usize,
+    pub max_score: usize,
+    // layout to rebuild on restart, if we're not playing on the default empty board
+    level: Option<Level>,
+    events: Vec<GameEvent>,
+}
+
+// For setting up a game all at once, instead of making one and then changing it and starting it
+// over. Anything that's left out is the same as with `SnakeGame::new`, on the medium board, and
+// `build` checks it all before starting the first run.
+//
+//     let game = SnakeGame::builder().size(15, 11).seed([1, 2]).mode(GameMode::Zen).build()?;
+#[derive(Clone, Debug)]
+pub struct SnakeGameBuilder {
+    width: isize,
+    height: isize,
+    // the first run's, otherwise it's picked from the random numbers like any other
+    seed: Option<[u16; 2]>,
+    mode: GameMode,
+    rules: RuleSet,
+    start_length: usize,
+    food_count: FoodCount,
+    growth: Growth,
+}
+
+impl Default for SnakeGameBuilder {
+    fn default() -> Self {
+        SnakeGameBuilder {
+            width: 21,
+            height: 15,
+            seed: None,
+            mode: GameMode::default(),
+            rules: RuleSet::default(),
+            start_length: START_LENGTH,
+            food_count: FoodCount::default(),
+            growth: Growth::default(),
+        }
+    }
+}
+
+impl SnakeGameBuilder {
+    pub fn size(self, width: isize, height: isize) -> SnakeGameBuilder {
+        SnakeGameBuilder {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    pub fn seed(self, seed: [u16; 2]) -> SnakeGameBuilder {
+        SnakeGameBuilder {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    pub fn mode(self, mode: GameMode) -> SnakeGameBuilder {
+        SnakeGameBuilder { mode, ..self }
+    }
+
+    pub fn rules(self, rules: RuleSet) -> SnakeGameBuilder {
+        SnakeGameBuilder { rules, ..self }
+    }
+
+    pub fn initial_snake_length(self, start_length: usize) -> SnakeGameBuilder {
+        SnakeGameBuilder {
+            start_length,
+            ..self
+        }
+    }
+
+    pub fn food_count(self, food_count: FoodCount) -> SnakeGameBuilder {
+        SnakeGameBuilder { food_count, ..self }
+    }
+
+    pub fn growth(self, growth: Growth) -> SnakeGameBuilder {
+        SnakeGameBuilder { growth, ..self }
+    }
+
+    pub fn build(self) -> Result<SnakeGame, String> {
+        let mut game = SnakeGame::try_new(self.width, self.height)?;
+
+        // with room in front of the head to get going
+        if !(1..=self.width as usize - 2).contains(&self.start_length) {
+            return Err(format!(
+                "a snake {} long doesn't fit on a {}x{} board",
+                self.start_length, self.width, self.height
+            ));
+        }
+        if !(1..=MAX_FOOD_COUNT).contains(&self.food_count.most()) {
+            return Err(format!(
+                "there can only be up to {} food out at once",
+                MAX_FOOD_COUNT
+            ));
+        }
+
+        game.mode = self.mode;
+        game.time_limit = self.mode.time_limit(game.tick_ms as i32);
+        game.rules = self.rules;
+        game.start_length = self.start_length;
+        game.food_count = self.food_count;
+        game.growth = self.growth;
+
+        match self.seed {
+            Some(seed) => game.restart_with_seed(seed),
+            None => game.restart(),
+        }
+
+        Ok(game)
+    }
 }
 
 impl SnakeGame {
+    // for sizes that are known to be fine, like the default. anything else panics, so sizes
+    // from the player or the page go through `try_new`
     pub fn new(width: isize, height: isize) -> SnakeGame {
-        assert!(width >= 5);
-        assert!(height >= 3);
+        SnakeGame::try_new(width, height).unwrap_or_else(|error| panic!("{error}"))
+    }
 
-        let snake = VecDeque::with_capacity((width * height).try_into().unwrap());
-        let free_positions = Vec::with_capacity((width * height).try_into().unwrap());
+    pub fn try_new(width: isize, height: isize) -> Result<SnakeGame, String> {
+        check_board(width, height)?;
 
         let mut game = SnakeGame {
             width,
             height,
-            snake,
-            free_positions,
+            tick_ms: effects::DEFAULT_TICK_MS,
+            start_length: START_LENGTH,
             ..SnakeGame::default()
         };
 
         game.restart();
 
-        game
+        Ok(game)
     }
 
-    pub fn restart(&mut self) {
-        let width = self.width;
-        let height = self.height;
+    pub fn builder() -> SnakeGameBuilder {
+        SnakeGameBuilder::default()
+    }
 
-        self.clear_board();
+    // same as `new` for whether it panics, see `try_resize`
+    pub fn resize(&mut self, width: isize, height: isize) {
+        self.try_resize(width, height)
+            .unwrap_or_else(|error| panic!("{error}"));
+    }
 
-        let tail = Vector(width - 1, height / 2);
-        self.push_snake_head(tail);
+    // Levels have a fixed size, so this also goes back to the default empty board. A size that's
+    // no good leaves everything as it was.
+    pub fn try_resize(&mut self, width: isize, height: isize) -> Result<(), String> {
+        check_board(width, height)?;
 
-        let head = Vector(width - 2, height / 2);
-        self.push_snake_head(head);
+        self.width = width;
+        self.height = height;
+        self.drop_level();
 
-        self.add_food(1);
+        self.restart();
+        Ok(())
+    }
 
-        self.direction = Direction::Left;
-        self.next_direction = Direction::Left;
-        self.game_over = false;
-        self.high_score_display = self.high_score;
-        self.score = 0;
+    // same as `new` for whether it panics, see `try_load_level`
+    pub fn load_level(&mut self, level: &Level) {
+        self.try_load_level(level)
+            .unwrap_or_else(|error| panic!("{error}"));
     }
 
-    fn clear_board(&mut self) {
-        self.snake.clear();
-        self.hazards.clear();
-        self.food.clear();
-        self.init_free_positions();
+    // a level that's no good leaves everything as it was
+    pub fn try_load_level(&mut self, level: &Level) -> Result<(), String> {
+        check_board(level.width, level.height)?;
+
+        let taken = level
+            .walls
+            .iter()
+            .chain(level.hazards.iter())
+            .chain(level.snake.iter())
+            .collect::<HashSet<_>>();
+        if taken.len() >= (level.width * level.height) as usize {
+            return Err("there's no room on the level for any food".to_string());
+        }
+
+        // levels are laid out for one player
+        self.versus = false;
+
+        self.width = level.width;
+        self.height = level.height;
+        self.drop_level();
+        self.level = Some(level.clone());
+
+        if !level.food.is_empty() {
+            self.spawner = Spawner::fixed(level.food.clone());
+        }
+
+        self.restart();
+        Ok(())
+    }
+
+    // `None` on the default empty board
+    pub fn level(&self) -> Option<&Level> {
+        self.level.as_ref()
+    }
+
+    pub fn is_versus(&self) -> bool {
+        self.versus
     }
 
-    fn push_snake_head(&mut self, head: Vector) {
-        remove_from_vec(&mut self.free_positions, &head);
-        self.snake.push_front(head);
+    // whether walls are still forgiving, see `grace_ticks`. the countdown doesn't use any up
+    pub fn in_grace(&self) -> bool {
+        self.ticks <= self.grace_ticks
     }
 
-    fn pop_snake_tail(&mut self) {
-        let pos = self.snake.pop_back().unwrap();
-        if !self.hazards.contains(&pos) {
-            self.free_positions.push(pos);
+    // versus is always played on the default empty board, and without hydras
+    pub fn set_versus(&mut self, versus: bool) {
+        self.versus = versus;
+        if versus {
+            self.hydra_mode = false;
         }
+
+        self.drop_level();
+        self.restart();
     }
 
-    pub fn change_direction(&mut self, direction: Direction) {
-        if self.direction == direction || self.direction.opposite() == direction {
-            return;
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    // Scores from different modes don't compare, so switching also starts the high score over.
+    pub fn set_mode(&mut self, mode: GameMode) {
+        if std::mem::replace(&mut self.mode, mode) != mode {
+            self.high_score = 0;
         }
 
-        self.next_direction = direction;
+        self.restart();
     }
 
-    fn is_within_board(&self, &Vector(x, y): &Vector) -> bool {
-        x >= 0 && y >= 0 && x < self.width && y < self.height
+    // in a race, how much more fruit there is to eat, otherwise `None`
+    pub fn fruit_left(&self) -> Option<usize> {
+        Some(self.mode.fruit_target()?.saturating_sub(self.fruit_eaten))
     }
 
-    fn init_free_positions(&mut self) {
-        self.free_positions.clear();
+    // how long a race that's been won took, on the play clock so it's the same however the page
+    // kept up, see `elapsed_ms`. `None` for the other modes, and for a race that was lost
+    pub fn race_ms(&self) -> Option<usize> {
+        (self.fruit_left() == Some(0) && self.state() == GameState::Won).then_some(self.elapsed_ms)
+    }
 
-        self.free_positions.extend(
-            (0..self.height)
-                .flat_map(|y| (0..self.width).map(move |x| Vector(x, y)))
-                .filter(|pos| {
-                    !self.snake.contains(pos)
-                        && !self.hazards.contains(pos)
-                        && !self.food.contains(pos)
-                }),
-        );
+    // in waves mode, the wave that's on, or the one that's coming up during the break
+    pub fn wave(&self) -> Option<Wave> {
+        self.wave
     }
 
-    pub fn tick(&mut self) {
-        if self.game_over {
-            return;
+    // in time attack, how long the run has left, otherwise `None`
+    pub fn ticks_left(&self) -> Option<usize> {
+        if !matches!(self.mode, GameMode::TimeAttack { .. }) {
+            return None;
         }
 
-        self.direction = self.next_direction.clone();
+        Some(self.time_limit?.saturating_sub(self.ticks))
+    }
 
-        // get new head position
-        let new_head = {
-            let old_head = self.snake.get(0).unwrap();
+    pub fn unload_level(&mut self) {
+        self.drop_level();
 
-            &self.direction.to_vector() + old_head
-        };
+        self.restart();
+    }
 
-        if !self.is_within_board(&new_head) {
-            self.end_game("avoid walls");
-            return;
+    fn drop_level(&mut self) {
+        // scripted food belongs to the level it came from
+        if let Some(level) = self.level.take() {
+            if !level.food.is_empty() {
+                self.spawner = Spawner::Random;
+            }
         }
+    }
 
-        if self.snake.contains(&new_head) {
-            self.end_game("avoid crashing into your own tail");
-            return;
-        }
+    pub fn restart(&mut self) {
+        let seed = match (self.mode.is_daily(), self.daily_seed) {
+            (true, Some(seed)) => seed,
+            _ => [random::get_u16(), random::get_u16()],
+        };
+        self.restart_with_seed(seed);
+    }
 
-        if self.hazards.contains(&new_head) {
-            self.end_game("don't slip on the leftovers");
-            return;
-        }
+    // plays a run again, given the seed it started with
+    pub fn restart_with_seed(&mut self, seed: [u16; 2]) {
+        let width = self.width;
+        let height = self.height;
+
+        self.seed = seed;
+        random::seed(seed);
+
+        self.clear_board();
+        self.spawner.rewind();
+
+        match self.level.take() {
+            Some(level) => {
+                self.walls.extend(level.walls.iter().cloned());
+                self.hazards.extend(level.hazards.iter().cloned());
+                self.portals = level.portals.clone();
+                self.ladders = level.ladders.clone();
+                self.floors = level.floors();
+                self.rebuild_board();
+
+                self.snakes.push(Snake::new(level.direction.clone(), false));
+
+                // pushing to the front, so start from the tail end
+                for pos in level.snake.iter().rev() {
+                    self.push_snake_head(0, pos.clone());
+                }
 
-        // add new head
-        self.push_snake_head(new_head.clone());
+                self.level = Some(level);
+            }
+            None => {
+                // head first, along the middle row from the right hand edge, with a tile left
+                // in front of it whatever the length
+                let length = self.start_length.clamp(1, width as usize - 2) as isize;
+                let body = (0..length)
+                    .map(|along| Vector(width - length + along, height / 2))
+                    .collect::<Vec<_>>();
+                // the same start, turned around to the other side of the board
+                let rival_body = body
+                    .iter()
+                    .map(|pos| geometry::mirror_position(width, height, pos))
+                    .collect::<Vec<_>>();
+
+                // a new maze every run, that the seed plays back
+                if self.mode.builds_maze() {
+                    let mut starts = vec![body.clone()];
+                    if self.versus {
+                        starts.push(rival_body.clone());
+                    }
+                    self.walls =
+                        generate::maze(width, height, seed, generate::MAZE_DENSITY, &starts);
+                    self.rebuild_board();
+                }
 
-        // check for eating
-        if self.food.contains(&new_head) {
-            self.score += 1;
+                // pushing to the front, so start from the tail end
+                self.snakes.push(Snake::new(Direction::Left, false));
+                for pos in body.into_iter().rev() {
+                    self.push_snake_head(0, pos);
+                }
 
-            let tail_pos = self.snake.back().unwrap();
+                if self.versus {
+                    let mut rival = Snake::new(Direction::Right, false);
+                    rival.player = 1;
+                    self.snakes.push(rival);
 
-            // note that we don't check if there's a hazard here. in the uncommon event that
-            // two food items are directly next to each other, two hazards can spawn in the same
-            // space. experts say this is "fine"
-            self.hazards.push(tail_pos.clone());
+                    for pos in rival_body.into_iter().rev() {
+                        self.push_snake_head(1, pos);
+                    }
+                }
+            }
+        }
 
-            remove_from_vec(&mut self.food, &new_head);
+        // everything that isn't a wall or a hazard at the start is fair game, though when the
+        // walls close in there's only ever the middle left in the end
+        let mut open_tiles = (width * height) as usize - self.walls.len() - self.hazards.len();
+        if self.mode.closes_in() {
+            let rings = close_in_rings(width, height);
+            open_tiles = open_tiles.min(((width - rings * 2) * (height - rings * 2)) as usize);
+        }
+        let lasting_hazards = if self.hazard_lifetime.is_none() {
+            self.hazards_per_fruit()
+        } else {
+            0
+        };
+        self.max_score = max_score_with(
+            open_tiles,
+            self.snakes[0].body.len(),
+            self.growth.of(FoodKind::Normal),
+            lasting_hazards,
+        );
+        if self.has_combos() {
+            self.max_score *= MAX_COMBO;
+        }
+        if self.has_fresh_food() {
+            self.max_score *= 1 + MAX_FRESH_BONUS;
+        }
 
-            //~ self.add_food(self.score);
-            self.add_food(1);
+        // the waves bring their own enemies
+        self.wave = None;
+        if self.mode.has_waves() {
+            let wave = Wave::first();
+            self.lay_wave(&wave);
+            self.wave = Some(wave);
         } else {
-            // remove tail if only if not eating; in other words, we grow if we eat
-            self.pop_snake_tail();
+            self.add_enemies(self.enemy_count);
         }
+        // nobody's scored yet
+        self.add_food(self.food_count.target(0));
+
+        self.game_over_reason = None;
+        self.high_score_display = self.high_score;
+        self.score = 0;
+        self.scores = vec![0; if self.versus { 2 } else { 1 }];
+        self.winner = None;
+        self.combo = 1;
+        self.last_food_ms = None;
+        self.ticks = 0;
+        self.fruit_eaten = 0;
+        self.elapsed_ms = 0;
+        self.rejected_inputs = 0;
+        self.vacated.clear();
+        self.paused = false;
+        self.tampered = false;
+        self.start_countdown();
+
+        self.events.push(GameEvent::Restarted);
     }
 
-    pub fn get_semi_open_tiles(&self) -> Vec<Vector> {
-        let snake_head = self.snake[0].clone();
+    pub fn has_combos(&self) -> bool {
+        self.combos && !self.versus
+    }
 
-        // Couldn't figure out how to do this with iterators haha
-        // should compile down about the same
-        let mut vec = vec![];
+    pub fn has_fresh_food(&self) -> bool {
+        self.fresh_food && !self.versus
+    }
 
-        for pos in self.adjacent_tiles(&snake_head) {
-            vec.push(pos);
+    // with fresh food on, what each food out now would be worth, while it's worth more than usual
+    pub fn fresh_values(&self) -> impl Iterator<Item = (&Vector, isize)> {
+        let fresh = self.has_fresh_food();
+        self.food.iter().filter(move |_| fresh).filter_map(|food| {
+            match food.fresh_bonus(self.elapsed_ms) {
+                0 => None,
+                bonus => Some((&food.position, food.kind.score_delta() + bonus as isize)),
+            }
+        })
+    }
+
+    // What food eaten this tick is multiplied by, building up the combo for the next one. Rotten
+    // food costs the same as ever, and breaks the combo.
+    fn combo_for(&mut self, kind: FoodKind) -> usize {
+        if !self.has_combos() {
+            return 1;
         }
 
-        for fruit in self.food.iter() {
-            for pos in self.adjacent_tiles(fruit) {
-                vec.push(pos);
-            }
+        if kind.score_delta() < 0 {
+            self.combo = 1;
+            self.last_food_ms = None;
+            return 1;
         }
 
-        vec
+        // the first food of a combo counts as usual
+        if self.last_food_ms.is_some() {
+            self.combo = (self.combo + 1).min(MAX_COMBO);
+        }
+        self.last_food_ms = Some(self.elapsed_ms);
+
+        self.combo
     }
 
-    fn adjacent_tiles(&self, position: &Vector) -> impl Iterator<Item = Vector> + '_ {
-        [
-            Vector(position.0 - 1, position.1),
-            Vector(position.0 + 1, position.1),
-            Vector(position.0, position.1 - 1),
-            Vector(position.0, position.1 + 1),
-        ]
-        .into_iter()
-        .filter(|pos| self.is_within_board(pos))
+    // counts down again from the top, for when the countdown length changes before a run starts
+    pub fn start_countdown(&mut self) {
+        self.countdown_left = COUNTDOWN_FROM * self.countdown_ticks;
     }
 
-    fn add_food(&mut self, number: usize) {
-        // TODO: make this take into account the "semi-open" tiles to either completely avoid
-        // placing food in them, or to reduce the chances
+    // the number to show while counting down, like 3, or `None` once the run is going
+    pub fn countdown(&self) -> Option<usize> {
+        (self.countdown_left > 0).then(|| self.countdown_left.div_ceil(self.countdown_ticks))
+    }
 
-        for _i in 0..number {
-            if self.free_positions.is_empty() {
-                // Kill screen
-                self.end_game("can't believe you made it this far");
-            } else {
-                let position_index =
-                    random::get_u16() as usize % self.free_positions.len() as usize;
+    fn clear_board(&mut self) {
+        self.snakes.clear();
+        self.hazards.clear();
+        self.hazard_spawn_ticks.clear();
+        self.walls.clear();
+        self.pending_walls.clear();
+        self.portals.clear();
+        self.ladders.clear();
+        self.floors.clear();
+        self.enemies.clear();
+        self.food.clear();
+        self.golden_food = None;
+        self.rebuild_board();
+    }
 
-                // removes the element at the index and replaces it with the last element
-                let position = self.free_positions.swap_remove(position_index);
+    fn push_snake_head(&mut self, index: usize, head: Vector) {
+        self.free_positions.remove(&head);
+        self.board.add_snake(&head);
+        self.snakes[index].body.push_front(head);
+    }
 
-                self.food.push(position);
-            }
+    fn pop_snake_tail(&mut self, index: usize) {
+        let pos = self.snakes[index].body.pop_back().unwrap();
+        self.board.remove_snake(&pos);
+        self.vacated.push((pos.clone(), self.ticks));
+        // in zen, another bit of snake might still be on the tile
+        if !self.board.is_blocked_at(&pos) {
+            self.free_positions.insert(pos);
         }
     }
 
-    fn end_game(&mut self, message: &'static str) {
-        self.game_over = true;
+    // Everything on the board is read only from outside, since it all has to stay in step with
+    // `board` and `free_positions`. For drawing, `board_view` has it all in one place.
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
 
-        if self.score >= self.high_score {
-            self.high_score = self.score;
-        }
+    pub fn hazards(&self) -> &[Vector] {
+        &self.hazards
+    }
 
-        let score_text = format!(
-            "{} / Score: {} / High Score: {}",
-            message, self.score, self.high_score
-        );
+    // tiles that tails have moved off lately, with how many ticks ago, newest last
+    pub fn trail(&self) -> impl Iterator<Item = (&Vector, usize)> {
+        self.vacated
+            .iter()
+            .map(|(pos, left)| (pos, self.ticks - left))
+    }
 
-        crate::log(&score_text);
+    pub fn enemies(&self) -> &[Enemy] {
+        &self.enemies
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn portals(&self) -> &[[Vector; 2]] {
+        &self.portals
+    }
 
-    #[test]
-    fn it_works() {
-        let mut game = SnakeGame::new(5, 5);
+    pub fn ladders(&self) -> &[[Vector; 2]] {
+        &self.ladders
+    }
 
-        //~ dbg!(&game);
+    // which rows each floor has, top to bottom, see `Level::floors`
+    pub fn floors(&self) -> Vec<Range<isize>> {
+        let mut floors = self.floors.clone();
+        if floors.is_empty() {
+            floors.push(0..self.height);
+        }
+        floors
+    }
 
-        for _i in 0..4 {
-            game.tick();
-            //~ dbg!(&game);
+    // The floor the first snake's head is on, which is the one that gets drawn. The others can
+    // only be seen on the map, see `floor_map`.
+    pub fn active_floor(&self) -> Range<isize> {
+        match self.snakes.first() {
+            Some(snake) => floor_of(&self.floors, self.height, snake.head()),
+            None => 0..self.height,
         }
+    }
+
+    pub fn food(&self) -> &[Food] {
+        &self.food
+    }
+
+    pub fn golden_food(&self) -> Option<&Vector> {
+        self.golden_food.as_ref()
+    }
+
+    pub fn free_positions(&self) -> &FreeSlots {
+        &self.free_positions
+    }
+
+    pub fn board_view(&self) -> BoardView {
+        BoardView::of(self)
+    }
+
+    // for tests elsewhere that set up a board by hand. call `rebuild_board` afterwards
+    #[cfg(test)]
+    pub(crate) fn snakes_mut(&mut self) -> &mut Vec<Snake> {
+        &mut self.snakes
+    }
+
+    #[cfg(test)]
+    pub(crate) fn food_mut(&mut self) -> &mut Vec<Food> {
+        &mut self.food
+    }
+
+    pub fn is_snake_at(&self, position: &Vector) -> bool {
+        self.board.is_snake_at(position)
+    }
+
+    pub fn is_hazard_at(&self, position: &Vector) -> bool {
+        self.board.is_hazard_at(position)
+    }
 
-        assert!(game.game_over);
+    pub fn is_enemy_at(&self, position: &Vector) -> bool {
+        self.board.is_enemy_at(position)
+    }
+
+    pub fn is_wall_at(&self, position: &Vector) -> bool {
+        self.board.is_wall_at(position)
+    }
+
+    pub fn is_portal_at(&self, position: &Vector) -> bool {
+        self.board.is_portal_at(position)
+    }
+
+    // Checks that everything's still in step: the snakes are in one piece, nothing shares a
+    // tile it shouldn't, `board` agrees with the lists, and every tile is either free or taken
+    // and never both. Returns what's wrong with it, if anything. For tests and the fuzzer,
+    // since it goes over the whole board.
+    pub fn invariants(&self) -> Result<(), String> {
+        let area = (self.width * self.height) as usize;
+        let tiles = || (0..self.height).flat_map(|y| (0..self.width).map(move |x| Vector(x, y)));
+
+        let mut occupied = HashSet::new();
+        // zen snakes slide over each other, and so do any without self collision
+        let solid = self.mode.snakes_collide() && self.rules.self_collision;
+
+        for snake in self.snakes.iter() {
+            if snake.body.is_empty() {
+                return Err("a snake has no body".to_string());
+            }
+
+            // one step apart, going across the edge in wrap or through a portal
+            for (a, b) in snake.body.iter().zip(snake.body.iter().skip(1)) {
+                let stepped = self
+                    .geometry()
+                    .directions()
+                    .iter()
+                    .any(|direction| self.next_tile(b, direction) == *a);
+
+                if !stepped {
+                    return Err(format!("snake body jumps from {a:?} to {b:?}"));
+                }
+            }
+
+            for pos in snake.body.iter() {
+                if !occupied.insert(pos.clone()) && solid {
+                    return Err(format!("two snake segments overlap at {pos:?}"));
+                }
+            }
+
+            // food a head reaches is eaten on the same tick. hazards aren't checked the same
+            // way, since eating leaves one under the tail, and a head can end up there when a
+            // snake bounces and turns around, bites its tail off, or goes through another one
+            let head = snake.head();
+            let eaten = self.food.iter().any(|food| food.position == *head)
+                || self.golden_food.as_ref() == Some(head);
+            if eaten {
+                return Err(format!("a head is on food at {head:?}"));
+            }
+
+            if snake.stride >= 100 || (snake.stride > 0 && !snake.is_boosted()) {
+                return Err(format!("a snake is {} of the way to a tile", snake.stride));
+            }
+        }
+
+        let normal_food = self.food.iter().any(|food| food.kind == FoodKind::Normal);
+        if !normal_food && !self.is_game_over() {
+            return Err("there's no normal food out".to_string());
+        }
+
+        occupied.extend(self.hazards.iter().cloned());
+        occupied.extend(self.walls.iter().cloned());
+        occupied.extend(self.food.iter().map(|food| food.position.clone()));
+        occupied.extend(self.golden_food.iter().cloned());
+
+        // hazards from levels never dry up, so they aren't in there
+        for (pos, _) in self.hazard_spawn_ticks.iter() {
+            if !self.hazards.contains(pos) {
+                return Err(format!("a hazard that's gone is drying up at {pos:?}"));
+            }
+        }
+
+        // nothing goes on a portal or ladder, not even a snake on its way through
+        let exits = || self.portals.iter().chain(self.ladders.iter()).flatten();
+        for pos in exits() {
+            if !occupied.insert(pos.clone()) {
+                return Err(format!("something is on a portal or ladder at {pos:?}"));
+            }
+        }
+
+        // enemies only step onto free tiles, so they never share one
+        for enemy in self.enemies.iter() {
+            if !occupied.insert(enemy.position.clone()) {
+                return Err(format!(
+                    "an enemy is on top of something at {:?}",
+                    enemy.position
+                ));
+            }
+        }
+
+        for pos in occupied.iter() {
+            if !geometry::is_within_board(self.width, self.height, pos) {
+                return Err(format!("something is off the board at {pos:?}"));
+            }
+        }
+
+        // the rows between floors are walls, so only ladders get a snake from one to another
+        let floors = self.floors();
+        for pos in self.snakes.iter().flat_map(|snake| snake.body.iter()) {
+            if !floors.iter().any(|floor| floor.contains(&pos.1)) {
+                return Err(format!("a snake is between floors at {pos:?}"));
+            }
+        }
+
+        for pos in tiles() {
+            let snake = self.snakes.iter().any(|snake| snake.body.contains(&pos));
+
+            if self.board.is_snake_at(&pos) != snake
+                || self.board.is_hazard_at(&pos) != self.hazards.contains(&pos)
+                || self.board.is_wall_at(&pos) != self.walls.contains(&pos)
+                || self.board.is_enemy_at(&pos)
+                    != self.enemies.iter().any(|enemy| enemy.position == pos)
+                || self.board.is_portal_at(&pos) != exits().any(|end| *end == pos)
+            {
+                return Err(format!("the board is out of sync at {pos:?}"));
+            }
+        }
+
+        let free = self.free_positions.iter().cloned().collect::<HashSet<_>>();
+
+        if free.len() != self.free_positions.len() {
+            return Err("a tile is listed as free more than once".to_string());
+        }
+
+        for pos in tiles() {
+            match (free.contains(&pos), occupied.contains(&pos)) {
+                (true, true) => return Err(format!("{pos:?} is both free and taken")),
+                (false, false) => return Err(format!("{pos:?} is neither free nor taken")),
+                _ => (),
+            }
+        }
+
+        if free.len() + occupied.len() != area {
+            return Err("free tiles are off the board".to_string());
+        }
+
+        Ok(())
+    }
+
+    // which tiles are next to which, for the grid this run is on
+    pub fn geometry(&self) -> &'static dyn Geometry {
+        self.grid.geometry()
+    }
+
+    // where a head at `pos` going `direction` ends up next tick, see `step_from`
+    pub fn next_tile(&self, pos: &Vector, direction: &Direction) -> Vector {
+        step_from(
+            self.topology,
+            (self.width, self.height),
+            &self.floors,
+            [&self.portals, &self.ladders],
+            pos,
+            direction,
+        )
+    }
+
+    // for tools that want to watch a game play out without driving it tick by tick. this takes
+    // the events, so don't mix it with `take_events`
+    pub fn run_iter<I>(&mut self, inputs: I) -> RunIter<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = Option<Direction>>,
+    {
+        RunIter {
+            game: self,
+            inputs: inputs.into_iter(),
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn high_score(&self) -> usize {
+        self.high_score
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over_reason.is_some()
+    }
+
+    // a win is a kind of game over too, as far as `is_game_over` goes
+    pub fn state(&self) -> GameState {
+        match self.game_over_reason {
+            None => GameState::Playing,
+            Some(WON_REASON) => GameState::Won,
+            Some(_) => GameState::Lost,
+        }
+    }
+
+    // `max_score`, unless there's no telling. bonus fruit is worth more than the tile it takes,
+    // and shrink berries make room for more, so with special food there's no end to it
+    pub fn possible_score(&self) -> Option<usize> {
+        if self.special_food && !self.versus {
+            None
+        } else {
+            Some(self.max_score)
+        }
+    }
+
+    // how much of the board that isn't wall the snakes cover, in percent
+    pub fn fill_percent(&self) -> usize {
+        let covered = self
+            .snakes
+            .iter()
+            .map(|snake| snake.body.len())
+            .sum::<usize>();
+        let open_tiles = (self.width * self.height) as usize - self.walls.len();
+
+        covered * 100 / open_tiles.max(1)
+    }
+
+    pub fn change_direction(&mut self, direction: Direction) {
+        let geometry = self.geometry();
+        let mut turned = false;
+        for snake in self.snakes.iter_mut() {
+            turned |= snake.change_direction(direction.clone(), geometry);
+        }
+
+        if !turned {
+            self.rejected_inputs += 1;
+        }
+    }
+
+    // whether steering `player` that way would change where any of their snakes go next tick
+    pub fn would_turn(&self, player: usize, direction: &Direction) -> bool {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.player == player)
+            .any(|snake| {
+                let direction = snake.steered(direction, self.geometry());
+
+                // same as `Snake::change_direction`, which leaves a turn that's already
+                // queued alone
+                self.geometry().directions().contains(&direction)
+                    && direction != snake.direction.opposite()
+                    && direction != snake.direction
+                    && direction != snake.next_direction
+            })
+    }
+
+    // steers just the snakes belonging to `player`
+    pub fn change_player_direction(&mut self, player: usize, direction: Direction) {
+        let geometry = self.geometry();
+        let mut turned = false;
+        for snake in self
+            .snakes
+            .iter_mut()
+            .filter(|snake| snake.player == player)
+        {
+            turned |= snake.change_direction(direction.clone(), geometry);
+        }
+
+        if !turned {
+            self.rejected_inputs += 1;
+        }
+    }
+
+    // Works out what's on each tile and which tiles are free from scratch, for after the
+    // snakes, hazards, walls, or food were changed directly.
+    pub fn rebuild_board(&mut self) {
+        self.board = Board::new(self.width, self.height);
+
+        for pos in self.snakes.iter().flat_map(|snake| snake.body.iter()) {
+            self.board.add_snake(pos);
+        }
+        for pos in self.hazards.iter() {
+            self.board.add_hazard(pos);
+        }
+        for pos in self.walls.iter() {
+            self.board.add_wall(pos);
+        }
+        for enemy in self.enemies.iter() {
+            self.board.set_enemy(&enemy.position, true);
+        }
+        // ladders are as good as portals to everything but the renderers
+        for pos in self.portals.iter().chain(self.ladders.iter()).flatten() {
+            self.board.add_portal(pos);
+        }
+
+        self.free_positions.reset(self.width, self.height);
+
+        let board = &self.board;
+        self.free_positions.extend(
+            (0..self.height)
+                .flat_map(|y| (0..self.width).map(move |x| Vector(x, y)))
+                .filter(|pos| {
+                    !board.is_blocked_at(pos)
+                        && !self.food.iter().any(|food| food.position == *pos)
+                        && self.golden_food.as_ref() != Some(pos)
+                }),
+        );
+    }
+
+    // One tick even while paused, for going through a run a tick at a time. It stays paused
+    // afterwards if it was before.
+    pub fn step(&mut self) {
+        let paused = std::mem::take(&mut self.paused);
+        self.tick();
+        self.paused = paused;
+    }
+
+    // Moves everything on by a tick, and says what happened, see `TickOutcome`.
+    pub fn tick(&mut self) -> TickOutcome {
+        let (ticks, events) = (self.ticks, self.events.len());
+        let food_before = self
+            .food
+            .iter()
+            .map(|food| food.position.clone())
+            .collect::<Vec<_>>();
+
+        self.advance();
+
+        TickOutcome {
+            ticked: self.ticks > ticks,
+            ..TickOutcome::of(self, &self.events[events..], &food_before)
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.is_game_over() || self.paused {
+            return;
+        }
+
+        // nothing moves or runs out during the countdown
+        if self.countdown_left > 0 {
+            self.countdown_left -= 1;
+            return;
+        }
+
+        self.ticks += 1;
+        // a game that was never told its speed still gets somewhere
+        self.elapsed_ms += self.tick_ms.max(1);
+        let ticks = self.ticks;
+        self.vacated.retain(|(_, left)| ticks - left < TRAIL_TICKS);
+        self.expire_hazards();
+        self.expire_food();
+
+        // too slow for the combo
+        if self
+            .last_food_ms
+            .is_some_and(|ms| self.elapsed_ms - ms > COMBO_MS)
+        {
+            self.combo = 1;
+            self.last_food_ms = None;
+        }
+
+        // before anyone else moves, so heads run into them wherever they've got to
+        if self.ticks.is_multiple_of(enemy::MOVE_TICKS) {
+            self.move_enemies();
+        }
+
+        // Sped up snakes go further, but still a tile at a time along with everyone else, so
+        // whatever's on the tile in between gets run into or eaten on the way.
+        let elapsed_ms = self.elapsed_ms;
+        let strides = self
+            .snakes
+            .iter_mut()
+            .map(|snake| snake.strides(elapsed_ms))
+            .collect::<Vec<_>>();
+        for step in 0..strides.iter().copied().max().unwrap_or(1) {
+            if !self.move_snakes(step, &strides) {
+                return;
+            }
+        }
+
+        if !self.drain_energy() {
+            return;
+        }
+
+        self.close_in();
+        self.run_waves();
+
+        if let Some(win_percent) = self.win_percent {
+            if !self.versus && self.fill_percent() >= win_percent {
+                self.end_game(WON_REASON);
+                return;
+            }
+        }
+
+        if self.fruit_left() == Some(0) {
+            self.finish_race();
+            return;
+        }
+
+        if self.ticks_left() == Some(0) {
+            self.end_game_on_time();
+        }
+    }
+
+    // Moves every snake with more than `step` tiles to go this tick, out of `strides`, on by
+    // one, and sees to whatever they run into or eat. Returns whether the game's still going.
+    // Snakes that have come along since the tick started, like hydra heads, wait for the next.
+    fn move_snakes(&mut self, step: usize, strides: &[usize]) -> bool {
+        // get new head positions. stunned snakes stay where they are, and hold on to any turn
+        // until they get going again
+        let mut near_misses = vec![];
+        let mut new_heads = self
+            .snakes
+            .iter_mut()
+            .enumerate()
+            .map(|(index, snake)| {
+                if strides.get(index).is_none_or(|strides| *strides <= step) {
+                    return None;
+                }
+
+                snake.previous_tail = Some(snake.tail().clone());
+
+                // the stun only counts down once a tick, however far the snake would've gone
+                if step > 0 && snake.stunned > 0 {
+                    return None;
+                }
+                if snake.stunned > 1 {
+                    snake.stunned -= 1;
+                    return None;
+                }
+
+                // once a tick, so a fast snake isn't counted twice
+                if step == 0 && snake.next_direction != snake.direction {
+                    let straight = step_from(
+                        self.topology,
+                        (self.width, self.height),
+                        &self.floors,
+                        [&self.portals, &self.ladders],
+                        snake.head(),
+                        &snake.direction,
+                    );
+                    if self.board.is_hazard_at(&straight) {
+                        near_misses.push(snake.player);
+                    }
+                }
+
+                snake.direction = snake.next_direction.clone();
+                Some(step_from(
+                    self.topology,
+                    (self.width, self.height),
+                    &self.floors,
+                    [&self.portals, &self.ladders],
+                    snake.head(),
+                    &snake.direction,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        // Without `RuleSet::tail_blocks`, the tiles tails are moving off of this tick are open
+        // already. A tail stays put if its snake is stunned, still growing, or about to eat, and
+        // so does one that's about to bounce off a wall or slip on a hazard.
+        let tail_leaves = |snake: &Snake, new_head: &Vector| {
+            snake.growing == 0
+                && geometry::is_within_board(self.width, self.height, new_head)
+                && !self.board.is_wall_at(new_head)
+                && !self.board.is_portal_at(new_head)
+                && !self.board.is_hazard_at(new_head)
+                && !self.board.is_enemy_at(new_head)
+                && !self
+                    .food
+                    .iter()
+                    .any(|food| food.position == *new_head && self.growth.of(food.kind) > 0)
+        };
+        let leaving_tails = match self.rules.tail_blocks {
+            true => vec![],
+            false => self
+                .snakes
+                .iter()
+                .zip(new_heads.iter())
+                .filter_map(|(snake, new_head)| {
+                    let new_head = new_head.as_ref()?;
+                    tail_leaves(snake, new_head).then(|| snake.tail().clone())
+                })
+                .collect::<Vec<_>>(),
+        };
+
+        // everyone moves at once, so check all of them before moving anyone
+        let mut crashes = vec![];
+        let mut stuns = vec![];
+        let mut bounces = vec![];
+        let mut cuts = vec![];
+
+        for (index, new_head) in new_heads.iter().enumerate() {
+            let Some(new_head) = new_head else {
+                continue;
+            };
+
+            // two heads going for the same tile counts too, for both of them
+            let head_on = new_heads
+                .iter()
+                .enumerate()
+                .any(|(other, other_head)| other != index && other_head.as_ref() == Some(new_head));
+            let crashes_into_snake =
+                self.is_snake_at(new_head) && !leaving_tails.contains(new_head);
+
+            let reason = if !geometry::is_within_board(self.width, self.height, new_head)
+                || self.board.is_wall_at(new_head)
+                || self.board.is_portal_at(new_head)
+            {
+                if self.in_grace() {
+                    bounces.push(index);
+                    continue;
+                }
+                "avoid walls"
+            } else if self.mode.snakes_collide()
+                && self.rules.self_collision
+                && (crashes_into_snake || head_on)
+            {
+                // only a snake's own body, and not anyone else's or a head coming the other way
+                if self.rules.tail_cut && !head_on && self.snakes[index].body.contains(new_head) {
+                    cuts.push(index);
+                    continue;
+                }
+                "avoid crashing into your own tail"
+            } else if self.board.is_enemy_at(new_head) {
+                "watch out for the wanderers"
+            } else if self.board.is_hazard_at(new_head) {
+                if self.hazard_stun && !self.snakes[index].is_stunned() {
+                    stuns.push(index);
+                    continue;
+                }
+                "don't slip on the leftovers"
+            } else {
+                continue;
+            };
+
+            crashes.push((self.snakes[index].player, reason));
+        }
+
+        if !crashes.is_empty() {
+            self.end_game_for(&crashes);
+            return false;
+        }
+
+        for player in near_misses {
+            self.events.push(GameEvent::NearMiss { player });
+        }
+
+        // bounced snakes spend the tick turning around
+        for index in bounces {
+            new_heads[index] = None;
+            self.snakes[index].reverse();
+            self.events.push(GameEvent::SnakeBounced {
+                player: self.snakes[index].player,
+            });
+        }
+
+        for index in cuts {
+            if let Some(pos) = new_heads[index].clone() {
+                self.cut_snake(index, &pos);
+            }
+        }
+
+        for index in stuns {
+            if let Some(pos) = new_heads[index].take() {
+                self.clear_hazards_at(&pos);
+            }
+            // sitting still for all but the last tick of it
+            self.snakes[index].stunned = effects::ticks(STUN_MS, self.tick_ms) + 1;
+            self.events.push(GameEvent::SnakeStunned {
+                player: self.snakes[index].player,
+            });
+        }
+
+        let mut starved = vec![];
+
+        for (index, new_head) in new_heads.into_iter().enumerate() {
+            let Some(new_head) = new_head else {
+                continue;
+            };
+            // back on the move, if it was stunned
+            self.snakes[index].stunned = 0;
+
+            // add new head
+            self.push_snake_head(index, new_head.clone());
+
+            // check for eating
+            if let Some(food_index) = self.food.iter().position(|food| food.position == new_head) {
+                let food = self.food.swap_remove(food_index);
+                let kind = food.kind;
+                self.snakes[index].hungry_ticks = 0;
+                self.snakes[index].spent_energy = 0;
+
+                let player = self.snakes[index].player;
+                let multiplier = self.combo_for(kind);
+                let bonus = match self.has_fresh_food() {
+                    true => food.fresh_bonus(self.elapsed_ms),
+                    false => 0,
+                };
+                let points = (kind.score_delta() + bonus as isize) * multiplier as isize;
+                self.scores[player] = self.scores[player].saturating_add_signed(points);
+                self.score = self.scores.iter().sum();
+                self.snakes[index].growing += self.growth.of(kind);
+                if kind == FoodKind::Speed {
+                    self.snakes[index].boost = Some(effects::Timer::new(self.elapsed_ms, BOOST_MS));
+                }
+                if kind == FoodKind::Shrink {
+                    self.shrink_snake(index, SHRINK_TILES);
+                }
+
+                self.events.push(GameEvent::FoodEaten {
+                    position: new_head.clone(),
+                    kind,
+                    multiplier,
+                    points,
+                });
+                self.events
+                    .push(GameEvent::ScoreChanged { score: self.score });
+
+                if kind.spawns_hazard() {
+                    let body = &self.snakes[index].body;
+                    for pos in spawner::hazard_tiles(body, self.hazards_per_fruit()) {
+                        self.lay_hazard(pos);
+                    }
+                }
+
+                if kind.score_delta() > 0 {
+                    self.fruit_eaten += 1;
+                }
+
+                // the fruit that clears a wave mops up the puddle it just left as well
+                if kind.score_delta() > 0 && self.wave.as_mut().is_some_and(|wave| wave.eat()) {
+                    self.clear_wave();
+                }
+
+                // any food can bring more out, if it's put the score over the next step. the rare
+                // kinds only come along with normal food being replaced
+                if self.top_up_food() > 0 && kind == FoodKind::Normal {
+                    self.maybe_add_golden_food();
+                    self.maybe_add_special_food();
+                }
+            }
+
+            // remove tail if only if not growing; in other words, we grow if we eat. a hazard
+            // left on the tail tile keeps it from being freed up
+            if self.snakes[index].growing > 0 {
+                self.snakes[index].growing -= 1;
+            } else {
+                self.pop_snake_tail(index);
+            }
+
+            if self.starve(index) {
+                starved.push((self.snakes[index].player, "don't starve"));
+            }
+
+            if self.golden_food.as_ref() == Some(&new_head) {
+                self.golden_food = None;
+                self.split_snake(index);
+            }
+        }
+
+        if !starved.is_empty() {
+            self.end_game_for(&starved);
+            return false;
+        }
+
+        true
+    }
+
+    // Bites a snake off at `pos`, where its head's about to go. Everything behind that turns into
+    // hazards and costs a point a segment, see `RuleSet::tail_cut`.
+    fn cut_snake(&mut self, index: usize, pos: &Vector) {
+        let Some(hit) = self.snakes[index]
+            .body
+            .iter()
+            .position(|segment| segment == pos)
+        else {
+            return;
+        };
+
+        let severed = self.snakes[index].body.split_off(hit);
+        for segment in severed.iter() {
+            self.board.remove_snake(segment);
+        }
+        // the head's taking the bitten tile, so only the ones after it are left behind
+        for segment in severed.iter().skip(1) {
+            self.hazards.push(segment.clone());
+            self.board.add_hazard(segment);
+            if self.hazard_lifetime.is_some() {
+                self.hazard_spawn_ticks.push((segment.clone(), self.ticks));
+            }
+            self.events.push(GameEvent::HazardSpawned {
+                position: segment.clone(),
+            });
+        }
+        // the tail's nowhere near where it was, so there's nothing to slide it from
+        self.snakes[index].previous_tail = None;
+
+        let player = self.snakes[index].player;
+        self.scores[player] = self.scores[player].saturating_sub(severed.len());
+        self.score = self.scores.iter().sum();
+        self.events.push(GameEvent::SnakeCut {
+            player,
+            segments: severed.len(),
+        });
+        self.events
+            .push(GameEvent::ScoreChanged { score: self.score });
+    }
+
+    // Takes up to `tiles` off the tail of the snake at `index`, for a shrink berry it's just
+    // eaten. Growth that hasn't happened yet goes first, like starving, and it never leaves less
+    // than a head and a tail, once the tail's moved up this tick as well.
+    fn shrink_snake(&mut self, index: usize, tiles: usize) {
+        for _ in 0..tiles {
+            let snake = &mut self.snakes[index];
+            if snake.growing > 0 {
+                snake.growing -= 1;
+            } else if snake.body.len() > START_LENGTH + 1 {
+                // which goes back to being free, unless there's a puddle on it
+                self.pop_snake_tail(index);
+            } else {
+                break;
+            }
+        }
+        // the tail's nowhere near where it was, so there's nothing to slide it from
+        self.snakes[index].previous_tail = None;
+    }
+
+    // Counts another hungry tick, and shrinks the snake if it's been hungry too long. Returns
+    // whether it's starved down to just a head.
+    fn starve(&mut self, index: usize) -> bool {
+        let Some(starve_ticks) = self.starve_ticks else {
+            return false;
+        };
+
+        let snake = &mut self.snakes[index];
+        snake.hungry_ticks += 1;
+
+        if snake.hungry_ticks < starve_ticks {
+            return false;
+        }
+
+        snake.hungry_ticks = 0;
+
+        // growth that hasn't happened yet goes first
+        if snake.growing > 0 {
+            snake.growing -= 1;
+        } else if snake.body.len() > 1 {
+            self.pop_snake_tail(index);
+        }
+
+        self.snakes[index].body.len() <= 1
+    }
+
+    // With `energy_drain`, uses up another tick's worth of every snake's energy, ending the run
+    // for any that's run out. Returns whether the game's still going.
+    fn drain_energy(&mut self) -> bool {
+        let Some(drain) = self.energy_drain else {
+            return true;
+        };
+
+        let mut starved = vec![];
+        for snake in self.snakes.iter_mut() {
+            snake.spent_energy = (snake.spent_energy + drain).min(MAX_ENERGY);
+            if snake.spent_energy == MAX_ENERGY {
+                starved.push((snake.player, STARVED_REASON));
+            }
+        }
+
+        if starved.is_empty() {
+            return true;
+        }
+        self.end_game_for(&starved);
+        false
+    }
+
+    // how much energy the snake at `index` has left, out of `MAX_ENERGY`, or `None` with hunger
+    // off
+    pub fn energy(&self, index: usize) -> Option<usize> {
+        self.energy_drain?;
+        Some(MAX_ENERGY - self.snakes.get(index)?.spent_energy)
+    }
+
+    fn hazards_per_fruit(&self) -> usize {
+        if !self.mode.has_hazards() || !self.rules.hazards_on_eat {
+            0
+        } else if self.double_hazards {
+            2
+        } else {
+            1
+        }
+    }
+
+    // how many ticks until the snake at `index` loses a segment, outside of starving mode `None`
+    pub fn ticks_until_starving(&self, index: usize) -> Option<usize> {
+        let starve_ticks = self.starve_ticks?;
+        Some(starve_ticks - self.snakes.get(index)?.hungry_ticks)
+    }
+
+    // how many more ticks the hazard at `position` sticks around for, or `None` if it's there for
+    // good
+    pub fn hazard_ticks_left(&self, position: &Vector) -> Option<usize> {
+        let lifetime = self.hazard_lifetime?;
+
+        // with stacked hazards, the tile stays until the newest one dries up
+        let spawn_tick = self
+            .hazard_spawn_ticks
+            .iter()
+            .filter(|(pos, _)| pos == position)
+            .map(|(_, spawn_tick)| *spawn_tick)
+            .max()?;
+
+        Some(lifetime - (self.ticks - spawn_tick))
+    }
+
+    // In closing in mode, starts another ring of walls every `CLOSE_IN_TICKS`, from the edge
+    // inwards, and puts up whatever walls are safe to by now.
+    fn close_in(&mut self) {
+        if !self.mode.closes_in() {
+            return;
+        }
+
+        let (width, height) = (self.width, self.height);
+        let ring = (self.ticks / CLOSE_IN_TICKS) as isize - 1;
+        if self.ticks.is_multiple_of(CLOSE_IN_TICKS) && ring < close_in_rings(width, height) {
+            let board = &self.board;
+            let new_walls = (0..height)
+                .flat_map(|y| (0..width).map(move |x| Vector(x, y)))
+                .filter(|pos| {
+                    ring_depth(width, height, pos) == ring
+                        && !board.is_wall_at(pos)
+                        && !board.is_portal_at(pos)
+                })
+                .collect::<Vec<_>>();
+
+            self.pending_walls.extend(new_walls);
+            self.events.push(GameEvent::WallsClosingIn);
+        }
+
+        let mut food_walled_over = false;
+        for pos in std::mem::take(&mut self.pending_walls) {
+            if self.wall_blocks_escape(&pos) {
+                self.pending_walls.push(pos);
+                continue;
+            }
+
+            self.walls.push(pos.clone());
+            self.board.add_wall(&pos);
+            self.free_positions.remove(&pos);
+            self.clear_hazards_at(&pos);
+
+            if let Some(index) = self.food.iter().position(|food| food.position == pos) {
+                self.food.swap_remove(index);
+                food_walled_over = true;
+            }
+            if self.golden_food.as_ref() == Some(&pos) {
+                self.golden_food = None;
+            }
+        }
+
+        if food_walled_over {
+            self.top_up_food();
+        }
+    }
+
+    // whether a wall at `pos` would be under a snake, right in front of one, or would leave a
+    // head with no way through to the tiles further in
+    fn wall_blocks_escape(&self, pos: &Vector) -> bool {
+        // an enemy under it has to move on first too
+        if self.board.is_snake_at(pos) || self.board.is_enemy_at(pos) {
+            return true;
+        }
+
+        let (width, height) = (self.width, self.height);
+        let depth = ring_depth(width, height, pos);
+        let open = |tile: &Vector| tile != pos && !self.board.is_wall_at(tile);
+
+        self.snakes.iter().any(|snake| {
+            let head = snake.head();
+            if self.next_tile(head, &snake.next_direction) == *pos {
+                return true;
+            }
+
+            // a head that's already further in can't be cut off by a wall further out
+            ring_depth(width, height, head) <= depth
+                && !self
+                    .geometry()
+                    .flood_fill(width, height, head, &open)
+                    .iter()
+                    .any(|tile| ring_depth(width, height, tile) > depth)
+        })
+    }
+
+    // In waves mode, counts down the break between waves, and puts the next one down once it's
+    // over.
+    fn run_waves(&mut self) {
+        let Some(wave) = self.wave.as_mut() else {
+            return;
+        };
+        if !wave.is_between() {
+            return;
+        }
+
+        wave.break_left -= 1;
+        if wave.break_left == 0 {
+            let wave = *wave;
+            self.lay_wave(&wave);
+            self.events
+                .push(GameEvent::WaveStarted { wave: wave.number });
+        }
+    }
+
+    // A wave's puddles and enemies. The puddles only go on free tiles away from the heads, and
+    // only where they don't shut any of the board off from the snakes, so whatever food comes
+    // along can be got to. They don't dry up, and stay until the wave's cleared.
+    fn lay_wave(&mut self, wave: &Wave) {
+        let (width, height) = (self.width, self.height);
+        let heads = self
+            .snakes
+            .iter()
+            .map(|snake| snake.head().clone())
+            .collect::<Vec<_>>();
+        // and off the way they're headed, for a few tiles further
+        let ahead = self
+            .snakes
+            .iter()
+            .flat_map(|snake| {
+                let mut pos = snake.head().clone();
+                (0..waves::CLEARANCE * 2)
+                    .map(|_| {
+                        pos = self.next_tile(&pos, &snake.next_direction);
+                        pos.clone()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let tiles = wave
+            .patterns()
+            .iter()
+            .flat_map(|pattern| pattern.tiles(width, height))
+            .collect::<Vec<_>>();
+
+        let count = |reachable: &[bool]| reachable.iter().filter(|reachable| **reachable).count();
+        let mut reachable = self.reachable_tiles();
+
+        for pos in tiles {
+            if !self.free_positions.contains(&pos)
+                || ahead.contains(&pos)
+                || heads
+                    .iter()
+                    .any(|head| self.geometry().distance(head, &pos) <= waves::CLEARANCE)
+            {
+                continue;
+            }
+
+            // anything more than the tile itself going out of reach means it's cut something off
+            self.board.add_hazard(&pos);
+            let now = self.reachable_tiles();
+            let was_reachable = reachable[(pos.1 * width + pos.0) as usize];
+            if count(&now) + (was_reachable as usize) < count(&reachable) {
+                self.board.remove_hazard(&pos);
+                continue;
+            }
+
+            reachable = now;
+            self.free_positions.remove(&pos);
+            self.hazards.push(pos);
+        }
+
+        self.add_enemies(wave.enemies());
+    }
+
+    // Once a wave's quota has been eaten, everything in the way goes, and the break before the
+    // next one starts.
+    fn clear_wave(&mut self) {
+        let Some(wave) = self.wave else {
+            return;
+        };
+
+        for pos in self.hazards.clone() {
+            self.clear_hazards_at(&pos);
+        }
+        for enemy in std::mem::take(&mut self.enemies) {
+            self.board.set_enemy(&enemy.position, false);
+            self.free_positions.insert(enemy.position);
+        }
+
+        self.wave = Some(wave.next());
+        self.events
+            .push(GameEvent::WaveCleared { wave: wave.number });
+    }
+
+    // Where the snakes started this run, or would have, which enemies keep away from. It's
+    // the same for any run on the same board, so it doesn't need keeping.
+    fn start_tiles(&self) -> Vec<Vector> {
+        if let Some(level) = &self.level {
+            return level.snake.clone();
+        }
+
+        let (width, height) = (self.width, self.height);
+        let mut starts = vec![Vector(width - 1, height / 2), Vector(width - 2, height / 2)];
+        if self.versus {
+            let rivals = starts
+                .iter()
+                .map(|pos| geometry::mirror_position(width, height, pos))
+                .collect::<Vec<_>>();
+            starts.extend(rivals);
+        }
+
+        starts
+    }
+
+    // `count` of them, on free tiles away from the start and from wherever the heads have got
+    // to. a crowded board might not fit them all
+    fn add_enemies(&mut self, count: usize) {
+        let starts = self.start_tiles();
+        let heads = self
+            .snakes
+            .iter()
+            .map(|snake| snake.head().clone())
+            .collect::<Vec<_>>();
+
+        for index in 0..count {
+            let options = self
+                .free_positions
+                .iter()
+                .filter(|pos| {
+                    !enemy::near_start(&starts, pos)
+                        && heads
+                            .iter()
+                            .all(|head| self.geometry().distance(head, pos) > waves::CLEARANCE)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if options.is_empty() {
+                return;
+            }
+
+            let position = options[random::get_u16() as usize % options.len()].clone();
+            self.free_positions.remove(&position);
+            self.board.set_enemy(&position, true);
+            self.enemies.push(Enemy {
+                position,
+                behavior: Behavior::for_index(index),
+            });
+        }
+    }
+
+    fn move_enemies(&mut self) {
+        let starts = self.start_tiles();
+        let heads = self
+            .snakes
+            .iter()
+            .map(|snake| snake.head().clone())
+            .collect::<Vec<_>>();
+
+        for index in 0..self.enemies.len() {
+            let from = self.enemies[index].position.clone();
+            let options = self
+                .geometry()
+                .neighbors(self.width, self.height, &from)
+                .into_iter()
+                .filter(|pos| self.free_positions.contains(pos) && !enemy::near_start(&starts, pos))
+                .collect::<Vec<_>>();
+
+            let roll = random::get_u16();
+            let Some(to) = self.enemies[index].step(self.geometry(), &options, &heads, roll) else {
+                continue;
+            };
+
+            self.board.set_enemy(&from, false);
+            self.free_positions.insert(from);
+            self.free_positions.remove(&to);
+            self.board.set_enemy(&to, true);
+            self.enemies[index].position = to;
+        }
+    }
+
+    // the whole puddle goes, even if it's a few stacked up
+    fn clear_hazards_at(&mut self, pos: &Vector) {
+        while self.board.is_hazard_at(pos) {
+            remove_from_vec(&mut self.hazards, pos);
+            self.board.remove_hazard(pos);
+        }
+        self.hazard_spawn_ticks
+            .retain(|(spawn_pos, _)| spawn_pos != pos);
+
+        if !self.board.is_blocked_at(pos) {
+            self.free_positions.insert(pos.clone());
+        }
+    }
+
+    fn expire_hazards(&mut self) {
+        let Some(lifetime) = self.hazard_lifetime else {
+            return;
+        };
+
+        let (expired, remaining) = std::mem::take(&mut self.hazard_spawn_ticks)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(_, spawn_tick)| self.ticks - spawn_tick >= lifetime);
+        self.hazard_spawn_ticks = remaining;
+
+        for (pos, _) in expired {
+            remove_from_vec(&mut self.hazards, &pos);
+            self.board.remove_hazard(&pos);
+
+            // the tail might still be sitting on it, in which case the tile frees up once the
+            // tail moves on
+            if !self.board.is_blocked_at(&pos) {
+                self.free_positions.insert(pos);
+            }
+        }
+    }
+
+    fn expire_food(&mut self) {
+        let now_ms = self.elapsed_ms;
+
+        let (expired, remaining) = std::mem::take(&mut self.food)
+            .into_iter()
+            .partition::<Vec<_>, _>(|food| food.ms_left(now_ms) == Some(0));
+        self.food = remaining;
+
+        self.free_positions
+            .extend(expired.into_iter().map(|food| food.position));
+    }
+
+    fn maybe_add_special_food(&mut self) {
+        // these don't come in twins, so they'd favor whoever happens to be closer
+        if !self.special_food || self.versus {
+            return;
+        }
+
+        let weights = self.spawn_weights();
+        let width = self.width;
+        let longest = self
+            .snakes
+            .iter()
+            .map(|snake| snake.body.len())
+            .max()
+            .unwrap_or(0);
+
+        for (kind, chance) in [
+            (FoodKind::Bonus, 10),
+            (FoodKind::Rotten, 6),
+            (FoodKind::Speed, 12),
+            (FoodKind::Shrink, 8),
+        ] {
+            if self.free_positions.is_empty() || self.food.iter().any(|food| food.kind == kind) {
+                continue;
+            }
+            if kind == FoodKind::Shrink && longest < SHRINK_FROM_LENGTH {
+                continue;
+            }
+
+            if !random::get_u16().is_multiple_of(chance) {
+                continue;
+            }
+
+            // not from the script, so scripted food stays in order
+            let Some(position) = spawner::pick_weighted(&mut self.free_positions, |pos| {
+                weights[(pos.1 * width + pos.0) as usize]
+            }) else {
+                continue;
+            };
+            self.food.push(Food::new(position, kind, self.elapsed_ms));
+        }
+    }
+
+    fn maybe_add_golden_food(&mut self) {
+        // one hydra at a time is plenty
+        if !self.hydra_mode || self.golden_food.is_some() || self.snakes.len() > 1 {
+            return;
+        }
+
+        if self.free_positions.is_empty() || !random::get_u16().is_multiple_of(8) {
+            return;
+        }
+
+        let weights = self.spawn_weights();
+        let width = self.width;
+        self.golden_food = spawner::pick_weighted(&mut self.free_positions, |pos| {
+            weights[(pos.1 * width + pos.0) as usize]
+        });
+    }
+
+    fn split_snake(&mut self, index: usize) {
+        let snake = &mut self.snakes[index];
+        let length = snake.body.len();
+
+        // each half needs a head and a neck to know which way it's facing
+        if length < 4 {
+            return;
+        }
+
+        let (tail, neck) = (&snake.body[length - 1], &snake.body[length - 2]);
+        let mut step = Vector(tail.0 - neck.0, tail.1 - neck.1);
+
+        // across the edge of a wrapping board, the neck is all the way on the other side
+        if self.topology == Topology::Wrap {
+            if step.0.abs() > 1 {
+                step.0 = -step.0.signum();
+            }
+            if step.1.abs() > 1 {
+                step.1 = -step.1.signum();
+            }
+        }
+
+        // a neck on the far side of a portal or ladder doesn't say which way that is
+        let Some(direction) = Direction::from_vector(&step) else {
+            return;
+        };
+
+        // the old tail end becomes the new head
+        let body = snake
+            .body
+            .split_off(length.div_ceil(2))
+            .into_iter()
+            .rev()
+            .collect::<VecDeque<_>>();
+
+        let mut hydra = Snake::new(direction, !snake.mirrored);
+        hydra.body = body;
+        hydra.player = snake.player;
+
+        self.snakes.push(hydra);
+        self.events.push(GameEvent::SnakeSplit);
+    }
+
+    pub fn get_semi_open_tiles(&self) -> Vec<Vector> {
+        // Couldn't figure out how to do this with iterators haha
+        // should compile down about the same
+        let mut vec = vec![];
+
+        let geometry = self.geometry();
+
+        for snake in self.snakes.iter() {
+            for pos in geometry.neighbors(self.width, self.height, snake.head()) {
+                vec.push(pos);
+            }
+        }
+
+        for fruit in self.food.iter() {
+            for pos in geometry.neighbors(self.width, self.height, &fruit.position) {
+                vec.push(pos);
+            }
+        }
+
+        vec
+    }
+
+    // Every tile some snake could get to, one flag each in rows from the top left. Snakes get
+    // out of each other's way sooner or later, so only walls and puddles stand in the way.
+    fn reachable_tiles(&self) -> Vec<bool> {
+        let (width, height) = (self.width, self.height);
+        let index = |pos: &Vector| (pos.1 * width + pos.0) as usize;
+        let mut reachable = vec![false; (width * height) as usize];
+        let mut frontier = vec![];
+
+        for snake in self.snakes.iter() {
+            if !reachable[index(snake.head())] {
+                reachable[index(snake.head())] = true;
+                frontier.push(snake.head().clone());
+            }
+        }
+
+        while let Some(pos) = frontier.pop() {
+            for direction in self.geometry().directions() {
+                let next = self.next_tile(&pos, direction);
+
+                if geometry::is_within_board(width, height, &next)
+                    && !reachable[index(&next)]
+                    && !self.board.is_wall_at(&next)
+                    && !self.board.is_portal_at(&next)
+                    && !self.board.is_hazard_at(&next)
+                {
+                    reachable[index(&next)] = true;
+                    frontier.push(next);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    // the mode's rules, kept away from heads too if that's been asked for
+    pub fn spawn_rules(&self) -> SpawnRules {
+        let rules = SpawnRules::for_mode(self.mode);
+        match self.food_away_from_heads {
+            true => SpawnRules {
+                head_distance: rules.head_distance.max(2),
+                ..rules
+            },
+            false => rules,
+        }
+    }
+
+    // a weight per tile for anything turning up, see `SpawnRules::weights`
+    fn spawn_weights(&self) -> Vec<u32> {
+        self.spawn_rules().weights(&Surroundings {
+            width: self.width,
+            height: self.height,
+            geometry: self.geometry(),
+            board: &self.board,
+            heads: self
+                .snakes
+                .iter()
+                .map(|snake| snake.head().clone())
+                .collect(),
+        })
+    }
+
+    // `spawn_weights`, leaving out anywhere nobody can get to, like `reachable_tiles`. Food
+    // there would leave the run stuck with nothing to eat
+    fn food_weights(&self) -> Vec<u32> {
+        self.spawn_weights()
+            .into_iter()
+            .zip(self.reachable_tiles())
+            .map(|(weight, reachable)| if reachable { weight } else { 0 })
+            .collect()
+    }
+
+    // A puddle on `pos`. One that's there already is topped up instead of getting another on
+    // top, and puddles that are there for good stay that way.
+    fn lay_hazard(&mut self, pos: Vector) {
+        if self.board.is_hazard_at(&pos) {
+            if let Some((_, spawn_tick)) = self
+                .hazard_spawn_ticks
+                .iter_mut()
+                .find(|(spawn_pos, _)| *spawn_pos == pos)
+            {
+                *spawn_tick = self.ticks;
+            }
+            return;
+        }
+
+        self.board.add_hazard(&pos);
+        if self.hazard_lifetime.is_some() {
+            self.hazard_spawn_ticks.push((pos.clone(), self.ticks));
+        }
+        self.hazards.push(pos.clone());
+        self.events.push(GameEvent::HazardSpawned { position: pos });
+    }
+
+    // Brings the normal food out up to `food_count`, returning how much went out. In versus, a
+    // pair still counts as there while either twin is left, so both have to be eaten first.
+    fn top_up_food(&mut self) -> usize {
+        let normal_food = self
+            .food
+            .iter()
+            .filter(|food| food.kind == FoodKind::Normal)
+            .count();
+        let out = if self.versus {
+            normal_food.div_ceil(2)
+        } else {
+            normal_food
+        };
+
+        let missing = self.food_count.target(self.score).saturating_sub(out);
+        self.add_food(missing);
+        missing
+    }
+
+    fn add_food(&mut self, number: usize) {
+        // food doesn't get in anyone's way, so these stay the same for the whole batch
+        let weights = self.food_weights();
+        let width = self.width;
+        let weight = |pos: &Vector| weights[(pos.1 * width + pos.0) as usize];
+        let is_fair = |pos: &Vector| weight(pos) > 0;
+
+        for _i in 0..number {
+            let position = if self.versus {
+                self.take_mirrored_position(is_fair)
+            } else {
+                // with every free tile fair, this picks just like `take_position` would
+                self.spawner
+                    .take_weighted(&mut self.free_positions, weight)
+                    // the snake's shut itself in, so the rest of the board is as good as anywhere
+                    .or_else(|| self.spawner.take_position(&mut self.free_positions))
+            };
+
+            match position {
+                Some(position) => {
+                    self.food
+                        .push(Food::new(position, FoodKind::Normal, self.elapsed_ms))
+                }
+                // it's only over once the last of it can't be replaced
+                None if self.food.iter().any(|food| food.kind == FoodKind::Normal) => break,
+                // there was no "you won" at first, just a kill screen
+                None if self.versus => {
+                    self.end_game("can't believe you made it this far");
+                    break;
+                }
+                None => {
+                    self.end_game(WON_REASON);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Picks a position whose mirror twin is free too, and spawns food on the twin. Returns the
+    // first position, for the caller to spawn food on. Both have to be `fair` if they can.
+    fn take_mirrored_position(&mut self, fair: impl Fn(&Vector) -> bool) -> Option<Vector> {
+        let (width, height) = (self.width, self.height);
+        let free = self.free_positions.clone();
+        let has_twin = |pos: &Vector| {
+            let twin = geometry::mirror_position(width, height, pos);
+            twin == *pos || free.contains(&twin)
+        };
+
+        let position = self
+            .spawner
+            .take_position_where(&mut self.free_positions, |pos| {
+                has_twin(pos) && fair(pos) && fair(&geometry::mirror_position(width, height, pos))
+            })
+            .or_else(|| {
+                self.spawner
+                    .take_position_where(&mut self.free_positions, has_twin)
+            })?;
+
+        // the center tile is its own twin
+        let twin = geometry::mirror_position(width, height, &position);
+        if twin != position {
+            self.free_positions.remove(&twin);
+            self.food
+                .push(Food::new(twin, FoodKind::Normal, self.elapsed_ms));
+        }
+
+        Some(position)
+    }
+
+    // Ends the game for the players in `losers`, along with why. If they're all the same player,
+    // the other one wins.
+    fn end_game_for(&mut self, losers: &[(usize, &'static str)]) {
+        let (player, reason) = losers[0];
+
+        // if both players crash on the same tick, nobody wins
+        if self.versus && !self.coop && losers.iter().all(|(other, _)| *other == player) {
+            self.winner = Some(1 - player);
+        }
+
+        self.end_game(reason);
+    }
+
+    // Everything needed to pick this run up again later, or `None` if there's nothing worth
+    // keeping: the run is over, or it's on a level, which might not be around next time.
+    pub fn to_snapshot(&self) -> Option<Snapshot> {
+        if self.is_game_over() || self.level.is_some() {
+            return None;
+        }
+
+        let snakes = self
+            .snakes
+            .iter()
+            .map(|snake| SnakeSnapshot {
+                body: snake.body.iter().cloned().collect(),
+                direction: snake.direction.clone(),
+                next_direction: snake.next_direction.clone(),
+                mirrored: snake.mirrored,
+                growing: snake.growing,
+                hungry_ticks: snake.hungry_ticks,
+                spent_energy: snake.spent_energy,
+                stunned: snake.stunned,
+                boost_ends_ms: snake.boost.map(|boost| boost.ends_ms),
+                stride: snake.stride,
+                previous_tail: snake.previous_tail.clone(),
+                player: snake.player,
+            })
+            .collect();
+
+        Some(Snapshot {
+            fingerprint: Fingerprint::of(self),
+            mode: self.mode,
+            time_limit: self.time_limit,
+            hazard_lifetime: self.hazard_lifetime,
+            double_hazards: self.double_hazards,
+            rules: self.rules,
+            hazard_stun: self.hazard_stun,
+            grace_ticks: self.grace_ticks,
+            win_percent: self.win_percent,
+            combos: self.combos,
+            fresh_food: self.fresh_food,
+            starve_ticks: self.starve_ticks,
+            energy_drain: self.energy_drain,
+            special_food: self.special_food,
+            food_away_from_heads: self.food_away_from_heads,
+            food_count: self.food_count,
+            hydra_mode: self.hydra_mode,
+            versus: self.versus,
+            coop: self.coop,
+            countdown_ticks: self.countdown_ticks,
+            start_length: self.start_length,
+            growth: self.growth,
+            snakes,
+            hazards: self.hazards.clone(),
+            hazard_spawn_ticks: self.hazard_spawn_ticks.clone(),
+            walls: self.walls.clone(),
+            pending_walls: self.pending_walls.clone(),
+            wave: self.wave,
+            enemy_count: self.enemy_count,
+            enemies: self.enemies.clone(),
+            food: self.food.clone(),
+            golden_food: self.golden_food.clone(),
+            free_positions: self.free_positions.iter().cloned().collect(),
+            countdown_left: self.countdown_left,
+            ticks: self.ticks,
+            seed: self.seed,
+            random: random::state(),
+            rejected_inputs: self.rejected_inputs,
+            tampered: self.tampered,
+            combo: self.combo,
+            last_food_ms: self.last_food_ms,
+            fruit_eaten: self.fruit_eaten,
+            tick_ms: self.tick_ms,
+            elapsed_ms: self.elapsed_ms,
+            scores: self.scores.clone(),
+            high_score: self.high_score,
+            high_score_display: self.high_score_display,
+            max_score: self.max_score,
+        })
+    }
+
+    // Carries on from a snapshot, random numbers and all, so the rest of the run plays out just
+    // like it would have. Snapshots from other rules, or ones that don't add up, are refused.
+    pub fn from_snapshot(snapshot: &Snapshot) -> Result<SnakeGame, String> {
+        let Fingerprint {
+            width,
+            height,
+            topology,
+            grid,
+            ..
+        } = snapshot.fingerprint;
+
+        let current = Fingerprint {
+            grid,
+            ..Fingerprint::new(width, height, topology)
+        };
+        if let Compatibility::Incompatible(why) = snapshot.fingerprint.check(&current) {
+            return Err(why);
+        }
+
+        check_board(width, height)?;
+
+        let players = if snapshot.versus { 2 } else { 1 };
+        if snapshot.scores.len() != players {
+            return Err(format!("there should be {players} scores"));
+        }
+
+        if snapshot.snakes.is_empty()
+            || snapshot
+                .snakes
+                .iter()
+                .any(|snake| snake.body.is_empty() || snake.player >= players)
+        {
+            return Err("every snake needs a player and a head".to_string());
+        }
+
+        if snapshot.mode.has_waves() != snapshot.wave.is_some() {
+            return Err("only waves mode has a wave on".to_string());
+        }
+
+        if !(1..=MAX_COMBO).contains(&snapshot.combo) {
+            return Err(format!("a combo of {} can't happen", snapshot.combo));
+        }
+
+        let mut game = SnakeGame {
+            width,
+            height,
+            topology,
+            grid,
+            snakes: snapshot
+                .snakes
+                .iter()
+                .map(|snake| Snake {
+                    body: snake.body.iter().cloned().collect(),
+                    direction: snake.direction.clone(),
+                    next_direction: snake.next_direction.clone(),
+                    mirrored: snake.mirrored,
+                    growing: snake.growing,
+                    hungry_ticks: snake.hungry_ticks,
+                    spent_energy: snake.spent_energy,
+                    stunned: snake.stunned,
+                    boost: snake
+                        .boost_ends_ms
+                        .map(|ends_ms| effects::Timer { ends_ms }),
+                    stride: snake.stride,
+                    previous_tail: snake.previous_tail.clone(),
+                    player: snake.player,
+                })
+                .collect(),
+            hazards: snapshot.hazards.clone(),
+            hazard_spawn_ticks: snapshot.hazard_spawn_ticks.clone(),
+            walls: snapshot.walls.clone(),
+            pending_walls: snapshot.pending_walls.clone(),
+            wave: snapshot.wave,
+            enemy_count: snapshot.enemy_count,
+            enemies: snapshot.enemies.clone(),
+            mode: snapshot.mode,
+            time_limit: snapshot.time_limit,
+            hazard_lifetime: snapshot.hazard_lifetime,
+            double_hazards: snapshot.double_hazards,
+            rules: snapshot.rules,
+            hazard_stun: snapshot.hazard_stun,
+            grace_ticks: snapshot.grace_ticks,
+            win_percent: snapshot.win_percent,
+            combos: snapshot.combos,
+            fresh_food: snapshot.fresh_food,
+            combo: snapshot.combo,
+            last_food_ms: snapshot.last_food_ms,
+            fruit_eaten: snapshot.fruit_eaten,
+            tick_ms: snapshot.tick_ms,
+            elapsed_ms: snapshot.elapsed_ms,
+            starve_ticks: snapshot.starve_ticks,
+            energy_drain: snapshot.energy_drain,
+            countdown_ticks: snapshot.countdown_ticks,
+            countdown_left: snapshot.countdown_left,
+            start_length: snapshot.start_length,
+            growth: snapshot.growth,
+            ticks: snapshot.ticks,
+            seed: snapshot.seed,
+            rejected_inputs: snapshot.rejected_inputs,
+            tampered: snapshot.tampered,
+            food: snapshot.food.clone(),
+            special_food: snapshot.special_food,
+            food_away_from_heads: snapshot.food_away_from_heads,
+            food_count: snapshot.food_count,
+            hydra_mode: snapshot.hydra_mode,
+            versus: snapshot.versus,
+            coop: snapshot.coop,
+            golden_food: snapshot.golden_food.clone(),
+            score: snapshot.scores.iter().sum(),
+            scores: snapshot.scores.clone(),
+            high_score: snapshot.high_score,
+            high_score_display: snapshot.high_score_display,
+            max_score: snapshot.max_score,
+            ..SnakeGame::default()
+        };
+
+        let on_board = game
+            .snakes
+            .iter()
+            .flat_map(|snake| snake.body.iter())
+            .chain(game.hazards.iter())
+            .chain(game.walls.iter())
+            .chain(game.pending_walls.iter())
+            .chain(game.food.iter().map(|food| &food.position))
+            .chain(game.golden_food.iter())
+            .chain(game.enemies.iter().map(|enemy| &enemy.position))
+            .all(|pos| geometry::is_within_board(width, height, pos));
+        if !on_board {
+            return Err("something's off the board".to_string());
+        }
+
+        game.rebuild_board();
+
+        // enemies only ever stand on tiles that would otherwise be free
+        let mut enemy_tiles = HashSet::new();
+        let enemies_alone = game.enemies.iter().all(|enemy| {
+            let pos = &enemy.position;
+            enemy_tiles.insert(pos)
+                && !game.board.is_snake_at(pos)
+                && !game.board.is_hazard_at(pos)
+                && !game.board.is_wall_at(pos)
+                && !game.food.iter().any(|food| food.position == *pos)
+                && game.golden_food.as_ref() != Some(pos)
+        });
+        if !enemies_alone {
+            return Err("an enemy is standing on something".to_string());
+        }
+
+        // the free tiles have to be the ones the board says are free, just maybe in another order
+        let free = snapshot.free_positions.iter().collect::<HashSet<_>>();
+        if free.len() != snapshot.free_positions.len()
+            || free.len() != game.free_positions.len()
+            || !game.free_positions.iter().all(|pos| free.contains(pos))
+        {
+            return Err("the free tiles don't match the board".to_string());
+        }
+
+        game.free_positions.reset(width, height);
+        game.free_positions
+            .extend(snapshot.free_positions.iter().cloned());
+
+        random::restore(snapshot.random);
+
+        Ok(game)
+    }
+
+    // Puts out more food of a kind, wherever the spawner would, for trying things out from the
+    // dev console. Returns how many fit on the board.
+    pub fn spawn_food(&mut self, kind: FoodKind, count: usize) -> usize {
+        let mut spawned = 0;
+
+        while spawned < count {
+            let Some(position) = self.spawner.take_position(&mut self.free_positions) else {
+                break;
+            };
+
+            self.food.push(Food::new(position, kind, self.elapsed_ms));
+            spawned += 1;
+        }
+
+        spawned
+    }
+
+    // Leaves a hazard on a free tile, like eating would, returning whether the tile was free.
+    // For the dev console.
+    pub fn spawn_hazard(&mut self, pos: Vector) -> bool {
+        if !self.free_positions.remove(&pos) {
+            return false;
+        }
+
+        self.lay_hazard(pos);
+        true
+    }
+
+    // Moves a whole snake so its head lands on `head`, keeping its shape. Everything it would
+    // land on has to be empty.
+    pub fn teleport(&mut self, index: usize, head: Vector) -> Result<(), String> {
+        let snake = self.snakes.get(index).ok_or("there's no snake like that")?;
+        let offset = Vector(head.0 - snake.head().0, head.1 - snake.head().1);
+
+        let body = snake
+            .body
+            .iter()
+            .map(|pos| &offset + pos)
+            .collect::<VecDeque<_>>();
+
+        for pos in body.iter() {
+            let taken = !geometry::is_within_board(self.width, self.height, pos)
+                || self.board.is_wall_at(pos)
+                || self.board.is_portal_at(pos)
+                || self.board.is_hazard_at(pos)
+                || self.board.is_enemy_at(pos)
+                || self.food.iter().any(|food| food.position == *pos)
+                || self.golden_food.as_ref() == Some(pos)
+                || self
+                    .snakes
+                    .iter()
+                    .enumerate()
+                    .any(|(other, snake)| other != index && snake.body.contains(pos));
+
+            if taken {
+                return Err(format!("{},{} isn't free", pos.0, pos.1));
+            }
+        }
+
+        while !self.snakes[index].body.is_empty() {
+            self.pop_snake_tail(index);
+        }
+        for pos in body.into_iter().rev() {
+            self.push_snake_head(index, pos);
+        }
+        self.snakes[index].previous_tail = None;
+
+        Ok(())
+    }
+
+    // for the dev console, which can also jump straight to the end
+    pub fn skip_countdown(&mut self) {
+        self.countdown_left = 0;
+    }
+
+    pub fn end_now(&mut self, reason: &'static str) {
+        if !self.is_game_over() {
+            self.end_game(reason);
+        }
+    }
+
+    // in versus, whoever scored more when the clock ran out wins
+    fn end_game_on_time(&mut self) {
+        self.pick_winner_by_score();
+        self.end_game("time's up");
+    }
+
+    // a race alone is won by finishing it, and in versus by whoever ate more on the way
+    fn finish_race(&mut self) {
+        if self.versus && !self.coop {
+            self.pick_winner_by_score();
+            self.end_game("that's the race");
+        } else {
+            self.end_game(WON_REASON);
+        }
+    }
+
+    fn pick_winner_by_score(&mut self) {
+        if self.versus && !self.coop {
+            self.winner = match self.scores[0].cmp(&self.scores[1]) {
+                std::cmp::Ordering::Greater => Some(0),
+                std::cmp::Ordering::Less => Some(1),
+                std::cmp::Ordering::Equal => None,
+            };
+        }
+    }
+
+    fn end_game(&mut self, message: &'static str) {
+        self.game_over_reason = Some(message);
+
+        // versus scores aren't comparable to solo ones
+        if !self.versus && !self.tampered && self.score >= self.high_score {
+            self.high_score = self.score;
+        }
+
+        self.events.push(GameEvent::GameOver { reason: message });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effects::Timer;
+    use crate::event::DeathCause;
+    use crate::food::FOOD_SCORE_STEP;
+    use crate::food::FRESH_STEP_MS;
+
+    use proptest::prelude::*;
+
+    #[test]
+    fn it_works() {
+        let mut game = SnakeGame::new(5, 5);
+
+        //~ dbg!(&game);
+
+        for _i in 0..4 {
+            game.tick();
+            //~ dbg!(&game);
+        }
+
+        assert_eq!(game.game_over_reason, Some("avoid walls"));
+        assert!(game.take_events().contains(&GameEvent::GameOver {
+            reason: "avoid walls"
+        }));
+        assert!(game.take_events().is_empty());
+    }
+
+    #[test]
+    fn resize_restarts() {
+        // so the food doesn't turn up right in front of the snake
+        crate::random::seed([1, 1]);
+        let mut game = SnakeGame::new(21, 15);
+        game.load_level(&Level::parse(crate::level::BUILTIN_LEVELS[1]).unwrap());
+        game.resize(8, 6);
+        game.tick();
+
+        assert!(game.walls.is_empty());
+        assert_eq!(game.food.len(), 1);
+        assert_eq!(*game.snakes[0].head(), Vector(5, 3));
+        assert_eq!(game.free_positions.len(), 8 * 6 - 3);
+    }
+
+    #[test]
+    fn wrapping_topology() {
+        let mut game = SnakeGame::new(5, 5);
+        game.topology = Topology::Wrap;
+        game.food.clear();
+        game.rebuild_board();
+
+        for _i in 0..4 {
+            game.tick();
+        }
+
+        assert!(!game.is_game_over());
+        assert_eq!(*game.snakes[0].head(), Vector(4, 2));
+    }
+
+    #[test]
+    fn board_limits() {
+        assert!(SnakeGame::try_new(5, 3).is_ok());
+        assert_eq!(
+            SnakeGame::try_new(4, 3).err(),
+            Some("a 4x3 board is too small, it has to be at least 5x3".to_string())
+        );
+        assert!(SnakeGame::try_new(61, 10).is_err());
+        assert!(check_board(MAX_WIDTH, MAX_HEIGHT).is_ok());
+
+        let mut game = SnakeGame::new(9, 7);
+        assert!(game.try_resize(9, 2).is_err());
+        assert_eq!((game.width, game.height), (9, 7));
+
+        let snake = vec![Vector(2, 1), Vector(3, 1)];
+        let mut level = Level {
+            name: "Packed".to_string(),
+            width: 5,
+            height: 3,
+            walls: (0..5)
+                .flat_map(|x| (0..3).map(move |y| Vector(x, y)))
+                .filter(|pos| !snake.contains(pos))
+                .collect(),
+            hazards: vec![],
+            snake: snake.clone(),
+            direction: Direction::Left,
+            food: vec![],
+            portals: vec![],
+            dividers: vec![],
+            ladders: vec![],
+        };
+        assert!(game.try_load_level(&level).is_err());
+        assert!(game.level().is_none());
+
+        level.walls.pop();
+        assert!(game.try_load_level(&level).is_ok());
+        assert_eq!(game.level().unwrap().name, "Packed");
+    }
+
+    #[test]
+    fn winning() {
+        let mut game = SnakeGame::new(5, 3);
+        assert_eq!(game.state(), GameState::Playing);
+        // two tiles out of fifteen
+        assert_eq!(game.fill_percent(), 13);
+
+        game.win_percent = Some(50);
+        game.tick();
+        assert_eq!(game.state(), GameState::Playing);
+
+        game.win_percent = Some(game.fill_percent());
+        game.tick();
+        assert_eq!(game.state(), GameState::Won);
+        assert!(game.is_game_over());
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::GameOver { reason: WON_REASON }));
+
+        // with food on every free tile, eating some of it just leaves the rest
+        let mut game = SnakeGame::new(5, 3);
+        game.spawn_food(FoodKind::Normal, 15);
+        assert_eq!(game.free_positions().len(), 0);
+        game.tick();
+        assert_eq!(game.state(), GameState::Playing);
+
+        // but eating the last of it leaves nowhere for the next one
+        let head = game.snakes[0].head().clone();
+        game.food
+            .retain(|food| food.position == Vector(head.0 - 1, head.1));
+        game.tick();
+        assert_eq!(game.state(), GameState::Won);
+
+        game.restart();
+        game.tick();
+        game.tick();
+        game.tick();
+        game.tick();
+        assert_eq!(game.state(), GameState::Lost);
+    }
+
+    #[test]
+    fn grace_ticks() {
+        let mut game = SnakeGame::new(21, 15);
+        game.grace_ticks = 10;
+        game.food.clear();
+        game.rebuild_board();
+
+        game.change_direction(Direction::Up);
+        while game.snakes[0].head().1 > 0 {
+            game.tick();
+        }
+        game.take_events();
+        let body = game.snakes[0].body.clone();
+
+        // the wall turns it around where it is
+        game.tick();
+        assert!(!game.is_game_over());
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::SnakeBounced { player: 0 }));
+        assert_eq!(
+            game.snakes[0].body,
+            body.iter().rev().cloned().collect::<VecDeque<_>>()
+        );
+
+        // and it heads off the way the tail was going
+        let tail = &body[body.len() - 1];
+        let before_tail = &body[body.len() - 2];
+        game.tick();
+        assert_eq!(
+            *game.snakes[0].head(),
+            Vector(tail.0 * 2 - before_tail.0, tail.1 * 2 - before_tail.1)
+        );
+
+        // once it's over, walls are walls again
+        game.grace_ticks = 3;
+        game.restart();
+        game.food.clear();
+        game.rebuild_board();
+        game.change_direction(Direction::Up);
+        while !game.is_game_over() {
+            game.tick();
+        }
+        assert_eq!(game.game_over_reason, Some("avoid walls"));
+        assert!(game.ticks > 3);
+    }
+
+    #[test]
+    fn fair_food() {
+        // the top left corner is walled off
+        let mut game = SnakeGame::new(5, 3);
+        game.walls = vec![Vector(1, 0), Vector(0, 1), Vector(1, 1)];
+        let head = game.snakes[0].head().clone();
+
+        for food_away_from_heads in [false, true] {
+            game.food_away_from_heads = food_away_from_heads;
+
+            for _ in 0..50 {
+                game.food.clear();
+                game.rebuild_board();
+                game.add_food(1);
+
+                let food = &game.food[0].position;
+                assert_ne!(*food, Vector(0, 0));
+                if food_away_from_heads {
+                    assert!(geometry::manhattan_distance(food, &head) > 1);
+                }
+            }
+        }
+
+        // a snake that's shut itself in still gets fed, just out of reach
+        game.walls = vec![
+            Vector(2, 1),
+            Vector(3, 0),
+            Vector(4, 0),
+            Vector(3, 2),
+            Vector(4, 2),
+        ];
+        game.food_away_from_heads = false;
+        game.food.clear();
+        game.rebuild_board();
+        assert_eq!(
+            game.reachable_tiles().iter().filter(|tile| **tile).count(),
+            2
+        );
+        game.add_food(1);
+        assert_eq!(game.food.len(), 1);
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn mazes() {
+        let mut game = SnakeGame::new(21, 15);
+        game.set_mode(GameMode::Maze);
+        game.restart_with_seed([3, 4]);
+        let walls = game.walls.clone();
+        assert!(!walls.is_empty());
+        assert!(walls.iter().all(|pos| !game.free_positions.contains(pos)));
+        assert!(game.food.iter().all(|food| !walls.contains(&food.position)));
+
+        // the same seed plays back the same maze, and another one doesn't
+        game.restart_with_seed([3, 4]);
+        assert_eq!(game.walls, walls);
+        game.restart_with_seed([5, 6]);
+        assert_ne!(game.walls, walls);
+
+        // back to an empty board in other modes
+        game.set_mode(GameMode::Classic);
+        assert!(game.walls.is_empty());
+
+        game.set_mode(GameMode::Maze);
+        game.set_versus(true);
+        let rival = game.snakes[1].head().clone();
+        assert!(!game.is_wall_at(&rival));
+    }
+
+    #[test]
+    fn versus_full_board() {
+        let mut game = SnakeGame::new(21, 15);
+        game.set_versus(true);
+        game.food.clear();
+        game.free_positions.reset(21, 15);
+        game.take_events();
+
+        // the board only fills up the once, however much food was still to come
+        game.add_food(3);
+        let game_overs = game
+            .take_events()
+            .into_iter()
+            .filter(|event| matches!(event, GameEvent::GameOver { .. }))
+            .count();
+        assert_eq!(game_overs, 1);
+        assert_eq!(
+            game.game_over_reason,
+            Some("can't believe you made it this far")
+        );
+    }
+
+    #[test]
+    fn daily() {
+        let mut game = SnakeGame::new(21, 15);
+        game.daily_seed = Some([7, 8]);
+        game.set_mode(GameMode::Daily);
+        assert_eq!(game.seed, [7, 8]);
+        let walls = game.walls.clone();
+        let food = game.food.clone();
+        assert!(!walls.is_empty());
+
+        // every run that day is the same board
+        game.restart();
+        assert_eq!(game.seed, [7, 8]);
+        assert_eq!(game.walls, walls);
+        assert!(game.food == food);
+
+        // and the other modes still get something new
+        game.set_mode(GameMode::Maze);
+        game.restart();
+        assert_ne!(game.seed, [7, 8]);
+    }
+
+    #[test]
+    fn closing_in() {
+        let mut game = SnakeGame::new(9, 7);
+        game.set_mode(GameMode::ClosingIn);
+        game.food.clear();
+        game.rebuild_board();
+
+        // the edge goes first
+        game.ticks = CLOSE_IN_TICKS - 1;
+        game.tick();
+        assert_eq!(game.walls.len(), 9 * 2 + 5 * 2);
+        assert!(game.pending_walls.is_empty());
+        assert!(game.take_events().contains(&GameEvent::WallsClosingIn));
+        assert!(game
+            .walls
+            .iter()
+            .all(|pos| !game.free_positions.contains(pos)));
+
+        game.change_direction(Direction::Up);
+        game.tick();
+        game.tick();
+        game.ticks = CLOSE_IN_TICKS * 2 - 1;
+        game.change_direction(Direction::Left);
+        game.tick();
+        assert_eq!(
+            game.snakes[0].body,
+            VecDeque::from([Vector(5, 1), Vector(6, 1)])
+        );
+
+        // the next ring waits on the snake, and the tile in front of it
+        let waiting = [Vector(4, 1), Vector(5, 1), Vector(6, 1)];
+        assert_eq!(game.pending_walls.len(), waiting.len());
+        assert!(waiting.iter().all(|pos| game.pending_walls.contains(pos)));
+
+        game.change_direction(Direction::Down);
+        game.tick();
+        assert_eq!(game.pending_walls, vec![Vector(5, 1)]);
+        game.tick();
+        assert!(game.pending_walls.is_empty());
+        assert_eq!(game.walls.len(), 28 + 7 * 2 + 3 * 2);
+
+        let restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        assert_eq!(restored.walls, game.walls);
+
+        // and with just the middle left, that's it
+        game.ticks = CLOSE_IN_TICKS * 3 - 1;
+        game.take_events();
+        game.tick();
+        assert!(!game.take_events().contains(&GameEvent::WallsClosingIn));
+    }
+
+    #[test]
+    fn hazard_stun() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hazard_stun = true;
+        game.restart();
+        game.food.clear();
+        game.hazards = vec![Vector(18, 7), Vector(18, 7), Vector(19, 6)];
+        game.rebuild_board();
+
+        // the first one stuns, and the puddle goes away
+        game.tick();
+        assert!(!game.is_game_over());
+        assert!(game.snakes[0].is_stunned());
+        assert_eq!(*game.snakes[0].head(), Vector(19, 7));
+        assert_eq!(game.hazards, vec![Vector(19, 6)]);
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::SnakeStunned { player: 0 }));
+
+        game.tick();
+        assert_eq!(*game.snakes[0].head(), Vector(19, 7));
+
+        // getting going again, it carries on the way it was headed
+        game.tick();
+        assert_eq!(*game.snakes[0].head(), Vector(18, 7));
+        assert!(!game.snakes[0].is_stunned());
+
+        // turning into another one as it gets going is the end of it
+        game.restart();
+        game.food.clear();
+        game.hazards = vec![Vector(18, 7), Vector(19, 6)];
+        game.rebuild_board();
+
+        game.tick();
+        game.tick();
+        game.change_direction(Direction::Up);
+        game.tick();
+        assert_eq!(game.game_over_reason, Some("don't slip on the leftovers"));
+    }
+
+    #[test]
+    fn tick_outcome() {
+        let mut game = SnakeGame::new(21, 15);
+        let Vector(x, y) = game.snakes[0].head().clone();
+        game.food = vec![Food::new(Vector(x - 1, y), FoodKind::Normal, 0)];
+        game.rebuild_board();
+
+        let outcome = game.tick();
+        assert!(outcome.ticked);
+        assert_eq!(outcome.heads, vec![Vector(x - 1, y)]);
+        assert_eq!(outcome.eaten, vec![FoodKind::Normal]);
+        // what came up in its place
+        assert_eq!(
+            outcome.spawned_food,
+            game.food
+                .iter()
+                .map(|food| food.position.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!((outcome.game_over, outcome.died), (None, None));
+        // the events are all still there too
+        assert!(game
+            .take_events()
+            .iter()
+            .any(|event| matches!(event, GameEvent::FoodEaten { .. })));
+
+        game.change_direction(Direction::Up);
+        let outcome = (0..y + 1)
+            .map(|_| game.tick())
+            .find(|outcome| outcome.game_over.is_some())
+            .unwrap();
+        assert_eq!(outcome.game_over, Some("avoid walls"));
+        assert_eq!(outcome.died, Some(DeathCause::Wall));
+        assert!(outcome.eaten.is_empty());
+
+        // once it's over, ticking does nothing
+        assert!(!game.tick().ticked);
+    }
+
+    #[test]
+    fn near_miss() {
+        let mut game = SnakeGame::new(21, 15);
+        game.food.clear();
+        assert_eq!(game.snakes[0].direction, Direction::Left);
+        let Vector(x, y) = game.snakes[0].head().clone();
+        game.hazards = vec![Vector(x - 2, y)];
+        game.rebuild_board();
+
+        let near_miss = |game: &mut SnakeGame| {
+            game.take_events()
+                .contains(&GameEvent::NearMiss { player: 0 })
+        };
+        game.tick();
+        assert!(!near_miss(&mut game));
+
+        // turning away from it at the last moment
+        game.change_direction(Direction::Up);
+        game.tick();
+        assert!(near_miss(&mut game));
+        assert_eq!(*game.snakes[0].head(), Vector(x - 1, y - 1));
+
+        // and turning with nothing in the way isn't one
+        game.change_direction(Direction::Left);
+        game.tick();
+        assert!(!near_miss(&mut game));
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn builder() {
+        let game = SnakeGame::builder()
+            .size(15, 11)
+            .seed([1, 2])
+            .mode(GameMode::Zen)
+            .initial_snake_length(4)
+            .food_count(FoodCount::Fixed(3))
+            .build()
+            .unwrap();
+
+        assert_eq!((game.width, game.height), (15, 11));
+        assert_eq!(game.seed, [1, 2]);
+        assert_eq!(game.mode(), GameMode::Zen);
+        assert_eq!(game.food.len(), 3);
+        // from the right hand edge, head first
+        assert_eq!(
+            game.snakes[0].body,
+            VecDeque::from([Vector(11, 5), Vector(12, 5), Vector(13, 5), Vector(14, 5)])
+        );
+        assert_eq!(game.max_score, 15 * 11 - 4);
+
+        // the same seed is the same game
+        let again = SnakeGame::builder()
+            .size(15, 11)
+            .seed([1, 2])
+            .mode(GameMode::Zen)
+            .initial_snake_length(4)
+            .food_count(FoodCount::Fixed(3))
+            .build()
+            .unwrap();
+        assert!(again.food == game.food);
+
+        // and it lasts through a restart, and being saved
+        let mut restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        restored.restart();
+        assert_eq!(restored.snakes[0].body.len(), 4);
+
+        assert!(SnakeGame::builder().size(3, 3).build().is_err());
+        assert!(SnakeGame::builder()
+            .initial_snake_length(20)
+            .build()
+            .is_err());
+        assert!(SnakeGame::builder()
+            .food_count(FoodCount::Fixed(0))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn waves() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hazard_lifetime = Some(50);
+        game.set_mode(GameMode::Waves);
+
+        // the first wave's down from the start, out of the way and for good
+        let wave = game.wave().unwrap();
+        assert_eq!((wave.number, wave.is_between()), (1, false));
+        assert!(!game.hazards.is_empty());
+        assert!(game.hazards.iter().all(|pos| {
+            !game.free_positions.contains(pos)
+                && game.hazard_ticks_left(pos).is_none()
+                && game.geometry().distance(pos, &Vector(19, 7)) > waves::CLEARANCE
+        }));
+        // nor along the row the snake's heading down
+        assert!(!game.hazards.contains(&Vector(14, 7)));
+        assert!(game.enemies.is_empty());
+
+        // the last fruit of the quota clears everything, its own puddle too
+        game.wave.as_mut().unwrap().eaten = 2;
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Normal, 0),
+            Food::new(Vector(1, 1), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+        game.take_events();
+        game.tick();
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::WaveCleared { wave: 1 }));
+        assert!(game.hazards.is_empty());
+        let wave = game.wave().unwrap();
+        // the break starts counting down on the same tick
+        assert_eq!((wave.number, wave.break_left), (2, waves::BREAK_TICKS - 1));
+
+        // and after the break, the next one
+        game.wave.as_mut().unwrap().break_left = 1;
+        game.change_direction(Direction::Up);
+        game.tick();
+        assert!(game
+            .take_events()
+            .contains(&GameEvent::WaveStarted { wave: 2 }));
+        assert!(!game.hazards.is_empty());
+        assert_eq!(game.enemies.len(), 1);
+
+        let restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        assert_eq!(restored.wave(), game.wave());
+        assert_eq!(restored.hazards, game.hazards);
+    }
+
+    #[test]
+    fn race() {
+        let mut game = SnakeGame::new(21, 15);
+        game.set_mode(GameMode::Race { fruit: 15 });
+        assert_eq!(game.fruit_left(), Some(15));
+
+        // the last fruit wins it on the tick it's eaten, and only fruit counts
+        game.fruit_eaten = 14;
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Rotten, 0),
+            Food::new(Vector(17, 7), FoodKind::Normal, 0),
+            Food::new(Vector(1, 1), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+        game.tick();
+        assert_eq!((game.fruit_left(), game.race_ms()), (Some(1), None));
+
+        let restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        assert_eq!(restored.fruit_left(), Some(1));
+
+        game.tick();
+        assert_eq!(game.state(), GameState::Won);
+        assert_eq!(game.race_ms(), Some(2 * game.tick_ms));
+
+        game.restart();
+        assert_eq!(game.fruit_left(), Some(15));
+        game.set_mode(GameMode::Classic);
+        assert_eq!(game.fruit_left(), None);
+    }
+
+    #[test]
+    fn speed_boost() {
+        let mut game = SnakeGame::new(21, 15);
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Speed, 0),
+            Food::new(Vector(16, 7), FoodKind::Bonus, 0),
+            // so nothing new turns up in the way
+            Food::new(Vector(1, 1), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        game.tick();
+        assert!(game.snakes[0].is_boosted());
+        assert_eq!(*game.snakes[0].head(), Vector(18, 7));
+
+        // a tile and a half a tick, so one and then two
+        game.tick();
+        assert_eq!(*game.snakes[0].head(), Vector(17, 7));
+        game.tick();
+        assert_eq!(*game.snakes[0].head(), Vector(15, 7));
+        // eating what it went over on the way
+        assert_eq!(game.score, 1 + 3);
+        assert_eq!(game.snakes[0].body.len(), 4);
+
+        let restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        assert!(restored.snakes[0].is_boosted());
+        assert_eq!(restored.snakes[0].stride, 0);
+
+        // and back to the usual speed once it wears off
+        let mut snake = Snake {
+            boost: Some(effects::Timer::new(0, 300)),
+            ..Snake::default()
+        };
+        assert_eq!(snake.strides(100), 1);
+        assert_eq!(snake.strides(200), 2);
+        assert_eq!(snake.strides(250), 1);
+        assert_eq!(snake.strides(300), 1);
+        assert!(!snake.is_boosted());
+        assert_eq!(snake.stride, 0);
+    }
+
+    #[test]
+    fn trail() {
+        let mut game = SnakeGame::new(21, 15);
+        game.food = vec![Food::new(Vector(1, 1), FoodKind::Normal, 0)];
+        game.rebuild_board();
+        assert_eq!(game.trail().count(), 0);
+
+        let tail = game.snakes[0].tail().clone();
+        game.tick();
+        assert_eq!(game.trail().collect::<Vec<_>>(), vec![(&tail, 0)]);
+
+        // the oldest ages out as newer ones come in behind it
+        for _ in 0..TRAIL_TICKS {
+            game.tick();
+        }
+        let ages = game.trail().map(|(_, age)| age).collect::<Vec<_>>();
+        assert_eq!(ages, (0..TRAIL_TICKS).rev().collect::<Vec<_>>());
+        assert!(game.trail().all(|(pos, _)| *pos != tail));
+
+        game.restart();
+        assert_eq!(game.trail().count(), 0);
+    }
+
+    #[test]
+    fn shrink_berries() {
+        let mut game = SnakeGame::new(21, 15);
+        game.special_food = true;
+        // a snake that can always shrink back down can always eat more
+        assert_eq!(game.possible_score(), None);
+        // a short snake has no use for them
+        for _ in 0..100 {
+            game.maybe_add_special_food();
+        }
+        assert!(game.food.iter().all(|food| food.kind != FoodKind::Shrink));
+
+        // heading left along the middle, with a puddle left on part of the tail
+        game.snakes[0].body = (11..19).map(|x| Vector(x, 7)).collect();
+        game.hazards = vec![Vector(17, 7)];
+        game.food = vec![
+            Food::new(Vector(10, 7), FoodKind::Shrink, 0),
+            Food::new(Vector(1, 1), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        game.tick();
+        assert!(!game.is_game_over());
+        assert_eq!(game.score, 0);
+        // three off, and the tail moving up as usual
+        assert_eq!(game.snakes[0].body.len(), 8 + 1 - SHRINK_TILES - 1);
+        assert_eq!(*game.snakes[0].tail(), Vector(14, 7));
+        assert!([15, 16, 18]
+            .iter()
+            .all(|x| game.free_positions.contains(&Vector(*x, 7))));
+        assert!(!game.free_positions.contains(&Vector(17, 7)));
+
+        // and never down to less than a head and a tail
+        game.food.push(Food::new(Vector(9, 7), FoodKind::Shrink, 0));
+        game.rebuild_board();
+        game.tick();
+        game.food.push(Food::new(Vector(8, 7), FoodKind::Shrink, 0));
+        game.rebuild_board();
+        game.tick();
+        assert_eq!(game.snakes[0].body.len(), START_LENGTH);
+    }
+
+    #[test]
+    fn combos() {
+        let mut game = SnakeGame::new(21, 15);
+        game.combos = true;
+        game.restart();
+        assert_eq!(game.max_score, 156 * MAX_COMBO);
+
+        // bonus food, so nothing else spawns in the way
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Bonus, 0),
+            Food::new(Vector(17, 7), FoodKind::Bonus, 0),
+            Food::new(Vector(1, 7), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        game.tick();
+        game.tick();
+        assert_eq!(game.score, 3 + 3 * 2);
+        assert_eq!(game.combo, 2);
+        assert!(game.take_events().contains(&GameEvent::FoodEaten {
+            position: Vector(17, 7),
+            kind: FoodKind::Bonus,
+            multiplier: 2,
+            points: 6,
+        }));
+
+        // too slow for the last one
+        for _ in 0..16 {
+            game.tick();
+        }
+        assert_eq!(*game.snakes[0].head(), Vector(1, 7));
+        assert_eq!(game.score, 3 + 3 * 2 + 1);
+        assert_eq!(game.combo, 1);
+
+        // and none of it in versus
+        game.set_versus(true);
+        assert!(!game.has_combos());
+    }
+
+    #[test]
+    fn fresh_food() {
+        let mut game = SnakeGame::new(21, 15);
+        game.fresh_food = true;
+        game.restart();
+        assert_eq!(game.max_score, 156 * (1 + MAX_FRESH_BONUS));
+
+        // a second a tick, so it goes off quickly
+        game.tick_ms = 1000;
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Normal, 0),
+            // bonus food would be gone by the time it's reached
+            Food::new(Vector(15, 7), FoodKind::Normal, 0),
+            Food::new(Vector(1, 1), FoodKind::Rotten, 0),
+            // so nothing new turns up in the way
+            Food::new(Vector(1, 7), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        // straight away it's worth the most it can be
+        game.tick();
+        assert_eq!(game.score, 1 + MAX_FRESH_BONUS);
+
+        // and a bit less for every step it's been out, rotten fruit aside
+        game.tick();
+        game.tick();
+        let values = game.fresh_values().collect::<Vec<_>>();
+        assert_eq!(values.len(), 2);
+        assert!(values.contains(&(&Vector(15, 7), 1 + 3)));
+        assert!(values.contains(&(&Vector(1, 7), 1 + 3)));
+        game.tick();
+        assert!(game.take_events().contains(&GameEvent::FoodEaten {
+            position: Vector(15, 7),
+            kind: FoodKind::Normal,
+            multiplier: 1,
+            points: 4,
+        }));
+
+        // until it's worth what it always is
+        let food = Food::new(Vector(1, 7), FoodKind::Normal, 0);
+        assert_eq!(food.fresh_bonus(FRESH_STEP_MS - 1), MAX_FRESH_BONUS);
+        assert_eq!(food.fresh_bonus(MAX_FRESH_BONUS * FRESH_STEP_MS), 0);
+
+        game.set_versus(true);
+        assert!(!game.has_fresh_food());
+    }
+
+    #[test]
+    fn max_score_counts_hazards() {
+        assert_eq!(max_score(315, 2, false), 313);
+        assert_eq!(max_score(315, 2, true), 156);
+        assert_eq!(max_score(1, 2, true), 0);
+
+        let game = SnakeGame::new(21, 15);
+        assert_eq!(game.max_score, 156);
+    }
+
+    #[test]
+    fn hazards_expire() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hazard_lifetime = Some(10);
+        game.spawner = Spawner::fixed(vec![Vector(18, 7), Vector(0, 0)]);
+        game.restart();
+        assert_eq!(game.max_score, 313);
+
+        game.tick();
+        assert_eq!(game.hazards, vec![Vector(20, 7)]);
+        assert!(!game.free_positions.contains(&Vector(20, 7)));
+        assert_eq!(game.hazard_ticks_left(&Vector(20, 7)), Some(10));
+
+        for _i in 0..9 {
+            game.tick();
+        }
+        assert_eq!(game.hazard_ticks_left(&Vector(20, 7)), Some(1));
+
+        game.tick();
+        assert!(game.hazards.is_empty());
+        assert!(game.free_positions.contains(&Vector(20, 7)));
+        assert_eq!(game.hazard_ticks_left(&Vector(20, 7)), None);
+    }
+
+    #[test]
+    fn growth() {
+        let mut game = SnakeGame::new(21, 15);
+        game.growth = Growth::default().with(FoodKind::Normal, 3);
+        game.spawner = Spawner::fixed(vec![Vector(18, 7), Vector(16, 7), Vector(0, 0)]);
+        game.restart();
+        assert_eq!(game.max_score, max_score_with(315, 2, 3, 1));
+
+        // the tail stays put a tick at a time
+        let tail = game.snakes[0].tail().clone();
+        for length in [3, 4] {
+            game.tick();
+            assert_eq!(game.snakes[0].body.len(), length);
+            assert_eq!(game.snakes[0].tail(), &tail);
+        }
+        // and eating again before it's caught up only adds to what's left
+        game.tick();
+        assert_eq!(game.snakes[0].body.len(), 5);
+        assert_eq!(game.snakes[0].tail(), &tail);
+        assert_eq!(game.snakes[0].growing, 3);
+        for _ in 0..3 {
+            game.tick();
+        }
+        assert_eq!(game.snakes[0].body.len(), 8);
+        game.tick();
+        assert_eq!(game.snakes[0].body.len(), 8);
+    }
+
+    #[test]
+    fn hazards_top_up() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hazard_lifetime = Some(10);
+        // nothing to eat on the way, which would lay a puddle of its own
+        game.spawner = Spawner::fixed(vec![Vector(0, 0)]);
+        game.restart();
+        game.skip_countdown();
+
+        assert!(game.spawn_hazard(Vector(3, 3)));
+        for _i in 0..4 {
+            game.tick();
+        }
+        assert_eq!(game.hazard_ticks_left(&Vector(3, 3)), Some(6));
+
+        // a second puddle on the same tile only makes the first one fresh again
+        game.lay_hazard(Vector(3, 3));
+        assert_eq!(game.hazards, vec![Vector(3, 3)]);
+        assert_eq!(game.hazard_ticks_left(&Vector(3, 3)), Some(10));
+
+        // and one that's there for good stays that way
+        game.hazard_spawn_ticks.clear();
+        game.lay_hazard(Vector(3, 3));
+        assert_eq!(game.hazard_ticks_left(&Vector(3, 3)), None);
+        assert_eq!(game.hazards.len(), 1);
+    }
+
+    #[test]
+    fn double_hazards() {
+        let mut game = SnakeGame::new(21, 15);
+        game.double_hazards = true;
+        game.spawner = Spawner::fixed(vec![Vector(18, 7), Vector(0, 0)]);
+        game.restart();
+        assert_eq!(game.max_score, max_score_with(315, 2, 1, 2));
+
+        // the tail and the segment in front of it
+        game.tick();
+        assert_eq!(game.hazards, vec![Vector(20, 7), Vector(19, 7)]);
+
+        for _i in 0..3 {
+            game.tick();
+        }
+        assert!(!game.free_positions.contains(&Vector(19, 7)));
+        assert!(!game.free_positions.contains(&Vector(20, 7)));
+    }
+
+    #[test]
+    fn classic_rules() {
+        // a snake curled up so its head is right next to its tail
+        let chase = |rules: RuleSet, growing: usize| {
+            let mut game = SnakeGame::new(21, 15);
+            game.rules = rules;
+            game.restart();
+            game.snakes[0].body = [Vector(10, 7), Vector(11, 7), Vector(11, 8), Vector(10, 8)]
+                .into_iter()
+                .collect();
+            game.snakes[0].growing = growing;
+            game.food.clear();
+            game.rebuild_board();
+
+            game.change_direction(Direction::Down);
+            game.tick();
+            game
+        };
+
+        let crash = Some("avoid crashing into your own tail");
+        assert_eq!(chase(RuleSet::SLAKE, 0).game_over_reason, crash);
+
+        let game = chase(RuleSet::CLASSIC, 0);
+        assert!(!game.is_game_over());
+        assert_eq!(game.snakes[0].head(), &Vector(10, 8));
+        assert!(!game.free_positions.contains(&Vector(10, 8)));
+        assert_eq!(game.free_positions.len(), 21 * 15 - 4);
+
+        // a tail that isn't going anywhere still counts
+        assert_eq!(chase(RuleSet::CLASSIC, 1).game_over_reason, crash);
+
+        let no_collisions = RuleSet {
+            self_collision: false,
+            ..RuleSet::SLAKE
+        };
+        assert!(!chase(no_collisions, 1).is_game_over());
+
+        // and no leftovers
+        let mut game = SnakeGame::new(21, 15);
+        game.rules = RuleSet::CLASSIC;
+        game.spawner = Spawner::fixed(vec![Vector(18, 7), Vector(0, 0)]);
+        game.restart();
+        assert_eq!(game.max_score, 313);
+
+        game.tick();
+        assert_eq!(game.score, 1);
+        assert!(game.hazards.is_empty());
+    }
+
+    #[test]
+    fn tail_cut() {
+        let mut game = SnakeGame::new(21, 15);
+        game.rules = RuleSet {
+            tail_cut: true,
+            ..RuleSet::SLAKE
+        };
+        game.restart();
+        // curled up with the head over the last two segments
+        game.snakes[0].body = [
+            Vector(10, 7),
+            Vector(11, 7),
+            Vector(12, 7),
+            Vector(12, 8),
+            Vector(11, 8),
+            Vector(10, 8),
+            Vector(9, 8),
+        ]
+        .into_iter()
+        .collect();
+        game.scores = vec![5];
+        game.score = 5;
+        game.food.clear();
+        game.rebuild_board();
+
+        game.change_direction(Direction::Down);
+        game.tick();
+
+        assert!(!game.is_game_over());
+        assert_eq!(game.snakes[0].head(), &Vector(10, 8));
+        assert_eq!(game.snakes[0].body.len(), 5);
+        assert_eq!(game.hazards, vec![Vector(9, 8)]);
+        assert!(!game.free_positions.contains(&Vector(9, 8)));
+        assert!(game.free_positions.contains(&Vector(11, 8)));
+        assert_eq!(game.score, 3);
+        assert!(game.take_events().contains(&GameEvent::SnakeCut {
+            player: 0,
+            segments: 2
+        }));
+    }
+
+    #[test]
+    fn food_counts() {
+        let normal_food = |game: &SnakeGame| {
+            game.food
+                .iter()
+                .filter(|food| food.kind == FoodKind::Normal)
+                .count()
+        };
+
+        let mut game = SnakeGame::new(21, 15);
+        game.food_count = FoodCount::Fixed(3);
+        game.restart();
+        assert_eq!(normal_food(&game), 3);
+
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Normal, 0),
+            Food::new(Vector(1, 1), FoodKind::Normal, 0),
+            Food::new(Vector(1, 2), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+        game.tick();
+        assert_eq!(game.score, 1);
+        assert_eq!(normal_food(&game), 3);
+        assert!(game.food.iter().all(|food| food.position != Vector(18, 7)));
+
+        // one more for every few points
+        game.food_count = FoodCount::UpTo(2);
+        game.restart();
+        assert_eq!(normal_food(&game), 1);
+
+        game.scores = vec![FOOD_SCORE_STEP - 1];
+        game.score = FOOD_SCORE_STEP - 1;
+        game.food = vec![Food::new(Vector(18, 7), FoodKind::Normal, 0)];
+        game.rebuild_board();
+        game.tick();
+        assert_eq!(normal_food(&game), 2);
+
+        // and in versus, that many pairs
+        game.food_count = FoodCount::Fixed(2);
+        game.set_versus(true);
+        game.restart();
+        assert_eq!(normal_food(&game), 4);
+    }
+
+    #[test]
+    fn food_kinds() {
+        let mut game = SnakeGame::new(21, 15);
+        // with some normal food out of the way, so none comes out after this
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Bonus, 0),
+            Food::new(Vector(17, 7), FoodKind::Rotten, 0),
+            Food::new(Vector(0, 14), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        game.tick();
+        assert_eq!(game.score, 3);
+        assert_eq!(game.snakes[0].body.len(), 3);
+        assert!(game.hazards.is_empty());
+
+        // rotten food doesn't grow the snake, but the puddle stays where the tail was
+        game.tick();
+        assert_eq!(game.score, 2);
+        assert_eq!(game.snakes[0].body.len(), 3);
+        assert_eq!(game.hazards, vec![Vector(20, 7)]);
+        assert!(!game.free_positions.contains(&Vector(20, 7)));
+
+        let mut bonus = Food::new(Vector(0, 0), FoodKind::Bonus, game.elapsed_ms);
+        bonus.despawn = Some(Timer::new(game.elapsed_ms, game.tick_ms));
+        game.food.push(bonus);
+        game.free_positions.remove(&Vector(0, 0));
+
+        game.tick();
+        assert_eq!(game.food.len(), 1);
+        assert!(game.free_positions.contains(&Vector(0, 0)));
+    }
+
+    #[test]
+    fn effects_keep_time() {
+        let mut game = SnakeGame::new(21, 15);
+        game.topology = Topology::Wrap;
+        game.restart();
+        game.food = vec![Food::new(Vector(0, 0), FoodKind::Bonus, game.elapsed_ms)];
+        game.rebuild_board();
+        let has_bonus =
+            |game: &SnakeGame| game.food.iter().any(|food| food.position == Vector(0, 0));
+
+        // halfway through at the usual speed
+        for _ in 0..20 {
+            game.tick();
+        }
+        assert_eq!(game.elapsed_ms, 2000);
+
+        // then twice as fast, so the other half takes twice as many ticks
+        game.tick_ms = 50;
+        for _ in 0..39 {
+            game.tick();
+        }
+        assert!(has_bonus(&game));
+        game.tick();
+        assert!(!has_bonus(&game));
+        assert!(!game.is_game_over());
+
+        game.restart();
+        assert_eq!(game.elapsed_ms, 0);
+    }
+
+    #[test]
+    fn countdown() {
+        let mut game = SnakeGame::new(21, 15);
+        assert_eq!(game.countdown(), None);
+
+        game.countdown_ticks = 2;
+        game.restart();
+        assert_eq!(game.countdown(), Some(3));
+
+        // turning during the countdown sticks
+        game.change_direction(Direction::Up);
+
+        for count in [3, 2, 2, 1, 1] {
+            game.tick();
+            assert_eq!(game.countdown(), Some(count));
+        }
+
+        game.tick();
+        assert_eq!(game.countdown(), None);
+        assert_eq!(game.ticks, 0);
+        assert_eq!(*game.snakes[0].head(), Vector(19, 7));
+
+        game.tick();
+        assert_eq!(game.ticks, 1);
+        assert_eq!(*game.snakes[0].head(), Vector(19, 6));
+    }
+
+    #[test]
+    fn seeded_restarts() {
+        let mut game = SnakeGame::new(21, 15);
+        let seed = game.seed;
+        let food = game.food.clone();
+
+        game.restart();
+        assert_ne!(game.seed, seed);
+
+        game.restart_with_seed(seed);
+        assert_eq!(game.seed, seed);
+        assert_eq!(game.food, food);
+    }
+
+    #[test]
+    fn zen() {
+        let mut game = SnakeGame::new(21, 15);
+        game.set_mode(GameMode::Zen);
+        assert_eq!(game.max_score, 21 * 15 - 2);
+
+        game.snakes[0].body = (10..15).map(|x| Vector(x, 7)).collect();
+        game.food.clear();
+        game.rebuild_board();
+
+        // up, right, and down again lands on the snake's own body
+        for direction in [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Down,
+        ] {
+            game.change_direction(direction);
+            game.tick();
+            assert!(!game.is_game_over());
+        }
+        assert_eq!(game.snakes[0].head(), &Vector(11, 8));
+
+        // tiles the snake went over twice only free up once it's off them both times
+        let tiles = game.snakes[0].body.iter().collect::<HashSet<_>>();
+        assert_eq!(game.free_positions.len(), 21 * 15 - tiles.len());
+        assert!(tiles.iter().all(|pos| !game.free_positions.contains(pos)));
+
+        // walls still count
+        game.change_direction(Direction::Left);
+        for _i in 0..12 {
+            game.tick();
+        }
+        assert_eq!(game.game_over_reason, Some("avoid walls"));
+    }
+
+    #[test]
+    fn time_attack() {
+        let mut game = SnakeGame::new(21, 15);
+        game.high_score = 5;
+        game.time_limit = Some(10);
+        assert_eq!(game.ticks_left(), None);
+
+        game.set_mode(GameMode::TimeAttack { seconds: 1 });
+        assert_eq!(game.high_score(), 0);
+        assert_eq!(game.ticks_left(), Some(10));
+
+        for _i in 0..10 {
+            assert!(!game.is_game_over());
+            game.tick();
+        }
+
+        assert_eq!(game.ticks_left(), Some(0));
+        assert_eq!(game.game_over_reason, Some("time's up"));
+    }
+
+    #[test]
+    fn pausing() {
+        let mut game = SnakeGame::new(21, 15);
+        game.paused = true;
+
+        for _i in 0..30 {
+            game.tick();
+        }
+        assert_eq!(game.ticks, 0);
+        assert_eq!(game.snakes[0].head(), &Vector(19, 7));
+
+        game.paused = false;
+        game.tick();
+        assert_eq!(game.snakes[0].head(), &Vector(18, 7));
+
+        game.paused = true;
+        game.restart();
+        assert!(!game.paused);
+    }
+
+    #[test]
+    fn snapshots() {
+        use crate::simulate;
+
+        random::seed([1, 2]);
+        let mut game = SnakeGame::new(15, 11);
+        game.special_food = true;
+        game.hydra_mode = true;
+        game.hazard_lifetime = Some(30);
+        game.restart();
+
+        simulate::simulate_game(&mut game, simulate::greedy, 30);
+        let snapshot = game.to_snapshot().unwrap();
+        let played_on = simulate::simulate_game(&mut game, simulate::greedy, 500);
+        assert!(played_on.score > snapshot.scores[0]);
+
+        // the food keeps turning up in the same places, so the bot goes the same way too
+        let mut resumed = SnakeGame::from_snapshot(&snapshot).unwrap();
+        assert_eq!(
+            simulate::simulate_game(&mut resumed, simulate::greedy, 500),
+            played_on
+        );
+        assert_eq!(resumed.food, game.food);
+        assert_eq!(resumed.hazards, game.hazards);
+
+        // a run that's over has nothing to pick up again
+        game.game_over_reason = Some("avoid walls");
+        assert!(game.to_snapshot().is_none());
+
+        let mut tampered = snapshot.clone();
+        tampered.free_positions.pop();
+        assert!(SnakeGame::from_snapshot(&tampered).is_err());
+
+        let mut old_rules = snapshot;
+        old_rules.fingerprint.rules += 1;
+        assert!(SnakeGame::from_snapshot(&old_rules).is_err());
+    }
+
+    #[test]
+    fn starving() {
+        let mut game = SnakeGame::new(21, 15);
+        game.starve_ticks = Some(3);
+        game.food = vec![Food::new(Vector(17, 7), FoodKind::Normal, 0)];
+        game.rebuild_board();
+
+        // eating on the second tick grows the snake and resets the hunger
+        game.tick();
+        game.tick();
+        assert_eq!(game.snakes[0].body.len(), 3);
+        assert_eq!(game.ticks_until_starving(0), Some(2));
+
+        game.food.clear();
+        game.rebuild_board();
+
+        game.tick();
+        game.tick();
+        assert_eq!(game.snakes[0].body.len(), 2);
+        assert_eq!(game.ticks_until_starving(0), Some(3));
+
+        for _i in 0..3 {
+            game.tick();
+        }
+        assert_eq!(game.snakes[0].body.len(), 1);
+        assert_eq!(game.game_over_reason, Some("don't starve"));
+    }
+
+    #[test]
+    fn hunger() {
+        let mut game = SnakeGame::new(21, 15);
+        assert_eq!(game.energy(0), None);
+        game.energy_drain = Some(MAX_ENERGY / 4);
+        game.food = vec![Food::new(Vector(17, 7), FoodKind::Normal, 0)];
+        game.rebuild_board();
+
+        game.tick();
+        assert_eq!(game.energy(0), Some(MAX_ENERGY * 3 / 4));
+        // eating fills it back up, less the tick it was eaten on
+        game.tick();
+        assert_eq!(game.energy(0), Some(MAX_ENERGY * 3 / 4));
+
+        game.food.clear();
+        game.rebuild_board();
+        let restored = SnakeGame::from_snapshot(&game.to_snapshot().unwrap()).unwrap();
+        assert_eq!(restored.energy(0), game.energy(0));
+
+        for _i in 0..2 {
+            game.tick();
+        }
+        assert!(!game.is_game_over());
+        game.tick();
+        assert_eq!(game.energy(0), Some(0));
+        assert_eq!(game.game_over_reason, Some(STARVED_REASON));
+    }
+
+    #[test]
+    fn run_iter_reports_ticks() {
+        let mut game = SnakeGame::new(21, 15);
+        game.spawner = Spawner::fixed(vec![Vector(18, 6), Vector(0, 0)]);
+        game.restart();
+        game.take_events();
+
+        let inputs = [Some(Direction::Up), None, Some(Direction::Left)];
+        let reports = game.run_iter(inputs).collect::<Vec<_>>();
+
+        let heads = reports
+            .iter()
+            .map(|report| report.head.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(heads, vec![Vector(19, 6), Vector(19, 5), Vector(18, 5)]);
+        assert_eq!(reports[2].tick, 3);
+        assert!(reports.iter().all(|report| !report.game_over));
+
+        // runs until the game ends, however many inputs are left
+        let reports = game.run_iter(std::iter::repeat(None)).collect::<Vec<_>>();
+        assert_eq!(reports.len(), 19);
+        assert!(reports.last().unwrap().game_over);
+        assert!(reports
+            .last()
+            .unwrap()
+            .events
+            .contains(&GameEvent::GameOver {
+                reason: "avoid walls"
+            }));
+    }
+
+    #[test]
+    fn scripted_food() {
+        let mut game = SnakeGame::new(21, 15);
+        game.spawner = Spawner::fixed(vec![Vector(18, 7), Vector(17, 7), Vector(0, 0)]);
+        game.restart();
+
+        for _i in 0..2 {
+            assert_eq!(game.food[0].position, Vector(18 - game.score as isize, 7));
+            game.tick();
+        }
+
+        assert_eq!(game.score, 2);
+        assert_eq!(game.food[0].position, Vector(0, 0));
+
+        // restarting replays the same food
+        game.restart();
+        assert_eq!(game.food[0].position, Vector(18, 7));
+    }
+
+    #[test]
+    fn level_food_stays_with_its_level() {
+        let mut game = SnakeGame::new(21, 15);
+        game.load_level(&Level::parse("1......\n....@o.\n.......").unwrap());
+        assert_eq!(game.food[0].position, Vector(0, 0));
+
+        // the next level doesn't have any food of its own, so it's random again
+        game.load_level(&Level::parse(".......\n....@o.\n.......").unwrap());
+        assert!(matches!(game.spawner, Spawner::Random));
+    }
+
+    #[test]
+    fn golden_food_splits_snake() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hydra_mode = true;
+
+        // stretch the snake out to the right of its head
+        let body = (0..6).map(|x| Vector(10 + x, 7)).collect();
+        game.snakes[0].body = body;
+        game.food.clear();
+        game.golden_food = Some(Vector(9, 7));
+        game.rebuild_board();
+
+        game.tick();
+
+        assert!(game.golden_food.is_none());
+        assert_eq!(game.snakes.len(), 2);
+        assert_eq!(game.snakes[0].body.len(), 3);
+        assert_eq!(*game.snakes[0].head(), Vector(9, 7));
+        assert_eq!(*game.snakes[1].head(), Vector(14, 7));
+        assert_eq!(game.snakes[1].direction, Direction::Right);
+        assert!(game.take_events().contains(&GameEvent::SnakeSplit));
+
+        game.change_direction(Direction::Up);
+        game.tick();
+
+        assert_eq!(*game.snakes[0].head(), Vector(9, 6));
+        assert_eq!(*game.snakes[1].head(), Vector(14, 6));
+
+        // the new head steers mirrored
+        game.change_direction(Direction::Left);
+        game.tick();
+
+        assert_eq!(*game.snakes[0].head(), Vector(8, 6));
+        assert_eq!(*game.snakes[1].head(), Vector(15, 6));
+    }
+
+    #[test]
+    fn split_across_wrapping_edge() {
+        let mut game = SnakeGame::new(7, 4);
+        game.topology = Topology::Wrap;
+        game.hydra_mode = true;
+
+        // found by the fuzzer. the new head's neck is on the far side of the board
+        let body = (3..9).map(|x| Vector(x % 7, 1)).collect();
+        game.snakes[0].body = body;
+        game.food.clear();
+        game.golden_food = Some(Vector(2, 1));
+        game.rebuild_board();
+
+        game.tick();
+
+        assert_eq!(game.snakes.len(), 2);
+        assert_eq!(*game.snakes[1].head(), Vector(0, 1));
+        assert_eq!(game.snakes[1].direction, Direction::Right);
+    }
+
+    fn is_mirrored(game: &SnakeGame) -> bool {
+        let mirror = |pos| geometry::mirror_position(game.width, game.height, pos);
+        let rival = game.snakes[0].body.iter().map(mirror).collect::<Vec<_>>();
+
+        game.snakes[1].body.iter().eq(rival.iter())
+            && game.food.iter().all(|food| {
+                game.food
+                    .iter()
+                    .any(|twin| twin.position == mirror(&food.position))
+            })
+    }
+
+    #[test]
+    fn versus_is_symmetric() {
+        let mut game = SnakeGame::new(21, 15);
+        game.hydra_mode = true;
+        game.set_versus(true);
+
+        assert!(!game.hydra_mode);
+        assert_eq!(game.snakes.len(), 2);
+        assert_eq!(game.snakes[1].player, 1);
+        assert_eq!(game.food.len(), 2);
+        assert_eq!(game.scores, vec![0, 0]);
+        assert!(is_mirrored(&game));
+
+        // both players eat their twin on the same tick, and get a new pair
+        game.food = vec![
+            Food::new(Vector(18, 7), FoodKind::Normal, 0),
+            Food::new(Vector(2, 7), FoodKind::Normal, 0),
+        ];
+        game.rebuild_board();
+
+        game.tick();
+        assert_eq!(game.scores, vec![1, 1]);
+        assert_eq!(game.score, 2);
+        assert_eq!(game.food.len(), 2);
+        assert!(is_mirrored(&game));
+
+        // levels are single player
+        game.load_level(&Level::parse(crate::level::BUILTIN_LEVELS[0]).unwrap());
+        assert!(!game.is_versus());
+        assert_eq!(game.snakes.len(), 1);
+        assert_eq!(game.scores, vec![0]);
+    }
+
+    #[test]
+    fn versus_winner() {
+        let mut game = SnakeGame::new(21, 15);
+        game.set_versus(true);
+        game.food.clear();
+        game.rebuild_board();
+
+        // player two turns into the top wall while player one keeps going
+        game.change_player_direction(1, Direction::Up);
+        for _i in 0..8 {
+            game.tick();
+        }
+        assert_eq!(game.game_over_reason, Some("avoid walls"));
+        assert_eq!(game.winner, Some(0));
+
+        // running straight into each other is a draw
+        game.restart();
+        game.food.clear();
+        game.rebuild_board();
+        for _i in 0..9 {
+            game.tick();
+        }
+        assert!(game.is_game_over());
+        assert_eq!(game.winner, None);
+    }
+
+    #[test]
+    fn enemies() {
+        let mut game = SnakeGame::new(21, 15);
+        game.topology = Topology::Wrap;
+        game.set_mode(GameMode::Zen);
+        game.enemy_count = 4;
+        game.restart();
+        assert_eq!(game.enemies().len(), 4);
+        let starts = game.start_tiles();
+
+        let mut moved = false;
+        for tick in 0..200 {
+            let before = game.enemies().to_vec();
+            game.change_direction(Direction::ALL[tick / 7 % 4].clone());
+            game.tick();
+            if game.is_game_over() {
+                game.restart();
+                continue;
+            }
+            moved |= game.enemies() != before;
+
+            for enemy in game.enemies() {
+                let pos = &enemy.position;
+                assert!(!enemy::near_start(&starts, pos));
+                assert!(!game.free_positions.contains(pos));
+                assert!(!game.food.iter().any(|food| food.position == *pos));
+                assert!(game.is_enemy_at(pos) && !game.is_snake_at(pos));
+            }
+        }
+        assert!(moved);
+
+        // running into one is the end
+        game.restart();
+        game.enemies = vec![Enemy {
+            position: Vector(18, 7),
+            behavior: Behavior::Wander,
+        }];
+        game.rebuild_board();
+        game.tick();
+        assert_eq!(game.game_over_reason, Some("watch out for the wanderers"));
+    }
+
+    #[test]
+    fn coop() {
+        let mut game = SnakeGame::new(21, 15);
+        game.coop = true;
+        game.set_versus(true);
+        game.food.clear();
+        game.rebuild_board();
+
+        // player two crashing ends it for player one too, with nobody beating anybody
+        game.change_player_direction(1, Direction::Up);
+        for _i in 0..8 {
+            game.tick();
+        }
+        assert_eq!(game.game_over_reason, Some("avoid walls"));
+        assert_eq!(game.winner, None);
+    }
+
+    #[test]
+    fn step() {
+        let mut game = SnakeGame::new(21, 15);
+        game.paused = true;
+        game.tick();
+        assert_eq!(game.ticks, 0);
+
+        game.step();
+        assert_eq!(game.ticks, 1);
+        assert!(game.paused);
+    }
+
+    #[test]
+    fn portals() {
+        let mut game = SnakeGame::new(7, 4);
+        game.load_level(&Level::parse("..A.@o.\n.......\n.......\n.....A.").unwrap());
+        game.food.clear();
+        game.rebuild_board();
+
+        for pos in game.portals().iter().flatten() {
+            assert!(game.is_portal_at(pos));
+            assert!(!game.free_positions().contains(pos));
+        }
+        assert_eq!(
+            game.next_tile(&Vector(3, 0), &Direction::Left),
+            Vector(4, 3)
+        );
+
+        // in one end and straight on out of the other
+        game.tick();
+        game.tick();
+        assert!(!game.is_game_over());
+        assert_eq!(game.snakes()[0].head(), &Vector(4, 3));
+        assert_eq!(game.snakes()[0].body.len(), 2);
+
+        // and back in the way it came out, from the other side
+        for direction in [Direction::Up, Direction::Right, Direction::Down] {
+            game.change_direction(direction);
+            game.tick();
+        }
+        assert!(!game.is_game_over());
+        assert_eq!(game.snakes()[0].head(), &Vector(2, 1));
+    }
+
+    #[test]
+    fn floors() {
+        let mut game = SnakeGame::new(7, 7);
+        game.load_level(
+            &Level::parse(".......\n.H.....\n.......\n=======\n.......\n.H.@o..\n.......").unwrap(),
+        );
+        game.food.clear();
+        game.rebuild_board();
+
+        assert!(game.floors() == vec![0..3, 4..7]);
+        assert!(game.active_floor() == (4..7));
+        for pos in game.ladders().iter().flatten() {
+            assert!(!game.free_positions().contains(pos));
+        }
+        // the rows between are walls, and nothing can spawn there
+        assert!(game.is_wall_at(&Vector(3, 3)));
+        assert!(!game.free_positions().contains(&Vector(3, 3)));
+
+        // in wrap, around the snake's own floor and not into the next one
+        game.topology = Topology::Wrap;
+        assert_eq!(game.next_tile(&Vector(3, 4), &Direction::Up), Vector(3, 6));
+        assert_eq!(
+            game.next_tile(&Vector(3, 2), &Direction::Down),
+            Vector(3, 0)
+        );
+
+        // up the ladder and on to the top floor
+        game.tick();
+        game.tick();
+        assert!(!game.is_game_over());
+        assert_eq!(game.snakes()[0].head(), &Vector(0, 1));
+        assert!(game.active_floor() == (0..3));
+
+        // a board that isn't split up is all one floor
+        game.drop_level();
+        game.restart();
+        assert_eq!(game.floors().len(), 1);
+        assert!(game.active_floor() == (0..game.height));
+    }
+
+    #[test]
+    fn hex_grid() {
+        // square boards don't have the diagonals
+        let mut game = SnakeGame::new(21, 15);
+        game.change_direction(Direction::UpRight);
+        assert_eq!(game.rejected_inputs, 1);
+
+        game.grid = Grid::Hex;
+        game.restart();
+        assert_eq!(game.snakes()[0].head(), &Vector(19, 7));
+
+        // heading left, down goes down and to the left
+        game.change_direction(Direction::Down);
+        game.tick();
+        assert_eq!(game.snakes()[0].head(), &Vector(18, 8));
+
+        game.change_direction(Direction::Right);
+        game.tick();
+        assert_eq!(game.snakes()[0].head(), &Vector(19, 8));
+
+        // and heading right, up goes up and to the right
+        game.change_direction(Direction::Up);
+        game.tick();
+        assert_eq!(game.snakes()[0].direction(), &Direction::UpRight);
+        assert_eq!(game.snakes()[0].head(), &Vector(20, 7));
+        assert!(!game.is_game_over());
+
+        // up against the right edge, two of the six ways are off the board
+        assert_eq!(
+            game.geometry()
+                .neighbors(game.width, game.height, &Vector(20, 7))
+                .len(),
+            4
+        );
+        assert_eq!(game.rejected_inputs, 0);
+    }
+
+    // the settings a run in `random_play` is played under
+    #[derive(Clone, Debug)]
+    struct PlayCase {
+        width: isize,
+        height: isize,
+        topology: Topology,
+        grid: Grid,
+        hydra_mode: bool,
+        special_food: bool,
+        hazard_stun: bool,
+        double_hazards: bool,
+        enemy_count: usize,
+        self_collision: bool,
+        tail_cut: bool,
+        mode: GameMode,
+        versus: bool,
+    }
+
+    impl PlayCase {
+        fn game(&self) -> SnakeGame {
+            let mut game = SnakeGame::new(self.width, self.height);
+            game.topology = self.topology;
+            game.grid = self.grid;
+            game.hydra_mode = self.hydra_mode;
+            game.special_food = self.special_food;
+            game.hazard_stun = self.hazard_stun;
+            game.double_hazards = self.double_hazards;
+            game.enemy_count = self.enemy_count;
+            game.rules.self_collision = self.self_collision;
+            game.rules.tail_cut = self.tail_cut;
+            game.tick_ms = 100;
+            game.time_limit = self.mode.time_limit(100);
+            game.set_mode(self.mode);
+            game.set_versus(self.versus);
+            game
+        }
+    }
+
+    prop_compose! {
+        // small boards, with square ones coming up twice as often as hex
+        fn play_case()(
+            (width, height) in (5..15isize, 3..11isize),
+            topology in prop::sample::select(vec![Topology::Walls, Topology::Wrap]),
+            grid in prop::sample::select(vec![Grid::Square, Grid::Square, Grid::Hex]),
+            (hydra_mode, special_food, hazard_stun) in any::<(bool, bool, bool)>(),
+            double_hazards in prop::bool::weighted(1.0 / 3.0),
+            enemy_count in 0..3usize,
+            (self_collision, tail_cut) in (prop::bool::weighted(0.75), prop::bool::weighted(1.0 / 3.0)),
+            mode in prop::sample::select(GameMode::ALL.to_vec()),
+            versus in prop::bool::weighted(1.0 / 3.0),
+        ) -> PlayCase {
+            PlayCase {
+                width,
+                height,
+                topology,
+                grid,
+                hydra_mode,
+                special_food,
+                hazard_stun,
+                double_hazards,
+                enemy_count,
+                self_collision,
+                tail_cut,
+                mode,
+                versus,
+            }
+        }
+    }
+
+    // what happens before a tick: a turn, any of the hex diagonals too, which square boards turn
+    // down, or a restart, which only does anything once the run's over
+    #[derive(Clone, Debug)]
+    enum PlayInput {
+        Wait,
+        Turn(Direction),
+        Restart,
+    }
+
+    fn play_input() -> impl Strategy<Value = PlayInput> {
+        prop_oneof![
+            5 => Just(PlayInput::Wait),
+            4 => prop::sample::select(Direction::HEX.to_vec()).prop_map(PlayInput::Turn),
+            1 => Just(PlayInput::Restart),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        // Short runs on small boards with the settings picked at random, steered at random,
+        // checking `invariants` after every tick. The fuzzer does the same for longer, see
+        // `bin/fuzz.rs`.
+        #[test]
+        fn random_play(
+            seed in any::<u16>(),
+            case in play_case(),
+            inputs in prop::collection::vec(play_input(), 1..=50),
+        ) {
+            random::seed([seed, 1]);
+            let mut game = case.game();
+            prop_assert_eq!(game.invariants(), Ok(()), "at the start");
+
+            for (tick, input) in inputs.into_iter().enumerate() {
+                match input {
+                    PlayInput::Wait => (),
+                    PlayInput::Turn(direction) => game.change_direction(direction),
+                    PlayInput::Restart if game.is_game_over() => game.restart(),
+                    PlayInput::Restart => (),
+                }
+                game.tick();
+
+                prop_assert_eq!(game.invariants(), Ok(()), "tick {}", tick);
+            }
+        }
     }
 }
@@ -0,0 +1,191 @@
+use crate::enemy::Enemy;
+use crate::food::Food;
+use crate::food::FoodCount;
+use crate::food::Growth;
+use crate::geometry::Direction;
+use crate::geometry::Vector;
+use crate::mode::GameMode;
+use crate::rules::RuleSet;
+use crate::version::Fingerprint;
+use crate::waves::Wave;
+
+use serde::{Deserialize, Serialize};
+use web_sys::console;
+
+// A run paused partway through, with everything it needs to carry on exactly the same way: the
+// rules, what's on the board, the score, and how far along the random numbers are. The game in
+// progress gets saved to local storage when the page goes away, so closing the tab doesn't
+// throw the run away. See `SnakeGame::to_snapshot`.
+
+const SNAPSHOT_KEY: &str = "slake_snapshot";
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SnakeSnapshot {
+    // head first
+    pub body: Vec<Vector>,
+    pub direction: Direction,
+    pub next_direction: Direction,
+    pub mirrored: bool,
+    pub growing: usize,
+    pub hungry_ticks: usize,
+    pub spent_energy: usize,
+    pub stunned: usize,
+    // on the play clock, while it's sped up
+    pub boost_ends_ms: Option<usize>,
+    pub stride: usize,
+    pub previous_tail: Option<Vector>,
+    pub player: usize,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Snapshot {
+    // for the board size and topology, and which rules it was saved under
+    pub fingerprint: Fingerprint,
+    pub mode: GameMode,
+    pub time_limit: Option<usize>,
+    pub hazard_lifetime: Option<usize>,
+    pub double_hazards: bool,
+    pub rules: RuleSet,
+    pub hazard_stun: bool,
+    pub grace_ticks: usize,
+    pub win_percent: Option<usize>,
+    pub combos: bool,
+    pub fresh_food: bool,
+    pub starve_ticks: Option<usize>,
+    pub energy_drain: Option<usize>,
+    pub special_food: bool,
+    pub food_away_from_heads: bool,
+    pub food_count: FoodCount,
+    pub hydra_mode: bool,
+    pub versus: bool,
+    pub coop: bool,
+    pub countdown_ticks: usize,
+    pub tick_ms: usize,
+    pub start_length: usize,
+    pub growth: Growth,
+
+    pub snakes: Vec<SnakeSnapshot>,
+    pub hazards: Vec<Vector>,
+    pub hazard_spawn_ticks: Vec<(Vector, usize)>,
+    // only ever any in closing in mode, since games on levels aren't saved
+    pub walls: Vec<Vector>,
+    pub pending_walls: Vec<Vector>,
+    // only in waves mode
+    pub wave: Option<Wave>,
+    pub enemy_count: usize,
+    pub enemies: Vec<Enemy>,
+    pub food: Vec<Food>,
+    pub golden_food: Option<Vector>,
+    // in the order new food picks from, see `FreeSlots`
+    pub free_positions: Vec<Vector>,
+    pub countdown_left: usize,
+    pub ticks: usize,
+    pub elapsed_ms: usize,
+    pub seed: [u16; 2],
+    // see `random::state`
+    pub random: ([u16; 2], usize),
+    pub rejected_inputs: usize,
+    pub tampered: bool,
+    pub combo: usize,
+    pub last_food_ms: Option<usize>,
+    pub fruit_eaten: usize,
+    pub scores: Vec<usize>,
+    pub high_score: usize,
+    pub high_score_display: usize,
+    pub max_score: usize,
+}
+
+impl Snapshot {
+    // as JSON, all on one line so a recording can start with it, see `Recording::serialize`
+    pub fn serialize(&self) -> String {
+        serde_json::to_string(self).expect("a snapshot is only ever plain data")
+    }
+
+    // like fuzzer cases, a broken snapshot is an error, since half a game isn't worth resuming
+    pub fn parse(source: &str) -> Result<Snapshot, String> {
+        serde_json::from_str(source).map_err(|error| format!("the snapshot doesn't parse: {error}"))
+    }
+}
+
+// the saved game, if there is one that parses
+pub fn load() -> Option<Snapshot> {
+    let saved = crate::local_storage()?
+        .get_item(SNAPSHOT_KEY)
+        .ok()
+        .flatten()?;
+
+    match Snapshot::parse(&saved) {
+        Ok(snapshot) => Some(snapshot),
+        Err(error) => {
+            console::warn_1(&format!("Couldn't load the saved game: {error}").into());
+            None
+        }
+    }
+}
+
+// `None` clears out the saved game, so a finished run doesn't come back
+pub fn save(snapshot: Option<&Snapshot>) {
+    let Some(storage) = crate::local_storage() else {
+        return;
+    };
+
+    // not being able to save isn't worth interrupting anything over
+    let _ = match snapshot {
+        Some(snapshot) => storage.set_item(SNAPSHOT_KEY, &snapshot.serialize()),
+        None => storage.remove_item(SNAPSHOT_KEY),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodKind;
+    use crate::snake::SnakeGame;
+
+    #[test]
+    fn round_trip() {
+        let mut game = SnakeGame::new(9, 7);
+        game.special_food = true;
+        game.food_away_from_heads = true;
+        game.food_count = FoodCount::UpTo(3);
+        game.rules = RuleSet::CLASSIC;
+        game.hazard_lifetime = Some(30);
+        game.hazard_stun = true;
+        game.grace_ticks = 5;
+        game.win_percent = Some(90);
+        game.combos = true;
+        game.fresh_food = true;
+        game.time_limit = Some(500);
+        game.tick_ms = 70;
+        game.set_mode(GameMode::TimeAttack { seconds: 60 });
+        game.coop = true;
+        game.enemy_count = 3;
+        game.growth = Growth::default().with(FoodKind::Normal, 3);
+        game.set_versus(true);
+
+        for tick in 0..60 {
+            let direction = Direction::ALL[tick / 4 % 4].clone();
+            game.change_player_direction(tick % 2, direction);
+            game.tick();
+            if game.is_game_over() {
+                game.restart();
+            }
+        }
+
+        let snapshot = game.to_snapshot().unwrap();
+        assert_eq!(Snapshot::parse(&snapshot.serialize()), Ok(snapshot.clone()));
+        assert!(!snapshot.serialize().contains('\n'));
+
+        let broken = snapshot
+            .serialize()
+            .replace(r#""kind":"Normal""#, r#""kind":"Mouldy""#);
+        assert!(
+            Snapshot::parse(&broken).is_err_and(|error| error.contains("unknown variant `Mouldy`"))
+        );
+        assert!(Snapshot::parse(r#"{"versus":true}"#).is_err());
+        let louder = snapshot.serialize().replacen('{', r#"{"volume":11,"#, 1);
+        assert!(Snapshot::parse(&louder).is_err());
+    }
+}
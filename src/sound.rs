@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+use web_sys::AudioContext;
+
+thread_local! {
+    // browsers only let audio start after user input, so this is created on first use
+    static AUDIO: RefCell<Option<AudioContext>> = const { RefCell::new(None) };
+}
+
+// a short fading square wave blip, since we don't ship any sound files
+pub fn beep(frequency: f32, duration: f64) -> Result<(), JsValue> {
+    AUDIO.with(|audio| {
+        let mut audio = audio.borrow_mut();
+
+        if audio.is_none() {
+            *audio = Some(AudioContext::new()?);
+        }
+
+        let context = audio.as_ref().unwrap();
+        let now = context.current_time();
+
+        let oscillator = context.create_oscillator()?;
+        oscillator.set_type(web_sys::OscillatorType::Square);
+        oscillator.frequency().set_value(frequency);
+
+        let gain = context.create_gain()?;
+        gain.gain().set_value_at_time(0.05, now)?;
+        gain.gain()
+            .linear_ramp_to_value_at_time(0.0, now + duration)?;
+
+        oscillator.connect_with_audio_node(&gain)?;
+        gain.connect_with_audio_node(&context.destination())?;
+
+        oscillator.start()?;
+        oscillator.stop_with_when(now + duration)?;
+
+        Ok(())
+    })
+}
@@ -0,0 +1,350 @@
+use crate::board::Board;
+use crate::board::FreeSlots;
+use crate::geometry::Geometry;
+use crate::geometry::Vector;
+use crate::mode::GameMode;
+use crate::random;
+
+use std::collections::VecDeque;
+
+// Decides where new food, power-ups and puddles show up. Normally food goes anywhere random
+// that the mode's `SpawnRules` allow, but tutorials, puzzles, and tests can script exact
+// positions instead.
+#[derive(Default, Clone)]
+pub enum Spawner {
+    #[default]
+    Random,
+    // positions are used in order, skipping any that are taken, and then it's random again
+    Fixed {
+        positions: Vec<Vector>,
+        next: usize,
+    },
+}
+
+impl Spawner {
+    pub fn fixed(positions: Vec<Vector>) -> Spawner {
+        Spawner::Fixed { positions, next: 0 }
+    }
+
+    // start the script over, for when the game restarts
+    pub fn rewind(&mut self) {
+        if let Spawner::Fixed { next, .. } = self {
+            *next = 0;
+        }
+    }
+
+    // picks one of `free_positions` and takes it out of the list
+    pub fn take_position(&mut self, free_positions: &mut FreeSlots) -> Option<Vector> {
+        if free_positions.is_empty() {
+            return None;
+        }
+
+        if let Some(pos) = self.take_scripted(free_positions, |_| true) {
+            return Some(pos);
+        }
+
+        // any free tile will do, so there's no need to go looking through them
+        let index = random::get_u16() as usize % free_positions.len();
+        Some(free_positions.swap_remove(index))
+    }
+
+    // same as `take_position`, but only picks positions `accept` is fine with. scripted positions
+    // it turns down are skipped for good
+    pub fn take_position_where(
+        &mut self,
+        free_positions: &mut FreeSlots,
+        accept: impl Fn(&Vector) -> bool,
+    ) -> Option<Vector> {
+        if free_positions.is_empty() {
+            return None;
+        }
+
+        if let Some(pos) = self.take_scripted(free_positions, &accept) {
+            return Some(pos);
+        }
+
+        let candidates = free_positions
+            .iter()
+            .enumerate()
+            .filter(|(_, pos)| accept(pos))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let index = candidates[random::get_u16() as usize % candidates.len()];
+
+        // removes the element at the index and replaces it with the last element
+        Some(free_positions.swap_remove(index))
+    }
+
+    // Like `take_position_where`, but going by a weight for each tile, so some are likelier than
+    // others. Tiles weighing 0 are never picked. When every tile that's left weighs the same,
+    // this picks exactly like `take_position_where` would, down to the random numbers.
+    pub fn take_weighted(
+        &mut self,
+        free_positions: &mut FreeSlots,
+        weight: impl Fn(&Vector) -> u32,
+    ) -> Option<Vector> {
+        if free_positions.is_empty() {
+            return None;
+        }
+
+        if let Some(pos) = self.take_scripted(free_positions, |pos| weight(pos) > 0) {
+            return Some(pos);
+        }
+
+        pick_weighted(free_positions, weight)
+    }
+
+    // the next scripted position that's free and accepted, if there are any left
+    fn take_scripted(
+        &mut self,
+        free_positions: &mut FreeSlots,
+        accept: impl Fn(&Vector) -> bool,
+    ) -> Option<Vector> {
+        let Spawner::Fixed { positions, next } = self else {
+            return None;
+        };
+
+        while let Some(pos) = positions.get(*next) {
+            *next += 1;
+
+            if accept(pos) && free_positions.remove(pos) {
+                return Some(pos.clone());
+            }
+        }
+
+        None
+    }
+}
+
+// `Spawner::take_weighted` without the script, for power-ups, which would otherwise take
+// scripted food's places out of order
+pub fn pick_weighted(
+    free_positions: &mut FreeSlots,
+    weight: impl Fn(&Vector) -> u32,
+) -> Option<Vector> {
+    let candidates = free_positions
+        .iter()
+        .enumerate()
+        .map(|(index, pos)| (index, weight(pos)))
+        .filter(|(_, weight)| *weight > 0)
+        .collect::<Vec<_>>();
+
+    let first = candidates.first()?.1;
+    let index = if candidates.iter().all(|(_, weight)| *weight == first) {
+        candidates[random::get_u16() as usize % candidates.len()].0
+    } else {
+        let total = candidates.iter().map(|(_, weight)| *weight).sum::<u32>();
+        let mut roll = ((random::get_u16() as u32) << 16 | random::get_u16() as u32) % total;
+
+        candidates
+            .iter()
+            .find(|(_, weight)| {
+                let here = roll < *weight;
+                roll = roll.saturating_sub(*weight);
+                here
+            })?
+            .0
+    };
+
+    Some(free_positions.swap_remove(index))
+}
+
+// What the rules look at when weighing up tiles, all as it is right now.
+pub struct Surroundings<'a> {
+    pub width: isize,
+    pub height: isize,
+    pub geometry: &'a dyn Geometry,
+    pub board: &'a Board,
+    pub heads: Vec<Vector>,
+}
+
+// Where food and power-ups may land besides on a free tile the snake can get to, which goes for
+// everything. Each mode has its own, see `for_mode`, and the default puts it anywhere at all.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub struct SpawnRules {
+    // the fewest steps it can be from any head. 2 keeps it off the tiles right next to one, and 0
+    // or 1 let it go anywhere, since a head's own tile is never free
+    pub head_distance: usize,
+    // kept off the tiles next to a puddle, so going for it is never a gamble
+    pub clear_of_hazards: bool,
+    // the more sides of a tile are boxed in, the less likely it is, so it's mostly out in the
+    // open. a tile open on every side is sixteen times as likely as a dead end on a square board
+    pub favor_open: bool,
+}
+
+impl SpawnRules {
+    // Classic, the daily, and mazes keep to the plain rules, so runs play out like they always
+    // have. The rest are made a little kinder, or quicker.
+    pub fn for_mode(mode: GameMode) -> SpawnRules {
+        match mode {
+            GameMode::Classic | GameMode::Maze | GameMode::Daily => SpawnRules::default(),
+            // no digging food out of a corner with the clock running
+            GameMode::TimeAttack { .. } | GameMode::Race { .. } => SpawnRules {
+                favor_open: true,
+                ..SpawnRules::default()
+            },
+            GameMode::Zen => SpawnRules {
+                clear_of_hazards: true,
+                favor_open: true,
+                ..SpawnRules::default()
+            },
+            // the walls coming in make it crowded enough, and a wave's puddles are in the way
+            // as it is
+            GameMode::ClosingIn | GameMode::Waves => SpawnRules {
+                clear_of_hazards: true,
+                ..SpawnRules::default()
+            },
+        }
+    }
+
+    // A weight for every tile, a row at a time, for `Spawner::take_weighted`. It's 0 wherever
+    // the rules say no, and otherwise only the same for every tile without `favor_open`.
+    pub fn weights(&self, surroundings: &Surroundings) -> Vec<u32> {
+        let Surroundings {
+            width,
+            height,
+            geometry,
+            board,
+            heads,
+        } = surroundings;
+        let index = |pos: &Vector| (pos.1 * width + pos.0) as usize;
+        let mut weights = vec![1; (width * height) as usize];
+
+        for y in 0..*height {
+            for x in 0..*width {
+                let pos = Vector(x, y);
+
+                if heads
+                    .iter()
+                    .any(|head| geometry.distance(head, &pos) < self.head_distance)
+                {
+                    weights[index(&pos)] = 0;
+                }
+
+                let neighbors = geometry.neighbors(*width, *height, &pos);
+                if self.clear_of_hazards && board.is_hazard_at(&pos) {
+                    for neighbor in neighbors.iter() {
+                        weights[index(neighbor)] = 0;
+                    }
+                }
+
+                if self.favor_open {
+                    let open = neighbors
+                        .iter()
+                        .filter(|neighbor| !board.is_blocked_at(neighbor))
+                        .count();
+                    weights[index(&pos)] <<= open;
+                }
+            }
+        }
+
+        weights
+    }
+}
+
+// Where eating leaves its puddles: on the tail, and with double hazards on the segment in front
+// of it too. Tiles that are a puddle already just get topped up, see `SnakeGame::lay_hazard`,
+// where there used to be a second one underneath that would outlast the first.
+pub fn hazard_tiles(body: &VecDeque<Vector>, count: usize) -> Vec<Vector> {
+    body.iter().rev().take(count).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Square;
+
+    #[test]
+    fn fixed_positions_in_order() {
+        let mut free_positions = FreeSlots::new(5, 1);
+        free_positions.extend((0..5).map(|x| Vector(x, 0)));
+        let mut spawner =
+            Spawner::fixed(vec![Vector(3, 0), Vector(3, 0), Vector(9, 9), Vector(1, 0)]);
+
+        // taken and off-board positions get skipped
+        assert_eq!(
+            spawner.take_position(&mut free_positions),
+            Some(Vector(3, 0))
+        );
+        assert_eq!(
+            spawner.take_position(&mut free_positions),
+            Some(Vector(1, 0))
+        );
+
+        // then it falls back to random
+        assert!(spawner.take_position(&mut free_positions).is_some());
+        assert_eq!(free_positions.len(), 2);
+
+        spawner.rewind();
+        free_positions.insert(Vector(3, 0));
+        assert_eq!(
+            spawner.take_position(&mut free_positions),
+            Some(Vector(3, 0))
+        );
+
+        free_positions.reset(5, 1);
+        assert_eq!(spawner.take_position(&mut free_positions), None);
+    }
+
+    #[test]
+    fn weighing_tiles() {
+        let mut board = Board::new(5, 5);
+        board.add_hazard(&Vector(0, 0));
+        board.add_wall(&Vector(4, 3));
+        let surroundings = Surroundings {
+            width: 5,
+            height: 5,
+            geometry: &Square,
+            board: &board,
+            heads: vec![Vector(2, 2)],
+        };
+        let index = |x: usize, y: usize| y * 5 + x;
+
+        assert!(SpawnRules::default()
+            .weights(&surroundings)
+            .iter()
+            .all(|weight| *weight == 1));
+
+        let rules = SpawnRules {
+            head_distance: 2,
+            clear_of_hazards: true,
+            favor_open: true,
+        };
+        let weights = rules.weights(&surroundings);
+        // right next to the head, and next to the puddle
+        assert_eq!(weights[index(2, 1)], 0);
+        assert_eq!(weights[index(1, 0)], 0);
+        // open all round, and in a corner with a wall next to it
+        assert_eq!(weights[index(1, 1)], 16);
+        assert_eq!(weights[index(4, 4)], 2);
+    }
+
+    #[test]
+    fn weighted_picks() {
+        let mut free_positions = FreeSlots::new(5, 1);
+        free_positions.extend((0..5).map(|x| Vector(x, 0)));
+
+        // the same weight everywhere goes just like the unweighted pick
+        random::seed([5, 6]);
+        let mut unweighted = free_positions.clone();
+        let expected = Spawner::Random.take_position_where(&mut unweighted, |_| true);
+        random::seed([5, 6]);
+        let weighted = Spawner::Random.take_weighted(&mut free_positions.clone(), |_| 3);
+        assert_eq!(weighted, expected);
+
+        for _i in 0..20 {
+            let picked = pick_weighted(&mut free_positions.clone(), |pos| match pos.0 {
+                1 => 1,
+                3 => 1000,
+                _ => 0,
+            });
+            assert!(matches!(picked, Some(Vector(1 | 3, 0))));
+        }
+        assert_eq!(pick_weighted(&mut free_positions, |_| 0), None);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::error::SlakeError;
+use crate::export;
+use crate::mode::GameMode;
+use crate::render;
+use crate::snake::SnakeGame;
+use crate::timer;
+use crate::toast;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::HtmlElement;
+
+// Speedrun splits. With the setting on, every `SPLIT_EVERY` fruit the run's time by the page's
+// clock gets noted, see `RunClock`, and listed under the board next to the best run's time at
+// the same point, green where this run's ahead of it and red where it's behind. The best run in
+// each mode is whichever got the most splits, and the quickest to the last of them between runs
+// that got as many. Versus and levels don't get splits, and runs that went through the dev
+// console don't become the best.
+
+pub const SPLIT_EVERY: usize = 10;
+const SPLITS_KEY: &str = "slake_splits";
+
+// how long it took to get to each split, in milliseconds
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Splits {
+    pub times: Vec<u64>,
+}
+
+impl Splits {
+    // one time to a line, skipping any that don't make sense
+    pub fn parse(text: &str) -> Splits {
+        let times = text
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect::<Vec<u64>>();
+        // times only ever go up, so anything after one that doesn't is garbage
+        let sorted = times
+            .windows(2)
+            .take_while(|pair| pair[0] <= pair[1])
+            .count();
+        Splits {
+            times: times[..times.len().min(sorted + 1)].to_vec(),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        self.times.iter().map(|ms| format!("{ms}\n")).collect()
+    }
+
+    // whether it's a better run than `best`
+    pub fn beats(&self, best: &Splits) -> bool {
+        match self.times.len().cmp(&best.times.len()) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => self.times.last() < best.times.last(),
+        }
+    }
+
+    // how far ahead, when negative, or behind each split is of the same one in `best`
+    pub fn deltas(&self, best: &Splits) -> Vec<Option<i64>> {
+        self.times
+            .iter()
+            .enumerate()
+            .map(|(index, ms)| Some(*ms as i64 - *best.times.get(index)? as i64))
+            .collect()
+    }
+
+    // for a spreadsheet, with a row for every split either run got to
+    pub fn csv(&self, best: &Splits) -> String {
+        let mut csv = "fruit,time_ms,best_ms,delta_ms\n".to_string();
+        let deltas = self.deltas(best);
+        for index in 0..self.times.len().max(best.times.len()) {
+            let cell = |value: Option<String>| value.unwrap_or_default();
+            csv += &format!(
+                "{},{},{},{}\n",
+                (index + 1) * SPLIT_EVERY,
+                cell(self.times.get(index).map(u64::to_string)),
+                cell(best.times.get(index).map(u64::to_string)),
+                cell(
+                    deltas
+                        .get(index)
+                        .copied()
+                        .flatten()
+                        .map(|ms| ms.to_string())
+                ),
+            );
+        }
+        csv
+    }
+}
+
+fn storage_key(mode: GameMode) -> String {
+    format!("{SPLITS_KEY}_{}", mode.name())
+}
+
+fn load(mode: GameMode) -> Splits {
+    crate::local_storage()
+        .and_then(|storage| storage.get_item(&storage_key(mode)).ok().flatten())
+        .map(|saved| Splits::parse(&saved))
+        .unwrap_or_default()
+}
+
+// the run going now, or the last one once it's over
+#[derive(Default)]
+struct Run {
+    seed: [u16; 2],
+    mode: Option<GameMode>,
+    splits: Splits,
+    best: Splits,
+    // whether it's been compared against the best yet
+    finished: bool,
+}
+
+thread_local! {
+    static RUN: RefCell<Run> = RefCell::new(Run::default());
+    // what's in the panel, so it's only made again once something's changed
+    static SHOWN: RefCell<Option<(Splits, Splits)>> = const { RefCell::new(None) };
+}
+
+// After some fruit's been eaten or a restart, with `run_ms` from the run clock. A finished run,
+// a new seed, or fewer fruit than were split on means a new one.
+pub fn note(game: &SnakeGame, run_ms: f64) {
+    if game.is_versus() || game.level().is_some() {
+        return;
+    }
+
+    RUN.with(|run| {
+        let mut run = run.borrow_mut();
+        let mode = game.mode();
+        if run.finished
+            || run.seed != game.seed
+            || run.mode != Some(mode)
+            || game.fruit_eaten < run.splits.times.len() * SPLIT_EVERY
+        {
+            *run = Run {
+                seed: game.seed,
+                mode: Some(mode),
+                best: load(mode),
+                ..Run::default()
+            };
+        }
+
+        // eating more than one at once can skip past a split, which gets the same time
+        while (run.splits.times.len() + 1) * SPLIT_EVERY <= game.fruit_eaten {
+            run.splits.times.push(run_ms as u64);
+        }
+    });
+}
+
+// At the end of a run, saving it if it's the best yet.
+pub fn finish(game: &SnakeGame) {
+    RUN.with(|run| {
+        let mut run = run.borrow_mut();
+        if run.finished || run.seed != game.seed || run.mode != Some(game.mode()) {
+            return;
+        }
+        run.finished = true;
+
+        if !game.tampered && run.splits.beats(&run.best) {
+            let Some(mode) = run.mode else { return };
+            if let Err(error) = crate::store(&storage_key(mode), &run.splits.serialize()) {
+                crate::error::report(error, None);
+            }
+            // the old best stays up to compare with until the next run
+        }
+    });
+}
+
+// the panel under the board, after every frame
+pub fn update(shown: bool) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+    let existing = document.get_element_by_id("splits");
+
+    let runs = RUN.with(|run| {
+        let run = run.borrow();
+        (run.splits.clone(), run.best.clone())
+    });
+    if !shown || (runs.0.times.is_empty() && runs.1.times.is_empty()) {
+        if let Some(panel) = existing {
+            panel.remove();
+        }
+        SHOWN.with(|drawn| drawn.borrow_mut().take());
+        return Ok(());
+    }
+
+    let panel = match existing {
+        Some(panel) => panel.dyn_into::<HtmlElement>()?,
+        // the board being built again takes the panel with it
+        None => {
+            SHOWN.with(|drawn| drawn.borrow_mut().take());
+
+            let panel = render::create_div("splits")?;
+            panel.set_id("splits");
+            crate::get_element::<HtmlElement>("root")?.append_child(&panel)?;
+            panel.into()
+        }
+    };
+
+    if SHOWN.with(|drawn| drawn.borrow().as_ref() == Some(&runs)) {
+        return Ok(());
+    }
+
+    let (splits, best) = &runs;
+    let deltas = splits.deltas(best);
+    panel.set_inner_html("");
+    for index in 0..splits.times.len().max(best.times.len()) {
+        let row = render::create_div("split")?;
+        let fruit = render::create_div("split_fruit")?;
+        fruit.set_inner_text(&((index + 1) * SPLIT_EVERY).to_string());
+        row.append_child(&fruit)?;
+
+        // where this run hasn't got to, the best run's time for it
+        let time = render::create_div("split_time")?;
+        match (splits.times.get(index), best.times.get(index)) {
+            (Some(ms), _) => time.set_inner_text(&timer::split_text(*ms as f64)),
+            (None, Some(ms)) => {
+                time.set_inner_text(&timer::split_text(*ms as f64));
+                row.set_class_name("split upcoming");
+            }
+            (None, None) => (),
+        }
+        row.append_child(&time)?;
+
+        let delta = render::create_div("split_delta")?;
+        if let Some(ms) = deltas.get(index).copied().flatten() {
+            delta.set_inner_text(&timer::delta_text(ms));
+            delta.set_class_name(match ms <= 0 {
+                true => "split_delta ahead",
+                false => "split_delta behind",
+            });
+        }
+        row.append_child(&delta)?;
+
+        panel.append_child(&row)?;
+    }
+
+    SHOWN.with(|drawn| *drawn.borrow_mut() = Some(runs));
+    Ok(())
+}
+
+fn copy_splits() {
+    let csv = RUN.with(|run| {
+        let run = run.borrow();
+        (!run.splits.times.is_empty()).then(|| run.splits.csv(&run.best))
+    });
+    let message = match csv {
+        Some(csv) if export::copy_text(&csv) => "Copied your splits",
+        // it's in the prompt to copy instead
+        Some(_) => return,
+        None => "There aren't any splits to copy yet",
+    };
+    let _ = toast::show(message);
+}
+
+// the button for it in the settings panel
+pub fn build_buttons() -> Result<(), SlakeError> {
+    let handle_copy = Closure::wrap(Box::new(copy_splits) as Box<dyn FnMut()>);
+    crate::get_element::<HtmlElement>("setting_export_splits")?
+        .set_onclick(Some(handle_copy.as_ref().unchecked_ref()));
+    // the panel is never removed, so neither is its handler
+    handle_copy.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits() {
+        let best = Splits::parse("12000\n25000\n41000\n");
+        assert_eq!(best.times, vec![12000, 25000, 41000]);
+        assert_eq!(Splits::parse(&best.serialize()), best);
+        // cut off where it stops making sense
+        assert_eq!(Splits::parse("5\nsix\n7\n3\n9").times, vec![5, 7]);
+
+        let run = Splits {
+            times: vec![11500, 26000],
+        };
+        assert_eq!(run.deltas(&best), vec![Some(-500), Some(1000)]);
+        assert!(!run.beats(&best));
+        assert!(run.beats(&Splits::default()));
+        assert!(!Splits::default().beats(&Splits::default()));
+
+        // as many splits, and quicker to the last one
+        let quicker = Splits {
+            times: vec![13000, 26000, 40000],
+        };
+        assert!(quicker.beats(&best));
+        assert!(!best.beats(&quicker));
+
+        assert_eq!(
+            run.csv(&best),
+            "fruit,time_ms,best_ms,delta_ms\n\
+             10,11500,12000,-500\n\
+             20,26000,25000,1000\n\
+             30,,41000,\n"
+        );
+    }
+}
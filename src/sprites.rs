@@ -0,0 +1,223 @@
+use crate::error::SlakeError;
+use crate::level::MAX_PORTAL_PAIRS;
+use crate::view::Segment;
+use crate::view::TileKind;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{window, Blob, BlobPropertyBag, HtmlElement, HtmlImageElement, Url};
+
+// The sprites theme's pictures, for systems where the emoji come out wrong or not at all. They're
+// all on one sheet that's baked into the build, in a grid of `SPRITE_PX` squares: the food and
+// the rest of the board along the top, then a row for each player's snake, with a head and tail
+// facing every way and a piece of body for every bend, then the portals. The smooth style draws
+// them onto its canvas and the emoji style puts them behind its tiles. The squares style has no
+// use for them, and until the sheet's loaded, or if it never is, the theme's emoji show instead.
+
+const SHEET: &[u8] = include_bytes!("sprites.png");
+
+pub const SPRITE_PX: f64 = 16.0;
+// the page's stylesheet sizes the sheet by these too
+pub const COLUMNS: u8 = 15;
+pub const ROWS: u8 = 4;
+
+thread_local! {
+    // only once it's loaded
+    static IMAGE: RefCell<Option<HtmlImageElement>> = const { RefCell::new(None) };
+}
+
+// which way round the head and tail pictures go along their row, see `Segment`
+fn facing(degrees: u16) -> u8 {
+    (degrees / 90 % 4) as u8
+}
+
+// The column and row of `kind`'s sprite on the sheet, turned and bent to fit `segment` where
+// it's a piece of snake.
+pub fn cell(kind: TileKind, segment: Option<Segment>) -> Option<(u8, u8)> {
+    let column = match kind {
+        TileKind::Empty => return None,
+        TileKind::Food => 0,
+        TileKind::BonusFood => 1,
+        TileKind::RottenFood => 2,
+        TileKind::GoldenFood => 3,
+        TileKind::SpeedFood => 4,
+        TileKind::Hazard => 5,
+        TileKind::Enemy => 6,
+        TileKind::Wall => 7,
+        TileKind::Ladder => 8,
+        TileKind::ShrinkFood => 9,
+        TileKind::Portal(pair) => return Some((pair % MAX_PORTAL_PAIRS as u8, 3)),
+        _ => {
+            let row = match kind {
+                TileKind::Head | TileKind::Tail | TileKind::Body => 1,
+                _ => 2,
+            };
+            let column = match (kind, segment) {
+                (TileKind::Head | TileKind::RivalHead, Some(Segment::Head(degrees))) => {
+                    facing(degrees)
+                }
+                (TileKind::Head | TileKind::RivalHead, _) => 0,
+                (TileKind::Tail | TileKind::RivalTail, Some(Segment::Tail(degrees))) => {
+                    4 + facing(degrees)
+                }
+                (TileKind::Tail | TileKind::RivalTail, _) => 4,
+                (_, Some(Segment::Horizontal)) => 8,
+                (_, Some(Segment::Vertical)) => 9,
+                (_, Some(Segment::UpRight)) => 10,
+                (_, Some(Segment::DownRight)) => 11,
+                (_, Some(Segment::DownLeft)) => 12,
+                (_, Some(Segment::UpLeft)) => 13,
+                // a body that can't say which way it bends is a plain round one
+                _ => 14,
+            };
+            return Some((column, row));
+        }
+    };
+
+    Some((column, 0))
+}
+
+// Where each tile's sprite is on the sheet, by the classes on it, for boards of divs that
+// show them, see `Theme::stylesheet` for the rest. The sheet's only on the page once it's loaded,
+// and boards only say they show sprites after that.
+pub fn stylesheet() -> String {
+    let position = |(column, row): (u8, u8)| {
+        let left = column as f64 * 100.0 / (COLUMNS - 1) as f64;
+        let top = row as f64 * 100.0 / (ROWS - 1) as f64;
+        format!("background-position: {left}% {top}%;")
+    };
+
+    let portals = (0..MAX_PORTAL_PAIRS as u8).map(TileKind::Portal);
+    let mut rules = vec![];
+    for kind in TileKind::ALL.into_iter().chain(portals) {
+        let Some(plain) = cell(kind, None) else {
+            continue;
+        };
+        rules.push(format!(
+            ".field_holder.sprites .field.{} {{ background-image: var(--sprites); {} }}",
+            kind.class_name(),
+            position(plain)
+        ));
+
+        // heads, tails and bodies turned and bent
+        for segment in Segment::all() {
+            match cell(kind, Some(segment)) {
+                Some(turned) if turned != plain => rules.push(format!(
+                    ".field_holder.sprites .field.{}.{} {{ {} }}",
+                    kind.class_name(),
+                    segment.class_name(),
+                    position(turned)
+                )),
+                _ => (),
+            }
+        }
+    }
+
+    rules.join("\n")
+}
+
+// the sheet, if it's there to draw from
+pub fn image() -> Option<HtmlImageElement> {
+    IMAGE.with(|image| image.borrow().clone())
+}
+
+pub fn is_loaded() -> bool {
+    IMAGE.with(|image| image.borrow().is_some())
+}
+
+// Starts the sheet loading, and draws the board over with it once it has.
+pub fn load() -> Result<(), SlakeError> {
+    let bytes = js_sys::Array::of1(&js_sys::Uint8Array::from(SHEET));
+    let options = BlobPropertyBag::new();
+    options.set_type("image/png");
+    let url = Url::create_object_url_with_blob(&Blob::new_with_u8_array_sequence_and_options(
+        &bytes, &options,
+    )?)?;
+
+    let image = HtmlImageElement::new()?;
+    let loaded = image.clone();
+    let page_url = url.clone();
+    let handle_load = Closure::once(move || {
+        // for the emoji style's tiles
+        if let Some(page) = window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.document_element())
+            .and_then(|page| page.dyn_into::<HtmlElement>().ok())
+        {
+            let _ = page
+                .style()
+                .set_property("--sprites", &format!("url({page_url})"));
+        }
+
+        IMAGE.with(|image| *image.borrow_mut() = Some(loaded));
+        crate::draw();
+    });
+
+    // a sheet that fails to load just leaves the emoji
+    image.set_onload(Some(handle_load.as_ref().unchecked_ref()));
+    image.set_src(&url);
+    handle_load.forget();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        let portals = (0..MAX_PORTAL_PAIRS as u8).map(TileKind::Portal);
+        let kinds = TileKind::ALL[1..].iter().cloned().chain(portals);
+        let segments = [
+            Segment::Head(0),
+            Segment::Head(270),
+            Segment::Tail(90),
+            Segment::Horizontal,
+            Segment::Vertical,
+            Segment::UpRight,
+            Segment::DownRight,
+            Segment::DownLeft,
+            Segment::UpLeft,
+        ];
+
+        // everything's somewhere on the sheet, and nothing shares a sprite with anything else
+        let mut used = vec![];
+        for kind in kinds {
+            let shown = [None]
+                .into_iter()
+                .chain(segments.map(Some))
+                .map(|segment| cell(kind, segment).unwrap())
+                .collect::<Vec<_>>();
+            let mut own = vec![];
+            for (column, row) in shown {
+                assert!(column < COLUMNS && row < ROWS);
+                assert!(!used.contains(&(column, row)), "{kind:?} overlaps");
+                own.push((column, row));
+            }
+            used.extend(own);
+        }
+        assert_eq!(cell(TileKind::Empty, None), None);
+
+        // heads and tails face the way they're going, and bodies bend
+        assert_eq!(cell(TileKind::Head, Some(Segment::Head(90))), Some((1, 1)));
+        assert_eq!(
+            cell(TileKind::RivalTail, Some(Segment::Tail(180))),
+            Some((6, 2))
+        );
+        assert_eq!(cell(TileKind::Body, Some(Segment::DownLeft)), Some((12, 1)));
+        assert_ne!(
+            cell(TileKind::Body, Some(Segment::Head(0))),
+            cell(TileKind::Head, Some(Segment::Head(0)))
+        );
+
+        // with rules only for the segments that change anything
+        let stylesheet = stylesheet();
+        assert!(stylesheet.contains(".field.tile-wall { background-image: var(--sprites); "));
+        assert!(stylesheet.contains(".field.tile-head.seg-head-90 { background-position: "));
+        assert!(stylesheet.contains(".field.tile-body.seg-down-left {"));
+        assert!(!stylesheet.contains(".tile-head.seg-head-0 "));
+        assert!(!stylesheet.contains(".tile-body.seg-head"));
+        assert!(!stylesheet.contains("tile-empty"));
+    }
+}
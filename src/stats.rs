@@ -0,0 +1,491 @@
+use crate::error::SlakeError;
+use crate::event::DeathCause;
+use crate::highscores;
+use crate::settings::Difficulty;
+
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlDivElement, HtmlElement};
+
+// Play statistics, for this visit and for all time. The lifetime ones are saved in local storage
+// after every game, one `key=value` per line like the settings. Versus games don't count, same as
+// for the high score.
+
+const STATS_KEY: &str = "slake_stats";
+
+// how many of the latest games are kept around for suggesting a difficulty, and the scores that
+// count as doing well or struggling
+const RECENT_GAMES: usize = 5;
+const GOOD_SCORE: usize = 20;
+const POOR_SCORE: usize = 3;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct RecentGame {
+    pub difficulty: Difficulty,
+    pub score: usize,
+    pub cause: Option<DeathCause>,
+    // how long it lasted in game time, which games saved before this was kept don't know
+    pub survival_ms: Option<u64>,
+}
+
+// a difficulty worth trying, going by how the last few games went
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Suggestion {
+    pub difficulty: Difficulty,
+    // like "you've beaten Normal 5 times in a row"
+    pub reason: String,
+}
+
+impl Suggestion {
+    pub fn text(&self) -> String {
+        format!("Try {} — {}", self.difficulty.label(), self.reason)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Stats {
+    pub games_played: u32,
+    pub food_eaten: u32,
+    pub longest_snake: usize,
+    // in game time, all games added up
+    pub survival_ms: u64,
+    // indexed by `DeathCause`
+    pub deaths: [u32; 4],
+    // the last `RECENT_GAMES` games, oldest first
+    pub recent: Vec<RecentGame>,
+}
+
+impl Stats {
+    pub fn record_food(&mut self, snake_length: usize) {
+        self.food_eaten += 1;
+        self.longest_snake = self.longest_snake.max(snake_length);
+    }
+
+    pub fn record_game(
+        &mut self,
+        reason: &str,
+        snake_length: usize,
+        survival_ms: u64,
+        score: usize,
+        difficulty: Difficulty,
+    ) {
+        self.games_played += 1;
+        self.longest_snake = self.longest_snake.max(snake_length);
+        self.survival_ms += survival_ms;
+
+        let cause = DeathCause::from_reason(reason);
+        if let Some(cause) = cause {
+            self.deaths[cause as usize] += 1;
+        }
+
+        self.recent.push(RecentGame {
+            difficulty,
+            score,
+            cause,
+            survival_ms: Some(survival_ms),
+        });
+        if self.recent.len() > RECENT_GAMES {
+            self.recent.remove(0);
+        }
+    }
+
+    // Only once the last few games were all on `current`, and all went well or all went badly.
+    pub fn suggestion(&self, current: Difficulty) -> Option<Suggestion> {
+        if self.recent.len() < RECENT_GAMES
+            || self.recent.iter().any(|game| game.difficulty != current)
+        {
+            return None;
+        }
+
+        if self.recent.iter().all(|game| game.score >= GOOD_SCORE) {
+            return current.harder().map(|difficulty| Suggestion {
+                difficulty,
+                reason: format!(
+                    "you've beaten {} {RECENT_GAMES} times in a row",
+                    current.label()
+                ),
+            });
+        }
+
+        if self.recent.iter().all(|game| game.score < POOR_SCORE) {
+            // puddles are what difficulty changes, so they're worth pointing out
+            let puddles = self
+                .recent
+                .iter()
+                .filter(|game| game.cause == Some(DeathCause::Hazard))
+                .count();
+            let reason = if puddles * 2 > RECENT_GAMES {
+                format!(
+                    "puddles ended {puddles} of your last {RECENT_GAMES} games on {}",
+                    current.label()
+                )
+            } else {
+                format!(
+                    "your last {RECENT_GAMES} games on {} scored under {POOR_SCORE}",
+                    current.label()
+                )
+            };
+
+            return current
+                .easier()
+                .map(|difficulty| Suggestion { difficulty, reason });
+        }
+
+        None
+    }
+
+    pub fn average_survival_ms(&self) -> Option<u64> {
+        self.survival_ms.checked_div(self.games_played as u64)
+    }
+
+    // Reads stats saved by `serialize`. Anything broken counts as zero, rather than throwing
+    // away the rest.
+    pub fn parse(source: &str) -> Stats {
+        let mut stats = Stats::default();
+
+        for line in source.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim() {
+                "games_played" => stats.games_played = value.parse().unwrap_or(0),
+                "food_eaten" => stats.food_eaten = value.parse().unwrap_or(0),
+                "longest_snake" => stats.longest_snake = value.parse().unwrap_or(0),
+                "survival_ms" => stats.survival_ms = value.parse().unwrap_or(0),
+                "recent" => {
+                    if let Some(game) = Self::parse_recent(value) {
+                        stats.recent.push(game);
+                    }
+                }
+                key => {
+                    let cause = key.strip_prefix("deaths_").and_then(|name| {
+                        DeathCause::ALL
+                            .into_iter()
+                            .find(|cause| cause.name() == name)
+                    });
+
+                    if let Some(cause) = cause {
+                        stats.deaths[cause as usize] = value.parse().unwrap_or(0);
+                    }
+                }
+            }
+        }
+
+        stats.recent.truncate(RECENT_GAMES);
+        stats
+    }
+
+    // like "normal 12 wall 45000", or "none" for a game that didn't end in a death, and with or
+    // without how long it lasted
+    fn parse_recent(value: &str) -> Option<RecentGame> {
+        let (difficulty, score, cause, survival_ms) =
+            match value.split_whitespace().collect::<Vec<_>>()[..] {
+                [difficulty, score, cause] => (difficulty, score, cause, None),
+                [difficulty, score, cause, survival_ms] => {
+                    (difficulty, score, cause, Some(survival_ms.parse().ok()?))
+                }
+                _ => return None,
+            };
+
+        Some(RecentGame {
+            difficulty: Difficulty::from_name(difficulty)?,
+            score: score.parse().ok()?,
+            cause: DeathCause::ALL
+                .into_iter()
+                .find(|known| known.name() == cause),
+            survival_ms,
+        })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut source = format!(
+            "games_played={}\nfood_eaten={}\nlongest_snake={}\nsurvival_ms={}\n",
+            self.games_played, self.food_eaten, self.longest_snake, self.survival_ms,
+        );
+
+        for cause in DeathCause::ALL {
+            source += &format!("deaths_{}={}\n", cause.name(), self.deaths[cause as usize]);
+        }
+
+        for game in &self.recent {
+            source += &format!(
+                "recent={} {} {}",
+                game.difficulty.name(),
+                game.score,
+                game.cause.map_or("none", |cause| cause.name())
+            );
+            if let Some(survival_ms) = game.survival_ms {
+                source += &format!(" {survival_ms}");
+            }
+            source += "\n";
+        }
+
+        source
+    }
+}
+
+// like "2m 05s"
+pub fn format_duration(ms: u64) -> String {
+    let seconds = ms / 1000;
+
+    if seconds < 60 {
+        format!("{seconds}s")
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
+
+thread_local! {
+    static SESSION_STATS: RefCell<Stats> = RefCell::new(Stats::default());
+    static LIFETIME_STATS: RefCell<Stats> = RefCell::new(Stats::default());
+}
+
+pub fn load() {
+    let saved =
+        crate::local_storage().and_then(|storage| storage.get_item(STATS_KEY).ok().flatten());
+
+    if let Some(saved) = saved {
+        LIFETIME_STATS.with(|stats| *stats.borrow_mut() = Stats::parse(&saved));
+    }
+}
+
+// the last few games of all time, oldest first
+pub fn recent_games() -> Vec<RecentGame> {
+    LIFETIME_STATS.with(|stats| stats.borrow().recent.clone())
+}
+
+fn update(change: impl Fn(&mut Stats)) {
+    SESSION_STATS.with(|stats| change(&mut stats.borrow_mut()));
+    LIFETIME_STATS.with(|stats| change(&mut stats.borrow_mut()));
+}
+
+pub fn record_food(snake_length: usize) {
+    update(|stats| stats.record_food(snake_length));
+}
+
+pub fn record_game(
+    reason: &str,
+    snake_length: usize,
+    survival_ms: u64,
+    score: usize,
+    difficulty: Difficulty,
+) {
+    update(|stats| stats.record_game(reason, snake_length, survival_ms, score, difficulty));
+
+    // the banner doesn't get in the way of the game over, see `error::report`
+    let saved = LIFETIME_STATS.with(|stats| stats.borrow().serialize());
+    if let Err(error) = crate::store(STATS_KEY, &saved) {
+        crate::error::report(error, None);
+    }
+
+    crate::error::report_failure(refresh_panel());
+}
+
+// for the difficulty that's set now, going by all the games played here
+pub fn suggestion() -> Option<Suggestion> {
+    let difficulty = crate::SETTINGS.with(|settings| settings.borrow().difficulty);
+    LIFETIME_STATS.with(|stats| stats.borrow().suggestion(difficulty))
+}
+
+// switches to the suggested difficulty, which starts a new game. it's only offered once a game
+// is over, so it doesn't do anything in the middle of one
+pub fn take_suggestion() {
+    if !crate::GAME.with(|game| game.borrow().is_game_over()) {
+        return;
+    }
+
+    if let Some(suggestion) = suggestion() {
+        crate::update_settings(|settings| settings.difficulty = suggestion.difficulty);
+        crate::log(&format!("Difficulty: {}", suggestion.difficulty.label()));
+    }
+}
+
+// for when something shown in the panel changes while it's open
+pub fn refresh_panel() -> Result<(), SlakeError> {
+    if is_panel_visible()? {
+        fill_panel()?;
+    }
+    Ok(())
+}
+
+pub fn build_panel() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    let panel_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    panel_element.set_id("stats");
+    panel_element.set_class_name("settings");
+    panel_element.style().set_property("display", "none")?;
+
+    crate::error::body()?.append_child(&panel_element)?;
+
+    Ok(())
+}
+
+fn is_panel_visible() -> Result<bool, SlakeError> {
+    let display = crate::get_element::<HtmlElement>("stats")?
+        .style()
+        .get_property_value("display")?;
+    Ok(display != "none")
+}
+
+fn fill_panel() -> Result<(), SlakeError> {
+    let session = SESSION_STATS.with(|stats| stats.borrow().clone());
+    let lifetime = LIFETIME_STATS.with(|stats| stats.borrow().clone());
+
+    let row = |label: &str, value: &dyn Fn(&Stats) -> String| {
+        format!(
+            "<tr><td>{label}</td><td>{}</td><td>{}</td></tr>",
+            value(&session),
+            value(&lifetime)
+        )
+    };
+
+    let mut rows = vec![
+        row("Games", &|stats| stats.games_played.to_string()),
+        row("Fruit eaten", &|stats| stats.food_eaten.to_string()),
+        row("Longest snake", &|stats| stats.longest_snake.to_string()),
+        row("Average game", &|stats| {
+            stats
+                .average_survival_ms()
+                .map_or("-".to_string(), format_duration)
+        }),
+    ];
+
+    for cause in DeathCause::ALL {
+        rows.push(row(cause.label(), &|stats| {
+            stats.deaths[cause as usize].to_string()
+        }));
+    }
+
+    crate::get_element::<HtmlElement>("stats")?.set_inner_html(&format!(
+        "<table><tr><th></th><th>This visit</th><th>All time</th></tr>{}</table>{}",
+        rows.concat(),
+        highscores::table_html(crate::GAME.with(|game| game.borrow().mode()), None)
+    ));
+    Ok(())
+}
+
+pub fn toggle_panel() -> Result<(), SlakeError> {
+    let visible = is_panel_visible()?;
+
+    if !visible {
+        fill_panel()?;
+    }
+
+    crate::get_element::<HtmlElement>("stats")?
+        .style()
+        .set_property("display", if visible { "none" } else { "block" })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snake::SnakeGame;
+
+    #[test]
+    fn recording() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.average_survival_ms(), None);
+
+        stats.record_food(3);
+        stats.record_food(4);
+        stats.record_game("avoid walls", 4, 3000, 1, Difficulty::Normal);
+        stats.record_game("don't slip on the leftovers", 2, 1000, 0, Difficulty::Easy);
+
+        assert_eq!(stats.games_played, 2);
+        assert_eq!(stats.food_eaten, 2);
+        assert_eq!(stats.longest_snake, 4);
+        assert_eq!(stats.average_survival_ms(), Some(2000));
+        assert_eq!(stats.deaths, [1, 0, 1, 0]);
+
+        assert_eq!(Stats::parse(&stats.serialize()), stats);
+        assert_eq!(
+            Stats::parse("games_played=lots\ndeaths_tail=2"),
+            Stats {
+                deaths: [0, 2, 0, 0],
+                ..Stats::default()
+            }
+        );
+        // from before how long they lasted was kept
+        assert_eq!(
+            Stats::parse("recent=easy 4 wall").recent,
+            vec![RecentGame {
+                difficulty: Difficulty::Easy,
+                score: 4,
+                cause: Some(DeathCause::Wall),
+                survival_ms: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let mut stats = Stats::default();
+        for _ in 0..RECENT_GAMES - 1 {
+            stats.record_game("avoid walls", 30, 9000, 25, Difficulty::Normal);
+        }
+        // not enough games to go on yet
+        assert_eq!(stats.suggestion(Difficulty::Normal), None);
+
+        stats.record_game("avoid walls", 30, 9000, 25, Difficulty::Normal);
+        assert_eq!(
+            stats.suggestion(Difficulty::Normal).unwrap().text(),
+            "Try Hard — you've beaten Normal 5 times in a row"
+        );
+        // or they've switched since
+        assert_eq!(stats.suggestion(Difficulty::Hard), None);
+
+        // one game that went badly breaks the streak
+        stats.record_game("avoid walls", 3, 900, 1, Difficulty::Normal);
+        assert_eq!(stats.suggestion(Difficulty::Normal), None);
+        assert_eq!(stats.recent.len(), RECENT_GAMES);
+
+        for _ in 0..3 {
+            stats.record_game("don't slip on the leftovers", 3, 900, 0, Difficulty::Normal);
+        }
+        stats.record_game(
+            "avoid crashing into your own tail",
+            3,
+            900,
+            2,
+            Difficulty::Normal,
+        );
+        assert_eq!(
+            stats.suggestion(Difficulty::Normal),
+            Some(Suggestion {
+                difficulty: Difficulty::Easy,
+                reason: "puddles ended 3 of your last 5 games on Normal".to_string()
+            })
+        );
+
+        assert_eq!(Stats::parse(&stats.serialize()), stats);
+    }
+
+    #[test]
+    fn death_causes_match_the_game() {
+        // straight into the wall, whatever gets eaten on the way
+        let mut game = SnakeGame::new(21, 15);
+
+        while !game.is_game_over() {
+            game.tick();
+        }
+
+        assert_eq!(
+            DeathCause::from_reason(game.game_over_reason.unwrap()),
+            Some(DeathCause::Wall)
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(999), "0s");
+        assert_eq!(format_duration(59_000), "59s");
+        assert_eq!(format_duration(125_500), "2m 05s");
+    }
+}
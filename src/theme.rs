@@ -0,0 +1,613 @@
+use crate::level::MAX_PORTAL_PAIRS;
+use crate::view::Segment;
+use crate::view::TileKind;
+
+// one look for each pair of portals, see `Level::portals`. the retro ones are the letters the
+// level has them as
+type PerPortal = [&'static str; MAX_PORTAL_PAIRS];
+
+const CLASSIC_PORTALS: PerPortal = ["🌀", "🔮", "🌐", "🧿", "💠", "🪐"];
+const RETRO_PORTALS: PerPortal = ["A", "B", "C", "D", "E", "F"];
+const HIGH_CONTRAST_PORTALS: PerPortal = ["Ⓐ", "Ⓑ", "Ⓒ", "Ⓓ", "Ⓔ", "Ⓕ"];
+
+const CLASSIC_PORTAL_COLORS: PerPortal = [
+    "darkviolet",
+    "teal",
+    "royalblue",
+    "darkcyan",
+    "mediumorchid",
+    "sienna",
+];
+const RETRO_PORTAL_COLORS: PerPortal = [
+    "#aaffaa", "#77dd77", "#ccff66", "#66ffcc", "#99cc99", "#55bb55",
+];
+const HIGH_CONTRAST_PORTAL_COLORS: PerPortal =
+    ["cyan", "magenta", "lime", "yellow", "white", "orange"];
+const COLORBLIND_PORTAL_COLORS: PerPortal = [
+    "#cc79a7", "#0072b2", "#009e73", "#e69f00", "#56b4e9", "#d55e00",
+];
+const MONOCHROME_PORTAL_COLORS: PerPortal = [
+    "#ffffff", "#e0e0e0", "#c0c0c0", "#a0a0a0", "#808080", "#606060",
+];
+
+fn portal(looks: &PerPortal, pair: u8) -> &'static str {
+    looks[pair as usize % MAX_PORTAL_PAIRS]
+}
+
+// How the board looks: what's drawn on each kind of tile, in which colors, and on what. Both
+// renderers take everything from the theme in the settings. Glyphs are used by the emoji and
+// smooth styles, colors by the squares and smooth styles. The squares style gets its colors
+// from a stylesheet made out of the theme, see `stylesheet`, with each tile only saying what kind
+// it is. The sprites theme draws pictures from a sheet instead where it can, see `sprites`, and
+// falls back to the classic look.
+
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum Theme {
+    #[default]
+    Classic,
+    // green on black, like an old terminal
+    Retro,
+    // plain shapes and strong colors on black
+    HighContrast,
+    // The Okabe-Ito colors, which stay apart with either kind of red-green color blindness.
+    // The player is blue and the rival orange, and nothing else leans on red against green.
+    Colorblind,
+    // the high contrast shapes, in nothing but greys as far apart as they'll go
+    Monochrome,
+    // pixel art instead of emoji, which not every system draws the same
+    Sprites,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 6] = [
+        Theme::Classic,
+        Theme::Retro,
+        Theme::HighContrast,
+        Theme::Colorblind,
+        Theme::Monochrome,
+        Theme::Sprites,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Retro => "retro",
+            Theme::HighContrast => "high_contrast",
+            Theme::Colorblind => "colorblind",
+            Theme::Monochrome => "monochrome",
+            Theme::Sprites => "sprites",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Theme::Classic => "Classic",
+            Theme::Retro => "Retro",
+            Theme::HighContrast => "High contrast",
+            Theme::Colorblind => "Colorblind safe",
+            Theme::Monochrome => "Monochrome",
+            Theme::Sprites => "Sprites",
+        }
+    }
+
+    pub fn next(&self) -> Theme {
+        match self {
+            Theme::Classic => Theme::Retro,
+            Theme::Retro => Theme::HighContrast,
+            Theme::HighContrast => Theme::Colorblind,
+            Theme::Colorblind => Theme::Monochrome,
+            Theme::Monochrome => Theme::Sprites,
+            Theme::Sprites => Theme::Classic,
+        }
+    }
+
+    // the new themes only change colors, so they borrow their glyphs
+    pub fn glyph(&self, kind: TileKind) -> &'static str {
+        match self {
+            Theme::Classic | Theme::Colorblind | Theme::Sprites => match kind {
+                TileKind::Empty => "",
+                TileKind::Food => "🍆",
+                TileKind::BonusFood => "🍒",
+                TileKind::RottenFood => "🍄",
+                TileKind::GoldenFood => "🍯",
+                TileKind::SpeedFood => "⚡",
+                TileKind::ShrinkFood => "🫐",
+                TileKind::Head => "😩",
+                TileKind::Tail => "🍑",
+                TileKind::Body => "🟡",
+                TileKind::RivalHead => "🥵",
+                TileKind::RivalTail => "🍊",
+                TileKind::RivalBody => "🟠",
+                TileKind::Hazard => "💦",
+                TileKind::Enemy => "👾",
+                TileKind::Portal(pair) => portal(&CLASSIC_PORTALS, pair),
+                TileKind::Ladder => "🪜",
+                TileKind::Wall => "🧱",
+            },
+            Theme::Retro => match kind {
+                TileKind::Empty => "",
+                TileKind::Food => "*",
+                TileKind::BonusFood => "$",
+                TileKind::RottenFood => "%",
+                TileKind::GoldenFood => "+",
+                TileKind::SpeedFood => ">",
+                TileKind::ShrinkFood => "-",
+                TileKind::Head => "@",
+                TileKind::Tail => ".",
+                TileKind::Body => "o",
+                TileKind::RivalHead => "&",
+                TileKind::RivalTail => ",",
+                TileKind::RivalBody => "x",
+                TileKind::Hazard => "~",
+                TileKind::Enemy => "!",
+                TileKind::Portal(pair) => portal(&RETRO_PORTALS, pair),
+                TileKind::Ladder => "H",
+                TileKind::Wall => "#",
+            },
+            Theme::HighContrast | Theme::Monochrome => match kind {
+                TileKind::Empty => "",
+                TileKind::Food => "★",
+                TileKind::BonusFood => "✚",
+                TileKind::RottenFood => "✖",
+                TileKind::GoldenFood => "♦",
+                TileKind::SpeedFood => "➤",
+                TileKind::ShrinkFood => "▼",
+                TileKind::Head => "◉",
+                TileKind::Tail => "○",
+                TileKind::Body => "●",
+                TileKind::RivalHead => "◆",
+                TileKind::RivalTail => "◇",
+                TileKind::RivalBody => "■",
+                TileKind::Hazard => "≈",
+                TileKind::Enemy => "☠",
+                TileKind::Portal(pair) => portal(&HIGH_CONTRAST_PORTALS, pair),
+                TileKind::Ladder => "☷",
+                TileKind::Wall => "▓",
+            },
+        }
+    }
+
+    // What the emoji style draws for a piece of snake, which can show which way it bends and
+    // faces. Themes without a glyph for that turn the usual one instead, see `rotation`.
+    pub fn segment_glyph(&self, kind: TileKind, segment: Option<Segment>) -> &'static str {
+        let rival = match kind {
+            TileKind::Head | TileKind::Body => false,
+            TileKind::RivalHead | TileKind::RivalBody => true,
+            _ => return self.glyph(kind),
+        };
+
+        let glyphs = match (self, rival) {
+            (Theme::HighContrast | Theme::Monochrome, false) => {
+                ["▲", "▶", "▼", "◀", "━", "┃", "┗", "┏", "┓", "┛"]
+            }
+            (Theme::HighContrast | Theme::Monochrome, true) => {
+                ["△", "▷", "▽", "◁", "═", "║", "╚", "╔", "╗", "╝"]
+            }
+            _ => return self.glyph(kind),
+        };
+
+        match (kind, segment) {
+            (TileKind::Head | TileKind::RivalHead, Some(Segment::Head(degrees))) => {
+                glyphs[(degrees / 90 % 4) as usize]
+            }
+            (TileKind::Body | TileKind::RivalBody, Some(segment)) => match segment {
+                Segment::Horizontal => glyphs[4],
+                Segment::Vertical => glyphs[5],
+                Segment::UpRight => glyphs[6],
+                Segment::DownRight => glyphs[7],
+                Segment::DownLeft => glyphs[8],
+                Segment::UpLeft => glyphs[9],
+                _ => self.glyph(kind),
+            },
+            _ => self.glyph(kind),
+        }
+    }
+
+    // how far to turn a head or tail's glyph clockwise, in degrees, where it can't show which
+    // way it's going by itself. the emoji faces look up to start with
+    pub fn rotation(&self, segment: Option<Segment>) -> Option<u16> {
+        match (self, segment?) {
+            (
+                Theme::Classic | Theme::Colorblind | Theme::Sprites,
+                Segment::Head(degrees) | Segment::Tail(degrees),
+            ) if degrees != 0 => Some(degrees),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self, kind: TileKind) -> Option<&'static str> {
+        let color = match self {
+            Theme::Classic | Theme::Sprites => match kind {
+                TileKind::Empty => return None,
+                TileKind::Food => "purple",
+                TileKind::BonusFood => "deeppink",
+                TileKind::RottenFood => "olive",
+                TileKind::GoldenFood => "orange",
+                TileKind::SpeedFood => "yellowgreen",
+                TileKind::ShrinkFood => "slateblue",
+                TileKind::Head => "crimson",
+                TileKind::Tail => "salmon",
+                TileKind::Body => "gold",
+                TileKind::RivalHead => "darkred",
+                TileKind::RivalTail => "coral",
+                TileKind::RivalBody => "darkorange",
+                TileKind::Hazard => "deepskyblue",
+                TileKind::Enemy => "slateblue",
+                TileKind::Portal(pair) => portal(&CLASSIC_PORTAL_COLORS, pair),
+                TileKind::Ladder => "sienna",
+                TileKind::Wall => "dimgrey",
+            },
+            Theme::Retro => match kind {
+                TileKind::Empty => return None,
+                TileKind::Food => "#66ff66",
+                TileKind::BonusFood => "#ccffcc",
+                TileKind::RottenFood => "#336633",
+                TileKind::GoldenFood => "#99ff00",
+                TileKind::SpeedFood => "#b0ff80",
+                TileKind::ShrinkFood => "#55aa55",
+                TileKind::Head => "#33ff33",
+                TileKind::Tail => "#117711",
+                TileKind::Body => "#22bb22",
+                // the rival glows amber instead, so the two can be told apart
+                TileKind::RivalHead => "#ffb000",
+                TileKind::RivalTail => "#805800",
+                TileKind::RivalBody => "#c08400",
+                TileKind::Hazard => "#0f3f0f",
+                TileKind::Enemy => "#88ff88",
+                TileKind::Portal(pair) => portal(&RETRO_PORTAL_COLORS, pair),
+                TileKind::Ladder => "#44dd44",
+                TileKind::Wall => "#1a5c1a",
+            },
+            Theme::HighContrast => match kind {
+                TileKind::Empty => return None,
+                TileKind::Food => "lime",
+                TileKind::BonusFood => "cyan",
+                TileKind::RottenFood => "magenta",
+                TileKind::GoldenFood => "yellow",
+                TileKind::SpeedFood => "hotpink",
+                TileKind::ShrinkFood => "dodgerblue",
+                TileKind::Head => "white",
+                TileKind::Tail => "darkgrey",
+                TileKind::Body => "lightgrey",
+                TileKind::RivalHead => "yellow",
+                TileKind::RivalTail => "darkorange",
+                TileKind::RivalBody => "orange",
+                TileKind::Hazard => "blue",
+                TileKind::Enemy => "red",
+                TileKind::Portal(pair) => portal(&HIGH_CONTRAST_PORTAL_COLORS, pair),
+                TileKind::Ladder => "white",
+                TileKind::Wall => "grey",
+            },
+            Theme::Colorblind => match kind {
+                TileKind::Empty => return None,
+                TileKind::Food => "#009e73",
+                TileKind::BonusFood => "#cc79a7",
+                TileKind::RottenFood => "#8c6d31",
+                TileKind::GoldenFood => "#f0e442",
+                TileKind::SpeedFood => "#882255",
+                TileKind::ShrinkFood => "#332288",
+                TileKind::Head => "#0072b2",
+                TileKind::Tail => "#a6d2f0",
+                TileKind::Body => "#56b4e9",
+                TileKind::RivalHead => "#d55e00",
+                TileKind::RivalTail => "#f5cf80",
+                TileKind::RivalBody => "#e69f00",
+                TileKind::Hazard => "#bbbbbb",
+                TileKind::Enemy => "#000000",
+                TileKind::Portal(pair) => portal(&COLORBLIND_PORTAL_COLORS, pair),
+                TileKind::Ladder => "#8c6d31",
+                TileKind::Wall => "#444444",
+            },
+            Theme::Monochrome => match kind {
+                TileKind::Empty => return None,
+                TileKind::Food => "#808080",
+                TileKind::BonusFood => "#b0b0b0",
+                TileKind::RottenFood => "#404040",
+                TileKind::GoldenFood => "#e8e8e8",
+                TileKind::SpeedFood => "#989898",
+                TileKind::ShrinkFood => "#787878",
+                TileKind::Head => "#ffffff",
+                TileKind::Tail => "#909090",
+                TileKind::Body => "#d0d0d0",
+                TileKind::RivalHead => "#e0e0e0",
+                TileKind::RivalTail => "#707070",
+                TileKind::RivalBody => "#b8b8b8",
+                TileKind::Hazard => "#333333",
+                TileKind::Enemy => "#aaaaaa",
+                TileKind::Portal(pair) => portal(&MONOCHROME_PORTAL_COLORS, pair),
+                TileKind::Ladder => "#999999",
+                TileKind::Wall => "#555555",
+            },
+        };
+
+        Some(color)
+    }
+
+    // behind empty tiles
+    pub fn background(&self) -> &'static str {
+        match self {
+            Theme::Classic | Theme::Colorblind | Theme::Sprites => "white",
+            Theme::Retro | Theme::HighContrast | Theme::Monochrome => "black",
+        }
+    }
+
+    // for glyphs that aren't emoji, and the text over the board
+    pub fn text_color(&self) -> &'static str {
+        match self {
+            Theme::Classic | Theme::Colorblind | Theme::Sprites => "black",
+            Theme::Retro => "#33ff33",
+            Theme::HighContrast | Theme::Monochrome => "white",
+        }
+    }
+
+    // behind the text over the board, so the game still shows through a bit
+    pub fn overlay_color(&self) -> &'static str {
+        match self {
+            Theme::Classic | Theme::Colorblind | Theme::Sprites => "rgba(255, 255, 255, 0.8)",
+            Theme::Retro | Theme::HighContrast | Theme::Monochrome => "rgba(0, 0, 0, 0.8)",
+        }
+    }
+
+    pub fn has_sprites(&self) -> bool {
+        *self == Theme::Sprites
+    }
+
+    // Everything the theme decides about how a board of divs looks, by the classes on each tile,
+    // see `TileKind::class_name` and `Segment::class_name`: the squares style's colors, and the
+    // emoji style's glyphs and which way they're turned. The border's in there too, since it's
+    // the wall color whatever the style. Boards showing sprites get theirs from
+    // `sprites::stylesheet` instead of the glyphs.
+    pub fn stylesheet(&self) -> String {
+        let portals = (0..MAX_PORTAL_PAIRS as u8).map(TileKind::Portal);
+        let kinds = TileKind::ALL.into_iter().chain(portals).collect::<Vec<_>>();
+
+        let mut rules = kinds
+            .iter()
+            .filter_map(|kind| {
+                let color = self.color(*kind)?;
+                Some(format!(
+                    ".field_holder.squares .field.{} {{ background-color: {color}; }}",
+                    kind.class_name()
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(wall) = self.color(TileKind::Wall) {
+            rules.push(format!(
+                ".field_holder .field.border {{ background-color: {wall}; }}"
+            ));
+        }
+
+        let emoji = ".field_holder.emoji:not(.sprites) .field";
+        for kind in kinds.iter() {
+            let glyph = self.glyph(*kind);
+            if !glyph.is_empty() {
+                rules.push(format!(
+                    "{emoji}.{}::before {{ content: {}; }}",
+                    kind.class_name(),
+                    css_string(glyph)
+                ));
+            }
+        }
+        // only the pieces of snake that have a glyph of their own need a rule past the kind's
+        let snakes = [
+            TileKind::Head,
+            TileKind::Body,
+            TileKind::RivalHead,
+            TileKind::RivalBody,
+        ];
+        for kind in snakes {
+            for segment in Segment::all() {
+                let glyph = self.segment_glyph(kind, Some(segment));
+                if glyph != self.glyph(kind) {
+                    rules.push(format!(
+                        "{emoji}.{}.{}::before {{ content: {}; }}",
+                        kind.class_name(),
+                        segment.class_name(),
+                        css_string(glyph)
+                    ));
+                }
+            }
+        }
+        for segment in Segment::all() {
+            if let Some(degrees) = self.rotation(Some(segment)) {
+                rules.push(format!(
+                    "{emoji}.{} {{ transform: rotate({degrees}deg); }}",
+                    segment.class_name()
+                ));
+            }
+        }
+
+        rules.join("\n")
+    }
+}
+
+// in quotes, for `content`
+fn css_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [TileKind; 17] = TileKind::ALL;
+
+    #[test]
+    fn themes() {
+        for theme in Theme::ALL {
+            assert_eq!(Theme::from_name(theme.name()), Some(theme));
+
+            // everything but empty tiles needs a glyph, and no two can share one
+            let portals = (0..MAX_PORTAL_PAIRS as u8).map(TileKind::Portal);
+            let shown = KINDS[1..]
+                .iter()
+                .cloned()
+                .chain(portals)
+                .collect::<Vec<_>>();
+            for (index, kind) in shown.iter().enumerate() {
+                assert!(!theme.glyph(*kind).is_empty());
+                assert!(theme.color(*kind).is_some());
+                assert!(!shown[..index]
+                    .iter()
+                    .any(|other| theme.glyph(*other) == theme.glyph(*kind)));
+            }
+
+            assert_eq!(theme.glyph(TileKind::Empty), "");
+            assert_eq!(theme.color(TileKind::Empty), None);
+        }
+
+        assert_eq!(Theme::from_name("neon"), None);
+    }
+
+    // what a color looks like without one kind of cone, from Machado, Oliveira and Fernandes
+    // (2009) at full strength. these work on linear rgb
+    const PROTANOPIA: [[f64; 3]; 3] = [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+    ];
+    const DEUTERANOPIA: [[f64; 3]; 3] = [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+    ];
+    const TYPICAL: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    // "#rrggbb" as seen through `vision`, in CIE L*a*b*
+    fn seen(color: &str, vision: &[[f64; 3]; 3]) -> [f64; 3] {
+        let linear = [1, 3, 5].map(|start| {
+            let channel = u8::from_str_radix(&color[start..start + 2], 16).unwrap() as f64 / 255.0;
+            match channel <= 0.04045 {
+                true => channel / 12.92,
+                false => ((channel + 0.055) / 1.055).powf(2.4),
+            }
+        });
+        let [r, g, b] = vision.map(|row| {
+            (0..3)
+                .map(|i| row[i] * linear[i])
+                .sum::<f64>()
+                .clamp(0.0, 1.0)
+        });
+
+        let xyz = [
+            (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.9505,
+            0.2126 * r + 0.7152 * g + 0.0722 * b,
+            (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.089,
+        ];
+        let [x, y, z] = xyz.map(|t| match t > 0.008856 {
+            true => t.cbrt(),
+            false => 7.787 * t + 16.0 / 116.0,
+        });
+        [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+    }
+
+    #[test]
+    fn palettes() {
+        // the tiles that most need telling apart at a glance, along with the board itself
+        let key = [
+            TileKind::Food,
+            TileKind::Head,
+            TileKind::Body,
+            TileKind::Hazard,
+            TileKind::Enemy,
+            TileKind::Wall,
+            TileKind::GoldenFood,
+            TileKind::BonusFood,
+            TileKind::RivalHead,
+            TileKind::RivalBody,
+        ];
+
+        // there aren't enough greys for everything, so monochrome leaves the rest to its shapes
+        for (theme, key) in [
+            (Theme::Colorblind, &key[..]),
+            (Theme::Monochrome, &key[..6]),
+        ] {
+            let colors = key
+                .iter()
+                .map(|kind| theme.color(*kind).unwrap())
+                .chain([match theme.background() {
+                    "white" => "#ffffff",
+                    _ => "#000000",
+                }])
+                .collect::<Vec<_>>();
+
+            for vision in [TYPICAL, PROTANOPIA, DEUTERANOPIA] {
+                for (index, color) in colors.iter().enumerate() {
+                    for other in &colors[..index] {
+                        let (a, b) = (seen(color, &vision), seen(other, &vision));
+                        let distance = (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt();
+                        assert!(
+                            distance > 12.0,
+                            "{theme:?}: {color} and {other} are too close"
+                        );
+                    }
+                }
+            }
+        }
+
+        // nothing but greys
+        for kind in KINDS[1..].iter() {
+            let color = Theme::Monochrome.color(*kind).unwrap();
+            assert!(color[1..3] == color[3..5] && color[3..5] == color[5..7]);
+        }
+
+        let stylesheet = Theme::Colorblind.stylesheet();
+        assert!(stylesheet.contains(".field.tile-head { background-color: #0072b2; }"));
+        assert!(stylesheet.contains(".field.tile-portal-5 {"));
+        assert!(!stylesheet.contains(".tile-empty"));
+        assert!(stylesheet.contains(".field.tile-hazard::before { content: \"💦\"; }"));
+        assert!(stylesheet.contains(".field.seg-tail-90 { transform: rotate(90deg); }"));
+        // its snakes' glyphs don't change, they're turned instead
+        assert!(!stylesheet.contains(".seg-head-90::before"));
+
+        let stylesheet = Theme::HighContrast.stylesheet();
+        assert!(stylesheet.contains(".field.tile-head.seg-head-90::before { content: \"▶\"; }"));
+        assert!(!stylesheet.contains("rotate"));
+        assert_eq!(css_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+
+    #[test]
+    fn segments() {
+        let bends = [
+            Segment::Horizontal,
+            Segment::Vertical,
+            Segment::UpRight,
+            Segment::DownRight,
+            Segment::DownLeft,
+            Segment::UpLeft,
+        ];
+
+        let theme = Theme::HighContrast;
+        assert_eq!(
+            theme.segment_glyph(TileKind::Head, Some(Segment::Head(90))),
+            "▶"
+        );
+        assert_eq!(
+            theme.segment_glyph(TileKind::Body, Some(Segment::UpRight)),
+            "┗"
+        );
+        assert_eq!(theme.segment_glyph(TileKind::Body, None), "●");
+        assert_eq!(theme.rotation(Some(Segment::Head(90))), None);
+
+        // the players still look different, bend for bend
+        for bend in bends {
+            let glyph = theme.segment_glyph(TileKind::Body, Some(bend));
+            assert_ne!(glyph, theme.segment_glyph(TileKind::RivalBody, Some(bend)));
+            assert!(!KINDS.iter().any(|kind| theme.glyph(*kind) == glyph));
+        }
+
+        let theme = Theme::Classic;
+        assert_eq!(
+            theme.segment_glyph(TileKind::Body, Some(Segment::UpRight)),
+            "🟡"
+        );
+        assert_eq!(theme.rotation(Some(Segment::Tail(180))), Some(180));
+        assert_eq!(theme.rotation(Some(Segment::Head(0))), None);
+        assert_eq!(theme.rotation(Some(Segment::Vertical)), None);
+    }
+}
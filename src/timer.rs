@@ -0,0 +1,192 @@
+// Keeps track of how long someone has been playing without a break, for the break reminder, and
+// how long the current run has been going. Times are in milliseconds from whatever clock the
+// caller uses, `Date.now()` or `performance.now()` in the browser.
+
+// not playing for this long counts as taking a break
+pub const BREAK_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+#[derive(Default, Debug)]
+pub struct PlayTimer {
+    // when the current stretch of play started, `None` before the first tick
+    started_ms: Option<f64>,
+    last_played_ms: f64,
+    // how many reminders went out during this stretch
+    reminders: u32,
+}
+
+impl PlayTimer {
+    pub fn update(&mut self, now_ms: f64, playing: bool) {
+        if !playing {
+            return;
+        }
+
+        if self.started_ms.is_none() || now_ms - self.last_played_ms > BREAK_MS {
+            self.started_ms = Some(now_ms);
+            self.reminders = 0;
+        }
+
+        self.last_played_ms = now_ms;
+    }
+
+    pub fn played_ms(&self) -> f64 {
+        match self.started_ms {
+            Some(started_ms) => self.last_played_ms - started_ms,
+            None => 0.0,
+        }
+    }
+
+    // Returns the minutes played once every `every_minutes` of continuous play. 0 turns
+    // reminders off.
+    pub fn take_reminder(&mut self, every_minutes: u32) -> Option<u32> {
+        if every_minutes == 0 {
+            return None;
+        }
+
+        let minutes = (self.played_ms() / 60_000.0) as u32;
+
+        if minutes >= (self.reminders + 1) * every_minutes {
+            self.reminders = minutes / every_minutes;
+            Some(minutes)
+        } else {
+            None
+        }
+    }
+}
+
+// How long the current run has really been played, leaving out pauses, countdowns, game overs,
+// and anything else that kept it from ticking. Unlike `SnakeGame::elapsed_ms`, which adds up
+// what the ticks were meant to take, this goes by the clock, so a page that fell behind shows
+// it.
+#[derive(Default, Debug)]
+pub struct RunClock {
+    elapsed_ms: f64,
+    // the last update, while the run was going
+    last_ms: Option<f64>,
+    // which run this is, to notice restarts
+    seed: [u16; 2],
+    ticks: usize,
+}
+
+impl RunClock {
+    // `seed` and `ticks` are the game's, and a new seed or fewer ticks means a new run.
+    // `running` is whether the run is going now, and time counts from an update that saw it
+    // going until the next one, which is where a pause or game over gets noticed.
+    pub fn update(&mut self, now_ms: f64, seed: [u16; 2], ticks: usize, running: bool) {
+        if seed != self.seed || ticks < self.ticks {
+            self.elapsed_ms = 0.0;
+            self.last_ms = None;
+        }
+        self.seed = seed;
+        self.ticks = ticks;
+
+        if let Some(last_ms) = self.last_ms {
+            self.elapsed_ms += (now_ms - last_ms).max(0.0);
+        }
+        self.last_ms = running.then_some(now_ms);
+    }
+
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+}
+
+// like 1:05, or 1:02:03 past an hour
+pub fn clock_text(ms: f64) -> String {
+    let seconds = (ms.max(0.0) / 1000.0) as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    match hours {
+        0 => format!("{minutes}:{seconds:02}"),
+        _ => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+// like clock_text, to a tenth of a second, for how long a race took
+pub fn race_text(ms: usize) -> String {
+    format!("{}.{}", clock_text(ms as f64), ms % 1000 / 100)
+}
+
+// like race_text, to a hundredth, for speedrun splits
+pub fn split_text(ms: f64) -> String {
+    let ms = ms.max(0.0) as u64;
+    format!("{}.{:02}", clock_text(ms as f64), ms % 1000 / 10)
+}
+
+// how far ahead or behind a split is, like -0.52 or +12.30
+pub fn delta_text(ms: i64) -> String {
+    let sign = if ms <= 0 { '-' } else { '+' };
+    let ms = ms.unsigned_abs();
+    format!("{sign}{}.{:02}", ms / 1000, ms % 1000 / 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: f64 = 60_000.0;
+
+    #[test]
+    fn reminds_after_continuous_play() {
+        let mut timer = PlayTimer::default();
+
+        for minute in 0..100 {
+            timer.update(minute as f64 * MINUTE, true);
+            let reminder = timer.take_reminder(45);
+
+            match minute {
+                45 | 90 => assert_eq!(reminder, Some(minute)),
+                _ => assert_eq!(reminder, None),
+            }
+        }
+
+        assert_eq!(timer.take_reminder(0), None);
+    }
+
+    #[test]
+    fn breaks_reset_the_timer() {
+        let mut timer = PlayTimer::default();
+
+        timer.update(0.0, true);
+        timer.update(40.0 * MINUTE, true);
+        // sitting on the game over screen doesn't count
+        timer.update(44.0 * MINUTE, false);
+        timer.update(50.0 * MINUTE, true);
+
+        assert_eq!(timer.take_reminder(45), None);
+        assert_eq!(timer.played_ms(), 0.0);
+
+        for minute in 51..95 {
+            timer.update(minute as f64 * MINUTE, true);
+            assert_eq!(timer.take_reminder(45), None);
+        }
+        timer.update(95.0 * MINUTE, true);
+        assert_eq!(timer.take_reminder(45), Some(45));
+    }
+
+    #[test]
+    fn run_clock() {
+        let mut clock = RunClock::default();
+
+        clock.update(1000.0, [1, 2], 0, true);
+        clock.update(1500.0, [1, 2], 5, true);
+        // a pause, and the first frame after it only picks the time back up
+        clock.update(1600.0, [1, 2], 6, false);
+        clock.update(9000.0, [1, 2], 6, true);
+        clock.update(9250.0, [1, 2], 8, true);
+        assert_eq!(clock.elapsed_ms(), 850.0);
+        assert_eq!(clock_text(clock.elapsed_ms()), "0:00");
+
+        // starting over
+        clock.update(9300.0, [1, 2], 0, true);
+        assert_eq!(clock.elapsed_ms(), 0.0);
+        clock.update(9400.0, [3, 4], 1, true);
+        assert_eq!(clock.elapsed_ms(), 0.0);
+
+        assert_eq!(clock_text(65_000.0), "1:05");
+        assert_eq!(clock_text(3_723_000.0), "1:02:03");
+        assert_eq!(race_text(65_380), "1:05.3");
+        assert_eq!(split_text(65_386.0), "1:05.38");
+        assert_eq!(delta_text(-520), "-0.52");
+        assert_eq!(delta_text(12_300), "+12.30");
+    }
+}
@@ -0,0 +1,294 @@
+use crate::geometry::Direction;
+use crate::mode::GameMode;
+use crate::random;
+use crate::simulate;
+use crate::snake::SnakeGame;
+
+use std::cell::{Cell, RefCell};
+
+// The title screen, shown when the page opens and again when nobody's touched anything for a
+// while between runs. A bot plays a demo game on the board behind the menu, on a game of its own
+// with its own random numbers, so the player's run waits underneath exactly as it was. The up
+// and down keys pick from the menu and enter or space goes with it.
+
+// how long without any input, while the run's paused or over, before the title comes back
+pub const IDLE_MS: f64 = 60_000.0;
+// ticks the demo sits crashed before it starts over
+const DEMO_RESTART_TICKS: usize = 20;
+// the little snake crawling along under the name, in characters
+const BANNER_WIDTH: usize = 16;
+const BANNER_SNAKE_LENGTH: usize = 5;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Entry {
+    Play,
+    Settings,
+    Stats,
+    Daily,
+    Tutorial,
+}
+
+impl Entry {
+    pub const ALL: [Entry; 5] = [
+        Entry::Play,
+        Entry::Settings,
+        Entry::Stats,
+        Entry::Daily,
+        Entry::Tutorial,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Entry::Play => "play",
+            Entry::Settings => "settings",
+            Entry::Stats => "stats",
+            Entry::Daily => "daily challenge",
+            Entry::Tutorial => "how to play",
+        }
+    }
+}
+
+struct Demo {
+    game: SnakeGame,
+    random: random::Aside,
+    // since it crashed
+    over_ticks: usize,
+}
+
+impl Demo {
+    fn new(width: isize, height: isize) -> Demo {
+        let now = js_sys::Date::now() as u64;
+        let mut random = random::Aside::new([now as u16, (now >> 16) as u16]);
+
+        random::swap(&mut random);
+        let game = SnakeGame::new(width, height);
+        random::swap(&mut random);
+
+        Demo {
+            game,
+            random,
+            over_ticks: 0,
+        }
+    }
+
+    fn tick(&mut self) {
+        random::swap(&mut self.random);
+
+        if self.game.is_game_over() {
+            self.over_ticks += 1;
+            if self.over_ticks >= DEMO_RESTART_TICKS {
+                self.over_ticks = 0;
+                self.game.restart();
+            }
+        } else {
+            if let Some(direction) = simulate::greedy(&self.game) {
+                self.game.change_direction(direction);
+            }
+            self.game.tick();
+        }
+        // nothing's listening, the same as a bot game
+        self.game.take_events();
+
+        random::swap(&mut self.random);
+    }
+}
+
+thread_local! {
+    static DEMO: RefCell<Option<Demo>> = const { RefCell::new(None) };
+    // into `Entry::ALL`
+    static SELECTED: Cell<usize> = const { Cell::new(0) };
+    // whether showing it is what paused the run, so closing it only carries on what it stopped
+    static PAUSED_GAME: Cell<bool> = const { Cell::new(false) };
+    static LAST_INPUT_MS: Cell<f64> = const { Cell::new(0.0) };
+}
+
+pub fn is_showing() -> bool {
+    DEMO.with(|demo| demo.borrow().is_some())
+}
+
+pub fn show() {
+    crate::GAME.with(|game| {
+        let mut game = game.borrow_mut();
+
+        let pausing = !game.paused && !game.is_game_over();
+        game.paused = true;
+        PAUSED_GAME.with(|paused_game| paused_game.set(pausing));
+
+        let demo = Demo::new(game.width, game.height);
+        DEMO.with(|shown| *shown.borrow_mut() = Some(demo));
+    });
+    SELECTED.with(|selected| selected.set(0));
+
+    crate::accessibility::announce("slake, up and down to choose, enter to pick. play");
+    crate::draw();
+}
+
+// `resume` carries on the run if showing the title is what paused it, otherwise it stays paused
+// for the next key
+fn close(resume: bool) {
+    DEMO.with(|demo| demo.borrow_mut().take());
+    note_input(crate::now_ms());
+
+    if PAUSED_GAME.with(|paused_game| paused_game.take()) && resume {
+        crate::GAME.with(|game| {
+            let mut game = game.borrow_mut();
+            game.paused = false;
+            // a moment to find the keys again
+            game.start_countdown();
+        });
+    }
+}
+
+pub fn note_input(now_ms: f64) {
+    LAST_INPUT_MS.with(|last_input_ms| last_input_ms.set(now_ms));
+}
+
+// Brings the title back if the run's been sitting paused or over for `IDLE_MS`. Not while
+// playing online, where the other player's still there, nor on a page that's embedded, which
+// the embedding page starts and stops.
+pub fn check_idle(now_ms: f64) {
+    if is_showing()
+        || crate::net::is_active()
+        || crate::editor::is_editing()
+        || crate::embed::is_embedded()
+    {
+        return;
+    }
+
+    let waiting = crate::GAME.with(|game| {
+        let game = game.borrow();
+        game.paused || game.is_game_over()
+    });
+    if waiting && now_ms - LAST_INPUT_MS.with(|last_input_ms| last_input_ms.get()) >= IDLE_MS {
+        show();
+    }
+}
+
+// one of the demo's ticks, in place of the run's
+pub fn tick() {
+    DEMO.with(|demo| {
+        if let Some(demo) = demo.borrow_mut().as_mut() {
+            demo.tick();
+        }
+    });
+}
+
+// the demo game, to draw instead of the run
+pub fn with_demo<R>(f: impl FnOnce(&SnakeGame) -> R) -> Option<R> {
+    DEMO.with(|demo| demo.borrow().as_ref().map(|demo| f(&demo.game)))
+}
+
+// `selected` moved by `step`, going round at either end
+fn moved(selected: usize, step: isize) -> usize {
+    (selected as isize + step).rem_euclid(Entry::ALL.len() as isize) as usize
+}
+
+// Takes a key while the title's showing, by what it's bound to steer or its name. Returns whether
+// it did anything with it.
+pub fn key(key: &str, steering: Option<Direction>) -> bool {
+    let step = match (key, steering) {
+        (_, Some(Direction::Up)) => -1,
+        (_, Some(Direction::Down)) => 1,
+        ("Enter" | " ", _) => {
+            choose(Entry::ALL[SELECTED.with(|selected| selected.get())]);
+            return true;
+        }
+        _ => return false,
+    };
+
+    let selected = SELECTED.with(|selected| {
+        selected.set(moved(selected.get(), step));
+        selected.get()
+    });
+    crate::accessibility::announce(Entry::ALL[selected].label());
+    crate::draw();
+    true
+}
+
+// the panels open over a run that's still paused, so it doesn't start behind them
+fn choose(entry: Entry) {
+    close(entry == Entry::Play);
+
+    match entry {
+        Entry::Play => {
+            let over = crate::GAME.with(|game| {
+                let mut game = game.borrow_mut();
+                if game.paused && !game.is_game_over() {
+                    game.paused = false;
+                    game.start_countdown();
+                }
+                game.is_game_over()
+            });
+            if over {
+                crate::restart_game();
+            }
+        }
+        Entry::Settings => crate::error::report_failure(crate::toggle_settings_panel()),
+        Entry::Stats => crate::error::report_failure(crate::stats::toggle_panel()),
+        Entry::Daily => {
+            crate::update_settings(|settings| settings.mode = GameMode::Daily);
+            // a fresh one even if the last run was a daily too
+            crate::restart_game();
+        }
+        Entry::Tutorial => crate::tutorial::start(),
+    }
+    crate::draw();
+}
+
+// a snake going round and round, a step a tick
+fn banner(ticks: usize) -> String {
+    let head = ticks % BANNER_WIDTH;
+
+    (0..BANNER_WIDTH)
+        .map(|x| match (head + BANNER_WIDTH - x) % BANNER_WIDTH {
+            0 => '@',
+            behind if behind < BANNER_SNAKE_LENGTH => 'o',
+            _ => '·',
+        })
+        .collect()
+}
+
+fn menu_text(selected: usize, ticks: usize) -> String {
+    let entries = Entry::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let marker = if index == selected { ">" } else { " " };
+            format!("{marker} {}", entry.label())
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "slake\n{}\n\n{}\n\nup and down to choose, enter to pick",
+        banner(ticks),
+        entries.join("\n")
+    )
+}
+
+pub fn text() -> String {
+    let ticks = with_demo(|game| game.ticks).unwrap_or(0);
+    menu_text(SELECTED.with(|selected| selected.get()), ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu() {
+        assert_eq!(banner(0).chars().count(), BANNER_WIDTH);
+        assert!(banner(6).starts_with("··oooo@·"));
+        // the tail comes round the other side
+        assert!(banner(1).starts_with("o@··"));
+        assert!(banner(1).ends_with("ooo"));
+        assert_eq!(banner(BANNER_WIDTH + 3), banner(3));
+
+        assert_eq!(moved(0, 1), 1);
+        assert_eq!(moved(0, -1), Entry::ALL.len() - 1);
+        assert_eq!(moved(Entry::ALL.len() - 1, 1), 0);
+
+        let text = menu_text(1, 0);
+        assert!(text.starts_with("slake\n@"));
+        assert!(text.contains("\n  play\n> settings\n  stats\n"));
+    }
+}
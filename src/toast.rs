@@ -0,0 +1,40 @@
+use crate::error::SlakeError;
+
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::HtmlDivElement;
+
+// Small messages that pop up at the bottom of the page for a few seconds and then go away on
+// their own, without getting in the way of the game.
+
+const TOAST_MS: i32 = 5000;
+
+pub fn show(message: &str) -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+
+    // all the toasts share one container, so they stack instead of drawing over each other
+    let container = match document.get_element_by_id("toasts") {
+        Some(container) => container,
+        None => {
+            let container = document.create_element("div")?;
+            container.set_id("toasts");
+            container.set_class_name("toasts");
+            crate::error::body()?.append_child(&container)?;
+            container
+        }
+    };
+
+    let toast_element = document
+        .create_element("div")?
+        .dyn_into::<HtmlDivElement>()?;
+
+    toast_element.set_class_name("toast");
+    toast_element.set_inner_text(message);
+    container.append_child(&toast_element)?;
+
+    let remove = Closure::once_into_js(move || toast_element.remove());
+
+    crate::error::window()?
+        .set_timeout_with_callback_and_timeout_and_arguments_0(remove.unchecked_ref(), TOAST_MS)?;
+
+    Ok(())
+}
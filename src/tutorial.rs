@@ -0,0 +1,188 @@
+use std::cell::RefCell;
+
+// A walk through the basics for someone who's never played, a prompt at a time over the board:
+// steering, eating, the puddles eating leaves behind, and starting over. Each prompt stays up
+// until it's been done, and crashing partway through only means starting over and picking up
+// where it left off. It starts by itself the first time the page is opened, in place of the
+// title screen, and again whenever it's picked from the title's menu. Escape skips it.
+
+const SEEN_KEY: &str = "slake_tutorial";
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Step {
+    Steer,
+    Eat,
+    Puddles,
+    Restart,
+}
+
+impl Step {
+    pub fn prompt(&self) -> &'static str {
+        match self {
+            Step::Steer => "use the arrow keys to steer your snake",
+            Step::Eat => "go and eat the fruit",
+            Step::Puddles => {
+                "every fruit leaves a puddle behind, and slipping on one ends the run\n\
+steer clear of it and eat another"
+            }
+            Step::Restart => {
+                "so do walls, and your own tail\n\
+when a run's over, press space to start another"
+            }
+        }
+    }
+}
+
+// what the player did, as far as the tutorial cares
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+    Steered,
+    Ate,
+    Crashed,
+    Restarted,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Tutorial {
+    step: Step,
+    // the run ended before the step was done, so it's starting over first
+    crashed: bool,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Tutorial {
+            step: Step::Steer,
+            crashed: false,
+        }
+    }
+}
+
+impl Tutorial {
+    pub fn prompt(&self) -> &'static str {
+        match self.crashed && self.step != Step::Restart {
+            true => "ouch! press space to start over and carry on",
+            false => self.step.prompt(),
+        }
+    }
+
+    // Moves on if `action` is what the step was waiting for. Returns whether that was the end
+    // of it.
+    pub fn advance(&mut self, action: Action) -> bool {
+        match (self.step, action) {
+            (Step::Restart, Action::Restarted) => return true,
+            (_, Action::Crashed) => self.crashed = true,
+            (_, Action::Restarted) => self.crashed = false,
+            // the crash has to be dealt with first
+            _ if self.crashed => (),
+            (Step::Steer, Action::Steered) => self.step = Step::Eat,
+            (Step::Eat, Action::Ate) => self.step = Step::Puddles,
+            (Step::Puddles, Action::Ate) => self.step = Step::Restart,
+            _ => (),
+        }
+        false
+    }
+}
+
+thread_local! {
+    static RUNNING: RefCell<Option<Tutorial>> = const { RefCell::new(None) };
+}
+
+pub fn is_running() -> bool {
+    RUNNING.with(|running| running.borrow().is_some())
+}
+
+// whether it's been done or skipped before, on this browser
+pub fn seen() -> bool {
+    crate::local_storage()
+        .and_then(|storage| storage.get_item(SEEN_KEY).ok().flatten())
+        .is_some()
+}
+
+fn finish() {
+    RUNNING.with(|running| running.borrow_mut().take());
+    if let Some(storage) = crate::local_storage() {
+        // it only means showing it again if this doesn't save
+        let _ = storage.set_item(SEEN_KEY, "done");
+    }
+}
+
+// on a fresh run, so there's nothing in the way
+pub fn start() {
+    let tutorial = Tutorial::default();
+    crate::accessibility::announce(tutorial.prompt());
+    RUNNING.with(|running| *running.borrow_mut() = Some(tutorial));
+    crate::restart_game();
+}
+
+pub fn skip() {
+    finish();
+    let _ = crate::toast::show("Tutorial skipped");
+    crate::draw();
+}
+
+pub fn note(action: Action) {
+    let (finished, changed) = RUNNING.with(|running| {
+        let mut running = running.borrow_mut();
+        let Some(tutorial) = running.as_mut() else {
+            return (false, None);
+        };
+
+        let before = tutorial.prompt();
+        let finished = tutorial.advance(action);
+        (
+            finished,
+            Some(tutorial.prompt()).filter(|prompt| *prompt != before),
+        )
+    });
+
+    if finished {
+        finish();
+        let _ = crate::toast::show("That's everything, have fun!");
+    } else if let Some(prompt) = changed {
+        crate::accessibility::announce(prompt);
+    }
+}
+
+// to show over the board, while it's going
+pub fn prompt() -> Option<String> {
+    RUNNING.with(|running| {
+        running
+            .borrow()
+            .as_ref()
+            .map(|tutorial| format!("{}\n(escape skips the tutorial)", tutorial.prompt()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps() {
+        let mut tutorial = Tutorial::default();
+        assert_eq!(tutorial.step, Step::Steer);
+
+        // eating before steering doesn't skip ahead
+        assert!(!tutorial.advance(Action::Ate));
+        assert_eq!(tutorial.step, Step::Steer);
+        assert!(!tutorial.advance(Action::Steered));
+        assert!(!tutorial.advance(Action::Ate));
+        assert_eq!(tutorial.step, Step::Puddles);
+
+        // crashing waits for a restart, then carries on from the same step
+        assert!(!tutorial.advance(Action::Crashed));
+        assert!(tutorial.prompt().contains("space"));
+        assert!(!tutorial.advance(Action::Ate));
+        assert_eq!(tutorial.step, Step::Puddles);
+        assert!(!tutorial.advance(Action::Restarted));
+        assert_eq!(tutorial.prompt(), Step::Puddles.prompt());
+
+        assert!(!tutorial.advance(Action::Ate));
+        assert_eq!(tutorial.step, Step::Restart);
+        // the last step is the one crash that's expected
+        assert!(!tutorial.advance(Action::Crashed));
+        assert_eq!(tutorial.prompt(), Step::Restart.prompt());
+        assert!(tutorial.advance(Action::Restarted));
+    }
+}
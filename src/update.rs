@@ -0,0 +1,99 @@
+use crate::error::SlakeError;
+use crate::keys;
+use crate::keys::Action;
+use crate::version;
+use crate::SETTINGS;
+
+use std::cell::RefCell;
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::HtmlElement;
+
+// New builds, for pages that install the game as an app that works offline. The game doesn't
+// run a service worker of its own, but the page's can ask which cache this build goes in and
+// what to put there, see `version::offline_manifest`. Once the worker finds a newer build
+// waiting, the page passes its asset version on with `slake_update_available`, and a notice
+// goes up across the top. Until the page is reloaded, the quick start key reloads it instead.
+
+thread_local! {
+    // the asset version of the build that's waiting, see `version::ASSET_VERSION`
+    static WAITING: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// whether `assets`, from the page, is some other build than this one
+fn is_update(assets: &str) -> bool {
+    let assets = assets.trim();
+    !assets.is_empty() && assets != version::ASSET_VERSION
+}
+
+// Puts the notice up if `assets` is a new build, and returns whether it was.
+pub fn announce(assets: &str) -> bool {
+    if !is_update(assets) {
+        return false;
+    }
+
+    WAITING.with(|waiting| *waiting.borrow_mut() = Some(assets.trim().to_string()));
+    // the page is still fine as it is without it
+    let _ = show_notice();
+    true
+}
+
+pub fn is_waiting() -> bool {
+    WAITING.with(|waiting| waiting.borrow().is_some())
+}
+
+// with the quick start key it says to press, if there is one
+fn notice_text(key: Option<&str>) -> String {
+    match key {
+        Some(key) => format!(
+            "A new version of slake is ready, press {} to reload",
+            keys::key_label(key)
+        ),
+        None => "A new version of slake is ready, reload to play it".to_string(),
+    }
+}
+
+fn show_notice() -> Result<(), SlakeError> {
+    let document = crate::error::document()?;
+    if document.get_element_by_id("update_notice").is_some() {
+        return Ok(());
+    }
+
+    let notice = document.create_element("div")?.dyn_into::<HtmlElement>()?;
+    notice.set_id("update_notice");
+    notice.set_class_name("update_notice");
+    let key = SETTINGS.with(|settings| {
+        let settings = settings.borrow();
+        settings
+            .key_bindings
+            .keys(Action::QuickStart)
+            .first()
+            .cloned()
+    });
+    notice.set_inner_text(&notice_text(key.as_deref()));
+
+    let handle_click = Closure::once_into_js(crate::error::reload);
+    notice.set_onclick(Some(handle_click.unchecked_ref()));
+    crate::error::body()?.prepend_with_node_1(&notice)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates() {
+        // never a real one, which is all hex digits
+        assert!(is_update("next-build"));
+        assert!(!is_update(version::ASSET_VERSION));
+        assert!(!is_update(&format!(" {}\n", version::ASSET_VERSION)));
+        assert!(!is_update(""));
+
+        assert_eq!(
+            notice_text(Some("r")),
+            "A new version of slake is ready, press R to reload"
+        );
+        assert!(notice_text(None).ends_with("reload to play it"));
+    }
+}
@@ -0,0 +1,334 @@
+use crate::geometry::Grid;
+use crate::geometry::Topology;
+use crate::snake::SnakeGame;
+
+use serde::{Deserialize, Serialize};
+
+// Which build this is. Shown at the bottom of the settings, and saved along with anything that
+// only plays back the same way on the same rules, like fuzzer cases.
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// "unknown" when built outside of git
+pub const GIT_HASH: &str = env!("SLAKE_GIT_HASH");
+// comma separated, empty without any
+pub const FEATURES: &str = env!("SLAKE_FEATURES");
+// eight hex digits that change with anything in the page, the code included, see `build.rs`.
+// offline caches go by this rather than `VERSION`, which only changes on releases
+pub const ASSET_VERSION: &str = env!("SLAKE_ASSET_VERSION");
+
+// what an installed copy needs to keep around to start up offline, relative to the page
+const ASSETS: [&str; 3] = ["index.html", "pkg/slake.js", "pkg/slake_bg.wasm"];
+
+// Bump this whenever a change means a recorded run would play out differently, like food
+// spawning somewhere else or the random numbers getting used in another order. Runs recorded
+// under another number are refused instead of drifting off partway through.
+pub const RULES_VERSION: u32 = 6;
+
+// What each rules version changed, oldest first, for players comparing runs across releases.
+// Add a line whenever `RULES_VERSION` goes up. Shown in the what's new overlay, see `about`.
+pub const RULE_CHANGES: [(u32, &str); 6] = [
+    (
+        1,
+        "recorded runs and saved games started noting the rules they were played under",
+    ),
+    (
+        2,
+        "food only spawns where a snake can get to it, and can be kept from landing right in front of one",
+    ),
+    (
+        3,
+        "bonus and rotten fruit, stuns, and combos last the same time whatever the speed, instead of the same number of moves",
+    ),
+    (
+        4,
+        "speed fruit turns up along with bonus and rotten fruit, and sends the snake a tile and a half a tick for a while",
+    ),
+    (
+        5,
+        "puddles don't stack up on one tile, and food keeps to open tiles away from puddles in zen, time attack, and closing in",
+    ),
+    (
+        6,
+        "shrink berries turn up along with the other special fruit once a snake's long enough, and take three tiles off its tail",
+    ),
+];
+
+// what changed after `rules`, up to this build's, oldest first
+pub fn changes_since(rules: u32) -> Vec<&'static str> {
+    RULE_CHANGES
+        .iter()
+        .filter(|(version, _)| *version > rules)
+        .map(|(_, change)| *change)
+        .collect()
+}
+
+pub fn features() -> Vec<&'static str> {
+    FEATURES
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+// like "0.1.0 (1a2b3c4)", with any features after the hash
+pub fn version_string() -> String {
+    let mut build = vec![GIT_HASH];
+    build.extend(features());
+
+    format!("{VERSION} ({})", build.join(", "))
+}
+
+// for the host page, see `api::slake_version`
+pub fn version_json() -> String {
+    let features = features()
+        .iter()
+        .map(|name| format!("\"{name}\""))
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"{{"version":"{VERSION}","git_hash":"{GIT_HASH}","rules":{RULES_VERSION},"assets":"{ASSET_VERSION}","features":[{}]}}"#,
+        features.join(",")
+    )
+}
+
+// `path` with the asset version on the end, so a cache that's gone by the URL misses once
+// there's a new build
+pub fn asset_url(path: &str) -> String {
+    format!("./{path}?v={ASSET_VERSION}")
+}
+
+// For a service worker to cache the game with, see `api::slake_offline_manifest`: which cache
+// this build goes in, and what to put there. Like
+// `{"cache":"slake-89abcdef","assets":["./","./index.html?v=89abcdef",...]}`
+pub fn offline_manifest() -> String {
+    let assets = std::iter::once("./".to_string())
+        .chain(ASSETS.iter().map(|path| asset_url(path)))
+        .map(|url| format!("\"{url}\""))
+        .collect::<Vec<_>>();
+    format!(
+        r#"{{"cache":"slake-{ASSET_VERSION}","assets":[{}]}}"#,
+        assets.join(",")
+    )
+}
+
+// What a recorded run needs to play back the same way: the rules, the board, and the build.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub rules: u32,
+    pub width: isize,
+    pub height: isize,
+    pub topology: Topology,
+    pub grid: Grid,
+    // the `version_string` of the build it came from
+    pub build: String,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Compatibility {
+    Compatible,
+    // from another build with the same rules, which should still play back fine but is worth a
+    // warning
+    OtherBuild(String),
+    // won't play back the same way, with why
+    Incompatible(String),
+}
+
+impl Fingerprint {
+    // for a square board, on this build
+    pub fn new(width: isize, height: isize, topology: Topology) -> Fingerprint {
+        Fingerprint {
+            rules: RULES_VERSION,
+            width,
+            height,
+            topology,
+            grid: Grid::Square,
+            build: version_string(),
+        }
+    }
+
+    pub fn of(game: &SnakeGame) -> Fingerprint {
+        Fingerprint {
+            grid: game.grid,
+            ..Fingerprint::new(game.width, game.height, game.topology)
+        }
+    }
+
+    // like "21x15", or "21x15 hex" for hexagons
+    fn board(&self) -> String {
+        match self.grid {
+            Grid::Square => format!("{}x{}", self.width, self.height),
+            grid => format!("{}x{} {}", self.width, self.height, grid.name()),
+        }
+    }
+
+    // like "1:21x15:walls:0.1.0 (1a2b3c4)". square boards leave the grid out, the way they were
+    // saved before there were any others
+    pub fn serialize(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.rules,
+            self.board(),
+            self.topology.name(),
+            self.build
+        )
+    }
+
+    pub fn parse(source: &str) -> Option<Fingerprint> {
+        let mut parts = source.trim().splitn(4, ':');
+        let rules = parts.next()?.parse().ok()?;
+        let board = parts.next()?;
+        let (size, grid) = match board.split_once(' ') {
+            Some((size, grid)) => (size, Grid::from_name(grid)?),
+            None => (board, Grid::Square),
+        };
+        let (width, height) = size.split_once('x')?;
+        let topology = Topology::from_name(parts.next()?)?;
+        let build = parts.next()?.to_string();
+
+        Some(Fingerprint {
+            rules,
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            topology,
+            grid,
+            build,
+        })
+    }
+
+    // whether a run recorded with this fingerprint can be played back as `current`
+    pub fn check(&self, current: &Fingerprint) -> Compatibility {
+        if self.rules != current.rules {
+            let mut reason = format!(
+                "it was recorded with rules version {}, but this build has {}",
+                self.rules, current.rules
+            );
+            // whatever's gone the other way, this build can't know about
+            let changes = changes_since(self.rules);
+            if self.rules < current.rules && !changes.is_empty() {
+                reason += &format!(". Since then, {}", changes.join("; "));
+            }
+
+            return Compatibility::Incompatible(reason);
+        }
+
+        if (self.width, self.height, self.topology, self.grid)
+            != (
+                current.width,
+                current.height,
+                current.topology,
+                current.grid,
+            )
+        {
+            return Compatibility::Incompatible(format!(
+                "it was recorded on a {} {} board, not {} {}",
+                self.board(),
+                self.topology.name(),
+                current.board(),
+                current.topology.name()
+            ));
+        }
+
+        if self.build != current.build {
+            return Compatibility::OtherBuild(format!(
+                "it was recorded by slake {}, and this is {}",
+                self.build, current.build
+            ));
+        }
+
+        Compatibility::Compatible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_info() {
+        assert!(version_string().starts_with(&format!("{VERSION} ({GIT_HASH}")));
+        assert!(version_json().contains(&format!(r#""version":"{VERSION}""#)));
+        assert!(!features().contains(&""));
+        assert!(version_json().contains(&format!(r#""rules":{RULES_VERSION}"#)));
+
+        assert_eq!(ASSET_VERSION.len(), 8);
+        assert!(ASSET_VERSION.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(version_json().contains(&format!(r#""assets":"{ASSET_VERSION}""#)));
+        assert_eq!(
+            asset_url("pkg/slake.js"),
+            format!("./pkg/slake.js?v={ASSET_VERSION}")
+        );
+        assert!(offline_manifest().starts_with(&format!(
+            r#"{{"cache":"slake-{ASSET_VERSION}","assets":["./","./index.html?v="#
+        )));
+        assert!(offline_manifest().contains(&asset_url("pkg/slake_bg.wasm")));
+    }
+
+    #[test]
+    fn rule_changes() {
+        // one for every version, the newest being this build's
+        for (index, (version, _)) in RULE_CHANGES.iter().enumerate() {
+            assert_eq!(*version, index as u32 + 1);
+        }
+        assert_eq!(RULE_CHANGES.last().unwrap().0, RULES_VERSION);
+
+        assert_eq!(changes_since(RULES_VERSION), Vec::<&str>::new());
+        assert_eq!(changes_since(RULES_VERSION - 1), vec![RULE_CHANGES[5].1]);
+
+        let saved = Fingerprint {
+            rules: 1,
+            ..Fingerprint::new(21, 15, Topology::Walls)
+        };
+        let Compatibility::Incompatible(reason) =
+            saved.check(&Fingerprint::new(21, 15, Topology::Walls))
+        else {
+            panic!("rules 1 should be refused");
+        };
+        assert!(reason.contains(RULE_CHANGES[1].1));
+    }
+
+    #[test]
+    fn fingerprints() {
+        let current = Fingerprint::new(21, 15, Topology::Walls);
+        assert_eq!(
+            Fingerprint::parse(&current.serialize()),
+            Some(current.clone())
+        );
+        assert_eq!(current.check(&current), Compatibility::Compatible);
+
+        let saved =
+            Fingerprint::parse(&format!("{RULES_VERSION}:21x15:walls:0.0.1 (abcdef0)")).unwrap();
+        assert!(matches!(
+            saved.check(&current),
+            Compatibility::OtherBuild(_)
+        ));
+
+        let saved = Fingerprint {
+            rules: RULES_VERSION + 1,
+            ..current.clone()
+        };
+        assert!(matches!(
+            saved.check(&current),
+            Compatibility::Incompatible(_)
+        ));
+
+        let saved = Fingerprint::new(21, 15, Topology::Wrap);
+        assert!(matches!(
+            saved.check(&current),
+            Compatibility::Incompatible(_)
+        ));
+
+        let saved = Fingerprint {
+            grid: Grid::Hex,
+            ..current.clone()
+        };
+        assert_eq!(Fingerprint::parse(&saved.serialize()), Some(saved.clone()));
+        assert!(saved.serialize().contains(":21x15 hex:walls:"));
+        let Compatibility::Incompatible(reason) = saved.check(&current) else {
+            panic!("a hex board should be refused on a square one");
+        };
+        assert!(reason.ends_with("21x15 hex walls board, not 21x15 walls"));
+
+        assert_eq!(Fingerprint::parse("1:21by15:walls:0.1.0"), None);
+        assert_eq!(Fingerprint::parse("1:21x15 round:walls:0.1.0"), None);
+        assert_eq!(Fingerprint::parse("1:21x15:walls"), None);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::food::FoodKind;
+use crate::geometry;
+use crate::geometry::Direction;
+use crate::geometry::Geometry;
+use crate::geometry::Vector;
+use crate::snake::Snake;
+use crate::snake::SnakeGame;
+
+// What's on each tile, worked out once for the whole board, for renderers and anything else
+// that just wants to look. Where a tile has more than one thing on it, like a head over a
+// puddle, it's whichever matters most for drawing.
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TileKind {
+    Empty,
+    Food,
+    BonusFood,
+    RottenFood,
+    GoldenFood,
+    SpeedFood,
+    ShrinkFood,
+    Head,
+    Tail,
+    Body,
+    // the second player's snake in versus
+    RivalHead,
+    RivalTail,
+    RivalBody,
+    Hazard,
+    // see `enemy`
+    Enemy,
+    // which pair it's in, in level order, so each pair can look different
+    Portal(u8),
+    // see `Level::ladders`
+    Ladder,
+    Wall,
+}
+
+impl TileKind {
+    // every kind but portals, which there's one of for each pair
+    pub const ALL: [TileKind; 17] = [
+        TileKind::Empty,
+        TileKind::Food,
+        TileKind::BonusFood,
+        TileKind::RottenFood,
+        TileKind::GoldenFood,
+        TileKind::SpeedFood,
+        TileKind::ShrinkFood,
+        TileKind::Head,
+        TileKind::Tail,
+        TileKind::Body,
+        TileKind::RivalHead,
+        TileKind::RivalTail,
+        TileKind::RivalBody,
+        TileKind::Hazard,
+        TileKind::Enemy,
+        TileKind::Ladder,
+        TileKind::Wall,
+    ];
+
+    // for the page's stylesheet, like "tile-bonus-food" or "tile-portal-2"
+    pub fn class_name(&self) -> String {
+        let name = match self {
+            TileKind::Empty => "tile-empty",
+            TileKind::Food => "tile-food",
+            TileKind::BonusFood => "tile-bonus-food",
+            TileKind::RottenFood => "tile-rotten-food",
+            TileKind::GoldenFood => "tile-golden-food",
+            TileKind::SpeedFood => "tile-speed-food",
+            TileKind::ShrinkFood => "tile-shrink-food",
+            TileKind::Head => "tile-head",
+            TileKind::Tail => "tile-tail",
+            TileKind::Body => "tile-body",
+            TileKind::RivalHead => "tile-rival-head",
+            TileKind::RivalTail => "tile-rival-tail",
+            TileKind::RivalBody => "tile-rival-body",
+            TileKind::Hazard => "tile-hazard",
+            TileKind::Enemy => "tile-enemy",
+            TileKind::Portal(pair) => return format!("tile-portal-{pair}"),
+            TileKind::Ladder => "tile-ladder",
+            TileKind::Wall => "tile-wall",
+        };
+
+        name.to_string()
+    }
+
+    // (head, tail, body) for one player's snakes
+    pub fn snake_parts(player: usize) -> (TileKind, TileKind, TileKind) {
+        match player {
+            0 => (TileKind::Head, TileKind::Tail, TileKind::Body),
+            _ => (
+                TileKind::RivalHead,
+                TileKind::RivalTail,
+                TileKind::RivalBody,
+            ),
+        }
+    }
+
+    pub fn is_snake(&self) -> bool {
+        matches!(
+            self,
+            TileKind::Head
+                | TileKind::Tail
+                | TileKind::Body
+                | TileKind::RivalHead
+                | TileKind::RivalTail
+                | TileKind::RivalBody
+        )
+    }
+}
+
+// Which way a snake runs through a tile, from the tiles either side of it, for drawing turns and
+// which way the head is facing instead of the same thing all along the snake.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Segment {
+    // which way the head is going, in degrees clockwise from up
+    Head(u16),
+    // which way the rest of the snake is from the tail, the same way
+    Tail(u16),
+    Horizontal,
+    Vertical,
+    // turns, by the two sides of the tile they join
+    UpRight,
+    DownRight,
+    DownLeft,
+    UpLeft,
+}
+
+impl Segment {
+    // Every one there can be, for rules in the stylesheet. Heads and tails point every 30
+    // degrees, which covers both kinds of grid.
+    pub fn all() -> Vec<Segment> {
+        let degrees = (0..360).step_by(30);
+        degrees
+            .clone()
+            .map(Segment::Head)
+            .chain(degrees.map(Segment::Tail))
+            .chain([
+                Segment::Horizontal,
+                Segment::Vertical,
+                Segment::UpRight,
+                Segment::DownRight,
+                Segment::DownLeft,
+                Segment::UpLeft,
+            ])
+            .collect()
+    }
+
+    // for the page's stylesheet, like "seg-head-90" or "seg-up-right"
+    pub fn class_name(&self) -> String {
+        let name = match self {
+            Segment::Head(degrees) => return format!("seg-head-{degrees}"),
+            Segment::Tail(degrees) => return format!("seg-tail-{degrees}"),
+            Segment::Horizontal => "seg-horizontal",
+            Segment::Vertical => "seg-vertical",
+            Segment::UpRight => "seg-up-right",
+            Segment::DownRight => "seg-down-right",
+            Segment::DownLeft => "seg-down-left",
+            Segment::UpLeft => "seg-up-left",
+        };
+
+        name.to_string()
+    }
+
+    // by which way the two sides point on screen, see `Geometry::degrees`
+    fn joining(a: u16, b: u16) -> Option<Segment> {
+        let segment = match (a.min(b), a.max(b)) {
+            (90, 270) => Segment::Horizontal,
+            (0, 180) => Segment::Vertical,
+            (0, 90) => Segment::UpRight,
+            (90, 180) => Segment::DownRight,
+            (180, 270) => Segment::DownLeft,
+            (0, 270) => Segment::UpLeft,
+            // a zen snake folded back over itself, or the slanted sides of a hexagon, which
+            // have no good way to draw
+            _ => return None,
+        };
+
+        Some(segment)
+    }
+}
+
+// Each of the snake's segments, head first like `Snake::body`. Segments that aren't next to the
+// ones either side of them, which shouldn't happen, are `None`.
+pub fn segments_of(
+    snake: &Snake,
+    width: isize,
+    height: isize,
+    geometry: &dyn Geometry,
+) -> Vec<Option<Segment>> {
+    let body = &snake.body;
+    let degrees = |direction: &Direction| geometry.degrees(direction);
+
+    // one step across the edge of the board is the same as one step back from the other side
+    let step = |from: &Vector, to: &Vector| {
+        let wrap = |step: isize, size: isize| match step {
+            step if step == size - 1 => -1,
+            step if step == 1 - size => 1,
+            step => step,
+        };
+        Direction::from_vector(&Vector(
+            wrap(to.0 - from.0, width),
+            wrap(to.1 - from.1, height),
+        ))
+    };
+
+    (0..body.len())
+        .map(|index| {
+            let ahead = index
+                .checked_sub(1)
+                .and_then(|ahead| step(&body[index], &body[ahead]));
+            let behind = body
+                .get(index + 1)
+                .and_then(|behind| step(&body[index], behind));
+
+            match (index, ahead, behind) {
+                (0, _, Some(behind)) => Some(Segment::Head(degrees(&behind.opposite()))),
+                // just a head left
+                (0, _, None) => Some(Segment::Head(degrees(snake.direction()))),
+                (_, Some(ahead), None) => Some(Segment::Tail(degrees(&ahead))),
+                (_, Some(ahead), Some(behind)) => {
+                    Segment::joining(degrees(&ahead), degrees(&behind))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct BoardView {
+    pub width: isize,
+    pub height: isize,
+    // in rows from the top left
+    pub tiles: Vec<TileKind>,
+    // the same, for the tiles with a snake on them
+    pub segments: Vec<Option<Segment>>,
+}
+
+impl BoardView {
+    pub fn of(game: &SnakeGame) -> BoardView {
+        let (width, height) = (game.width, game.height);
+        let mut tiles = vec![TileKind::Empty; (width * height) as usize];
+        let mut segments = vec![None; tiles.len()];
+
+        // whatever's painted over a snake hides its segment too
+        let mut paint = |pos: &Vector, kind, segment| {
+            if geometry::is_within_board(width, height, pos) {
+                let index = (pos.1 * width + pos.0) as usize;
+                tiles[index] = kind;
+                segments[index] = segment;
+            }
+        };
+
+        // later paint wins, so this goes from least to most important
+        for pos in game.walls.iter() {
+            paint(pos, TileKind::Wall, None);
+        }
+        for (pair, ends) in game.portals().iter().enumerate() {
+            for pos in ends {
+                paint(pos, TileKind::Portal(pair as u8), None);
+            }
+        }
+        for pos in game.ladders().iter().flatten() {
+            paint(pos, TileKind::Ladder, None);
+        }
+        for pos in game.hazards().iter() {
+            paint(pos, TileKind::Hazard, None);
+        }
+        for enemy in game.enemies().iter() {
+            paint(&enemy.position, TileKind::Enemy, None);
+        }
+        let snake_segments = game
+            .snakes()
+            .iter()
+            .map(|snake| segments_of(snake, width, height, game.geometry()))
+            .collect::<Vec<_>>();
+        for (snake, segments) in game.snakes().iter().zip(snake_segments.iter()) {
+            let (_, _, body) = TileKind::snake_parts(snake.player);
+            for (pos, segment) in snake.body.iter().zip(segments.iter()) {
+                paint(pos, body, *segment);
+            }
+        }
+        for (snake, segments) in game.snakes().iter().zip(snake_segments.iter()) {
+            let (_, tail, _) = TileKind::snake_parts(snake.player);
+            paint(snake.tail(), tail, segments.last().copied().flatten());
+        }
+        for (snake, segments) in game.snakes().iter().zip(snake_segments.iter()) {
+            let (head, _, _) = TileKind::snake_parts(snake.player);
+            paint(snake.head(), head, segments.first().copied().flatten());
+        }
+        if let Some(pos) = game.golden_food() {
+            paint(pos, TileKind::GoldenFood, None);
+        }
+        for food in game.food().iter() {
+            let kind = match food.kind {
+                FoodKind::Normal => TileKind::Food,
+                FoodKind::Bonus => TileKind::BonusFood,
+                FoodKind::Rotten => TileKind::RottenFood,
+                FoodKind::Speed => TileKind::SpeedFood,
+                FoodKind::Shrink => TileKind::ShrinkFood,
+            };
+            paint(&food.position, kind, None);
+        }
+
+        BoardView {
+            width,
+            height,
+            tiles,
+            segments,
+        }
+    }
+
+    // anything off the board is empty
+    pub fn get(&self, pos: &Vector) -> TileKind {
+        if geometry::is_within_board(self.width, self.height, pos) {
+            self.tiles[(pos.1 * self.width + pos.0) as usize]
+        } else {
+            TileKind::Empty
+        }
+    }
+
+    pub fn segment(&self, pos: &Vector) -> Option<Segment> {
+        if geometry::is_within_board(self.width, self.height, pos) {
+            self.segments[(pos.1 * self.width + pos.0) as usize]
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let mut game = SnakeGame::new(21, 15);
+        game.walls = vec![Vector(0, 0)];
+        game.rebuild_board();
+        let hazard = game.free_positions().get(0).cloned().unwrap();
+        assert!(game.spawn_hazard(hazard.clone()));
+        let snake = game.snakes()[0].clone();
+        let food = game.food()[0].position.clone();
+
+        let view = game.board_view();
+        assert_eq!(view.tiles.len(), 21 * 15);
+        assert_eq!(view.get(&Vector(0, 0)), TileKind::Wall);
+        assert_eq!(view.get(&hazard), TileKind::Hazard);
+        assert_eq!(view.get(snake.head()), TileKind::Head);
+        assert_eq!(view.get(snake.tail()), TileKind::Tail);
+        assert_eq!(view.get(&food), TileKind::Food);
+        assert_eq!(view.get(&Vector(21, 0)), TileKind::Empty);
+        assert_eq!(view.segment(&food), None);
+    }
+
+    #[test]
+    fn snake_segments() {
+        let game = SnakeGame::new(21, 15);
+        let mut snake = game.snakes()[0].clone();
+
+        // just gone up through the top edge and come out at the bottom, after turning off a row
+        snake.body = [
+            Vector(3, 14),
+            Vector(3, 0),
+            Vector(2, 0),
+            Vector(1, 0),
+            Vector(1, 1),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            segments_of(&snake, 21, 15, game.geometry()),
+            [
+                Some(Segment::Head(0)),
+                Some(Segment::UpLeft),
+                Some(Segment::Horizontal),
+                Some(Segment::DownRight),
+                Some(Segment::Tail(0)),
+            ]
+        );
+
+        // the head and tail of a fresh game, facing left
+        let view = game.board_view();
+        let snake = &game.snakes()[0];
+        assert_eq!(view.segment(snake.head()), Some(Segment::Head(270)));
+        assert_eq!(view.segment(snake.tail()), Some(Segment::Tail(270)));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::enemy;
+use crate::geometry::Vector;
+
+use serde::{Deserialize, Serialize};
+
+// Waves mode, a run in rounds. Every wave puts down a pattern of puddles and sends out a few
+// enemies, and eating its quota of fruit clears the lot, puddles from eating included. After a
+// short break the next one comes, with a bigger quota, more enemies, and from the sixth wave on
+// two patterns at once. The patterns are puddles with gaps in, and `SnakeGame` leaves out any
+// tile of one that would shut part of the board off, so there's always a way round.
+
+// ticks between clearing one wave and the next one going down, with its number over the board
+pub const BREAK_TICKS: usize = 20;
+// how close to a head a wave can put anything down, in tiles
+pub const CLEARANCE: usize = 3;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Pattern {
+    // along the middle row and column, every other tile
+    Cross,
+    // an L in each corner
+    Corners,
+    // up and down the board, with a way through in the middle
+    Stripes,
+    // every other tile of a box around the middle
+    Ring,
+    // dotted all over
+    Dots,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 5] = [
+        Pattern::Cross,
+        Pattern::Corners,
+        Pattern::Stripes,
+        Pattern::Ring,
+        Pattern::Dots,
+    ];
+
+    // Where it goes on a board this size, never on the edge. Anything can be under it, so it's
+    // up to the game which of these are free.
+    pub fn tiles(&self, width: isize, height: isize) -> Vec<Vector> {
+        let (middle_x, middle_y) = (width / 2, height / 2);
+        let inside =
+            |pos: &Vector| (1..width - 1).contains(&pos.0) && (1..height - 1).contains(&pos.1);
+
+        let tiles = match self {
+            Pattern::Cross => (2..width.max(height))
+                .step_by(2)
+                .flat_map(|along| {
+                    [
+                        Vector(middle_x - along, middle_y),
+                        Vector(middle_x + along, middle_y),
+                        Vector(middle_x, middle_y - along),
+                        Vector(middle_x, middle_y + along),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+            Pattern::Corners => [(2, 2), (3, 2), (4, 2), (2, 3), (2, 4)]
+                .into_iter()
+                .flat_map(|(x, y)| {
+                    [
+                        Vector(x, y),
+                        Vector(width - 1 - x, y),
+                        Vector(x, height - 1 - y),
+                        Vector(width - 1 - x, height - 1 - y),
+                    ]
+                })
+                .collect(),
+            Pattern::Stripes => (3..width - 2)
+                .step_by(4)
+                .flat_map(|x| {
+                    (2..height - 2)
+                        .filter(move |y| (y - middle_y).abs() > 1)
+                        .map(move |y| Vector(x, y))
+                })
+                .collect(),
+            Pattern::Ring => {
+                let (left, top, right, bottom) = (3, 3, width - 4, height - 4);
+                (left..=right)
+                    .flat_map(|x| (top..=bottom).map(move |y| Vector(x, y)))
+                    .filter(|pos| {
+                        let edge =
+                            pos.0 == left || pos.0 == right || pos.1 == top || pos.1 == bottom;
+                        edge && (pos.0 + pos.1).rem_euclid(2) == 0
+                    })
+                    .collect()
+            }
+            Pattern::Dots => (2..width - 1)
+                .step_by(4)
+                .flat_map(|x| (2..height - 1).step_by(4).map(move |y| Vector(x, y)))
+                .collect(),
+        };
+
+        // small boards fold the corners over onto each other
+        let mut unique = vec![];
+        for pos in tiles.into_iter().filter(inside) {
+            if !unique.contains(&pos) {
+                unique.push(pos);
+            }
+        }
+        unique
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Wave {
+    // from 1
+    pub number: usize,
+    // fruit eaten towards the quota
+    pub eaten: usize,
+    // ticks until it goes down, while it's between waves
+    pub break_left: usize,
+}
+
+impl Wave {
+    // right away, since the countdown's a break already
+    pub fn first() -> Wave {
+        Wave {
+            number: 1,
+            eaten: 0,
+            break_left: 0,
+        }
+    }
+
+    pub fn next(&self) -> Wave {
+        Wave {
+            number: self.number + 1,
+            eaten: 0,
+            break_left: BREAK_TICKS,
+        }
+    }
+
+    pub fn quota(&self) -> usize {
+        self.number + 2
+    }
+
+    pub fn enemies(&self) -> usize {
+        (self.number / 2).min(enemy::MAX_ENEMIES)
+    }
+
+    pub fn patterns(&self) -> Vec<Pattern> {
+        let first = Pattern::ALL[(self.number - 1) % Pattern::ALL.len()];
+        let second = Pattern::ALL[self.number % Pattern::ALL.len()];
+        match self.number > Pattern::ALL.len() {
+            true => vec![first, second],
+            false => vec![first],
+        }
+    }
+
+    pub fn is_between(&self) -> bool {
+        self.break_left > 0
+    }
+
+    // Counts a fruit towards the quota, returning whether that clears the wave. Fruit eaten
+    // between waves doesn't count towards the next one.
+    pub fn eat(&mut self) -> bool {
+        if self.is_between() {
+            return false;
+        }
+
+        self.eaten += 1;
+        self.eaten >= self.quota()
+    }
+
+    // like "3 1 0", the number, what's been eaten, and the break left
+    pub fn serialize(&self) -> String {
+        format!("{} {} {}", self.number, self.eaten, self.break_left)
+    }
+
+    pub fn parse(source: &str) -> Result<Wave, String> {
+        let numbers = source
+            .split_whitespace()
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("bad number '{value}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let [number, eaten, break_left] = numbers[..] else {
+            return Err(format!("bad wave '{source}'"));
+        };
+        if number == 0 {
+            return Err("waves start from 1".to_string());
+        }
+
+        Ok(Wave {
+            number,
+            eaten,
+            break_left,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        for pattern in Pattern::ALL {
+            for (width, height) in [(21, 15), (7, 5), (4, 4)] {
+                let tiles = pattern.tiles(width, height);
+                assert!(tiles.iter().all(|pos| {
+                    (1..width - 1).contains(&pos.0) && (1..height - 1).contains(&pos.1)
+                }));
+            }
+        }
+
+        // the middle's left open for getting across
+        let cross = Pattern::Cross.tiles(21, 15);
+        assert!(cross.contains(&Vector(12, 7)));
+        assert!(!cross.contains(&Vector(10, 7)));
+        assert!(!cross.contains(&Vector(11, 7)));
+
+        let stripes = Pattern::Stripes.tiles(21, 15);
+        assert!(stripes.contains(&Vector(3, 2)));
+        assert!(!stripes.contains(&Vector(3, 7)));
+    }
+
+    #[test]
+    fn waves() {
+        let mut wave = Wave::first();
+        assert_eq!(wave.quota(), 3);
+        assert_eq!(wave.enemies(), 0);
+        assert_eq!(wave.patterns(), vec![Pattern::Cross]);
+
+        assert!(!wave.eat());
+        assert!(!wave.eat());
+        assert!(wave.eat());
+
+        let mut wave = wave.next();
+        assert_eq!((wave.number, wave.eaten), (2, 0));
+        assert!(wave.is_between());
+        assert!(!wave.eat());
+        assert_eq!(wave.eaten, 0);
+
+        wave.number = 6;
+        assert_eq!(wave.patterns(), vec![Pattern::Cross, Pattern::Corners]);
+        assert_eq!(Wave::parse(&wave.serialize()), Ok(wave));
+        assert!(Wave::parse("0 0 0").is_err());
+        assert!(Wave::parse("1 2").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+// Random steering over lots of seeds and board sizes, checking `SnakeGame::invariants` after
+// every tick like the unit tests' `random_play`, but for far longer runs, and playing every one
+// of them twice to check the same seed and inputs always play out the same way. A panic or a
+// broken invariant says which case it was, to pick apart with the fuzzer, see `bin/fuzz.rs`.
+// The quick one runs along with everything else, and the long one is a few million ticks, best
+// left to release builds:
+//
+//   cargo test --release --test soak -- --ignored
+//
+// `SLAKE_SOAK_RUNS` changes how many runs the long one plays.
+
+use prng::Prng16;
+use slake::food::FoodCount;
+use slake::geometry::Direction;
+use slake::geometry::Grid;
+use slake::geometry::Topology;
+use slake::geometry::Vector;
+use slake::mode::GameMode;
+use slake::random;
+use slake::snake::SnakeGame;
+
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+const TICK_MS: i32 = 100;
+const LONG_RUNS: usize = 1000;
+const LONG_TICKS: usize = 2000;
+
+// what's checked against the second time through, every tick
+type Trace = Vec<(usize, Vec<Vector>)>;
+
+fn play(case: usize, ticks: usize) -> Result<Trace, String> {
+    random::seed([case as u16, (case >> 16) as u16 ^ 0x5eed]);
+    // apart from the game's own random numbers, so steering doesn't change what it draws
+    let mut prng = Prng16::new([case as u16, 77]);
+    let mut roll = |below: usize| prng.next().unwrap() as usize % below;
+
+    let mut game = SnakeGame::new(5 + roll(28) as isize, 3 + roll(20) as isize);
+    game.topology = [Topology::Walls, Topology::Wrap][roll(2)];
+    game.grid = [Grid::Square, Grid::Square, Grid::Hex][roll(3)];
+    game.hydra_mode = roll(2) == 0;
+    game.special_food = roll(2) == 0;
+    game.food_count = [FoodCount::Fixed(1), FoodCount::Fixed(3), FoodCount::UpTo(5)][roll(3)];
+    game.hazard_lifetime = [None, Some(30), Some(200)][roll(3)];
+    game.starve_ticks = [None, None, Some(40)][roll(3)];
+    game.hazard_stun = roll(2) == 0;
+    game.double_hazards = roll(3) == 0;
+    game.enemy_count = roll(3);
+    game.combos = roll(2) == 0;
+    game.rules.self_collision = roll(4) != 0;
+    game.rules.tail_cut = roll(3) == 0;
+    game.tick_ms = TICK_MS as usize;
+    let mode = GameMode::ALL[roll(GameMode::ALL.len())];
+    game.time_limit = mode.time_limit(TICK_MS);
+    game.set_mode(mode);
+    game.set_versus(roll(3) == 0);
+
+    game.invariants()
+        .map_err(|error| format!("case {case}, at the start: {error}"))?;
+
+    let mut trace = Trace::with_capacity(ticks);
+    for tick in 0..ticks {
+        match roll(10) {
+            // the hex diagonals too, which square boards turn down
+            0..=3 => game.change_direction(Direction::HEX[roll(6)].clone()),
+            4..=6 if game.is_game_over() => game.restart(),
+            _ => (),
+        }
+        let outcome = game.tick();
+        game.take_events();
+
+        game.invariants()
+            .map_err(|error| format!("case {case}, tick {tick}: {error}"))?;
+        trace.push((game.score, outcome.heads));
+    }
+
+    Ok(trace)
+}
+
+fn soak(runs: usize, ticks: usize) {
+    for case in 0..runs {
+        let first = panic::catch_unwind(AssertUnwindSafe(|| play(case, ticks)))
+            .unwrap_or_else(|_| Err(format!("case {case} panicked")));
+        let first = first.unwrap_or_else(|error| panic!("{error}"));
+
+        let again = play(case, ticks).unwrap_or_else(|error| panic!("{error}"));
+        if let Some(tick) = (0..ticks).find(|tick| first[*tick] != again[*tick]) {
+            panic!("case {case} played out differently the second time, from tick {tick}");
+        }
+    }
+}
+
+#[test]
+fn quick() {
+    soak(12, 400);
+}
+
+#[test]
+#[ignore]
+fn long() {
+    let runs = std::env::var("SLAKE_SOAK_RUNS")
+        .ok()
+        .and_then(|runs| runs.parse().ok())
+        .unwrap_or(LONG_RUNS);
+    soak(runs, LONG_TICKS);
+}